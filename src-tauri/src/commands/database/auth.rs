@@ -1,5 +1,7 @@
+use crate::core::auth_session_manager::SessionLockReason;
 use crate::database::encryption::master_password::SetupMasterPasswordRequest;
 use crate::models::auth::{ChangeMasterPasswordRequest, VerifyMasterPasswordRequest};
+use crate::models::backup::SnapshotReason;
 use crate::services::sync::SyncService;
 use crate::state::AppState;
 use std::sync::Arc;
@@ -15,6 +17,17 @@ async fn auto_connect_sync_databases(sync_service: Arc<SyncService>) -> Result<(
         .map_err(|e| format!("Failed to auto-connect databases: {}", e))
 }
 
+/// Wipe decrypted secrets cached outside the database service after the session locks
+async fn on_session_locked(state: &AppState) {
+    let auth_session_manager = state.auth_session_manager.lock().await;
+    if let Err(e) = auth_session_manager
+        .on_session_locked(SessionLockReason::Manual)
+        .await
+    {
+        eprintln!("Failed to handle session lock: {}", e);
+    }
+}
+
 /// Setup master password for first time
 #[tauri::command]
 pub async fn setup_master_password(
@@ -83,6 +96,7 @@ pub async fn try_auto_unlock(state: State<'_, AppState>) -> Result<bool, String>
 #[tauri::command]
 pub async fn lock_session(state: State<'_, AppState>) -> Result<(), String> {
     state.auth_service.lock_session().await;
+    on_session_locked(&state).await;
     Ok(())
 }
 
@@ -120,12 +134,20 @@ pub async fn change_master_password(
         serde_json::from_value(request.get("request").unwrap_or(&request).clone())
             .map_err(|e| format!("Invalid request format: {}", e))?;
 
+    state
+        .backup_scheduler
+        .snapshot_before(SnapshotReason::PasswordChange)
+        .await;
+
     match state
         .auth_service
         .change_master_password(req.old_password, req.new_password)
         .await
     {
-        Ok(()) => Ok(()),
+        Ok(()) => {
+            on_session_locked(&state).await;
+            Ok(())
+        }
         Err(e) => match e {
             crate::database::error::DatabaseError::AuthenticationFailed(_) => {
                 Err("Current password is incorrect".to_string())
@@ -142,8 +164,16 @@ pub async fn change_master_password(
 /// Reset master password (dangerous operation)
 #[tauri::command]
 pub async fn reset_master_password(state: State<'_, AppState>) -> Result<(), String> {
+    state
+        .backup_scheduler
+        .snapshot_before(SnapshotReason::PasswordReset)
+        .await;
+
     match state.auth_service.reset_master_password().await {
-        Ok(()) => Ok(()),
+        Ok(()) => {
+            on_session_locked(&state).await;
+            Ok(())
+        }
         Err(e) => match e {
             crate::database::error::DatabaseError::MasterPasswordRequired => {
                 Err("Master password is not set up".to_string())
@@ -1,7 +1,9 @@
 use tauri::{Emitter, State};
 
 use crate::database::encryption::ExternalDbEncryptor;
-use crate::database::providers::{MongoDBProvider, MySQLProvider, PostgreSQLProvider};
+use crate::database::providers::{
+    ChangeLogProvider, MongoDBProvider, MySQLProvider, PostgreSQLProvider, SQLiteTargetProvider,
+};
 use crate::database::traits_sync::SyncTarget;
 use crate::models::sync::external_db::{
     AddExternalDatabaseRequest, DatabaseType, ExternalDatabaseConfig, ExternalDatabaseWithDetails,
@@ -101,10 +103,30 @@ pub async fn update_external_database(
         config.name = name;
     }
 
-    if let Some(connection_details) = request.connection_details {
+    if let Some(mut connection_details) = request.connection_details {
         let master_password_manager = database_service.get_master_password_manager_arc();
         let encryptor = ExternalDbEncryptor::new(master_password_manager);
 
+        // File-based targets can be edited with only one of their two secrets
+        if config.db_type.is_file_based() {
+            let current = encryptor
+                .decrypt_connection_details(&config.connection_details_encrypted)
+                .await
+                .map_err(|e| format!("Failed to decrypt existing connection details: {}", e))?;
+
+            if connection_details.password.is_empty() {
+                connection_details.password = current.password;
+            }
+            if connection_details
+                .encryption_passphrase
+                .as_deref()
+                .unwrap_or_default()
+                .is_empty()
+            {
+                connection_details.encryption_passphrase = current.encryption_passphrase;
+            }
+        }
+
         let encrypted = encryptor
             .encrypt_connection_details(&connection_details)
             .await
@@ -147,25 +169,32 @@ pub async fn test_external_database_connection(
 ) -> Result<bool, String> {
     let database_service = app_state.database_service.lock().await;
 
-    let connection_details =
-        if request.connection_details.password.is_empty() && request.database_id.is_some() {
-            let db_id = request.database_id.as_ref().unwrap();
-            let config = database_service
-                .find_external_database_by_id(db_id)
-                .await
-                .map_err(|e| format!("Failed to get existing database: {}", e))?
-                .ok_or_else(|| format!("Database not found: {}", db_id))?;
+    let secrets_omitted = request.connection_details.password.is_empty()
+        && request
+            .connection_details
+            .encryption_passphrase
+            .as_deref()
+            .unwrap_or_default()
+            .is_empty();
+
+    let connection_details = if secrets_omitted && request.database_id.is_some() {
+        let db_id = request.database_id.as_ref().unwrap();
+        let config = database_service
+            .find_external_database_by_id(db_id)
+            .await
+            .map_err(|e| format!("Failed to get existing database: {}", e))?
+            .ok_or_else(|| format!("Database not found: {}", db_id))?;
 
-            let master_password_manager = database_service.get_master_password_manager_arc();
-            let encryptor = ExternalDbEncryptor::new(master_password_manager);
+        let master_password_manager = database_service.get_master_password_manager_arc();
+        let encryptor = ExternalDbEncryptor::new(master_password_manager);
 
-            encryptor
-                .decrypt_connection_details(&config.connection_details_encrypted)
-                .await
-                .map_err(|e| format!("Failed to decrypt existing connection details: {}", e))?
-        } else {
-            request.connection_details
-        };
+        encryptor
+            .decrypt_connection_details(&config.connection_details_encrypted)
+            .await
+            .map_err(|e| format!("Failed to decrypt existing connection details: {}", e))?
+    } else {
+        request.connection_details
+    };
 
     let connection_string = connection_details.to_connection_string(&request.db_type);
 
@@ -207,6 +236,32 @@ pub async fn test_external_database_connection(
                 .map_err(|e| format!("MongoDB test failed: {}", e))?;
             Ok(())
         }
+        DatabaseType::SQLite => {
+            let mut provider = SQLiteTargetProvider::new(connection_string);
+            provider
+                .connect()
+                .await
+                .map_err(|e| format!("SQLite connection failed: {}", e))?;
+            provider
+                .test_connection()
+                .await
+                .map_err(|e| format!("SQLite test failed: {}", e))?;
+            Ok(())
+        }
+        DatabaseType::LocalFolder | DatabaseType::WebDAV | DatabaseType::S3 => {
+            let mut provider =
+                ChangeLogProvider::from_connection_details(&request.db_type, &connection_details)
+                    .map_err(|e| e.to_string())?;
+            provider
+                .connect()
+                .await
+                .map_err(|e| format!("{} connection failed: {}", request.db_type, e))?;
+            provider
+                .test_connection()
+                .await
+                .map_err(|e| format!("{} test failed: {}", request.db_type, e))?;
+            Ok(())
+        }
     };
 
     result.map(|_| true)
@@ -1,7 +1,11 @@
 use crate::models::ssh::{
-    CreateSSHGroupRequest, CreateSSHKeyRequest, CreateSSHProfileRequest, DeleteGroupAction,
-    SSHConfigHost, SSHGroup, SSHKey, SSHProfile, TestSSHConnectionRequest, UpdateSSHGroupRequest,
-    UpdateSSHKeyRequest, UpdateSSHProfileRequest,
+    CreateCertificateAuthorityRequest, CreateSSHGroupRequest, CreateSSHKeyRequest,
+    CreateSSHProfileRequest, DeleteGroupAction, DeploySSHKeyRequest, ExpiringCertificate,
+    ExportSSHConfigRequest, GenerateSSHKeyRequest, ImportConnectionsRequest, ImportPreview,
+    ImportResult, IssueCertificateRequest, KeyDeployReport, KeyFormat, ManagedSSHConfigStatus,
+    PreviewImportRequest, RotateSSHKeyRequest, SSHCertificateAuthority, SSHConfigExportPreview,
+    SSHConfigExportResult, SSHConfigExportSelection, SSHConfigHost, SSHGroup, SSHKey, SSHProfile,
+    TestSSHConnectionRequest, UpdateSSHGroupRequest, UpdateSSHKeyRequest, UpdateSSHProfileRequest,
 };
 use crate::services::ssh_config_parser;
 use crate::state::AppState;
@@ -178,6 +182,58 @@ pub async fn create_ssh_key(
     Ok(key)
 }
 
+/// Generate a new SSH key pair
+#[tauri::command]
+pub async fn generate_ssh_key(
+    state: State<'_, AppState>,
+    request: GenerateSSHKeyRequest,
+    app_handle: tauri::AppHandle,
+) -> Result<SSHKey, String> {
+    let service = state.ssh_key_service.lock().await;
+    let key = app_result!(service.generate_ssh_key(request).await)?;
+    let _ = app_handle.emit("ssh_key_created", &key);
+    Ok(key)
+}
+
+/// Add, change or remove the passphrase of an SSH key
+#[tauri::command]
+pub async fn change_ssh_key_passphrase(
+    state: State<'_, AppState>,
+    id: String,
+    passphrase: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> Result<SSHKey, String> {
+    let service = state.ssh_key_service.lock().await;
+    let key = app_result!(service.change_ssh_key_passphrase(&id, passphrase).await)?;
+    let _ = app_handle.emit("ssh_key_updated", &key);
+    Ok(key)
+}
+
+/// Export an SSH private key as OpenSSH, PEM or PPK
+#[tauri::command]
+pub async fn export_ssh_key(
+    state: State<'_, AppState>,
+    id: String,
+    format: KeyFormat,
+) -> Result<String, String> {
+    let service = state.ssh_key_service.lock().await;
+    app_result!(service.export_ssh_key(&id, format).await)
+}
+
+/// List the keys on the tokens of a PKCS#11 module
+#[tauri::command]
+pub async fn list_pkcs11_keys(
+    provider_path: String,
+    pin: Option<String>,
+) -> Result<Vec<crate::services::ssh::hardware_key::Pkcs11KeyInfo>, String> {
+    let keys = tokio::task::spawn_blocking(move || {
+        crate::services::ssh::hardware_key::list_pkcs11_keys(&provider_path, pin.as_deref())
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    app_result!(keys)
+}
+
 /// Get all SSH keys
 #[tauri::command]
 pub async fn get_ssh_keys(state: State<'_, AppState>) -> Result<Vec<SSHKey>, String> {
@@ -223,6 +279,115 @@ pub async fn count_profiles_using_key(
     app_result!(service.count_profiles_using_key(&key_id).await)
 }
 
+/// Install an SSH key on the hosts of the given profiles
+#[tauri::command]
+pub async fn deploy_ssh_key(
+    state: State<'_, AppState>,
+    request: DeploySSHKeyRequest,
+    app_handle: tauri::AppHandle,
+) -> Result<KeyDeployReport, String> {
+    let report = app_result!(state.ssh_service.deploy_ssh_key(request).await)?;
+    emit_switched_profiles(&state, &report, &app_handle).await;
+    Ok(report)
+}
+
+/// Replace an SSH key with another on the hosts that use it
+#[tauri::command]
+pub async fn rotate_ssh_key(
+    state: State<'_, AppState>,
+    request: RotateSSHKeyRequest,
+    app_handle: tauri::AppHandle,
+) -> Result<KeyDeployReport, String> {
+    let report = app_result!(state.ssh_service.rotate_ssh_key(request).await)?;
+    emit_switched_profiles(&state, &report, &app_handle).await;
+    Ok(report)
+}
+
+/// Notify the frontend of profiles whose authentication a deploy changed
+async fn emit_switched_profiles(
+    state: &State<'_, AppState>,
+    report: &KeyDeployReport,
+    app_handle: &tauri::AppHandle,
+) {
+    for result in report.results.iter().filter(|r| r.auth_switched) {
+        if let Ok(profile) = state.ssh_service.get_ssh_profile(&result.profile_id).await {
+            let _ = app_handle.emit("ssh_profile_updated", &profile);
+        }
+    }
+}
+
+/// Create a local SSH certificate authority
+#[tauri::command]
+pub async fn create_certificate_authority(
+    state: State<'_, AppState>,
+    request: CreateCertificateAuthorityRequest,
+) -> Result<SSHCertificateAuthority, String> {
+    app_result!(
+        state
+            .certificate_authority_service
+            .create_certificate_authority(request)
+            .await
+    )
+}
+
+/// Get all local SSH certificate authorities
+#[tauri::command]
+pub async fn get_certificate_authorities(
+    state: State<'_, AppState>,
+) -> Result<Vec<SSHCertificateAuthority>, String> {
+    app_result!(
+        state
+            .certificate_authority_service
+            .get_certificate_authorities()
+            .await
+    )
+}
+
+/// Delete a local SSH certificate authority
+#[tauri::command]
+pub async fn delete_certificate_authority(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<(), String> {
+    app_result!(
+        state
+            .certificate_authority_service
+            .delete_certificate_authority(&id)
+            .await
+    )
+}
+
+/// Sign a short-lived certificate for a profile
+#[tauri::command]
+pub async fn issue_ssh_certificate(
+    state: State<'_, AppState>,
+    request: IssueCertificateRequest,
+    app_handle: tauri::AppHandle,
+) -> Result<SSHProfile, String> {
+    let profile = app_result!(
+        state
+            .certificate_authority_service
+            .issue_certificate(request)
+            .await
+    )?;
+    let _ = app_handle.emit("ssh_profile_updated", &profile);
+    Ok(profile)
+}
+
+/// Get profiles whose certificate expires within the given number of minutes
+#[tauri::command]
+pub async fn get_expiring_certificates(
+    state: State<'_, AppState>,
+    within_minutes: u32,
+) -> Result<Vec<ExpiringCertificate>, String> {
+    app_result!(
+        state
+            .certificate_authority_service
+            .get_expiring_certificates(within_minutes)
+            .await
+    )
+}
+
 /// Import SSH key from file path
 #[tauri::command]
 pub async fn import_ssh_key_from_file(
@@ -268,6 +433,56 @@ pub async fn get_ssh_config_hosts(
     }
 }
 
+/// Read connections from another SSH client and mark the ones already stored
+#[tauri::command]
+pub async fn preview_connection_import(
+    state: State<'_, AppState>,
+    request: PreviewImportRequest,
+) -> Result<ImportPreview, String> {
+    app_result!(state.connection_import_service.preview(request).await)
+}
+
+/// Import the selected connections as profiles, with their groups, keys and tunnels
+#[tauri::command]
+pub async fn import_connections(
+    state: State<'_, AppState>,
+    request: ImportConnectionsRequest,
+) -> Result<ImportResult, String> {
+    app_result!(state.connection_import_service.import(request).await)
+}
+
+/// Render the OpenSSH config for the selected profiles without writing it
+#[tauri::command]
+pub async fn preview_ssh_config_export(
+    state: State<'_, AppState>,
+    request: SSHConfigExportSelection,
+) -> Result<SSHConfigExportPreview, String> {
+    app_result!(state.ssh_config_exporter.preview(request).await)
+}
+
+/// Export profiles to an OpenSSH config file or the managed Include
+#[tauri::command]
+pub async fn export_ssh_config(
+    state: State<'_, AppState>,
+    request: ExportSSHConfigRequest,
+) -> Result<SSHConfigExportResult, String> {
+    app_result!(state.ssh_config_exporter.export(request).await)
+}
+
+/// Get the managed OpenSSH config export, if it is on
+#[tauri::command]
+pub async fn get_managed_ssh_config(
+    state: State<'_, AppState>,
+) -> Result<Option<ManagedSSHConfigStatus>, String> {
+    app_result!(state.ssh_config_exporter.get_managed_status().await)
+}
+
+/// Stop the managed OpenSSH config export and remove its files
+#[tauri::command]
+pub async fn disable_managed_ssh_config(state: State<'_, AppState>) -> Result<(), String> {
+    app_result!(state.ssh_config_exporter.disable_managed().await)
+}
+
 /// Scan local ~/.ssh directory for SSH keys
 #[tauri::command]
 pub async fn scan_local_ssh_keys(
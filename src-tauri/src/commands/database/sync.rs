@@ -1,7 +1,11 @@
+use std::collections::HashMap;
 use tauri::{Emitter, State};
 
 use crate::{
-    models::sync::{ConflictResolutionStrategy, SyncDirection, SyncLog},
+    models::sync::{
+        conflict::FieldChoice, ConflictResolutionStrategy, SyncDirection, SyncEncryptionStatus,
+        SyncLog, SyncPlan,
+    },
     state::AppState,
 };
 
@@ -18,6 +22,18 @@ pub async fn sync_now(
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn preview_sync(
+    database_id: String,
+    app_state: State<'_, AppState>,
+) -> Result<SyncPlan, String> {
+    app_state
+        .sync_service
+        .preview_sync(&database_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_sync_status(
     database_id: String,
@@ -30,6 +46,70 @@ pub async fn get_sync_status(
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn get_sync_encryption_status(
+    database_id: String,
+    app_state: State<'_, AppState>,
+) -> Result<SyncEncryptionStatus, String> {
+    app_state
+        .sync_service
+        .get_encryption_status(&database_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn enable_sync_encryption(
+    database_id: String,
+    password: String,
+    app_state: State<'_, AppState>,
+) -> Result<SyncEncryptionStatus, String> {
+    app_state
+        .sync_service
+        .enable_encryption(&database_id, &password)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn unlock_sync_encryption(
+    database_id: String,
+    password: String,
+    app_state: State<'_, AppState>,
+) -> Result<SyncEncryptionStatus, String> {
+    app_state
+        .sync_service
+        .unlock_encryption(&database_id, &password)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn rotate_sync_encryption_key(
+    database_id: String,
+    password: String,
+    app_state: State<'_, AppState>,
+) -> Result<SyncEncryptionStatus, String> {
+    app_state
+        .sync_service
+        .rotate_encryption_key(&database_id, &password)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn disable_sync_encryption(
+    database_id: String,
+    password: String,
+    app_state: State<'_, AppState>,
+) -> Result<(), String> {
+    app_state
+        .sync_service
+        .disable_encryption(&database_id, &password)
+        .await
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_sync_logs(
     database_id: String,
@@ -163,20 +243,19 @@ pub async fn get_unresolved_conflict_resolutions(
         .map_err(|e| e.to_string())
 }
 
-/// Resolve a conflict resolution with specified strategy
+/// Resolve a conflict with the specified strategy, saving the resulting record
+/// `field_choices` picks a side for each conflicting field under manual resolution
 #[tauri::command]
 pub async fn resolve_conflict_resolution(
     id: String,
     strategy: ConflictResolutionStrategy,
+    field_choices: Option<HashMap<String, FieldChoice>>,
     app_state: State<'_, AppState>,
     app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
-    let database_service = app_state.database_service.lock().await;
-    let local_db = database_service.get_local_database();
-    let local_db_read = local_db.read().await;
-
-    local_db_read
-        .resolve_conflict_resolution(&id, strategy)
+    app_state
+        .sync_service
+        .resolve_conflict(&id, strategy, &field_choices.unwrap_or_default())
         .await
         .map_err(|e| e.to_string())?;
 
@@ -1,3 +1,4 @@
+use crate::models::base::BaseModel;
 use crate::models::recording::*;
 use crate::services::recording::*;
 use crate::state::AppState;
@@ -100,8 +101,14 @@ pub async fn stop_recording(
         .map(|m| m.len() as i64)
         .unwrap_or(0);
 
+    let db = state.database_service.lock().await;
+
     let recording = SessionRecording {
-        id: recorder.recording_id.clone(),
+        base: BaseModel {
+            id: recorder.recording_id.clone(),
+            created_at: recorder.started_at,
+            ..BaseModel::new(db.get_device_id().to_string())
+        },
         terminal_id: request.terminal_id.clone(),
         session_name: recorder.session_name.clone(),
         terminal_type: recorder.terminal_type.clone(),
@@ -113,11 +120,9 @@ pub async fn stop_recording(
         width: recorder.header.width,
         height: recorder.header.height,
         metadata: None,
-        created_at: recorder.started_at,
     };
 
     // Save to database
-    let db = state.database_service.lock().await;
     db.save_session_recording(&recording)
         .await
         .map_err(|e| format!("Failed to save recording to database: {}", e))?;
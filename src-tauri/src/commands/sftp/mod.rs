@@ -1,18 +1,23 @@
+use crate::models::sftp::archive::{ArchiveFormat, ArchiveProgressEvent, ArchiveSummary};
+use crate::models::sftp::editor::{FileChunk, FileSnapshot, TextFileContent};
 use crate::models::sftp::error::SFTPError;
 use crate::models::sftp::file_entry::FileEntry;
 use crate::models::sftp::requests::{
-    CancelTransferRequest, CompareDirectoriesRequest, ConnectSFTPRequest, CreateDirectoryRequest,
-    CreateSymlinkRequest, DeleteRequest, DisconnectSFTPRequest, DownloadFileRequest,
-    GetAllTransfersRequest, GetTransferProgressRequest, ListDirectoryRequest, PauseTransferRequest,
-    ReadFileRequest, ReadSymlinkRequest, RenameRequest, ReorderQueueRequest, ResumeTransferRequest,
-    RetryTransferRequest, SearchRequest, SetPermissionsRequest, SetTransferPriorityRequest,
-    StatRequest, SyncDirectoriesRequest, UploadFileRequest, WriteFileRequest,
+    CancelSearchRequest, CancelTransferRequest, CompareDirectoriesRequest,
+    CompareRemoteDirectoriesRequest, ConnectSFTPRequest, CreateArchiveRequest,
+    CreateDirectoryRequest, CreateSymlinkRequest, DeleteRequest, DisconnectSFTPRequest,
+    DownloadArchiveRequest, DownloadFileRequest, ExtractArchiveRequest, GetAllTransfersRequest,
+    GetTransferProgressRequest, ListDirectoryRequest, PauseTransferRequest, ReadFileRangeRequest,
+    ReadFileRequest, ReadSymlinkRequest, RemoteCopyRequest, RenameRequest, ReorderQueueRequest,
+    ResumeTransferRequest, RetryTransferRequest, SearchRequest, SetPermissionsRequest,
+    SetTransferPriorityRequest, StatRequest, SyncDirectoriesRequest, UploadArchiveRequest,
+    UploadFileRequest, WriteFileRequest,
 };
-use crate::models::sftp::search::SearchResult;
+use crate::models::sftp::search::{SearchCompleteEvent, SearchResult, SearchResultEvent};
 use crate::models::sftp::sync::DiffEntry;
 use crate::models::sftp::transfer::TransferProgress;
 use crate::state::AppState;
-use tauri::State;
+use tauri::{Emitter, State};
 
 /// Convert SFTPError to String for Tauri compatibility
 impl From<SFTPError> for String {
@@ -192,6 +197,27 @@ pub async fn sftp_download_file(
     )
 }
 
+/// Copy file between two remote sessions
+#[tauri::command]
+pub async fn sftp_copy_remote_file(
+    state: State<'_, AppState>,
+    request: RemoteCopyRequest,
+    app_handle: tauri::AppHandle,
+) -> Result<String, String> {
+    sftp_result!(
+        state
+            .sftp_transfer_manager
+            .copy_remote_file(
+                request.source_session_id,
+                request.source_path,
+                request.target_session_id,
+                request.target_path,
+                app_handle
+            )
+            .await
+    )
+}
+
 /// Get transfer progress
 #[tauri::command]
 pub async fn sftp_get_transfer_progress(
@@ -270,6 +296,26 @@ pub async fn sftp_compare_directories(
     )
 }
 
+/// Compare directories on two remote sessions
+#[tauri::command]
+pub async fn sftp_compare_remote_directories(
+    state: State<'_, AppState>,
+    request: CompareRemoteDirectoriesRequest,
+) -> Result<Vec<DiffEntry>, String> {
+    sftp_result!(
+        state
+            .sftp_sync_service
+            .compare_remote_directories(
+                request.source_session_id,
+                request.source_path,
+                request.target_session_id,
+                request.target_path,
+                None
+            )
+            .await
+    )
+}
+
 /// Synchronize directories
 #[tauri::command]
 pub async fn sftp_sync_directory(
@@ -289,11 +335,30 @@ pub async fn sftp_sync_directory(
 pub async fn sftp_read_file(
     state: State<'_, AppState>,
     request: ReadFileRequest,
-) -> Result<String, String> {
+) -> Result<TextFileContent, String> {
     sftp_result!(
         state
             .sftp_service
-            .read_file(request.session_id, request.path)
+            .read_file(request.session_id, request.path, request.options)
+            .await
+    )
+}
+
+/// Read a raw byte range of a file
+#[tauri::command]
+pub async fn sftp_read_file_range(
+    state: State<'_, AppState>,
+    request: ReadFileRangeRequest,
+) -> Result<FileChunk, String> {
+    sftp_result!(
+        state
+            .sftp_service
+            .read_file_range(
+                request.session_id,
+                request.path,
+                request.offset,
+                request.length
+            )
             .await
     )
 }
@@ -303,11 +368,16 @@ pub async fn sftp_read_file(
 pub async fn sftp_write_file(
     state: State<'_, AppState>,
     request: WriteFileRequest,
-) -> Result<(), String> {
+) -> Result<FileSnapshot, String> {
     sftp_result!(
         state
             .sftp_service
-            .write_file(request.session_id, request.path, request.content)
+            .write_file(
+                request.session_id,
+                request.path,
+                request.content,
+                request.options
+            )
             .await
     )
 }
@@ -381,16 +451,182 @@ pub async fn sftp_retry_transfer(
             .await
     )
 }
-/// Search for text in files
+/// Search file contents
+/// Matches are streamed as `sftp_search_results` events and completion is signalled with
+/// `sftp_search_complete`; the full result list is also returned
 #[tauri::command]
 pub async fn sftp_search(
     state: State<'_, AppState>,
     request: SearchRequest,
+    app_handle: tauri::AppHandle,
 ) -> Result<Vec<SearchResult>, String> {
+    let search_id = request
+        .search_id
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let event_handle = app_handle.clone();
+    let event_search_id = search_id.clone();
+    let result = state
+        .sftp_service
+        .search(
+            request.session_id,
+            request.path,
+            request.query,
+            request.options,
+            Some(search_id.clone()),
+            move |results: &[SearchResult]| {
+                let _ = event_handle.emit(
+                    "sftp_search_results",
+                    &SearchResultEvent {
+                        search_id: event_search_id.clone(),
+                        results: results.to_vec(),
+                    },
+                );
+            },
+        )
+        .await;
+
+    let complete = match &result {
+        Ok(summary) => SearchCompleteEvent {
+            search_id,
+            total: summary.results.len(),
+            truncated: summary.truncated,
+            cancelled: summary.cancelled,
+            fallback: summary.fallback,
+            error: None,
+        },
+        Err(e) => SearchCompleteEvent {
+            search_id,
+            total: 0,
+            truncated: false,
+            cancelled: false,
+            fallback: false,
+            error: Some(e.to_string()),
+        },
+    };
+    let _ = app_handle.emit("sftp_search_complete", &complete);
+
+    sftp_result!(result.map(|summary| summary.results))
+}
+
+/// Cancel a running search
+#[tauri::command]
+pub async fn sftp_cancel_search(
+    state: State<'_, AppState>,
+    request: CancelSearchRequest,
+) -> Result<(), String> {
+    sftp_result!(state.sftp_service.cancel_search(&request.search_id).await)
+}
+
+/// Download a remote file or directory as a tar archive
+/// Progress is reported through `sftp_archive_progress` events
+#[tauri::command]
+pub async fn sftp_download_archive(
+    state: State<'_, AppState>,
+    request: DownloadArchiveRequest,
+    app_handle: tauri::AppHandle,
+) -> Result<ArchiveSummary, String> {
+    let format = request
+        .format
+        .or_else(|| ArchiveFormat::from_path(&request.local_path))
+        .unwrap_or(ArchiveFormat::TarGz);
+    let operation_id = request
+        .operation_id
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let on_progress = archive_progress_emitter(app_handle, operation_id.clone());
+
     sftp_result!(
         state
-            .sftp_service
-            .search(request.session_id, request.path, request.query)
+            .sftp_archive_service
+            .download_archive(
+                &request.session_id,
+                &request.remote_path,
+                &request.local_path,
+                format,
+                operation_id,
+                on_progress
+            )
+            .await
+    )
+}
+
+/// Upload a local file or directory as a tar stream extracted on the remote host
+/// Progress is reported through `sftp_archive_progress` events
+#[tauri::command]
+pub async fn sftp_upload_archive(
+    state: State<'_, AppState>,
+    request: UploadArchiveRequest,
+    app_handle: tauri::AppHandle,
+) -> Result<ArchiveSummary, String> {
+    let operation_id = request
+        .operation_id
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let on_progress = archive_progress_emitter(app_handle, operation_id.clone());
+
+    sftp_result!(
+        state
+            .sftp_archive_service
+            .upload_archive(
+                &request.session_id,
+                &request.local_path,
+                &request.remote_path,
+                operation_id,
+                on_progress
+            )
+            .await
+    )
+}
+
+/// Extract an archive on the remote host
+#[tauri::command]
+pub async fn sftp_extract_archive(
+    state: State<'_, AppState>,
+    request: ExtractArchiveRequest,
+) -> Result<(), String> {
+    sftp_result!(
+        state
+            .sftp_archive_service
+            .extract_archive(
+                &request.session_id,
+                &request.archive_path,
+                request.destination.as_deref(),
+                request.format
+            )
             .await
     )
 }
+
+/// Create an archive on the remote host
+#[tauri::command]
+pub async fn sftp_create_archive(
+    state: State<'_, AppState>,
+    request: CreateArchiveRequest,
+) -> Result<(), String> {
+    sftp_result!(
+        state
+            .sftp_archive_service
+            .create_archive(
+                &request.session_id,
+                &request.source_paths,
+                &request.archive_path,
+                request.format
+            )
+            .await
+    )
+}
+
+/// Build a progress callback that emits `sftp_archive_progress` events
+fn archive_progress_emitter(
+    app_handle: tauri::AppHandle,
+    operation_id: String,
+) -> impl FnMut(u64) + Send {
+    move |bytes_transferred| {
+        let _ = app_handle.emit(
+            "sftp_archive_progress",
+            &ArchiveProgressEvent {
+                operation_id: operation_id.clone(),
+                bytes_transferred,
+            },
+        );
+    }
+}
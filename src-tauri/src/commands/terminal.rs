@@ -79,21 +79,21 @@ pub async fn create_ssh_config_terminal(
     app_state: State<'_, AppState>,
     app_handle: AppHandle,
 ) -> Result<CreateTerminalResponse, AppError> {
-    use crate::services::ssh_config_parser::parse_ssh_config;
+    use crate::services::ssh_config_parser::load_ssh_config;
 
-    let hosts = parse_ssh_config(None)
+    let ssh_config = load_ssh_config(None)
         .await
         .map_err(|e| AppError::Config(format!("Failed to parse SSH config: {}", e)))?;
 
-    let host = hosts
-        .iter()
-        .find(|h| h.name == request.host_name)
-        .ok_or_else(|| {
-            AppError::Config(format!(
-                "Host '{}' not found in SSH config",
-                request.host_name
-            ))
-        })?;
+    if !ssh_config.host_aliases().contains(&request.host_name) {
+        return Err(AppError::Config(format!(
+            "Host '{}' not found in SSH config",
+            request.host_name
+        )));
+    }
+
+    // Options from wildcard and Match blocks apply as they would for ssh
+    let host = ssh_config.resolve(&request.host_name);
 
     // Check if password is required but not provided
     if host.requires_password() && request.password.is_none() {
@@ -1,3 +1,4 @@
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -32,11 +33,25 @@ pub enum SessionLockReason {
     Error(String),
 }
 
+/// Holder of decrypted secrets that must be wiped when the session locks
+/// Holders re-resolve their secrets lazily once the session is unlocked again
+#[async_trait]
+pub trait SessionLockHandler: Send + Sync {
+    async fn on_session_locked(&self);
+}
+
+async fn run_lock_handlers(handlers: &[Arc<dyn SessionLockHandler>]) {
+    for handler in handlers {
+        handler.on_session_locked().await;
+    }
+}
+
 pub struct AuthSessionManager {
     database_service: Arc<Mutex<DatabaseService>>,
     event_sender: broadcast::Sender<AuthEvent>,
     session_check_handle: Option<tokio::task::JoinHandle<()>>,
     app_handle: Option<tauri::AppHandle>,
+    lock_handlers: Vec<Arc<dyn SessionLockHandler>>,
 }
 
 impl AuthSessionManager {
@@ -48,6 +63,7 @@ impl AuthSessionManager {
             event_sender,
             session_check_handle: None,
             app_handle: None,
+            lock_handlers: Vec::new(),
         }
     }
 
@@ -55,6 +71,12 @@ impl AuthSessionManager {
         self.app_handle = Some(app_handle);
     }
 
+    /// Register a holder of decrypted secrets to wipe on lock
+    /// Must be called before session monitoring starts
+    pub fn register_lock_handler(&mut self, handler: Arc<dyn SessionLockHandler>) {
+        self.lock_handlers.push(handler);
+    }
+
     fn emit_tauri_event(&self, event_name: &str, payload: &impl serde::Serialize) {
         if let Some(handle) = &self.app_handle {
             let _ = handle.emit(event_name, payload);
@@ -113,6 +135,7 @@ impl AuthSessionManager {
         let db_service = Arc::clone(&self.database_service);
         let event_sender = self.event_sender.clone();
         let app_handle = self.app_handle.clone();
+        let lock_handlers = self.lock_handlers.clone();
 
         let session_check_task = tokio::spawn(async move {
             let mut check_interval = interval(Duration::from_secs(30));
@@ -127,6 +150,8 @@ impl AuthSessionManager {
                         if !is_valid {
                             // Lock session first
                             db_guard.lock_session().await;
+                            drop(db_guard);
+                            run_lock_handlers(&lock_handlers).await;
 
                             let event = AuthEvent::SessionLocked {
                                 timestamp: Utc::now(),
@@ -148,6 +173,9 @@ impl AuthSessionManager {
                         }
                     }
                     Err(_) => {
+                        drop(db_guard);
+                        run_lock_handlers(&lock_handlers).await;
+
                         let event = AuthEvent::SessionLocked {
                             timestamp: Utc::now(),
                             reason: SessionLockReason::Error("Session check failed".to_string()),
@@ -186,8 +214,10 @@ impl AuthSessionManager {
         Ok(())
     }
 
-    /// Handle manual lock
+    /// Handle manual lock, wiping every registered holder of decrypted secrets
     pub async fn on_session_locked(&self, reason: SessionLockReason) -> DatabaseResult<()> {
+        run_lock_handlers(&self.lock_handlers).await;
+
         let event = AuthEvent::SessionLocked {
             timestamp: Utc::now(),
             reason,
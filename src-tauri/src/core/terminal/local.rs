@@ -17,6 +17,8 @@ pub struct LocalTerminal {
     pty_pair: Option<(Box<dyn MasterPty + Send>, Box<dyn Child + Send + Sync>)>,
     writer: Option<Arc<Mutex<Box<dyn Write + Send>>>>,
     title_detector: TitleDetector,
+    /// Arguments for running the configured shell as a plain program
+    program_args: Option<Vec<String>>,
 }
 
 impl LocalTerminal {
@@ -34,9 +36,16 @@ impl LocalTerminal {
             pty_pair: None,
             writer: None,
             title_detector: TitleDetector::new(),
+            program_args: None,
         })
     }
 
+    /// Run the configured shell as a program with these arguments, without login or command flags
+    pub fn with_program_args(mut self, args: Vec<String>) -> Self {
+        self.program_args = Some(args);
+        self
+    }
+
     /// Connect to the local terminal by creating a PTY
     pub async fn connect(&mut self) -> Result<(), AppError> {
         self.state = TerminalState::Connecting;
@@ -73,13 +82,20 @@ impl LocalTerminal {
 
         let mut cmd = CommandBuilder::new(&shell);
 
-        if shell.contains("zsh") {
+        if let Some(args) = &self.program_args {
+            cmd.args(args);
+        } else if shell.contains("zsh") {
             cmd.arg("-l");
         } else if shell.contains("bash") {
             cmd.arg("-l");
         }
 
-        if let Some(command) = &self.local_config.command {
+        if let Some(command) = self
+            .local_config
+            .command
+            .as_ref()
+            .filter(|_| self.program_args.is_none())
+        {
             if !command.is_empty() {
                 if shell.contains("zsh") || shell.contains("bash") || shell.ends_with("/sh") {
                     let new_cmd = format!("{}; exec {} -l", command, shell);
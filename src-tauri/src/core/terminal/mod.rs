@@ -3,7 +3,13 @@ pub mod ssh;
 
 use crate::database::service::DatabaseService;
 use crate::error::AppError;
-use crate::models::terminal::{TerminalConfig, TerminalExited, TerminalState, TerminalType};
+use crate::models::ssh::key::ResolvedSSHKey;
+use crate::models::ssh::SSHProfile;
+use crate::models::terminal::{
+    LocalConfig, TerminalConfig, TerminalExited, TerminalState, TerminalType,
+};
+use crate::services::ssh::certificate_authority::renew_expiring_certificate;
+use crate::services::ssh::hardware_key::{openssh_args, security_key_file};
 use std::sync::Arc;
 use tokio::sync::{mpsc, Mutex};
 
@@ -102,6 +108,23 @@ impl TerminalWrapper {
 pub struct TerminalFactory;
 
 impl TerminalFactory {
+    /// Terminal running the system OpenSSH client, for profiles using a FIDO2 security key
+    pub fn create_openssh_terminal(
+        id: String,
+        config: TerminalConfig,
+        profile: &SSHProfile,
+        key: &ResolvedSSHKey,
+    ) -> Result<TerminalWrapper, AppError> {
+        let identity_file = security_key_file(key)?;
+        let local_config = LocalConfig {
+            shell: Some("ssh".to_string()),
+            ..LocalConfig::default()
+        };
+        let terminal = local::LocalTerminal::new(id, config, local_config)?
+            .with_program_args(openssh_args(profile, &identity_file));
+        Ok(TerminalWrapper::Local(Box::new(terminal)))
+    }
+
     /// Create a new terminal instance with SSH key service for key resolution
     pub async fn create_terminal(
         id: String,
@@ -132,6 +155,12 @@ impl TerminalFactory {
 
                 let ssh_profile = {
                     let db_service = database_service.lock().await;
+                    if let Err(e) = renew_expiring_certificate(&db_service, &ssh_profile_id).await {
+                        eprintln!(
+                            "Warning: Failed to renew certificate for SSH profile {}: {}",
+                            ssh_profile_id, e
+                        );
+                    }
                     db_service
                         .get_ssh_profile(&ssh_profile_id)
                         .await
@@ -158,12 +187,13 @@ impl TerminalFactory {
                     .to_temporary_profile(password)
                     .map_err(|e| AppError::Config(format!("Failed to create profile: {}", e)))?;
 
-                Ok(TerminalWrapper::Ssh(Box::new(ssh::SSHTerminal::new(
-                    id,
-                    config,
-                    ssh_profile,
-                    database_service,
-                )?)))
+                let mut terminal =
+                    ssh::SSHTerminal::new(id, config, ssh_profile, database_service)?;
+                if let Some(interval) = ssh_config_host.keepalive_interval() {
+                    terminal = terminal.with_keepalive_interval(interval);
+                }
+
+                Ok(TerminalWrapper::Ssh(Box::new(terminal)))
             }
         }
     }
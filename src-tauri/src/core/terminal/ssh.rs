@@ -3,6 +3,8 @@ use crate::error::AppError;
 use crate::models::ssh::key::ResolvedSSHKey;
 use crate::models::ssh::{AuthData, SSHProfile};
 use crate::models::terminal::{TerminalConfig, TerminalState};
+use crate::services::ssh::certificate_authority::load_certificate;
+use crate::services::ssh::hardware_key::authenticate_with_key;
 use async_trait::async_trait;
 use russh::client::{DisconnectReason, Handle, Handler, Session};
 use russh::{client::Msg, Channel, ChannelId, Disconnect};
@@ -167,6 +169,7 @@ pub struct SSHTerminal {
     channel: Option<Channel<Msg>>,
     handler: Arc<ClientHandler>,
     database_service: Option<Arc<tokio::sync::Mutex<crate::database::service::DatabaseService>>>,
+    keepalive_interval: std::time::Duration,
 }
 
 impl SSHTerminal {
@@ -188,9 +191,16 @@ impl SSHTerminal {
             channel: None,
             handler,
             database_service,
+            keepalive_interval: std::time::Duration::from_secs(15),
         })
     }
 
+    /// Send keepalives at this interval instead of the default 15 seconds
+    pub fn with_keepalive_interval(mut self, interval: std::time::Duration) -> Self {
+        self.keepalive_interval = interval;
+        self
+    }
+
     /// Connect to the SSH server
     pub async fn connect(&mut self) -> Result<(), AppError> {
         self.connect_with_resolved_data(None).await
@@ -204,7 +214,7 @@ impl SSHTerminal {
         self.state = TerminalState::Connecting;
 
         let keepalive_interval = if self.ssh_profile.keep_alive {
-            Some(std::time::Duration::from_secs(15))
+            Some(self.keepalive_interval)
         } else {
             None
         };
@@ -279,52 +289,7 @@ impl SSHTerminal {
             )
             .await;
 
-        // Handle command, working directory, and environment variables
-        let mut command_parts: Vec<String> = Vec::new();
-
-        // Inject environment variables via export commands
-        if let Some(env) = &self.ssh_profile.env {
-            for (key, value) in env {
-                // Validate key to prevent command injection
-                if key.is_empty()
-                    || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
-                    || key
-                        .chars()
-                        .next()
-                        .map(|c| c.is_ascii_digit())
-                        .unwrap_or(false)
-                {
-                    eprintln!(
-                        "Warning: Skipping invalid environment variable key: {}",
-                        key
-                    );
-                    continue;
-                }
-
-                // Escape single quotes in value
-                let escaped_value = value.replace("'", "'\\''");
-                command_parts.push(format!("export {}='{}'", key, escaped_value));
-            }
-        }
-
-        if let Some(wd) = &self.ssh_profile.working_dir {
-            if !wd.is_empty() {
-                command_parts.push(format!("cd \"{}\"", wd));
-            }
-        }
-
-        if let Some(cmd) = &self.ssh_profile.command {
-            if !cmd.is_empty() {
-                command_parts.push(cmd.clone());
-            }
-        }
-
-        if !command_parts.is_empty() {
-            // Join parts with && to ensure sequence
-            let mut full_command = command_parts.join(" && ");
-            // Append shell execution to keep session open
-            full_command.push_str("; exec ${SHELL:-bash} -l");
-
+        if let Some(full_command) = startup_command(&self.ssh_profile) {
             let _ = channel.exec(false, full_command.as_bytes()).await;
         } else {
             let _ = channel.request_shell(false).await;
@@ -372,33 +337,7 @@ impl SSHTerminal {
                     ))
                 })?;
 
-                let key = if Path::new(&key_data.private_key).exists() {
-                    russh_keys::load_secret_key(
-                        &key_data.private_key,
-                        key_data.passphrase.as_deref(),
-                    )
-                    .map_err(|e| {
-                        AppError::authentication_failed(format!("Failed to load SSH key: {}", e))
-                    })?
-                } else {
-                    russh_keys::decode_secret_key(
-                        &key_data.private_key,
-                        key_data.passphrase.as_deref(),
-                    )
-                    .map_err(|e| {
-                        AppError::authentication_failed(format!("Failed to parse SSH key: {}", e))
-                    })?
-                };
-
-                let result = session
-                    .authenticate_publickey(username, Arc::new(key))
-                    .await
-                    .map_err(|e| {
-                        AppError::authentication_failed(format!(
-                            "SSH key authentication error for user '{}': {}",
-                            username, e
-                        ))
-                    })?;
+                let result = authenticate_with_key(session, username, &key_data).await?;
 
                 if !result {
                     return Err(AppError::authentication_failed(format!(
@@ -436,32 +375,32 @@ impl SSHTerminal {
                     })?
                 };
 
-                let _cert = if Path::new(certificate).exists() {
-                    russh_keys::load_public_key(certificate).map_err(|e| {
-                        AppError::authentication_failed(format!(
-                            "Failed to load certificate: {}",
-                            e
-                        ))
-                    })?
+                // Identities from ssh config only carry a certificate when one is found
+                let result = if certificate.trim().is_empty() {
+                    session
+                        .authenticate_publickey(username, Arc::new(key))
+                        .await
+                        .map_err(|e| {
+                            AppError::authentication_failed(format!(
+                                "Public key authentication error for user '{}': {}",
+                                username, e
+                            ))
+                        })?
                 } else {
-                    russh_keys::parse_public_key_base64(certificate).map_err(|e| {
-                        AppError::authentication_failed(format!(
-                            "Failed to parse certificate: {}",
-                            e
-                        ))
-                    })?
+                    let cert = load_certificate(certificate)
+                        .map_err(|e| AppError::authentication_failed(e.to_string()))?;
+
+                    session
+                        .authenticate_openssh_cert(username, Arc::new(key), cert)
+                        .await
+                        .map_err(|e| {
+                            AppError::authentication_failed(format!(
+                                "Certificate authentication error for user '{}': {}",
+                                username, e
+                            ))
+                        })?
                 };
 
-                let result = session
-                    .authenticate_publickey(username, Arc::new(key))
-                    .await
-                    .map_err(|e| {
-                        AppError::authentication_failed(format!(
-                            "Certificate authentication error for user '{}': {}",
-                            username, e
-                        ))
-                    })?;
-
                 if !result {
                     return Err(AppError::authentication_failed(format!(
                         "Certificate authentication failed for user '{}'",
@@ -593,3 +532,55 @@ impl SSHTerminal {
         Ok(())
     }
 }
+
+/// Command a session runs instead of a plain shell, for the profile's environment,
+/// working directory and startup command
+pub(crate) fn startup_command(profile: &SSHProfile) -> Option<String> {
+    let mut command_parts: Vec<String> = Vec::new();
+
+    // Inject environment variables via export commands
+    if let Some(env) = &profile.env {
+        for (key, value) in env {
+            // Validate key to prevent command injection
+            if key.is_empty()
+                || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                || key
+                    .chars()
+                    .next()
+                    .map(|c| c.is_ascii_digit())
+                    .unwrap_or(false)
+            {
+                eprintln!(
+                    "Warning: Skipping invalid environment variable key: {}",
+                    key
+                );
+                continue;
+            }
+
+            // Escape single quotes in value
+            let escaped_value = value.replace("'", "'\\''");
+            command_parts.push(format!("export {}='{}'", key, escaped_value));
+        }
+    }
+
+    if let Some(wd) = &profile.working_dir {
+        if !wd.is_empty() {
+            command_parts.push(format!("cd \"{}\"", wd));
+        }
+    }
+
+    if let Some(cmd) = &profile.command {
+        if !cmd.is_empty() {
+            command_parts.push(cmd.clone());
+        }
+    }
+
+    if command_parts.is_empty() {
+        return None;
+    }
+    // Join parts with && to ensure sequence
+    let mut full_command = command_parts.join(" && ");
+    // Append shell execution to keep session open
+    full_command.push_str("; exec ${SHELL:-bash} -l");
+    Some(full_command)
+}
@@ -1,6 +1,6 @@
 use crate::database::error::{EncryptionError, EncryptionResult};
 use aes_gcm::{
-    aead::{Aead, KeyInit, OsRng},
+    aead::{Aead, KeyInit, OsRng, Payload},
     Aes256Gcm, Nonce,
 };
 use rand::RngCore;
@@ -11,6 +11,11 @@ pub struct AESEncryption;
 impl AESEncryption {
     /// Encrypt data using AES-256-GCM
     pub fn encrypt(key: &[u8; 32], data: &[u8]) -> EncryptionResult<Vec<u8>> {
+        Self::encrypt_with_aad(key, data, &[])
+    }
+
+    /// Encrypt data using AES-256-GCM, authenticating `aad` alongside it without encrypting it
+    pub fn encrypt_with_aad(key: &[u8; 32], data: &[u8], aad: &[u8]) -> EncryptionResult<Vec<u8>> {
         let cipher = Aes256Gcm::new_from_slice(key)
             .map_err(|e| EncryptionError::InvalidKey(e.to_string()))?;
 
@@ -19,7 +24,7 @@ impl AESEncryption {
         let nonce = &Nonce::from(nonce_bytes);
 
         let ciphertext = cipher
-            .encrypt(nonce, data)
+            .encrypt(nonce, Payload { msg: data, aad })
             .map_err(|e| EncryptionError::EncryptionFailed(e.to_string()))?;
 
         let mut result = Vec::with_capacity(12 + ciphertext.len());
@@ -31,6 +36,15 @@ impl AESEncryption {
 
     /// Decrypt data using AES-256-GCM
     pub fn decrypt(key: &[u8; 32], encrypted_data: &[u8]) -> EncryptionResult<Vec<u8>> {
+        Self::decrypt_with_aad(key, encrypted_data, &[])
+    }
+
+    /// Decrypt data sealed by `encrypt_with_aad`; fails if `aad` differs from the one used to encrypt
+    pub fn decrypt_with_aad(
+        key: &[u8; 32],
+        encrypted_data: &[u8],
+        aad: &[u8],
+    ) -> EncryptionResult<Vec<u8>> {
         if encrypted_data.len() < 12 {
             return Err(EncryptionError::InvalidFormat);
         }
@@ -42,7 +56,13 @@ impl AESEncryption {
         let nonce = &Nonce::from(*<&[u8; 12]>::try_from(nonce_bytes).unwrap());
 
         let plaintext = cipher
-            .decrypt(nonce, ciphertext)
+            .decrypt(
+                nonce,
+                Payload {
+                    msg: ciphertext,
+                    aad,
+                },
+            )
             .map_err(|e| EncryptionError::DecryptionFailed(e.to_string()))?;
 
         Ok(plaintext)
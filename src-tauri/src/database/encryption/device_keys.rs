@@ -2,6 +2,7 @@ use argon2::password_hash::SaltString;
 use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
+use zeroize::{Zeroize, Zeroizing};
 
 use crate::database::{
     config::MasterPasswordConfig,
@@ -18,6 +19,7 @@ pub struct DeviceKeyManager {
 }
 
 /// Device encryption key information
+/// Key material is wiped from memory when the key is dropped
 #[derive(Debug, Clone)]
 pub struct DeviceEncryptionKey {
     #[allow(dead_code)]
@@ -34,6 +36,13 @@ pub struct DeviceEncryptionKey {
     pub last_used_at: DateTime<Utc>,
 }
 
+impl Drop for DeviceEncryptionKey {
+    fn drop(&mut self) {
+        self.encryption_key.zeroize();
+        self.key_salt.zeroize();
+    }
+}
+
 /// Master password entry information
 #[derive(Debug, Clone)]
 pub struct MasterPasswordEntry {
@@ -146,6 +155,7 @@ impl DeviceKeyManager {
 
         match self.keychain.get_master_password(device_id) {
             Ok(Some(password)) => {
+                let password = Zeroizing::new(password);
                 if self.verify_password_for_device(&password, entry)? {
                     let device_key = self.derive_device_key(&password, &entry.password_salt)?;
                     self.device_keys.insert(device_id.to_string(), device_key);
@@ -159,6 +169,7 @@ impl DeviceKeyManager {
         match self.keychain.get_device_key(device_id) {
             Ok(Some(key_bytes)) => {
                 if key_bytes.len() == 32 {
+                    let key_bytes = Zeroizing::new(key_bytes);
                     let mut key_array = [0u8; 32];
                     key_array.copy_from_slice(&key_bytes);
 
@@ -353,7 +364,7 @@ impl DeviceKeyManager {
         self.create_master_password(new_password, config)
     }
 
-    /// Clear all device keys from memory
+    /// Clear all device keys from memory, wiping their key material
     pub fn clear_all_keys(&mut self) {
         self.device_keys.clear();
     }
@@ -467,3 +478,32 @@ impl DeviceKeyManager {
             .is_ok())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clear_all_keys_evicts_device_keys() {
+        let config = MasterPasswordConfig {
+            use_keychain: false,
+            ..MasterPasswordConfig::default()
+        };
+        let mut manager = DeviceKeyManager::new("device-1".to_string());
+        let entry = manager.create_master_password("secret", &config).unwrap();
+        manager.ensure_shared_device_key_from_current().unwrap();
+        let encrypted = manager.encrypt_with_device(b"payload", None).unwrap();
+
+        manager.clear_all_keys();
+
+        assert!(manager.get_loaded_device_ids().is_empty());
+        assert!(manager.decrypt_with_device(&encrypted, None).is_err());
+
+        // Unlocking again re-derives the same key
+        assert!(manager.verify_master_password("secret", &entry).unwrap());
+        assert_eq!(
+            manager.decrypt_with_device(&encrypted, None).unwrap(),
+            b"payload"
+        );
+    }
+}
@@ -100,6 +100,45 @@ impl KeychainManager {
         }
     }
 
+    /// Store the password automatic backup snapshots are encrypted with
+    pub fn store_backup_password(&self, device_id: &str, password: &str) -> EncryptionResult<()> {
+        let service = format!("{}_backup_password", self.app_name);
+        let entry = Entry::new(&service, device_id)
+            .map_err(|e| EncryptionError::KeychainError(e.to_string()))?;
+
+        entry
+            .set_password(password)
+            .map_err(|e| EncryptionError::KeychainError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Retrieve the backup snapshot password from keychain
+    pub fn get_backup_password(&self, device_id: &str) -> EncryptionResult<Option<String>> {
+        let service = format!("{}_backup_password", self.app_name);
+        let entry = Entry::new(&service, device_id)
+            .map_err(|e| EncryptionError::KeychainError(e.to_string()))?;
+
+        match entry.get_password() {
+            Ok(password) => Ok(Some(password)),
+            Err(KeyringError::NoEntry) => Ok(None),
+            Err(e) => Err(EncryptionError::KeychainError(e.to_string())),
+        }
+    }
+
+    /// Delete the backup snapshot password from keychain
+    pub fn delete_backup_password(&self, device_id: &str) -> EncryptionResult<()> {
+        let service = format!("{}_backup_password", self.app_name);
+        let entry = Entry::new(&service, device_id)
+            .map_err(|e| EncryptionError::KeychainError(e.to_string()))?;
+
+        match entry.delete_password() {
+            Ok(()) => Ok(()),
+            Err(KeyringError::NoEntry) => Ok(()), // Already deleted
+            Err(e) => Err(EncryptionError::KeychainError(e.to_string())),
+        }
+    }
+
     pub fn is_available(&self) -> bool {
         let service = format!("{}_test", self.app_name);
         Entry::new(&service, "test").is_ok()
@@ -3,9 +3,11 @@ pub mod device_keys;
 pub mod external_db;
 pub mod keychain;
 pub mod master_password;
+pub mod sync_keys;
 
 pub use aes::AESEncryption;
 pub use device_keys::DeviceKeyManager;
 pub use external_db::ExternalDbEncryptor;
 pub use keychain::KeychainManager;
 pub use master_password::MasterPasswordManager;
+pub use sync_keys::{SyncKeyring, WrappedSyncKey};
@@ -0,0 +1,328 @@
+use argon2::Argon2;
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use zeroize::Zeroizing;
+
+use crate::database::{
+    encryption::AESEncryption,
+    error::{EncryptionError, EncryptionResult},
+};
+
+/// Read a field that sync targets may return in camelCase or snake_case
+fn record_field<'a>(record: &'a Value, camel: &str, snake: &str) -> Option<&'a Value> {
+    record
+        .get(camel)
+        .or_else(|| record.get(snake))
+        .filter(|v| !v.is_null())
+}
+
+fn record_str<'a>(record: &'a Value, camel: &str, snake: &str) -> EncryptionResult<&'a str> {
+    record_field(record, camel, snake)
+        .and_then(|v| v.as_str())
+        .ok_or(EncryptionError::InvalidFormat)
+}
+
+fn decode_base64(data: &str) -> EncryptionResult<Vec<u8>> {
+    general_purpose::STANDARD
+        .decode(data)
+        .map_err(|_| EncryptionError::InvalidFormat)
+}
+
+/// Parse a timestamp as RFC 3339, or in the format MySQL stores it
+fn parse_timestamp(value: &str) -> EncryptionResult<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").map(|dt| dt.and_utc())
+        })
+        .map_err(|_| EncryptionError::InvalidFormat)
+}
+
+/// Id of the sealed envelope holding a record of the given table
+pub fn sealed_record_id(table: &str, id: &str) -> String {
+    format!("{}:{}", table, id)
+}
+
+/// Clear envelope fields authenticated along with the payload, so the server cannot alter them
+/// Values are normalized first: targets return versions as numbers or text, and some keep
+/// timestamps only to the second
+fn envelope_aad(envelope: &Value) -> EncryptionResult<Vec<u8>> {
+    let version = match record_field(envelope, "version", "version") {
+        Some(Value::Number(n)) => n.as_i64(),
+        Some(Value::String(s)) => s.parse().ok(),
+        _ => None,
+    }
+    .ok_or(EncryptionError::InvalidFormat)?;
+    let timestamp = |camel, snake| -> EncryptionResult<Option<i64>> {
+        record_field(envelope, camel, snake)
+            .map(|v| {
+                let text = v.as_str().ok_or(EncryptionError::InvalidFormat)?;
+                parse_timestamp(text).map(|dt| dt.timestamp())
+            })
+            .transpose()
+    };
+
+    Ok(json!([
+        record_str(envelope, "tableName", "table_name")?,
+        record_str(envelope, "id", "id")?,
+        version,
+        timestamp("updatedAt", "updated_at")?,
+        timestamp("deletedAt", "deleted_at")?,
+    ])
+    .to_string()
+    .into_bytes())
+}
+
+/// Derive the key wrapping a sync key from the master password (Argon2id)
+fn derive_wrapping_key(password: &str, salt: &[u8]) -> EncryptionResult<Zeroizing<[u8; 32]>> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::default()
+        .hash_password_into(password.as_bytes(), salt, key.as_mut())
+        .map_err(|e| EncryptionError::KeyDerivationFailed(e.to_string()))?;
+    Ok(key)
+}
+
+/// A sync key wrapped with the master password, as published on the sync target
+#[derive(Debug, Clone)]
+pub struct WrappedSyncKey {
+    pub key_id: String,
+    pub salt: Vec<u8>,
+    pub wrapped_key: Vec<u8>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl WrappedSyncKey {
+    /// Wrap a sync key with a fresh salt
+    pub fn wrap(key_id: String, key: &[u8; 32], password: &str) -> EncryptionResult<Self> {
+        let salt = AESEncryption::generate_salt();
+        let wrapping_key = derive_wrapping_key(password, &salt)?;
+
+        Ok(Self {
+            key_id,
+            salt: salt.to_vec(),
+            wrapped_key: AESEncryption::encrypt(&wrapping_key, key)?,
+            created_at: Utc::now(),
+        })
+    }
+
+    /// Recover the sync key, failing if the password is wrong
+    pub fn unwrap_key(&self, password: &str) -> EncryptionResult<Zeroizing<[u8; 32]>> {
+        let wrapping_key = derive_wrapping_key(password, &self.salt)?;
+        let decrypted = AESEncryption::decrypt(&wrapping_key, &self.wrapped_key)
+            .map(Zeroizing::new)
+            .map_err(|_| EncryptionError::MasterPasswordVerificationFailed)?;
+        if decrypted.len() != 32 {
+            return Err(EncryptionError::InvalidFormat);
+        }
+
+        let mut key = Zeroizing::new([0u8; 32]);
+        key.copy_from_slice(&decrypted);
+        Ok(key)
+    }
+
+    pub fn to_record(&self) -> Value {
+        json!({
+            "id": self.key_id,
+            "version": 1,
+            "salt": general_purpose::STANDARD.encode(&self.salt),
+            "wrappedKey": general_purpose::STANDARD.encode(&self.wrapped_key),
+            "createdAt": self.created_at.to_rfc3339(),
+            "updatedAt": self.created_at.to_rfc3339(),
+        })
+    }
+
+    pub fn from_record(record: &Value) -> EncryptionResult<Self> {
+        let created_at = parse_timestamp(record_str(record, "createdAt", "created_at")?)?;
+
+        Ok(Self {
+            key_id: record_str(record, "id", "id")?.to_string(),
+            salt: decode_base64(record_str(record, "salt", "salt")?)?,
+            wrapped_key: decode_base64(record_str(record, "wrappedKey", "wrapped_key")?)?,
+            created_at,
+        })
+    }
+}
+
+/// Unwrapped sync keys for one sync target
+/// The newest key seals outgoing records; older keys still open records sealed before a rotation
+/// Keys are wiped from memory when the keyring is dropped
+#[derive(Clone, Default)]
+pub struct SyncKeyring {
+    keys: HashMap<String, Zeroizing<[u8; 32]>>,
+    active_key_id: Option<String>,
+}
+
+impl SyncKeyring {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a key; keys must be added oldest first, the last one becomes active
+    pub fn add_key(&mut self, key_id: String, key: [u8; 32]) {
+        self.keys.insert(key_id.clone(), Zeroizing::new(key));
+        self.active_key_id = Some(key_id);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn has_key(&self, key_id: &str) -> bool {
+        self.keys.contains_key(key_id)
+    }
+
+    pub fn active_key_id(&self) -> Option<&str> {
+        self.active_key_id.as_deref()
+    }
+
+    pub fn key_count(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Drop every key, wiping it from memory
+    pub fn clear(&mut self) {
+        self.keys.clear();
+        self.active_key_id = None;
+    }
+
+    /// Seal a record into an envelope that only exposes its id, version and timestamps
+    /// The exposed fields are bound to the payload as associated data
+    pub fn seal(&self, table: &str, record: &Value) -> EncryptionResult<Value> {
+        let id = record_str(record, "id", "id")?;
+        let key_id = self
+            .active_key_id
+            .as_deref()
+            .ok_or_else(|| EncryptionError::UnknownDeviceKey("sync key".to_string()))?;
+        let key = &self.keys[key_id];
+
+        let mut envelope = json!({
+            "id": sealed_record_id(table, id),
+            "tableName": table,
+            "keyId": key_id,
+            "version": record.get("version").cloned().unwrap_or(Value::from(1)),
+            "updatedAt": record_field(record, "updatedAt", "updated_at").cloned().unwrap_or(Value::Null),
+            "deletedAt": record_field(record, "deletedAt", "deleted_at").cloned().unwrap_or(Value::Null),
+        });
+
+        let plaintext = json!({ "table": table, "record": record }).to_string();
+        let payload =
+            AESEncryption::encrypt_with_aad(key, plaintext.as_bytes(), &envelope_aad(&envelope)?)?;
+        envelope["payload"] = Value::String(general_purpose::STANDARD.encode(payload));
+
+        Ok(envelope)
+    }
+
+    /// Open an envelope sealed by `seal`
+    pub fn open(&self, table: &str, envelope: &Value) -> EncryptionResult<Value> {
+        let key_id = record_str(envelope, "keyId", "key_id")?;
+        let key = self
+            .keys
+            .get(key_id)
+            .ok_or_else(|| EncryptionError::UnknownDeviceKey(format!("sync key {}", key_id)))?;
+
+        let payload = decode_base64(record_str(envelope, "payload", "payload")?)?;
+        let plaintext = AESEncryption::decrypt_with_aad(key, &payload, &envelope_aad(envelope)?)?;
+        let mut sealed: Value = serde_json::from_slice(&plaintext)
+            .map_err(|e| EncryptionError::DecryptionFailed(e.to_string()))?;
+
+        // The server could move a payload to another row, so it must match the envelope it came in
+        let record = sealed
+            .get_mut("record")
+            .map(Value::take)
+            .ok_or(EncryptionError::InvalidFormat)?;
+        let sealed_table = sealed.get("table").and_then(|v| v.as_str());
+        let expected_id = sealed_record_id(table, record_str(&record, "id", "id")?);
+        if sealed_table != Some(table) || record_str(envelope, "id", "id")? != expected_id {
+            return Err(EncryptionError::DecryptionFailed(
+                "Sealed record does not match its envelope".to_string(),
+            ));
+        }
+
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let mut keyring = SyncKeyring::new();
+        keyring.add_key("k1".to_string(), AESEncryption::generate_key());
+
+        let record = json!({ "id": "p1", "version": 3, "name": "prod", "host": "10.0.0.1" });
+        let envelope = keyring.seal("ssh_profiles", &record).unwrap();
+
+        assert_eq!(envelope["id"], "ssh_profiles:p1");
+        assert_eq!(envelope["version"], 3);
+        assert!(envelope.get("host").is_none());
+        assert!(!envelope["payload"].as_str().unwrap().contains("10.0.0.1"));
+        assert_eq!(keyring.open("ssh_profiles", &envelope).unwrap(), record);
+
+        // A payload moved to another record or table is rejected
+        let mut moved = envelope.clone();
+        moved["id"] = json!("ssh_profiles:p2");
+        assert!(keyring.open("ssh_profiles", &moved).is_err());
+        assert!(keyring.open("ssh_keys", &envelope).is_err());
+    }
+
+    #[test]
+    fn test_tampered_envelope_fields_are_rejected() {
+        let mut keyring = SyncKeyring::new();
+        keyring.add_key("k1".to_string(), AESEncryption::generate_key());
+
+        let record = json!({
+            "id": "p1",
+            "version": 3,
+            "updatedAt": "2024-05-01T10:00:00.123Z",
+            "deletedAt": null,
+        });
+        let envelope = keyring.seal("ssh_profiles", &record).unwrap();
+
+        // Targets may hand back the fields as text, in their own timestamp format
+        let mut stored = envelope.clone();
+        stored["version"] = json!("3");
+        stored["updatedAt"] = json!("2024-05-01 10:00:00");
+        assert_eq!(keyring.open("ssh_profiles", &stored).unwrap(), record);
+
+        let tampered = [
+            ("version", json!(4)),
+            ("updatedAt", json!("2024-05-02T10:00:00Z")),
+            ("deletedAt", json!("2024-05-01T11:00:00Z")),
+            ("tableName", json!("ssh_keys")),
+        ];
+        for (field, value) in tampered {
+            let mut changed = envelope.clone();
+            changed[field] = value;
+            assert!(keyring.open("ssh_profiles", &changed).is_err(), "{}", field);
+        }
+    }
+
+    #[test]
+    fn test_rotated_keyring_opens_old_records() {
+        let mut keyring = SyncKeyring::new();
+        keyring.add_key("k1".to_string(), AESEncryption::generate_key());
+        let record = json!({ "id": "g1", "name": "servers" });
+        let old = keyring.seal("ssh_groups", &record).unwrap();
+
+        keyring.add_key("k2".to_string(), AESEncryption::generate_key());
+        let new = keyring.seal("ssh_groups", &record).unwrap();
+
+        assert_eq!(new["keyId"], "k2");
+        assert_eq!(keyring.open("ssh_groups", &old).unwrap(), record);
+        assert!(SyncKeyring::new().open("ssh_groups", &new).is_err());
+    }
+
+    #[test]
+    fn test_wrapped_key_requires_password() {
+        let key = AESEncryption::generate_key();
+        let wrapped = WrappedSyncKey::wrap("k1".to_string(), &key, "secret").unwrap();
+        let restored = WrappedSyncKey::from_record(&wrapped.to_record()).unwrap();
+
+        assert_eq!(*restored.unwrap_key("secret").unwrap(), key);
+        assert!(restored.unwrap_key("wrong").is_err());
+    }
+}
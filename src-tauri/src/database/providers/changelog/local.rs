@@ -0,0 +1,93 @@
+use async_trait::async_trait;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use crate::database::error::{DatabaseError, DatabaseResult};
+
+use super::store::{split_path, ObjectStore};
+
+/// Object store backed by a local folder, which can itself be shared with Syncthing or Dropbox
+pub struct LocalFolderStore {
+    root: PathBuf,
+}
+
+impl LocalFolderStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn resolve(&self, path: &str) -> PathBuf {
+        path.split('/')
+            .filter(|part| !part.is_empty())
+            .fold(self.root.clone(), |dir, part| dir.join(part))
+    }
+}
+
+fn io_error(path: &str, e: std::io::Error) -> DatabaseError {
+    DatabaseError::QueryFailed(format!("{}: {}", path, e))
+}
+
+#[async_trait]
+impl ObjectStore for LocalFolderStore {
+    async fn check(&self) -> DatabaseResult<()> {
+        tokio::fs::create_dir_all(&self.root)
+            .await
+            .map_err(|e| DatabaseError::ConnectionFailed(format!("{}: {}", self.root.display(), e)))
+    }
+
+    async fn read(&self, path: &str) -> DatabaseResult<Option<Vec<u8>>> {
+        match tokio::fs::read(self.resolve(path)).await {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(io_error(path, e)),
+        }
+    }
+
+    /// Written to a hidden temporary file first, so folder sync tools never pick up partial files
+    async fn write(&self, path: &str, data: Vec<u8>) -> DatabaseResult<()> {
+        let (dir, name) = split_path(path);
+        let dir = self.resolve(dir);
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| io_error(path, e))?;
+
+        let temp = dir.join(format!(".{}.tmp", name));
+        tokio::fs::write(&temp, data)
+            .await
+            .map_err(|e| io_error(path, e))?;
+        tokio::fs::rename(&temp, dir.join(name))
+            .await
+            .map_err(|e| io_error(path, e))
+    }
+
+    async fn list(&self, dir: &str) -> DatabaseResult<Vec<String>> {
+        let mut entries = match tokio::fs::read_dir(self.resolve(dir)).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(io_error(dir, e)),
+        };
+
+        let mut names = Vec::new();
+        while let Some(entry) = entries.next_entry().await.map_err(|e| io_error(dir, e))? {
+            let is_file = entry
+                .file_type()
+                .await
+                .map(|t| t.is_file())
+                .unwrap_or(false);
+            let name = entry.file_name().to_string_lossy().to_string();
+            if is_file && !name.starts_with('.') {
+                names.push(name);
+            }
+        }
+
+        Ok(names)
+    }
+
+    async fn delete(&self, path: &str) -> DatabaseResult<()> {
+        match tokio::fs::remove_file(self.resolve(path)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(io_error(path, e)),
+        }
+    }
+}
@@ -0,0 +1,713 @@
+mod local;
+mod s3;
+mod store;
+mod webdav;
+
+pub use local::LocalFolderStore;
+pub use s3::S3Store;
+pub use store::ObjectStore;
+pub use webdav::WebDavStore;
+
+use argon2::Argon2;
+use async_trait::async_trait;
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use zeroize::Zeroizing;
+
+use crate::database::{
+    encryption::AESEncryption,
+    error::{DatabaseError, DatabaseResult, EncryptionError},
+    traits_sync::{ChangeNotifications, RecordChanges, SyncTarget},
+};
+use crate::models::sync::external_db::{ConnectionDetails, DatabaseType};
+
+/// Plaintext manifest holding the salt of the store key
+const MANIFEST_PATH: &str = "kerminal-sync.json";
+const MANIFEST_FORMAT: u64 = 1;
+/// Known plaintext sealed in the manifest to detect a wrong passphrase
+const KEY_CHECK: &[u8] = b"kerminal-sync";
+const SEGMENT_EXTENSION: &str = ".enc";
+/// Segments of a table are merged into one once there are more than this
+const COMPACT_THRESHOLD: usize = 32;
+
+/// Sync target storing encrypted change logs in an object store
+///
+/// Each push appends a segment holding the pushed records; the current state of a table is
+/// the fold of its segments, keeping the highest version of every record. Segments are
+/// never rewritten, so devices only ever add files, which keeps folder sync tools
+/// (Syncthing, Dropbox) free of conflicting copies.
+///
+/// Layout:
+/// - `kerminal-sync.json`: salt of the key derived from the passphrase
+/// - `changes/<table>/<timestamp>-<uuid>.enc`: encrypted JSON array of records
+/// - `devices/<device>.enc`: registered devices
+/// - `acks/<table>/<device>.enc`: tombstones acknowledged by a device
+pub struct ChangeLogProvider {
+    store: Box<dyn ObjectStore>,
+    passphrase: Zeroizing<String>,
+    /// Wiped when the provider is dropped, as the sync manager does when the session locks
+    key: Option<Zeroizing<[u8; 32]>>,
+}
+
+impl ChangeLogProvider {
+    pub fn new(store: Box<dyn ObjectStore>, passphrase: String) -> Self {
+        Self {
+            store,
+            passphrase: Zeroizing::new(passphrase),
+            key: None,
+        }
+    }
+
+    /// Build the provider for a file-based database type from its connection details
+    ///
+    /// - folder: `host` is the folder path
+    /// - WebDAV: `host` is the server URL, `database_name` the folder created under it
+    /// - S3: `host` is the endpoint, `database_name` the bucket, `username`/`password` the
+    ///   access and secret keys; `options` accepts `region`, `prefix` and `pathStyle`
+    pub fn from_connection_details(
+        db_type: &DatabaseType,
+        details: &ConnectionDetails,
+    ) -> DatabaseResult<Self> {
+        let passphrase = details
+            .encryption_passphrase
+            .clone()
+            .filter(|p| !p.is_empty())
+            .ok_or_else(|| {
+                DatabaseError::ConfigError(
+                    "An encryption passphrase is required for file-based sync targets".to_string(),
+                )
+            })?;
+
+        let store: Box<dyn ObjectStore> = match db_type {
+            DatabaseType::LocalFolder => Box::new(LocalFolderStore::new(&details.host)),
+            DatabaseType::WebDAV => Box::new(WebDavStore::new(
+                &details.host,
+                &details.database_name,
+                details.username.clone(),
+                details.password.clone(),
+            )),
+            DatabaseType::S3 => Box::new(S3Store::new(
+                &details.host,
+                details.database_name.clone(),
+                details
+                    .option("region")
+                    .unwrap_or_else(|| "us-east-1".to_string()),
+                details.username.clone(),
+                details.password.clone(),
+                &details
+                    .option("prefix")
+                    .unwrap_or_else(|| "kerminal".to_string()),
+                details.option("pathStyle").as_deref() != Some("false"),
+            )?),
+            _ => {
+                return Err(DatabaseError::ConfigError(format!(
+                    "{} is not a file-based sync target",
+                    db_type
+                )))
+            }
+        };
+
+        Ok(Self::new(store, passphrase))
+    }
+
+    fn key(&self) -> DatabaseResult<&[u8; 32]> {
+        self.key
+            .as_deref()
+            .ok_or_else(|| DatabaseError::ConnectionFailed("Database not connected".to_string()))
+    }
+
+    /// Read the manifest, creating it on first use, and derive the store key
+    async fn open_manifest(&self) -> DatabaseResult<[u8; 32]> {
+        if let Some(data) = self.store.read(MANIFEST_PATH).await? {
+            let manifest: Value = serde_json::from_slice(&data)?;
+            let format = manifest.get("format").and_then(|v| v.as_u64()).unwrap_or(0);
+            if format > MANIFEST_FORMAT {
+                return Err(DatabaseError::SyncError(format!(
+                    "Sync store format {} is newer than this version supports",
+                    format
+                )));
+            }
+
+            let field = |name: &str| -> DatabaseResult<Vec<u8>> {
+                manifest
+                    .get(name)
+                    .and_then(|v| v.as_str())
+                    .and_then(|v| general_purpose::STANDARD.decode(v).ok())
+                    .ok_or_else(|| {
+                        DatabaseError::ParseError(format!("Invalid sync manifest: {}", name))
+                    })
+            };
+
+            let key = derive_store_key(&self.passphrase, &field("salt")?)?;
+            AESEncryption::decrypt(&key, &field("check")?).map_err(|_| {
+                DatabaseError::AuthenticationFailed("Wrong encryption passphrase".to_string())
+            })?;
+            return Ok(key);
+        }
+
+        let salt = AESEncryption::generate_salt();
+        let key = derive_store_key(&self.passphrase, &salt)?;
+        let manifest = json!({
+            "format": MANIFEST_FORMAT,
+            "salt": general_purpose::STANDARD.encode(salt),
+            "check": general_purpose::STANDARD.encode(AESEncryption::encrypt(&key, KEY_CHECK)?),
+            "createdAt": Utc::now().to_rfc3339(),
+        });
+        self.store
+            .write(MANIFEST_PATH, serde_json::to_vec_pretty(&manifest)?)
+            .await?;
+
+        Ok(key)
+    }
+
+    async fn read_encrypted(&self, path: &str) -> DatabaseResult<Option<Value>> {
+        let Some(data) = self.store.read(path).await? else {
+            return Ok(None);
+        };
+        let plaintext = AESEncryption::decrypt(self.key()?, &data)?;
+        Ok(Some(serde_json::from_slice(&plaintext)?))
+    }
+
+    async fn write_encrypted(&self, path: &str, value: &Value) -> DatabaseResult<()> {
+        let data = AESEncryption::encrypt(self.key()?, &serde_json::to_vec(value)?)?;
+        self.store.write(path, data).await
+    }
+
+    /// Segment names of a table, oldest first
+    async fn list_segments(&self, table: &str) -> DatabaseResult<Vec<String>> {
+        let mut segments: Vec<String> = self
+            .store
+            .list(&format!("changes/{}", table))
+            .await?
+            .into_iter()
+            .filter(|name| name.ends_with(SEGMENT_EXTENSION))
+            .collect();
+        segments.sort();
+        Ok(segments)
+    }
+
+    async fn write_segment(&self, table: &str, records: &[Value]) -> DatabaseResult<()> {
+        let name = format!(
+            "{:013}-{}{}",
+            Utc::now().timestamp_millis(),
+            uuid::Uuid::new_v4(),
+            SEGMENT_EXTENSION
+        );
+        self.write_encrypted(&format!("changes/{}/{}", table, name), &json!(records))
+            .await
+    }
+
+    /// Current records of a table, along with the segments they were read from
+    async fn load_table(&self, table: &str) -> DatabaseResult<(Vec<Value>, Vec<String>)> {
+        let segments = self.list_segments(table).await?;
+        let mut records = Vec::new();
+
+        for segment in &segments {
+            // A segment may have been compacted away by another device since it was listed
+            if let Some(Value::Array(batch)) = self
+                .read_encrypted(&format!("changes/{}/{}", table, segment))
+                .await?
+            {
+                records.extend(batch);
+            }
+        }
+
+        Ok((fold_records(records), segments))
+    }
+
+    /// Replace the given segments with a single one holding `records`
+    /// The new segment is written before the old ones are removed, so a crash only leaves duplicates
+    async fn rewrite_table(
+        &self,
+        table: &str,
+        records: &[Value],
+        segments: &[String],
+    ) -> DatabaseResult<()> {
+        if !records.is_empty() {
+            self.write_segment(table, records).await?;
+        }
+        for segment in segments {
+            self.store
+                .delete(&format!("changes/{}/{}", table, segment))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn registered_devices(&self) -> DatabaseResult<Vec<String>> {
+        Ok(self
+            .store
+            .list("devices")
+            .await?
+            .into_iter()
+            .filter_map(|name| name.strip_suffix(SEGMENT_EXTENSION).map(|s| s.to_string()))
+            .collect())
+    }
+
+    async fn read_acks(&self, table: &str, device: &str) -> DatabaseResult<HashSet<String>> {
+        Ok(self
+            .read_encrypted(&acks_path(table, device))
+            .await?
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default())
+    }
+
+    async fn write_acks(
+        &self,
+        table: &str,
+        device: &str,
+        ids: &HashSet<String>,
+    ) -> DatabaseResult<()> {
+        let mut ids: Vec<&String> = ids.iter().collect();
+        ids.sort();
+        self.write_encrypted(&acks_path(table, device), &json!(ids))
+            .await
+    }
+}
+
+/// Derive the store key from the passphrase (Argon2id)
+fn derive_store_key(passphrase: &str, salt: &[u8]) -> DatabaseResult<[u8; 32]> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| EncryptionError::KeyDerivationFailed(e.to_string()))?;
+    Ok(key)
+}
+
+/// Device ids become file names, so anything but a safe character is replaced
+fn file_name(device_id: &str) -> String {
+    device_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn acks_path(table: &str, device: &str) -> String {
+    format!("acks/{}/{}{}", table, device, SEGMENT_EXTENSION)
+}
+
+fn record_id(record: &Value) -> Option<&str> {
+    record.get("id").and_then(|v| v.as_str())
+}
+
+fn record_version(record: &Value) -> u64 {
+    record.get("version").and_then(|v| v.as_u64()).unwrap_or(0)
+}
+
+fn record_time(record: &Value, camel: &str, snake: &str) -> Option<DateTime<Utc>> {
+    let value = record.get(camel).or_else(|| record.get(snake))?.as_str()?;
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok()
+}
+
+fn is_tombstone(record: &Value) -> bool {
+    record_time(record, "deletedAt", "deleted_at").is_some()
+}
+
+/// Keep the newest copy of every record: highest version, then latest update,
+/// then the copy from the latest segment
+fn fold_records(records: Vec<Value>) -> Vec<Value> {
+    let mut latest: HashMap<String, Value> = HashMap::new();
+    let mut order = Vec::new();
+
+    for record in records {
+        let Some(id) = record_id(&record).map(|id| id.to_string()) else {
+            continue;
+        };
+
+        match latest.get(&id) {
+            Some(current) => {
+                let newer = (
+                    record_version(&record),
+                    record_time(&record, "updatedAt", "updated_at"),
+                ) >= (
+                    record_version(current),
+                    record_time(current, "updatedAt", "updated_at"),
+                );
+                if newer {
+                    latest.insert(id, record);
+                }
+            }
+            None => {
+                order.push(id.clone());
+                latest.insert(id, record);
+            }
+        }
+    }
+
+    order
+        .into_iter()
+        .filter_map(|id| latest.remove(&id))
+        .collect()
+}
+
+#[async_trait]
+impl SyncTarget for ChangeLogProvider {
+    async fn connect(&mut self) -> DatabaseResult<()> {
+        self.store.check().await?;
+        self.key = Some(Zeroizing::new(self.open_manifest().await?));
+        Ok(())
+    }
+
+    /// Opening the manifest of a store never set up would create it
+    async fn connect_read_only(&mut self) -> DatabaseResult<bool> {
+        if self.store.read(MANIFEST_PATH).await?.is_none() {
+            return Ok(false);
+        }
+        self.key = Some(Zeroizing::new(self.open_manifest().await?));
+        Ok(true)
+    }
+
+    async fn test_connection(&self) -> DatabaseResult<()> {
+        self.key()?;
+        self.store.check().await
+    }
+
+    async fn push_records(&self, table: &str, records: Vec<Value>) -> DatabaseResult<usize> {
+        if records.is_empty() {
+            return Ok(0);
+        }
+
+        self.write_segment(table, &records).await?;
+
+        if self.list_segments(table).await?.len() > COMPACT_THRESHOLD {
+            let (current, segments) = self.load_table(table).await?;
+            self.rewrite_table(table, &current, &segments).await?;
+        }
+
+        Ok(records.len())
+    }
+
+    async fn pull_records(
+        &self,
+        table: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> DatabaseResult<Vec<Value>> {
+        let (records, _) = self.load_table(table).await?;
+
+        let mut records: Vec<Value> = records
+            .into_iter()
+            .filter(|record| match since {
+                Some(since) => record_time(record, "updatedAt", "updated_at")
+                    .is_none_or(|updated| updated > since),
+                None => true,
+            })
+            .collect();
+        records.sort_by_key(|record| record_time(record, "updatedAt", "updated_at"));
+
+        Ok(records)
+    }
+
+    /// The cursor lists the segments already read; since segments are never rewritten, the
+    /// records of any other segment are new, whichever clock named it. Compaction keeps the
+    /// list short, and only costs a full pull once after it ran.
+    async fn pull_changes(
+        &self,
+        table: &str,
+        cursor: Option<&str>,
+    ) -> DatabaseResult<RecordChanges> {
+        let seen: HashSet<String> = match cursor {
+            Some(cursor) => serde_json::from_str(cursor).unwrap_or_else(|_| {
+                eprintln!("[WARN] Ignoring unrecognized sync cursor {}", cursor);
+                HashSet::new()
+            }),
+            None => HashSet::new(),
+        };
+
+        let segments = self.list_segments(table).await?;
+        let mut records = Vec::new();
+        let mut changed_ids = HashSet::new();
+
+        for segment in &segments {
+            if let Some(Value::Array(batch)) = self
+                .read_encrypted(&format!("changes/{}/{}", table, segment))
+                .await?
+            {
+                if !seen.contains(segment) {
+                    changed_ids.extend(batch.iter().filter_map(|r| record_id(r).map(String::from)));
+                }
+                records.extend(batch);
+            }
+        }
+
+        // Changed records come back in their current state, folded over every segment
+        let records = fold_records(records)
+            .into_iter()
+            .filter(|record| record_id(record).is_some_and(|id| changed_ids.contains(id)))
+            .collect();
+
+        Ok(RecordChanges {
+            records,
+            cursor: serde_json::to_string(&segments)?,
+        })
+    }
+
+    /// Folders and object stores cannot announce new segments, so they are polled by the scheduler
+    async fn watch_changes(&self) -> DatabaseResult<Option<ChangeNotifications>> {
+        Ok(None)
+    }
+
+    async fn get_record_versions(
+        &self,
+        table: &str,
+        ids: Vec<String>,
+    ) -> DatabaseResult<HashMap<String, u64>> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let wanted: HashSet<String> = ids.into_iter().collect();
+        let (records, _) = self.load_table(table).await?;
+
+        Ok(records
+            .iter()
+            .filter_map(|record| {
+                let id = record_id(record)?;
+                wanted
+                    .contains(id)
+                    .then(|| (id.to_string(), record_version(record)))
+            })
+            .collect())
+    }
+
+    async fn register_device(&self, device_id: &str) -> DatabaseResult<()> {
+        let path = format!("devices/{}{}", file_name(device_id), SEGMENT_EXTENSION);
+        let now = Utc::now().to_rfc3339();
+        let registered_at = self
+            .read_encrypted(&path)
+            .await?
+            .and_then(|device| device.get("registeredAt").cloned())
+            .unwrap_or_else(|| json!(now));
+
+        self.write_encrypted(
+            &path,
+            &json!({
+                "deviceId": device_id,
+                "registeredAt": registered_at,
+                "lastSeenAt": now,
+            }),
+        )
+        .await
+    }
+
+    /// Each device only writes its own acknowledgement file
+    async fn acknowledge_tombstones(
+        &self,
+        table: &str,
+        device_id: &str,
+        ids: Vec<String>,
+    ) -> DatabaseResult<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let device = file_name(device_id);
+        let mut acks = self.read_acks(table, &device).await?;
+        let before = acks.len();
+        acks.extend(ids);
+
+        if acks.len() != before {
+            self.write_acks(table, &device, &acks).await?;
+        }
+        Ok(())
+    }
+
+    async fn purge_acknowledged_tombstones(&self, table: &str) -> DatabaseResult<Vec<String>> {
+        let devices = self.registered_devices().await?;
+        if devices.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut device_acks = Vec::new();
+        for device in &devices {
+            device_acks.push(self.read_acks(table, device).await?);
+        }
+
+        let (records, segments) = self.load_table(table).await?;
+        let (purged, kept): (Vec<Value>, Vec<Value>) = records.into_iter().partition(|record| {
+            is_tombstone(record)
+                && record_id(record)
+                    .is_some_and(|id| device_acks.iter().all(|acks| acks.contains(id)))
+        });
+
+        let ids: Vec<String> = purged
+            .iter()
+            .filter_map(|record| record_id(record).map(|id| id.to_string()))
+            .collect();
+        if ids.is_empty() {
+            return Ok(ids);
+        }
+
+        self.rewrite_table(table, &kept, &segments).await?;
+
+        // Acknowledgements of purged records are no longer needed
+        for (device, mut acks) in devices.iter().zip(device_acks) {
+            let before = acks.len();
+            for id in &ids {
+                acks.remove(id);
+            }
+            if acks.len() != before {
+                self.write_acks(table, device, &acks).await?;
+            }
+        }
+
+        Ok(ids)
+    }
+
+    async fn clear_records(&self, table: &str) -> DatabaseResult<usize> {
+        let (records, segments) = self.load_table(table).await?;
+        self.rewrite_table(table, &[], &segments).await?;
+        Ok(records.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store() -> (std::path::PathBuf, Box<dyn ObjectStore>) {
+        let dir = std::env::temp_dir().join(format!("kerminal-changelog-{}", uuid::Uuid::new_v4()));
+        (dir.clone(), Box::new(LocalFolderStore::new(dir)))
+    }
+
+    #[test]
+    fn test_fold_records_keeps_newest_version() {
+        let records = vec![
+            json!({ "id": "a", "version": 2, "name": "new" }),
+            json!({ "id": "b", "version": 1, "name": "only" }),
+            json!({ "id": "a", "version": 1, "name": "stale" }),
+            json!({ "id": "b", "version": 1, "name": "later push" }),
+        ];
+
+        let folded = fold_records(records);
+        assert_eq!(folded.len(), 2);
+        assert_eq!(folded[0]["name"], "new");
+        assert_eq!(folded[1]["name"], "later push");
+    }
+
+    #[tokio::test]
+    async fn test_local_folder_round_trip() {
+        let (dir, store) = temp_store();
+        let mut provider = ChangeLogProvider::new(store, "passphrase".to_string());
+        provider.connect().await.unwrap();
+
+        provider
+            .push_records(
+                "ssh_groups",
+                vec![json!({ "id": "g1", "version": 1, "name": "servers", "updatedAt": "2026-01-01T00:00:00Z" })],
+            )
+            .await
+            .unwrap();
+        provider
+            .push_records(
+                "ssh_groups",
+                vec![json!({ "id": "g1", "version": 2, "name": "prod", "updatedAt": "2026-01-02T00:00:00Z", "deletedAt": "2026-01-02T00:00:00Z" })],
+            )
+            .await
+            .unwrap();
+
+        let pulled = provider.pull_records("ssh_groups", None).await.unwrap();
+        assert_eq!(pulled.len(), 1);
+        assert_eq!(pulled[0]["name"], "prod");
+
+        // Nothing is stored in the clear
+        let segment = std::fs::read_dir(dir.join("changes/ssh_groups"))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        assert!(!String::from_utf8_lossy(&std::fs::read(segment).unwrap()).contains("prod"));
+
+        // A second device needs the same passphrase
+        let mut wrong =
+            ChangeLogProvider::new(Box::new(LocalFolderStore::new(&dir)), "nope".to_string());
+        assert!(wrong.connect().await.is_err());
+
+        // Tombstones are purged once every registered device acknowledged them
+        provider.register_device("device-1").await.unwrap();
+        provider.register_device("device-2").await.unwrap();
+        provider
+            .acknowledge_tombstones("ssh_groups", "device-1", vec!["g1".to_string()])
+            .await
+            .unwrap();
+        assert!(provider
+            .purge_acknowledged_tombstones("ssh_groups")
+            .await
+            .unwrap()
+            .is_empty());
+
+        provider
+            .acknowledge_tombstones("ssh_groups", "device-2", vec!["g1".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(
+            provider
+                .purge_acknowledged_tombstones("ssh_groups")
+                .await
+                .unwrap(),
+            vec!["g1".to_string()]
+        );
+        assert!(provider
+            .pull_records("ssh_groups", None)
+            .await
+            .unwrap()
+            .is_empty());
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_pull_changes_returns_records_of_unseen_segments() {
+        let (dir, store) = temp_store();
+        let mut provider = ChangeLogProvider::new(store, "passphrase".to_string());
+        provider.connect().await.unwrap();
+
+        provider
+            .push_records(
+                "ssh_groups",
+                vec![
+                    json!({ "id": "g1", "version": 1, "name": "servers" }),
+                    json!({ "id": "g2", "version": 1, "name": "desktops" }),
+                ],
+            )
+            .await
+            .unwrap();
+
+        let first = provider.pull_changes("ssh_groups", None).await.unwrap();
+        assert_eq!(first.records.len(), 2);
+
+        let unchanged = provider
+            .pull_changes("ssh_groups", Some(&first.cursor))
+            .await
+            .unwrap();
+        assert!(unchanged.records.is_empty());
+
+        // A device with its clock far behind still names its segment in the past
+        provider
+            .write_encrypted(
+                "changes/ssh_groups/0000000000001-skewed.enc",
+                &json!([{ "id": "g2", "version": 2, "name": "laptops" }]),
+            )
+            .await
+            .unwrap();
+
+        let changed = provider
+            .pull_changes("ssh_groups", Some(&unchanged.cursor))
+            .await
+            .unwrap();
+        assert_eq!(changed.records.len(), 1);
+        assert_eq!(changed.records[0]["name"], "laptops");
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,434 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use regex::Regex;
+use reqwest::{Client, Method, StatusCode};
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+
+use crate::database::error::{DatabaseError, DatabaseResult};
+
+use super::store::ObjectStore;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Object store in an S3-compatible bucket (AWS S3, MinIO, Cloudflare R2, Backblaze B2, ...)
+/// Requests are signed with AWS Signature Version 4
+pub struct S3Store {
+    client: Client,
+    endpoint: reqwest::Url,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    /// Key prefix under which every object is stored
+    prefix: String,
+    /// Address the bucket in the path (MinIO) rather than in the host name (AWS)
+    path_style: bool,
+}
+
+impl S3Store {
+    pub fn new(
+        endpoint: &str,
+        bucket: String,
+        region: String,
+        access_key: String,
+        secret_key: String,
+        prefix: &str,
+        path_style: bool,
+    ) -> DatabaseResult<Self> {
+        let endpoint = reqwest::Url::parse(endpoint)
+            .map_err(|e| DatabaseError::ConfigError(format!("Invalid S3 endpoint: {}", e)))?;
+        if endpoint.host_str().is_none() {
+            return Err(DatabaseError::ConfigError(
+                "Invalid S3 endpoint: missing host".to_string(),
+            ));
+        }
+
+        let prefix = prefix.trim_matches('/');
+        Ok(Self {
+            client: Client::new(),
+            endpoint,
+            bucket,
+            region,
+            access_key,
+            secret_key,
+            prefix: if prefix.is_empty() {
+                String::new()
+            } else {
+                format!("{}/", prefix)
+            },
+            path_style,
+        })
+    }
+
+    fn host(&self) -> String {
+        let host = self.endpoint.host_str().unwrap_or_default();
+        let host = if self.path_style {
+            host.to_string()
+        } else {
+            format!("{}.{}", self.bucket, host)
+        };
+
+        match self.endpoint.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host,
+        }
+    }
+
+    /// Send a signed request for an object key, or for the bucket when `key` is empty
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+    ) -> DatabaseResult<reqwest::Response> {
+        let mut path = String::new();
+        if self.path_style {
+            path.push('/');
+            path.push_str(&uri_encode(&self.bucket, true));
+        }
+        path.push('/');
+        path.push_str(&uri_encode(key, false));
+
+        let mut query: Vec<(String, String)> = query
+            .iter()
+            .map(|(k, v)| (uri_encode(k, true), uri_encode(v, true)))
+            .collect();
+        query.sort();
+        let query = query
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let host = self.host();
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = hex(&Sha256::digest(&body));
+
+        let signature = sign_v4(&SigningRequest {
+            method: method.as_str(),
+            path: &path,
+            query: &query,
+            host: &host,
+            amz_date: &amz_date,
+            payload_hash: &payload_hash,
+            region: &self.region,
+            secret_key: &self.secret_key,
+        });
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key,
+            credential_scope(&amz_date, &self.region),
+            SIGNED_HEADERS,
+            signature
+        );
+
+        let mut url = format!("{}://{}{}", self.endpoint.scheme(), host, path);
+        if !query.is_empty() {
+            url.push('?');
+            url.push_str(&query);
+        }
+
+        let response = self
+            .client
+            .request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("Authorization", authorization)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| DatabaseError::ConnectionFailed(e.to_string()))?;
+
+        match response.status() {
+            StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED => Err(
+                DatabaseError::AuthenticationFailed(format!("S3 returned {}", response.status())),
+            ),
+            _ => Ok(response),
+        }
+    }
+
+    fn key(&self, path: &str) -> String {
+        format!("{}{}", self.prefix, path)
+    }
+}
+
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+struct SigningRequest<'a> {
+    method: &'a str,
+    path: &'a str,
+    query: &'a str,
+    host: &'a str,
+    amz_date: &'a str,
+    payload_hash: &'a str,
+    region: &'a str,
+    secret_key: &'a str,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn credential_scope(amz_date: &str, region: &str) -> String {
+    format!("{}/{}/s3/aws4_request", &amz_date[..8], region)
+}
+
+/// Derive the SigV4 signing key for a day, region and service
+fn signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac_sha256(format!("AWS4{}", secret_key).as_bytes(), date.as_bytes());
+    let key = hmac_sha256(&key, region.as_bytes());
+    let key = hmac_sha256(&key, service.as_bytes());
+    hmac_sha256(&key, b"aws4_request")
+}
+
+/// Signature of a request, headers limited to `SIGNED_HEADERS`
+fn sign_v4(request: &SigningRequest) -> String {
+    let canonical_request = format!(
+        "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+        request.method,
+        request.path,
+        request.query,
+        request.host,
+        request.payload_hash,
+        request.amz_date,
+        SIGNED_HEADERS,
+        request.payload_hash
+    );
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        request.amz_date,
+        credential_scope(request.amz_date, request.region),
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let key = signing_key(
+        request.secret_key,
+        &request.amz_date[..8],
+        request.region,
+        "s3",
+    );
+    hex(&hmac_sha256(&key, string_to_sign.as_bytes()))
+}
+
+/// Percent-encode everything but unreserved characters, keeping `/` unless `encode_slash`
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            b'/' if !encode_slash => "/".to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Text of every occurrence of an XML element
+fn xml_values(body: &str, tag: &str) -> Vec<String> {
+    static ELEMENT: OnceLock<Regex> = OnceLock::new();
+    ELEMENT
+        .get_or_init(|| Regex::new(r"<([A-Za-z]+)>([^<]*)</([A-Za-z]+)>").unwrap())
+        .captures_iter(body)
+        .filter(|caps| &caps[1] == tag && &caps[3] == tag)
+        .filter_map(|caps| caps.get(2))
+        .map(|m| {
+            m.as_str()
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&")
+        })
+        .collect()
+}
+
+async fn error_body(response: reqwest::Response) -> String {
+    static CODE: OnceLock<Regex> = OnceLock::new();
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    let code = CODE
+        .get_or_init(|| Regex::new(r"<Code>([^<]*)</Code>").unwrap())
+        .captures(&body)
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str().to_string());
+
+    match code {
+        Some(code) => format!("{} ({})", status, code),
+        None => status.to_string(),
+    }
+}
+
+#[async_trait]
+impl ObjectStore for S3Store {
+    async fn check(&self) -> DatabaseResult<()> {
+        let response = self
+            .send(
+                Method::GET,
+                "",
+                &[
+                    ("list-type", "2"),
+                    ("max-keys", "1"),
+                    ("prefix", &self.prefix),
+                ],
+                Vec::new(),
+            )
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(DatabaseError::ConnectionFailed(format!(
+                "S3 bucket {} is not reachable: {}",
+                self.bucket,
+                error_body(response).await
+            )));
+        }
+        Ok(())
+    }
+
+    async fn read(&self, path: &str) -> DatabaseResult<Option<Vec<u8>>> {
+        let response = self
+            .send(Method::GET, &self.key(path), &[], Vec::new())
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(DatabaseError::QueryFailed(format!(
+                "Failed to read {}: {}",
+                path,
+                error_body(response).await
+            )));
+        }
+
+        let data = response
+            .bytes()
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+        Ok(Some(data.to_vec()))
+    }
+
+    async fn write(&self, path: &str, data: Vec<u8>) -> DatabaseResult<()> {
+        let response = self.send(Method::PUT, &self.key(path), &[], data).await?;
+        if !response.status().is_success() {
+            return Err(DatabaseError::QueryFailed(format!(
+                "Failed to write {}: {}",
+                path,
+                error_body(response).await
+            )));
+        }
+        Ok(())
+    }
+
+    async fn list(&self, dir: &str) -> DatabaseResult<Vec<String>> {
+        let dir_prefix = self.key(&format!("{}/", dir.trim_matches('/')));
+        let mut names = Vec::new();
+        let mut continuation: Option<String> = None;
+
+        loop {
+            let mut query = vec![
+                ("list-type", "2"),
+                ("delimiter", "/"),
+                ("prefix", dir_prefix.as_str()),
+            ];
+            if let Some(token) = &continuation {
+                query.push(("continuation-token", token.as_str()));
+            }
+
+            let response = self.send(Method::GET, "", &query, Vec::new()).await?;
+            if !response.status().is_success() {
+                return Err(DatabaseError::QueryFailed(format!(
+                    "Failed to list {}: {}",
+                    dir,
+                    error_body(response).await
+                )));
+            }
+            let body = response
+                .text()
+                .await
+                .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+            names.extend(
+                xml_values(&body, "Key")
+                    .into_iter()
+                    .filter_map(|key| key.strip_prefix(&dir_prefix).map(|n| n.to_string()))
+                    .filter(|name| !name.is_empty()),
+            );
+
+            continuation = xml_values(&body, "NextContinuationToken")
+                .into_iter()
+                .next();
+            if continuation.is_none() {
+                break;
+            }
+        }
+
+        Ok(names)
+    }
+
+    async fn delete(&self, path: &str) -> DatabaseResult<()> {
+        let response = self
+            .send(Method::DELETE, &self.key(path), &[], Vec::new())
+            .await?;
+        if !response.status().is_success() && response.status() != StatusCode::NOT_FOUND {
+            return Err(DatabaseError::QueryFailed(format!(
+                "Failed to delete {}: {}",
+                path,
+                error_body(response).await
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signing_key() {
+        // Example from the AWS Signature Version 4 documentation
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+        assert_eq!(
+            hex(&key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[test]
+    fn test_uri_encode() {
+        assert_eq!(
+            uri_encode("changes/ssh keys/a~b.enc", false),
+            "changes/ssh%20keys/a~b.enc"
+        );
+        assert_eq!(
+            uri_encode("kerminal/changes/", true),
+            "kerminal%2Fchanges%2F"
+        );
+    }
+
+    #[test]
+    fn test_xml_values() {
+        let body = "<ListBucketResult><Contents><Key>a&amp;b.enc</Key></Contents>\
+            <Contents><Key>c.enc</Key></Contents>\
+            <NextContinuationToken>t1</NextContinuationToken></ListBucketResult>";
+
+        assert_eq!(xml_values(body, "Key"), vec!["a&b.enc", "c.enc"]);
+        assert_eq!(xml_values(body, "NextContinuationToken"), vec!["t1"]);
+        assert!(xml_values(body, "Contents").is_empty());
+    }
+}
@@ -0,0 +1,28 @@
+use async_trait::async_trait;
+
+use crate::database::error::DatabaseResult;
+
+/// Minimal object storage used by change log sync targets
+/// Paths are relative and use `/` separators; listing is not recursive
+#[async_trait]
+pub trait ObjectStore: Send + Sync {
+    /// Make sure the store is reachable and its root exists
+    async fn check(&self) -> DatabaseResult<()>;
+
+    /// Read an object, `None` if it does not exist
+    async fn read(&self, path: &str) -> DatabaseResult<Option<Vec<u8>>>;
+
+    /// Write an object, replacing any previous content
+    async fn write(&self, path: &str, data: Vec<u8>) -> DatabaseResult<()>;
+
+    /// Names of the objects directly inside a directory
+    async fn list(&self, dir: &str) -> DatabaseResult<Vec<String>>;
+
+    /// Delete an object, succeeding if it is already gone
+    async fn delete(&self, path: &str) -> DatabaseResult<()>;
+}
+
+/// Split a path into its directory and file name
+pub(super) fn split_path(path: &str) -> (&str, &str) {
+    path.rsplit_once('/').unwrap_or(("", path))
+}
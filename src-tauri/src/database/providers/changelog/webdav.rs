@@ -0,0 +1,252 @@
+use async_trait::async_trait;
+use regex::Regex;
+use reqwest::{Client, Method, RequestBuilder, StatusCode};
+use std::collections::HashSet;
+use std::sync::OnceLock;
+use tokio::sync::Mutex;
+
+use crate::database::error::{DatabaseError, DatabaseResult};
+
+use super::store::{split_path, ObjectStore};
+
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:"><d:prop><d:resourcetype/></d:prop></d:propfind>"#;
+
+/// Object store on a WebDAV server (Nextcloud, ownCloud, Apache mod_dav, rclone serve webdav, ...)
+pub struct WebDavStore {
+    client: Client,
+    root_url: String,
+    folder: String,
+    username: String,
+    password: String,
+    /// Collections known to exist, to avoid a MKCOL round trip on every write
+    collections: Mutex<HashSet<String>>,
+}
+
+impl WebDavStore {
+    /// `root_url` must exist on the server; `folder` is created under it and holds every object
+    pub fn new(root_url: &str, folder: &str, username: String, password: String) -> Self {
+        Self {
+            client: Client::new(),
+            root_url: root_url.trim_end_matches('/').to_string(),
+            folder: folder.trim_matches('/').to_string(),
+            username,
+            password,
+            collections: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Path relative to the root URL
+    fn root_path(&self, path: &str) -> String {
+        [self.folder.as_str(), path]
+            .iter()
+            .filter(|part| !part.is_empty())
+            .copied()
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    fn request(&self, method: Method, root_path: &str) -> RequestBuilder {
+        let request = self.client.request(
+            method,
+            format!("{}/{}", self.root_url, root_path.trim_start_matches('/')),
+        );
+        if self.username.is_empty() {
+            request
+        } else {
+            request.basic_auth(&self.username, Some(&self.password))
+        }
+    }
+
+    async fn send(&self, request: RequestBuilder) -> DatabaseResult<reqwest::Response> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| DatabaseError::ConnectionFailed(e.to_string()))?;
+
+        match response.status() {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                Err(DatabaseError::AuthenticationFailed(format!(
+                    "WebDAV server returned {}",
+                    response.status()
+                )))
+            }
+            _ => Ok(response),
+        }
+    }
+
+    async fn propfind(&self, root_path: &str, depth: &str) -> DatabaseResult<reqwest::Response> {
+        let method = Method::from_bytes(b"PROPFIND").expect("valid method");
+        self.send(
+            self.request(method, root_path)
+                .header("Depth", depth)
+                .header("Content-Type", "application/xml")
+                .body(PROPFIND_BODY),
+        )
+        .await
+    }
+
+    /// Create a collection and its parents, relative to the root URL
+    async fn ensure_collection(&self, root_path: &str) -> DatabaseResult<()> {
+        if root_path.is_empty() || self.collections.lock().await.contains(root_path) {
+            return Ok(());
+        }
+
+        let mut current = String::new();
+        for part in root_path.split('/').filter(|part| !part.is_empty()) {
+            if !current.is_empty() {
+                current.push('/');
+            }
+            current.push_str(part);
+
+            let method = Method::from_bytes(b"MKCOL").expect("valid method");
+            let response = self
+                .send(self.request(method, &format!("{}/", current)))
+                .await?;
+
+            // 405 means the collection already exists
+            if !response.status().is_success()
+                && response.status() != StatusCode::METHOD_NOT_ALLOWED
+            {
+                return Err(DatabaseError::QueryFailed(format!(
+                    "Failed to create WebDAV collection {}: {}",
+                    current,
+                    response.status()
+                )));
+            }
+        }
+
+        self.collections.lock().await.insert(root_path.to_string());
+        Ok(())
+    }
+}
+
+/// Names of the resources listed in a PROPFIND multistatus response
+fn parse_propfind_names(body: &str) -> Vec<String> {
+    static HREF: OnceLock<Regex> = OnceLock::new();
+    let href = HREF.get_or_init(|| {
+        Regex::new(r"(?is)<(?:[a-z0-9]+:)?href>\s*([^<]+?)\s*</(?:[a-z0-9]+:)?href>").unwrap()
+    });
+
+    href.captures_iter(body)
+        .filter_map(|caps| {
+            let href = caps.get(1)?.as_str();
+            // Collections end with a slash, the listed folder itself included
+            if href.ends_with('/') {
+                return None;
+            }
+            let name = href.rsplit('/').next()?;
+            Some(name.replace("%20", " "))
+        })
+        .collect()
+}
+
+#[async_trait]
+impl ObjectStore for WebDavStore {
+    async fn check(&self) -> DatabaseResult<()> {
+        self.ensure_collection(&self.folder).await?;
+
+        let response = self.propfind(&format!("{}/", self.folder), "0").await?;
+        if !response.status().is_success() {
+            return Err(DatabaseError::ConnectionFailed(format!(
+                "WebDAV server returned {}",
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+
+    async fn read(&self, path: &str) -> DatabaseResult<Option<Vec<u8>>> {
+        let response = self
+            .send(self.request(Method::GET, &self.root_path(path)))
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(DatabaseError::QueryFailed(format!(
+                "Failed to read {}: {}",
+                path,
+                response.status()
+            )));
+        }
+
+        let data = response
+            .bytes()
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+        Ok(Some(data.to_vec()))
+    }
+
+    async fn write(&self, path: &str, data: Vec<u8>) -> DatabaseResult<()> {
+        self.ensure_collection(&self.root_path(split_path(path).0))
+            .await?;
+
+        let response = self
+            .send(self.request(Method::PUT, &self.root_path(path)).body(data))
+            .await?;
+        if !response.status().is_success() {
+            return Err(DatabaseError::QueryFailed(format!(
+                "Failed to write {}: {}",
+                path,
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+
+    async fn list(&self, dir: &str) -> DatabaseResult<Vec<String>> {
+        let response = self
+            .propfind(&format!("{}/", self.root_path(dir)), "1")
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+        if !response.status().is_success() {
+            return Err(DatabaseError::QueryFailed(format!(
+                "Failed to list {}: {}",
+                dir,
+                response.status()
+            )));
+        }
+
+        let body = response
+            .text()
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+        Ok(parse_propfind_names(&body))
+    }
+
+    async fn delete(&self, path: &str) -> DatabaseResult<()> {
+        let response = self
+            .send(self.request(Method::DELETE, &self.root_path(path)))
+            .await?;
+        if !response.status().is_success() && response.status() != StatusCode::NOT_FOUND {
+            return Err(DatabaseError::QueryFailed(format!(
+                "Failed to delete {}: {}",
+                path,
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_propfind_names() {
+        let body = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:">
+  <d:response><d:href>/dav/kerminal/changes/ssh_groups/</d:href></d:response>
+  <d:response><d:href>/dav/kerminal/changes/ssh_groups/0001-a.enc</d:href></d:response>
+  <D:response><D:href>
+    /dav/kerminal/changes/ssh_groups/0002-b.enc
+  </D:href></D:response>
+</d:multistatus>"#;
+
+        assert_eq!(parse_propfind_names(body), vec!["0001-a.enc", "0002-b.enc"]);
+    }
+}
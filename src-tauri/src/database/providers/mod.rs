@@ -1,9 +1,14 @@
+pub mod changelog;
 pub mod mongodb;
 pub mod mysql;
 pub mod postgres;
 pub mod sqlite;
+pub mod sqlite_target;
+pub mod sync_schema;
 
+pub use changelog::ChangeLogProvider;
 pub use mongodb::MongoDBProvider;
 pub use mysql::MySQLProvider;
 pub use postgres::PostgreSQLProvider;
 pub use sqlite::SQLiteProvider;
+pub use sqlite_target::SQLiteTargetProvider;
@@ -11,7 +11,7 @@ use tokio::sync::RwLock;
 
 use crate::database::{
     error::{DatabaseError, DatabaseResult},
-    traits_sync::SyncTarget,
+    traits_sync::{ChangeNotifications, RecordChanges, SyncTarget, SEALED_RECORDS_TABLE},
 };
 
 pub struct MongoDBProvider {
@@ -54,6 +54,14 @@ impl MongoDBProvider {
             "ssh_tunnels",
             "saved_commands",
             "saved_command_groups",
+            "terminal_profiles",
+            "session_recordings",
+            "known_hosts",
+            "sync_devices",
+            "sync_tombstone_acks",
+            "sync_sealed_records",
+            "sync_keys",
+            "sync_sequence",
         ];
 
         for collection_name in collections {
@@ -75,6 +83,12 @@ impl MongoDBProvider {
 #[async_trait]
 impl SyncTarget for MongoDBProvider {
     async fn connect(&mut self) -> DatabaseResult<()> {
+        self.connect_read_only().await?;
+        self.create_sync_collections().await?;
+        Ok(())
+    }
+
+    async fn connect_read_only(&mut self) -> DatabaseResult<bool> {
         let mut client_options = ClientOptions::parse(&self.connection_string)
             .await
             .map_err(|e| DatabaseError::ConnectionFailed(e.to_string()))?;
@@ -85,12 +99,16 @@ impl SyncTarget for MongoDBProvider {
             .map_err(|e| DatabaseError::ConnectionFailed(e.to_string()))?;
 
         let database = client.database(&self.database_name);
+        let set_up = database
+            .list_collection_names(None)
+            .await
+            .map_err(|e| DatabaseError::ConnectionFailed(e.to_string()))?
+            .iter()
+            .any(|name| name == "sync_sequence");
 
         self.client = Some(Arc::new(RwLock::new(client)));
         self.database = Some(Arc::new(RwLock::new(database)));
-
-        self.create_sync_collections().await?;
-        Ok(())
+        Ok(set_up)
     }
 
     async fn test_connection(&self) -> DatabaseResult<()> {
@@ -112,7 +130,35 @@ impl SyncTarget for MongoDBProvider {
         table: &str,
         since: Option<DateTime<Utc>>,
     ) -> DatabaseResult<Vec<Value>> {
-        sync::pull_records(self, table, since).await
+        sync::pull_records(self, table, since, None).await
+    }
+
+    async fn pull_changes(
+        &self,
+        table: &str,
+        cursor: Option<&str>,
+    ) -> DatabaseResult<RecordChanges> {
+        sync::pull_changes(self, table, cursor, None).await
+    }
+
+    async fn pull_sealed_records(
+        &self,
+        table: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> DatabaseResult<Vec<Value>> {
+        sync::pull_records(self, SEALED_RECORDS_TABLE, since, Some(table)).await
+    }
+
+    async fn pull_sealed_changes(
+        &self,
+        table: &str,
+        cursor: Option<&str>,
+    ) -> DatabaseResult<RecordChanges> {
+        sync::pull_changes(self, SEALED_RECORDS_TABLE, cursor, Some(table)).await
+    }
+
+    async fn watch_changes(&self) -> DatabaseResult<Option<ChangeNotifications>> {
+        sync::watch_changes(self).await
     }
 
     async fn get_record_versions(
@@ -122,4 +168,25 @@ impl SyncTarget for MongoDBProvider {
     ) -> DatabaseResult<HashMap<String, u64>> {
         sync::get_record_versions(self, table, ids).await
     }
+
+    async fn register_device(&self, device_id: &str) -> DatabaseResult<()> {
+        sync::register_device(self, device_id).await
+    }
+
+    async fn acknowledge_tombstones(
+        &self,
+        table: &str,
+        device_id: &str,
+        ids: Vec<String>,
+    ) -> DatabaseResult<()> {
+        sync::acknowledge_tombstones(self, table, device_id, ids).await
+    }
+
+    async fn purge_acknowledged_tombstones(&self, table: &str) -> DatabaseResult<Vec<String>> {
+        sync::purge_acknowledged_tombstones(self, table).await
+    }
+
+    async fn clear_records(&self, table: &str) -> DatabaseResult<usize> {
+        sync::clear_records(self, table).await
+    }
 }
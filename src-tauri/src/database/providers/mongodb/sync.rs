@@ -3,10 +3,87 @@ use chrono::{DateTime, Utc};
 use serde_json::Value;
 use std::collections::HashMap;
 
-use crate::database::error::{DatabaseError, DatabaseResult};
+use crate::database::{
+    error::{DatabaseError, DatabaseResult},
+    providers::sync_schema::{parse_cursor, CHANGE_SEQUENCE, CHANGE_SEQ_COLUMN},
+    traits_sync::{
+        ChangeNotification, ChangeNotifications, RecordChanges, SEALED_RECORDS_TABLE,
+        TOMBSTONE_TABLES,
+    },
+};
 
 use super::MongoDBProvider;
 
+/// Collection holding the change sequence counter
+const SEQUENCE_COLLECTION: &str = "sync_sequence";
+
+/// Sequence ranges of pushes that never released them are dropped after this long
+const STALE_RESERVATION_MS: i64 = 10 * 60 * 1000;
+
+/// Watch pushes to the synced collections through a change stream
+///
+/// Change streams need a replica set or sharded cluster; on a standalone server this
+/// returns `None` so the scheduler keeps polling.
+pub async fn watch_changes(
+    provider: &MongoDBProvider,
+) -> DatabaseResult<Option<ChangeNotifications>> {
+    use futures::StreamExt;
+
+    let collections: Vec<&str> = TOMBSTONE_TABLES
+        .iter()
+        .copied()
+        .chain([SEALED_RECORDS_TABLE])
+        .collect();
+    let pipeline = [doc! {
+        "$match": {
+            "operationType": { "$in": ["insert", "update", "replace"] },
+            "ns.coll": { "$in": collections },
+        }
+    }];
+
+    let stream = {
+        let db_arc = provider.get_database()?;
+        let db = db_arc.read().await;
+        db.watch(pipeline, None).await
+    };
+    let mut stream = match stream {
+        Ok(stream) => stream,
+        Err(e) => {
+            eprintln!(
+                "[WARN] MongoDB change streams unavailable, falling back to polling: {}",
+                e
+            );
+            return Ok(None);
+        }
+    };
+
+    let (sender, receiver) = tokio::sync::mpsc::channel(64);
+    tokio::spawn(async move {
+        while let Some(event) = stream.next().await {
+            match event {
+                Ok(event) => {
+                    let Some(table) = event.ns.and_then(|ns| ns.coll) else {
+                        continue;
+                    };
+                    let notification = ChangeNotification {
+                        table,
+                        device_id: None,
+                    };
+                    if sender.send(notification).await.is_err() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    eprintln!("[WARN] MongoDB change stream stopped: {}", e);
+                    break;
+                }
+            }
+        }
+    });
+
+    Ok(Some(receiver))
+}
+
 /// Convert camelCase to snake_case for MongoDB field names
 fn to_snake_case(s: &str) -> String {
     let mut result = String::new();
@@ -24,6 +101,7 @@ fn to_snake_case(s: &str) -> String {
 }
 
 /// Push records to MongoDB database using upsert (replaceOne with upsert=true)
+/// Every document gets the next change sequence number, from a range reserved for this push
 pub async fn push_records(
     provider: &MongoDBProvider,
     collection_name: &str,
@@ -34,27 +112,147 @@ pub async fn push_records(
     }
 
     let collection = provider.get_collection(collection_name).await?;
-    let mut count = 0;
+    let (token, first_seq) = reserve_sequence(provider, records.len() as i64).await?;
+
+    let result = async {
+        let mut count = 0;
 
-    for record in records {
-        let doc = json_to_bson_document(&record)?;
+        for (offset, record) in records.iter().enumerate() {
+            let mut doc = json_to_bson_document(record)?;
+            doc.insert(CHANGE_SEQ_COLUMN.name, first_seq + offset as i64);
+
+            if let Some(Bson::String(id)) = doc.get("id") {
+                let filter = doc! { "id": id };
+                let options = mongodb::options::ReplaceOptions::builder()
+                    .upsert(true)
+                    .build();
+
+                collection
+                    .replace_one(filter, doc, options)
+                    .await
+                    .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+                count += 1;
+            }
+        }
 
-        if let Some(Bson::String(id)) = doc.get("id") {
-            let filter = doc! { "id": id };
-            let options = mongodb::options::ReplaceOptions::builder()
-                .upsert(true)
-                .build();
+        Ok(count)
+    }
+    .await;
 
-            collection
-                .replace_one(filter, doc, options)
-                .await
-                .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+    release_sequence(provider, &token).await?;
+    result
+}
 
-            count += 1;
+/// `$filter` expression keeping the sequence reservations that are not stale
+fn live_reservations() -> Document {
+    doc! {
+        "$filter": {
+            "input": { "$ifNull": ["$pending", []] },
+            "as": "reservation",
+            "cond": { "$gt": ["$$reservation.at", { "$subtract": ["$$NOW", STALE_RESERVATION_MS] }] },
         }
     }
+}
+
+fn bson_i64(value: Option<&Bson>) -> Option<i64> {
+    match value? {
+        Bson::Int32(v) => Some(*v as i64),
+        Bson::Int64(v) => Some(*v),
+        Bson::Double(v) => Some(*v as i64),
+        _ => None,
+    }
+}
+
+/// Reserve `count` change sequence numbers, returning the reservation token and the first number
+///
+/// Unlike the SQL targets, a push cannot hold the counter until its writes are visible.
+/// Instead the reserved range is listed on the counter document until the push releases it,
+/// and pulls never move their cursor past the start of a range still being written.
+async fn reserve_sequence(provider: &MongoDBProvider, count: i64) -> DatabaseResult<(String, i64)> {
+    let collection = provider.get_collection(SEQUENCE_COLLECTION).await?;
+    let token = uuid::Uuid::new_v4().to_string();
+
+    let pipeline = vec![
+        doc! { "$set": { "value": { "$add": [{ "$ifNull": ["$value", 0_i64] }, count] } } },
+        doc! {
+            "$set": {
+                "pending": {
+                    "$concatArrays": [
+                        live_reservations(),
+                        [{
+                            "token": token.as_str(),
+                            "first": { "$subtract": ["$value", count - 1] },
+                            "at": "$$NOW",
+                        }],
+                    ]
+                }
+            }
+        },
+    ];
+    let options = mongodb::options::FindOneAndUpdateOptions::builder()
+        .upsert(true)
+        .return_document(mongodb::options::ReturnDocument::After)
+        .build();
+
+    let sequence = collection
+        .find_one_and_update(doc! { "_id": CHANGE_SEQUENCE }, pipeline, options)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?
+        .ok_or_else(|| DatabaseError::SyncError("Change sequence is missing".to_string()))?;
+
+    let last = bson_i64(sequence.get("value"))
+        .ok_or_else(|| DatabaseError::SyncError("Invalid change sequence".to_string()))?;
+    Ok((token, last - count + 1))
+}
+
+/// Release the sequence range of a finished push
+async fn release_sequence(provider: &MongoDBProvider, token: &str) -> DatabaseResult<()> {
+    let collection = provider.get_collection(SEQUENCE_COLLECTION).await?;
+
+    collection
+        .update_one(
+            doc! { "_id": CHANGE_SEQUENCE },
+            doc! { "$pull": { "pending": { "token": token } } },
+            None,
+        )
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Current counter value, and the first number of the oldest range still being written
+async fn sequence_state(provider: &MongoDBProvider) -> DatabaseResult<(i64, Option<i64>)> {
+    let collection = provider.get_collection(SEQUENCE_COLLECTION).await?;
+    let options = mongodb::options::FindOneAndUpdateOptions::builder()
+        .return_document(mongodb::options::ReturnDocument::After)
+        .build();
 
-    Ok(count)
+    // Stale reservations are dropped here too, so a crashed push cannot hold pulls back
+    let Some(sequence) = collection
+        .find_one_and_update(
+            doc! { "_id": CHANGE_SEQUENCE },
+            vec![doc! { "$set": { "pending": live_reservations() } }],
+            options,
+        )
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?
+    else {
+        return Ok((0, None));
+    };
+
+    let pending = sequence
+        .get_array("pending")
+        .map(|pending| {
+            pending
+                .iter()
+                .filter_map(|reservation| bson_i64(reservation.as_document()?.get("first")))
+                .min()
+        })
+        .unwrap_or(None);
+
+    Ok((bson_i64(sequence.get("value")).unwrap_or(0), pending))
 }
 
 /// Pull records from MongoDB database modified since timestamp
@@ -62,14 +260,18 @@ pub async fn pull_records(
     provider: &MongoDBProvider,
     collection_name: &str,
     since: Option<DateTime<Utc>>,
+    table_name: Option<&str>,
 ) -> DatabaseResult<Vec<Value>> {
     let collection = provider.get_collection(collection_name).await?;
 
-    let filter = if let Some(since_time) = since {
+    let mut filter = if let Some(since_time) = since {
         doc! { "updated_at": { "$gt": since_time.to_rfc3339() } }
     } else {
         doc! {}
     };
+    if let Some(name) = table_name {
+        filter.insert("table_name", name);
+    }
 
     let options = mongodb::options::FindOptions::builder()
         .sort(doc! { "updated_at": 1 })
@@ -97,6 +299,62 @@ pub async fn pull_records(
     Ok(records)
 }
 
+/// Pull the documents written after a change sequence number
+/// Documents written before the sequence existed have none, and only come with a full pull
+pub async fn pull_changes(
+    provider: &MongoDBProvider,
+    collection_name: &str,
+    cursor: Option<&str>,
+    table_name: Option<&str>,
+) -> DatabaseResult<RecordChanges> {
+    let (current, pending) = sequence_state(provider).await?;
+
+    // A cursor past the counter belongs to a sync database that has since been recreated
+    let after = parse_cursor(cursor).filter(|seq| *seq <= current);
+
+    let collection = provider.get_collection(collection_name).await?;
+    let mut filter = match after {
+        Some(seq) => doc! { CHANGE_SEQ_COLUMN.name: { "$gt": seq } },
+        None => doc! {},
+    };
+    if let Some(name) = table_name {
+        filter.insert("table_name", name);
+    }
+    let options = mongodb::options::FindOptions::builder()
+        .sort(doc! { CHANGE_SEQ_COLUMN.name: 1 })
+        .build();
+
+    let mut cursor = collection
+        .find(filter, options)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    let mut last_seq = after.unwrap_or(0);
+    let mut records = Vec::new();
+    while cursor
+        .advance()
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?
+    {
+        let doc = cursor
+            .deserialize_current()
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        last_seq = last_seq.max(bson_i64(doc.get(CHANGE_SEQ_COLUMN.name)).unwrap_or(0));
+        records.push(bson_document_to_json(&doc)?);
+    }
+
+    // Documents of a push still writing may land below what was read; pull them again next time
+    if let Some(first) = pending {
+        last_seq = last_seq.min(first - 1).max(after.unwrap_or(0));
+    }
+
+    Ok(RecordChanges {
+        records,
+        cursor: last_seq.to_string(),
+    })
+}
+
 /// Get record versions for conflict detection
 pub async fn get_record_versions(
     provider: &MongoDBProvider,
@@ -141,6 +399,208 @@ pub async fn get_record_versions(
     Ok(versions)
 }
 
+/// Register a device, or refresh its last seen time
+pub async fn register_device(provider: &MongoDBProvider, device_id: &str) -> DatabaseResult<()> {
+    let collection = provider.get_collection("sync_devices").await?;
+    let now = Utc::now().to_rfc3339();
+
+    let options = mongodb::options::UpdateOptions::builder()
+        .upsert(true)
+        .build();
+
+    collection
+        .update_one(
+            doc! { "device_id": device_id },
+            doc! {
+                "$set": { "last_seen_at": now.as_str() },
+                "$setOnInsert": { "registered_at": now.as_str() },
+            },
+            options,
+        )
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Record that a device has seen the given tombstones
+pub async fn acknowledge_tombstones(
+    provider: &MongoDBProvider,
+    collection_name: &str,
+    device_id: &str,
+    ids: Vec<String>,
+) -> DatabaseResult<()> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    let collection = provider.get_collection("sync_tombstone_acks").await?;
+    let acknowledged: std::collections::HashSet<String> = collect_strings(
+        collection.clone(),
+        doc! {
+            "table_name": collection_name,
+            "device_id": device_id,
+            "record_id": { "$in": ids.clone() },
+        },
+        "record_id",
+    )
+    .await?
+    .into_iter()
+    .collect();
+
+    let now = Utc::now().to_rfc3339();
+    let docs: Vec<Document> = ids
+        .iter()
+        .filter(|id| !acknowledged.contains(*id))
+        .map(|id| {
+            doc! {
+                "table_name": collection_name,
+                "record_id": id.as_str(),
+                "device_id": device_id,
+                "acknowledged_at": now.as_str(),
+            }
+        })
+        .collect();
+    if docs.is_empty() {
+        return Ok(());
+    }
+
+    collection
+        .insert_many(docs, None)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Delete tombstones that every registered device has acknowledged
+pub async fn purge_acknowledged_tombstones(
+    provider: &MongoDBProvider,
+    collection_name: &str,
+) -> DatabaseResult<Vec<String>> {
+    let devices = collect_strings(
+        provider.get_collection("sync_devices").await?,
+        doc! {},
+        "device_id",
+    )
+    .await?;
+    if devices.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let collection = provider.get_collection(collection_name).await?;
+    let tombstones = collect_strings(
+        collection.clone(),
+        doc! { "deleted_at": { "$ne": Bson::Null } },
+        "id",
+    )
+    .await?;
+    if tombstones.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let acks_collection = provider.get_collection("sync_tombstone_acks").await?;
+    let mut acks: HashMap<String, std::collections::HashSet<String>> = HashMap::new();
+    let mut cursor = acks_collection
+        .find(
+            doc! { "table_name": collection_name, "record_id": { "$in": tombstones.clone() } },
+            None,
+        )
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+    while cursor
+        .advance()
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?
+    {
+        let doc = cursor
+            .deserialize_current()
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+        if let (Ok(record_id), Ok(device_id)) = (doc.get_str("record_id"), doc.get_str("device_id"))
+        {
+            acks.entry(record_id.to_string())
+                .or_default()
+                .insert(device_id.to_string());
+        }
+    }
+
+    let ids: Vec<String> = tombstones
+        .into_iter()
+        .filter(|id| {
+            acks.get(id)
+                .map(|seen| devices.iter().all(|d| seen.contains(d)))
+                .unwrap_or(false)
+        })
+        .collect();
+    if ids.is_empty() {
+        return Ok(ids);
+    }
+
+    collection
+        .delete_many(
+            doc! { "id": { "$in": ids.clone() }, "deleted_at": { "$ne": Bson::Null } },
+            None,
+        )
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+    acks_collection
+        .delete_many(
+            doc! { "table_name": collection_name, "record_id": { "$in": ids.clone() } },
+            None,
+        )
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    Ok(ids)
+}
+
+/// Delete every document of a collection
+pub async fn clear_records(
+    provider: &MongoDBProvider,
+    collection_name: &str,
+) -> DatabaseResult<usize> {
+    let collection = provider.get_collection(collection_name).await?;
+
+    let result = collection
+        .delete_many(doc! {}, None)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    Ok(result.deleted_count as usize)
+}
+
+/// Collect a string field from every document matching the filter
+async fn collect_strings(
+    collection: mongodb::Collection<Document>,
+    filter: Document,
+    field: &str,
+) -> DatabaseResult<Vec<String>> {
+    let options = mongodb::options::FindOptions::builder()
+        .projection(doc! { field: 1 })
+        .build();
+
+    let mut cursor = collection
+        .find(filter, options)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    let mut values = Vec::new();
+    while cursor
+        .advance()
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?
+    {
+        let doc = cursor
+            .deserialize_current()
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+        if let Ok(value) = doc.get_str(field) {
+            values.push(value.to_string());
+        }
+    }
+
+    Ok(values)
+}
+
 /// Helper function to convert JSON Value to BSON Document
 fn json_to_bson_document(value: &Value) -> DatabaseResult<Document> {
     let mut doc = Document::new();
@@ -166,6 +626,10 @@ fn bson_document_to_json(doc: &Document) -> DatabaseResult<Value> {
     if let Some(obj) = json.as_object() {
         let mut camel_obj = serde_json::Map::new();
         for (key, val) in obj {
+            if key == CHANGE_SEQ_COLUMN.name {
+                continue;
+            }
+
             let camel_key = to_camel_case(key);
             camel_obj.insert(camel_key, val.clone());
         }
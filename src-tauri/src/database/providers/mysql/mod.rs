@@ -3,16 +3,31 @@ mod sync;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde_json::Value;
-use sqlx::MySqlPool;
-use std::collections::HashMap;
+use sqlx::{MySqlPool, Row};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 use crate::database::{
     error::{DatabaseError, DatabaseResult},
-    traits_sync::SyncTarget,
+    providers::sync_schema::{
+        missing_columns, ColumnKind, CHANGE_SEQUENCE, CHANGE_SEQ_COLUMN, SCHEMA_TABLES,
+    },
+    traits_sync::{ChangeNotifications, RecordChanges, SyncTarget, SEALED_RECORDS_TABLE},
 };
 
+/// Bound values allowed in one statement by the MySQL protocol
+pub(crate) const MAX_BIND_PARAMS: usize = 65_535;
+
+fn column_type(kind: ColumnKind) -> &'static str {
+    match kind {
+        ColumnKind::Text => "TEXT",
+        ColumnKind::Integer => "BIGINT",
+        ColumnKind::Boolean => "BOOLEAN",
+        ColumnKind::Timestamp => "DATETIME",
+    }
+}
+
 /// MySQL provider for sync operations only
 pub struct MySQLProvider {
     connection_string: String,
@@ -60,6 +75,7 @@ impl MySQLProvider {
                 device_id VARCHAR(255) NOT NULL,
                 version BIGINT NOT NULL DEFAULT 1,
                 sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced',
+                deleted_at DATETIME,
                 INDEX idx_ssh_profiles_group_id (group_id),
                 INDEX idx_ssh_profiles_updated_at (updated_at)
             ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4
@@ -75,6 +91,7 @@ impl MySQLProvider {
                 device_id VARCHAR(255) NOT NULL,
                 version BIGINT NOT NULL DEFAULT 1,
                 sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced',
+                deleted_at DATETIME,
                 INDEX idx_ssh_groups_updated_at (updated_at)
             ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4
             "#,
@@ -89,11 +106,13 @@ impl MySQLProvider {
                 fingerprint VARCHAR(255) NOT NULL,
                 description TEXT,
                 last_used DATETIME,
+                hardware TEXT,
                 created_at DATETIME NOT NULL,
                 updated_at DATETIME NOT NULL,
                 device_id VARCHAR(255) NOT NULL,
                 version BIGINT NOT NULL DEFAULT 1,
                 sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced',
+                deleted_at DATETIME,
                 INDEX idx_ssh_keys_fingerprint (fingerprint),
                 INDEX idx_ssh_keys_updated_at (updated_at)
             ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4
@@ -115,6 +134,7 @@ impl MySQLProvider {
                 device_id VARCHAR(255) NOT NULL,
                 version BIGINT NOT NULL DEFAULT 1,
                 sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced',
+                deleted_at DATETIME,
                 INDEX idx_ssh_tunnels_profile_id (profile_id),
                 INDEX idx_ssh_tunnels_updated_at (updated_at),
                 FOREIGN KEY (profile_id) REFERENCES ssh_profiles(id) ON DELETE CASCADE
@@ -136,6 +156,7 @@ impl MySQLProvider {
                 device_id VARCHAR(255) NOT NULL,
                 version BIGINT NOT NULL DEFAULT 1,
                 sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced',
+                deleted_at DATETIME,
                 INDEX idx_saved_commands_group_id (group_id),
                 INDEX idx_saved_commands_updated_at (updated_at)
             ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4
@@ -152,9 +173,112 @@ impl MySQLProvider {
                 device_id VARCHAR(255) NOT NULL,
                 version BIGINT NOT NULL DEFAULT 1,
                 sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced',
+                deleted_at DATETIME,
                 INDEX idx_saved_command_groups_updated_at (updated_at)
             ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4
             "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS terminal_profiles (
+                id VARCHAR(36) PRIMARY KEY,
+                name VARCHAR(255) NOT NULL,
+                shell TEXT NOT NULL,
+                working_dir TEXT,
+                env TEXT,
+                icon VARCHAR(50),
+                color VARCHAR(50),
+                command TEXT,
+                created_at DATETIME NOT NULL,
+                updated_at DATETIME NOT NULL,
+                device_id VARCHAR(255) NOT NULL,
+                version BIGINT NOT NULL DEFAULT 1,
+                sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced',
+                deleted_at DATETIME,
+                INDEX idx_terminal_profiles_updated_at (updated_at)
+            ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS session_recordings (
+                id VARCHAR(36) PRIMARY KEY,
+                terminal_id VARCHAR(255),
+                session_name VARCHAR(255) NOT NULL,
+                terminal_type VARCHAR(50) NOT NULL,
+                started_at DATETIME NOT NULL,
+                ended_at DATETIME,
+                duration_ms BIGINT,
+                file_path TEXT NOT NULL,
+                file_size BIGINT NOT NULL DEFAULT 0,
+                width INT NOT NULL DEFAULT 80,
+                height INT NOT NULL DEFAULT 24,
+                metadata TEXT,
+                created_at DATETIME NOT NULL,
+                updated_at DATETIME NOT NULL,
+                device_id VARCHAR(255) NOT NULL,
+                version BIGINT NOT NULL DEFAULT 1,
+                sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced',
+                deleted_at DATETIME,
+                INDEX idx_session_recordings_updated_at (updated_at)
+            ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS known_hosts (
+                id VARCHAR(64) PRIMARY KEY,
+                marker VARCHAR(50),
+                hosts TEXT NOT NULL,
+                key_type VARCHAR(100) NOT NULL,
+                public_key TEXT NOT NULL,
+                created_at DATETIME NOT NULL,
+                updated_at DATETIME NOT NULL,
+                device_id VARCHAR(255) NOT NULL,
+                version BIGINT NOT NULL DEFAULT 1,
+                sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced',
+                deleted_at DATETIME
+            ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS sync_sealed_records (
+                id VARCHAR(128) PRIMARY KEY,
+                table_name VARCHAR(64) NOT NULL,
+                key_id VARCHAR(36) NOT NULL,
+                payload LONGTEXT NOT NULL,
+                version BIGINT NOT NULL DEFAULT 1,
+                updated_at DATETIME NOT NULL,
+                deleted_at DATETIME,
+                INDEX idx_sync_sealed_records_updated_at (updated_at),
+                INDEX idx_sync_sealed_records_table_name (table_name)
+            ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS sync_keys (
+                id VARCHAR(36) PRIMARY KEY,
+                salt VARCHAR(64) NOT NULL,
+                wrapped_key TEXT NOT NULL,
+                version BIGINT NOT NULL DEFAULT 1,
+                created_at VARCHAR(64) NOT NULL,
+                updated_at VARCHAR(64) NOT NULL
+            ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS sync_sequence (
+                name VARCHAR(64) PRIMARY KEY,
+                value BIGINT NOT NULL
+            ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS sync_devices (
+                device_id VARCHAR(255) PRIMARY KEY,
+                registered_at DATETIME NOT NULL,
+                last_seen_at DATETIME NOT NULL
+            ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS sync_tombstone_acks (
+                table_name VARCHAR(64) NOT NULL,
+                record_id VARCHAR(128) NOT NULL,
+                device_id VARCHAR(255) NOT NULL,
+                acknowledged_at DATETIME NOT NULL,
+                PRIMARY KEY (table_name, record_id, device_id)
+            ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4
+            "#,
         ];
 
         for table_sql in tables {
@@ -164,6 +288,72 @@ impl MySQLProvider {
                 .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
         }
 
+        sqlx::query("INSERT IGNORE INTO sync_sequence (name, value) VALUES (?, 0)")
+            .bind(CHANGE_SEQUENCE)
+            .execute(&*pool)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        self.add_missing_columns(&pool).await?;
+
+        // Sealed record ids are prefixed with their table name
+        sqlx::query("ALTER TABLE sync_tombstone_acks MODIFY record_id VARCHAR(128) NOT NULL")
+            .execute(&*pool)
+            .await
+            .ok();
+
+        // Pulls select the sealed records of one table; fails once the index exists
+        sqlx::query(
+            "ALTER TABLE sync_sealed_records ADD INDEX idx_sync_sealed_records_table_name (table_name)",
+        )
+        .execute(&*pool)
+        .await
+        .ok();
+
+        Ok(())
+    }
+
+    /// Add the columns models gained after the remote tables were created
+    async fn add_missing_columns(&self, pool: &MySqlPool) -> DatabaseResult<()> {
+        for table in SCHEMA_TABLES {
+            let rows = sqlx::query(
+                "SELECT CAST(COLUMN_NAME AS CHAR) AS name FROM information_schema.COLUMNS WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ?",
+            )
+            .bind(table)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+            let existing = rows
+                .iter()
+                .map(|row| row.try_get::<String, _>("name"))
+                .collect::<Result<HashSet<_>, _>>()
+                .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+            for column in missing_columns(table, &existing)? {
+                eprintln!("[INFO] Adding column {}.{} to MySQL", table, column.name);
+                sqlx::query(&format!(
+                    "ALTER TABLE {} ADD COLUMN {} {}",
+                    table,
+                    column.name,
+                    column_type(column.kind)
+                ))
+                .execute(pool)
+                .await
+                .map_err(|e| DatabaseError::MigrationError(e.to_string()))?;
+
+                if column == CHANGE_SEQ_COLUMN {
+                    sqlx::query(&format!(
+                        "ALTER TABLE {} ADD INDEX idx_{}_change_seq (change_seq)",
+                        table, table
+                    ))
+                    .execute(pool)
+                    .await
+                    .map_err(|e| DatabaseError::MigrationError(e.to_string()))?;
+                }
+            }
+        }
+
         Ok(())
     }
 }
@@ -171,13 +361,24 @@ impl MySQLProvider {
 #[async_trait]
 impl SyncTarget for MySQLProvider {
     async fn connect(&mut self) -> DatabaseResult<()> {
+        self.connect_read_only().await?;
+        self.create_sync_tables().await?;
+        Ok(())
+    }
+
+    async fn connect_read_only(&mut self) -> DatabaseResult<bool> {
         let pool = MySqlPool::connect(&self.connection_string)
             .await
             .map_err(|e| DatabaseError::ConnectionFailed(e.to_string()))?;
+        let tables: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM information_schema.TABLES WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = 'sync_sequence'",
+        )
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
 
         self.pool = Some(Arc::new(RwLock::new(pool)));
-        self.create_sync_tables().await?;
-        Ok(())
+        Ok(tables > 0)
     }
 
     async fn test_connection(&self) -> DatabaseResult<()> {
@@ -201,7 +402,36 @@ impl SyncTarget for MySQLProvider {
         table: &str,
         since: Option<DateTime<Utc>>,
     ) -> DatabaseResult<Vec<Value>> {
-        sync::pull_records(self, table, since).await
+        sync::pull_records(self, table, since, None).await
+    }
+
+    async fn pull_changes(
+        &self,
+        table: &str,
+        cursor: Option<&str>,
+    ) -> DatabaseResult<RecordChanges> {
+        sync::pull_changes(self, table, cursor, None).await
+    }
+
+    async fn pull_sealed_records(
+        &self,
+        table: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> DatabaseResult<Vec<Value>> {
+        sync::pull_records(self, SEALED_RECORDS_TABLE, since, Some(table)).await
+    }
+
+    async fn pull_sealed_changes(
+        &self,
+        table: &str,
+        cursor: Option<&str>,
+    ) -> DatabaseResult<RecordChanges> {
+        sync::pull_changes(self, SEALED_RECORDS_TABLE, cursor, Some(table)).await
+    }
+
+    /// MySQL cannot notify clients of changes, so it is polled by the scheduler
+    async fn watch_changes(&self) -> DatabaseResult<Option<ChangeNotifications>> {
+        Ok(None)
     }
 
     async fn get_record_versions(
@@ -211,4 +441,25 @@ impl SyncTarget for MySQLProvider {
     ) -> DatabaseResult<HashMap<String, u64>> {
         sync::get_record_versions(self, table, ids).await
     }
+
+    async fn register_device(&self, device_id: &str) -> DatabaseResult<()> {
+        sync::register_device(self, device_id).await
+    }
+
+    async fn acknowledge_tombstones(
+        &self,
+        table: &str,
+        device_id: &str,
+        ids: Vec<String>,
+    ) -> DatabaseResult<()> {
+        sync::acknowledge_tombstones(self, table, device_id, ids).await
+    }
+
+    async fn purge_acknowledged_tombstones(&self, table: &str) -> DatabaseResult<Vec<String>> {
+        sync::purge_acknowledged_tombstones(self, table).await
+    }
+
+    async fn clear_records(&self, table: &str) -> DatabaseResult<usize> {
+        sync::clear_records(self, table).await
+    }
 }
@@ -1,26 +1,17 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use serde_json::Value;
-use sqlx::Row;
+use sqlx::{Row, TypeInfo};
 use std::collections::HashMap;
 
-use crate::database::error::{DatabaseError, DatabaseResult};
+use crate::database::{
+    error::{DatabaseError, DatabaseResult},
+    providers::sync_schema::{
+        parse_cursor, row_batches, where_clause, RowBatch, CHANGE_SEQUENCE, CHANGE_SEQ_COLUMN,
+    },
+    traits_sync::RecordChanges,
+};
 
-use super::MySQLProvider;
-
-fn to_snake_case(s: &str) -> String {
-    let mut result = String::new();
-    for (i, ch) in s.chars().enumerate() {
-        if ch.is_uppercase() {
-            if i > 0 {
-                result.push('_');
-            }
-            result.push(ch.to_lowercase().next().unwrap());
-        } else {
-            result.push(ch);
-        }
-    }
-    result
-}
+use super::{MySQLProvider, MAX_BIND_PARAMS};
 
 fn convert_datetime_value(value: &Value) -> Value {
     if let Some(s) = value.as_str() {
@@ -33,6 +24,33 @@ fn convert_datetime_value(value: &Value) -> Value {
     value.clone()
 }
 
+/// Multi-row upsert of a batch, numbering its rows in the change sequence after `seq`
+fn upsert_sql(table: &str, batch: &RowBatch, seq: i64) -> String {
+    let placeholders = vec!["?"; batch.columns.len()].join(", ");
+    let values: Vec<String> = (1..=batch.rows.len() as i64)
+        .map(|row| format!("({}, {})", placeholders, seq + row))
+        .collect();
+    let updates: Vec<String> = batch
+        .columns
+        .iter()
+        .filter(|c| **c != "id")
+        .chain(&["change_seq"])
+        .map(|c| format!("{} = VALUES({})", c, c))
+        .collect();
+
+    format!(
+        "INSERT INTO {} ({}, change_seq) VALUES {} ON DUPLICATE KEY UPDATE {}",
+        table,
+        batch.columns.join(", "),
+        values.join(", "),
+        updates.join(", ")
+    )
+}
+
+/// Upsert records with multi-row statements, all in one transaction
+///
+/// Every row gets the next change sequence number. The counter row stays locked until the
+/// transaction commits, so rows become visible in sequence order and a pull never skips one.
 pub async fn push_records(
     provider: &MySQLProvider,
     table: &str,
@@ -42,55 +60,56 @@ pub async fn push_records(
         return Ok(0);
     }
 
+    let batches = row_batches(table, &records, MAX_BIND_PARAMS)?;
+    let total: usize = batches.iter().map(|batch| batch.rows.len()).sum();
+
     let pool = provider.get_pool()?;
     let pool = pool.read().await;
 
-    let mut count = 0;
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    let current: i64 =
+        sqlx::query_scalar("SELECT value FROM sync_sequence WHERE name = ? FOR UPDATE")
+            .bind(CHANGE_SEQUENCE)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+    sqlx::query("UPDATE sync_sequence SET value = ? WHERE name = ?")
+        .bind(current + total as i64)
+        .bind(CHANGE_SEQUENCE)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+    let mut seq = current;
 
-    for record in records.iter() {
-        let obj = record
-            .as_object()
-            .ok_or_else(|| DatabaseError::QueryFailed("Expected JSON object".to_string()))?;
-
-        let column_mapping: Vec<(String, String)> = obj
-            .keys()
-            .map(|k| (k.to_string(), to_snake_case(k)))
-            .collect();
-
-        let db_columns: Vec<String> = column_mapping
-            .iter()
-            .map(|(_, db_col)| db_col.clone())
-            .collect();
-        let placeholders: Vec<String> = (0..db_columns.len()).map(|_| "?".to_string()).collect();
-        let updates: Vec<String> = db_columns
-            .iter()
-            .filter(|c| c.as_str() != "id")
-            .map(|c| format!("{} = VALUES({})", c, c))
-            .collect();
+    let mut count = 0;
 
-        let sql = format!(
-            "INSERT INTO {} ({}) VALUES ({}) ON DUPLICATE KEY UPDATE {}",
-            table,
-            db_columns.join(", "),
-            placeholders.join(", "),
-            updates.join(", ")
-        );
+    for batch in &batches {
+        let sql = upsert_sql(table, batch, seq);
+        seq += batch.rows.len() as i64;
 
         let mut query = sqlx::query(&sql);
-        for (json_key, _) in &column_mapping {
-            let value = &obj[json_key];
-            let converted_value = convert_datetime_value(value);
-            query = bind_value(query, &converted_value);
+        for row in &batch.rows {
+            for value in row {
+                query = bind_value(query, &convert_datetime_value(value));
+            }
         }
 
-        let _result = query
-            .execute(&*pool)
+        query
+            .execute(&mut *tx)
             .await
             .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
 
-        count += 1;
+        count += batch.rows.len();
     }
 
+    tx.commit()
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
     Ok(count)
 }
 
@@ -98,28 +117,36 @@ pub async fn pull_records(
     provider: &MySQLProvider,
     table: &str,
     since: Option<DateTime<Utc>>,
+    table_name: Option<&str>,
 ) -> DatabaseResult<Vec<Value>> {
     let pool = provider.get_pool()?;
     let pool = pool.read().await;
 
-    let sql = if let Some(_since_time) = since {
-        format!(
-            "SELECT * FROM {} WHERE updated_at > ? ORDER BY updated_at ASC",
-            table
-        )
-    } else {
-        format!("SELECT * FROM {} ORDER BY updated_at ASC", table)
-    };
-
-    let rows = if let Some(since_time) = since {
-        sqlx::query(&sql)
-            .bind(since_time.to_rfc3339())
-            .fetch_all(&*pool)
-            .await
-    } else {
-        sqlx::query(&sql).fetch_all(&*pool).await
+    let mut conditions = Vec::new();
+    if since.is_some() {
+        conditions.push("updated_at > ?");
     }
-    .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+    if table_name.is_some() {
+        conditions.push("table_name = ?");
+    }
+    let sql = format!(
+        "SELECT * FROM {}{} ORDER BY updated_at ASC",
+        table,
+        where_clause(&conditions)
+    );
+
+    // Timestamps are stored in the format pushes convert them to
+    let mut query = sqlx::query(&sql);
+    if let Some(since_time) = since {
+        query = query.bind(since_time.format("%Y-%m-%d %H:%M:%S").to_string());
+    }
+    if let Some(name) = table_name {
+        query = query.bind(name);
+    }
+    let rows = query
+        .fetch_all(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
 
     let mut records = Vec::new();
     for row in rows {
@@ -130,6 +157,67 @@ pub async fn pull_records(
     Ok(records)
 }
 
+/// Pull the rows written after a change sequence number
+/// Rows written before the sequence existed have none, and only come with a full pull
+pub async fn pull_changes(
+    provider: &MySQLProvider,
+    table: &str,
+    cursor: Option<&str>,
+    table_name: Option<&str>,
+) -> DatabaseResult<RecordChanges> {
+    let pool = provider.get_pool()?;
+    let pool = pool.read().await;
+
+    let current: i64 = sqlx::query_scalar("SELECT value FROM sync_sequence WHERE name = ?")
+        .bind(CHANGE_SEQUENCE)
+        .fetch_one(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    // A cursor past the counter belongs to a sync database that has since been recreated
+    let after = parse_cursor(cursor).filter(|seq| *seq <= current);
+
+    let mut conditions = Vec::new();
+    if after.is_some() {
+        conditions.push("change_seq > ?");
+    }
+    if table_name.is_some() {
+        conditions.push("table_name = ?");
+    }
+    let sql = format!(
+        "SELECT * FROM {}{} ORDER BY change_seq ASC",
+        table,
+        where_clause(&conditions)
+    );
+
+    let mut query = sqlx::query(&sql);
+    if let Some(seq) = after {
+        query = query.bind(seq);
+    }
+    if let Some(name) = table_name {
+        query = query.bind(name);
+    }
+    let rows = query
+        .fetch_all(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    let mut last_seq = after.unwrap_or(0);
+    let mut records = Vec::with_capacity(rows.len());
+    for row in &rows {
+        let seq: Option<i64> = row
+            .try_get(CHANGE_SEQ_COLUMN.name)
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+        last_seq = last_seq.max(seq.unwrap_or(0));
+        records.push(row_to_json(row)?);
+    }
+
+    Ok(RecordChanges {
+        records,
+        cursor: last_seq.to_string(),
+    })
+}
+
 pub async fn get_record_versions(
     provider: &MySQLProvider,
     table: &str,
@@ -173,6 +261,154 @@ pub async fn get_record_versions(
     Ok(versions)
 }
 
+/// Register a device, or refresh its last seen time
+pub async fn register_device(provider: &MySQLProvider, device_id: &str) -> DatabaseResult<()> {
+    let pool = provider.get_pool()?;
+    let pool = pool.read().await;
+    let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+    sqlx::query(
+        r#"
+        INSERT INTO sync_devices (device_id, registered_at, last_seen_at) VALUES (?, ?, ?)
+        ON DUPLICATE KEY UPDATE last_seen_at = VALUES(last_seen_at)
+        "#,
+    )
+    .bind(device_id)
+    .bind(&now)
+    .bind(&now)
+    .execute(&*pool)
+    .await
+    .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Record that a device has seen the given tombstones
+pub async fn acknowledge_tombstones(
+    provider: &MySQLProvider,
+    table: &str,
+    device_id: &str,
+    ids: Vec<String>,
+) -> DatabaseResult<()> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    let pool = provider.get_pool()?;
+    let pool = pool.read().await;
+    let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+    for chunk in ids.chunks(MAX_BIND_PARAMS / 4) {
+        let values = vec!["(?, ?, ?, ?)"; chunk.len()].join(", ");
+        let sql = format!(
+            "INSERT IGNORE INTO sync_tombstone_acks (table_name, record_id, device_id, acknowledged_at) VALUES {}",
+            values
+        );
+
+        let mut query = sqlx::query(&sql);
+        for id in chunk {
+            query = query.bind(table).bind(id).bind(device_id).bind(&now);
+        }
+
+        query
+            .execute(&*pool)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Delete tombstones that every registered device has acknowledged
+pub async fn purge_acknowledged_tombstones(
+    provider: &MySQLProvider,
+    table: &str,
+) -> DatabaseResult<Vec<String>> {
+    let pool = provider.get_pool()?;
+    let pool = pool.read().await;
+
+    let sql = format!(
+        r#"
+        SELECT t.id FROM {} t
+        WHERE t.deleted_at IS NOT NULL
+          AND (SELECT COUNT(*) FROM sync_tombstone_acks a
+               JOIN sync_devices d ON d.device_id = a.device_id
+               WHERE a.table_name = ? AND a.record_id = t.id)
+              >= (SELECT COUNT(*) FROM sync_devices)
+        "#,
+        table
+    );
+
+    let rows = sqlx::query(&sql)
+        .bind(table)
+        .fetch_all(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    let ids = rows
+        .iter()
+        .map(|row| row.try_get::<String, _>("id"))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    if ids.is_empty() {
+        return Ok(ids);
+    }
+
+    let placeholders: Vec<String> = (0..ids.len()).map(|_| "?".to_string()).collect();
+    let delete_records = format!(
+        "DELETE FROM {} WHERE deleted_at IS NOT NULL AND id IN ({})",
+        table,
+        placeholders.join(", ")
+    );
+    let delete_acks = format!(
+        "DELETE FROM sync_tombstone_acks WHERE table_name = ? AND record_id IN ({})",
+        placeholders.join(", ")
+    );
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    let mut query = sqlx::query(&delete_records);
+    for id in &ids {
+        query = query.bind(id);
+    }
+    query
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    let mut query = sqlx::query(&delete_acks).bind(table);
+    for id in &ids {
+        query = query.bind(id);
+    }
+    query
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    Ok(ids)
+}
+
+/// Delete every record of a table
+pub async fn clear_records(provider: &MySQLProvider, table: &str) -> DatabaseResult<usize> {
+    let pool = provider.get_pool()?;
+    let pool = pool.read().await;
+
+    let result = sqlx::query(&format!("DELETE FROM {}", table))
+        .execute(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    Ok(result.rows_affected() as usize)
+}
+
 fn bind_value<'q>(
     query: sqlx::query::Query<'q, sqlx::MySql, sqlx::mysql::MySqlArguments>,
     value: &Value,
@@ -202,15 +438,98 @@ fn row_to_json(row: &sqlx::mysql::MySqlRow) -> DatabaseResult<Value> {
     let mut obj = serde_json::Map::new();
 
     for (idx, col) in row.columns().iter().enumerate() {
-        let col_name = col.name().to_string();
-        let value: Option<String> = row.try_get(idx).ok();
-
-        if let Some(v) = value {
-            obj.insert(col_name, Value::String(v));
-        } else {
-            obj.insert(col_name, Value::Null);
+        if col.name() == CHANGE_SEQ_COLUMN.name {
+            continue;
         }
+
+        let col_name = col.name().to_string();
+        // Numbers and dates do not decode as strings; sealed records authenticate their version
+        let value = match col.type_info().name() {
+            "BIGINT" => row
+                .try_get::<Option<i64>, _>(idx)
+                .ok()
+                .flatten()
+                .map(Value::from),
+            "BOOLEAN" => row
+                .try_get::<Option<bool>, _>(idx)
+                .ok()
+                .flatten()
+                .map(Value::Bool),
+            "DATETIME" => row
+                .try_get::<Option<NaiveDateTime>, _>(idx)
+                .ok()
+                .flatten()
+                .map(|dt| Value::String(dt.and_utc().to_rfc3339())),
+            _ => row
+                .try_get::<Option<String>, _>(idx)
+                .ok()
+                .flatten()
+                .map(Value::String),
+        };
+
+        obj.insert(col_name, value.unwrap_or(Value::Null));
     }
 
     Ok(Value::Object(obj))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn groups(count: usize) -> Vec<Value> {
+        (0..count)
+            .map(|i| {
+                json!({
+                    "id": format!("g{}", i),
+                    "name": format!("group {}", i),
+                    "description": null,
+                    "color": "#00ff00",
+                    "createdAt": "2026-01-01T00:00:00Z",
+                    "updatedAt": "2026-01-01T00:00:00Z",
+                    "deviceId": "d1",
+                    "version": 1,
+                    "syncStatus": "Synced",
+                    "deletedAt": null
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_upsert_sql_numbers_sequence() {
+        let records = vec![
+            json!({ "id": "g1", "name": "one" }),
+            json!({ "id": "g2", "name": "two" }),
+        ];
+        let batches = row_batches("ssh_groups", &records, MAX_BIND_PARAMS).unwrap();
+
+        assert_eq!(
+            upsert_sql("ssh_groups", &batches[0], 7),
+            "INSERT INTO ssh_groups (id, name, change_seq) VALUES (?, ?, 8), (?, ?, 9) \
+             ON DUPLICATE KEY UPDATE name = VALUES(name), change_seq = VALUES(change_seq)"
+        );
+    }
+
+    #[test]
+    fn test_push_batches_stay_within_bind_limit() {
+        let records = groups(10_000);
+
+        // Ten columns a row: 6553 rows fit in one statement
+        let batches = row_batches("ssh_groups", &records, MAX_BIND_PARAMS).unwrap();
+        assert_eq!(batches.len(), 2);
+
+        let mut seq = 0;
+        for batch in &batches {
+            let params = batch.columns.len() * batch.rows.len();
+            assert!(params <= MAX_BIND_PARAMS);
+
+            let sql = upsert_sql("ssh_groups", batch, seq);
+            seq += batch.rows.len() as i64;
+            assert_eq!(sql.matches('?').count(), params);
+            assert!(sql.contains(&format!(", {}) ON DUPLICATE KEY", seq)));
+        }
+        assert_eq!(seq, 10_000);
+    }
+}
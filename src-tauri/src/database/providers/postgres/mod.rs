@@ -3,20 +3,37 @@ mod sync;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde_json::Value;
-use sqlx::PgPool;
-use std::collections::HashMap;
+use sqlx::{PgPool, Row};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 use crate::database::{
     error::{DatabaseError, DatabaseResult},
-    traits_sync::SyncTarget,
+    providers::sync_schema::{
+        missing_columns, ColumnKind, CHANGE_SEQUENCE, CHANGE_SEQ_COLUMN, SCHEMA_TABLES,
+    },
+    traits_sync::{ChangeNotifications, RecordChanges, SyncTarget, SEALED_RECORDS_TABLE},
 };
 
+/// Bound values allowed in one statement by the PostgreSQL protocol
+pub(crate) const MAX_BIND_PARAMS: usize = 65_535;
+
+/// Timestamps are stored as text, as the tables were created with
+fn column_type(kind: ColumnKind) -> &'static str {
+    match kind {
+        ColumnKind::Text | ColumnKind::Timestamp => "TEXT",
+        ColumnKind::Integer => "BIGINT",
+        ColumnKind::Boolean => "BOOLEAN",
+    }
+}
+
 /// PostgreSQL provider for sync operations only
 pub struct PostgreSQLProvider {
     connection_string: String,
     pool: Option<Arc<RwLock<PgPool>>>,
+    /// Device registered by the last sync, named in the notifications of its pushes
+    device_id: std::sync::RwLock<Option<String>>,
 }
 
 impl PostgreSQLProvider {
@@ -24,6 +41,7 @@ impl PostgreSQLProvider {
         Self {
             connection_string,
             pool: None,
+            device_id: std::sync::RwLock::new(None),
         }
     }
 
@@ -33,6 +51,10 @@ impl PostgreSQLProvider {
             .ok_or_else(|| DatabaseError::ConnectionFailed("Database not connected".to_string()))
     }
 
+    pub(crate) fn device_id(&self) -> Option<String> {
+        self.device_id.read().ok().and_then(|id| id.clone())
+    }
+
     /// Create sync tables in PostgreSQL database
     async fn create_sync_tables(&self) -> DatabaseResult<()> {
         let pool = self.get_pool()?;
@@ -59,7 +81,8 @@ impl PostgreSQLProvider {
                 updated_at TEXT NOT NULL,
                 device_id VARCHAR(255) NOT NULL,
                 version BIGINT NOT NULL DEFAULT 1,
-                sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced'
+                sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced',
+                deleted_at TEXT
             )
             "#,
             r#"
@@ -72,7 +95,8 @@ impl PostgreSQLProvider {
                 updated_at TEXT NOT NULL,
                 device_id VARCHAR(255) NOT NULL,
                 version BIGINT NOT NULL DEFAULT 1,
-                sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced'
+                sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced',
+                deleted_at TEXT
             )
             "#,
             r#"
@@ -86,11 +110,13 @@ impl PostgreSQLProvider {
                 fingerprint VARCHAR(255) NOT NULL,
                 description TEXT,
                 last_used TEXT,
+                hardware TEXT,
                 created_at TEXT NOT NULL,
                 updated_at TEXT NOT NULL,
                 device_id VARCHAR(255) NOT NULL,
                 version BIGINT NOT NULL DEFAULT 1,
-                sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced'
+                sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced',
+                deleted_at TEXT
             )
             "#,
             r#"
@@ -110,6 +136,7 @@ impl PostgreSQLProvider {
                 device_id VARCHAR(255) NOT NULL,
                 version BIGINT NOT NULL DEFAULT 1,
                 sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced',
+                deleted_at TEXT,
                 FOREIGN KEY (profile_id) REFERENCES ssh_profiles(id) ON DELETE CASCADE
             )
             "#,
@@ -128,7 +155,8 @@ impl PostgreSQLProvider {
                 updated_at TEXT NOT NULL,
                 device_id VARCHAR(255) NOT NULL,
                 version BIGINT NOT NULL DEFAULT 1,
-                sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced'
+                sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced',
+                deleted_at TEXT
             )
             "#,
             r#"
@@ -142,7 +170,106 @@ impl PostgreSQLProvider {
                 updated_at TEXT NOT NULL,
                 device_id VARCHAR(255) NOT NULL,
                 version BIGINT NOT NULL DEFAULT 1,
-                sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced'
+                sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced',
+                deleted_at TEXT
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS terminal_profiles (
+                id VARCHAR(36) PRIMARY KEY,
+                name VARCHAR(255) NOT NULL,
+                shell TEXT NOT NULL,
+                working_dir TEXT,
+                env TEXT,
+                icon VARCHAR(50),
+                color VARCHAR(50),
+                command TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                device_id VARCHAR(255) NOT NULL,
+                version BIGINT NOT NULL DEFAULT 1,
+                sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced',
+                deleted_at TEXT
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS session_recordings (
+                id VARCHAR(36) PRIMARY KEY,
+                terminal_id VARCHAR(255),
+                session_name VARCHAR(255) NOT NULL,
+                terminal_type VARCHAR(50) NOT NULL,
+                started_at TEXT NOT NULL,
+                ended_at TEXT,
+                duration_ms BIGINT,
+                file_path TEXT NOT NULL,
+                file_size BIGINT NOT NULL DEFAULT 0,
+                width INTEGER NOT NULL DEFAULT 80,
+                height INTEGER NOT NULL DEFAULT 24,
+                metadata TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                device_id VARCHAR(255) NOT NULL,
+                version BIGINT NOT NULL DEFAULT 1,
+                sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced',
+                deleted_at TEXT
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS known_hosts (
+                id VARCHAR(64) PRIMARY KEY,
+                marker VARCHAR(50),
+                hosts TEXT NOT NULL,
+                key_type VARCHAR(100) NOT NULL,
+                public_key TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                device_id VARCHAR(255) NOT NULL,
+                version BIGINT NOT NULL DEFAULT 1,
+                sync_status VARCHAR(50) NOT NULL DEFAULT 'Synced',
+                deleted_at TEXT
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS sync_sealed_records (
+                id VARCHAR(128) PRIMARY KEY,
+                table_name VARCHAR(64) NOT NULL,
+                key_id VARCHAR(36) NOT NULL,
+                payload TEXT NOT NULL,
+                version BIGINT NOT NULL DEFAULT 1,
+                updated_at TEXT NOT NULL,
+                deleted_at TEXT
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS sync_keys (
+                id VARCHAR(36) PRIMARY KEY,
+                salt TEXT NOT NULL,
+                wrapped_key TEXT NOT NULL,
+                version BIGINT NOT NULL DEFAULT 1,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS sync_sequence (
+                name VARCHAR(64) PRIMARY KEY,
+                value BIGINT NOT NULL
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS sync_devices (
+                device_id VARCHAR(255) PRIMARY KEY,
+                registered_at TEXT NOT NULL,
+                last_seen_at TEXT NOT NULL
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS sync_tombstone_acks (
+                table_name VARCHAR(64) NOT NULL,
+                record_id VARCHAR(128) NOT NULL,
+                device_id VARCHAR(255) NOT NULL,
+                acknowledged_at TEXT NOT NULL,
+                PRIMARY KEY (table_name, record_id, device_id)
             )
             "#,
         ];
@@ -154,6 +281,22 @@ impl PostgreSQLProvider {
                 .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
         }
 
+        sqlx::query(
+            "INSERT INTO sync_sequence (name, value) VALUES ($1, 0) ON CONFLICT (name) DO NOTHING",
+        )
+        .bind(CHANGE_SEQUENCE)
+        .execute(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        self.add_missing_columns(&pool).await?;
+
+        // Sealed record ids are prefixed with their table name
+        sqlx::query("ALTER TABLE sync_tombstone_acks ALTER COLUMN record_id TYPE VARCHAR(128)")
+            .execute(&*pool)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
         let indexes = vec![
             "CREATE INDEX IF NOT EXISTS idx_ssh_profiles_group_id ON ssh_profiles (group_id)",
             "CREATE INDEX IF NOT EXISTS idx_ssh_profiles_updated_at ON ssh_profiles (updated_at)",
@@ -165,6 +308,10 @@ impl PostgreSQLProvider {
             "CREATE INDEX IF NOT EXISTS idx_saved_commands_group_id ON saved_commands (group_id)",
             "CREATE INDEX IF NOT EXISTS idx_saved_commands_updated_at ON saved_commands (updated_at)",
             "CREATE INDEX IF NOT EXISTS idx_saved_command_groups_updated_at ON saved_command_groups (updated_at)",
+            "CREATE INDEX IF NOT EXISTS idx_terminal_profiles_updated_at ON terminal_profiles (updated_at)",
+            "CREATE INDEX IF NOT EXISTS idx_session_recordings_updated_at ON session_recordings (updated_at)",
+            "CREATE INDEX IF NOT EXISTS idx_sync_sealed_records_updated_at ON sync_sealed_records (updated_at)",
+            "CREATE INDEX IF NOT EXISTS idx_sync_sealed_records_table_name ON sync_sealed_records (table_name)",
         ];
 
         for index_sql in indexes {
@@ -176,18 +323,74 @@ impl PostgreSQLProvider {
 
         Ok(())
     }
+
+    /// Add the columns models gained after the remote tables were created
+    async fn add_missing_columns(&self, pool: &PgPool) -> DatabaseResult<()> {
+        for table in SCHEMA_TABLES {
+            let rows = sqlx::query(
+                "SELECT column_name::TEXT AS name FROM information_schema.columns WHERE table_schema = current_schema() AND table_name = $1",
+            )
+            .bind(table)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+            let existing = rows
+                .iter()
+                .map(|row| row.try_get::<String, _>("name"))
+                .collect::<Result<HashSet<_>, _>>()
+                .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+            for column in missing_columns(table, &existing)? {
+                eprintln!(
+                    "[INFO] Adding column {}.{} to PostgreSQL",
+                    table, column.name
+                );
+                sqlx::query(&format!(
+                    "ALTER TABLE {} ADD COLUMN IF NOT EXISTS {} {}",
+                    table,
+                    column.name,
+                    column_type(column.kind)
+                ))
+                .execute(pool)
+                .await
+                .map_err(|e| DatabaseError::MigrationError(e.to_string()))?;
+
+                if column == CHANGE_SEQ_COLUMN {
+                    sqlx::query(&format!(
+                        "CREATE INDEX IF NOT EXISTS idx_{}_change_seq ON {} (change_seq)",
+                        table, table
+                    ))
+                    .execute(pool)
+                    .await
+                    .map_err(|e| DatabaseError::MigrationError(e.to_string()))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[async_trait]
 impl SyncTarget for PostgreSQLProvider {
     async fn connect(&mut self) -> DatabaseResult<()> {
+        self.connect_read_only().await?;
+        self.create_sync_tables().await?;
+        Ok(())
+    }
+
+    async fn connect_read_only(&mut self) -> DatabaseResult<bool> {
         let pool = PgPool::connect(&self.connection_string)
             .await
             .map_err(|e| DatabaseError::ConnectionFailed(e.to_string()))?;
+        let set_up: bool = sqlx::query_scalar("SELECT to_regclass('sync_sequence') IS NOT NULL")
+            .fetch_one(&pool)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
 
         self.pool = Some(Arc::new(RwLock::new(pool)));
-        self.create_sync_tables().await?;
-        Ok(())
+        Ok(set_up)
     }
 
     async fn test_connection(&self) -> DatabaseResult<()> {
@@ -211,7 +414,35 @@ impl SyncTarget for PostgreSQLProvider {
         table: &str,
         since: Option<DateTime<Utc>>,
     ) -> DatabaseResult<Vec<Value>> {
-        sync::pull_records(self, table, since).await
+        sync::pull_records(self, table, since, None).await
+    }
+
+    async fn pull_changes(
+        &self,
+        table: &str,
+        cursor: Option<&str>,
+    ) -> DatabaseResult<RecordChanges> {
+        sync::pull_changes(self, table, cursor, None).await
+    }
+
+    async fn pull_sealed_records(
+        &self,
+        table: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> DatabaseResult<Vec<Value>> {
+        sync::pull_records(self, SEALED_RECORDS_TABLE, since, Some(table)).await
+    }
+
+    async fn pull_sealed_changes(
+        &self,
+        table: &str,
+        cursor: Option<&str>,
+    ) -> DatabaseResult<RecordChanges> {
+        sync::pull_changes(self, SEALED_RECORDS_TABLE, cursor, Some(table)).await
+    }
+
+    async fn watch_changes(&self) -> DatabaseResult<Option<ChangeNotifications>> {
+        sync::watch_changes(self).await.map(Some)
     }
 
     async fn get_record_versions(
@@ -221,4 +452,29 @@ impl SyncTarget for PostgreSQLProvider {
     ) -> DatabaseResult<HashMap<String, u64>> {
         sync::get_record_versions(self, table, ids).await
     }
+
+    async fn register_device(&self, device_id: &str) -> DatabaseResult<()> {
+        sync::register_device(self, device_id).await?;
+        if let Ok(mut registered) = self.device_id.write() {
+            *registered = Some(device_id.to_string());
+        }
+        Ok(())
+    }
+
+    async fn acknowledge_tombstones(
+        &self,
+        table: &str,
+        device_id: &str,
+        ids: Vec<String>,
+    ) -> DatabaseResult<()> {
+        sync::acknowledge_tombstones(self, table, device_id, ids).await
+    }
+
+    async fn purge_acknowledged_tombstones(&self, table: &str) -> DatabaseResult<Vec<String>> {
+        sync::purge_acknowledged_tombstones(self, table).await
+    }
+
+    async fn clear_records(&self, table: &str) -> DatabaseResult<usize> {
+        sync::clear_records(self, table).await
+    }
 }
@@ -0,0 +1,138 @@
+use sqlx::Row;
+
+use crate::{
+    database::error::{DatabaseError, DatabaseResult},
+    models::ssh::SSHCertificateAuthority,
+};
+
+use super::SQLiteProvider;
+
+const SELECT_COLUMNS: &str = "id, name, key_type, private_key, public_key, fingerprint, default_principals, default_ttl_minutes, next_serial, description, created_at, updated_at, device_id, version, sync_status, deleted_at";
+
+pub async fn save_certificate_authority(
+    provider: &SQLiteProvider,
+    model: &SSHCertificateAuthority,
+) -> DatabaseResult<()> {
+    let pool_arc = provider.get_pool()?;
+    let pool = pool_arc.read().await;
+
+    sqlx::query(
+        r#"
+        INSERT OR REPLACE INTO ssh_certificate_authorities (
+            id, name, key_type, private_key, public_key, fingerprint,
+            default_principals, default_ttl_minutes, next_serial, description,
+            created_at, updated_at, device_id, version, sync_status, deleted_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    "#,
+    )
+    .bind(&model.base.id)
+    .bind(&model.name)
+    .bind(serde_json::to_string(&model.key_type).unwrap())
+    .bind(&model.private_key)
+    .bind(&model.public_key)
+    .bind(&model.fingerprint)
+    .bind(serde_json::to_string(&model.default_principals).unwrap())
+    .bind(model.default_ttl_minutes as i64)
+    .bind(model.next_serial as i64)
+    .bind(&model.description)
+    .bind(model.base.created_at.to_rfc3339())
+    .bind(model.base.updated_at.to_rfc3339())
+    .bind(&model.base.device_id)
+    .bind(model.base.version as i64)
+    .bind(serde_json::to_string(&model.base.sync_status).unwrap())
+    .bind(model.base.deleted_at.map(|dt| dt.to_rfc3339()))
+    .execute(&*pool)
+    .await
+    .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    Ok(())
+}
+
+pub async fn find_certificate_authority_by_id(
+    provider: &SQLiteProvider,
+    id: &str,
+) -> DatabaseResult<Option<SSHCertificateAuthority>> {
+    let pool = provider.get_pool()?;
+    let pool = pool.read().await;
+
+    let row = sqlx::query(&format!(
+        "SELECT {} FROM ssh_certificate_authorities WHERE id = ? AND deleted_at IS NULL",
+        SELECT_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(&*pool)
+    .await
+    .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    row.map(|row| certificate_authority_from_row(&row))
+        .transpose()
+}
+
+pub async fn find_all_certificate_authorities(
+    provider: &SQLiteProvider,
+) -> DatabaseResult<Vec<SSHCertificateAuthority>> {
+    let pool = provider.get_pool()?;
+    let pool = pool.read().await;
+
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM ssh_certificate_authorities WHERE deleted_at IS NULL ORDER BY name",
+        SELECT_COLUMNS
+    ))
+    .fetch_all(&*pool)
+    .await
+    .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    rows.iter().map(certificate_authority_from_row).collect()
+}
+
+/// Certificate authorities are local-only, so they are removed outright instead of tombstoned
+pub async fn delete_certificate_authority(
+    provider: &SQLiteProvider,
+    id: &str,
+) -> DatabaseResult<()> {
+    let pool = provider.get_pool()?;
+    let pool = pool.read().await;
+
+    sqlx::query("DELETE FROM ssh_certificate_authorities WHERE id = ?")
+        .bind(id)
+        .execute(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    Ok(())
+}
+
+fn certificate_authority_from_row(
+    row: &sqlx::sqlite::SqliteRow,
+) -> DatabaseResult<SSHCertificateAuthority> {
+    Ok(SSHCertificateAuthority {
+        base: crate::models::base::BaseModel {
+            id: row.get("id"),
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))
+                .map_err(|e| DatabaseError::ParseError(format!("Parse error: {}", e)))?
+                .with_timezone(&chrono::Utc),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))
+                .map_err(|e| DatabaseError::ParseError(format!("Parse error: {}", e)))?
+                .with_timezone(&chrono::Utc),
+            device_id: row.get("device_id"),
+            version: row.get::<i64, _>("version") as u64,
+            sync_status: serde_json::from_str(&row.get::<String, _>("sync_status"))
+                .unwrap_or(crate::database::traits::SyncStatus::Synced),
+            deleted_at: row
+                .get::<Option<String>, _>("deleted_at")
+                .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                .map(|dt| dt.with_timezone(&chrono::Utc)),
+        },
+        name: row.get("name"),
+        key_type: serde_json::from_str(&row.get::<String, _>("key_type"))
+            .map_err(DatabaseError::SerializationError)?,
+        private_key: row.get("private_key"),
+        public_key: row.get("public_key"),
+        fingerprint: row.get("fingerprint"),
+        default_principals: serde_json::from_str(&row.get::<String, _>("default_principals"))
+            .map_err(DatabaseError::SerializationError)?,
+        default_ttl_minutes: row.get::<i64, _>("default_ttl_minutes") as u32,
+        next_serial: row.get::<i64, _>("next_serial") as u64,
+        description: row.get("description"),
+    })
+}
@@ -5,7 +5,7 @@ use crate::{
     models::saved_command::{SavedCommand, SavedCommandGroup},
 };
 
-use super::SQLiteProvider;
+use super::{sync_ops::changed_since_filter, SQLiteProvider};
 
 pub async fn save_saved_command(
     provider: &SQLiteProvider,
@@ -14,12 +14,23 @@ pub async fn save_saved_command(
     let pool = provider.get_pool()?;
     let pool = pool.read().await;
 
+    write_saved_command(&*pool, command).await
+}
+
+pub(super) async fn write_saved_command<'e, E>(
+    executor: E,
+    command: &SavedCommand,
+) -> DatabaseResult<()>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
     sqlx::query(
         r#"
         INSERT OR REPLACE INTO saved_commands (
             id, name, description, command, group_id, tags, is_favorite,
-            usage_count, last_used_at, created_at, updated_at, device_id, version, sync_status
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            usage_count, last_used_at, created_at, updated_at, device_id, version, sync_status,
+            deleted_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
     )
     .bind(&command.base.id)
@@ -39,7 +50,8 @@ pub async fn save_saved_command(
         serde_json::to_string(&command.base.sync_status)
             .unwrap_or_else(|_| "\"synced\"".to_string()),
     )
-    .execute(&*pool)
+    .bind(command.base.deleted_at.map(|dt| dt.to_rfc3339()))
+    .execute(executor)
     .await
     .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
 
@@ -48,13 +60,40 @@ pub async fn save_saved_command(
 
 pub async fn find_all_saved_commands(
     provider: &SQLiteProvider,
+) -> DatabaseResult<Vec<SavedCommand>> {
+    find_saved_commands_where(provider, "deleted_at IS NULL").await
+}
+
+/// Tombstones left by deleted commands, used to sync deletions
+pub async fn find_deleted_saved_commands(
+    provider: &SQLiteProvider,
+) -> DatabaseResult<Vec<SavedCommand>> {
+    find_saved_commands_where(provider, "deleted_at IS NOT NULL").await
+}
+
+/// Commands written after a change log position, tombstones included
+pub async fn find_changed_saved_commands(
+    provider: &SQLiteProvider,
+    after_seq: i64,
+) -> DatabaseResult<Vec<SavedCommand>> {
+    find_saved_commands_where(
+        provider,
+        &changed_since_filter("saved_commands", after_seq)?,
+    )
+    .await
+}
+
+async fn find_saved_commands_where(
+    provider: &SQLiteProvider,
+    filter: &str,
 ) -> DatabaseResult<Vec<SavedCommand>> {
     let pool = provider.get_pool()?;
     let pool = pool.read().await;
 
-    let rows = sqlx::query(
-        "SELECT id, name, description, command, group_id, tags, is_favorite, usage_count, last_used_at, created_at, updated_at, device_id, version, sync_status FROM saved_commands ORDER BY name"
-    )
+    let rows = sqlx::query(&format!(
+        "SELECT id, name, description, command, group_id, tags, is_favorite, usage_count, last_used_at, created_at, updated_at, device_id, version, sync_status, deleted_at FROM saved_commands WHERE {} ORDER BY name",
+        filter
+    ))
     .fetch_all(&*pool)
     .await
     .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
@@ -78,6 +117,10 @@ pub async fn find_all_saved_commands(
                 version: row.get::<i64, _>("version") as u64,
                 sync_status: serde_json::from_str(&row.get::<String, _>("sync_status"))
                     .unwrap_or(crate::database::traits::SyncStatus::Synced),
+                deleted_at: row
+                    .get::<Option<String>, _>("deleted_at")
+                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                    .map(|dt| dt.with_timezone(&chrono::Utc)),
             },
             name: row.get("name"),
             description: row.get("description"),
@@ -102,7 +145,7 @@ pub async fn find_saved_command_by_id(
     let pool = pool.read().await;
 
     let row = sqlx::query(
-        "SELECT id, name, description, command, group_id, tags, is_favorite, usage_count, last_used_at, created_at, updated_at, device_id, version, sync_status FROM saved_commands WHERE id = ?"
+        "SELECT id, name, description, command, group_id, tags, is_favorite, usage_count, last_used_at, created_at, updated_at, device_id, version, sync_status, deleted_at FROM saved_commands WHERE id = ? AND deleted_at IS NULL"
     )
     .bind(id)
     .fetch_optional(&*pool)
@@ -127,6 +170,10 @@ pub async fn find_saved_command_by_id(
                 version: row.get::<i64, _>("version") as u64,
                 sync_status: serde_json::from_str(&row.get::<String, _>("sync_status"))
                     .unwrap_or(crate::database::traits::SyncStatus::Synced),
+                deleted_at: row
+                    .get::<Option<String>, _>("deleted_at")
+                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                    .map(|dt| dt.with_timezone(&chrono::Utc)),
             },
             name: row.get("name"),
             description: row.get("description"),
@@ -150,9 +197,9 @@ pub async fn find_saved_commands_by_group_id(
     let pool = pool.read().await;
 
     let (query, bind_value) = if let Some(group_id) = group_id {
-        ("SELECT id, name, description, command, group_id, tags, is_favorite, usage_count, last_used_at, created_at, updated_at, device_id, version, sync_status FROM saved_commands WHERE group_id = ? ORDER BY name", Some(group_id))
+        ("SELECT id, name, description, command, group_id, tags, is_favorite, usage_count, last_used_at, created_at, updated_at, device_id, version, sync_status, deleted_at FROM saved_commands WHERE group_id = ? AND deleted_at IS NULL ORDER BY name", Some(group_id))
     } else {
-        ("SELECT id, name, description, command, group_id, tags, is_favorite, usage_count, last_used_at, created_at, updated_at, device_id, version, sync_status FROM saved_commands WHERE group_id IS NULL ORDER BY name", None)
+        ("SELECT id, name, description, command, group_id, tags, is_favorite, usage_count, last_used_at, created_at, updated_at, device_id, version, sync_status, deleted_at FROM saved_commands WHERE group_id IS NULL AND deleted_at IS NULL ORDER BY name", None)
     };
 
     let mut query_builder = sqlx::query(query);
@@ -184,6 +231,10 @@ pub async fn find_saved_commands_by_group_id(
                 version: row.get::<i64, _>("version") as u64,
                 sync_status: serde_json::from_str(&row.get::<String, _>("sync_status"))
                     .unwrap_or(crate::database::traits::SyncStatus::Synced),
+                deleted_at: row
+                    .get::<Option<String>, _>("deleted_at")
+                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                    .map(|dt| dt.with_timezone(&chrono::Utc)),
             },
             name: row.get("name"),
             description: row.get("description"),
@@ -201,16 +252,7 @@ pub async fn find_saved_commands_by_group_id(
 }
 
 pub async fn delete_saved_command(provider: &SQLiteProvider, id: &str) -> DatabaseResult<()> {
-    let pool = provider.get_pool()?;
-    let pool = pool.read().await;
-
-    sqlx::query("DELETE FROM saved_commands WHERE id = ?")
-        .bind(id)
-        .execute(&*pool)
-        .await
-        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
-
-    Ok(())
+    provider.mark_deleted("saved_commands", id).await
 }
 
 pub async fn save_saved_command_group(
@@ -220,11 +262,22 @@ pub async fn save_saved_command_group(
     let pool = provider.get_pool()?;
     let pool = pool.read().await;
 
+    write_saved_command_group(&*pool, group).await
+}
+
+pub(super) async fn write_saved_command_group<'e, E>(
+    executor: E,
+    group: &SavedCommandGroup,
+) -> DatabaseResult<()>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
     sqlx::query(
         r#"
         INSERT OR REPLACE INTO saved_command_groups (
-            id, name, description, color, icon, created_at, updated_at, device_id, version, sync_status
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            id, name, description, color, icon, created_at, updated_at, device_id, version, sync_status,
+            deleted_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
     )
     .bind(&group.base.id)
@@ -240,7 +293,8 @@ pub async fn save_saved_command_group(
         serde_json::to_string(&group.base.sync_status)
             .unwrap_or_else(|_| "\"synced\"".to_string()),
     )
-    .execute(&*pool)
+    .bind(group.base.deleted_at.map(|dt| dt.to_rfc3339()))
+    .execute(executor)
     .await
     .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
 
@@ -249,13 +303,40 @@ pub async fn save_saved_command_group(
 
 pub async fn find_all_saved_command_groups(
     provider: &SQLiteProvider,
+) -> DatabaseResult<Vec<SavedCommandGroup>> {
+    find_saved_command_groups_where(provider, "deleted_at IS NULL").await
+}
+
+/// Tombstones left by deleted command groups, used to sync deletions
+pub async fn find_deleted_saved_command_groups(
+    provider: &SQLiteProvider,
+) -> DatabaseResult<Vec<SavedCommandGroup>> {
+    find_saved_command_groups_where(provider, "deleted_at IS NOT NULL").await
+}
+
+/// Command groups written after a change log position, tombstones included
+pub async fn find_changed_saved_command_groups(
+    provider: &SQLiteProvider,
+    after_seq: i64,
+) -> DatabaseResult<Vec<SavedCommandGroup>> {
+    find_saved_command_groups_where(
+        provider,
+        &changed_since_filter("saved_command_groups", after_seq)?,
+    )
+    .await
+}
+
+async fn find_saved_command_groups_where(
+    provider: &SQLiteProvider,
+    filter: &str,
 ) -> DatabaseResult<Vec<SavedCommandGroup>> {
     let pool = provider.get_pool()?;
     let pool = pool.read().await;
 
-    let rows = sqlx::query(
-        "SELECT id, name, description, color, icon, created_at, updated_at, device_id, version, sync_status FROM saved_command_groups ORDER BY name"
-    )
+    let rows = sqlx::query(&format!(
+        "SELECT id, name, description, color, icon, created_at, updated_at, device_id, version, sync_status, deleted_at FROM saved_command_groups WHERE {} ORDER BY name",
+        filter
+    ))
     .fetch_all(&*pool)
     .await
     .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
@@ -279,6 +360,10 @@ pub async fn find_all_saved_command_groups(
                 version: row.get::<i64, _>("version") as u64,
                 sync_status: serde_json::from_str(&row.get::<String, _>("sync_status"))
                     .unwrap_or(crate::database::traits::SyncStatus::Synced),
+                deleted_at: row
+                    .get::<Option<String>, _>("deleted_at")
+                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                    .map(|dt| dt.with_timezone(&chrono::Utc)),
             },
             name: row.get("name"),
             description: row.get("description"),
@@ -299,7 +384,7 @@ pub async fn find_saved_command_group_by_id(
     let pool = pool.read().await;
 
     let row = sqlx::query(
-        "SELECT id, name, description, color, icon, created_at, updated_at, device_id, version, sync_status FROM saved_command_groups WHERE id = ?"
+        "SELECT id, name, description, color, icon, created_at, updated_at, device_id, version, sync_status, deleted_at FROM saved_command_groups WHERE id = ? AND deleted_at IS NULL"
     )
     .bind(id)
     .fetch_optional(&*pool)
@@ -324,6 +409,10 @@ pub async fn find_saved_command_group_by_id(
                 version: row.get::<i64, _>("version") as u64,
                 sync_status: serde_json::from_str(&row.get::<String, _>("sync_status"))
                     .unwrap_or(crate::database::traits::SyncStatus::Synced),
+                deleted_at: row
+                    .get::<Option<String>, _>("deleted_at")
+                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                    .map(|dt| dt.with_timezone(&chrono::Utc)),
             },
             name: row.get("name"),
             description: row.get("description"),
@@ -336,16 +425,7 @@ pub async fn find_saved_command_group_by_id(
 }
 
 pub async fn delete_saved_command_group(provider: &SQLiteProvider, id: &str) -> DatabaseResult<()> {
-    let pool = provider.get_pool()?;
-    let pool = pool.read().await;
-
-    sqlx::query("DELETE FROM saved_command_groups WHERE id = ?")
-        .bind(id)
-        .execute(&*pool)
-        .await
-        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
-
-    Ok(())
+    provider.mark_deleted("saved_command_groups", id).await
 }
 
 impl SQLiteProvider {
@@ -357,6 +437,17 @@ impl SQLiteProvider {
         find_all_saved_commands(self).await
     }
 
+    pub async fn find_deleted_saved_commands(&self) -> DatabaseResult<Vec<SavedCommand>> {
+        find_deleted_saved_commands(self).await
+    }
+
+    pub async fn find_changed_saved_commands(
+        &self,
+        after_seq: i64,
+    ) -> DatabaseResult<Vec<SavedCommand>> {
+        find_changed_saved_commands(self, after_seq).await
+    }
+
     pub async fn find_saved_command_by_id(&self, id: &str) -> DatabaseResult<Option<SavedCommand>> {
         find_saved_command_by_id(self, id).await
     }
@@ -380,6 +471,19 @@ impl SQLiteProvider {
         find_all_saved_command_groups(self).await
     }
 
+    pub async fn find_deleted_saved_command_groups(
+        &self,
+    ) -> DatabaseResult<Vec<SavedCommandGroup>> {
+        find_deleted_saved_command_groups(self).await
+    }
+
+    pub async fn find_changed_saved_command_groups(
+        &self,
+        after_seq: i64,
+    ) -> DatabaseResult<Vec<SavedCommandGroup>> {
+        find_changed_saved_command_groups(self, after_seq).await
+    }
+
     pub async fn find_saved_command_group_by_id(
         &self,
         id: &str,
@@ -0,0 +1,105 @@
+use sqlx::Row;
+
+use crate::{
+    database::error::{DatabaseError, DatabaseResult},
+    models::ssh::KnownHost,
+};
+
+use super::{sync_ops::changed_since_filter, SQLiteProvider};
+
+fn map_known_host_row(row: &sqlx::sqlite::SqliteRow) -> DatabaseResult<KnownHost> {
+    Ok(KnownHost {
+        base: crate::models::base::BaseModel {
+            id: row.get("id"),
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))
+                .map_err(|e| DatabaseError::ParseError(format!("Parse error: {}", e)))?
+                .with_timezone(&chrono::Utc),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))
+                .map_err(|e| DatabaseError::ParseError(format!("Parse error: {}", e)))?
+                .with_timezone(&chrono::Utc),
+            device_id: row.get("device_id"),
+            version: row.get::<i64, _>("version") as u64,
+            sync_status: serde_json::from_str(&row.get::<String, _>("sync_status"))
+                .unwrap_or(crate::database::traits::SyncStatus::Synced),
+            deleted_at: row
+                .get::<Option<String>, _>("deleted_at")
+                .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                .map(|dt| dt.with_timezone(&chrono::Utc)),
+        },
+        marker: row.get("marker"),
+        hosts: row.get("hosts"),
+        key_type: row.get("key_type"),
+        public_key: row.get("public_key"),
+    })
+}
+
+async fn find_known_hosts_where(
+    provider: &SQLiteProvider,
+    filter: &str,
+) -> DatabaseResult<Vec<KnownHost>> {
+    let pool = provider.get_pool()?;
+    let pool = pool.read().await;
+
+    let rows = sqlx::query(&format!(
+        "SELECT * FROM known_hosts WHERE {} ORDER BY created_at",
+        filter
+    ))
+    .fetch_all(&*pool)
+    .await
+    .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    rows.iter().map(map_known_host_row).collect()
+}
+
+pub(super) async fn write_known_host<'e, E>(executor: E, host: &KnownHost) -> DatabaseResult<()>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    sqlx::query(
+        r#"
+        INSERT OR REPLACE INTO known_hosts (
+            id, marker, hosts, key_type, public_key, created_at, updated_at,
+            device_id, version, sync_status, deleted_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&host.base.id)
+    .bind(&host.marker)
+    .bind(&host.hosts)
+    .bind(&host.key_type)
+    .bind(&host.public_key)
+    .bind(host.base.created_at.to_rfc3339())
+    .bind(host.base.updated_at.to_rfc3339())
+    .bind(&host.base.device_id)
+    .bind(host.base.version as i64)
+    .bind(serde_json::to_string(&host.base.sync_status).unwrap())
+    .bind(host.base.deleted_at.map(|dt| dt.to_rfc3339()))
+    .execute(executor)
+    .await
+    .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    Ok(())
+}
+
+impl SQLiteProvider {
+    pub async fn save_known_host(&self, host: &KnownHost) -> DatabaseResult<()> {
+        let pool = self.get_pool()?;
+        let pool = pool.read().await;
+
+        write_known_host(&*pool, host).await
+    }
+
+    pub async fn find_all_known_hosts(&self) -> DatabaseResult<Vec<KnownHost>> {
+        find_known_hosts_where(self, "deleted_at IS NULL").await
+    }
+
+    /// Tombstones left by host keys removed from known_hosts, used to sync deletions
+    pub async fn find_deleted_known_hosts(&self) -> DatabaseResult<Vec<KnownHost>> {
+        find_known_hosts_where(self, "deleted_at IS NOT NULL").await
+    }
+
+    /// Host keys written after a change log position, tombstones included
+    pub async fn find_changed_known_hosts(&self, after_seq: i64) -> DatabaseResult<Vec<KnownHost>> {
+        find_known_hosts_where(self, &changed_since_filter("known_hosts", after_seq)?).await
+    }
+}
@@ -1,5 +1,8 @@
 mod auth;
+mod certificate_authority;
 mod command;
+mod known_host;
+mod recording;
 mod ssh;
 pub mod sync_ops;
 mod terminal;
@@ -88,7 +91,8 @@ impl Database for SQLiteProvider {
                 updated_at TEXT NOT NULL,
                 device_id TEXT NOT NULL,
                 version INTEGER NOT NULL DEFAULT 1,
-                sync_status TEXT NOT NULL DEFAULT 'Clean'
+                sync_status TEXT NOT NULL DEFAULT 'Clean',
+                deleted_at TEXT
             )
         "#,
         )
@@ -107,7 +111,8 @@ impl Database for SQLiteProvider {
                 updated_at TEXT NOT NULL,
                 device_id TEXT NOT NULL,
                 version INTEGER NOT NULL DEFAULT 1,
-                sync_status TEXT NOT NULL DEFAULT 'Clean'
+                sync_status TEXT NOT NULL DEFAULT 'Clean',
+                deleted_at TEXT
             )
         "#,
         )
@@ -127,11 +132,13 @@ impl Database for SQLiteProvider {
                 fingerprint TEXT NOT NULL,
                 description TEXT,
                 last_used TEXT,
+                hardware TEXT,
                 created_at TEXT NOT NULL,
                 updated_at TEXT NOT NULL,
                 device_id TEXT NOT NULL,
                 version INTEGER NOT NULL DEFAULT 1,
-                sync_status TEXT NOT NULL DEFAULT 'Clean'
+                sync_status TEXT NOT NULL DEFAULT 'Clean',
+                deleted_at TEXT
             )
         "#,
         )
@@ -149,6 +156,33 @@ impl Database for SQLiteProvider {
         .await
         .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
 
+        // Local-only: certificate authorities are never synced, so no sync triggers
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS ssh_certificate_authorities (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                key_type TEXT NOT NULL,
+                private_key TEXT NOT NULL,
+                public_key TEXT NOT NULL,
+                fingerprint TEXT NOT NULL,
+                default_principals TEXT NOT NULL DEFAULT '[]',
+                default_ttl_minutes INTEGER NOT NULL,
+                next_serial INTEGER NOT NULL DEFAULT 1,
+                description TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                device_id TEXT NOT NULL,
+                version INTEGER NOT NULL DEFAULT 1,
+                sync_status TEXT NOT NULL DEFAULT 'Clean',
+                deleted_at TEXT
+            )
+        "#,
+        )
+        .execute(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
         sqlx::query(
             r#"
             CREATE TABLE IF NOT EXISTS ssh_tunnels (
@@ -167,6 +201,7 @@ impl Database for SQLiteProvider {
                 device_id TEXT NOT NULL,
                 version INTEGER NOT NULL DEFAULT 1,
                 sync_status TEXT NOT NULL DEFAULT 'Clean',
+                deleted_at TEXT,
                 FOREIGN KEY (profile_id) REFERENCES ssh_profiles(id) ON DELETE CASCADE
             )
         "#,
@@ -237,6 +272,7 @@ impl Database for SQLiteProvider {
                 device_id TEXT NOT NULL,
                 version INTEGER NOT NULL DEFAULT 1,
                 sync_status TEXT NOT NULL DEFAULT 'synced',
+                deleted_at TEXT,
                 FOREIGN KEY (group_id) REFERENCES saved_command_groups(id) ON DELETE SET NULL
             )
         "#,
@@ -257,7 +293,8 @@ impl Database for SQLiteProvider {
                 updated_at TEXT NOT NULL,
                 device_id TEXT NOT NULL,
                 version INTEGER NOT NULL DEFAULT 1,
-                sync_status TEXT NOT NULL DEFAULT 'synced'
+                sync_status TEXT NOT NULL DEFAULT 'synced',
+                deleted_at TEXT
             )
         "#,
         )
@@ -295,6 +332,7 @@ impl Database for SQLiteProvider {
                 sync_direction TEXT NOT NULL DEFAULT 'Bidirectional',
                 selected_database_id TEXT,
                 last_sync_at TEXT,
+                synced_entities TEXT,
                 created_at TEXT NOT NULL,
                 updated_at TEXT NOT NULL
             )
@@ -353,6 +391,37 @@ impl Database for SQLiteProvider {
         .await
         .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
 
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS sync_encryption_keys (
+                database_id TEXT NOT NULL,
+                key_id TEXT NOT NULL,
+                encrypted_key TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (database_id, key_id)
+            )
+        "#,
+        )
+        .execute(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS sync_base_records (
+                table_name TEXT NOT NULL,
+                record_id TEXT NOT NULL,
+                data TEXT NOT NULL,
+                version INTEGER NOT NULL,
+                synced_at TEXT NOT NULL,
+                PRIMARY KEY (table_name, record_id)
+            )
+        "#,
+        )
+        .execute(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
         sqlx::query(
             r#"
             CREATE INDEX IF NOT EXISTS idx_conflict_resolutions_entity
@@ -378,7 +447,12 @@ impl Database for SQLiteProvider {
                 width INTEGER NOT NULL DEFAULT 80,
                 height INTEGER NOT NULL DEFAULT 24,
                 metadata TEXT,
-                created_at TEXT NOT NULL
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                device_id TEXT NOT NULL DEFAULT '',
+                version INTEGER NOT NULL DEFAULT 1,
+                sync_status TEXT NOT NULL DEFAULT '"Pending"',
+                deleted_at TEXT
             )
             "#,
         )
@@ -407,8 +481,33 @@ impl Database for SQLiteProvider {
                 icon TEXT,
                 color TEXT,
                 command TEXT,
-                created_at INTEGER NOT NULL,
-                updated_at INTEGER NOT NULL
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                device_id TEXT NOT NULL DEFAULT '',
+                version INTEGER NOT NULL DEFAULT 1,
+                sync_status TEXT NOT NULL DEFAULT '"Pending"',
+                deleted_at TEXT
+            )
+            "#,
+        )
+        .execute(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS known_hosts (
+                id TEXT PRIMARY KEY,
+                marker TEXT,
+                hosts TEXT NOT NULL,
+                key_type TEXT NOT NULL,
+                public_key TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                device_id TEXT NOT NULL,
+                version INTEGER NOT NULL DEFAULT 1,
+                sync_status TEXT NOT NULL DEFAULT '"Pending"',
+                deleted_at TEXT
             )
             "#,
         )
@@ -436,6 +535,135 @@ impl Database for SQLiteProvider {
             .await
             .ok();
 
+        sqlx::query("ALTER TABLE ssh_keys ADD COLUMN hardware TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+
+        sqlx::query("ALTER TABLE sync_settings ADD COLUMN synced_entities TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+
+        // Add three-way merge details to conflict resolutions (migration)
+        for column in ["base_data", "merged_data", "field_conflicts"] {
+            sqlx::query(&format!(
+                "ALTER TABLE conflict_resolutions ADD COLUMN {} TEXT",
+                column
+            ))
+            .execute(&*pool)
+            .await
+            .ok();
+        }
+
+        // Add sync metadata to terminal profiles and recordings (migration)
+        for table in ["terminal_profiles", "session_recordings"] {
+            sqlx::query(&format!(
+                "ALTER TABLE {} ADD COLUMN device_id TEXT NOT NULL DEFAULT ''",
+                table
+            ))
+            .execute(&*pool)
+            .await
+            .ok();
+            sqlx::query(&format!(
+                "ALTER TABLE {} ADD COLUMN version INTEGER NOT NULL DEFAULT 1",
+                table
+            ))
+            .execute(&*pool)
+            .await
+            .ok();
+            sqlx::query(&format!(
+                "ALTER TABLE {} ADD COLUMN sync_status TEXT NOT NULL DEFAULT '\"Pending\"'",
+                table
+            ))
+            .execute(&*pool)
+            .await
+            .ok();
+        }
+        sqlx::query("ALTER TABLE session_recordings ADD COLUMN updated_at TEXT")
+            .execute(&*pool)
+            .await
+            .ok();
+        sqlx::query(
+            "UPDATE session_recordings SET updated_at = created_at WHERE updated_at IS NULL",
+        )
+        .execute(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        // Terminal profiles used to store millisecond timestamps
+        sqlx::query(
+            r#"
+            UPDATE terminal_profiles SET
+                created_at = strftime('%Y-%m-%dT%H:%M:%fZ', created_at / 1000.0, 'unixepoch'),
+                updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', updated_at / 1000.0, 'unixepoch')
+            WHERE typeof(created_at) = 'integer'
+            "#,
+        )
+        .execute(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        // Add tombstone column to synced tables (migration)
+        for table in crate::database::traits_sync::TOMBSTONE_TABLES {
+            sqlx::query(&format!("ALTER TABLE {} ADD COLUMN deleted_at TEXT", table))
+                .execute(&*pool)
+                .await
+                .ok();
+        }
+
+        // Every write to a synced table moves the record to the end of the change log, so a
+        // sync only looks at the records written since the position it reached last time
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS sync_change_log (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                table_name TEXT NOT NULL,
+                record_id TEXT NOT NULL,
+                UNIQUE (table_name, record_id)
+            )
+            "#,
+        )
+        .execute(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        for table in crate::database::traits_sync::TOMBSTONE_TABLES {
+            for event in ["INSERT", "UPDATE"] {
+                sqlx::query(&format!(
+                    r#"
+                    CREATE TRIGGER IF NOT EXISTS {table}_{event}_change_log
+                    AFTER {event} ON {table}
+                    BEGIN
+                        INSERT OR REPLACE INTO sync_change_log (table_name, record_id)
+                        VALUES ('{table}', NEW.id);
+                    END
+                    "#,
+                    table = table,
+                    event = event.to_lowercase(),
+                ))
+                .execute(&*pool)
+                .await
+                .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+            }
+        }
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS sync_cursors (
+                database_id TEXT NOT NULL,
+                table_name TEXT NOT NULL,
+                local_seq INTEGER NOT NULL,
+                remote_cursor TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (database_id, table_name)
+            )
+            "#,
+        )
+        .execute(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
         Ok(())
     }
 
@@ -724,10 +952,47 @@ impl SQLiteProvider {
         terminal::find_all_terminal_profiles(self).await
     }
 
+    pub async fn find_deleted_terminal_profiles(
+        &self,
+    ) -> DatabaseResult<Vec<crate::models::terminal::profile::TerminalProfile>> {
+        terminal::find_deleted_terminal_profiles(self).await
+    }
+
+    pub async fn find_changed_terminal_profiles(
+        &self,
+        after_seq: i64,
+    ) -> DatabaseResult<Vec<crate::models::terminal::profile::TerminalProfile>> {
+        terminal::find_changed_terminal_profiles(self, after_seq).await
+    }
+
     pub async fn delete_terminal_profile(&self, id: &str) -> DatabaseResult<()> {
         terminal::delete_terminal_profile(self, id).await
     }
 
+    pub async fn save_certificate_authority(
+        &self,
+        model: &crate::models::ssh::SSHCertificateAuthority,
+    ) -> DatabaseResult<()> {
+        certificate_authority::save_certificate_authority(self, model).await
+    }
+
+    pub async fn find_certificate_authority_by_id(
+        &self,
+        id: &str,
+    ) -> DatabaseResult<Option<crate::models::ssh::SSHCertificateAuthority>> {
+        certificate_authority::find_certificate_authority_by_id(self, id).await
+    }
+
+    pub async fn find_all_certificate_authorities(
+        &self,
+    ) -> DatabaseResult<Vec<crate::models::ssh::SSHCertificateAuthority>> {
+        certificate_authority::find_all_certificate_authorities(self).await
+    }
+
+    pub async fn delete_certificate_authority(&self, id: &str) -> DatabaseResult<()> {
+        certificate_authority::delete_certificate_authority(self, id).await
+    }
+
     pub async fn get_all_external_databases(
         &self,
     ) -> DatabaseResult<Vec<crate::models::sync::external_db::ExternalDatabaseConfig>> {
@@ -914,180 +1179,4 @@ impl SQLiteProvider {
 
         Ok(result.rows_affected())
     }
-
-    // Session recording operations
-    pub async fn save_session_recording(
-        &self,
-        recording: &crate::models::recording::SessionRecording,
-    ) -> DatabaseResult<()> {
-        let pool = self.get_pool()?;
-        let pool_guard = pool.read().await;
-
-        sqlx::query(
-            r#"
-            INSERT OR REPLACE INTO session_recordings (
-                id, terminal_id, session_name, terminal_type, started_at, ended_at,
-                duration_ms, file_path, file_size, width, height, metadata, created_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-            "#,
-        )
-        .bind(&recording.id)
-        .bind(&recording.terminal_id)
-        .bind(&recording.session_name)
-        .bind(&recording.terminal_type)
-        .bind(recording.started_at.to_rfc3339())
-        .bind(recording.ended_at.as_ref().map(|dt| dt.to_rfc3339()))
-        .bind(recording.duration_ms)
-        .bind(&recording.file_path)
-        .bind(recording.file_size)
-        .bind(recording.width as i32)
-        .bind(recording.height as i32)
-        .bind(&recording.metadata)
-        .bind(recording.created_at.to_rfc3339())
-        .execute(&*pool_guard)
-        .await
-        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
-
-        Ok(())
-    }
-
-    pub async fn get_session_recording(
-        &self,
-        id: &str,
-    ) -> DatabaseResult<Option<crate::models::recording::SessionRecording>> {
-        let pool = self.get_pool()?;
-        let pool_guard = pool.read().await;
-
-        let result = sqlx::query_as::<
-            _,
-            (
-                String,
-                String,
-                String,
-                String,
-                String,
-                Option<String>,
-                Option<i64>,
-                String,
-                i64,
-                i32,
-                i32,
-                Option<String>,
-                String,
-            ),
-        >(
-            r#"
-            SELECT id, terminal_id, session_name, terminal_type, started_at, ended_at,
-                   duration_ms, file_path, file_size, width, height, metadata, created_at
-            FROM session_recordings
-            WHERE id = ?
-            "#,
-        )
-        .bind(id)
-        .fetch_optional(&*pool_guard)
-        .await
-        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
-
-        Ok(
-            result.map(|row| crate::models::recording::SessionRecording {
-                id: row.0,
-                terminal_id: row.1,
-                session_name: row.2,
-                terminal_type: row.3,
-                started_at: chrono::DateTime::parse_from_rfc3339(&row.4)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-                ended_at: row.5.and_then(|s| {
-                    chrono::DateTime::parse_from_rfc3339(&s)
-                        .ok()
-                        .map(|dt| dt.with_timezone(&chrono::Utc))
-                }),
-                duration_ms: row.6,
-                file_path: row.7,
-                file_size: row.8,
-                width: row.9 as u16,
-                height: row.10 as u16,
-                metadata: row.11,
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.12)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-            }),
-        )
-    }
-
-    pub async fn list_session_recordings(
-        &self,
-    ) -> DatabaseResult<Vec<crate::models::recording::SessionRecording>> {
-        let pool = self.get_pool()?;
-        let pool_guard = pool.read().await;
-
-        let results = sqlx::query_as::<
-            _,
-            (
-                String,
-                String,
-                String,
-                String,
-                String,
-                Option<String>,
-                Option<i64>,
-                String,
-                i64,
-                i32,
-                i32,
-                Option<String>,
-                String,
-            ),
-        >(
-            r#"
-            SELECT id, terminal_id, session_name, terminal_type, started_at, ended_at,
-                   duration_ms, file_path, file_size, width, height, metadata, created_at
-            FROM session_recordings
-            ORDER BY started_at DESC
-            "#,
-        )
-        .fetch_all(&*pool_guard)
-        .await
-        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
-
-        Ok(results
-            .into_iter()
-            .map(|row| crate::models::recording::SessionRecording {
-                id: row.0,
-                terminal_id: row.1,
-                session_name: row.2,
-                terminal_type: row.3,
-                started_at: chrono::DateTime::parse_from_rfc3339(&row.4)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-                ended_at: row.5.and_then(|s| {
-                    chrono::DateTime::parse_from_rfc3339(&s)
-                        .ok()
-                        .map(|dt| dt.with_timezone(&chrono::Utc))
-                }),
-                duration_ms: row.6,
-                file_path: row.7,
-                file_size: row.8,
-                width: row.9 as u16,
-                height: row.10 as u16,
-                metadata: row.11,
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.12)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-            })
-            .collect())
-    }
-
-    pub async fn delete_session_recording(&self, id: &str) -> DatabaseResult<()> {
-        let pool = self.get_pool()?;
-        let pool_guard = pool.read().await;
-
-        sqlx::query("DELETE FROM session_recordings WHERE id = ?")
-            .bind(id)
-            .execute(&*pool_guard)
-            .await
-            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
-
-        Ok(())
-    }
 }
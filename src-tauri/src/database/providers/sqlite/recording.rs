@@ -0,0 +1,160 @@
+use sqlx::Row;
+
+use crate::{
+    database::error::{DatabaseError, DatabaseResult},
+    models::recording::SessionRecording,
+};
+
+use super::{sync_ops::changed_since_filter, SQLiteProvider};
+
+const RECORDING_COLUMNS: &str = "id, terminal_id, session_name, terminal_type, started_at, ended_at, duration_ms, file_path, file_size, width, height, metadata, created_at, updated_at, device_id, version, sync_status, deleted_at";
+
+fn map_session_recording_row(row: &sqlx::sqlite::SqliteRow) -> DatabaseResult<SessionRecording> {
+    let parse = |column: &str| -> DatabaseResult<chrono::DateTime<chrono::Utc>> {
+        chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>(column))
+            .map(|dt| dt.with_timezone(&chrono::Utc))
+            .map_err(|e| DatabaseError::ParseError(format!("Parse error: {}", e)))
+    };
+
+    Ok(SessionRecording {
+        base: crate::models::base::BaseModel {
+            id: row.get("id"),
+            created_at: parse("created_at")?,
+            updated_at: parse("updated_at")?,
+            device_id: row.get("device_id"),
+            version: row.get::<i64, _>("version") as u64,
+            sync_status: serde_json::from_str(&row.get::<String, _>("sync_status"))
+                .unwrap_or(crate::database::traits::SyncStatus::Synced),
+            deleted_at: row
+                .get::<Option<String>, _>("deleted_at")
+                .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                .map(|dt| dt.with_timezone(&chrono::Utc)),
+        },
+        terminal_id: row.get("terminal_id"),
+        session_name: row.get("session_name"),
+        terminal_type: row.get("terminal_type"),
+        started_at: parse("started_at")?,
+        ended_at: row
+            .get::<Option<String>, _>("ended_at")
+            .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&chrono::Utc)),
+        duration_ms: row.get("duration_ms"),
+        file_path: row.get("file_path"),
+        file_size: row.get("file_size"),
+        width: row.get::<i32, _>("width") as u16,
+        height: row.get::<i32, _>("height") as u16,
+        metadata: row.get("metadata"),
+    })
+}
+
+async fn find_session_recordings_where(
+    provider: &SQLiteProvider,
+    filter: &str,
+) -> DatabaseResult<Vec<SessionRecording>> {
+    let pool = provider.get_pool()?;
+    let pool_guard = pool.read().await;
+
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM session_recordings WHERE {} ORDER BY started_at DESC",
+        RECORDING_COLUMNS, filter
+    ))
+    .fetch_all(&*pool_guard)
+    .await
+    .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    rows.iter().map(map_session_recording_row).collect()
+}
+
+pub(super) async fn write_session_recording<'e, E>(
+    executor: E,
+    recording: &SessionRecording,
+) -> DatabaseResult<()>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    sqlx::query(&format!(
+        r#"
+        INSERT OR REPLACE INTO session_recordings ({})
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+        RECORDING_COLUMNS
+    ))
+    .bind(&recording.base.id)
+    .bind(&recording.terminal_id)
+    .bind(&recording.session_name)
+    .bind(&recording.terminal_type)
+    .bind(recording.started_at.to_rfc3339())
+    .bind(recording.ended_at.as_ref().map(|dt| dt.to_rfc3339()))
+    .bind(recording.duration_ms)
+    .bind(&recording.file_path)
+    .bind(recording.file_size)
+    .bind(recording.width as i32)
+    .bind(recording.height as i32)
+    .bind(&recording.metadata)
+    .bind(recording.base.created_at.to_rfc3339())
+    .bind(recording.base.updated_at.to_rfc3339())
+    .bind(&recording.base.device_id)
+    .bind(recording.base.version as i64)
+    .bind(serde_json::to_string(&recording.base.sync_status).unwrap())
+    .bind(recording.base.deleted_at.map(|dt| dt.to_rfc3339()))
+    .execute(executor)
+    .await
+    .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    Ok(())
+}
+
+// Session recording operations
+impl SQLiteProvider {
+    pub async fn save_session_recording(&self, recording: &SessionRecording) -> DatabaseResult<()> {
+        let pool = self.get_pool()?;
+        let pool_guard = pool.read().await;
+
+        write_session_recording(&*pool_guard, recording).await
+    }
+
+    pub async fn get_session_recording(
+        &self,
+        id: &str,
+    ) -> DatabaseResult<Option<SessionRecording>> {
+        let pool = self.get_pool()?;
+        let pool_guard = pool.read().await;
+
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM session_recordings WHERE id = ? AND deleted_at IS NULL",
+            RECORDING_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&*pool_guard)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        row.map(|row| map_session_recording_row(&row)).transpose()
+    }
+
+    pub async fn list_session_recordings(&self) -> DatabaseResult<Vec<SessionRecording>> {
+        find_session_recordings_where(self, "deleted_at IS NULL").await
+    }
+
+    /// Tombstones left by deleted recordings, used to sync deletions
+    pub async fn find_deleted_session_recordings(&self) -> DatabaseResult<Vec<SessionRecording>> {
+        find_session_recordings_where(self, "deleted_at IS NOT NULL").await
+    }
+
+    /// Recordings written after a change log position, tombstones included
+    pub async fn find_changed_session_recordings(
+        &self,
+        after_seq: i64,
+    ) -> DatabaseResult<Vec<SessionRecording>> {
+        find_session_recordings_where(
+            self,
+            &changed_since_filter("session_recordings", after_seq)?,
+        )
+        .await
+    }
+
+    /// Soft-delete a recording, leaving a tombstone for sync
+    pub async fn delete_session_recording(&self, id: &str) -> DatabaseResult<()> {
+        self.mark_deleted("session_recordings", id).await
+    }
+}
@@ -5,19 +5,27 @@ use crate::{
     models::ssh::{SSHGroup, SSHKey, SSHProfile},
 };
 
-use super::SQLiteProvider;
+use super::{sync_ops::changed_since_filter, SQLiteProvider};
 
 pub async fn save_ssh_profile(provider: &SQLiteProvider, model: &SSHProfile) -> DatabaseResult<()> {
     let pool_arc = provider.get_pool()?;
     let pool = pool_arc.read().await;
 
+    write_ssh_profile(&*pool, model).await
+}
+
+/// Write a profile on any executor, so conflict resolution can save it inside its transaction
+pub(super) async fn write_ssh_profile<'e, E>(executor: E, model: &SSHProfile) -> DatabaseResult<()>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
     sqlx::query(
         r#"
         INSERT INTO ssh_profiles (
             id, name, host, port, username, group_id, auth_method, auth_data,
             description, color, timeout, keep_alive, compression, command, working_dir, env, created_at, updated_at,
-            device_id, version, sync_status
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            device_id, version, sync_status, deleted_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         ON CONFLICT(id) DO UPDATE SET
             name = excluded.name,
             host = excluded.host,
@@ -37,7 +45,8 @@ pub async fn save_ssh_profile(provider: &SQLiteProvider, model: &SSHProfile) ->
             updated_at = excluded.updated_at,
             device_id = excluded.device_id,
             version = excluded.version,
-            sync_status = excluded.sync_status
+            sync_status = excluded.sync_status,
+            deleted_at = excluded.deleted_at
     "#,
     )
     .bind(&model.base.id)
@@ -56,12 +65,13 @@ pub async fn save_ssh_profile(provider: &SQLiteProvider, model: &SSHProfile) ->
     .bind(&model.command)
     .bind(&model.working_dir)
     .bind(serde_json::to_string(&model.env).unwrap_or_default())
-    .bind(model.base.created_at)
-    .bind(model.base.updated_at)
+    .bind(model.base.created_at.to_rfc3339())
+    .bind(model.base.updated_at.to_rfc3339())
     .bind(&model.base.device_id)
     .bind(model.base.version as i64)
     .bind(serde_json::to_string(&model.base.sync_status).unwrap_or_default())
-    .execute(&*pool)
+    .bind(model.base.deleted_at.map(|dt| dt.to_rfc3339()))
+    .execute(executor)
     .await
     .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
 
@@ -76,7 +86,7 @@ pub async fn find_ssh_profile_by_id(
     let pool = pool.read().await;
 
     let row = sqlx::query(
-        "SELECT id, name, host, port, username, group_id, auth_method, auth_data, description, color, timeout, keep_alive, compression, command, working_dir, env, created_at, updated_at, device_id, version, sync_status FROM ssh_profiles WHERE id = ?"
+        "SELECT id, name, host, port, username, group_id, auth_method, auth_data, description, color, timeout, keep_alive, compression, command, working_dir, env, created_at, updated_at, device_id, version, sync_status, deleted_at FROM ssh_profiles WHERE id = ? AND deleted_at IS NULL"
     )
     .bind(id)
     .fetch_optional(&*pool)
@@ -101,6 +111,10 @@ pub async fn find_ssh_profile_by_id(
                 version: row.get::<i64, _>("version") as u64,
                 sync_status: serde_json::from_str(&row.get::<String, _>("sync_status"))
                     .unwrap_or(crate::database::traits::SyncStatus::Synced),
+                deleted_at: row
+                    .get::<Option<String>, _>("deleted_at")
+                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                    .map(|dt| dt.with_timezone(&chrono::Utc)),
             },
             name: row.get("name"),
             host: row.get("host"),
@@ -128,12 +142,35 @@ pub async fn find_ssh_profile_by_id(
 }
 
 pub async fn find_all_ssh_profiles(provider: &SQLiteProvider) -> DatabaseResult<Vec<SSHProfile>> {
+    find_ssh_profiles_where(provider, "deleted_at IS NULL").await
+}
+
+/// Tombstones left by deleted profiles, used to sync deletions
+pub async fn find_deleted_ssh_profiles(
+    provider: &SQLiteProvider,
+) -> DatabaseResult<Vec<SSHProfile>> {
+    find_ssh_profiles_where(provider, "deleted_at IS NOT NULL").await
+}
+
+/// Profiles written after a change log position, tombstones included
+pub async fn find_changed_ssh_profiles(
+    provider: &SQLiteProvider,
+    after_seq: i64,
+) -> DatabaseResult<Vec<SSHProfile>> {
+    find_ssh_profiles_where(provider, &changed_since_filter("ssh_profiles", after_seq)?).await
+}
+
+async fn find_ssh_profiles_where(
+    provider: &SQLiteProvider,
+    filter: &str,
+) -> DatabaseResult<Vec<SSHProfile>> {
     let pool = provider.get_pool()?;
     let pool = pool.read().await;
 
-    let rows = sqlx::query(
-        "SELECT id, name, host, port, username, group_id, auth_method, auth_data, description, color, timeout, keep_alive, compression, command, working_dir, env, created_at, updated_at, device_id, version, sync_status FROM ssh_profiles ORDER BY name"
-    )
+    let rows = sqlx::query(&format!(
+        "SELECT id, name, host, port, username, group_id, auth_method, auth_data, description, color, timeout, keep_alive, compression, command, working_dir, env, created_at, updated_at, device_id, version, sync_status, deleted_at FROM ssh_profiles WHERE {} ORDER BY name",
+        filter
+    ))
     .fetch_all(&*pool)
     .await
     .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
@@ -157,6 +194,10 @@ pub async fn find_all_ssh_profiles(provider: &SQLiteProvider) -> DatabaseResult<
                 version: row.get::<i64, _>("version") as u64,
                 sync_status: serde_json::from_str(&row.get::<String, _>("sync_status"))
                     .unwrap_or(crate::database::traits::SyncStatus::Synced),
+                deleted_at: row
+                    .get::<Option<String>, _>("deleted_at")
+                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                    .map(|dt| dt.with_timezone(&chrono::Utc)),
             },
             name: row.get("name"),
             host: row.get("host"),
@@ -190,41 +231,44 @@ pub async fn update_ssh_profile(
     save_ssh_profile(provider, model).await
 }
 
+/// Soft-delete a profile, leaving a tombstone for sync
 pub async fn delete_ssh_profile(provider: &SQLiteProvider, id: &str) -> DatabaseResult<()> {
-    let pool_arc = provider.get_pool()?;
-    let pool = pool_arc.read().await;
-
-    sqlx::query("DELETE FROM ssh_profiles WHERE id = ?")
-        .bind(id)
-        .execute(&*pool)
-        .await
-        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+    provider.mark_deleted("ssh_profiles", id).await?;
 
-    Ok(())
+    // Tombstones keep their rows, so the ON DELETE CASCADE on tunnels never fires
+    super::tunnel::delete_ssh_tunnels_by_profile_id(provider, id).await
 }
 
 pub async fn save_ssh_group(provider: &SQLiteProvider, model: &SSHGroup) -> DatabaseResult<()> {
     let pool_arc = provider.get_pool()?;
     let pool = pool_arc.read().await;
 
+    write_ssh_group(&*pool, model).await
+}
+
+pub(super) async fn write_ssh_group<'e, E>(executor: E, model: &SSHGroup) -> DatabaseResult<()>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
     sqlx::query(
         r#"
         INSERT OR REPLACE INTO ssh_groups (
             id, name, description, color,
-            created_at, updated_at, device_id, version, sync_status
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            created_at, updated_at, device_id, version, sync_status, deleted_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
     "#,
     )
     .bind(&model.base.id)
     .bind(&model.name)
     .bind(&model.description)
     .bind(&model.color)
-    .bind(model.base.created_at)
-    .bind(model.base.updated_at)
+    .bind(model.base.created_at.to_rfc3339())
+    .bind(model.base.updated_at.to_rfc3339())
     .bind(&model.base.device_id)
     .bind(model.base.version as i64)
     .bind(serde_json::to_string(&model.base.sync_status).unwrap_or_default())
-    .execute(&*pool)
+    .bind(model.base.deleted_at.map(|dt| dt.to_rfc3339()))
+    .execute(executor)
     .await
     .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
 
@@ -239,7 +283,7 @@ pub async fn find_ssh_group_by_id(
     let pool = pool.read().await;
 
     let row = sqlx::query(
-        "SELECT id, name, description, color, created_at, updated_at, device_id, version, sync_status FROM ssh_groups WHERE id = ?"
+        "SELECT id, name, description, color, created_at, updated_at, device_id, version, sync_status, deleted_at FROM ssh_groups WHERE id = ? AND deleted_at IS NULL"
     )
     .bind(id)
     .fetch_optional(&*pool)
@@ -264,6 +308,10 @@ pub async fn find_ssh_group_by_id(
                 version: row.get::<i64, _>("version") as u64,
                 sync_status: serde_json::from_str(&row.get::<String, _>("sync_status"))
                     .unwrap_or(crate::database::traits::SyncStatus::Synced),
+                deleted_at: row
+                    .get::<Option<String>, _>("deleted_at")
+                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                    .map(|dt| dt.with_timezone(&chrono::Utc)),
             },
             name: row.get("name"),
             description: row.get("description"),
@@ -276,12 +324,33 @@ pub async fn find_ssh_group_by_id(
 }
 
 pub async fn find_all_ssh_groups(provider: &SQLiteProvider) -> DatabaseResult<Vec<SSHGroup>> {
+    find_ssh_groups_where(provider, "deleted_at IS NULL").await
+}
+
+/// Tombstones left by deleted groups, used to sync deletions
+pub async fn find_deleted_ssh_groups(provider: &SQLiteProvider) -> DatabaseResult<Vec<SSHGroup>> {
+    find_ssh_groups_where(provider, "deleted_at IS NOT NULL").await
+}
+
+/// Groups written after a change log position, tombstones included
+pub async fn find_changed_ssh_groups(
+    provider: &SQLiteProvider,
+    after_seq: i64,
+) -> DatabaseResult<Vec<SSHGroup>> {
+    find_ssh_groups_where(provider, &changed_since_filter("ssh_groups", after_seq)?).await
+}
+
+async fn find_ssh_groups_where(
+    provider: &SQLiteProvider,
+    filter: &str,
+) -> DatabaseResult<Vec<SSHGroup>> {
     let pool = provider.get_pool()?;
     let pool = pool.read().await;
 
-    let rows = sqlx::query(
-        "SELECT id, name, description, color, created_at, updated_at, device_id, version, sync_status FROM ssh_groups ORDER BY name"
-    )
+    let rows = sqlx::query(&format!(
+        "SELECT id, name, description, color, created_at, updated_at, device_id, version, sync_status, deleted_at FROM ssh_groups WHERE {} ORDER BY name",
+        filter
+    ))
     .fetch_all(&*pool)
     .await
     .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
@@ -305,6 +374,10 @@ pub async fn find_all_ssh_groups(provider: &SQLiteProvider) -> DatabaseResult<Ve
                 version: row.get::<i64, _>("version") as u64,
                 sync_status: serde_json::from_str(&row.get::<String, _>("sync_status"))
                     .unwrap_or(crate::database::traits::SyncStatus::Synced),
+                deleted_at: row
+                    .get::<Option<String>, _>("deleted_at")
+                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                    .map(|dt| dt.with_timezone(&chrono::Utc)),
             },
             name: row.get("name"),
             description: row.get("description"),
@@ -317,29 +390,27 @@ pub async fn find_all_ssh_groups(provider: &SQLiteProvider) -> DatabaseResult<Ve
 }
 
 pub async fn delete_ssh_group(provider: &SQLiteProvider, id: &str) -> DatabaseResult<()> {
-    let pool_arc = provider.get_pool()?;
-    let pool = pool_arc.read().await;
-
-    sqlx::query("DELETE FROM ssh_groups WHERE id = ?")
-        .bind(id)
-        .execute(&*pool)
-        .await
-        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
-
-    Ok(())
+    provider.mark_deleted("ssh_groups", id).await
 }
 
 pub async fn save_ssh_key(provider: &SQLiteProvider, model: &SSHKey) -> DatabaseResult<()> {
     let pool_arc = provider.get_pool()?;
     let pool = pool_arc.read().await;
 
+    write_ssh_key(&*pool, model).await
+}
+
+pub(super) async fn write_ssh_key<'e, E>(executor: E, model: &SSHKey) -> DatabaseResult<()>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
     sqlx::query(
         r#"
         INSERT OR REPLACE INTO ssh_keys (
             id, name, key_type, private_key, public_key, passphrase,
-            fingerprint, description, last_used, created_at, updated_at,
-            device_id, version, sync_status
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            fingerprint, description, last_used, hardware, created_at, updated_at,
+            device_id, version, sync_status, deleted_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
     "#,
     )
     .bind(&model.base.id)
@@ -351,12 +422,19 @@ pub async fn save_ssh_key(provider: &SQLiteProvider, model: &SSHKey) -> Database
     .bind(&model.fingerprint)
     .bind(&model.description)
     .bind(model.last_used.map(|dt| dt.to_rfc3339()))
+    .bind(
+        model
+            .hardware
+            .as_ref()
+            .map(|hardware| serde_json::to_string(hardware).unwrap()),
+    )
     .bind(model.base.created_at.to_rfc3339())
     .bind(model.base.updated_at.to_rfc3339())
     .bind(&model.base.device_id)
     .bind(model.base.version as i64)
     .bind(serde_json::to_string(&model.base.sync_status).unwrap())
-    .execute(&*pool)
+    .bind(model.base.deleted_at.map(|dt| dt.to_rfc3339()))
+    .execute(executor)
     .await
     .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
 
@@ -371,7 +449,7 @@ pub async fn find_ssh_key_by_id(
     let pool = pool.read().await;
 
     let result = sqlx::query(
-        "SELECT id, name, key_type, private_key, public_key, passphrase, fingerprint, description, last_used, created_at, updated_at, device_id, version, sync_status FROM ssh_keys WHERE id = ?"
+        "SELECT id, name, key_type, private_key, public_key, passphrase, fingerprint, description, last_used, hardware, created_at, updated_at, device_id, version, sync_status, deleted_at FROM ssh_keys WHERE id = ? AND deleted_at IS NULL"
     )
     .bind(id)
     .fetch_optional(&*pool)
@@ -396,6 +474,10 @@ pub async fn find_ssh_key_by_id(
                 version: row.get::<i64, _>("version") as u64,
                 sync_status: serde_json::from_str(&row.get::<String, _>("sync_status"))
                     .unwrap_or(crate::database::traits::SyncStatus::Synced),
+                deleted_at: row
+                    .get::<Option<String>, _>("deleted_at")
+                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                    .map(|dt| dt.with_timezone(&chrono::Utc)),
             },
             name: row.get("name"),
             key_type: serde_json::from_str(&row.get::<String, _>("key_type"))
@@ -404,6 +486,9 @@ pub async fn find_ssh_key_by_id(
             public_key: row.get("public_key"),
             passphrase: row.get("passphrase"),
             fingerprint: row.get("fingerprint"),
+            hardware: row
+                .get::<Option<String>, _>("hardware")
+                .and_then(|s| serde_json::from_str(&s).ok()),
             description: row.get("description"),
             last_used: row
                 .get::<Option<String>, _>("last_used")
@@ -417,12 +502,33 @@ pub async fn find_ssh_key_by_id(
 }
 
 pub async fn find_all_ssh_keys(provider: &SQLiteProvider) -> DatabaseResult<Vec<SSHKey>> {
+    find_ssh_keys_where(provider, "deleted_at IS NULL").await
+}
+
+/// Tombstones left by deleted keys, used to sync deletions
+pub async fn find_deleted_ssh_keys(provider: &SQLiteProvider) -> DatabaseResult<Vec<SSHKey>> {
+    find_ssh_keys_where(provider, "deleted_at IS NOT NULL").await
+}
+
+/// Keys written after a change log position, tombstones included
+pub async fn find_changed_ssh_keys(
+    provider: &SQLiteProvider,
+    after_seq: i64,
+) -> DatabaseResult<Vec<SSHKey>> {
+    find_ssh_keys_where(provider, &changed_since_filter("ssh_keys", after_seq)?).await
+}
+
+async fn find_ssh_keys_where(
+    provider: &SQLiteProvider,
+    filter: &str,
+) -> DatabaseResult<Vec<SSHKey>> {
     let pool = provider.get_pool()?;
     let pool = pool.read().await;
 
-    let rows = sqlx::query(
-        "SELECT id, name, key_type, private_key, public_key, passphrase, fingerprint, description, last_used, created_at, updated_at, device_id, version, sync_status FROM ssh_keys ORDER BY name"
-    )
+    let rows = sqlx::query(&format!(
+        "SELECT id, name, key_type, private_key, public_key, passphrase, fingerprint, description, last_used, hardware, created_at, updated_at, device_id, version, sync_status, deleted_at FROM ssh_keys WHERE {} ORDER BY name",
+        filter
+    ))
     .fetch_all(&*pool)
     .await
     .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
@@ -446,6 +552,10 @@ pub async fn find_all_ssh_keys(provider: &SQLiteProvider) -> DatabaseResult<Vec<
                 version: row.get::<i64, _>("version") as u64,
                 sync_status: serde_json::from_str(&row.get::<String, _>("sync_status"))
                     .unwrap_or(crate::database::traits::SyncStatus::Synced),
+                deleted_at: row
+                    .get::<Option<String>, _>("deleted_at")
+                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                    .map(|dt| dt.with_timezone(&chrono::Utc)),
             },
             name: row.get("name"),
             key_type: serde_json::from_str(&row.get::<String, _>("key_type"))
@@ -454,6 +564,9 @@ pub async fn find_all_ssh_keys(provider: &SQLiteProvider) -> DatabaseResult<Vec<
             public_key: row.get("public_key"),
             passphrase: row.get("passphrase"),
             fingerprint: row.get("fingerprint"),
+            hardware: row
+                .get::<Option<String>, _>("hardware")
+                .and_then(|s| serde_json::from_str(&s).ok()),
             description: row.get("description"),
             last_used: row
                 .get::<Option<String>, _>("last_used")
@@ -467,16 +580,7 @@ pub async fn find_all_ssh_keys(provider: &SQLiteProvider) -> DatabaseResult<Vec<
 }
 
 pub async fn delete_ssh_key(provider: &SQLiteProvider, id: &str) -> DatabaseResult<()> {
-    let pool_arc = provider.get_pool()?;
-    let pool = pool_arc.read().await;
-
-    sqlx::query("DELETE FROM ssh_keys WHERE id = ?")
-        .bind(id)
-        .execute(&*pool)
-        .await
-        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
-
-    Ok(())
+    provider.mark_deleted("ssh_keys", id).await
 }
 
 pub async fn count_profiles_using_key(
@@ -489,7 +593,7 @@ pub async fn count_profiles_using_key(
     let count = sqlx::query_scalar::<_, i64>(
         r#"
         SELECT COUNT(*) FROM ssh_profiles
-        WHERE auth_data LIKE '%"key_id":"' || ? || '"%'
+        WHERE deleted_at IS NULL AND auth_data LIKE '%"key_id":"' || ? || '"%'
     "#,
     )
     .bind(key_id)
@@ -499,3 +603,32 @@ pub async fn count_profiles_using_key(
 
     Ok(count as u32)
 }
+
+impl SQLiteProvider {
+    pub async fn find_deleted_ssh_profiles(&self) -> DatabaseResult<Vec<SSHProfile>> {
+        find_deleted_ssh_profiles(self).await
+    }
+
+    pub async fn find_deleted_ssh_groups(&self) -> DatabaseResult<Vec<SSHGroup>> {
+        find_deleted_ssh_groups(self).await
+    }
+
+    pub async fn find_deleted_ssh_keys(&self) -> DatabaseResult<Vec<SSHKey>> {
+        find_deleted_ssh_keys(self).await
+    }
+
+    pub async fn find_changed_ssh_profiles(
+        &self,
+        after_seq: i64,
+    ) -> DatabaseResult<Vec<SSHProfile>> {
+        find_changed_ssh_profiles(self, after_seq).await
+    }
+
+    pub async fn find_changed_ssh_groups(&self, after_seq: i64) -> DatabaseResult<Vec<SSHGroup>> {
+        find_changed_ssh_groups(self, after_seq).await
+    }
+
+    pub async fn find_changed_ssh_keys(&self, after_seq: i64) -> DatabaseResult<Vec<SSHKey>> {
+        find_changed_ssh_keys(self, after_seq).await
+    }
+}
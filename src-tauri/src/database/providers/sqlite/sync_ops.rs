@@ -1,14 +1,232 @@
 use chrono::{DateTime, Utc};
 use sqlx::Row;
+use std::collections::HashMap;
 
 use crate::{
     database::error::{DatabaseError, DatabaseResult},
     database::traits::SyncStatus,
-    models::sync::{ConflictResolutionStrategy, ExternalDatabaseConfig},
+    database::traits_sync::TOMBSTONE_TABLES,
+    models::sync::{
+        ConflictResolutionStrategy, ExternalDatabaseConfig, SyncCursor, SyncEncryptionKey,
+    },
 };
 
 use super::SQLiteProvider;
 
+fn ensure_tombstone_table(table: &str) -> DatabaseResult<()> {
+    if TOMBSTONE_TABLES.contains(&table) {
+        Ok(())
+    } else {
+        Err(DatabaseError::QueryFailed(format!(
+            "Table {} does not keep tombstones",
+            table
+        )))
+    }
+}
+
+/// `WHERE` clause selecting the records of a table written after a change log position
+pub(super) fn changed_since_filter(table: &str, seq: i64) -> DatabaseResult<String> {
+    ensure_tombstone_table(table)?;
+    Ok(format!(
+        "id IN (SELECT record_id FROM sync_change_log WHERE table_name = '{}' AND seq > {})",
+        table, seq
+    ))
+}
+
+const CONFLICT_COLUMNS: &str = "id, entity_type, entity_id, local_data, remote_data, \
+    base_data, merged_data, field_conflicts, resolution_strategy, resolved_at, created_at";
+
+fn map_conflict_resolution_row(
+    row: &sqlx::sqlite::SqliteRow,
+) -> DatabaseResult<crate::models::sync::conflict::ConflictResolution> {
+    let local_data: String = row.get("local_data");
+    let remote_data: String = row.get("remote_data");
+    let base_data: Option<String> = row.get("base_data");
+    let merged_data: Option<String> = row.get("merged_data");
+    let field_conflicts: Option<String> = row.get("field_conflicts");
+    let resolution_strategy: Option<String> = row.get("resolution_strategy");
+    let resolved_at: Option<String> = row.get("resolved_at");
+    let created_at: String = row.get("created_at");
+
+    Ok(crate::models::sync::conflict::ConflictResolution {
+        id: row.get("id"),
+        entity_type: row.get("entity_type"),
+        entity_id: row.get("entity_id"),
+        local_data: serde_json::from_str(&local_data).map_err(DatabaseError::SerializationError)?,
+        remote_data: serde_json::from_str(&remote_data)
+            .map_err(DatabaseError::SerializationError)?,
+        base_data: base_data.and_then(|s| serde_json::from_str(&s).ok()),
+        merged_data: merged_data.and_then(|s| serde_json::from_str(&s).ok()),
+        field_conflicts: field_conflicts
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default(),
+        resolution_strategy: resolution_strategy
+            .and_then(|s| s.parse::<ConflictResolutionStrategy>().ok()),
+        resolved_at: resolved_at
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc)),
+        created_at: DateTime::parse_from_rfc3339(&created_at)
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?
+            .with_timezone(&Utc),
+    })
+}
+
+/// Tombstone a row on any executor, bumping its version so the deletion wins over older copies
+async fn write_tombstone<'e, E>(executor: E, table: &str, id: &str, now: &str) -> DatabaseResult<()>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    ensure_tombstone_table(table)?;
+
+    sqlx::query(&format!(
+        r#"
+        UPDATE {}
+        SET deleted_at = ?, updated_at = ?, version = version + 1, sync_status = ?
+        WHERE id = ? AND deleted_at IS NULL
+        "#,
+        table
+    ))
+    .bind(now)
+    .bind(now)
+    .bind(serde_json::to_string(&SyncStatus::Deleted).unwrap_or_default())
+    .bind(id)
+    .execute(executor)
+    .await
+    .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Tombstone the tunnels of a deleted profile
+/// Tombstones keep their rows, so the ON DELETE CASCADE on tunnels never fires
+async fn write_tunnel_tombstones<'e, E>(
+    executor: E,
+    profile_id: &str,
+    now: &str,
+) -> DatabaseResult<()>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    sqlx::query(
+        r#"
+        UPDATE ssh_tunnels
+        SET deleted_at = ?, updated_at = ?, version = version + 1, sync_status = ?
+        WHERE profile_id = ? AND deleted_at IS NULL
+        "#,
+    )
+    .bind(now)
+    .bind(now)
+    .bind(serde_json::to_string(&SyncStatus::Deleted).unwrap_or_default())
+    .bind(profile_id)
+    .execute(executor)
+    .await
+    .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    Ok(())
+}
+
+async fn write_global_sync_settings<'e, E>(
+    executor: E,
+    settings: &crate::models::sync::SyncSettings,
+) -> DatabaseResult<()>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO sync_settings (
+            id, is_active, auto_sync_enabled, sync_interval_minutes,
+            conflict_strategy, sync_direction, selected_database_id, last_sync_at,
+            synced_entities, created_at, updated_at
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+        ON CONFLICT(id) DO UPDATE SET
+            is_active = excluded.is_active,
+            auto_sync_enabled = excluded.auto_sync_enabled,
+            sync_interval_minutes = excluded.sync_interval_minutes,
+            conflict_strategy = excluded.conflict_strategy,
+            sync_direction = excluded.sync_direction,
+            selected_database_id = excluded.selected_database_id,
+            last_sync_at = excluded.last_sync_at,
+            synced_entities = excluded.synced_entities,
+            updated_at = excluded.updated_at
+    "#,
+    )
+    .bind(&settings.id)
+    .bind(settings.is_active)
+    .bind(settings.auto_sync_enabled)
+    .bind(settings.sync_interval_minutes as i64)
+    .bind(settings.conflict_strategy.to_string())
+    .bind(settings.sync_direction.to_string())
+    .bind(&settings.selected_database_id)
+    .bind(settings.last_sync_at.map(|dt| dt.to_rfc3339()))
+    .bind(serde_json::to_string(&settings.synced_entities)?)
+    .bind(settings.created_at.to_rfc3339())
+    .bind(settings.updated_at.to_rfc3339())
+    .execute(executor)
+    .await
+    .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Records to write and IDs to tombstone in one table, applied by `apply_restore`
+pub struct TableChanges {
+    pub table: &'static str,
+    pub writes: Vec<serde_json::Value>,
+    pub deletes: Vec<String>,
+}
+
+/// Write a sync record through its table's save path inside a transaction
+async fn write_sync_record(
+    conn: &mut sqlx::SqliteConnection,
+    table: &str,
+    record: &serde_json::Value,
+) -> DatabaseResult<()> {
+    fn decode<T: serde::de::DeserializeOwned>(record: &serde_json::Value) -> DatabaseResult<T> {
+        serde_json::from_value(record.clone()).map_err(DatabaseError::SerializationError)
+    }
+
+    match table {
+        "ssh_profiles" => {
+            let profile: crate::models::ssh::SSHProfile = decode(record)?;
+            super::ssh::write_ssh_profile(&mut *conn, &profile).await?;
+            if profile.base.is_deleted() {
+                // Same cleanup as deleting the profile: its tunnels go with it
+                write_tunnel_tombstones(
+                    &mut *conn,
+                    &profile.base.id,
+                    &profile.base.updated_at.to_rfc3339(),
+                )
+                .await?;
+            }
+        }
+        "ssh_groups" => super::ssh::write_ssh_group(&mut *conn, &decode(record)?).await?,
+        "ssh_keys" => super::ssh::write_ssh_key(&mut *conn, &decode(record)?).await?,
+        "ssh_tunnels" => super::tunnel::write_ssh_tunnel(&mut *conn, &decode(record)?).await?,
+        "saved_command_groups" => {
+            super::command::write_saved_command_group(&mut *conn, &decode(record)?).await?
+        }
+        "saved_commands" => {
+            super::command::write_saved_command(&mut *conn, &decode(record)?).await?
+        }
+        "terminal_profiles" => {
+            super::terminal::write_terminal_profile(&mut *conn, &decode(record)?).await?
+        }
+        "session_recordings" => {
+            super::recording::write_session_recording(&mut *conn, &decode(record)?).await?
+        }
+        "known_hosts" => super::known_host::write_known_host(&mut *conn, &decode(record)?).await?,
+        _ => {
+            return Err(DatabaseError::ValidationError(format!(
+                "Conflicts in {} cannot be resolved",
+                table
+            )))
+        }
+    }
+
+    Ok(())
+}
+
 impl SQLiteProvider {
     pub async fn save_external_database(
         &self,
@@ -126,6 +344,18 @@ impl SQLiteProvider {
         .await
         .map_err(|e| crate::database::error::DatabaseError::QueryFailed(e.to_string()))?;
 
+        sqlx::query("DELETE FROM sync_encryption_keys WHERE database_id = ?")
+            .bind(id)
+            .execute(&*pool)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        sqlx::query("DELETE FROM sync_cursors WHERE database_id = ?")
+            .bind(id)
+            .execute(&*pool)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
         Ok(())
     }
 
@@ -147,6 +377,9 @@ impl SQLiteProvider {
         let connection_details_encrypted: String = row
             .try_get("connection_details_encrypted")
             .map_err(|e| crate::database::error::DatabaseError::QueryFailed(e.to_string()))?;
+        let synced_entities: Option<String> = row
+            .try_get("synced_entities")
+            .map_err(|e| crate::database::error::DatabaseError::QueryFailed(e.to_string()))?;
         let created_at: String = row
             .try_get("created_at")
             .map_err(|e| crate::database::error::DatabaseError::QueryFailed(e.to_string()))?;
@@ -181,6 +414,7 @@ impl SQLiteProvider {
                 device_id,
                 version: version as u64,
                 sync_status,
+                deleted_at: None,
             },
             name,
             db_type,
@@ -201,7 +435,7 @@ impl SQLiteProvider {
             r#"
             SELECT id, is_active, auto_sync_enabled, sync_interval_minutes,
                 conflict_strategy, sync_direction, selected_database_id, last_sync_at,
-                created_at, updated_at
+                synced_entities, created_at, updated_at
             FROM sync_settings
             WHERE id = 'global'
         "#,
@@ -227,39 +461,7 @@ impl SQLiteProvider {
         let pool = self.get_pool()?;
         let pool = pool.read().await;
 
-        sqlx::query(
-            r#"
-            INSERT INTO sync_settings (
-                id, is_active, auto_sync_enabled, sync_interval_minutes,
-                conflict_strategy, sync_direction, selected_database_id, last_sync_at,
-                created_at, updated_at
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
-            ON CONFLICT(id) DO UPDATE SET
-                is_active = excluded.is_active,
-                auto_sync_enabled = excluded.auto_sync_enabled,
-                sync_interval_minutes = excluded.sync_interval_minutes,
-                conflict_strategy = excluded.conflict_strategy,
-                sync_direction = excluded.sync_direction,
-                selected_database_id = excluded.selected_database_id,
-                last_sync_at = excluded.last_sync_at,
-                updated_at = excluded.updated_at
-        "#,
-        )
-        .bind(&settings.id)
-        .bind(settings.is_active)
-        .bind(settings.auto_sync_enabled)
-        .bind(settings.sync_interval_minutes as i64)
-        .bind(settings.conflict_strategy.to_string())
-        .bind(settings.sync_direction.to_string())
-        .bind(&settings.selected_database_id)
-        .bind(settings.last_sync_at.map(|dt| dt.to_rfc3339()))
-        .bind(settings.created_at.to_rfc3339())
-        .bind(settings.updated_at.to_rfc3339())
-        .execute(&*pool)
-        .await
-        .map_err(|e| crate::database::error::DatabaseError::QueryFailed(e.to_string()))?;
-
-        Ok(())
+        write_global_sync_settings(&*pool, settings).await
     }
 
     /// Update global sync settings with partial data
@@ -296,6 +498,9 @@ impl SQLiteProvider {
         if let Some(ref selected_database_id) = request.selected_database_id {
             settings.selected_database_id = Some(selected_database_id.clone());
         }
+        if let Some(synced_entities) = request.synced_entities {
+            settings.synced_entities = synced_entities;
+        }
 
         settings.touch();
 
@@ -366,6 +571,10 @@ impl SQLiteProvider {
             sync_direction,
             selected_database_id,
             last_sync_at: last_sync_at_parsed,
+            // Settings saved before per-entity toggles existed sync everything
+            synced_entities: synced_entities
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default(),
             created_at: DateTime::parse_from_rfc3339(&created_at)
                 .map_err(|e| crate::database::error::DatabaseError::QueryFailed(e.to_string()))?
                 .with_timezone(&Utc),
@@ -387,8 +596,9 @@ impl SQLiteProvider {
             r#"
             INSERT INTO conflict_resolutions (
                 id, entity_type, entity_id, local_data, remote_data,
+                base_data, merged_data, field_conflicts,
                 resolution_strategy, resolved_at, created_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&resolution.id)
@@ -396,6 +606,9 @@ impl SQLiteProvider {
         .bind(&resolution.entity_id)
         .bind(resolution.local_data.to_string())
         .bind(resolution.remote_data.to_string())
+        .bind(resolution.base_data.as_ref().map(|v| v.to_string()))
+        .bind(resolution.merged_data.as_ref().map(|v| v.to_string()))
+        .bind(serde_json::to_string(&resolution.field_conflicts)?)
         .bind(
             resolution
                 .resolution_strategy
@@ -418,71 +631,330 @@ impl SQLiteProvider {
         let pool = self.get_pool()?;
         let pool = pool.read().await;
 
-        let rows = sqlx::query(
-            r#"
-            SELECT id, entity_type, entity_id, local_data, remote_data,
-                   resolution_strategy, resolved_at, created_at
-            FROM conflict_resolutions
-            WHERE resolved_at IS NULL
-            ORDER BY created_at DESC
-            "#,
-        )
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM conflict_resolutions WHERE resolved_at IS NULL ORDER BY created_at DESC",
+            CONFLICT_COLUMNS
+        ))
         .fetch_all(&*pool)
         .await
         .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
 
-        let mut resolutions = Vec::new();
-        for row in rows {
-            let id: String = row.get("id");
-            let entity_type: String = row.get("entity_type");
-            let entity_id: String = row.get("entity_id");
-            let local_data: String = row.get("local_data");
-            let remote_data: String = row.get("remote_data");
-            let resolution_strategy: Option<String> = row.get("resolution_strategy");
-            let resolved_at: Option<String> = row.get("resolved_at");
-            let created_at: String = row.get("created_at");
-
-            resolutions.push(crate::models::sync::conflict::ConflictResolution {
-                id,
-                entity_type,
-                entity_id,
-                local_data: serde_json::from_str(&local_data)
-                    .map_err(DatabaseError::SerializationError)?,
-                remote_data: serde_json::from_str(&remote_data)
-                    .map_err(DatabaseError::SerializationError)?,
-                resolution_strategy: resolution_strategy
-                    .and_then(|s| s.parse::<ConflictResolutionStrategy>().ok()),
-                resolved_at: resolved_at
-                    .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
-                    .map(|dt| dt.with_timezone(&Utc)),
-                created_at: DateTime::parse_from_rfc3339(&created_at)
-                    .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?
-                    .with_timezone(&Utc),
-            });
-        }
+        rows.iter().map(map_conflict_resolution_row).collect()
+    }
+
+    /// Get a conflict resolution by id
+    pub async fn find_conflict_resolution(
+        &self,
+        id: &str,
+    ) -> DatabaseResult<Option<crate::models::sync::conflict::ConflictResolution>> {
+        let pool = self.get_pool()?;
+        let pool = pool.read().await;
 
-        Ok(resolutions)
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM conflict_resolutions WHERE id = ?",
+            CONFLICT_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        row.as_ref().map(map_conflict_resolution_row).transpose()
     }
 
-    /// Resolve a conflict resolution
+    /// Save the record a conflict settled on and mark the conflict resolved, in one transaction
+    /// The remote side becomes the record's sync base, so the next sync pushes the resolved
+    /// record instead of reporting the same conflict again
     pub async fn resolve_conflict_resolution(
         &self,
-        id: &str,
+        conflict: &crate::models::sync::conflict::ConflictResolution,
         strategy: ConflictResolutionStrategy,
+        record: &serde_json::Value,
     ) -> DatabaseResult<()> {
         let pool = self.get_pool()?;
         let pool = pool.read().await;
+        let now = Utc::now().to_rfc3339();
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
 
-        sqlx::query(
+        let resolved = sqlx::query(
             r#"
             UPDATE conflict_resolutions
             SET resolution_strategy = ?, resolved_at = ?
-            WHERE id = ?
+            WHERE id = ? AND resolved_at IS NULL
             "#,
         )
         .bind(strategy.to_string())
-        .bind(Utc::now().to_rfc3339())
-        .bind(id)
+        .bind(&now)
+        .bind(&conflict.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+        if resolved.rows_affected() == 0 {
+            return Err(DatabaseError::NotFound(format!(
+                "Unresolved conflict {} not found",
+                conflict.id
+            )));
+        }
+
+        write_sync_record(&mut *tx, &conflict.entity_type, record).await?;
+
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO sync_base_records (table_name, record_id, data, version, synced_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&conflict.entity_type)
+        .bind(&conflict.entity_id)
+        .bind(conflict.remote_data.to_string())
+        .bind(
+            conflict
+                .remote_data
+                .get("version")
+                .and_then(|v| v.as_i64())
+                .unwrap_or(1),
+        )
+        .bind(&now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Last-synced copies of a table's records, keyed by record id
+    pub async fn get_sync_base_records(
+        &self,
+        table: &str,
+    ) -> DatabaseResult<HashMap<String, serde_json::Value>> {
+        let pool = self.get_pool()?;
+        let pool = pool.read().await;
+
+        let rows =
+            sqlx::query("SELECT record_id, data FROM sync_base_records WHERE table_name = ?")
+                .bind(table)
+                .fetch_all(&*pool)
+                .await
+                .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        let mut bases = HashMap::new();
+        for row in rows {
+            let data: String = row.get("data");
+            if let Ok(value) = serde_json::from_str(&data) {
+                bases.insert(row.get("record_id"), value);
+            }
+        }
+
+        Ok(bases)
+    }
+
+    /// Remember records as both sides agreed on them after a sync
+    pub async fn save_sync_base_records(
+        &self,
+        table: &str,
+        records: &[serde_json::Value],
+    ) -> DatabaseResult<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let pool = self.get_pool()?;
+        let pool = pool.read().await;
+        let now = Utc::now().to_rfc3339();
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        for record in records {
+            let Some(id) = record.get("id").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            sqlx::query(
+                r#"
+                INSERT OR REPLACE INTO sync_base_records (table_name, record_id, data, version, synced_at)
+                VALUES (?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(table)
+            .bind(id)
+            .bind(record.to_string())
+            .bind(record.get("version").and_then(|v| v.as_i64()).unwrap_or(1))
+            .bind(&now)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Position of the latest write to a synced table in the change log
+    pub async fn latest_change_seq(&self) -> DatabaseResult<i64> {
+        let pool = self.get_pool()?;
+        let pool = pool.read().await;
+
+        sqlx::query_scalar("SELECT COALESCE(MAX(seq), 0) FROM sync_change_log")
+            .fetch_one(&*pool)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))
+    }
+
+    /// Versions of the given local records, tombstones included
+    pub async fn get_record_versions(
+        &self,
+        table: &str,
+        ids: &[String],
+    ) -> DatabaseResult<HashMap<String, u64>> {
+        ensure_tombstone_table(table)?;
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let pool = self.get_pool()?;
+        let pool = pool.read().await;
+
+        let placeholders: Vec<&str> = ids.iter().map(|_| "?").collect();
+        let sql = format!(
+            "SELECT id, version FROM {} WHERE id IN ({})",
+            table,
+            placeholders.join(", ")
+        );
+
+        let mut query = sqlx::query(&sql);
+        for id in ids {
+            query = query.bind(id);
+        }
+
+        let rows = query
+            .fetch_all(&*pool)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("id"), row.get::<i64, _>("version") as u64))
+            .collect())
+    }
+
+    /// How far a table has been synced with a sync target, `None` before its first sync
+    pub async fn get_sync_cursor(
+        &self,
+        database_id: &str,
+        table: &str,
+    ) -> DatabaseResult<Option<SyncCursor>> {
+        let pool = self.get_pool()?;
+        let pool = pool.read().await;
+
+        let row = sqlx::query(
+            r#"
+            SELECT database_id, table_name, local_seq, remote_cursor, updated_at
+            FROM sync_cursors
+            WHERE database_id = ? AND table_name = ?
+            "#,
+        )
+        .bind(database_id)
+        .bind(table)
+        .fetch_optional(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        row.map(|row| {
+            Ok(SyncCursor {
+                database_id: row.get("database_id"),
+                table_name: row.get("table_name"),
+                local_seq: row.get("local_seq"),
+                remote_cursor: row.get("remote_cursor"),
+                updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))
+                    .map_err(|e| DatabaseError::ParseError(e.to_string()))?
+                    .with_timezone(&Utc),
+            })
+        })
+        .transpose()
+    }
+
+    pub async fn save_sync_cursor(&self, cursor: &SyncCursor) -> DatabaseResult<()> {
+        let pool = self.get_pool()?;
+        let pool = pool.read().await;
+
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO sync_cursors (database_id, table_name, local_seq, remote_cursor, updated_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&cursor.database_id)
+        .bind(&cursor.table_name)
+        .bind(cursor.local_seq)
+        .bind(&cursor.remote_cursor)
+        .bind(cursor.updated_at.to_rfc3339())
+        .execute(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Sync keys of an end-to-end encrypted sync target, oldest first
+    pub async fn get_sync_encryption_keys(
+        &self,
+        database_id: &str,
+    ) -> DatabaseResult<Vec<SyncEncryptionKey>> {
+        let pool = self.get_pool()?;
+        let pool = pool.read().await;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT database_id, key_id, encrypted_key, created_at
+            FROM sync_encryption_keys
+            WHERE database_id = ?
+            ORDER BY created_at ASC
+            "#,
+        )
+        .bind(database_id)
+        .fetch_all(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(SyncEncryptionKey {
+                    database_id: row.get("database_id"),
+                    key_id: row.get("key_id"),
+                    encrypted_key: row.get("encrypted_key"),
+                    created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))
+                        .map_err(|e| DatabaseError::ParseError(e.to_string()))?
+                        .with_timezone(&Utc),
+                })
+            })
+            .collect()
+    }
+
+    pub async fn save_sync_encryption_key(&self, key: &SyncEncryptionKey) -> DatabaseResult<()> {
+        let pool = self.get_pool()?;
+        let pool = pool.read().await;
+
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO sync_encryption_keys (database_id, key_id, encrypted_key, created_at)
+            VALUES (?, ?, ?, ?)
+            "#,
+        )
+        .bind(&key.database_id)
+        .bind(&key.key_id)
+        .bind(&key.encrypted_key)
+        .bind(key.created_at.to_rfc3339())
         .execute(&*pool)
         .await
         .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
@@ -490,6 +962,20 @@ impl SQLiteProvider {
         Ok(())
     }
 
+    /// Forget the sync keys of a sync target, once it no longer holds sealed records
+    pub async fn delete_sync_encryption_keys(&self, database_id: &str) -> DatabaseResult<()> {
+        let pool = self.get_pool()?;
+        let pool = pool.read().await;
+
+        sqlx::query("DELETE FROM sync_encryption_keys WHERE database_id = ?")
+            .bind(database_id)
+            .execute(&*pool)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        Ok(())
+    }
+
     /// Delete resolved conflict resolutions older than specified days
     pub async fn cleanup_resolved_conflicts(&self, days: i64) -> DatabaseResult<usize> {
         let pool = self.get_pool()?;
@@ -509,4 +995,136 @@ impl SQLiteProvider {
 
         Ok(result.rows_affected() as usize)
     }
+
+    /// Turn a row into a tombstone, bumping its version so the deletion wins over older copies
+    pub async fn mark_deleted(&self, table: &str, id: &str) -> DatabaseResult<()> {
+        let pool = self.get_pool()?;
+        let pool = pool.read().await;
+
+        write_tombstone(&*pool, table, id, &Utc::now().to_rfc3339()).await
+    }
+
+    /// Apply a backup restore in one transaction, so a failure leaves nothing half restored
+    /// Deletes run in reverse table order and writes in table order, so records referencing
+    /// others go before them on delete and after them on write
+    pub async fn apply_restore(
+        &self,
+        changes: &[TableChanges],
+        sync_settings: Option<&crate::models::sync::SyncSettings>,
+    ) -> DatabaseResult<()> {
+        let pool = self.get_pool()?;
+        let pool = pool.read().await;
+        let now = Utc::now().to_rfc3339();
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        for change in changes.iter().rev() {
+            for id in &change.deletes {
+                write_tombstone(&mut *tx, change.table, id, &now).await?;
+                if change.table == "ssh_profiles" {
+                    write_tunnel_tombstones(&mut *tx, id, &now).await?;
+                }
+            }
+        }
+
+        for change in changes {
+            for record in &change.writes {
+                write_sync_record(&mut *tx, change.table, record).await?;
+            }
+        }
+
+        if let Some(settings) = sync_settings {
+            write_global_sync_settings(&mut *tx, settings).await?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Flag tombstones as acknowledged on the sync target
+    pub async fn mark_tombstones_acknowledged(
+        &self,
+        table: &str,
+        ids: &[String],
+    ) -> DatabaseResult<()> {
+        ensure_tombstone_table(table)?;
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let pool = self.get_pool()?;
+        let pool = pool.read().await;
+
+        let placeholders: Vec<&str> = ids.iter().map(|_| "?").collect();
+        let sql = format!(
+            "UPDATE {} SET sync_status = ? WHERE deleted_at IS NOT NULL AND id IN ({})",
+            table,
+            placeholders.join(", ")
+        );
+
+        let mut query =
+            sqlx::query(&sql).bind(serde_json::to_string(&SyncStatus::Synced).unwrap_or_default());
+        for id in ids {
+            query = query.bind(id);
+        }
+
+        query
+            .execute(&*pool)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Permanently remove tombstones that every device has acknowledged
+    pub async fn purge_tombstones(&self, table: &str, ids: &[String]) -> DatabaseResult<usize> {
+        ensure_tombstone_table(table)?;
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let pool = self.get_pool()?;
+        let pool = pool.read().await;
+
+        let placeholders: Vec<&str> = ids.iter().map(|_| "?").collect();
+        let sql = format!(
+            "DELETE FROM {} WHERE deleted_at IS NOT NULL AND id IN ({})",
+            table,
+            placeholders.join(", ")
+        );
+
+        let mut query = sqlx::query(&sql);
+        for id in ids {
+            query = query.bind(id);
+        }
+
+        let result = query
+            .execute(&*pool)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        // Purged records no longer need a merge base or a change log entry
+        for bookkeeping in ["sync_base_records", "sync_change_log"] {
+            let sql = format!(
+                "DELETE FROM {} WHERE table_name = ? AND record_id IN ({})",
+                bookkeeping,
+                placeholders.join(", ")
+            );
+            let mut query = sqlx::query(&sql).bind(table);
+            for id in ids {
+                query = query.bind(id);
+            }
+            query
+                .execute(&*pool)
+                .await
+                .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+        }
+
+        Ok(result.rows_affected() as usize)
+    }
 }
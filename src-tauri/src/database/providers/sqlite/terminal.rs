@@ -1,6 +1,6 @@
 use crate::database::{
     error::{DatabaseError, DatabaseResult},
-    providers::sqlite::SQLiteProvider,
+    providers::sqlite::{sync_ops::changed_since_filter, SQLiteProvider},
 };
 use crate::models::terminal::profile::TerminalProfile;
 use sqlx::Row;
@@ -13,16 +13,27 @@ pub async fn save_terminal_profile(
     let pool_arc = provider.get_pool()?;
     let pool = pool_arc.read().await;
 
+    write_terminal_profile(&*pool, profile).await
+}
+
+pub(super) async fn write_terminal_profile<'e, E>(
+    executor: E,
+    profile: &TerminalProfile,
+) -> DatabaseResult<()>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
     let env_json = serde_json::to_string(&profile.env)?;
 
     sqlx::query(
         r#"
         INSERT OR REPLACE INTO terminal_profiles (
-            id, name, shell, working_dir, env, icon, color, command, created_at, updated_at
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            id, name, shell, working_dir, env, icon, color, command, created_at, updated_at,
+            device_id, version, sync_status, deleted_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
     )
-    .bind(&profile.id)
+    .bind(&profile.base.id)
     .bind(&profile.name)
     .bind(&profile.shell)
     .bind(&profile.working_dir)
@@ -30,9 +41,13 @@ pub async fn save_terminal_profile(
     .bind(&profile.icon)
     .bind(&profile.color)
     .bind(&profile.command)
-    .bind(profile.created_at)
-    .bind(profile.updated_at)
-    .execute(&*pool)
+    .bind(profile.base.created_at.to_rfc3339())
+    .bind(profile.base.updated_at.to_rfc3339())
+    .bind(&profile.base.device_id)
+    .bind(profile.base.version as i64)
+    .bind(serde_json::to_string(&profile.base.sync_status).unwrap())
+    .bind(profile.base.deleted_at.map(|dt| dt.to_rfc3339()))
+    .execute(executor)
     .await
     .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
 
@@ -48,9 +63,10 @@ pub async fn find_terminal_profile_by_id(
 
     let row = sqlx::query(
         r#"
-        SELECT id, name, shell, working_dir, env, icon, color, command, created_at, updated_at
+        SELECT id, name, shell, working_dir, env, icon, color, command, created_at, updated_at,
+            device_id, version, sync_status, deleted_at
         FROM terminal_profiles
-        WHERE id = ?
+        WHERE id = ? AND deleted_at IS NULL
         "#,
     )
     .bind(id)
@@ -58,75 +74,91 @@ pub async fn find_terminal_profile_by_id(
     .await
     .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
 
-    if let Some(row) = row {
-        let env_str: String = row.try_get("env").unwrap_or_default();
-        let env: Option<HashMap<String, String>> = serde_json::from_str(&env_str).ok();
-
-        Ok(Some(TerminalProfile {
-            id: row.try_get("id").unwrap_or_default(),
-            name: row.try_get("name").unwrap_or_default(),
-            shell: row.try_get("shell").unwrap_or_default(),
-            working_dir: row.try_get("working_dir").ok(),
-            env,
-            icon: row.try_get("icon").ok(),
-            color: row.try_get("color").ok(),
-            command: row.try_get("command").ok(),
-            created_at: row.try_get("created_at").unwrap_or_default(),
-            updated_at: row.try_get("updated_at").unwrap_or_default(),
-        }))
-    } else {
-        Ok(None)
-    }
+    row.map(|row| map_terminal_profile_row(&row)).transpose()
 }
 
 pub async fn find_all_terminal_profiles(
     provider: &SQLiteProvider,
+) -> DatabaseResult<Vec<TerminalProfile>> {
+    find_terminal_profiles_where(provider, "deleted_at IS NULL").await
+}
+
+/// Tombstones left by deleted terminal profiles, used to sync deletions
+pub async fn find_deleted_terminal_profiles(
+    provider: &SQLiteProvider,
+) -> DatabaseResult<Vec<TerminalProfile>> {
+    find_terminal_profiles_where(provider, "deleted_at IS NOT NULL").await
+}
+
+/// Terminal profiles written after a change log position, tombstones included
+pub async fn find_changed_terminal_profiles(
+    provider: &SQLiteProvider,
+    after_seq: i64,
+) -> DatabaseResult<Vec<TerminalProfile>> {
+    find_terminal_profiles_where(
+        provider,
+        &changed_since_filter("terminal_profiles", after_seq)?,
+    )
+    .await
+}
+
+async fn find_terminal_profiles_where(
+    provider: &SQLiteProvider,
+    filter: &str,
 ) -> DatabaseResult<Vec<TerminalProfile>> {
     let pool_arc = provider.get_pool()?;
     let pool = pool_arc.read().await;
 
-    let rows = sqlx::query(
+    let rows = sqlx::query(&format!(
         r#"
-        SELECT id, name, shell, working_dir, env, icon, color, command, created_at, updated_at
+        SELECT id, name, shell, working_dir, env, icon, color, command, created_at, updated_at,
+            device_id, version, sync_status, deleted_at
         FROM terminal_profiles
+        WHERE {}
         ORDER BY name ASC
         "#,
-    )
+        filter
+    ))
     .fetch_all(&*pool)
     .await
     .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
 
-    let mut profiles = Vec::new();
-    for row in rows {
-        let env_str: String = row.try_get("env").unwrap_or_default();
-        let env: Option<HashMap<String, String>> = serde_json::from_str(&env_str).ok();
+    rows.iter().map(map_terminal_profile_row).collect()
+}
 
-        profiles.push(TerminalProfile {
+fn map_terminal_profile_row(row: &sqlx::sqlite::SqliteRow) -> DatabaseResult<TerminalProfile> {
+    let env_str: String = row.try_get("env").unwrap_or_default();
+    let env: Option<HashMap<String, String>> = serde_json::from_str(&env_str).ok();
+
+    Ok(TerminalProfile {
+        base: crate::models::base::BaseModel {
             id: row.try_get("id").unwrap_or_default(),
-            name: row.try_get("name").unwrap_or_default(),
-            shell: row.try_get("shell").unwrap_or_default(),
-            working_dir: row.try_get("working_dir").ok(),
-            env,
-            icon: row.try_get("icon").ok(),
-            color: row.try_get("color").ok(),
-            command: row.try_get("command").ok(),
-            created_at: row.try_get("created_at").unwrap_or_default(),
-            updated_at: row.try_get("updated_at").unwrap_or_default(),
-        });
-    }
-
-    Ok(profiles)
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))
+                .map_err(|e| DatabaseError::ParseError(format!("Parse error: {}", e)))?
+                .with_timezone(&chrono::Utc),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))
+                .map_err(|e| DatabaseError::ParseError(format!("Parse error: {}", e)))?
+                .with_timezone(&chrono::Utc),
+            device_id: row.try_get("device_id").unwrap_or_default(),
+            version: row.get::<i64, _>("version") as u64,
+            sync_status: serde_json::from_str(&row.get::<String, _>("sync_status"))
+                .unwrap_or(crate::database::traits::SyncStatus::Synced),
+            deleted_at: row
+                .get::<Option<String>, _>("deleted_at")
+                .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                .map(|dt| dt.with_timezone(&chrono::Utc)),
+        },
+        name: row.try_get("name").unwrap_or_default(),
+        shell: row.try_get("shell").unwrap_or_default(),
+        working_dir: row.try_get("working_dir").ok(),
+        env,
+        icon: row.try_get("icon").ok(),
+        color: row.try_get("color").ok(),
+        command: row.try_get("command").ok(),
+    })
 }
 
+/// Soft-delete a terminal profile, leaving a tombstone for sync
 pub async fn delete_terminal_profile(provider: &SQLiteProvider, id: &str) -> DatabaseResult<()> {
-    let pool_arc = provider.get_pool()?;
-    let pool = pool_arc.read().await;
-
-    sqlx::query("DELETE FROM terminal_profiles WHERE id = ?")
-        .bind(id)
-        .execute(&*pool)
-        .await
-        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
-
-    Ok(())
+    provider.mark_deleted("terminal_profiles", id).await
 }
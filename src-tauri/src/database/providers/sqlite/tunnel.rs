@@ -5,19 +5,26 @@ use crate::{
     models::ssh::SSHTunnel,
 };
 
-use super::SQLiteProvider;
+use super::{sync_ops::changed_since_filter, SQLiteProvider};
 
 pub async fn save_ssh_tunnel(provider: &SQLiteProvider, model: &SSHTunnel) -> DatabaseResult<()> {
     let pool = provider.get_pool()?;
     let pool = pool.read().await;
 
+    write_ssh_tunnel(&*pool, model).await
+}
+
+pub(super) async fn write_ssh_tunnel<'e, E>(executor: E, model: &SSHTunnel) -> DatabaseResult<()>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
     sqlx::query(
         r#"
         INSERT OR REPLACE INTO ssh_tunnels (
             id, name, description, profile_id, tunnel_type, local_host, local_port,
             remote_host, remote_port, auto_start, created_at, updated_at,
-            device_id, version, sync_status
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            device_id, version, sync_status, deleted_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
     "#,
     )
     .bind(&model.base.id)
@@ -35,7 +42,8 @@ pub async fn save_ssh_tunnel(provider: &SQLiteProvider, model: &SSHTunnel) -> Da
     .bind(&model.base.device_id)
     .bind(model.base.version as i64)
     .bind(serde_json::to_string(&model.base.sync_status).unwrap())
-    .execute(&*pool)
+    .bind(model.base.deleted_at.map(|dt| dt.to_rfc3339()))
+    .execute(executor)
     .await
     .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
 
@@ -49,7 +57,7 @@ pub async fn find_ssh_tunnel_by_id(
     let pool = provider.get_pool()?;
     let pool = pool.read().await;
 
-    let row = sqlx::query("SELECT * FROM ssh_tunnels WHERE id = ?")
+    let row = sqlx::query("SELECT * FROM ssh_tunnels WHERE id = ? AND deleted_at IS NULL")
         .bind(id)
         .fetch_optional(&*pool)
         .await
@@ -73,6 +81,10 @@ pub async fn find_ssh_tunnel_by_id(
                 version: row.get::<i64, _>("version") as u64,
                 sync_status: serde_json::from_str(&row.get::<String, _>("sync_status"))
                     .unwrap_or(crate::database::traits::SyncStatus::Synced),
+                deleted_at: row
+                    .get::<Option<String>, _>("deleted_at")
+                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                    .map(|dt| dt.with_timezone(&chrono::Utc)),
             },
             name: row.get("name"),
             description: row.get("description"),
@@ -94,63 +106,42 @@ pub async fn find_ssh_tunnel_by_id(
 }
 
 pub async fn find_all_ssh_tunnels(provider: &SQLiteProvider) -> DatabaseResult<Vec<SSHTunnel>> {
-    let pool = provider.get_pool()?;
-    let pool = pool.read().await;
-
-    let rows = sqlx::query("SELECT * FROM ssh_tunnels ORDER BY name")
-        .fetch_all(&*pool)
-        .await
-        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+    find_ssh_tunnels_where(provider, "deleted_at IS NULL").await
+}
 
-    let mut tunnels = Vec::new();
-    for row in rows {
-        let tunnel = SSHTunnel {
-            base: crate::models::base::BaseModel {
-                id: row.get("id"),
-                created_at: chrono::DateTime::parse_from_rfc3339(
-                    &row.get::<String, _>("created_at"),
-                )
-                .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?
-                .with_timezone(&chrono::Utc),
-                updated_at: chrono::DateTime::parse_from_rfc3339(
-                    &row.get::<String, _>("updated_at"),
-                )
-                .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?
-                .with_timezone(&chrono::Utc),
-                device_id: row.get("device_id"),
-                version: row.get::<i64, _>("version") as u64,
-                sync_status: serde_json::from_str(&row.get::<String, _>("sync_status"))
-                    .unwrap_or(crate::database::traits::SyncStatus::Synced),
-            },
-            name: row.get("name"),
-            description: row.get("description"),
-            profile_id: row.get("profile_id"),
-            tunnel_type: serde_json::from_str(&row.get::<String, _>("tunnel_type"))
-                .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?,
-            local_host: row.get("local_host"),
-            local_port: row.get::<i32, _>("local_port") as u16,
-            remote_host: row.get("remote_host"),
-            remote_port: row.get::<Option<i32>, _>("remote_port").map(|p| p as u16),
-            auto_start: row.get("auto_start"),
-            status: crate::models::ssh::TunnelStatus::default(),
-            error_message: None,
-        };
-        tunnels.push(tunnel);
-    }
+/// Tombstones left by deleted tunnels, used to sync deletions
+pub async fn find_deleted_ssh_tunnels(provider: &SQLiteProvider) -> DatabaseResult<Vec<SSHTunnel>> {
+    find_ssh_tunnels_where(provider, "deleted_at IS NOT NULL").await
+}
 
-    Ok(tunnels)
+/// Tunnels written after a change log position, tombstones included
+pub async fn find_changed_ssh_tunnels(
+    provider: &SQLiteProvider,
+    after_seq: i64,
+) -> DatabaseResult<Vec<SSHTunnel>> {
+    find_ssh_tunnels_where(provider, &changed_since_filter("ssh_tunnels", after_seq)?).await
 }
 
 pub async fn find_auto_start_ssh_tunnels(
     provider: &SQLiteProvider,
+) -> DatabaseResult<Vec<SSHTunnel>> {
+    find_ssh_tunnels_where(provider, "auto_start = true AND deleted_at IS NULL").await
+}
+
+async fn find_ssh_tunnels_where(
+    provider: &SQLiteProvider,
+    filter: &str,
 ) -> DatabaseResult<Vec<SSHTunnel>> {
     let pool = provider.get_pool()?;
     let pool = pool.read().await;
 
-    let rows = sqlx::query("SELECT * FROM ssh_tunnels WHERE auto_start = true ORDER BY name")
-        .fetch_all(&*pool)
-        .await
-        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+    let rows = sqlx::query(&format!(
+        "SELECT * FROM ssh_tunnels WHERE {} ORDER BY name",
+        filter
+    ))
+    .fetch_all(&*pool)
+    .await
+    .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
 
     let mut tunnels = Vec::new();
     for row in rows {
@@ -171,6 +162,10 @@ pub async fn find_auto_start_ssh_tunnels(
                 version: row.get::<i64, _>("version") as u64,
                 sync_status: serde_json::from_str(&row.get::<String, _>("sync_status"))
                     .unwrap_or(crate::database::traits::SyncStatus::Synced),
+                deleted_at: row
+                    .get::<Option<String>, _>("deleted_at")
+                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                    .map(|dt| dt.with_timezone(&chrono::Utc)),
             },
             name: row.get("name"),
             description: row.get("description"),
@@ -191,15 +186,40 @@ pub async fn find_auto_start_ssh_tunnels(
     Ok(tunnels)
 }
 
+/// Soft-delete a tunnel, leaving a tombstone for sync
 pub async fn delete_ssh_tunnel(provider: &SQLiteProvider, id: &str) -> DatabaseResult<()> {
-    let pool = provider.get_pool()?;
-    let pool = pool.read().await;
+    provider.mark_deleted("ssh_tunnels", id).await
+}
 
-    sqlx::query("DELETE FROM ssh_tunnels WHERE id = ?")
-        .bind(id)
-        .execute(&*pool)
-        .await
-        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+/// Soft-delete every tunnel of a profile
+pub async fn delete_ssh_tunnels_by_profile_id(
+    provider: &SQLiteProvider,
+    profile_id: &str,
+) -> DatabaseResult<()> {
+    let ids: Vec<String> = {
+        let pool = provider.get_pool()?;
+        let pool = pool.read().await;
+
+        sqlx::query_scalar("SELECT id FROM ssh_tunnels WHERE profile_id = ? AND deleted_at IS NULL")
+            .bind(profile_id)
+            .fetch_all(&*pool)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?
+    };
+
+    for id in ids {
+        provider.mark_deleted("ssh_tunnels", &id).await?;
+    }
 
     Ok(())
 }
+
+impl SQLiteProvider {
+    pub async fn find_deleted_ssh_tunnels(&self) -> DatabaseResult<Vec<SSHTunnel>> {
+        find_deleted_ssh_tunnels(self).await
+    }
+
+    pub async fn find_changed_ssh_tunnels(&self, after_seq: i64) -> DatabaseResult<Vec<SSHTunnel>> {
+        find_changed_ssh_tunnels(self, after_seq).await
+    }
+}
@@ -0,0 +1,368 @@
+use chrono::Utc;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::PoisonError;
+use std::time::{Duration, Instant};
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{Mutex, MutexGuard};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::database::error::{DatabaseError, DatabaseResult};
+
+/// How long to wait for another device to release the lock, longer than a stale lock takes to show
+const LOCK_TIMEOUT: Duration = Duration::from_secs(75);
+/// A lock file unchanged for this long was left behind by a device that crashed
+///
+/// Timed with this device's monotonic clock from when the contents were first seen, so clock skew
+/// between devices and the share's file times play no part.
+const STALE_AFTER: Duration = Duration::from_secs(60);
+/// How often a held lock file is rewritten with a new beat, so long writes never look stale
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+const RETRY_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Exclusive write lock shared by every device through a lock file
+/// Creating a file exclusively is atomic on local disks as well as on SMB and NFS shares
+pub(crate) struct WriteLock {
+    path: PathBuf,
+    /// Writers of this process queue here instead of polling the lock file
+    local: Mutex<()>,
+    observed_lock: Observed,
+    observed_claim: Observed,
+    stale_after: Duration,
+    heartbeat_interval: Duration,
+}
+
+/// Held while writing; the lock file is removed when dropped
+pub(crate) struct WriteLockGuard<'a> {
+    path: &'a PathBuf,
+    /// Written into the lock file, so only the lock this guard created is ever removed
+    token: String,
+    heartbeat: JoinHandle<()>,
+    _local: MutexGuard<'a, ()>,
+}
+
+/// Whether the lock file still holds the given token
+/// A device that took over a lock it considered stale replaced the file with its own token
+fn owns_lock(contents: &str, token: &str) -> bool {
+    contents.split_whitespace().next() == Some(token)
+}
+
+/// Token, heartbeat count and details for whoever looks at the file by hand
+fn lock_contents(token: &str, beat: u64) -> String {
+    format!(
+        "{} {} {} {}",
+        token,
+        beat,
+        std::process::id(),
+        Utc::now().to_rfc3339()
+    )
+}
+
+/// Write the owner of the lock, flushed so the token is on disk before the guard relies on it
+async fn write_owner(file: &mut File, token: &str, beat: u64) -> std::io::Result<()> {
+    file.write_all(lock_contents(token, beat).as_bytes())
+        .await?;
+    file.flush().await
+}
+
+async fn read_lock(path: &Path) -> Option<String> {
+    tokio::fs::read_to_string(path).await.ok()
+}
+
+/// Rewrite the lock file while it is held, until the guard is dropped or the lock is lost
+async fn heartbeat(path: PathBuf, token: String, interval: Duration) {
+    let mut beat = 0;
+    loop {
+        tokio::time::sleep(interval).await;
+
+        // A missing file may be a takeover checking it and putting it back; only another
+        // device's token means the lock is lost
+        match read_lock(&path).await {
+            Some(contents) if !owns_lock(&contents, &token) => {
+                eprintln!("[WARN] Lost sync lock {}", path.display());
+                return;
+            }
+            Some(_) => {}
+            None => continue,
+        }
+
+        // Never create the file: it must not reappear once removed
+        beat += 1;
+        let refreshed = match tokio::fs::OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .open(&path)
+            .await
+        {
+            Ok(mut file) => write_owner(&mut file, &token, beat).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = refreshed {
+            eprintln!(
+                "[WARN] Failed to refresh sync lock {}: {}",
+                path.display(),
+                e
+            );
+        }
+    }
+}
+
+impl WriteLock {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            local: Mutex::new(()),
+            observed_lock: Observed::default(),
+            observed_claim: Observed::default(),
+            stale_after: STALE_AFTER,
+            heartbeat_interval: HEARTBEAT_INTERVAL,
+        }
+    }
+
+    pub(crate) async fn acquire(&self) -> DatabaseResult<WriteLockGuard<'_>> {
+        let local = self.local.lock().await;
+        let deadline = Instant::now() + LOCK_TIMEOUT;
+
+        loop {
+            let created = tokio::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&self.path)
+                .await;
+
+            match created {
+                Ok(mut file) => {
+                    let token = Uuid::new_v4().to_string();
+                    if let Err(e) = write_owner(&mut file, &token, 0).await {
+                        tokio::fs::remove_file(&self.path).await.ok();
+                        return Err(DatabaseError::ConnectionFailed(format!(
+                            "Failed to write lock file {}: {}",
+                            self.path.display(),
+                            e
+                        )));
+                    }
+                    drop(file);
+
+                    // A device taking over a lock it judged stale may have moved this file away
+                    let confirmed = read_lock(&self.path)
+                        .await
+                        .is_some_and(|contents| owns_lock(&contents, &token));
+                    if !confirmed {
+                        continue;
+                    }
+
+                    let heartbeat = tokio::spawn(heartbeat(
+                        self.path.clone(),
+                        token.clone(),
+                        self.heartbeat_interval,
+                    ));
+                    return Ok(WriteLockGuard {
+                        path: &self.path,
+                        token,
+                        heartbeat,
+                        _local: local,
+                    });
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    if let Some(stale) = self.stale_contents().await {
+                        self.take_over(&stale).await;
+                        continue;
+                    }
+                    if Instant::now() >= deadline {
+                        return Err(DatabaseError::SyncError(format!(
+                            "Sync database is locked by another device ({})",
+                            self.path.display()
+                        )));
+                    }
+                    tokio::time::sleep(RETRY_INTERVAL).await;
+                }
+                Err(e) => {
+                    return Err(DatabaseError::ConnectionFailed(format!(
+                        "Failed to create lock file {}: {}",
+                        self.path.display(),
+                        e
+                    )))
+                }
+            }
+        }
+    }
+
+    /// Contents of the lock file once they have not changed for `stale_after`
+    async fn stale_contents(&self) -> Option<String> {
+        let contents = read_lock(&self.path).await?;
+        self.observed_lock
+            .unchanged_for(&contents, self.stale_after)
+            .then_some(contents)
+    }
+
+    /// Remove a stale lock file, unless it changed since it was judged stale
+    ///
+    /// Devices take over one at a time through a claim file, and the lock is renamed to a name no
+    /// other device uses before it is removed; a lock that came back to life is put back.
+    async fn take_over(&self, stale: &str) {
+        self.observed_lock.reset();
+
+        let claim = self.path.with_extension("lock.takeover");
+        let created = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&claim)
+            .await;
+        match created {
+            Ok(mut file) => {
+                let token = Uuid::new_v4().to_string();
+                file.write_all(token.as_bytes()).await.ok();
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                // A device that crashed while taking over leaves its claim behind
+                let abandoned = read_lock(&claim).await.is_some_and(|contents| {
+                    self.observed_claim
+                        .unchanged_for(&contents, self.stale_after)
+                });
+                if abandoned {
+                    self.observed_claim.reset();
+                    tokio::fs::remove_file(&claim).await.ok();
+                }
+                return;
+            }
+            Err(_) => return,
+        }
+
+        // Another device may have taken over and locked again since the lock was judged stale
+        if read_lock(&self.path).await.as_deref() == Some(stale) {
+            let moved = self
+                .path
+                .with_extension(format!("lock.stale-{}", Uuid::new_v4()));
+            if tokio::fs::rename(&self.path, &moved).await.is_ok() {
+                if read_lock(&moved).await.as_deref() == Some(stale) {
+                    eprintln!("[WARN] Removed stale sync lock {}", self.path.display());
+                } else if tokio::fs::hard_link(&moved, &self.path).await.is_err() {
+                    eprintln!(
+                        "[WARN] Could not put back sync lock {} that came back to life",
+                        self.path.display()
+                    );
+                }
+                tokio::fs::remove_file(&moved).await.ok();
+            }
+        }
+
+        tokio::fs::remove_file(&claim).await.ok();
+    }
+}
+
+/// Contents a file was last seen with and since when, timed with this device's clock
+#[derive(Default)]
+struct Observed(std::sync::Mutex<Option<(String, Instant)>>);
+
+impl Observed {
+    /// Whether the contents stayed the same for `after`; a change starts the wait over
+    fn unchanged_for(&self, contents: &str, after: Duration) -> bool {
+        let mut observed = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        match observed.as_ref() {
+            Some((seen, since)) if seen == contents => since.elapsed() > after,
+            _ => {
+                *observed = Some((contents.to_string(), Instant::now()));
+                false
+            }
+        }
+    }
+
+    fn reset(&self) {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+}
+
+impl Drop for WriteLockGuard<'_> {
+    fn drop(&mut self) {
+        self.heartbeat.abort();
+        let owned = std::fs::read_to_string(self.path)
+            .is_ok_and(|contents| owns_lock(&contents, &self.token));
+        if owned {
+            let _ = std::fs::remove_file(self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_lock() -> WriteLock {
+        let dir = std::env::temp_dir().join(format!("kerminal-lock-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        WriteLock::new(dir.join("sync.db.lock"))
+    }
+
+    #[tokio::test]
+    async fn test_guard_removes_only_its_own_lock() {
+        let lock = temp_lock();
+
+        drop(lock.acquire().await.unwrap());
+        assert!(!lock.path.exists());
+
+        // Another device took the lock over while this one was stalled
+        let guard = lock.acquire().await.unwrap();
+        std::fs::write(&lock.path, lock_contents("other-device", 0)).unwrap();
+        drop(guard);
+        assert!(lock.path.exists());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_contenders_never_hold_a_taken_over_lock_together() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        for _ in 0..5 {
+            let path = temp_lock().path;
+            // Left behind by a device that crashed while holding it
+            std::fs::write(&path, lock_contents("crashed-device", 3)).unwrap();
+
+            let holders = Arc::new(AtomicUsize::new(0));
+            let contenders = (0..3).map(|_| {
+                let path = path.clone();
+                let mut lock = WriteLock::new(path.clone());
+                lock.stale_after = Duration::from_millis(150);
+                lock.heartbeat_interval = Duration::from_millis(20);
+                let holders = holders.clone();
+                tokio::spawn(async move {
+                    let guard = lock.acquire().await.unwrap();
+                    assert_eq!(holders.fetch_add(1, Ordering::SeqCst), 0);
+                    // Held well past the stale threshold; the heartbeat keeps it live
+                    tokio::time::sleep(Duration::from_millis(300)).await;
+                    let contents = std::fs::read_to_string(&path).unwrap();
+                    assert!(owns_lock(&contents, &guard.token));
+                    holders.fetch_sub(1, Ordering::SeqCst);
+                })
+            });
+
+            for contender in contenders.collect::<Vec<_>>() {
+                contender.await.unwrap();
+            }
+            assert!(!path.exists());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_refreshes_held_lock() {
+        let lock = temp_lock();
+        let interval = Duration::from_millis(50);
+        std::fs::write(&lock.path, "token").unwrap();
+        let task = tokio::spawn(heartbeat(lock.path.clone(), "token".to_string(), interval));
+
+        tokio::time::sleep(interval * 3).await;
+        let contents = std::fs::read_to_string(&lock.path).unwrap();
+        assert!(owns_lock(&contents, "token"));
+        assert_ne!(contents, "token");
+
+        // Once the lock is lost the heartbeat stops rather than writing over it
+        std::fs::write(&lock.path, lock_contents("other-device", 0)).unwrap();
+        tokio::time::sleep(interval * 3).await;
+        assert!(task.is_finished());
+        assert!(owns_lock(
+            &std::fs::read_to_string(&lock.path).unwrap(),
+            "other-device"
+        ));
+    }
+}
@@ -0,0 +1,755 @@
+mod lock;
+mod sync;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::{Row, SqlitePool};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+use crate::database::{
+    error::{DatabaseError, DatabaseResult},
+    providers::sync_schema::{
+        missing_columns, ColumnKind, CHANGE_SEQUENCE, CHANGE_SEQ_COLUMN, SCHEMA_TABLES,
+    },
+    traits_sync::{ChangeNotifications, RecordChanges, SyncTarget, SEALED_RECORDS_TABLE},
+};
+
+/// Bound values allowed in one statement by SQLite
+pub(crate) const MAX_BIND_PARAMS: usize = 32_766;
+
+fn column_type(kind: ColumnKind) -> &'static str {
+    match kind {
+        ColumnKind::Text | ColumnKind::Timestamp => "TEXT",
+        ColumnKind::Integer => "INTEGER",
+        ColumnKind::Boolean => "BOOLEAN",
+    }
+}
+
+use lock::WriteLock;
+
+/// SQLite file used as a sync target, typically on a network share
+///
+/// SQLite's own locks are unreliable over SMB/NFS, so every write also holds a lock
+/// file next to the database, and the rollback journal is used instead of WAL.
+pub struct SQLiteTargetProvider {
+    database_path: PathBuf,
+    pool: Option<Arc<RwLock<SqlitePool>>>,
+    write_lock: WriteLock,
+}
+
+impl SQLiteTargetProvider {
+    pub fn new(database_path: impl Into<PathBuf>) -> Self {
+        let database_path = database_path.into();
+        let mut lock_path = database_path.clone().into_os_string();
+        lock_path.push(".lock");
+
+        Self {
+            database_path,
+            pool: None,
+            write_lock: WriteLock::new(lock_path.into()),
+        }
+    }
+
+    pub(crate) fn get_pool(&self) -> DatabaseResult<&Arc<RwLock<SqlitePool>>> {
+        self.pool
+            .as_ref()
+            .ok_or_else(|| DatabaseError::ConnectionFailed("Database not connected".to_string()))
+    }
+
+    async fn open_pool(&mut self, options: SqliteConnectOptions) -> DatabaseResult<()> {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(4)
+            .connect_with(options)
+            .await
+            .map_err(|e| DatabaseError::ConnectionFailed(e.to_string()))?;
+
+        self.pool = Some(Arc::new(RwLock::new(pool)));
+        Ok(())
+    }
+
+    /// Lock the database file against writers on other devices
+    pub(crate) async fn lock(&self) -> DatabaseResult<lock::WriteLockGuard<'_>> {
+        self.write_lock.acquire().await
+    }
+
+    /// Create sync tables in the SQLite file, with the columns of the synced models
+    async fn create_sync_tables(&self) -> DatabaseResult<()> {
+        let pool = self.get_pool()?;
+        let pool = pool.read().await;
+
+        let tables = vec![
+            r#"
+            CREATE TABLE IF NOT EXISTS ssh_profiles (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                host TEXT NOT NULL,
+                port INTEGER NOT NULL,
+                username TEXT NOT NULL,
+                group_id TEXT,
+                auth_method TEXT NOT NULL,
+                auth_data TEXT NOT NULL,
+                description TEXT,
+                color TEXT,
+                timeout INTEGER,
+                keep_alive BOOLEAN NOT NULL DEFAULT 1,
+                compression BOOLEAN NOT NULL DEFAULT 0,
+                proxy TEXT,
+                command TEXT,
+                working_dir TEXT,
+                env TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                device_id TEXT NOT NULL,
+                version INTEGER NOT NULL DEFAULT 1,
+                sync_status TEXT NOT NULL DEFAULT 'Synced',
+                deleted_at TEXT
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS ssh_groups (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT,
+                color TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                device_id TEXT NOT NULL,
+                version INTEGER NOT NULL DEFAULT 1,
+                sync_status TEXT NOT NULL DEFAULT 'Synced',
+                deleted_at TEXT
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS ssh_keys (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                key_type TEXT NOT NULL,
+                private_key TEXT NOT NULL,
+                public_key TEXT,
+                passphrase TEXT,
+                fingerprint TEXT NOT NULL,
+                description TEXT,
+                last_used TEXT,
+                hardware TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                device_id TEXT NOT NULL,
+                version INTEGER NOT NULL DEFAULT 1,
+                sync_status TEXT NOT NULL DEFAULT 'Synced',
+                deleted_at TEXT
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS ssh_tunnels (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT,
+                profile_id TEXT NOT NULL,
+                tunnel_type TEXT NOT NULL,
+                local_host TEXT NOT NULL,
+                local_port INTEGER NOT NULL,
+                remote_host TEXT,
+                remote_port INTEGER,
+                auto_start BOOLEAN NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                device_id TEXT NOT NULL,
+                version INTEGER NOT NULL DEFAULT 1,
+                sync_status TEXT NOT NULL DEFAULT 'Synced',
+                deleted_at TEXT
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS saved_commands (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT,
+                command TEXT NOT NULL,
+                group_id TEXT,
+                tags TEXT,
+                is_favorite BOOLEAN NOT NULL DEFAULT 0,
+                usage_count INTEGER NOT NULL DEFAULT 0,
+                last_used_at TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                device_id TEXT NOT NULL,
+                version INTEGER NOT NULL DEFAULT 1,
+                sync_status TEXT NOT NULL DEFAULT 'Synced',
+                deleted_at TEXT
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS saved_command_groups (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT,
+                color TEXT,
+                icon TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                device_id TEXT NOT NULL,
+                version INTEGER NOT NULL DEFAULT 1,
+                sync_status TEXT NOT NULL DEFAULT 'Synced',
+                deleted_at TEXT
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS terminal_profiles (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                shell TEXT NOT NULL,
+                working_dir TEXT,
+                env TEXT,
+                icon TEXT,
+                color TEXT,
+                command TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                device_id TEXT NOT NULL,
+                version INTEGER NOT NULL DEFAULT 1,
+                sync_status TEXT NOT NULL DEFAULT 'Synced',
+                deleted_at TEXT
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS session_recordings (
+                id TEXT PRIMARY KEY,
+                terminal_id TEXT,
+                session_name TEXT NOT NULL,
+                terminal_type TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                ended_at TEXT,
+                duration_ms INTEGER,
+                file_path TEXT NOT NULL,
+                file_size INTEGER NOT NULL DEFAULT 0,
+                width INTEGER NOT NULL DEFAULT 80,
+                height INTEGER NOT NULL DEFAULT 24,
+                metadata TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                device_id TEXT NOT NULL,
+                version INTEGER NOT NULL DEFAULT 1,
+                sync_status TEXT NOT NULL DEFAULT 'Synced',
+                deleted_at TEXT
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS known_hosts (
+                id TEXT PRIMARY KEY,
+                marker TEXT,
+                hosts TEXT NOT NULL,
+                key_type TEXT NOT NULL,
+                public_key TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                device_id TEXT NOT NULL,
+                version INTEGER NOT NULL DEFAULT 1,
+                sync_status TEXT NOT NULL DEFAULT 'Synced',
+                deleted_at TEXT
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS sync_sealed_records (
+                id TEXT PRIMARY KEY,
+                table_name TEXT NOT NULL,
+                key_id TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                version INTEGER NOT NULL DEFAULT 1,
+                updated_at TEXT NOT NULL,
+                deleted_at TEXT
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS sync_keys (
+                id TEXT PRIMARY KEY,
+                salt TEXT NOT NULL,
+                wrapped_key TEXT NOT NULL,
+                version INTEGER NOT NULL DEFAULT 1,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS sync_sequence (
+                name TEXT PRIMARY KEY,
+                value INTEGER NOT NULL
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS sync_devices (
+                device_id TEXT PRIMARY KEY,
+                registered_at TEXT NOT NULL,
+                last_seen_at TEXT NOT NULL
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS sync_tombstone_acks (
+                table_name TEXT NOT NULL,
+                record_id TEXT NOT NULL,
+                device_id TEXT NOT NULL,
+                acknowledged_at TEXT NOT NULL,
+                PRIMARY KEY (table_name, record_id, device_id)
+            )
+            "#,
+            "CREATE INDEX IF NOT EXISTS idx_ssh_profiles_updated_at ON ssh_profiles (updated_at)",
+            "CREATE INDEX IF NOT EXISTS idx_ssh_groups_updated_at ON ssh_groups (updated_at)",
+            "CREATE INDEX IF NOT EXISTS idx_ssh_keys_updated_at ON ssh_keys (updated_at)",
+            "CREATE INDEX IF NOT EXISTS idx_ssh_tunnels_updated_at ON ssh_tunnels (updated_at)",
+            "CREATE INDEX IF NOT EXISTS idx_saved_commands_updated_at ON saved_commands (updated_at)",
+            "CREATE INDEX IF NOT EXISTS idx_saved_command_groups_updated_at ON saved_command_groups (updated_at)",
+            "CREATE INDEX IF NOT EXISTS idx_terminal_profiles_updated_at ON terminal_profiles (updated_at)",
+            "CREATE INDEX IF NOT EXISTS idx_session_recordings_updated_at ON session_recordings (updated_at)",
+            "CREATE INDEX IF NOT EXISTS idx_sync_sealed_records_updated_at ON sync_sealed_records (updated_at)",
+            "CREATE INDEX IF NOT EXISTS idx_sync_sealed_records_table_name ON sync_sealed_records (table_name)",
+        ];
+
+        let _lock = self.lock().await?;
+        for table_sql in tables {
+            sqlx::query(table_sql)
+                .execute(&*pool)
+                .await
+                .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+        }
+
+        sqlx::query("INSERT OR IGNORE INTO sync_sequence (name, value) VALUES (?, 0)")
+            .bind(CHANGE_SEQUENCE)
+            .execute(&*pool)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        self.add_missing_columns(&pool).await
+    }
+
+    /// Add the columns models gained after the tables were created
+    async fn add_missing_columns(&self, pool: &SqlitePool) -> DatabaseResult<()> {
+        for table in SCHEMA_TABLES {
+            let rows = sqlx::query("SELECT name FROM pragma_table_info(?)")
+                .bind(table)
+                .fetch_all(pool)
+                .await
+                .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+            let existing = rows
+                .iter()
+                .map(|row| row.try_get::<String, _>("name"))
+                .collect::<Result<HashSet<_>, _>>()
+                .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+            for column in missing_columns(table, &existing)? {
+                eprintln!("[INFO] Adding column {}.{} to SQLite", table, column.name);
+                sqlx::query(&format!(
+                    "ALTER TABLE {} ADD COLUMN {} {}",
+                    table,
+                    column.name,
+                    column_type(column.kind)
+                ))
+                .execute(pool)
+                .await
+                .map_err(|e| DatabaseError::MigrationError(e.to_string()))?;
+
+                if column == CHANGE_SEQ_COLUMN {
+                    sqlx::query(&format!(
+                        "CREATE INDEX IF NOT EXISTS idx_{}_change_seq ON {} (change_seq)",
+                        table, table
+                    ))
+                    .execute(pool)
+                    .await
+                    .map_err(|e| DatabaseError::MigrationError(e.to_string()))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SyncTarget for SQLiteTargetProvider {
+    async fn connect(&mut self) -> DatabaseResult<()> {
+        if let Some(parent) = self.database_path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| {
+                DatabaseError::ConnectionFailed(format!(
+                    "Failed to create database directory: {}",
+                    e
+                ))
+            })?;
+        }
+
+        // WAL needs shared memory, which network file systems do not provide
+        let options = SqliteConnectOptions::new()
+            .filename(&self.database_path)
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Delete)
+            .busy_timeout(Duration::from_secs(30));
+
+        self.open_pool(options).await?;
+        self.create_sync_tables().await?;
+        Ok(())
+    }
+
+    async fn connect_read_only(&mut self) -> DatabaseResult<bool> {
+        if !self.database_path.exists() {
+            return Ok(false);
+        }
+
+        let options = SqliteConnectOptions::new()
+            .filename(&self.database_path)
+            .read_only(true)
+            .busy_timeout(Duration::from_secs(30));
+        self.open_pool(options).await?;
+
+        let pool = self.get_pool()?;
+        let pool = pool.read().await;
+        sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'sync_sequence')",
+        )
+        .fetch_one(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))
+    }
+
+    async fn test_connection(&self) -> DatabaseResult<()> {
+        let pool = self.get_pool()?;
+        let pool = pool.read().await;
+
+        sqlx::query("SELECT 1")
+            .execute(&*pool)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn push_records(&self, table: &str, records: Vec<Value>) -> DatabaseResult<usize> {
+        sync::push_records(self, table, records).await
+    }
+
+    async fn pull_records(
+        &self,
+        table: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> DatabaseResult<Vec<Value>> {
+        sync::pull_records(self, table, since, None).await
+    }
+
+    async fn pull_changes(
+        &self,
+        table: &str,
+        cursor: Option<&str>,
+    ) -> DatabaseResult<RecordChanges> {
+        sync::pull_changes(self, table, cursor, None).await
+    }
+
+    async fn pull_sealed_records(
+        &self,
+        table: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> DatabaseResult<Vec<Value>> {
+        sync::pull_records(self, SEALED_RECORDS_TABLE, since, Some(table)).await
+    }
+
+    async fn pull_sealed_changes(
+        &self,
+        table: &str,
+        cursor: Option<&str>,
+    ) -> DatabaseResult<RecordChanges> {
+        sync::pull_changes(self, SEALED_RECORDS_TABLE, cursor, Some(table)).await
+    }
+
+    /// A shared file cannot announce changes, so it is polled by the scheduler
+    async fn watch_changes(&self) -> DatabaseResult<Option<ChangeNotifications>> {
+        Ok(None)
+    }
+
+    async fn get_record_versions(
+        &self,
+        table: &str,
+        ids: Vec<String>,
+    ) -> DatabaseResult<HashMap<String, u64>> {
+        sync::get_record_versions(self, table, ids).await
+    }
+
+    async fn register_device(&self, device_id: &str) -> DatabaseResult<()> {
+        sync::register_device(self, device_id).await
+    }
+
+    async fn acknowledge_tombstones(
+        &self,
+        table: &str,
+        device_id: &str,
+        ids: Vec<String>,
+    ) -> DatabaseResult<()> {
+        sync::acknowledge_tombstones(self, table, device_id, ids).await
+    }
+
+    async fn purge_acknowledged_tombstones(&self, table: &str) -> DatabaseResult<Vec<String>> {
+        sync::purge_acknowledged_tombstones(self, table).await
+    }
+
+    async fn clear_records(&self, table: &str) -> DatabaseResult<usize> {
+        sync::clear_records(self, table).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::providers::sync_schema::row_batches;
+    use serde_json::json;
+
+    fn temp_path() -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("kerminal-sqlite-target-{}", uuid::Uuid::new_v4()))
+            .join("sync.db")
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_restores_camel_case_types() {
+        let path = temp_path();
+        let mut provider = SQLiteTargetProvider::new(&path);
+        provider.connect().await.unwrap();
+
+        let tunnel = json!({
+            "id": "t1",
+            "name": "db",
+            "description": null,
+            "profileId": "p1",
+            "tunnelType": "Local",
+            "localHost": "127.0.0.1",
+            "localPort": 5432,
+            "remoteHost": "db.internal",
+            "remotePort": 5432,
+            "autoStart": true,
+            "createdAt": "2026-01-01T00:00:00Z",
+            "updatedAt": "2026-01-02T00:00:00.250+00:00",
+            "deviceId": "d1",
+            "version": 3,
+            "syncStatus": "Synced",
+            "deletedAt": null
+        });
+        provider
+            .push_records("ssh_tunnels", vec![tunnel.clone()])
+            .await
+            .unwrap();
+        assert!(!path.with_extension("db.lock").exists());
+
+        let pulled = provider.pull_records("ssh_tunnels", None).await.unwrap();
+        assert_eq!(pulled, vec![tunnel]);
+
+        let since = "2026-01-02T00:00:00.250Z".parse().unwrap();
+        assert!(provider
+            .pull_records("ssh_tunnels", Some(since))
+            .await
+            .unwrap()
+            .is_empty());
+
+        let versions = provider
+            .get_record_versions("ssh_tunnels", vec!["t1".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(versions.get("t1"), Some(&3));
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_push_batches_thousands_of_records() {
+        let path = temp_path();
+        let mut provider = SQLiteTargetProvider::new(&path);
+        provider.connect().await.unwrap();
+
+        let records: Vec<Value> = (0..5000)
+            .map(|i| {
+                json!({
+                    "id": format!("g{}", i),
+                    "name": format!("group {}", i),
+                    "description": null,
+                    "color": "#00ff00",
+                    "createdAt": "2026-01-01T00:00:00Z",
+                    "updatedAt": "2026-01-01T00:00:00Z",
+                    "deviceId": "d1",
+                    "version": 1,
+                    "syncStatus": "Synced",
+                    "deletedAt": null
+                })
+            })
+            .collect();
+
+        // Ten columns a row: 3276 rows fit in one statement, so two statements carry the push
+        let batches = row_batches("ssh_groups", &records, MAX_BIND_PARAMS).unwrap();
+        assert_eq!(batches.len(), 2);
+        assert!(batches
+            .iter()
+            .all(|batch| batch.columns.len() * batch.rows.len() <= MAX_BIND_PARAMS));
+
+        let pushed = provider
+            .push_records("ssh_groups", records.clone())
+            .await
+            .unwrap();
+        assert_eq!(pushed, 5000);
+        let changes = provider.pull_changes("ssh_groups", None).await.unwrap();
+        assert_eq!(changes.cursor, "5000");
+        assert_eq!(
+            provider
+                .pull_records("ssh_groups", None)
+                .await
+                .unwrap()
+                .len(),
+            5000
+        );
+
+        // Pushing again updates the same rows
+        assert_eq!(
+            provider.push_records("ssh_groups", records).await.unwrap(),
+            5000
+        );
+        let changes = provider.pull_changes("ssh_groups", None).await.unwrap();
+        assert_eq!(changes.cursor, "10000");
+        assert_eq!(
+            provider
+                .pull_records("ssh_groups", None)
+                .await
+                .unwrap()
+                .len(),
+            5000
+        );
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_read_only_connection_leaves_target_untouched() {
+        let path = temp_path();
+        let mut provider = SQLiteTargetProvider::new(&path);
+        assert!(!provider.connect_read_only().await.unwrap());
+        assert!(!path.exists());
+
+        provider.connect().await.unwrap();
+        let group = json!({ "id": "g1", "name": "servers", "version": 1 });
+        provider
+            .push_records("ssh_groups", vec![group])
+            .await
+            .unwrap();
+
+        let mut reader = SQLiteTargetProvider::new(&path);
+        assert!(reader.connect_read_only().await.unwrap());
+        assert_eq!(
+            reader.pull_records("ssh_groups", None).await.unwrap().len(),
+            1
+        );
+        let other = json!({ "id": "g2", "name": "other", "version": 1 });
+        assert!(reader
+            .push_records("ssh_groups", vec![other])
+            .await
+            .is_err());
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_pull_changes_follows_change_sequence() {
+        let path = temp_path();
+        let mut provider = SQLiteTargetProvider::new(&path);
+        provider.connect().await.unwrap();
+
+        let group = |id: &str, updated_at: &str| {
+            json!({
+                "id": id,
+                "name": id,
+                "description": null,
+                "color": null,
+                "createdAt": "2026-01-01T00:00:00Z",
+                "updatedAt": updated_at,
+                "deviceId": "d1",
+                "version": 1,
+                "syncStatus": "Synced",
+                "deletedAt": null
+            })
+        };
+
+        provider
+            .push_records("ssh_groups", vec![group("g1", "2026-01-02T00:00:00Z")])
+            .await
+            .unwrap();
+        let first = provider.pull_changes("ssh_groups", None).await.unwrap();
+        assert_eq!(first.records, vec![group("g1", "2026-01-02T00:00:00Z")]);
+
+        // A write stamped by a clock running behind is still picked up
+        provider
+            .push_records("ssh_groups", vec![group("g2", "2000-01-01T00:00:00Z")])
+            .await
+            .unwrap();
+        let second = provider
+            .pull_changes("ssh_groups", Some(&first.cursor))
+            .await
+            .unwrap();
+        assert_eq!(second.records, vec![group("g2", "2000-01-01T00:00:00Z")]);
+
+        let unchanged = provider
+            .pull_changes("ssh_groups", Some(&second.cursor))
+            .await
+            .unwrap();
+        assert!(unchanged.records.is_empty());
+        assert_eq!(unchanged.cursor, second.cursor);
+
+        // A cursor from another target starts over
+        let restarted = provider
+            .pull_changes("ssh_groups", Some("999999"))
+            .await
+            .unwrap();
+        assert_eq!(restarted.records.len(), 2);
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_connect_adds_columns_of_new_model_fields() {
+        let path = temp_path();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        {
+            let options = SqliteConnectOptions::new()
+                .filename(&path)
+                .create_if_missing(true);
+            let pool = SqlitePool::connect_with(options).await.unwrap();
+            sqlx::query(
+                "CREATE TABLE ssh_groups (id TEXT PRIMARY KEY, name TEXT NOT NULL, created_at TEXT NOT NULL, updated_at TEXT NOT NULL, device_id TEXT NOT NULL, version INTEGER NOT NULL DEFAULT 1, sync_status TEXT NOT NULL DEFAULT 'Synced')",
+            )
+            .execute(&pool)
+            .await
+            .unwrap();
+            pool.close().await;
+        }
+
+        let mut provider = SQLiteTargetProvider::new(&path);
+        provider.connect().await.unwrap();
+
+        let group = json!({
+            "id": "g1",
+            "name": "servers",
+            "description": "added later",
+            "color": null,
+            "createdAt": "2026-01-01T00:00:00Z",
+            "updatedAt": "2026-01-01T00:00:00Z",
+            "deviceId": "d1",
+            "version": 1,
+            "syncStatus": "Synced",
+            "deletedAt": null
+        });
+        provider
+            .push_records("ssh_groups", vec![group.clone()])
+            .await
+            .unwrap();
+        assert_eq!(
+            provider.pull_records("ssh_groups", None).await.unwrap(),
+            vec![group]
+        );
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
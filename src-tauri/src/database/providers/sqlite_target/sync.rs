@@ -0,0 +1,496 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::sqlite::{SqliteArguments, SqliteRow};
+use sqlx::{Column, Row, Sqlite, TypeInfo, ValueRef};
+use std::collections::HashMap;
+
+use crate::database::{
+    error::{DatabaseError, DatabaseResult},
+    providers::sync_schema::{
+        parse_cursor, row_batches, where_clause, RowBatch, CHANGE_SEQUENCE, CHANGE_SEQ_COLUMN,
+    },
+    traits_sync::RecordChanges,
+};
+
+use super::{SQLiteTargetProvider, MAX_BIND_PARAMS};
+
+/// Columns holding serialized structs or enums, returned as JSON rather than text
+const JSON_COLUMNS: &[&str] = &[
+    "auth_method",
+    "auth_data",
+    "proxy",
+    "env",
+    "key_type",
+    "tunnel_type",
+    "ignore_patterns",
+];
+
+fn to_camel_case(s: &str) -> String {
+    let mut result = String::new();
+    let mut upper = false;
+    for ch in s.chars() {
+        if ch == '_' {
+            upper = true;
+        } else if upper {
+            result.extend(ch.to_uppercase());
+            upper = false;
+        } else {
+            result.push(ch);
+        }
+    }
+    result
+}
+
+/// Multi-row upsert of a batch, numbering its rows in the change sequence after `seq`
+fn upsert_sql(table: &str, batch: &RowBatch, seq: i64) -> String {
+    let placeholders = vec!["?"; batch.columns.len()].join(", ");
+    let values: Vec<String> = (1..=batch.rows.len() as i64)
+        .map(|row| format!("({}, {})", placeholders, seq + row))
+        .collect();
+    let updates: Vec<String> = batch
+        .columns
+        .iter()
+        .filter(|c| **c != "id")
+        .chain(&["change_seq"])
+        .map(|c| format!("{} = excluded.{}", c, c))
+        .collect();
+
+    format!(
+        "INSERT INTO {} ({}, change_seq) VALUES {} ON CONFLICT(id) DO UPDATE SET {}",
+        table,
+        batch.columns.join(", "),
+        values.join(", "),
+        updates.join(", ")
+    )
+}
+
+/// Upsert records with multi-row statements, all in one transaction
+/// Every row gets the next change sequence number, so pulls can resume where they left off
+pub async fn push_records(
+    provider: &SQLiteTargetProvider,
+    table: &str,
+    records: Vec<Value>,
+) -> DatabaseResult<usize> {
+    if records.is_empty() {
+        return Ok(0);
+    }
+
+    let batches = row_batches(table, &records, MAX_BIND_PARAMS)?;
+    let total: usize = batches.iter().map(|batch| batch.rows.len()).sum();
+
+    let pool = provider.get_pool()?;
+    let pool = pool.read().await;
+
+    let _lock = provider.lock().await?;
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    let last_seq: i64 = sqlx::query_scalar(
+        "UPDATE sync_sequence SET value = value + ? WHERE name = ? RETURNING value",
+    )
+    .bind(total as i64)
+    .bind(CHANGE_SEQUENCE)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+    let mut seq = last_seq - total as i64;
+
+    let mut count = 0;
+
+    for batch in &batches {
+        let sql = upsert_sql(table, batch, seq);
+        seq += batch.rows.len() as i64;
+
+        let mut query = sqlx::query(&sql);
+        for row in &batch.rows {
+            for value in row {
+                query = bind_value(query, value);
+            }
+        }
+
+        query
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        count += batch.rows.len();
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    Ok(count)
+}
+
+pub async fn pull_records(
+    provider: &SQLiteTargetProvider,
+    table: &str,
+    since: Option<DateTime<Utc>>,
+    table_name: Option<&str>,
+) -> DatabaseResult<Vec<Value>> {
+    let pool = provider.get_pool()?;
+    let pool = pool.read().await;
+
+    // Timestamps are stored as RFC 3339 text, which only orders correctly once normalized
+    let mut conditions = Vec::new();
+    if since.is_some() {
+        conditions.push("julianday(updated_at) > julianday(?)");
+    }
+    if table_name.is_some() {
+        conditions.push("table_name = ?");
+    }
+    let sql = format!(
+        "SELECT * FROM {}{} ORDER BY julianday(updated_at) ASC",
+        table,
+        where_clause(&conditions)
+    );
+
+    let mut query = sqlx::query(&sql);
+    if let Some(since_time) = since {
+        query = query.bind(since_time.to_rfc3339());
+    }
+    if let Some(name) = table_name {
+        query = query.bind(name);
+    }
+    let rows = query
+        .fetch_all(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    rows.iter().map(row_to_json).collect()
+}
+
+/// Pull the rows written after a change sequence number
+/// Rows written before the sequence existed have none, and only come with a full pull
+pub async fn pull_changes(
+    provider: &SQLiteTargetProvider,
+    table: &str,
+    cursor: Option<&str>,
+    table_name: Option<&str>,
+) -> DatabaseResult<RecordChanges> {
+    let pool = provider.get_pool()?;
+    let pool = pool.read().await;
+
+    let current: i64 = sqlx::query_scalar("SELECT value FROM sync_sequence WHERE name = ?")
+        .bind(CHANGE_SEQUENCE)
+        .fetch_one(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    // A cursor past the counter belongs to a sync database that has since been recreated
+    let after = parse_cursor(cursor).filter(|seq| *seq <= current);
+
+    let mut conditions = Vec::new();
+    if after.is_some() {
+        conditions.push("change_seq > ?");
+    }
+    if table_name.is_some() {
+        conditions.push("table_name = ?");
+    }
+    let sql = format!(
+        "SELECT * FROM {}{} ORDER BY change_seq ASC",
+        table,
+        where_clause(&conditions)
+    );
+
+    let mut query = sqlx::query(&sql);
+    if let Some(seq) = after {
+        query = query.bind(seq);
+    }
+    if let Some(name) = table_name {
+        query = query.bind(name);
+    }
+    let rows = query
+        .fetch_all(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    let mut last_seq = after.unwrap_or(0);
+    let mut records = Vec::with_capacity(rows.len());
+    for row in &rows {
+        let seq: Option<i64> = row
+            .try_get(CHANGE_SEQ_COLUMN.name)
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+        last_seq = last_seq.max(seq.unwrap_or(0));
+        records.push(row_to_json(row)?);
+    }
+
+    Ok(RecordChanges {
+        records,
+        cursor: last_seq.to_string(),
+    })
+}
+
+pub async fn get_record_versions(
+    provider: &SQLiteTargetProvider,
+    table: &str,
+    ids: Vec<String>,
+) -> DatabaseResult<HashMap<String, u64>> {
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let pool = provider.get_pool()?;
+    let pool = pool.read().await;
+
+    let placeholders: Vec<String> = (0..ids.len()).map(|_| "?".to_string()).collect();
+    let sql = format!(
+        "SELECT id, version FROM {} WHERE id IN ({})",
+        table,
+        placeholders.join(", ")
+    );
+
+    let mut query = sqlx::query(&sql);
+    for id in ids {
+        query = query.bind(id);
+    }
+
+    let rows = query
+        .fetch_all(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    let mut versions = HashMap::new();
+    for row in rows {
+        let id: String = row
+            .try_get("id")
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+        let version: i64 = row
+            .try_get("version")
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+        versions.insert(id, version as u64);
+    }
+
+    Ok(versions)
+}
+
+/// Register a device, or refresh its last seen time
+pub async fn register_device(
+    provider: &SQLiteTargetProvider,
+    device_id: &str,
+) -> DatabaseResult<()> {
+    let pool = provider.get_pool()?;
+    let pool = pool.read().await;
+    let now = Utc::now().to_rfc3339();
+
+    let _lock = provider.lock().await?;
+    sqlx::query(
+        r#"
+        INSERT INTO sync_devices (device_id, registered_at, last_seen_at) VALUES (?, ?, ?)
+        ON CONFLICT(device_id) DO UPDATE SET last_seen_at = excluded.last_seen_at
+        "#,
+    )
+    .bind(device_id)
+    .bind(&now)
+    .bind(&now)
+    .execute(&*pool)
+    .await
+    .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Record that a device has seen the given tombstones
+pub async fn acknowledge_tombstones(
+    provider: &SQLiteTargetProvider,
+    table: &str,
+    device_id: &str,
+    ids: Vec<String>,
+) -> DatabaseResult<()> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    let pool = provider.get_pool()?;
+    let pool = pool.read().await;
+    let now = Utc::now().to_rfc3339();
+
+    let _lock = provider.lock().await?;
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    for chunk in ids.chunks(MAX_BIND_PARAMS / 4) {
+        let values = vec!["(?, ?, ?, ?)"; chunk.len()].join(", ");
+        let sql = format!(
+            "INSERT OR IGNORE INTO sync_tombstone_acks (table_name, record_id, device_id, acknowledged_at) VALUES {}",
+            values
+        );
+
+        let mut query = sqlx::query(&sql);
+        for id in chunk {
+            query = query.bind(table).bind(id).bind(device_id).bind(&now);
+        }
+
+        query
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Delete tombstones that every registered device has acknowledged
+pub async fn purge_acknowledged_tombstones(
+    provider: &SQLiteTargetProvider,
+    table: &str,
+) -> DatabaseResult<Vec<String>> {
+    let pool = provider.get_pool()?;
+    let pool = pool.read().await;
+
+    let sql = format!(
+        r#"
+        SELECT t.id FROM {} t
+        WHERE t.deleted_at IS NOT NULL
+          AND (SELECT COUNT(*) FROM sync_tombstone_acks a
+               JOIN sync_devices d ON d.device_id = a.device_id
+               WHERE a.table_name = ? AND a.record_id = t.id)
+              >= (SELECT COUNT(*) FROM sync_devices)
+        "#,
+        table
+    );
+
+    let _lock = provider.lock().await?;
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    let rows = sqlx::query(&sql)
+        .bind(table)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    let ids = rows
+        .iter()
+        .map(|row| row.try_get::<String, _>("id"))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    if ids.is_empty() {
+        return Ok(ids);
+    }
+
+    let placeholders: Vec<String> = (0..ids.len()).map(|_| "?".to_string()).collect();
+    let delete_records = format!(
+        "DELETE FROM {} WHERE deleted_at IS NOT NULL AND id IN ({})",
+        table,
+        placeholders.join(", ")
+    );
+    let delete_acks = format!(
+        "DELETE FROM sync_tombstone_acks WHERE table_name = ? AND record_id IN ({})",
+        placeholders.join(", ")
+    );
+
+    let mut query = sqlx::query(&delete_records);
+    for id in &ids {
+        query = query.bind(id);
+    }
+    query
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    let mut query = sqlx::query(&delete_acks).bind(table);
+    for id in &ids {
+        query = query.bind(id);
+    }
+    query
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    Ok(ids)
+}
+
+/// Delete every record of a table
+pub async fn clear_records(provider: &SQLiteTargetProvider, table: &str) -> DatabaseResult<usize> {
+    let pool = provider.get_pool()?;
+    let pool = pool.read().await;
+
+    let _lock = provider.lock().await?;
+    let result = sqlx::query(&format!("DELETE FROM {}", table))
+        .execute(&*pool)
+        .await
+        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+    Ok(result.rows_affected() as usize)
+}
+
+fn bind_value<'q>(
+    query: sqlx::query::Query<'q, Sqlite, SqliteArguments<'q>>,
+    value: &Value,
+) -> sqlx::query::Query<'q, Sqlite, SqliteArguments<'q>> {
+    match value {
+        Value::Null => query.bind(Option::<String>::None),
+        Value::Bool(b) => query.bind(*b),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                query.bind(i)
+            } else if let Some(u) = n.as_u64() {
+                query.bind(u as i64)
+            } else if let Some(f) = n.as_f64() {
+                query.bind(f)
+            } else {
+                query.bind(n.to_string())
+            }
+        }
+        Value::String(s) => query.bind(s.clone()),
+        Value::Array(_) | Value::Object(_) => query.bind(value.to_string()),
+    }
+}
+
+/// Convert a row to the camelCase JSON the models deserialize from
+fn row_to_json(row: &SqliteRow) -> DatabaseResult<Value> {
+    let mut obj = serde_json::Map::new();
+
+    for (idx, col) in row.columns().iter().enumerate() {
+        let name = col.name();
+        if name == CHANGE_SEQ_COLUMN.name {
+            continue;
+        }
+
+        let raw = row
+            .try_get_raw(idx)
+            .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+
+        let value = if raw.is_null() {
+            Value::Null
+        } else if col.type_info().name() == "BOOLEAN" {
+            Value::Bool(row.try_get::<bool, _>(idx).unwrap_or_default())
+        } else {
+            match raw.type_info().name() {
+                "INTEGER" => Value::from(row.try_get::<i64, _>(idx).unwrap_or_default()),
+                "REAL" => Value::from(row.try_get::<f64, _>(idx).unwrap_or_default()),
+                _ => {
+                    let text: String = row
+                        .try_get(idx)
+                        .map_err(|e| DatabaseError::QueryFailed(e.to_string()))?;
+                    if JSON_COLUMNS.contains(&name) {
+                        serde_json::from_str(&text).unwrap_or(Value::String(text))
+                    } else {
+                        Value::String(text)
+                    }
+                }
+            }
+        };
+
+        obj.insert(to_camel_case(name), value);
+    }
+
+    Ok(Value::Object(obj))
+}
@@ -0,0 +1,415 @@
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::database::{
+    error::{DatabaseError, DatabaseResult},
+    traits_sync::{SEALED_RECORDS_TABLE, SYNC_KEYS_TABLE},
+};
+
+/// Storage class of a synced column, mapped to a concrete type by each SQL provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    Text,
+    Integer,
+    Boolean,
+    Timestamp,
+}
+
+/// A column the SQL sync targets accept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncColumn {
+    pub name: &'static str,
+    pub kind: ColumnKind,
+}
+
+const fn column(name: &'static str, kind: ColumnKind) -> SyncColumn {
+    SyncColumn { name, kind }
+}
+
+use ColumnKind::{Boolean, Integer, Text, Timestamp};
+
+/// Columns every synced model gets from `BaseModel`
+const BASE_COLUMNS: &[SyncColumn] = &[
+    column("id", Text),
+    column("created_at", Timestamp),
+    column("updated_at", Timestamp),
+    column("device_id", Text),
+    column("version", Integer),
+    column("sync_status", Text),
+    column("deleted_at", Timestamp),
+];
+
+const SSH_PROFILE_COLUMNS: &[SyncColumn] = &[
+    column("name", Text),
+    column("host", Text),
+    column("port", Integer),
+    column("username", Text),
+    column("group_id", Text),
+    column("auth_method", Text),
+    column("auth_data", Text),
+    column("description", Text),
+    column("color", Text),
+    column("timeout", Integer),
+    column("keep_alive", Boolean),
+    column("compression", Boolean),
+    column("proxy", Text),
+    column("command", Text),
+    column("working_dir", Text),
+    column("env", Text),
+];
+
+const SSH_GROUP_COLUMNS: &[SyncColumn] = &[
+    column("name", Text),
+    column("description", Text),
+    column("color", Text),
+];
+
+const SSH_KEY_COLUMNS: &[SyncColumn] = &[
+    column("name", Text),
+    column("key_type", Text),
+    column("private_key", Text),
+    column("public_key", Text),
+    column("passphrase", Text),
+    column("fingerprint", Text),
+    column("description", Text),
+    column("last_used", Timestamp),
+    column("hardware", Text),
+];
+
+const SSH_TUNNEL_COLUMNS: &[SyncColumn] = &[
+    column("name", Text),
+    column("description", Text),
+    column("profile_id", Text),
+    column("tunnel_type", Text),
+    column("local_host", Text),
+    column("local_port", Integer),
+    column("remote_host", Text),
+    column("remote_port", Integer),
+    column("auto_start", Boolean),
+];
+
+const SAVED_COMMAND_COLUMNS: &[SyncColumn] = &[
+    column("name", Text),
+    column("description", Text),
+    column("command", Text),
+    column("group_id", Text),
+    column("tags", Text),
+    column("is_favorite", Boolean),
+    column("usage_count", Integer),
+    column("last_used_at", Timestamp),
+];
+
+const SAVED_COMMAND_GROUP_COLUMNS: &[SyncColumn] = &[
+    column("name", Text),
+    column("description", Text),
+    column("color", Text),
+    column("icon", Text),
+];
+
+const TERMINAL_PROFILE_COLUMNS: &[SyncColumn] = &[
+    column("name", Text),
+    column("shell", Text),
+    column("working_dir", Text),
+    column("env", Text),
+    column("icon", Text),
+    column("color", Text),
+    column("command", Text),
+];
+
+const SESSION_RECORDING_COLUMNS: &[SyncColumn] = &[
+    column("terminal_id", Text),
+    column("session_name", Text),
+    column("terminal_type", Text),
+    column("started_at", Timestamp),
+    column("ended_at", Timestamp),
+    column("duration_ms", Integer),
+    column("file_path", Text),
+    column("file_size", Integer),
+    column("width", Integer),
+    column("height", Integer),
+    column("metadata", Text),
+];
+
+const KNOWN_HOST_COLUMNS: &[SyncColumn] = &[
+    column("marker", Text),
+    column("hosts", Text),
+    column("key_type", Text),
+    column("public_key", Text),
+];
+
+const SEALED_RECORD_COLUMNS: &[SyncColumn] = &[
+    column("id", Text),
+    column("table_name", Text),
+    column("key_id", Text),
+    column("payload", Text),
+    column("version", Integer),
+    column("updated_at", Timestamp),
+    column("deleted_at", Timestamp),
+];
+
+/// Sync keys keep their timestamps as RFC 3339 text on every target
+const SYNC_KEY_COLUMNS: &[SyncColumn] = &[
+    column("id", Text),
+    column("salt", Text),
+    column("wrapped_key", Text),
+    column("version", Integer),
+    column("created_at", Text),
+    column("updated_at", Text),
+];
+
+/// Position of a row in the target's change sequence, assigned by the target on every write
+/// Kept out of the pushable columns, so records can never set it themselves
+pub const CHANGE_SEQ_COLUMN: SyncColumn = column("change_seq", Integer);
+
+/// Name of the counter row in a target's `sync_sequence` table
+pub const CHANGE_SEQUENCE: &str = "changes";
+
+/// Columns a table accepts, the only ones pushes may write
+/// Tables outside the sync schema are rejected, so record keys never reach SQL unchecked
+pub fn table_columns(table: &str) -> DatabaseResult<Vec<SyncColumn>> {
+    let model_columns = match table {
+        "ssh_profiles" => SSH_PROFILE_COLUMNS,
+        "ssh_groups" => SSH_GROUP_COLUMNS,
+        "ssh_keys" => SSH_KEY_COLUMNS,
+        "ssh_tunnels" => SSH_TUNNEL_COLUMNS,
+        "saved_commands" => SAVED_COMMAND_COLUMNS,
+        "saved_command_groups" => SAVED_COMMAND_GROUP_COLUMNS,
+        "terminal_profiles" => TERMINAL_PROFILE_COLUMNS,
+        "session_recordings" => SESSION_RECORDING_COLUMNS,
+        "known_hosts" => KNOWN_HOST_COLUMNS,
+        SEALED_RECORDS_TABLE => return Ok(SEALED_RECORD_COLUMNS.to_vec()),
+        SYNC_KEYS_TABLE => return Ok(SYNC_KEY_COLUMNS.to_vec()),
+        _ => {
+            return Err(DatabaseError::ValidationError(format!(
+                "Table {} is not synced",
+                table
+            )))
+        }
+    };
+
+    Ok(BASE_COLUMNS.iter().chain(model_columns).copied().collect())
+}
+
+/// Every table whose columns are kept up to date with the models
+pub const SCHEMA_TABLES: &[&str] = &[
+    "ssh_groups",
+    "ssh_keys",
+    "ssh_profiles",
+    "ssh_tunnels",
+    "saved_command_groups",
+    "saved_commands",
+    "terminal_profiles",
+    "session_recordings",
+    "known_hosts",
+    SEALED_RECORDS_TABLE,
+    SYNC_KEYS_TABLE,
+];
+
+/// Columns of a table missing from a remote created by an older version
+pub fn missing_columns(table: &str, existing: &HashSet<String>) -> DatabaseResult<Vec<SyncColumn>> {
+    Ok(table_columns(table)?
+        .into_iter()
+        .chain([CHANGE_SEQ_COLUMN])
+        .filter(|column| !existing.contains(column.name))
+        .collect())
+}
+
+/// Sequence number a pull resumes after
+/// Cursors of another target or format start over with a full pull instead of failing the sync
+pub fn parse_cursor(cursor: Option<&str>) -> Option<i64> {
+    let cursor = cursor?;
+    match cursor.parse::<i64>() {
+        Ok(seq) => Some(seq),
+        Err(_) => {
+            eprintln!("[WARN] Ignoring unrecognized sync cursor {}", cursor);
+            None
+        }
+    }
+}
+
+/// `WHERE` clause joining the conditions of a pull, empty when there are none
+pub fn where_clause<S: AsRef<str>>(conditions: &[S]) -> String {
+    if conditions.is_empty() {
+        return String::new();
+    }
+    let conditions: Vec<&str> = conditions.iter().map(AsRef::as_ref).collect();
+    format!(" WHERE {}", conditions.join(" AND "))
+}
+
+/// Records sharing the same columns, upserted with one multi-row statement
+#[derive(Debug)]
+pub struct RowBatch<'a> {
+    pub columns: Vec<&'static str>,
+    pub rows: Vec<Vec<&'a Value>>,
+}
+
+fn to_snake_case(s: &str) -> String {
+    let mut result = String::new();
+    for (i, ch) in s.chars().enumerate() {
+        if ch.is_uppercase() {
+            if i > 0 {
+                result.push('_');
+            }
+            result.push(ch.to_lowercase().next().unwrap());
+        } else {
+            result.push(ch);
+        }
+    }
+    result
+}
+
+/// Split records into batches of at most `max_params` bound values
+///
+/// Keys outside the table's columns are dropped. Records are grouped by the columns they
+/// carry, so an upsert never resets a column a record did not send, and only the last
+/// record pushed for an id is kept, as one statement cannot update a row twice.
+pub fn row_batches<'a>(
+    table: &str,
+    records: &'a [Value],
+    max_params: usize,
+) -> DatabaseResult<Vec<RowBatch<'a>>> {
+    let allowed = table_columns(table)?;
+    let mut ignored: BTreeSet<String> = BTreeSet::new();
+
+    let mut latest: HashMap<&'a str, usize> = HashMap::new();
+    for (index, record) in records.iter().enumerate() {
+        let id = record
+            .get("id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| DatabaseError::ValidationError("Record has no id".to_string()))?;
+        latest.insert(id, index);
+    }
+
+    let mut groups: Vec<RowBatch<'a>> = Vec::new();
+    for (index, record) in records.iter().enumerate() {
+        let obj = record
+            .as_object()
+            .ok_or_else(|| DatabaseError::QueryFailed("Expected JSON object".to_string()))?;
+        let id = obj.get("id").and_then(|v| v.as_str()).unwrap_or_default();
+        if latest.get(id) != Some(&index) {
+            continue;
+        }
+
+        let mut values: HashMap<&'static str, &'a Value> = HashMap::new();
+        for (key, value) in obj {
+            let name = to_snake_case(key);
+            match allowed.iter().find(|column| column.name == name) {
+                Some(column) => {
+                    values.insert(column.name, value);
+                }
+                None => {
+                    ignored.insert(key.clone());
+                }
+            }
+        }
+
+        // Whitelist order keeps the column lists of equal records identical
+        let columns: Vec<&'static str> = allowed
+            .iter()
+            .map(|column| column.name)
+            .filter(|name| values.contains_key(name))
+            .collect();
+        let row: Vec<&'a Value> = columns.iter().map(|name| values[name]).collect();
+
+        match groups.iter_mut().find(|group| group.columns == columns) {
+            Some(group) => group.rows.push(row),
+            None => groups.push(RowBatch {
+                columns,
+                rows: vec![row],
+            }),
+        }
+    }
+
+    if !ignored.is_empty() {
+        eprintln!(
+            "[WARN] Ignoring fields not synced to {}: {}",
+            table,
+            ignored.into_iter().collect::<Vec<_>>().join(", ")
+        );
+    }
+
+    let mut batches = Vec::new();
+    for group in groups {
+        let rows_per_batch = (max_params / group.columns.len().max(1)).max(1);
+        let mut rows = group.rows.into_iter().peekable();
+        while rows.peek().is_some() {
+            batches.push(RowBatch {
+                columns: group.columns.clone(),
+                rows: rows.by_ref().take(rows_per_batch).collect(),
+            });
+        }
+    }
+
+    Ok(batches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_unknown_tables_are_rejected() {
+        assert!(table_columns("ssh_groups; DROP TABLE ssh_keys").is_err());
+        assert!(table_columns("sync_devices").is_err());
+        assert!(table_columns("sync_sealed_records").is_ok());
+    }
+
+    #[test]
+    fn test_row_batches_drop_unknown_columns_and_group_records() {
+        let records = vec![
+            json!({ "id": "a", "name": "one", "color": null, "evil) VALUES (1); --": 1 }),
+            json!({ "id": "b", "name": "two" }),
+            json!({ "id": "c", "name": "three", "color": "#fff" }),
+        ];
+
+        let batches = row_batches("ssh_groups", &records, 1000).unwrap();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].columns, vec!["id", "name", "color"]);
+        assert_eq!(batches[0].rows.len(), 2);
+        assert_eq!(batches[1].columns, vec!["id", "name"]);
+        assert_eq!(batches[1].rows, vec![vec![&json!("b"), &json!("two")]]);
+    }
+
+    #[test]
+    fn test_row_batches_keep_last_record_per_id_and_respect_param_limit() {
+        let records: Vec<Value> = (0..10)
+            .map(|i| json!({ "id": format!("r{}", i % 5), "version": i }))
+            .collect();
+
+        let batches = row_batches("ssh_keys", &records, 4).unwrap();
+        assert_eq!(
+            batches.iter().map(|b| b.rows.len()).collect::<Vec<_>>(),
+            vec![2, 2, 1]
+        );
+        let versions: Vec<i64> = batches
+            .iter()
+            .flat_map(|b| b.rows.iter().map(|row| row[1].as_i64().unwrap()))
+            .collect();
+        assert_eq!(versions, vec![5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn test_parse_cursor() {
+        assert_eq!(parse_cursor(Some("42")), Some(42));
+        assert_eq!(parse_cursor(Some("sealed:42")), None);
+        assert_eq!(parse_cursor(None), None);
+    }
+
+    #[test]
+    fn test_missing_columns() {
+        let existing: HashSet<String> = table_columns("ssh_profiles")
+            .unwrap()
+            .iter()
+            .map(|c| c.name.to_string())
+            .filter(|name| !["command", "working_dir", "env"].contains(&name.as_str()))
+            .collect();
+
+        let missing: Vec<&str> = missing_columns("ssh_profiles", &existing)
+            .unwrap()
+            .iter()
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(missing, vec!["command", "working_dir", "env", "change_seq"]);
+    }
+}
@@ -23,8 +23,8 @@ use crate::models::{
     },
     ssh::{
         CreateSSHGroupRequest, CreateSSHKeyRequest, CreateSSHProfileRequest, DeleteGroupAction,
-        SSHGroup, SSHKey, SSHProfile, UpdateSSHGroupRequest, UpdateSSHKeyRequest,
-        UpdateSSHProfileRequest,
+        SSHCertificateAuthority, SSHGroup, SSHKey, SSHProfile, UpdateSSHGroupRequest,
+        UpdateSSHKeyRequest, UpdateSSHProfileRequest,
     },
     sync::SyncStats,
 };
@@ -834,6 +834,53 @@ impl DatabaseService {
         local_db.save_ssh_key(&key).await
     }
 
+    /// Save an SSH certificate authority, encrypting its signing key
+    pub async fn save_certificate_authority(
+        &self,
+        ca: &SSHCertificateAuthority,
+    ) -> DatabaseResult<()> {
+        let mut ca = ca.clone();
+
+        let mp_manager = self.master_password_manager.read().await;
+        ca.encrypt_fields(&*mp_manager)?;
+
+        let local_db = self.local_db.read().await;
+        local_db.save_certificate_authority(&ca).await
+    }
+
+    /// Get all SSH certificate authorities (signing keys stay encrypted)
+    pub async fn get_certificate_authorities(
+        &self,
+    ) -> DatabaseResult<Vec<SSHCertificateAuthority>> {
+        let local_db = self.local_db.read().await;
+        local_db.find_all_certificate_authorities().await
+    }
+
+    /// Get SSH certificate authority by ID with its signing key decrypted
+    pub async fn get_certificate_authority(
+        &self,
+        id: &str,
+    ) -> DatabaseResult<SSHCertificateAuthority> {
+        let local_db = self.local_db.read().await;
+        let mut ca = local_db
+            .find_certificate_authority_by_id(id)
+            .await?
+            .ok_or_else(|| {
+                DatabaseError::NotFound(format!("Certificate authority {} not found", id))
+            })?;
+
+        let mp_manager = self.master_password_manager.read().await;
+        ca.decrypt_fields(&*mp_manager)?;
+
+        Ok(ca)
+    }
+
+    /// Delete SSH certificate authority
+    pub async fn delete_certificate_authority(&self, id: &str) -> DatabaseResult<()> {
+        let local_db = self.local_db.read().await;
+        local_db.delete_certificate_authority(id).await
+    }
+
     /// Create SSH tunnel
     pub async fn create_ssh_tunnel(
         &self,
@@ -1193,6 +1240,16 @@ impl DatabaseService {
     }
 }
 
+impl DatabaseServiceConfig {
+    /// Folder holding the local database, also used for other per-device files
+    pub fn data_dir(&self) -> std::path::PathBuf {
+        std::path::Path::new(&self.local_db_path)
+            .parent()
+            .map(std::path::Path::to_path_buf)
+            .unwrap_or_default()
+    }
+}
+
 impl Default for DatabaseServiceConfig {
     fn default() -> Self {
         let data_dir = dirs::data_dir()
@@ -1219,7 +1276,7 @@ impl DatabaseService {
         request: crate::models::terminal::profile::CreateTerminalProfileRequest,
     ) -> DatabaseResult<crate::models::terminal::profile::TerminalProfile> {
         let profile = crate::models::terminal::profile::TerminalProfile {
-            id: uuid::Uuid::new_v4().to_string(),
+            base: crate::models::base::BaseModel::new(self.current_device.device_id.clone()),
             name: request.name,
             shell: request.shell,
             working_dir: request.working_dir.filter(|s| !s.is_empty()),
@@ -1227,8 +1284,6 @@ impl DatabaseService {
             icon: request.icon,
             color: request.color,
             command: request.command.filter(|s| !s.is_empty()),
-            created_at: Utc::now().timestamp_millis(),
-            updated_at: Utc::now().timestamp_millis(),
         };
 
         let local_db = self.local_db.read().await;
@@ -1296,7 +1351,7 @@ impl DatabaseService {
             };
         }
 
-        profile.updated_at = Utc::now().timestamp_millis();
+        profile.base.touch();
 
         local_db.save_terminal_profile(&profile).await?;
 
@@ -151,6 +151,11 @@ pub trait Syncable: Send + Sync {
     /// Generate a checksum for change detection
     fn checksum(&self) -> String;
 
+    /// Get the deletion timestamp if this record is a tombstone
+    fn deleted_at(&self) -> Option<DateTime<Utc>> {
+        None
+    }
+
     /// Check if this model should be synced to external databases
     fn should_sync(&self) -> bool {
         true
@@ -205,6 +210,7 @@ pub enum SyncStatus {
     Synced,   // Successfully synced
     Failed,   // Sync failed
     Conflict, // Conflict detected
+    Deleted,  // Soft-deleted tombstone kept until every device has seen it
 }
 
 impl std::fmt::Display for SyncStatus {
@@ -215,6 +221,7 @@ impl std::fmt::Display for SyncStatus {
             SyncStatus::Synced => write!(f, "Synced"),
             SyncStatus::Failed => write!(f, "Failed"),
             SyncStatus::Conflict => write!(f, "Conflict"),
+            SyncStatus::Deleted => write!(f, "Deleted"),
         }
     }
 }
@@ -229,6 +236,7 @@ impl std::str::FromStr for SyncStatus {
             "Synced" => Ok(SyncStatus::Synced),
             "Failed" => Ok(SyncStatus::Failed),
             "Conflict" => Ok(SyncStatus::Conflict),
+            "Deleted" => Ok(SyncStatus::Deleted),
             _ => Err(format!("Unknown sync status: {}", s)),
         }
     }
@@ -4,6 +4,53 @@ use serde_json::Value;
 
 use crate::database::error::DatabaseResult;
 
+/// Synced tables, whose rows are soft-deleted so deletions can be synced
+/// Listed in sync order: referenced tables come before the tables pointing at them
+pub const TOMBSTONE_TABLES: &[&str] = &[
+    "ssh_groups",
+    "ssh_keys",
+    "ssh_profiles",
+    "ssh_tunnels",
+    "saved_command_groups",
+    "saved_commands",
+    "terminal_profiles",
+    "session_recordings",
+    "known_hosts",
+];
+
+/// Remote table holding sealed records when end-to-end encryption is enabled
+pub const SEALED_RECORDS_TABLE: &str = "sync_sealed_records";
+
+/// Synced table a sealed record belongs to, stored in the clear next to the sealed payload
+pub fn sealed_table_name(envelope: &Value) -> Option<&str> {
+    envelope
+        .get("tableName")
+        .or_else(|| envelope.get("table_name"))
+        .and_then(|v| v.as_str())
+}
+
+/// Remote table holding sync keys wrapped with the master password
+pub const SYNC_KEYS_TABLE: &str = "sync_keys";
+
+/// Records returned by a pull, with the cursor to resume from on the next one
+#[derive(Debug, Clone, Default)]
+pub struct RecordChanges {
+    pub records: Vec<Value>,
+    /// Opaque position in the target's change sequence
+    pub cursor: String,
+}
+
+/// A table changed on a sync target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeNotification {
+    pub table: String,
+    /// Device that pushed the change, for targets able to tell
+    pub device_id: Option<String>,
+}
+
+/// Changes to a sync target, sent as the target learns about them
+pub type ChangeNotifications = tokio::sync::mpsc::Receiver<ChangeNotification>;
+
 /// Simplified trait for sync target databases
 /// These databases only serve as sync endpoints - no business logic
 #[async_trait]
@@ -11,6 +58,13 @@ pub trait SyncTarget: Send + Sync {
     /// Connect to the database
     async fn connect(&mut self) -> DatabaseResult<()>;
 
+    /// Connect without creating or migrating anything on the target, for previews
+    /// Returns false when nothing was ever synced to the target, leaving it unconnected
+    /// Targets whose connection never writes keep this default
+    async fn connect_read_only(&mut self) -> DatabaseResult<bool> {
+        self.connect().await.map(|_| true)
+    }
+
     /// Test the database connection
     async fn test_connection(&self) -> DatabaseResult<()>;
 
@@ -26,6 +80,45 @@ pub trait SyncTarget: Send + Sync {
         since: Option<DateTime<Utc>>,
     ) -> DatabaseResult<Vec<Value>>;
 
+    /// Pull records changed after a cursor returned by a previous pull, or every record without one
+    /// Cursors come from a sequence kept by the target, so device clocks play no part;
+    /// a cursor the target does not recognize is treated as no cursor
+    async fn pull_changes(
+        &self,
+        table: &str,
+        cursor: Option<&str>,
+    ) -> DatabaseResult<RecordChanges>;
+
+    /// Pull the sealed records of one synced table modified since timestamp
+    /// Targets that can filter on the clear table name override this, so a pull does not
+    /// transfer the sealed records of every table
+    async fn pull_sealed_records(
+        &self,
+        table: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> DatabaseResult<Vec<Value>> {
+        let mut envelopes = self.pull_records(SEALED_RECORDS_TABLE, since).await?;
+        envelopes.retain(|envelope| sealed_table_name(envelope) == Some(table));
+        Ok(envelopes)
+    }
+
+    /// Pull the sealed records of one synced table changed after a cursor
+    async fn pull_sealed_changes(
+        &self,
+        table: &str,
+        cursor: Option<&str>,
+    ) -> DatabaseResult<RecordChanges> {
+        let mut changes = self.pull_changes(SEALED_RECORDS_TABLE, cursor).await?;
+        changes
+            .records
+            .retain(|envelope| sealed_table_name(envelope) == Some(table));
+        Ok(changes)
+    }
+
+    /// Subscribe to changes pushed to the target, for targets able to announce them
+    /// Returns `None` for targets that can only be polled, leaving them to the scheduler
+    async fn watch_changes(&self) -> DatabaseResult<Option<ChangeNotifications>>;
+
     /// Get record versions for conflict detection
     /// Get version information for conflict detection
     async fn get_record_versions(
@@ -33,4 +126,22 @@ pub trait SyncTarget: Send + Sync {
         table: &str,
         ids: Vec<String>,
     ) -> DatabaseResult<std::collections::HashMap<String, u64>>;
+
+    /// Register a device so tombstones are kept until it has seen them
+    async fn register_device(&self, device_id: &str) -> DatabaseResult<()>;
+
+    /// Record that a device has seen the given tombstones
+    async fn acknowledge_tombstones(
+        &self,
+        table: &str,
+        device_id: &str,
+        ids: Vec<String>,
+    ) -> DatabaseResult<()>;
+
+    /// Permanently remove tombstones acknowledged by every registered device
+    /// Returns the ids of the purged records
+    async fn purge_acknowledged_tombstones(&self, table: &str) -> DatabaseResult<Vec<String>>;
+
+    /// Remove every record of a table, used when migrating to or from sealed records
+    async fn clear_records(&self, table: &str) -> DatabaseResult<usize>;
 }
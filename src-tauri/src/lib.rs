@@ -65,6 +65,17 @@ pub fn main() {
             commands::database::ssh::duplicate_ssh_profile,
             commands::database::ssh::test_ssh_connection,
             commands::database::ssh::create_ssh_key,
+            commands::database::ssh::generate_ssh_key,
+            commands::database::ssh::change_ssh_key_passphrase,
+            commands::database::ssh::export_ssh_key,
+            commands::database::ssh::list_pkcs11_keys,
+            commands::database::ssh::deploy_ssh_key,
+            commands::database::ssh::rotate_ssh_key,
+            commands::database::ssh::create_certificate_authority,
+            commands::database::ssh::get_certificate_authorities,
+            commands::database::ssh::delete_certificate_authority,
+            commands::database::ssh::issue_ssh_certificate,
+            commands::database::ssh::get_expiring_certificates,
             commands::database::ssh::get_ssh_keys,
             commands::database::ssh::update_ssh_key,
             commands::database::ssh::delete_ssh_key,
@@ -74,6 +85,12 @@ pub fn main() {
             commands::database::ssh::clear_connection_pool,
             commands::database::ssh::get_connection_pool_size,
             commands::database::ssh::get_ssh_config_hosts,
+            commands::database::ssh::preview_connection_import,
+            commands::database::ssh::import_connections,
+            commands::database::ssh::preview_ssh_config_export,
+            commands::database::ssh::export_ssh_config,
+            commands::database::ssh::get_managed_ssh_config,
+            commands::database::ssh::disable_managed_ssh_config,
             commands::database::ssh::scan_local_ssh_keys,
             commands::database::tunnel::create_tunnel,
             commands::database::tunnel::get_tunnels,
@@ -104,8 +121,14 @@ pub fn main() {
             commands::database::external_db::connect_to_database,
             commands::database::external_db::disconnect_from_database,
             commands::database::sync::sync_now,
+            commands::database::sync::preview_sync,
             commands::database::sync::get_sync_status,
             commands::database::sync::get_sync_logs,
+            commands::database::sync::get_sync_encryption_status,
+            commands::database::sync::enable_sync_encryption,
+            commands::database::sync::unlock_sync_encryption,
+            commands::database::sync::rotate_sync_encryption_key,
+            commands::database::sync::disable_sync_encryption,
             commands::database::sync::enable_auto_sync,
             commands::database::sync::disable_auto_sync,
             commands::database::sync::get_sync_statistics,
@@ -136,6 +159,7 @@ pub fn main() {
             commands::sftp::sftp_read_symlink,
             commands::sftp::sftp_upload_file,
             commands::sftp::sftp_download_file,
+            commands::sftp::sftp_copy_remote_file,
             commands::sftp::sftp_get_transfer_progress,
             commands::sftp::sftp_cancel_transfer,
             commands::sftp::sftp_pause_transfer,
@@ -145,10 +169,17 @@ pub fn main() {
             commands::sftp::sftp_reorder_queue,
             commands::sftp::sftp_retry_transfer,
             commands::sftp::sftp_compare_directories,
+            commands::sftp::sftp_compare_remote_directories,
             commands::sftp::sftp_sync_directory,
             commands::sftp::sftp_read_file,
+            commands::sftp::sftp_read_file_range,
             commands::sftp::sftp_write_file,
             commands::sftp::sftp_search,
+            commands::sftp::sftp_cancel_search,
+            commands::sftp::sftp_download_archive,
+            commands::sftp::sftp_upload_archive,
+            commands::sftp::sftp_extract_archive,
+            commands::sftp::sftp_create_archive,
             commands::history::get_terminal_history,
             commands::history::search_history,
             commands::history::export_history,
@@ -159,6 +190,13 @@ pub fn main() {
             commands::terminal_profile::delete_terminal_profile,
             commands::database::backup::export_backup,
             commands::database::backup::import_backup,
+            commands::database::backup::inspect_backup,
+            commands::database::backup::get_backup_schedule,
+            commands::database::backup::update_backup_schedule,
+            commands::database::backup::list_backup_snapshots,
+            commands::database::backup::create_backup_snapshot,
+            commands::database::backup::restore_backup_snapshot,
+            commands::database::backup::delete_backup_snapshot,
             commands::ai::get_available_models,
             commands::ai::generate_command,
         ])
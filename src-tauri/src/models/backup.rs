@@ -0,0 +1,254 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Kind of data that a backup can hold and restore independently
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupEntity {
+    SshGroups,
+    SshKeys,
+    SshProfiles,
+    SshTunnels,
+    /// Saved commands together with their groups
+    SavedCommands,
+    TerminalProfiles,
+    SyncSettings,
+    /// Lines of `~/.ssh/known_hosts`
+    KnownHosts,
+}
+
+impl BackupEntity {
+    /// All entities, in the order they are restored so references resolve
+    pub const ALL: [BackupEntity; 8] = [
+        BackupEntity::SshGroups,
+        BackupEntity::SshKeys,
+        BackupEntity::SshProfiles,
+        BackupEntity::SshTunnels,
+        BackupEntity::SavedCommands,
+        BackupEntity::TerminalProfiles,
+        BackupEntity::SyncSettings,
+        BackupEntity::KnownHosts,
+    ];
+}
+
+/// Key derivation used to encrypt a backup with a password
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupKdf {
+    #[default]
+    Argon2id,
+    /// PBKDF2-HMAC-SHA256, for tools that cannot run Argon2
+    Pbkdf2,
+}
+
+/// How restored records are combined with existing data
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreMode {
+    /// Keep local records that are not in the backup
+    #[default]
+    Merge,
+    /// Make each selected entity match the backup exactly, deleting local extras
+    Replace,
+}
+
+/// What to do when a backup record has the same ID as a local record (merge mode)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Replace the local record with the backup copy
+    #[default]
+    Overwrite,
+    /// Keep the local record and drop the backup copy
+    Skip,
+    /// Keep both, restoring the backup copy under a new ID
+    KeepBoth,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportBackupOptions {
+    pub password: Option<String>,
+    #[serde(default)]
+    pub kdf: BackupKdf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreOptions {
+    /// Entities to restore; every entity in the backup when unset
+    pub entities: Option<Vec<BackupEntity>>,
+    #[serde(default)]
+    pub mode: RestoreMode,
+    #[serde(default)]
+    pub conflict_policy: ConflictPolicy,
+}
+
+impl RestoreOptions {
+    pub fn includes(&self, entity: BackupEntity) -> bool {
+        self.entities
+            .as_ref()
+            .is_none_or(|entities| entities.contains(&entity))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupEntityCount {
+    pub entity: BackupEntity,
+    pub count: usize,
+}
+
+/// Backup header details shown before restoring
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupSummary {
+    pub format_version: u32,
+    pub schema_version: u32,
+    pub created_at: Option<String>,
+    pub app_version: Option<String>,
+    pub encrypted: bool,
+    pub kdf: Option<String>,
+    pub entities: Vec<BackupEntity>,
+    /// Record counts, only known once an encrypted backup is unlocked
+    pub counts: Option<Vec<BackupEntityCount>>,
+}
+
+/// Outcome of restoring one entity
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct EntityRestoreReport {
+    pub entity: BackupEntity,
+    pub created: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    /// Restored under a new ID because of an ID collision
+    pub renamed: usize,
+    pub deleted: usize,
+}
+
+impl EntityRestoreReport {
+    pub fn new(entity: BackupEntity) -> Self {
+        Self {
+            entity,
+            created: 0,
+            updated: 0,
+            skipped: 0,
+            renamed: 0,
+            deleted: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreReport {
+    /// Schema version of the backup before it was migrated
+    pub source_schema_version: u32,
+    pub entities: Vec<EntityRestoreReport>,
+}
+
+/// Why a snapshot was written
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotReason {
+    Scheduled,
+    Manual,
+    /// Taken before the master password is changed
+    PasswordChange,
+    /// Taken before the master password is reset
+    PasswordReset,
+    /// Taken before a sync applies remote changes locally
+    SyncPull,
+}
+
+impl SnapshotReason {
+    pub const ALL: [SnapshotReason; 5] = [
+        SnapshotReason::Scheduled,
+        SnapshotReason::Manual,
+        SnapshotReason::PasswordChange,
+        SnapshotReason::PasswordReset,
+        SnapshotReason::SyncPull,
+    ];
+
+    /// Tag used in snapshot file names
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SnapshotReason::Scheduled => "scheduled",
+            SnapshotReason::Manual => "manual",
+            SnapshotReason::PasswordChange => "password-change",
+            SnapshotReason::PasswordReset => "password-reset",
+            SnapshotReason::SyncPull => "sync-pull",
+        }
+    }
+}
+
+/// Automatic snapshot settings, kept per device and never synced
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BackupScheduleSettings {
+    pub enabled: bool,
+    /// Five-field cron expression in local time (minute hour day-of-month month day-of-week)
+    pub cron: String,
+    /// Snapshot folder; the app data folder's `backups` directory when unset
+    pub directory: Option<String>,
+    /// Newest snapshot of each of the last N days is kept
+    pub keep_daily: u32,
+    /// Newest snapshot of each of the last N weeks is kept
+    pub keep_weekly: u32,
+    pub kdf: BackupKdf,
+    /// Snapshot before master password changes and resets, and before sync pulls
+    pub snapshot_before_risky_operations: bool,
+}
+
+impl Default for BackupScheduleSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cron: "0 3 * * *".to_string(),
+            directory: None,
+            keep_daily: 7,
+            keep_weekly: 4,
+            kdf: BackupKdf::default(),
+            snapshot_before_risky_operations: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateBackupScheduleRequest {
+    pub enabled: Option<bool>,
+    pub cron: Option<String>,
+    /// Empty string resets to the default folder
+    pub directory: Option<String>,
+    pub keep_daily: Option<u32>,
+    pub keep_weekly: Option<u32>,
+    pub kdf: Option<BackupKdf>,
+    pub snapshot_before_risky_operations: Option<bool>,
+    /// Snapshot password stored in the system keychain; empty string removes it
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupScheduleStatus {
+    #[serde(flatten)]
+    pub settings: BackupScheduleSettings,
+    pub password_configured: bool,
+    /// Folder snapshots are written to
+    pub snapshot_directory: String,
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_snapshot_at: Option<DateTime<Utc>>,
+}
+
+/// Snapshot file in the snapshot folder
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupSnapshot {
+    /// File name, used to refer to the snapshot
+    pub id: String,
+    pub reason: SnapshotReason,
+    pub created_at: DateTime<Utc>,
+    pub size_bytes: u64,
+}
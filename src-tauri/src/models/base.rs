@@ -15,6 +15,10 @@ pub struct BaseModel {
     pub device_id: String,
     pub version: u64,
     pub sync_status: SyncStatus,
+    /// Set when the record has been deleted; the row is kept as a tombstone so the
+    /// deletion can be synced to other devices
+    #[serde(default)]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 impl BaseModel {
@@ -28,6 +32,7 @@ impl BaseModel {
             device_id,
             version: 1,
             sync_status: SyncStatus::Pending,
+            deleted_at: None,
         }
     }
 
@@ -38,6 +43,11 @@ impl BaseModel {
         self.sync_status = SyncStatus::Pending;
     }
 
+    /// Check whether the record is a tombstone
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
     #[allow(dead_code)]
     pub fn generate_checksum<T: Serialize>(&self, model: &T) -> String {
         let json = serde_json::to_string(model).unwrap_or_default();
@@ -97,6 +107,10 @@ macro_rules! impl_syncable {
             fn checksum(&self) -> String {
                 self.base.generate_checksum(self)
             }
+
+            fn deleted_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+                self.base.deleted_at
+            }
         }
     };
 }
@@ -1,4 +1,5 @@
 pub mod auth;
+pub mod backup;
 pub mod base;
 pub mod buffer;
 pub mod history;
@@ -1,10 +1,17 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::{impl_syncable, models::base::BaseModel};
+
+/// Metadata of a recorded session
+/// Only the metadata is synced; the cast file stays on the device that recorded it
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SessionRecording {
-    pub id: String,
+    /// Base model with sync metadata
+    #[serde(flatten)]
+    pub base: BaseModel,
+
     pub terminal_id: String,
     pub session_name: String,
     pub terminal_type: String, // "Local" | "SSH"
@@ -16,9 +23,10 @@ pub struct SessionRecording {
     pub width: u16,
     pub height: u16,
     pub metadata: Option<String>, // JSON
-    pub created_at: DateTime<Utc>,
 }
 
+impl_syncable!(SessionRecording, "session_recordings");
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AsciicastHeader {
     pub version: u8,
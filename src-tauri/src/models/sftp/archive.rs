@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+/// Archive format
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ArchiveFormat {
+    /// Uncompressed tar
+    Tar,
+    /// Gzip compressed tar
+    TarGz,
+    /// Zstandard compressed tar
+    TarZst,
+    /// Zip (only for archives created or extracted on the remote host)
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Guess the format from a file name extension
+    pub fn from_path(path: &str) -> Option<Self> {
+        let lower = path.to_lowercase();
+        if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if lower.ends_with(".tar.zst") || lower.ends_with(".tzst") {
+            Some(Self::TarZst)
+        } else if lower.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if lower.ends_with(".zip") {
+            Some(Self::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// Progress of a streamed archive transfer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveProgressEvent {
+    pub operation_id: String,
+    /// Archive bytes transferred so far (compressed size when compression is used)
+    pub bytes_transferred: u64,
+}
+
+/// Result of an archive transfer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveSummary {
+    pub operation_id: String,
+    /// Archive bytes transferred
+    pub bytes_transferred: u64,
+    /// Whether the archive was built locally over SFTP because remote tar was unavailable
+    pub fallback: bool,
+}
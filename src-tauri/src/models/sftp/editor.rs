@@ -0,0 +1,108 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Text encoding of an edited file
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TextEncoding {
+    /// UTF-8 (also used for plain ASCII)
+    Utf8,
+    /// UTF-16 little endian
+    Utf16le,
+    /// UTF-16 big endian
+    Utf16be,
+    /// ISO-8859-1, used for 8-bit files that are not valid UTF-8
+    Latin1,
+}
+
+/// Line ending style of an edited file
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    /// Unix (`\n`)
+    Lf,
+    /// Windows (`\r\n`)
+    Crlf,
+    /// Classic Mac OS (`\r`)
+    Cr,
+}
+
+/// Size and modification time of a remote file
+/// Returned when a file is opened or saved and passed back on save to detect conflicts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileSnapshot {
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+}
+
+/// Options for reading a file as text
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadFileOptions {
+    /// Byte offset to start reading from (default: 0)
+    #[serde(default)]
+    pub offset: Option<u64>,
+    /// Number of bytes to read (default: whole file, limited to 10MB)
+    #[serde(default)]
+    pub length: Option<u64>,
+    /// Encoding to decode with (default: detected)
+    #[serde(default)]
+    pub encoding: Option<TextEncoding>,
+}
+
+/// Text content of a remote file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextFileContent {
+    /// Decoded text, with original line endings
+    pub content: String,
+    /// Detected or requested encoding
+    pub encoding: TextEncoding,
+    /// Whether the file starts with a byte order mark
+    pub bom: bool,
+    /// Dominant line ending (default: lf when the text has no line breaks)
+    pub line_ending: LineEnding,
+    /// Byte offset of the decoded range
+    pub offset: u64,
+    /// Number of bytes decoded (may be shorter than requested to end on a character boundary)
+    pub length: u64,
+    /// Whether only part of the file was read
+    pub partial: bool,
+    /// File state when it was read
+    pub snapshot: FileSnapshot,
+}
+
+/// Options for saving a file as text
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteFileOptions {
+    /// Encoding to write with (default: utf8)
+    #[serde(default)]
+    pub encoding: Option<TextEncoding>,
+    /// Whether to write a byte order mark
+    #[serde(default)]
+    pub bom: bool,
+    /// Convert all line breaks to this style (default: keep content as is)
+    #[serde(default)]
+    pub line_ending: Option<LineEnding>,
+    /// File state when it was opened; saving fails if the remote file has changed since
+    #[serde(default)]
+    pub expected: Option<FileSnapshot>,
+}
+
+/// Raw bytes of a file range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileChunk {
+    /// Byte offset of the chunk
+    pub offset: u64,
+    /// Base64 encoded data
+    pub data: String,
+    /// Number of bytes in the chunk
+    pub length: u64,
+    /// Total file size
+    pub total_size: u64,
+    /// Whether the chunk reaches the end of the file
+    pub eof: bool,
+}
@@ -25,6 +25,10 @@ pub enum SFTPError {
     #[error("File already exists: {path}")]
     FileExists { path: String },
 
+    /// File changed on the server since it was opened
+    #[error("File was modified on the server since it was opened: {path}")]
+    FileModified { path: String },
+
     /// Invalid path
     #[error("Invalid path: {path}")]
     InvalidPath { path: String },
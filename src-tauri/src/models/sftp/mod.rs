@@ -1,3 +1,5 @@
+pub mod archive;
+pub mod editor;
 pub mod error;
 pub mod file_entry;
 pub mod requests;
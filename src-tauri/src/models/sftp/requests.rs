@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+use crate::models::sftp::archive::ArchiveFormat;
+use crate::models::sftp::editor::{ReadFileOptions, WriteFileOptions};
+use crate::models::sftp::search::SearchOptions;
 use crate::models::sftp::sync::SyncOperation;
 
 /// Request for connecting to SFTP server
@@ -102,6 +105,16 @@ pub struct DownloadFileRequest {
     pub local_path: String,
 }
 
+/// Request for copying a file between two remote sessions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteCopyRequest {
+    pub source_session_id: String,
+    pub source_path: String,
+    pub target_session_id: String,
+    pub target_path: String,
+}
+
 /// Request for getting transfer progress
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -139,6 +152,16 @@ pub struct CompareDirectoriesRequest {
     pub remote_path: String,
 }
 
+/// Request for comparing directories on two remote sessions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompareRemoteDirectoriesRequest {
+    pub source_session_id: String,
+    pub source_path: String,
+    pub target_session_id: String,
+    pub target_path: String,
+}
+
 /// Request for syncing directories
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -153,6 +176,18 @@ pub struct SyncDirectoriesRequest {
 pub struct ReadFileRequest {
     pub session_id: String,
     pub path: String,
+    #[serde(default)]
+    pub options: ReadFileOptions,
+}
+
+/// Request for reading a raw byte range of a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadFileRangeRequest {
+    pub session_id: String,
+    pub path: String,
+    pub offset: u64,
+    pub length: u64,
 }
 
 /// Request for writing file content
@@ -162,6 +197,8 @@ pub struct WriteFileRequest {
     pub session_id: String,
     pub path: String,
     pub content: String,
+    #[serde(default)]
+    pub options: WriteFileOptions,
 }
 
 /// Request for setting transfer priority
@@ -200,4 +237,72 @@ pub struct SearchRequest {
     pub session_id: String,
     pub path: String,
     pub query: String,
+    /// Client-chosen ID used to correlate streamed result events and cancellation
+    #[serde(default)]
+    pub search_id: Option<String>,
+    #[serde(default)]
+    pub options: SearchOptions,
+}
+
+/// Request for cancelling a running search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelSearchRequest {
+    pub search_id: String,
+}
+
+/// Request for downloading a remote path as an archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadArchiveRequest {
+    pub session_id: String,
+    pub remote_path: String,
+    /// Local archive file to create
+    pub local_path: String,
+    /// Archive format (default: from local file extension, otherwise tar.gz)
+    #[serde(default)]
+    pub format: Option<ArchiveFormat>,
+    /// Identifier used in progress events (default: generated)
+    #[serde(default)]
+    pub operation_id: Option<String>,
+}
+
+/// Request for uploading a local path by streaming a tar and extracting it remotely
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadArchiveRequest {
+    pub session_id: String,
+    pub local_path: String,
+    /// Remote path the local file or directory is written to
+    pub remote_path: String,
+    /// Identifier used in progress events (default: generated)
+    #[serde(default)]
+    pub operation_id: Option<String>,
+}
+
+/// Request for extracting an archive on the remote host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractArchiveRequest {
+    pub session_id: String,
+    pub archive_path: String,
+    /// Directory to extract into (default: the archive's directory)
+    #[serde(default)]
+    pub destination: Option<String>,
+    /// Archive format (default: from archive file extension)
+    #[serde(default)]
+    pub format: Option<ArchiveFormat>,
+}
+
+/// Request for creating an archive on the remote host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateArchiveRequest {
+    pub session_id: String,
+    /// Remote files and directories to add
+    pub source_paths: Vec<String>,
+    pub archive_path: String,
+    /// Archive format (default: from archive file extension)
+    #[serde(default)]
+    pub format: Option<ArchiveFormat>,
 }
@@ -8,3 +8,59 @@ pub struct SearchResult {
     pub line_number: u64,
     pub content: String,
 }
+
+/// Options for content search
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchOptions {
+    /// Treat query as an extended regular expression (default: fixed string)
+    #[serde(default)]
+    pub regex: bool,
+    /// Ignore case when matching (default: case-sensitive)
+    #[serde(default)]
+    pub ignore_case: bool,
+    /// Only search files whose name matches one of these glob patterns
+    #[serde(default)]
+    pub include_patterns: Vec<String>,
+    /// Skip files and directories whose name matches one of these glob patterns
+    #[serde(default)]
+    pub exclude_patterns: Vec<String>,
+    /// Stop after this many matches (default: 1000)
+    #[serde(default)]
+    pub max_results: Option<usize>,
+}
+
+/// Batch of search results emitted while a search is running
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResultEvent {
+    pub search_id: String,
+    pub results: Vec<SearchResult>,
+}
+
+/// Emitted once a search has finished
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchCompleteEvent {
+    pub search_id: String,
+    /// Total number of results found
+    pub total: usize,
+    /// Whether the search stopped at `max_results`
+    pub truncated: bool,
+    /// Whether the search was cancelled
+    pub cancelled: bool,
+    /// Whether the pure-SFTP fallback was used instead of remote grep
+    pub fallback: bool,
+    /// Error message if the search failed
+    pub error: Option<String>,
+}
+
+/// Summary of a finished search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchSummary {
+    pub results: Vec<SearchResult>,
+    pub truncated: bool,
+    pub cancelled: bool,
+    pub fallback: bool,
+}
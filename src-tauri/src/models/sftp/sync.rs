@@ -33,11 +33,23 @@ pub struct SyncOperation {
     /// Checksum algorithm to use: "md5", "sha256" (default: "md5")
     #[serde(default)]
     pub checksum_algorithm: Option<String>,
+    /// Whether to upload only changed blocks of modified files (rsync-style delta, default: true)
+    /// Only used for local to remote sync of files that already exist on the remote
+    #[serde(default = "default_true")]
+    pub delta_transfer: bool,
+    /// Minimum file size in bytes for delta transfer (default: 8MB)
+    /// Smaller files are re-uploaded in full
+    #[serde(default)]
+    pub delta_min_size: Option<u64>,
+}
+
+fn default_true() -> bool {
+    true
 }
 
 /// Synchronization direction
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "lowercase")]
+#[serde(rename_all = "camelCase")]
 pub enum SyncDirection {
     /// Sync from local to remote (upload)
     LocalToRemote,
@@ -10,10 +10,13 @@ pub struct TransferProgress {
     pub status: TransferStatus,
     /// Transfer direction (upload or download)
     pub direction: TransferDirection,
-    /// Local file path
+    /// Local file path (source path for remote-to-remote transfers)
     pub local_path: String,
-    /// Remote file path
+    /// Remote file path (target path for remote-to-remote transfers)
     pub remote_path: String,
+    /// Source session for remote-to-remote transfers
+    #[serde(default)]
+    pub source_session_id: Option<String>,
     /// Total size in bytes
     pub total_bytes: u64,
     /// Transferred bytes so far
@@ -64,6 +67,8 @@ pub enum TransferDirection {
     Upload,
     /// Downloading from remote to local
     Download,
+    /// Copying between two remote sessions
+    Remote,
 }
 
 impl TransferProgress {
@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::{
+        error::DatabaseResult,
+        traits::{Encryptable, EncryptionService},
+    },
+    models::base::BaseModel,
+};
+
+use super::profile::KeyType;
+
+/// Local SSH certificate authority used to sign short-lived user certificates
+///
+/// Kept on this device only; the signing key never leaves the local database.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SSHCertificateAuthority {
+    #[serde(flatten)]
+    pub base: BaseModel,
+
+    pub name: String,
+    pub key_type: KeyType,
+
+    /// CA signing key in OpenSSH format (encrypted)
+    #[serde(skip_serializing)]
+    pub private_key: String,
+
+    /// CA public key, to be listed in the servers' `TrustedUserCAKeys`
+    pub public_key: String,
+    pub fingerprint: String,
+
+    /// Principals signed into certificates when a request names none
+    pub default_principals: Vec<String>,
+    /// Certificate lifetime when a request sets none
+    pub default_ttl_minutes: u32,
+
+    /// Serial number for the next certificate issued
+    pub next_serial: u64,
+
+    pub description: Option<String>,
+}
+
+impl Encryptable for SSHCertificateAuthority {
+    fn encrypted_fields() -> Vec<&'static str> {
+        vec!["private_key"]
+    }
+
+    fn encrypt_fields(&mut self, encryption_service: &dyn EncryptionService) -> DatabaseResult<()> {
+        self.private_key = tokio::task::block_in_place(|| {
+            tokio::runtime::Handle::current().block_on(async {
+                encryption_service
+                    .encrypt_string(&self.private_key, Some("__shared__"))
+                    .await
+            })
+        })?;
+        Ok(())
+    }
+
+    fn decrypt_fields(&mut self, encryption_service: &dyn EncryptionService) -> DatabaseResult<()> {
+        self.private_key = tokio::task::block_in_place(|| {
+            tokio::runtime::Handle::current().block_on(async {
+                encryption_service
+                    .decrypt_string(&self.private_key, Some("__shared__"))
+                    .await
+            })
+        })?;
+        Ok(())
+    }
+
+    fn has_encrypted_data(&self) -> bool {
+        true
+    }
+
+    fn encryption_device_id(&self) -> Option<&str> {
+        Some(&self.base.device_id)
+    }
+}
+
+/// Request to create a certificate authority
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateCertificateAuthorityRequest {
+    pub name: String,
+    /// Type of the generated CA key (ignored when importing)
+    pub key_type: Option<KeyType>,
+    pub bits: Option<u32>,
+    /// Existing CA key to import instead of generating one
+    pub private_key: Option<String>,
+    pub passphrase: Option<String>,
+    #[serde(default)]
+    pub default_principals: Vec<String>,
+    pub default_ttl_minutes: Option<u32>,
+    pub description: Option<String>,
+}
+
+/// Request to sign a certificate for a profile
+///
+/// The profile is switched to certificate authentication. Its current certificate key is
+/// reused when it has one; otherwise a new Ed25519 key is generated for it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueCertificateRequest {
+    pub ca_id: String,
+    pub profile_id: String,
+    /// Defaults to the CA's principals, or the profile's username when the CA has none
+    pub principals: Option<Vec<String>>,
+    pub ttl_minutes: Option<u32>,
+}
+
+/// Profile whose certificate expires soon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpiringCertificate {
+    pub profile_id: String,
+    pub profile_name: String,
+    pub valid_to: String,
+    /// Seconds until expiry, negative once expired
+    pub expires_in_seconds: i64,
+    /// Issued by a local CA and renewed automatically on connect
+    pub auto_renew: bool,
+}
@@ -0,0 +1,65 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Where exported Host blocks are written
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SSHConfigExportTarget {
+    /// Standalone config file, replaced on every export
+    File { path: String },
+    /// `~/.ssh/kerminal/config`, included from `~/.ssh/config` and rewritten whenever the
+    /// exported profiles change
+    ManagedInclude,
+}
+
+/// Profiles to export, by ID or by group
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SSHConfigExportSelection {
+    #[serde(default)]
+    pub profile_ids: Vec<String>,
+    /// Every profile in these groups, including ones added later
+    #[serde(default)]
+    pub group_ids: Vec<String>,
+    /// Folder key files are written to; `~/.ssh/kerminal/keys` when unset
+    #[serde(default)]
+    pub key_directory: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportSSHConfigRequest {
+    #[serde(flatten)]
+    pub selection: SSHConfigExportSelection,
+    pub target: SSHConfigExportTarget,
+}
+
+/// Rendered config, without writing anything
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SSHConfigExportPreview {
+    pub content: String,
+    /// Profile settings OpenSSH config cannot express
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SSHConfigExportResult {
+    pub config_path: String,
+    pub hosts_exported: usize,
+    pub keys_written: usize,
+    pub warnings: Vec<String>,
+}
+
+/// State of the managed `Include` snippet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManagedSSHConfigStatus {
+    #[serde(flatten)]
+    pub selection: SSHConfigExportSelection,
+    pub config_path: String,
+    pub include_path: String,
+    pub last_synced_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-use super::profile::{AuthData, AuthMethod, KeyType, SSHProfile};
+use super::profile::{AuthData, AuthMethod, KeyType, ProxyConfig, ProxyType, SSHProfile};
 use crate::database::traits::SyncStatus;
+use crate::services::ssh_config_parser::{expand_tilde, local_username};
 
 /**
  * Represents a host entry from SSH config file (~/.ssh/config)
@@ -25,6 +26,14 @@ pub struct SSHConfigHost {
     /// Path to identity file (private key)
     pub identity_file: Option<String>,
 
+    /// Every IdentityFile that applies, in the order ssh tries them
+    #[serde(default)]
+    pub identity_files: Vec<String>,
+
+    /// CertificateFile entries
+    #[serde(default)]
+    pub certificate_files: Vec<String>,
+
     /// ProxyJump configuration
     pub proxy_jump: Option<String>,
 
@@ -36,6 +45,26 @@ pub struct SSHConfigHost {
 
     /// Additional options from config
     pub other_options: Option<String>,
+
+    /// LocalForward entries, as written in the config
+    #[serde(default)]
+    pub local_forwards: Vec<String>,
+
+    /// RemoteForward entries, as written in the config
+    #[serde(default)]
+    pub remote_forwards: Vec<String>,
+
+    /// DynamicForward entries, as written in the config
+    #[serde(default)]
+    pub dynamic_forwards: Vec<String>,
+
+    /// ServerAliveInterval in seconds; 0 turns keepalives off
+    #[serde(default)]
+    pub server_alive_interval: Option<u32>,
+
+    /// Whether to use compression
+    #[serde(default)]
+    pub compression: Option<bool>,
 }
 
 impl SSHConfigHost {
@@ -50,24 +79,47 @@ impl SSHConfigHost {
 
         use crate::models::base::BaseModel;
 
-        let username = self
-            .user
-            .clone()
-            .ok_or_else(|| "Username is required".to_string())?;
-
-        let (auth_method, auth_data) = if let Some(identity_file) = &self.identity_file {
-            // Has key file - use certificate auth
-            let expanded_path = if identity_file.starts_with("~/") {
-                let home = std::env::var("HOME")
-                    .map_err(|_| "Cannot determine HOME directory".to_string())?;
-                identity_file.replacen("~", &home, 1)
-            } else {
-                identity_file.clone()
-            };
+        // The terminal opens a plain session, so refuse hosts it cannot reach as configured
+        if let Some(proxy_jump) = &self.proxy_jump {
+            return Err(format!(
+                "ProxyJump '{}' is not supported for SSH config connections",
+                proxy_jump
+            ));
+        }
+        let proxy = match &self.proxy_command {
+            Some(command) => Some(proxy_from_command(command).ok_or_else(|| {
+                format!(
+                    "ProxyCommand '{}' is not supported for SSH config connections",
+                    command
+                )
+            })?),
+            None => None,
+        };
+        if !self.local_forwards.is_empty()
+            || !self.remote_forwards.is_empty()
+            || !self.dynamic_forwards.is_empty()
+        {
+            return Err(
+                "Port forwards are not supported for SSH config connections; import the host to use them as tunnels"
+                    .to_string(),
+            );
+        }
 
-            let private_key = std::fs::read_to_string(&expanded_path)
-                .map_err(|e| format!("Failed to read identity file '{}': {}", expanded_path, e))?;
+        // ssh logs in as the local user when the config names none
+        let username = self.user.clone().unwrap_or_else(local_username);
+        if username.is_empty() {
+            return Err("Username is required".to_string());
+        }
 
+        // Like ssh, use the first identity that can be read; the terminal offers a single key
+        let identity = self.identity_files().iter().find_map(|path| {
+            let expanded = expand_tilde(path);
+            std::fs::read_to_string(&expanded)
+                .ok()
+                .map(|key| (expanded, key))
+        });
+
+        let (auth_method, auth_data) = if let Some((identity_path, private_key)) = identity {
             let key_type = if private_key.contains("BEGIN OPENSSH PRIVATE KEY")
                 || private_key.contains("ssh-ed25519")
             {
@@ -78,15 +130,26 @@ impl SSHConfigHost {
                 KeyType::RSA
             };
 
+            // ssh also picks up a certificate stored next to the key as `<key>-cert.pub`
+            let certificate = self
+                .certificate_files
+                .iter()
+                .map(|path| expand_tilde(path))
+                .chain(std::iter::once(format!("{}-cert.pub", identity_path)))
+                .find_map(|path| std::fs::read_to_string(path).ok())
+                .unwrap_or_default();
+
             (
                 AuthMethod::Certificate,
                 AuthData::Certificate {
-                    certificate: String::new(),
+                    certificate,
                     private_key,
                     key_type,
                     validity_period: None,
                 },
             )
+        } else if let Some(identity_file) = self.identity_files().first() {
+            return Err(format!("Failed to read identity file '{}'", identity_file));
         } else {
             // No key file - use password auth
             let pwd =
@@ -103,6 +166,7 @@ impl SSHConfigHost {
                 device_id: "temp".to_string(),
                 version: 0,
                 sync_status: SyncStatus::Synced,
+                deleted_at: None,
             },
             name: self.name.clone(),
             host: self.hostname.clone(),
@@ -112,9 +176,9 @@ impl SSHConfigHost {
             auth_method,
             auth_data,
             timeout: Some(30),
-            keep_alive: true,
-            compression: false,
-            proxy: None,
+            keep_alive: self.server_alive_interval != Some(0),
+            compression: self.compression.unwrap_or(false),
+            proxy,
             color: None,
             description: Some(format!("Temporary profile from SSH config: {}", self.name)),
             command: None,
@@ -128,6 +192,110 @@ impl SSHConfigHost {
      * Returns true if no identity file is configured
      */
     pub fn requires_password(&self) -> bool {
-        self.identity_file.is_none()
+        self.identity_files().is_empty()
+    }
+
+    /// Keepalive interval set by ServerAliveInterval; None keeps the terminal default
+    pub fn keepalive_interval(&self) -> Option<std::time::Duration> {
+        self.server_alive_interval
+            .filter(|interval| *interval > 0)
+            .map(|interval| std::time::Duration::from_secs(interval.into()))
+    }
+
+    /// Identity files to try, including the single `identity_file` older clients send
+    fn identity_files(&self) -> Vec<String> {
+        if self.identity_files.is_empty() {
+            self.identity_file.iter().cloned().collect()
+        } else {
+            self.identity_files.clone()
+        }
+    }
+}
+
+/// Proxy for a netcat ProxyCommand such as `nc -X 5 -x proxy:1080 %h %p`
+fn proxy_from_command(command: &str) -> Option<ProxyConfig> {
+    // Arguments may be single-quoted, as the managed export writes them
+    let mut args = command.split_whitespace().map(|arg| {
+        arg.strip_prefix('\'')
+            .and_then(|arg| arg.strip_suffix('\''))
+            .unwrap_or(arg)
+    });
+    if !matches!(args.next()?, "nc" | "/usr/bin/nc" | "/bin/nc") {
+        return None;
+    }
+
+    // nc talks SOCKS5 unless -X says otherwise
+    let mut proxy_type = ProxyType::Socks5;
+    let mut address = None;
+    let mut username = None;
+    let mut rest = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg {
+            "-X" => {
+                proxy_type = match args.next()? {
+                    "5" => ProxyType::Socks5,
+                    "4" => ProxyType::Socks4,
+                    "connect" => ProxyType::Http,
+                    _ => return None,
+                }
+            }
+            "-x" => address = Some(args.next()?),
+            "-P" => username = Some(args.next()?.to_string()),
+            _ => rest.push(arg),
+        }
+    }
+    if rest != ["%h", "%p"] {
+        return None;
+    }
+
+    let address = address?;
+    // nc's default ports when -x names only a host
+    let default_port = if proxy_type == ProxyType::Http {
+        3128
+    } else {
+        1080
+    };
+    let (host, port) = if let Some(bracketed) = address.strip_prefix('[') {
+        let (host, port) = bracketed.split_once(']')?;
+        match port {
+            "" => (host, default_port),
+            _ => (host, port.strip_prefix(':')?.parse().ok()?),
+        }
+    } else {
+        match address.split_once(':') {
+            Some((host, port)) => (host, port.parse().ok()?),
+            None => (address, default_port),
+        }
+    };
+    Some(ProxyConfig {
+        proxy_type,
+        host: host.to_string(),
+        port,
+        username,
+        password: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proxy_from_netcat_command() {
+        let proxy = proxy_from_command("nc -X connect -x proxy.local:8080 %h %p").unwrap();
+        assert_eq!(proxy.proxy_type, ProxyType::Http);
+        assert_eq!((proxy.host.as_str(), proxy.port), ("proxy.local", 8080));
+
+        let proxy = proxy_from_command("nc -x [::1] %h %p").unwrap();
+        assert_eq!(proxy.proxy_type, ProxyType::Socks5);
+        assert_eq!((proxy.host.as_str(), proxy.port), ("::1", 1080));
+
+        let proxy = proxy_from_command("nc -X connect -P 'me' -x 'proxy.lan:3128' %h %p");
+        let proxy = proxy.unwrap();
+        assert_eq!(proxy.username.as_deref(), Some("me"));
+        assert_eq!(proxy.port, 3128);
+
+        assert!(proxy_from_command("ssh -W %h:%p bastion").is_none());
+        assert!(proxy_from_command("nc -X 5 -x proxy:1080 target 22").is_none());
     }
 }
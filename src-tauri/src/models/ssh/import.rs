@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+
+use super::profile::ProxyConfig;
+use super::tunnel::TunnelType;
+
+/// Application or file format connections are imported from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportSource {
+    /// `~/.ssh/config` or another OpenSSH client config file
+    OpenSsh,
+    /// Registry export (`.reg`) of `HKCU\Software\SimonTatham\PuTTY\Sessions`
+    Putty,
+    /// MobaXterm sessions export (`.mxtsessions`)
+    MobaXterm,
+    /// Termius CSV or JSON export
+    Termius,
+    /// A `.remmina` file, or a folder of them
+    Remmina,
+}
+
+/// Port forward read from an imported host
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedTunnel {
+    pub tunnel_type: TunnelType,
+    pub local_host: String,
+    pub local_port: u16,
+    pub remote_host: Option<String>,
+    pub remote_port: Option<u16>,
+}
+
+/// Private key referenced by imported hosts
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedKey {
+    /// Identifier hosts refer to the key by; the file path for keys read from disk
+    pub id: String,
+    pub name: String,
+    /// Key file, when the source only points at one
+    pub path: Option<String>,
+    /// Inline key content, never sent to the frontend
+    #[serde(skip_serializing, default)]
+    pub private_key: Option<String>,
+    #[serde(skip_serializing, default)]
+    pub passphrase: Option<String>,
+    /// Stored key with the same fingerprint
+    pub duplicate_of: Option<String>,
+}
+
+/// Connection read from an import source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedHost {
+    /// Selects the host for import: its folder, name and address, numbered when the source
+    /// repeats them, so a source that changed since the preview never imports the wrong host
+    pub id: String,
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    /// Folder the host sits in at the source, created as an SSH group
+    pub group: Option<String>,
+    /// `ImportedKey::id` of the key the host authenticates with
+    pub key: Option<String>,
+    /// Saved password, never sent to the frontend
+    #[serde(skip_serializing, default)]
+    pub password: Option<String>,
+    pub has_password: bool,
+    pub proxy: Option<ProxyConfig>,
+    pub tunnels: Vec<ImportedTunnel>,
+    pub description: Option<String>,
+    /// Stored profile with the same host, port and username
+    pub duplicate_of: Option<String>,
+}
+
+impl ImportedHost {
+    pub fn new(name: String, host: String, port: u16) -> Self {
+        Self {
+            id: String::new(),
+            name,
+            host,
+            port,
+            username: None,
+            group: None,
+            key: None,
+            password: None,
+            has_password: false,
+            proxy: None,
+            tunnels: Vec::new(),
+            description: None,
+            duplicate_of: None,
+        }
+    }
+
+    pub fn set_password(&mut self, password: Option<String>) {
+        self.password = password.filter(|p| !p.is_empty());
+        self.has_password = self.password.is_some();
+    }
+}
+
+/// Everything read from an import source
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportPreview {
+    pub hosts: Vec<ImportedHost>,
+    pub keys: Vec<ImportedKey>,
+    /// Entries that were skipped or only partly understood
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewImportRequest {
+    pub source: ImportSource,
+    /// File or folder to read; `~/.ssh/config` when unset for OpenSSH
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportConnectionsRequest {
+    pub source: ImportSource,
+    pub path: Option<String>,
+    /// `ImportedHost::id` of the hosts to import; every host that is not a duplicate when unset
+    pub hosts: Option<Vec<String>>,
+    /// Import port forwards as SSH tunnels
+    #[serde(default = "default_true")]
+    pub import_tunnels: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Outcome of an import
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportResult {
+    pub profiles_created: usize,
+    pub groups_created: usize,
+    pub keys_created: usize,
+    pub keys_reused: usize,
+    pub tunnels_created: usize,
+    pub warnings: Vec<String>,
+}
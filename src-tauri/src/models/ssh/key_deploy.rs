@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+/// Request to install an SSH key's public key on a set of hosts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeploySSHKeyRequest {
+    pub key_id: String,
+    /// Profiles to deploy to
+    #[serde(default)]
+    pub profile_ids: Vec<String>,
+    /// Deploy to every profile in this group as well
+    pub group_id: Option<String>,
+    /// Switch each profile to authenticate with the key once login is verified
+    #[serde(default)]
+    pub switch_auth: bool,
+}
+
+/// Request to replace one SSH key with another on a set of hosts
+///
+/// Without explicit targets, every profile that authenticates with the old key is rotated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RotateSSHKeyRequest {
+    pub old_key_id: String,
+    pub new_key_id: String,
+    #[serde(default)]
+    pub profile_ids: Vec<String>,
+    pub group_id: Option<String>,
+}
+
+/// Outcome of installing the public key on one host
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum KeyDeployStatus {
+    /// Key was appended to authorized_keys
+    Added,
+    /// Key was already authorized, nothing was written
+    AlreadyPresent,
+    /// Connecting or writing authorized_keys failed
+    Failed,
+}
+
+/// Per-host result of a deploy or rotation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyDeployResult {
+    pub profile_id: String,
+    pub profile_name: String,
+    pub host: String,
+    pub status: KeyDeployStatus,
+    /// Login with the deployed key succeeded
+    pub verified: bool,
+    /// Profile was switched over to the deployed key
+    pub auth_switched: bool,
+    /// Old key was removed from authorized_keys (rotation only)
+    pub old_key_removed: Option<bool>,
+    pub error: Option<String>,
+}
+
+/// Report of a deploy or rotation across all target hosts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyDeployReport {
+    pub key_id: String,
+    pub results: Vec<KeyDeployResult>,
+}
+
+impl KeyDeployReport {
+    /// Number of hosts where the key was deployed and login with it verified
+    pub fn succeeded(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|r| r.verified && r.error.is_none())
+            .count()
+    }
+}
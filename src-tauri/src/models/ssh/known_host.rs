@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{impl_syncable, models::base::BaseModel};
+
+/// Host key line from `~/.ssh/known_hosts`, shared by all devices through sync
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KnownHost {
+    /// Base model with sync metadata; the ID is derived from the line itself
+    #[serde(flatten)]
+    pub base: BaseModel,
+
+    /// `@cert-authority` or `@revoked`, when the line starts with a marker
+    pub marker: Option<String>,
+
+    /// Comma-separated host patterns, possibly hashed (`|1|...`)
+    pub hosts: String,
+
+    pub key_type: String,
+
+    /// Base64 public key blob
+    pub public_key: String,
+}
+
+impl KnownHost {
+    /// Parse a known_hosts line, ignoring comments, blank lines and trailing key comments
+    pub fn parse(line: &str, device_id: &str) -> Option<Self> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let mut fields = line.split_whitespace();
+        let mut first = fields.next()?;
+        let marker = if first.starts_with('@') {
+            let marker = first.to_string();
+            first = fields.next()?;
+            Some(marker)
+        } else {
+            None
+        };
+        let key_type = fields.next()?;
+        let public_key = fields.next()?;
+
+        let mut base = BaseModel::new(device_id.to_string());
+        base.id = Self::entry_id(marker.as_deref(), first, key_type, public_key);
+
+        Some(Self {
+            base,
+            marker,
+            hosts: first.to_string(),
+            key_type: key_type.to_string(),
+            public_key: public_key.to_string(),
+        })
+    }
+
+    /// Same line on every device gets the same ID, so devices adding it never duplicate it
+    fn entry_id(marker: Option<&str>, hosts: &str, key_type: &str, public_key: &str) -> String {
+        let mut hasher = Sha256::new();
+        for field in [marker.unwrap_or_default(), hosts, key_type, public_key] {
+            hasher.update(field.as_bytes());
+            hasher.update([0]);
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// The line as written to known_hosts
+    pub fn line(&self) -> String {
+        match &self.marker {
+            Some(marker) => format!(
+                "{} {} {} {}",
+                marker, self.hosts, self.key_type, self.public_key
+            ),
+            None => format!("{} {} {}", self.hosts, self.key_type, self.public_key),
+        }
+    }
+}
+
+impl_syncable!(KnownHost, "known_hosts");
@@ -1,12 +1,33 @@
+pub mod certificate_authority;
+pub mod config_export;
 pub mod config_host;
 pub mod group;
+pub mod import;
 pub mod key;
+pub mod key_deploy;
+pub mod known_host;
 pub mod profile;
 pub mod tunnel;
 
+pub use certificate_authority::{
+    CreateCertificateAuthorityRequest, ExpiringCertificate, IssueCertificateRequest,
+    SSHCertificateAuthority,
+};
+pub use config_export::{
+    ExportSSHConfigRequest, ManagedSSHConfigStatus, SSHConfigExportPreview, SSHConfigExportResult,
+    SSHConfigExportSelection, SSHConfigExportTarget,
+};
 pub use config_host::SSHConfigHost;
 pub use group::{CreateSSHGroupRequest, DeleteGroupAction, SSHGroup, UpdateSSHGroupRequest};
-pub use key::{CreateSSHKeyRequest, SSHKey, UpdateSSHKeyRequest};
+pub use import::{
+    ImportConnectionsRequest, ImportPreview, ImportResult, ImportSource, ImportedHost, ImportedKey,
+    ImportedTunnel, PreviewImportRequest,
+};
+pub use key::{CreateSSHKeyRequest, GenerateSSHKeyRequest, KeyFormat, SSHKey, UpdateSSHKeyRequest};
+pub use key_deploy::{
+    DeploySSHKeyRequest, KeyDeployReport, KeyDeployResult, KeyDeployStatus, RotateSSHKeyRequest,
+};
+pub use known_host::KnownHost;
 pub use profile::{
     AuthData, CreateSSHProfileRequest, SSHProfile, TestSSHConnectionRequest,
     UpdateSSHProfileRequest,
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
 
 use crate::{
     database::{
@@ -118,6 +119,24 @@ pub enum AuthData {
     },
 }
 
+/// Wipes inline secrets in place; used once a connection no longer needs them
+impl Zeroize for AuthData {
+    fn zeroize(&mut self) {
+        match self {
+            AuthData::Password { password } => password.zeroize(),
+            AuthData::KeyReference { .. } => {}
+            AuthData::Certificate {
+                certificate,
+                private_key,
+                ..
+            } => {
+                certificate.zeroize();
+                private_key.zeroize();
+            }
+        }
+    }
+}
+
 /// Certificate validity information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -126,6 +145,31 @@ pub struct CertificateValidity {
     pub valid_to: String,
     pub serial: Option<String>,
     pub ca_fingerprint: Option<String>,
+    /// Local certificate authority that issued the certificate, used to renew it
+    #[serde(default)]
+    pub ca_id: Option<String>,
+    #[serde(default)]
+    pub principals: Vec<String>,
+}
+
+impl CertificateValidity {
+    /// Time left until the certificate expires (negative once expired)
+    pub fn expires_in(&self) -> Option<chrono::Duration> {
+        let valid_to = chrono::DateTime::parse_from_rfc3339(&self.valid_to).ok()?;
+        Some(valid_to.with_timezone(&chrono::Utc) - chrono::Utc::now())
+    }
+
+    /// Whether less than a quarter of the certificate's lifetime remains
+    pub fn needs_renewal(&self) -> bool {
+        let (Ok(valid_from), Ok(valid_to)) = (
+            chrono::DateTime::parse_from_rfc3339(&self.valid_from),
+            chrono::DateTime::parse_from_rfc3339(&self.valid_to),
+        ) else {
+            return true;
+        };
+        let lifetime = valid_to - valid_from;
+        valid_to.with_timezone(&chrono::Utc) - chrono::Utc::now() < lifetime / 4
+    }
 }
 
 /// SSH key types
@@ -135,6 +179,10 @@ pub enum KeyType {
     Ed25519,
     ECDSA,
     DSA,
+    /// FIDO2 security key (`sk-ssh-ed25519@openssh.com`)
+    SkEd25519,
+    /// FIDO2 security key (`sk-ecdsa-sha2-nistp256@openssh.com`)
+    SkECDSA,
 }
 
 impl SSHProfile {
@@ -341,6 +389,7 @@ impl CreateSSHProfileRequest {
         profile.timeout = self.timeout.or(Some(30));
         profile.keep_alive = self.keep_alive.unwrap_or(true);
         profile.compression = self.compression.unwrap_or(false);
+        profile.proxy = self.proxy;
         profile.color = self.color;
         profile.description = self.description;
         profile.command = self.command;
@@ -352,7 +401,7 @@ impl CreateSSHProfileRequest {
 }
 
 /// Request to update SSH profile
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateSSHProfileRequest {
     pub name: Option<String>,
@@ -532,6 +581,8 @@ impl std::fmt::Display for KeyType {
             KeyType::Ed25519 => write!(f, "Ed25519"),
             KeyType::ECDSA => write!(f, "ECDSA"),
             KeyType::DSA => write!(f, "DSA"),
+            KeyType::SkEd25519 => write!(f, "Ed25519-SK"),
+            KeyType::SkECDSA => write!(f, "ECDSA-SK"),
         }
     }
 }
@@ -3,6 +3,17 @@ use serde::{Deserialize, Serialize};
 
 pub use super::external_db::ConflictResolutionStrategy;
 
+/// A field that both sides changed since the last sync
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldConflict {
+    pub field: String,
+    /// Value at the last sync (None if the field did not exist yet)
+    pub base: Option<serde_json::Value>,
+    pub local: serde_json::Value,
+    pub remote: serde_json::Value,
+}
+
 /// Conflict resolution record stored in database
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -12,7 +23,24 @@ pub struct ConflictResolution {
     pub entity_id: String,
     pub local_data: serde_json::Value,
     pub remote_data: serde_json::Value,
+    /// Record as of the last sync, when a three-way merge was attempted
+    #[serde(default)]
+    pub base_data: Option<serde_json::Value>,
+    /// Record with every non-overlapping edit merged; conflicting fields keep the local value
+    #[serde(default)]
+    pub merged_data: Option<serde_json::Value>,
+    /// Fields both sides changed (empty when the whole record conflicts)
+    #[serde(default)]
+    pub field_conflicts: Vec<FieldConflict>,
     pub resolution_strategy: Option<ConflictResolutionStrategy>,
     pub resolved_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
+
+/// Side a conflicting field is taken from when a conflict is resolved field by field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FieldChoice {
+    Local,
+    Remote,
+}
@@ -0,0 +1,15 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// How far a table has been synced with a sync target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncCursor {
+    pub database_id: String,
+    pub table_name: String,
+    /// Local change log position the last sync read up to
+    pub local_seq: i64,
+    /// Position in the sync target's change sequence, as returned by its last pull
+    pub remote_cursor: String,
+    pub updated_at: DateTime<Utc>,
+}
@@ -0,0 +1,25 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Sync key for an end-to-end encrypted sync target, kept locally
+/// encrypted with this device's key so syncing does not need the master password
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncEncryptionKey {
+    pub database_id: String,
+    pub key_id: String,
+    pub encrypted_key: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// End-to-end encryption state of a sync target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncEncryptionStatus {
+    /// The sync target holds sealed records
+    pub enabled: bool,
+    /// This device holds every key published on the sync target
+    pub unlocked: bool,
+    pub active_key_id: Option<String>,
+    pub key_count: usize,
+}
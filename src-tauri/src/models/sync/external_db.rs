@@ -11,6 +11,24 @@ pub enum DatabaseType {
     PostgreSQL,
     #[serde(rename = "mongodb")]
     MongoDB,
+    #[serde(rename = "sqlite")]
+    SQLite,
+    #[serde(rename = "folder")]
+    LocalFolder,
+    #[serde(rename = "webdav")]
+    WebDAV,
+    #[serde(rename = "s3")]
+    S3,
+}
+
+impl DatabaseType {
+    /// Whether the target stores encrypted change logs as files rather than database rows
+    pub fn is_file_based(&self) -> bool {
+        matches!(
+            self,
+            DatabaseType::LocalFolder | DatabaseType::WebDAV | DatabaseType::S3
+        )
+    }
 }
 
 impl std::fmt::Display for DatabaseType {
@@ -19,6 +37,10 @@ impl std::fmt::Display for DatabaseType {
             DatabaseType::MySQL => write!(f, "mysql"),
             DatabaseType::PostgreSQL => write!(f, "postgresql"),
             DatabaseType::MongoDB => write!(f, "mongodb"),
+            DatabaseType::SQLite => write!(f, "sqlite"),
+            DatabaseType::LocalFolder => write!(f, "folder"),
+            DatabaseType::WebDAV => write!(f, "webdav"),
+            DatabaseType::S3 => write!(f, "s3"),
         }
     }
 }
@@ -31,6 +53,10 @@ impl std::str::FromStr for DatabaseType {
             "mysql" => Ok(DatabaseType::MySQL),
             "postgresql" | "postgres" => Ok(DatabaseType::PostgreSQL),
             "mongodb" | "mongo" => Ok(DatabaseType::MongoDB),
+            "sqlite" | "sqlite3" => Ok(DatabaseType::SQLite),
+            "folder" | "file" => Ok(DatabaseType::LocalFolder),
+            "webdav" => Ok(DatabaseType::WebDAV),
+            "s3" => Ok(DatabaseType::S3),
             _ => Err(format!("Unknown database type: {}", s)),
         }
     }
@@ -54,15 +80,23 @@ pub struct ConnectionDetails {
     pub protocol: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub options: Option<String>,
+    /// Passphrase encrypting the change logs of file-based sync targets
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption_passphrase: Option<String>,
 }
 
 impl ConnectionDetails {
     /// Build connection string for the database type
+    /// SQLite and file-based targets have no connection string, their location is the host
     pub fn to_connection_string(&self, db_type: &DatabaseType) -> String {
         let protocol = self.protocol.as_deref().unwrap_or(match db_type {
             DatabaseType::MySQL => "mysql",
             DatabaseType::PostgreSQL => "postgresql",
             DatabaseType::MongoDB => "mongodb",
+            DatabaseType::SQLite
+            | DatabaseType::LocalFolder
+            | DatabaseType::WebDAV
+            | DatabaseType::S3 => return self.host.clone(),
         });
 
         // Build base URL with or without port
@@ -87,6 +121,16 @@ impl ConnectionDetails {
 
         base_url
     }
+
+    /// Value of a `key=value` pair in the options query string
+    pub fn option(&self, key: &str) -> Option<String> {
+        self.options
+            .as_deref()?
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(k, _)| k.trim() == key)
+            .map(|(_, v)| v.trim().to_string())
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
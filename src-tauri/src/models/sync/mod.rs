@@ -1,13 +1,19 @@
 pub mod conflict;
+pub mod cursor;
+pub mod encryption;
 pub mod external_db;
 pub mod log;
+pub mod plan;
 pub mod progress;
 pub mod settings;
 pub mod stats;
 
 pub use conflict::ConflictResolutionStrategy;
+pub use cursor::SyncCursor;
+pub use encryption::{SyncEncryptionKey, SyncEncryptionStatus};
 pub use external_db::{DatabaseType, ExternalDatabaseConfig};
 pub use log::{SyncDirection, SyncLog};
+pub use plan::SyncPlan;
 pub use progress::SyncProgressEvent;
 pub use settings::{SyncSettings, UpdateSyncSettingsRequest};
 pub use stats::SyncStats;
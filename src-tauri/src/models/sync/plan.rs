@@ -0,0 +1,61 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// What a sync would do to a record
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PlannedAction {
+    /// The local copy is written to the remote
+    Push,
+    /// The remote copy is written locally
+    Pull,
+    /// A local deletion is written to the remote
+    DeleteRemote,
+    /// A remote deletion is applied locally
+    DeleteLocal,
+    /// Both sides changed different fields, and the merged record is written to both
+    Merge,
+    /// Both sides changed the same record; settled by the conflict strategy or left for manual resolution
+    Conflict,
+}
+
+/// A change a sync would make to one record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedChange {
+    pub record_id: String,
+    pub action: PlannedAction,
+    /// Copy the change overwrites: the remote one for pushes, the local one otherwise
+    /// `None` for records the overwritten side does not have or did not send
+    pub before: Option<Value>,
+    /// Copy written by the change, `None` for conflicts left for manual resolution
+    pub after: Option<Value>,
+    /// Remote copy a merge or conflict was settled against
+    pub remote: Option<Value>,
+}
+
+/// Changes a sync would make to one table
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableSyncPlan {
+    pub table: String,
+    pub changes: Vec<PlannedChange>,
+}
+
+/// Everything a bidirectional sync would change, computed without changing either side
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncPlan {
+    pub database_id: String,
+    /// Tables with at least one pending change, in sync order
+    pub tables: Vec<TableSyncPlan>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl SyncPlan {
+    /// Number of records the sync would change
+    pub fn change_count(&self) -> usize {
+        self.tables.iter().map(|table| table.changes.len()).sum()
+    }
+}
@@ -41,6 +41,56 @@ impl std::str::FromStr for SyncDirection {
     }
 }
 
+/// Which entity types take part in sync
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SyncedEntities {
+    pub ssh_profiles: bool,
+    pub ssh_groups: bool,
+    pub ssh_keys: bool,
+    pub ssh_tunnels: bool,
+    /// Saved commands and their groups
+    pub saved_commands: bool,
+    pub terminal_profiles: bool,
+    /// Recording metadata only; cast files stay on the device that recorded them
+    pub session_recordings: bool,
+    /// Host keys from ~/.ssh/known_hosts; off unless the user opts in, since a synced key is trusted
+    pub known_hosts: bool,
+}
+
+impl Default for SyncedEntities {
+    fn default() -> Self {
+        Self {
+            ssh_profiles: true,
+            ssh_groups: true,
+            ssh_keys: true,
+            ssh_tunnels: true,
+            saved_commands: true,
+            terminal_profiles: true,
+            session_recordings: true,
+            known_hosts: false,
+        }
+    }
+}
+
+impl SyncedEntities {
+    /// Check whether a sync table is enabled
+    pub fn includes(&self, table: &str) -> bool {
+        match table {
+            "ssh_profiles" => self.ssh_profiles,
+            "ssh_groups" => self.ssh_groups,
+            "ssh_keys" => self.ssh_keys,
+            // Tunnels reference their profile, so they need profiles on the remote too
+            "ssh_tunnels" => self.ssh_tunnels && self.ssh_profiles,
+            "saved_commands" | "saved_command_groups" => self.saved_commands,
+            "terminal_profiles" => self.terminal_profiles,
+            "session_recordings" => self.session_recordings,
+            "known_hosts" => self.known_hosts,
+            _ => false,
+        }
+    }
+}
+
 /// Global sync settings (applies to ALL external databases)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -69,6 +119,10 @@ pub struct SyncSettings {
     /// Last sync timestamp
     pub last_sync_at: Option<DateTime<Utc>>,
 
+    /// Per-entity enable/disable toggles
+    #[serde(default)]
+    pub synced_entities: SyncedEntities,
+
     /// Created timestamp
     pub created_at: DateTime<Utc>,
 
@@ -89,6 +143,7 @@ impl SyncSettings {
             sync_direction: SyncDirection::Both,
             selected_database_id: None,
             last_sync_at: None,
+            synced_entities: SyncedEntities::default(),
             created_at: now,
             updated_at: now,
         }
@@ -110,4 +165,6 @@ pub struct UpdateSyncSettingsRequest {
     pub conflict_strategy: Option<String>,
     pub sync_direction: Option<String>,
     pub selected_database_id: Option<String>,
+    #[serde(default)]
+    pub synced_entities: Option<SyncedEntities>,
 }
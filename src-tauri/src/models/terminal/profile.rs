@@ -1,10 +1,15 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::{impl_syncable, models::base::BaseModel};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TerminalProfile {
-    pub id: String,
+    /// Base model with sync metadata
+    #[serde(flatten)]
+    pub base: BaseModel,
+
     pub name: String,
     pub shell: String,
     pub working_dir: Option<String>,
@@ -12,10 +17,10 @@ pub struct TerminalProfile {
     pub icon: Option<String>,
     pub color: Option<String>,
     pub command: Option<String>,
-    pub created_at: i64,
-    pub updated_at: i64,
 }
 
+impl_syncable!(TerminalProfile, "terminal_profiles");
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateTerminalProfileRequest {
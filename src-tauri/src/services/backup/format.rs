@@ -0,0 +1,659 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+use crate::database::{
+    encryption::AESEncryption,
+    error::{DatabaseError, DatabaseResult, EncryptionError},
+};
+use crate::models::backup::{BackupEntity, BackupEntityCount, BackupKdf, BackupSummary};
+use crate::models::saved_command::{SavedCommand, SavedCommandGroup};
+use crate::models::ssh::{SSHGroup, SSHKey, SSHProfile, SSHTunnel};
+use crate::models::sync::SyncSettings;
+use crate::models::terminal::profile::TerminalProfile;
+
+/// Marker identifying a versioned backup container
+pub const BACKUP_FORMAT: &str = "kerminal-backup";
+/// Layout of the container (header, payload, checksum)
+pub const FORMAT_VERSION: u32 = 2;
+/// Layout of the payload; bumped together with a new entry in `MIGRATIONS`
+pub const SCHEMA_VERSION: u32 = 2;
+
+const CIPHER: &str = "aes-256-gcm";
+const ARGON2_MEMORY_KIB: u32 = 19_456;
+const ARGON2_ITERATIONS: u32 = 2;
+const ARGON2_PARALLELISM: u32 = 1;
+const PBKDF2_ITERATIONS: u32 = 600_000;
+/// Iterations used by 1.0 backups, which had no header to record them
+const LEGACY_PBKDF2_ITERATIONS: u32 = 100_000;
+const LEGACY_SALT_LEN: usize = 32;
+
+// Upper bounds on header parameters, so a crafted file cannot stall the app
+const MAX_ARGON2_MEMORY_KIB: u32 = 1_048_576;
+const MAX_ARGON2_ITERATIONS: u32 = 10;
+const MAX_ARGON2_PARALLELISM: u32 = 16;
+const MAX_PBKDF2_ITERATIONS: u32 = 10_000_000;
+
+/// Backed up records; field names are the stable on-disk schema
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupData {
+    pub schema_version: u32,
+    pub profiles: Vec<SSHProfile>,
+    pub keys: Vec<SSHKey>,
+    pub groups: Vec<SSHGroup>,
+    pub tunnels: Vec<SSHTunnel>,
+    pub saved_commands: Vec<SavedCommand>,
+    pub saved_command_groups: Vec<SavedCommandGroup>,
+    pub terminal_profiles: Vec<TerminalProfile>,
+    #[serde(default)]
+    pub sync_settings: Option<SyncSettings>,
+    #[serde(default)]
+    pub known_hosts: Option<String>,
+    /// Set when the backup was taken while the database was locked, so passwords, certificates
+    /// and private keys are blank
+    #[serde(default)]
+    pub secrets_omitted: bool,
+}
+
+impl BackupData {
+    /// Entities that hold data in this backup
+    pub fn entities(&self) -> Vec<BackupEntity> {
+        self.counts()
+            .into_iter()
+            .filter(|c| c.count > 0)
+            .map(|c| c.entity)
+            .collect()
+    }
+
+    pub fn counts(&self) -> Vec<BackupEntityCount> {
+        BackupEntity::ALL
+            .into_iter()
+            .map(|entity| {
+                let count = match entity {
+                    BackupEntity::SshGroups => self.groups.len(),
+                    BackupEntity::SshKeys => self.keys.len(),
+                    BackupEntity::SshProfiles => self.profiles.len(),
+                    BackupEntity::SshTunnels => self.tunnels.len(),
+                    BackupEntity::SavedCommands => {
+                        self.saved_commands.len() + self.saved_command_groups.len()
+                    }
+                    BackupEntity::TerminalProfiles => self.terminal_profiles.len(),
+                    BackupEntity::SyncSettings => usize::from(self.sync_settings.is_some()),
+                    BackupEntity::KnownHosts => self
+                        .known_hosts
+                        .as_deref()
+                        .map_or(0, |hosts| known_host_lines(hosts).count()),
+                };
+                BackupEntityCount { entity, count }
+            })
+            .collect()
+    }
+}
+
+/// Non-comment lines of a known_hosts file
+pub fn known_host_lines(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+/// Key derivation parameters, stored in the header so they can change between releases
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "algorithm")]
+pub enum KdfParams {
+    #[serde(rename = "argon2id", rename_all = "camelCase")]
+    Argon2id {
+        memory_kib: u32,
+        iterations: u32,
+        parallelism: u32,
+        salt: String,
+    },
+    #[serde(rename = "pbkdf2-sha256", rename_all = "camelCase")]
+    Pbkdf2Sha256 { iterations: u32, salt: String },
+}
+
+impl KdfParams {
+    /// Current parameters for the algorithm, with a fresh salt
+    pub fn new(kdf: BackupKdf) -> Self {
+        let salt = general_purpose::STANDARD.encode(AESEncryption::generate_salt());
+        match kdf {
+            BackupKdf::Argon2id => KdfParams::Argon2id {
+                memory_kib: ARGON2_MEMORY_KIB,
+                iterations: ARGON2_ITERATIONS,
+                parallelism: ARGON2_PARALLELISM,
+                salt,
+            },
+            BackupKdf::Pbkdf2 => KdfParams::Pbkdf2Sha256 {
+                iterations: PBKDF2_ITERATIONS,
+                salt,
+            },
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            KdfParams::Argon2id { .. } => "argon2id",
+            KdfParams::Pbkdf2Sha256 { .. } => "pbkdf2-sha256",
+        }
+    }
+
+    fn derive_key(&self, password: &str) -> DatabaseResult<Zeroizing<[u8; 32]>> {
+        let mut key = Zeroizing::new([0u8; 32]);
+        match self {
+            KdfParams::Argon2id {
+                memory_kib,
+                iterations,
+                parallelism,
+                salt,
+            } => {
+                if *memory_kib > MAX_ARGON2_MEMORY_KIB
+                    || *iterations > MAX_ARGON2_ITERATIONS
+                    || *parallelism > MAX_ARGON2_PARALLELISM
+                {
+                    return Err(invalid_backup("Argon2 parameters are out of range"));
+                }
+                let params = Params::new(*memory_kib, *iterations, *parallelism, Some(32))
+                    .map_err(|e| EncryptionError::KeyDerivationFailed(e.to_string()))?;
+                Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                    .hash_password_into(password.as_bytes(), &decode_salt(salt)?, key.as_mut())
+                    .map_err(|e| EncryptionError::KeyDerivationFailed(e.to_string()))?;
+            }
+            KdfParams::Pbkdf2Sha256 { iterations, salt } => {
+                if *iterations == 0 || *iterations > MAX_PBKDF2_ITERATIONS {
+                    return Err(invalid_backup("PBKDF2 iterations are out of range"));
+                }
+                pbkdf2::pbkdf2::<hmac::Hmac<Sha256>>(
+                    password.as_bytes(),
+                    &decode_salt(salt)?,
+                    *iterations,
+                    key.as_mut(),
+                )
+                .map_err(|e| EncryptionError::KeyDerivationFailed(e.to_string()))?;
+            }
+        }
+        Ok(key)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupEncryption {
+    pub cipher: String,
+    pub kdf: KdfParams,
+}
+
+/// Unencrypted header; authenticated as AES-GCM associated data when the payload is encrypted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupHeader {
+    pub format_version: u32,
+    pub schema_version: u32,
+    pub created_at: DateTime<Utc>,
+    pub app_version: String,
+    pub entities: Vec<BackupEntity>,
+    pub encryption: Option<BackupEncryption>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupContainer {
+    format: String,
+    header: Value,
+    /// Payload JSON, or base64 of nonce + ciphertext when encrypted
+    payload: String,
+    /// SHA-256 over header and payload, catching corruption before any key derivation
+    checksum: String,
+}
+
+enum ParsedBackup {
+    Container {
+        header: BackupHeader,
+        header_bytes: Vec<u8>,
+        payload: String,
+    },
+    /// 1.0 backup written as bare JSON
+    LegacyPlain(Value),
+    /// 1.0 backup: base64 of salt + AES-GCM ciphertext, key from PBKDF2
+    LegacyEncrypted(Vec<u8>),
+}
+
+fn invalid_backup(message: &str) -> DatabaseError {
+    DatabaseError::ValidationError(format!("Invalid backup file: {}", message))
+}
+
+fn decode_salt(salt: &str) -> DatabaseResult<Vec<u8>> {
+    let salt = general_purpose::STANDARD
+        .decode(salt)
+        .map_err(|_| invalid_backup("salt is not base64"))?;
+    if salt.len() < 16 {
+        return Err(invalid_backup("salt is too short"));
+    }
+    Ok(salt)
+}
+
+/// Header bytes covered by the checksum and AEAD; `Value` serialization is stable across a round trip
+fn header_bytes(header: &Value) -> DatabaseResult<Vec<u8>> {
+    Ok(serde_json::to_vec(header)?)
+}
+
+fn checksum(header_bytes: &[u8], payload: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(header_bytes);
+    hasher.update(payload.as_bytes());
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Write a backup container, encrypting the payload when a password is given
+pub fn encode_backup(
+    data: &BackupData,
+    encryption: Option<(&str, KdfParams)>,
+) -> DatabaseResult<String> {
+    let header = BackupHeader {
+        format_version: FORMAT_VERSION,
+        schema_version: data.schema_version,
+        created_at: Utc::now(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        entities: data.entities(),
+        encryption: encryption.as_ref().map(|(_, kdf)| BackupEncryption {
+            cipher: CIPHER.to_string(),
+            kdf: kdf.clone(),
+        }),
+    };
+    let header = serde_json::to_value(&header)?;
+    let header_bytes = header_bytes(&header)?;
+    let plaintext = Zeroizing::new(serde_json::to_string(data)?);
+
+    let payload = match encryption {
+        Some((password, kdf)) => {
+            let key = kdf.derive_key(password)?;
+            let sealed =
+                AESEncryption::encrypt_with_aad(&key, plaintext.as_bytes(), &header_bytes)?;
+            general_purpose::STANDARD.encode(sealed)
+        }
+        None => plaintext.to_string(),
+    };
+
+    let container = BackupContainer {
+        format: BACKUP_FORMAT.to_string(),
+        checksum: checksum(&header_bytes, &payload),
+        header,
+        payload,
+    };
+    Ok(serde_json::to_string_pretty(&container)?)
+}
+
+fn parse_backup(content: &str) -> DatabaseResult<ParsedBackup> {
+    let content = content.trim();
+    if !content.starts_with('{') {
+        let bytes = general_purpose::STANDARD
+            .decode(content)
+            .map_err(|_| invalid_backup("not JSON or base64"))?;
+        if bytes.len() <= LEGACY_SALT_LEN {
+            return Err(invalid_backup("encrypted data is too short"));
+        }
+        return Ok(ParsedBackup::LegacyEncrypted(bytes));
+    }
+
+    let value: Value = serde_json::from_str(content).map_err(|e| invalid_backup(&e.to_string()))?;
+    if value.get("format").and_then(Value::as_str) != Some(BACKUP_FORMAT) {
+        return Ok(ParsedBackup::LegacyPlain(value));
+    }
+
+    let container: BackupContainer =
+        serde_json::from_value(value).map_err(|e| invalid_backup(&e.to_string()))?;
+    let header_bytes = header_bytes(&container.header)?;
+    if checksum(&header_bytes, &container.payload) != container.checksum {
+        return Err(DatabaseError::ValidationError(
+            "Backup integrity check failed: the file is corrupted or was modified".to_string(),
+        ));
+    }
+
+    let header: BackupHeader =
+        serde_json::from_value(container.header).map_err(|e| invalid_backup(&e.to_string()))?;
+    if header.format_version > FORMAT_VERSION {
+        return Err(DatabaseError::ValidationError(format!(
+            "Backup format {} was written by a newer version of the app",
+            header.format_version
+        )));
+    }
+
+    Ok(ParsedBackup::Container {
+        header,
+        header_bytes,
+        payload: container.payload,
+    })
+}
+
+fn require_password(password: Option<&str>) -> DatabaseResult<&str> {
+    password.ok_or_else(|| {
+        DatabaseError::ValidationError("This backup is encrypted; enter its password".to_string())
+    })
+}
+
+fn wrong_password() -> DatabaseError {
+    EncryptionError::DecryptionFailed("wrong password or damaged backup".to_string()).into()
+}
+
+fn decrypt_payload(parsed: ParsedBackup, password: Option<&str>) -> DatabaseResult<Value> {
+    let plaintext = match parsed {
+        ParsedBackup::LegacyPlain(value) => return Ok(value),
+        ParsedBackup::Container {
+            header,
+            header_bytes,
+            payload,
+        } => match header.encryption {
+            None => {
+                return serde_json::from_str(&payload).map_err(|e| invalid_backup(&e.to_string()))
+            }
+            Some(encryption) => {
+                if encryption.cipher != CIPHER {
+                    return Err(invalid_backup("unsupported cipher"));
+                }
+                let key = encryption.kdf.derive_key(require_password(password)?)?;
+                let sealed = general_purpose::STANDARD
+                    .decode(payload)
+                    .map_err(|_| invalid_backup("payload is not base64"))?;
+                AESEncryption::decrypt_with_aad(&key, &sealed, &header_bytes)
+                    .map_err(|_| wrong_password())?
+            }
+        },
+        ParsedBackup::LegacyEncrypted(bytes) => {
+            let (salt, sealed) = bytes.split_at(LEGACY_SALT_LEN);
+            let kdf = KdfParams::Pbkdf2Sha256 {
+                iterations: LEGACY_PBKDF2_ITERATIONS,
+                salt: general_purpose::STANDARD.encode(salt),
+            };
+            let key = kdf.derive_key(require_password(password)?)?;
+            AESEncryption::decrypt(&key, sealed).map_err(|_| wrong_password())?
+        }
+    };
+
+    let plaintext = Zeroizing::new(plaintext);
+    serde_json::from_slice(&plaintext).map_err(|e| invalid_backup(&e.to_string()))
+}
+
+/// Upgrades a payload from the schema version it is keyed under to the next one
+type Migration = fn(&mut Map<String, Value>);
+
+const MIGRATIONS: &[(u32, Migration)] = &[(1, migrate_v1_to_v2)];
+
+/// 1.0 payloads carried their own version and timestamp, which now live in the header
+fn migrate_v1_to_v2(payload: &mut Map<String, Value>) {
+    payload.remove("version");
+    payload.remove("created_at");
+    payload
+        .entry("terminal_profiles")
+        .or_insert_with(|| json!([]));
+}
+
+/// Bring a payload of any known schema version up to `SCHEMA_VERSION`
+/// Returns the data and the schema version the backup was written with
+pub fn migrate_payload(mut payload: Value) -> DatabaseResult<(BackupData, u32)> {
+    let object = payload
+        .as_object_mut()
+        .ok_or_else(|| invalid_backup("payload is not an object"))?;
+
+    // 1.0 payloads have `"version": "1.0"` instead of a schema version
+    let source_version = object
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .map_or(1, |v| v as u32);
+    if source_version > SCHEMA_VERSION {
+        return Err(DatabaseError::ValidationError(format!(
+            "Backup schema {} was written by a newer version of the app",
+            source_version
+        )));
+    }
+
+    let mut version = source_version;
+    for (from, migrate) in MIGRATIONS {
+        if *from == version {
+            migrate(object);
+            version += 1;
+        }
+    }
+    if version != SCHEMA_VERSION {
+        return Err(DatabaseError::MigrationError(format!(
+            "No migration path from backup schema {}",
+            source_version
+        )));
+    }
+    object.insert("schema_version".to_string(), json!(SCHEMA_VERSION));
+
+    let data = serde_json::from_value(payload).map_err(|e| invalid_backup(&e.to_string()))?;
+    Ok((data, source_version))
+}
+
+/// Read, verify, decrypt and migrate a backup
+pub fn decode_backup(content: &str, password: Option<&str>) -> DatabaseResult<(BackupData, u32)> {
+    let parsed = parse_backup(content)?;
+    migrate_payload(decrypt_payload(parsed, password)?)
+}
+
+/// Describe a backup; record counts need the password when the backup is encrypted
+pub fn read_summary(content: &str, password: Option<&str>) -> DatabaseResult<BackupSummary> {
+    let parsed = parse_backup(content)?;
+
+    let mut summary = match &parsed {
+        ParsedBackup::Container { header, .. } => BackupSummary {
+            format_version: header.format_version,
+            schema_version: header.schema_version,
+            created_at: Some(header.created_at.to_rfc3339()),
+            app_version: Some(header.app_version.clone()),
+            encrypted: header.encryption.is_some(),
+            kdf: header
+                .encryption
+                .as_ref()
+                .map(|encryption| encryption.kdf.name().to_string()),
+            entities: header.entities.clone(),
+            counts: None,
+        },
+        ParsedBackup::LegacyPlain(value) => BackupSummary {
+            format_version: 1,
+            schema_version: 1,
+            created_at: value
+                .get("created_at")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            app_version: None,
+            encrypted: false,
+            kdf: None,
+            entities: Vec::new(),
+            counts: None,
+        },
+        ParsedBackup::LegacyEncrypted(_) => BackupSummary {
+            format_version: 1,
+            schema_version: 1,
+            created_at: None,
+            app_version: None,
+            encrypted: true,
+            kdf: Some("pbkdf2-sha256".to_string()),
+            entities: Vec::new(),
+            counts: None,
+        },
+    };
+
+    if summary.encrypted && password.is_none() {
+        return Ok(summary);
+    }
+
+    let (data, _) = migrate_payload(decrypt_payload(parsed, password)?)?;
+    if summary.entities.is_empty() {
+        summary.entities = data.entities();
+    }
+    summary.counts = Some(data.counts());
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_data() -> BackupData {
+        BackupData {
+            schema_version: SCHEMA_VERSION,
+            profiles: Vec::new(),
+            keys: Vec::new(),
+            groups: Vec::new(),
+            tunnels: Vec::new(),
+            saved_commands: Vec::new(),
+            saved_command_groups: Vec::new(),
+            terminal_profiles: Vec::new(),
+            sync_settings: None,
+            known_hosts: Some("# comment\nexample.com ssh-ed25519 AAAA\n".to_string()),
+            secrets_omitted: false,
+        }
+    }
+
+    fn fast_argon2() -> KdfParams {
+        KdfParams::Argon2id {
+            memory_kib: 64,
+            iterations: 1,
+            parallelism: 1,
+            salt: general_purpose::STANDARD.encode([7u8; 16]),
+        }
+    }
+
+    fn tamper(content: &str, edit: impl FnOnce(&mut Value)) -> String {
+        let mut container: Value = serde_json::from_str(content).unwrap();
+        edit(&mut container);
+        let header = header_bytes(&container["header"]).unwrap();
+        let payload = container["payload"].as_str().unwrap().to_string();
+        container["checksum"] = json!(checksum(&header, &payload));
+        container.to_string()
+    }
+
+    #[test]
+    fn test_plain_backup_round_trip() {
+        let encoded = encode_backup(&sample_data(), None).unwrap();
+
+        let summary = read_summary(&encoded, None).unwrap();
+        assert!(!summary.encrypted);
+        assert_eq!(summary.format_version, FORMAT_VERSION);
+        assert_eq!(summary.entities, vec![BackupEntity::KnownHosts]);
+        let counts = summary.counts.unwrap();
+        assert_eq!(counts.iter().map(|c| c.count).sum::<usize>(), 1);
+
+        let (data, source_version) = decode_backup(&encoded, None).unwrap();
+        assert_eq!(source_version, SCHEMA_VERSION);
+        assert_eq!(data.known_hosts, sample_data().known_hosts);
+    }
+
+    #[test]
+    fn test_encrypted_backup_requires_password() {
+        for kdf in [
+            fast_argon2(),
+            KdfParams::Pbkdf2Sha256 {
+                iterations: 1_000,
+                salt: general_purpose::STANDARD.encode([9u8; 16]),
+            },
+        ] {
+            let encoded = encode_backup(&sample_data(), Some(("secret", kdf.clone()))).unwrap();
+            assert!(!encoded.contains("example.com"));
+
+            let summary = read_summary(&encoded, None).unwrap();
+            assert!(summary.encrypted);
+            assert_eq!(summary.kdf.as_deref(), Some(kdf.name()));
+            assert!(summary.counts.is_none());
+
+            assert!(decode_backup(&encoded, None).is_err());
+            assert!(decode_backup(&encoded, Some("wrong")).is_err());
+            let (data, _) = decode_backup(&encoded, Some("secret")).unwrap();
+            assert_eq!(data.known_hosts, sample_data().known_hosts);
+        }
+    }
+
+    #[test]
+    fn test_tampering_is_detected() {
+        let plain = encode_backup(&sample_data(), None).unwrap();
+        let mut corrupted: Value = serde_json::from_str(&plain).unwrap();
+        corrupted["payload"] = json!(corrupted["payload"]
+            .as_str()
+            .unwrap()
+            .replace("example.com", "attacker.io"));
+        let err = decode_backup(&corrupted.to_string(), None).unwrap_err();
+        assert!(err.to_string().contains("integrity"));
+
+        // Rewriting the header and its checksum still breaks the authenticated payload
+        let encrypted = encode_backup(&sample_data(), Some(("secret", fast_argon2()))).unwrap();
+        let downgraded = tamper(&encrypted, |container| {
+            container["header"]["schemaVersion"] = json!(1);
+        });
+        assert!(decode_backup(&downgraded, Some("secret")).is_err());
+    }
+
+    #[test]
+    fn test_costly_kdf_headers_are_rejected() {
+        let encrypted = encode_backup(&sample_data(), Some(("secret", fast_argon2()))).unwrap();
+        for (field, value) in [
+            ("iterations", MAX_ARGON2_ITERATIONS + 1),
+            ("parallelism", MAX_ARGON2_PARALLELISM + 1),
+            ("memoryKib", MAX_ARGON2_MEMORY_KIB + 1),
+        ] {
+            let costly = tamper(&encrypted, |container| {
+                container["header"]["encryption"]["kdf"][field] = json!(value);
+            });
+            let err = decode_backup(&costly, Some("secret")).unwrap_err();
+            assert!(
+                err.to_string().contains("out of range"),
+                "{}: {}",
+                field,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_legacy_backups_are_migrated() {
+        let legacy = json!({
+            "version": "1.0",
+            "created_at": "2024-01-01T00:00:00Z",
+            "profiles": [],
+            "keys": [],
+            "groups": [],
+            "tunnels": [],
+            "saved_commands": [],
+            "saved_command_groups": [],
+        });
+
+        let summary = read_summary(&legacy.to_string(), None).unwrap();
+        assert_eq!(summary.schema_version, 1);
+        assert_eq!(summary.created_at.as_deref(), Some("2024-01-01T00:00:00Z"));
+
+        let (data, source_version) = decode_backup(&legacy.to_string(), None).unwrap();
+        assert_eq!(source_version, 1);
+        assert_eq!(data.schema_version, SCHEMA_VERSION);
+        assert!(data.terminal_profiles.is_empty());
+
+        // 1.0 encrypted backups: base64(salt + AES-GCM) with a PBKDF2 key
+        let salt = [3u8; LEGACY_SALT_LEN];
+        let key = KdfParams::Pbkdf2Sha256 {
+            iterations: LEGACY_PBKDF2_ITERATIONS,
+            salt: general_purpose::STANDARD.encode(salt),
+        }
+        .derive_key("secret")
+        .unwrap();
+        let mut sealed = salt.to_vec();
+        sealed.extend(AESEncryption::encrypt(&key, legacy.to_string().as_bytes()).unwrap());
+        let encoded = general_purpose::STANDARD.encode(sealed);
+
+        assert!(read_summary(&encoded, None).unwrap().encrypted);
+        let (_, source_version) = decode_backup(&encoded, Some("secret")).unwrap();
+        assert_eq!(source_version, 1);
+    }
+
+    #[test]
+    fn test_newer_schema_is_rejected() {
+        let mut payload = serde_json::to_value(sample_data()).unwrap();
+        payload["schema_version"] = json!(SCHEMA_VERSION + 1);
+        assert!(migrate_payload(payload).is_err());
+    }
+}
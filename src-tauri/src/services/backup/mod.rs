@@ -0,0 +1,8 @@
+pub mod format;
+pub mod schedule;
+pub mod scheduler;
+pub mod service;
+pub mod snapshots;
+
+pub use scheduler::BackupScheduler;
+pub use service::BackupService;
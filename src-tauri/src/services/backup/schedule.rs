@@ -0,0 +1,214 @@
+use chrono::{Datelike, Duration, NaiveDateTime, Timelike};
+
+use crate::database::error::{DatabaseError, DatabaseResult};
+
+/// Longest stretch searched for the next run, so impossible dates like `0 0 31 2 *` end the search
+const MAX_SEARCH_DAYS: i64 = 366 * 4;
+
+/// Values allowed by one cron field, as a bit set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FieldSet(u64);
+
+impl FieldSet {
+    fn contains(&self, value: u32) -> bool {
+        self.0 & (1 << value) != 0
+    }
+}
+
+/// Five-field cron expression: minute, hour, day of month, month, day of week
+///
+/// Each field accepts `*`, single values, ranges (`1-5`), lists (`1,15`) and steps (`*/15`, `0-30/10`).
+/// Day of week runs from 0 (Sunday) to 7 (also Sunday). As in cron, when both day fields are
+/// restricted a day matches if either one does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: FieldSet,
+    hours: FieldSet,
+    days_of_month: FieldSet,
+    months: FieldSet,
+    days_of_week: FieldSet,
+    day_of_month_any: bool,
+    day_of_week_any: bool,
+}
+
+fn parse_value(value: &str, field: &str, min: u32, max: u32) -> DatabaseResult<u32> {
+    let parsed: u32 = value.parse().map_err(|_| {
+        DatabaseError::ValidationError(format!("Invalid {} value '{}'", field, value))
+    })?;
+    if parsed < min || parsed > max {
+        return Err(DatabaseError::ValidationError(format!(
+            "{} value {} is outside {}-{}",
+            field, parsed, min, max
+        )));
+    }
+    Ok(parsed)
+}
+
+fn parse_field(expr: &str, field: &str, min: u32, max: u32) -> DatabaseResult<FieldSet> {
+    let mut bits = 0u64;
+
+    for part in expr.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, parse_value(step, field, 1, max)?),
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            let start = parse_value(start, field, min, max)?;
+            let end = parse_value(end, field, min, max)?;
+            if start > end {
+                return Err(DatabaseError::ValidationError(format!(
+                    "Invalid {} range '{}'",
+                    field, range
+                )));
+            }
+            (start, end)
+        } else {
+            let value = parse_value(range, field, min, max)?;
+            // `5/15` means every 15 starting at 5
+            (value, if part.contains('/') { max } else { value })
+        };
+
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+
+    Ok(FieldSet(bits))
+}
+
+impl CronSchedule {
+    pub fn parse(expr: &str) -> DatabaseResult<Self> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(DatabaseError::ValidationError(format!(
+                "Cron expression '{}' must have 5 fields",
+                expr
+            )));
+        }
+
+        let mut days_of_week = parse_field(fields[4], "day of week", 0, 7)?;
+        if days_of_week.contains(7) {
+            days_of_week.0 |= 1;
+        }
+
+        Ok(Self {
+            minutes: parse_field(fields[0], "minute", 0, 59)?,
+            hours: parse_field(fields[1], "hour", 0, 23)?,
+            days_of_month: parse_field(fields[2], "day of month", 1, 31)?,
+            months: parse_field(fields[3], "month", 1, 12)?,
+            days_of_week,
+            day_of_month_any: fields[2] == "*",
+            day_of_week_any: fields[4] == "*",
+        })
+    }
+
+    fn matches_day(&self, time: &NaiveDateTime) -> bool {
+        let day_of_month = self.days_of_month.contains(time.day());
+        let day_of_week = self
+            .days_of_week
+            .contains(time.weekday().num_days_from_sunday());
+
+        match (self.day_of_month_any, self.day_of_week_any) {
+            (true, true) => true,
+            (true, false) => day_of_week,
+            (false, true) => day_of_month,
+            (false, false) => day_of_month || day_of_week,
+        }
+    }
+
+    /// First matching minute strictly after `after`
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = after + Duration::days(MAX_SEARCH_DAYS);
+
+        while time <= limit {
+            if !self.months.contains(time.month()) || !self.matches_day(&time) {
+                time = time.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if !self.hours.contains(time.hour()) {
+                time = time.with_minute(0)? + Duration::hours(1);
+            } else if !self.minutes.contains(time.minute()) {
+                time += Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_next_after_daily_and_stepped() {
+        let daily = CronSchedule::parse("0 3 * * *").unwrap();
+        assert_eq!(
+            daily.next_after(at(2024, 5, 1, 2, 59)),
+            Some(at(2024, 5, 1, 3, 0))
+        );
+        assert_eq!(
+            daily.next_after(at(2024, 5, 1, 3, 0)),
+            Some(at(2024, 5, 2, 3, 0))
+        );
+
+        let stepped = CronSchedule::parse("*/15 9-17 * * 1-5").unwrap();
+        // Saturday 2024-05-04 rolls over to Monday morning
+        assert_eq!(
+            stepped.next_after(at(2024, 5, 3, 17, 45)),
+            Some(at(2024, 5, 6, 9, 0))
+        );
+        assert_eq!(
+            stepped.next_after(at(2024, 5, 6, 9, 1)),
+            Some(at(2024, 5, 6, 9, 15))
+        );
+    }
+
+    #[test]
+    fn test_day_fields_match_either_when_both_restricted() {
+        // The 1st of the month or any Sunday
+        let schedule = CronSchedule::parse("30 1 1 * 7").unwrap();
+        assert_eq!(
+            schedule.next_after(at(2024, 5, 1, 2, 0)),
+            Some(at(2024, 5, 5, 1, 30))
+        );
+        assert_eq!(
+            schedule.next_after(at(2024, 5, 26, 2, 0)),
+            Some(at(2024, 6, 1, 1, 30))
+        );
+
+        assert_eq!(
+            CronSchedule::parse("0 0 31 2 *")
+                .unwrap()
+                .next_after(at(2024, 1, 1, 0, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_expressions() {
+        for expr in [
+            "0 3 * *",
+            "60 * * * *",
+            "0 24 * * *",
+            "0 0 0 * *",
+            "5-1 * * * *",
+            "*/0 * * * *",
+            "a * * * *",
+        ] {
+            assert!(CronSchedule::parse(expr).is_err(), "{}", expr);
+        }
+    }
+}
@@ -0,0 +1,418 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Local, TimeZone, Utc};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::{
+    sync::Mutex,
+    time::{interval, Duration as TokioDuration},
+};
+
+use crate::database::{
+    encryption::keychain::KeychainManager,
+    error::{DatabaseError, DatabaseResult},
+    service::DatabaseService,
+};
+use crate::models::backup::{
+    BackupScheduleSettings, BackupScheduleStatus, BackupSnapshot, ExportBackupOptions,
+    RestoreOptions, RestoreReport, SnapshotReason, UpdateBackupScheduleRequest,
+};
+use crate::models::sync::external_db::ExternalDatabaseConfig;
+use crate::services::backup::{
+    schedule::CronSchedule,
+    snapshots::{expired_snapshots, SnapshotStore},
+    BackupService,
+};
+use crate::services::sync::PullHandler;
+
+const SETTINGS_FILE: &str = "backup_schedule.json";
+const DEFAULT_SNAPSHOT_DIR: &str = "backups";
+
+/// Auto-sync pulls every few minutes; one snapshot an hour is enough to roll back a bad pull
+const SYNC_PULL_SNAPSHOT_INTERVAL_MINUTES: i64 = 60;
+
+/// First run of `schedule` after `after`, skipping local times that do not exist because of DST
+fn next_run(schedule: &CronSchedule, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let mut local = after.with_timezone(&Local).naive_local();
+    loop {
+        local = schedule.next_after(local)?;
+        if let Some(time) = Local.from_local_datetime(&local).earliest() {
+            return Some(time.with_timezone(&Utc));
+        }
+    }
+}
+
+fn load_settings(path: &Path) -> BackupScheduleSettings {
+    match std::fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            eprintln!("[BackupScheduler] Invalid {}: {}", path.display(), e);
+            BackupScheduleSettings::default()
+        }),
+        Err(e) if e.kind() == ErrorKind::NotFound => BackupScheduleSettings::default(),
+        Err(e) => {
+            eprintln!("[BackupScheduler] Failed to read {}: {}", path.display(), e);
+            BackupScheduleSettings::default()
+        }
+    }
+}
+
+/// Writes encrypted snapshots of the local database on a cron schedule and before risky operations
+pub struct BackupScheduler {
+    database_service: Arc<Mutex<DatabaseService>>,
+    backup_service: Arc<BackupService>,
+    keychain: Arc<KeychainManager>,
+    data_dir: PathBuf,
+    settings: Arc<Mutex<BackupScheduleSettings>>,
+    is_running: Arc<Mutex<bool>>,
+    /// Runs are scheduled after the newest scheduled snapshot, or after startup when there is none
+    started_at: DateTime<Utc>,
+    /// Set when scheduling is turned on or a run fails, so runs missed before then are not retried
+    schedule_anchor: Arc<Mutex<Option<DateTime<Utc>>>>,
+    /// Serializes snapshot writes and retention
+    snapshot_lock: Arc<Mutex<()>>,
+}
+
+impl BackupScheduler {
+    pub fn new(
+        database_service: Arc<Mutex<DatabaseService>>,
+        backup_service: Arc<BackupService>,
+        data_dir: PathBuf,
+    ) -> Self {
+        let settings = load_settings(&data_dir.join(SETTINGS_FILE));
+
+        Self {
+            database_service,
+            backup_service,
+            keychain: Arc::new(KeychainManager::new("kerminal".to_string())),
+            data_dir,
+            settings: Arc::new(Mutex::new(settings)),
+            is_running: Arc::new(Mutex::new(false)),
+            started_at: Utc::now(),
+            schedule_anchor: Arc::new(Mutex::new(None)),
+            snapshot_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Start the scheduler
+    pub async fn start(&self) -> DatabaseResult<()> {
+        let mut is_running = self.is_running.lock().await;
+        if *is_running {
+            return Ok(());
+        }
+
+        *is_running = true;
+        drop(is_running);
+
+        let scheduler = self.clone_for_task();
+        tokio::spawn(async move {
+            scheduler.run_loop().await;
+        });
+
+        Ok(())
+    }
+
+    /// Stop the scheduler
+    #[allow(dead_code)]
+    pub async fn stop(&self) -> DatabaseResult<()> {
+        let mut is_running = self.is_running.lock().await;
+        *is_running = false;
+        Ok(())
+    }
+
+    /// Main scheduler loop
+    async fn run_loop(&self) {
+        let mut ticker = interval(TokioDuration::from_secs(60));
+
+        loop {
+            ticker.tick().await;
+
+            let is_running = *self.is_running.lock().await;
+            if !is_running {
+                break;
+            }
+
+            match self.is_due().await {
+                Ok(true) => {
+                    if !self.is_unlocked().await {
+                        continue;
+                    }
+                    if let Err(e) = self.create_snapshot(SnapshotReason::Scheduled).await {
+                        eprintln!("[BackupScheduler] Scheduled snapshot failed: {}", e);
+                        // Wait for the next scheduled time instead of retrying every minute
+                        *self.schedule_anchor.lock().await = Some(Utc::now());
+                    }
+                }
+                Ok(false) => {}
+                Err(e) => eprintln!("[BackupScheduler] Scheduler error: {}", e),
+            }
+        }
+    }
+
+    /// Clone for spawning background task
+    fn clone_for_task(&self) -> Self {
+        Self {
+            database_service: self.database_service.clone(),
+            backup_service: self.backup_service.clone(),
+            keychain: self.keychain.clone(),
+            data_dir: self.data_dir.clone(),
+            settings: self.settings.clone(),
+            is_running: self.is_running.clone(),
+            started_at: self.started_at,
+            schedule_anchor: self.schedule_anchor.clone(),
+            snapshot_lock: self.snapshot_lock.clone(),
+        }
+    }
+
+    /// Snapshots need the database unlocked to read encrypted fields
+    async fn is_unlocked(&self) -> bool {
+        let db_service = self.database_service.lock().await;
+        let manager = db_service.get_master_password_manager_arc();
+        let manager_guard = manager.read().await;
+        manager_guard.get_status().await.is_unlocked
+    }
+
+    async fn device_id(&self) -> String {
+        self.database_service
+            .lock()
+            .await
+            .get_device_id()
+            .to_string()
+    }
+
+    async fn password(&self) -> DatabaseResult<Option<String>> {
+        let device_id = self.device_id().await;
+        Ok(self.keychain.get_backup_password(&device_id)?)
+    }
+
+    fn snapshot_dir(&self, settings: &BackupScheduleSettings) -> PathBuf {
+        match settings.directory.as_deref() {
+            Some(dir) => match (dir.strip_prefix("~/"), dirs::home_dir()) {
+                (Some(rest), Some(home)) => home.join(rest),
+                _ => PathBuf::from(dir),
+            },
+            None => self.data_dir.join(DEFAULT_SNAPSHOT_DIR),
+        }
+    }
+
+    async fn store(&self) -> (SnapshotStore, BackupScheduleSettings) {
+        let settings = self.settings.lock().await.clone();
+        (SnapshotStore::new(self.snapshot_dir(&settings)), settings)
+    }
+
+    /// Time of the next scheduled snapshot, when scheduling is enabled
+    async fn next_run_at(
+        &self,
+        settings: &BackupScheduleSettings,
+        snapshots: &[BackupSnapshot],
+    ) -> DatabaseResult<Option<DateTime<Utc>>> {
+        if !settings.enabled {
+            return Ok(None);
+        }
+
+        let schedule = CronSchedule::parse(&settings.cron)?;
+        let last_scheduled = snapshots
+            .iter()
+            .find(|s| s.reason == SnapshotReason::Scheduled)
+            .map_or(self.started_at, |s| s.created_at);
+        let after = match *self.schedule_anchor.lock().await {
+            Some(anchor) => last_scheduled.max(anchor),
+            None => last_scheduled,
+        };
+
+        Ok(next_run(&schedule, after))
+    }
+
+    async fn is_due(&self) -> DatabaseResult<bool> {
+        let (store, settings) = self.store().await;
+        if !settings.enabled {
+            return Ok(false);
+        }
+
+        let snapshots = store.list().await?;
+        Ok(self
+            .next_run_at(&settings, &snapshots)
+            .await?
+            .is_some_and(|next| next <= Utc::now()))
+    }
+
+    pub async fn get_status(&self) -> DatabaseResult<BackupScheduleStatus> {
+        let (store, settings) = self.store().await;
+        let snapshots = store.list().await?;
+        let password_configured = matches!(self.password().await, Ok(Some(_)));
+
+        Ok(BackupScheduleStatus {
+            next_run_at: self.next_run_at(&settings, &snapshots).await?,
+            last_snapshot_at: snapshots.first().map(|s| s.created_at),
+            snapshot_directory: self.snapshot_dir(&settings).to_string_lossy().to_string(),
+            password_configured,
+            settings,
+        })
+    }
+
+    pub async fn update_settings(
+        &self,
+        request: UpdateBackupScheduleRequest,
+    ) -> DatabaseResult<BackupScheduleStatus> {
+        let mut settings = self.settings.lock().await.clone();
+        let was_enabled = settings.enabled;
+
+        if let Some(enabled) = request.enabled {
+            settings.enabled = enabled;
+        }
+        if let Some(cron) = request.cron {
+            CronSchedule::parse(&cron)?;
+            settings.cron = cron.trim().to_string();
+        }
+        if let Some(directory) = request.directory {
+            let directory = directory.trim();
+            settings.directory = (!directory.is_empty()).then(|| directory.to_string());
+        }
+        if let Some(keep_daily) = request.keep_daily {
+            settings.keep_daily = keep_daily;
+        }
+        if let Some(keep_weekly) = request.keep_weekly {
+            settings.keep_weekly = keep_weekly;
+        }
+        if let Some(kdf) = request.kdf {
+            settings.kdf = kdf;
+        }
+        if let Some(before_risky) = request.snapshot_before_risky_operations {
+            settings.snapshot_before_risky_operations = before_risky;
+        }
+
+        let device_id = self.device_id().await;
+        match request.password.as_deref() {
+            Some("") => self.keychain.delete_backup_password(&device_id)?,
+            Some(password) => self.keychain.store_backup_password(&device_id, password)?,
+            None => {}
+        }
+
+        if settings.enabled && self.keychain.get_backup_password(&device_id)?.is_none() {
+            return Err(DatabaseError::ValidationError(
+                "Set a snapshot password before enabling automatic backups".to_string(),
+            ));
+        }
+
+        let path = self.data_dir.join(SETTINGS_FILE);
+        let content = serde_json::to_string_pretty(&settings)?;
+        let temp = self.data_dir.join(format!(".{}.tmp", SETTINGS_FILE));
+        tokio::fs::write(&temp, content)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(format!("{}: {}", temp.display(), e)))?;
+        tokio::fs::rename(&temp, &path)
+            .await
+            .map_err(|e| DatabaseError::QueryFailed(format!("{}: {}", path.display(), e)))?;
+
+        if settings.enabled && !was_enabled {
+            *self.schedule_anchor.lock().await = Some(Utc::now());
+        }
+        *self.settings.lock().await = settings;
+
+        self.get_status().await
+    }
+
+    pub async fn list_snapshots(&self) -> DatabaseResult<Vec<BackupSnapshot>> {
+        let (store, _) = self.store().await;
+        store.list().await
+    }
+
+    /// Write a snapshot and prune old ones according to the retention settings
+    pub async fn create_snapshot(&self, reason: SnapshotReason) -> DatabaseResult<BackupSnapshot> {
+        let _guard = self.snapshot_lock.lock().await;
+        let (store, settings) = self.store().await;
+
+        let password = self.password().await?.ok_or_else(|| {
+            DatabaseError::ValidationError("No snapshot password is configured".to_string())
+        })?;
+        let options = ExportBackupOptions {
+            password: Some(password),
+            kdf: settings.kdf,
+        };
+        let content = if reason == SnapshotReason::PasswordReset {
+            self.backup_service.export_readable(options).await?
+        } else {
+            self.backup_service.export(options).await?
+        };
+        let snapshot = store.write(reason, Utc::now(), &content).await?;
+
+        let snapshots = store.list().await?;
+        for expired in expired_snapshots(
+            &snapshots,
+            settings.keep_daily,
+            settings.keep_weekly,
+            &Local,
+        ) {
+            if let Err(e) = store.delete(&expired.id).await {
+                eprintln!("[BackupScheduler] Failed to prune {}: {}", expired.id, e);
+            }
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Restore a snapshot with the stored snapshot password
+    pub async fn restore_snapshot(
+        &self,
+        id: &str,
+        options: RestoreOptions,
+    ) -> DatabaseResult<RestoreReport> {
+        let (store, _) = self.store().await;
+        let content = store.read(id).await?;
+        let password = self.password().await?;
+
+        self.backup_service
+            .restore(&content, password.as_deref(), options)
+            .await
+    }
+
+    pub async fn delete_snapshot(&self, id: &str) -> DatabaseResult<()> {
+        let (store, _) = self.store().await;
+        store.delete(id).await
+    }
+
+    /// Best-effort snapshot before an operation that rewrites local data
+    ///
+    /// Skipped when turned off, when no snapshot password is configured, or while the database
+    /// is locked. A reset follows a forgotten master password, so its snapshot is taken locked,
+    /// without secrets. Failures are only logged so the operation itself is never blocked.
+    pub async fn snapshot_before(&self, reason: SnapshotReason) {
+        let (store, settings) = self.store().await;
+        if !settings.snapshot_before_risky_operations {
+            return;
+        }
+
+        if reason == SnapshotReason::SyncPull {
+            let cutoff = Utc::now() - Duration::minutes(SYNC_PULL_SNAPSHOT_INTERVAL_MINUTES);
+            let recent = store
+                .list()
+                .await
+                .map(|snapshots| snapshots.first().is_some_and(|s| s.created_at > cutoff))
+                .unwrap_or(false);
+            if recent {
+                return;
+            }
+        }
+
+        if !matches!(self.password().await, Ok(Some(_))) {
+            return;
+        }
+        if reason != SnapshotReason::PasswordReset && !self.is_unlocked().await {
+            return;
+        }
+
+        if let Err(e) = self.create_snapshot(reason).await {
+            eprintln!(
+                "[BackupScheduler] Failed to take {} snapshot: {}",
+                reason.as_str(),
+                e
+            );
+        }
+    }
+}
+
+#[async_trait]
+impl PullHandler for BackupScheduler {
+    async fn before_pull(&self, _config: &ExternalDatabaseConfig) {
+        self.snapshot_before(SnapshotReason::SyncPull).await;
+    }
+}
@@ -0,0 +1,682 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use zeroize::Zeroizing;
+
+use crate::database::{
+    error::{DatabaseError, DatabaseResult},
+    providers::sqlite::sync_ops::TableChanges,
+    service::DatabaseService,
+    traits::{Database, Encryptable, EncryptionService, Syncable},
+};
+use crate::models::backup::{
+    BackupEntity, BackupSummary, ConflictPolicy, EntityRestoreReport, ExportBackupOptions,
+    RestoreMode, RestoreOptions, RestoreReport,
+};
+use crate::models::saved_command::{SavedCommand, SavedCommandGroup};
+use crate::models::ssh::{AuthData, SSHGroup, SSHKey, SSHProfile, SSHTunnel};
+use crate::models::terminal::profile::TerminalProfile;
+use crate::services::backup::format::{self, known_host_lines, BackupData, KdfParams};
+use crate::services::ssh::config_export::write_if_changed;
+
+/// Record restored by ID
+trait BackupRecord: Syncable {
+    /// Move the record to a fresh ID so it can sit next to the local record it collided with
+    fn assign_new_id(&mut self, id: String);
+}
+
+macro_rules! impl_backup_record {
+    ($($model:ty),*) => {
+        $(
+            impl BackupRecord for $model {
+                fn assign_new_id(&mut self, id: String) {
+                    self.base.id = id;
+                    self.name = format!("{} (restored)", self.name);
+                }
+            }
+        )*
+    };
+}
+
+impl_backup_record!(
+    SSHGroup,
+    SSHKey,
+    SSHProfile,
+    SSHTunnel,
+    SavedCommand,
+    SavedCommandGroup,
+    TerminalProfile
+);
+
+/// Writes and deletes needed to restore one kind of record
+struct RestorePlan<T> {
+    writes: Vec<T>,
+    deletes: Vec<String>,
+    /// Backup ID -> new ID, for records restored next to a colliding local record
+    renamed: HashMap<String, String>,
+    report: EntityRestoreReport,
+}
+
+fn plan_restore<T: BackupRecord>(
+    entity: BackupEntity,
+    records: Vec<T>,
+    existing_ids: &HashSet<String>,
+    options: &RestoreOptions,
+) -> RestorePlan<T> {
+    let mut plan = RestorePlan {
+        writes: Vec::new(),
+        deletes: Vec::new(),
+        renamed: HashMap::new(),
+        report: EntityRestoreReport::new(entity),
+    };
+
+    if options.mode == RestoreMode::Replace {
+        let backup_ids: HashSet<&str> = records.iter().map(|r| r.id()).collect();
+        plan.deletes = existing_ids
+            .iter()
+            .filter(|id| !backup_ids.contains(id.as_str()))
+            .cloned()
+            .collect();
+        plan.report.deleted = plan.deletes.len();
+    }
+
+    for mut record in records {
+        if !existing_ids.contains(record.id()) {
+            plan.report.created += 1;
+            plan.writes.push(record);
+            continue;
+        }
+
+        match (options.mode, options.conflict_policy) {
+            (RestoreMode::Replace, _) | (RestoreMode::Merge, ConflictPolicy::Overwrite) => {
+                plan.report.updated += 1;
+                plan.writes.push(record);
+            }
+            (RestoreMode::Merge, ConflictPolicy::Skip) => {
+                plan.report.skipped += 1;
+            }
+            (RestoreMode::Merge, ConflictPolicy::KeepBoth) => {
+                let new_id = uuid::Uuid::new_v4().to_string();
+                plan.renamed.insert(record.id().to_string(), new_id.clone());
+                record.assign_new_id(new_id);
+                plan.report.renamed += 1;
+                plan.writes.push(record);
+            }
+        }
+    }
+
+    plan
+}
+
+impl<T: Serialize> RestorePlan<T> {
+    /// Queue the plan's writes and deletes for the restore transaction, returning its report
+    fn into_changes(
+        self,
+        table: &'static str,
+        changes: &mut Vec<TableChanges>,
+    ) -> DatabaseResult<EntityRestoreReport> {
+        let writes = self
+            .writes
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<_, _>>()?;
+        changes.push(TableChanges {
+            table,
+            writes,
+            deletes: self.deletes,
+        });
+        Ok(self.report)
+    }
+}
+
+fn record_ids<T: Syncable>(records: &[T]) -> HashSet<String> {
+    records.iter().map(|r| r.id().to_string()).collect()
+}
+
+/// Point a reference at the new ID of a record restored under a new ID
+fn remap_id(id: &mut String, renamed: &HashMap<String, String>) {
+    if let Some(new_id) = renamed.get(id) {
+        *id = new_id.clone();
+    }
+}
+
+fn remap_optional_id(id: &mut Option<String>, renamed: &HashMap<String, String>) {
+    if let Some(id) = id {
+        remap_id(id, renamed);
+    }
+}
+
+/// Blank what is encrypted at rest, for records read while the database is locked
+fn omit_secrets(profiles: &mut [SSHProfile], keys: &mut [SSHKey]) {
+    for profile in profiles {
+        match &mut profile.auth_data {
+            AuthData::Password { password } => password.clear(),
+            AuthData::Certificate {
+                certificate,
+                private_key,
+                ..
+            } => {
+                certificate.clear();
+                private_key.clear();
+            }
+            AuthData::KeyReference { .. } => {}
+        }
+    }
+    for key in keys {
+        key.private_key.clear();
+        key.passphrase = None;
+    }
+}
+
+/// Encrypt the secrets of restored keys the way the local database stores them
+/// A backup taken while locked holds none, so keys it shares with the local database keep
+/// their local secrets
+fn seal_keys(
+    keys: &mut [SSHKey],
+    local: &[SSHKey],
+    secrets_omitted: bool,
+    encryption: &dyn EncryptionService,
+) -> DatabaseResult<()> {
+    for key in keys {
+        match local.iter().find(|l| l.base.id == key.base.id) {
+            Some(local) if secrets_omitted => {
+                key.private_key = local.private_key.clone();
+                key.passphrase = local.passphrase.clone();
+            }
+            _ => key.encrypt_fields(encryption)?,
+        }
+    }
+    Ok(())
+}
+
+/// Encrypt the secrets of restored profiles, keeping local secrets like `seal_keys`
+fn seal_profiles(
+    profiles: &mut [SSHProfile],
+    local: &[SSHProfile],
+    secrets_omitted: bool,
+    encryption: &dyn EncryptionService,
+) -> DatabaseResult<()> {
+    for profile in profiles {
+        if !profile.has_encrypted_data() {
+            continue;
+        }
+        match local.iter().find(|l| l.base.id == profile.base.id) {
+            Some(local) if secrets_omitted && local.has_encrypted_data() => {
+                profile.auth_data = local.auth_data.clone();
+            }
+            _ => profile.encrypt_fields(encryption)?,
+        }
+    }
+    Ok(())
+}
+
+/// Known hosts after restoring `incoming`, with the number of lines added
+fn merge_known_hosts(existing: &str, incoming: &str) -> (String, usize) {
+    let mut seen: HashSet<&str> = known_host_lines(existing).collect();
+    let mut merged = existing.to_string();
+    let mut added = 0;
+
+    for line in known_host_lines(incoming) {
+        if seen.insert(line) {
+            if !merged.is_empty() && !merged.ends_with('\n') {
+                merged.push('\n');
+            }
+            merged.push_str(line);
+            merged.push('\n');
+            added += 1;
+        }
+    }
+
+    (merged, added)
+}
+
+fn known_hosts_path() -> DatabaseResult<PathBuf> {
+    dirs::home_dir()
+        .map(|home| home.join(".ssh").join("known_hosts"))
+        .ok_or_else(|| DatabaseError::ConfigError("Could not determine home directory".to_string()))
+}
+
+async fn read_known_hosts() -> DatabaseResult<Option<String>> {
+    match tokio::fs::read_to_string(known_hosts_path()?).await {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(DatabaseError::ConfigError(format!(
+            "Failed to read known_hosts: {}",
+            e
+        ))),
+    }
+}
+
+async fn write_known_hosts(content: &str) -> DatabaseResult<()> {
+    let path = known_hosts_path()?;
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await.map_err(|e| {
+            DatabaseError::ConfigError(format!("Failed to create {}: {}", parent.display(), e))
+        })?;
+    }
+    // Replaced through a temporary file, so a crash never leaves it half written
+    write_if_changed(&path, content, 0o600).await?;
+    Ok(())
+}
+
+/// Run backup work off the async runtime; the Argon2id and PBKDF2 key derivations take seconds
+async fn run_blocking<T, F>(task: F) -> DatabaseResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> DatabaseResult<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(task)
+        .await
+        .map_err(|e| DatabaseError::Internal(anyhow::anyhow!("Backup task failed: {}", e)))?
+}
+
+/// Backup service for exporting and selectively restoring local data
+pub struct BackupService {
+    database_service: Arc<Mutex<DatabaseService>>,
+}
+
+impl BackupService {
+    /// Create new BackupService instance
+    pub fn new(database_service: Arc<Mutex<DatabaseService>>) -> Self {
+        Self { database_service }
+    }
+
+    /// Gather everything a backup holds, with secrets decrypted so the backup restores on any
+    /// device and under any master password
+    /// While the database is locked secrets cannot be read: they are left blank when
+    /// `allow_locked` is set, otherwise collecting fails
+    async fn collect(&self, allow_locked: bool) -> DatabaseResult<BackupData> {
+        let db_service = self.database_service.lock().await;
+        let manager = db_service.get_master_password_manager_arc();
+        let manager = manager.read().await;
+        let unlocked = manager.get_status().await.is_unlocked;
+        if !unlocked && !allow_locked {
+            return Err(DatabaseError::MasterPasswordRequired);
+        }
+
+        let local_db = db_service.get_local_database();
+        let local_db = local_db.read().await;
+
+        let mut profiles = local_db.find_all_ssh_profiles().await?;
+        let mut keys = local_db.find_all_ssh_keys().await?;
+        if unlocked {
+            for profile in &mut profiles {
+                if profile.has_encrypted_data() {
+                    profile.decrypt_fields(&*manager)?;
+                }
+            }
+            for key in &mut keys {
+                key.decrypt_fields(&*manager)?;
+            }
+        } else {
+            omit_secrets(&mut profiles, &mut keys);
+        }
+
+        Ok(BackupData {
+            schema_version: format::SCHEMA_VERSION,
+            profiles,
+            keys,
+            groups: local_db.find_all_ssh_groups().await?,
+            tunnels: local_db.find_all_ssh_tunnels().await?,
+            saved_commands: local_db.find_all_saved_commands().await?,
+            saved_command_groups: local_db.find_all_saved_command_groups().await?,
+            terminal_profiles: local_db.find_all_terminal_profiles().await?,
+            sync_settings: local_db.get_global_sync_settings().await?,
+            known_hosts: read_known_hosts().await?,
+            secrets_omitted: !unlocked,
+        })
+    }
+
+    async fn encode(data: BackupData, options: ExportBackupOptions) -> DatabaseResult<String> {
+        run_blocking(move || {
+            let encryption = options
+                .password
+                .as_deref()
+                .map(|password| (password, KdfParams::new(options.kdf)));
+            format::encode_backup(&data, encryption)
+        })
+        .await
+    }
+
+    /// Export a backup, encrypted when the options carry a password
+    /// The database must be unlocked, so the backup holds readable secrets
+    pub async fn export(&self, options: ExportBackupOptions) -> DatabaseResult<String> {
+        Self::encode(self.collect(false).await?, options).await
+    }
+
+    /// Export whatever can be read, with secrets left blank while the database is locked
+    /// A master password reset follows a forgotten password, so its snapshot cannot wait for
+    /// an unlock
+    pub async fn export_readable(&self, options: ExportBackupOptions) -> DatabaseResult<String> {
+        Self::encode(self.collect(true).await?, options).await
+    }
+
+    /// Describe a backup before restoring it
+    pub async fn inspect(
+        &self,
+        content: &str,
+        password: Option<&str>,
+    ) -> DatabaseResult<BackupSummary> {
+        let content = content.to_string();
+        let password = password.map(|p| Zeroizing::new(p.to_string()));
+
+        run_blocking(move || {
+            format::read_summary(&content, password.as_deref().map(String::as_str))
+        })
+        .await
+    }
+
+    /// Restore the selected entities of a backup
+    /// Entities the backup holds no data for are left untouched, even in replace mode
+    /// The whole backup is decrypted and planned before anything is written, and all database
+    /// changes are applied in one transaction
+    pub async fn restore(
+        &self,
+        content: &str,
+        password: Option<&str>,
+        options: RestoreOptions,
+    ) -> DatabaseResult<RestoreReport> {
+        let owned_content = content.to_string();
+        let password = password.map(|p| Zeroizing::new(p.to_string()));
+        let (mut data, source_schema_version) = run_blocking(move || {
+            format::decode_backup(&owned_content, password.as_deref().map(String::as_str))
+        })
+        .await?;
+        let present = data.entities();
+        let selected = |entity| options.includes(entity) && present.contains(&entity);
+
+        let db_service = self.database_service.lock().await;
+        let manager = db_service.get_master_password_manager_arc();
+        let manager = manager.read().await;
+        let local_db = db_service.get_local_database();
+        let local_db = local_db.write().await;
+
+        let mut reports = Vec::new();
+        let mut changes = Vec::new();
+        let mut group_ids = HashMap::new();
+        let mut key_ids = HashMap::new();
+        let mut profile_ids = HashMap::new();
+
+        if selected(BackupEntity::SshGroups) {
+            let existing = record_ids(&local_db.find_all_ssh_groups().await?);
+            let mut plan = plan_restore(
+                BackupEntity::SshGroups,
+                std::mem::take(&mut data.groups),
+                &existing,
+                &options,
+            );
+            group_ids = std::mem::take(&mut plan.renamed);
+            reports.push(plan.into_changes("ssh_groups", &mut changes)?);
+        }
+
+        if selected(BackupEntity::SshKeys) {
+            let local = local_db.find_all_ssh_keys().await?;
+            seal_keys(&mut data.keys, &local, data.secrets_omitted, &*manager)?;
+            let existing = record_ids(&local);
+            let mut plan = plan_restore(
+                BackupEntity::SshKeys,
+                std::mem::take(&mut data.keys),
+                &existing,
+                &options,
+            );
+            key_ids = std::mem::take(&mut plan.renamed);
+            reports.push(plan.into_changes("ssh_keys", &mut changes)?);
+        }
+
+        if selected(BackupEntity::SshProfiles) {
+            for profile in &mut data.profiles {
+                remap_optional_id(&mut profile.group_id, &group_ids);
+                if let AuthData::KeyReference { key_id } = &mut profile.auth_data {
+                    remap_id(key_id, &key_ids);
+                }
+            }
+
+            let local = local_db.find_all_ssh_profiles().await?;
+            seal_profiles(&mut data.profiles, &local, data.secrets_omitted, &*manager)?;
+            let existing = record_ids(&local);
+            let mut plan = plan_restore(
+                BackupEntity::SshProfiles,
+                std::mem::take(&mut data.profiles),
+                &existing,
+                &options,
+            );
+            profile_ids = std::mem::take(&mut plan.renamed);
+            reports.push(plan.into_changes("ssh_profiles", &mut changes)?);
+        }
+
+        if selected(BackupEntity::SshTunnels) {
+            for tunnel in &mut data.tunnels {
+                remap_id(&mut tunnel.profile_id, &profile_ids);
+            }
+
+            let existing = record_ids(&local_db.find_all_ssh_tunnels().await?);
+            let plan = plan_restore(
+                BackupEntity::SshTunnels,
+                std::mem::take(&mut data.tunnels),
+                &existing,
+                &options,
+            );
+            reports.push(plan.into_changes("ssh_tunnels", &mut changes)?);
+        }
+
+        if selected(BackupEntity::SavedCommands) {
+            let existing = record_ids(&local_db.find_all_saved_command_groups().await?);
+            let group_plan = plan_restore(
+                BackupEntity::SavedCommands,
+                std::mem::take(&mut data.saved_command_groups),
+                &existing,
+                &options,
+            );
+            for command in &mut data.saved_commands {
+                remap_optional_id(&mut command.group_id, &group_plan.renamed);
+            }
+
+            let existing = record_ids(&local_db.find_all_saved_commands().await?);
+            let command_plan = plan_restore(
+                BackupEntity::SavedCommands,
+                std::mem::take(&mut data.saved_commands),
+                &existing,
+                &options,
+            );
+
+            // Groups come first, so commands are deleted before and written after their groups
+            let mut report = group_plan.into_changes("saved_command_groups", &mut changes)?;
+            let command_report = command_plan.into_changes("saved_commands", &mut changes)?;
+            report.created += command_report.created;
+            report.updated += command_report.updated;
+            report.skipped += command_report.skipped;
+            report.renamed += command_report.renamed;
+            report.deleted += command_report.deleted;
+            reports.push(report);
+        }
+
+        if selected(BackupEntity::TerminalProfiles) {
+            let existing = record_ids(&local_db.find_all_terminal_profiles().await?);
+            let plan = plan_restore(
+                BackupEntity::TerminalProfiles,
+                std::mem::take(&mut data.terminal_profiles),
+                &existing,
+                &options,
+            );
+            reports.push(plan.into_changes("terminal_profiles", &mut changes)?);
+        }
+
+        let mut sync_settings = None;
+        if let (true, Some(settings)) = (
+            selected(BackupEntity::SyncSettings),
+            data.sync_settings.as_ref(),
+        ) {
+            let mut report = EntityRestoreReport::new(BackupEntity::SyncSettings);
+            let exists = local_db.get_global_sync_settings().await?.is_some();
+            // There is a single settings record, so keeping both is the same as skipping
+            if exists
+                && options.mode == RestoreMode::Merge
+                && options.conflict_policy != ConflictPolicy::Overwrite
+            {
+                report.skipped = 1;
+            } else {
+                sync_settings = Some(settings);
+                if exists {
+                    report.updated = 1;
+                } else {
+                    report.created = 1;
+                }
+            }
+            reports.push(report);
+        }
+
+        let mut known_hosts = None;
+        if let (true, Some(incoming)) = (
+            selected(BackupEntity::KnownHosts),
+            data.known_hosts.as_deref(),
+        ) {
+            let mut report = EntityRestoreReport::new(BackupEntity::KnownHosts);
+            let existing = read_known_hosts().await?.unwrap_or_default();
+
+            match options.mode {
+                RestoreMode::Merge => {
+                    let (merged, added) = merge_known_hosts(&existing, incoming);
+                    report.created = added;
+                    report.skipped = known_host_lines(incoming).count() - added;
+                    if added > 0 {
+                        known_hosts = Some(merged);
+                    }
+                }
+                RestoreMode::Replace => {
+                    let incoming_lines: HashSet<&str> = known_host_lines(incoming).collect();
+                    let existing_lines: HashSet<&str> = known_host_lines(&existing).collect();
+                    report.created = incoming_lines.difference(&existing_lines).count();
+                    report.updated = incoming_lines.intersection(&existing_lines).count();
+                    report.deleted = existing_lines.difference(&incoming_lines).count();
+                    known_hosts = Some(incoming.to_string());
+                }
+            }
+            reports.push(report);
+        }
+
+        local_db.apply_restore(&changes, sync_settings).await?;
+        // The file cannot join the transaction, so it is written once the database changes are in
+        if let Some(content) = known_hosts {
+            write_known_hosts(&content).await?;
+        }
+
+        Ok(RestoreReport {
+            source_schema_version,
+            entities: reports,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::sync::fixture;
+
+    fn group(id: &str, name: &str) -> SSHGroup {
+        let mut group = SSHGroup::new("device".to_string(), name.to_string());
+        group.base.id = id.to_string();
+        group
+    }
+
+    fn existing(ids: &[&str]) -> HashSet<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    fn options(mode: RestoreMode, conflict_policy: ConflictPolicy) -> RestoreOptions {
+        RestoreOptions {
+            entities: None,
+            mode,
+            conflict_policy,
+        }
+    }
+
+    #[test]
+    fn test_merge_conflict_policies() {
+        let records = || vec![group("a", "prod"), group("b", "staging")];
+        let local = existing(&["a", "c"]);
+
+        let plan = plan_restore(
+            BackupEntity::SshGroups,
+            records(),
+            &local,
+            &options(RestoreMode::Merge, ConflictPolicy::Overwrite),
+        );
+        assert_eq!((plan.report.created, plan.report.updated), (1, 1));
+        assert!(plan.deletes.is_empty());
+
+        let plan = plan_restore(
+            BackupEntity::SshGroups,
+            records(),
+            &local,
+            &options(RestoreMode::Merge, ConflictPolicy::Skip),
+        );
+        assert_eq!((plan.report.created, plan.report.skipped), (1, 1));
+        assert_eq!(plan.writes.len(), 1);
+
+        let plan = plan_restore(
+            BackupEntity::SshGroups,
+            records(),
+            &local,
+            &options(RestoreMode::Merge, ConflictPolicy::KeepBoth),
+        );
+        assert_eq!(plan.report.renamed, 1);
+        let new_id = &plan.renamed["a"];
+        let copy = plan.writes.iter().find(|g| &g.base.id == new_id).unwrap();
+        assert_eq!(copy.name, "prod (restored)");
+    }
+
+    #[test]
+    fn test_replace_deletes_local_extras() {
+        let plan = plan_restore(
+            BackupEntity::SshGroups,
+            vec![group("a", "prod")],
+            &existing(&["a", "c"]),
+            &options(RestoreMode::Replace, ConflictPolicy::Skip),
+        );
+        assert_eq!(plan.deletes, vec!["c".to_string()]);
+        assert_eq!((plan.report.updated, plan.report.deleted), (1, 1));
+        assert_eq!(plan.writes.len(), 1);
+    }
+
+    #[test]
+    fn test_merge_known_hosts() {
+        let existing = "# local\nhost-a ssh-ed25519 AAAA";
+        let incoming = "host-a ssh-ed25519 AAAA\nhost-b ssh-rsa BBBB\n";
+
+        let (merged, added) = merge_known_hosts(existing, incoming);
+        assert_eq!(added, 1);
+        assert_eq!(
+            merged,
+            "# local\nhost-a ssh-ed25519 AAAA\nhost-b ssh-rsa BBBB\n"
+        );
+        assert_eq!(merge_known_hosts(&merged, incoming).1, 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_restore_changes_nothing() {
+        let dir = fixture::temp_dir();
+        let database_service = fixture::database_service(&dir, "device").await;
+        let local_db = database_service.get_local_database();
+        let local_db = local_db.read().await;
+        local_db.save_ssh_group(&group("c", "local")).await.unwrap();
+
+        let changes = vec![
+            TableChanges {
+                table: "ssh_groups",
+                writes: vec![serde_json::to_value(group("a", "prod")).unwrap()],
+                deletes: vec!["c".to_string()],
+            },
+            TableChanges {
+                table: "ssh_profiles",
+                writes: vec![serde_json::json!({ "id": "broken" })],
+                deletes: Vec::new(),
+            },
+        ];
+        assert!(local_db.apply_restore(&changes, None).await.is_err());
+
+        let groups = record_ids(&local_db.find_all_ssh_groups().await.unwrap());
+        assert_eq!(groups, existing(&["c"]));
+    }
+}
@@ -0,0 +1,345 @@
+use chrono::{DateTime, Datelike, NaiveDateTime, SubsecRound, TimeZone, Utc};
+use std::collections::HashSet;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::database::error::{DatabaseError, DatabaseResult};
+use crate::models::backup::{BackupSnapshot, SnapshotReason};
+
+const SNAPSHOT_PREFIX: &str = "kerminal-";
+const SNAPSHOT_EXTENSION: &str = ".kbak";
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+const UNIQUE_SUFFIX_LEN: usize = 8;
+
+/// Snapshots before a master password change or reset are the way back to the data as it was
+/// under the old password, so the newest few are kept apart from the day and week buckets
+const KEEP_PASSWORD_SNAPSHOTS: usize = 3;
+
+/// Snapshot file name, e.g. `kerminal-20240501-030000-scheduled-1f2e3d4c.kbak` (UTC time)
+/// The suffix keeps snapshots taken within the same second apart
+pub fn snapshot_file_name(
+    reason: SnapshotReason,
+    created_at: DateTime<Utc>,
+    suffix: &str,
+) -> String {
+    format!(
+        "{}{}-{}-{}{}",
+        SNAPSHOT_PREFIX,
+        created_at.format(TIMESTAMP_FORMAT),
+        reason.as_str(),
+        suffix,
+        SNAPSHOT_EXTENSION
+    )
+}
+
+fn is_unique_suffix(suffix: &str) -> bool {
+    suffix.len() == UNIQUE_SUFFIX_LEN && suffix.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Read the time and reason back out of a snapshot file name
+/// Names without a suffix were written by earlier versions and are still accepted
+pub fn parse_snapshot_file_name(name: &str) -> Option<(DateTime<Utc>, SnapshotReason)> {
+    let stem = name
+        .strip_prefix(SNAPSHOT_PREFIX)?
+        .strip_suffix(SNAPSHOT_EXTENSION)?;
+    // Timestamp is fixed width: YYYYmmdd-HHMMSS
+    let (timestamp, rest) = (stem.get(..15)?, stem.get(15..)?.strip_prefix('-')?);
+
+    let created_at = NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT)
+        .ok()?
+        .and_utc();
+    let reason = SnapshotReason::ALL.into_iter().find(|r| {
+        rest.strip_prefix(r.as_str()).is_some_and(|suffix| {
+            suffix.is_empty() || suffix.strip_prefix('-').is_some_and(is_unique_suffix)
+        })
+    })?;
+
+    Some((created_at, reason))
+}
+
+/// Snapshots to delete so that only the newest snapshot of each of the last `keep_daily` days
+/// and `keep_weekly` ISO weeks remains, with days and weeks taken in `tz`
+///
+/// Password change and reset snapshots neither fill nor take a day or week; the newest
+/// `KEEP_PASSWORD_SNAPSHOTS` of them are kept. Nothing expires when both limits are 0.
+pub fn expired_snapshots<'a, Tz: TimeZone>(
+    snapshots: &'a [BackupSnapshot],
+    keep_daily: u32,
+    keep_weekly: u32,
+    tz: &Tz,
+) -> Vec<&'a BackupSnapshot> {
+    if keep_daily == 0 && keep_weekly == 0 {
+        return Vec::new();
+    }
+
+    let mut newest_first: Vec<&BackupSnapshot> = snapshots.iter().collect();
+    newest_first.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+
+    let mut days = HashSet::new();
+    let mut weeks = HashSet::new();
+    let mut password_snapshots = 0;
+    let mut expired = Vec::new();
+
+    for snapshot in newest_first {
+        if matches!(
+            snapshot.reason,
+            SnapshotReason::PasswordChange | SnapshotReason::PasswordReset
+        ) {
+            password_snapshots += 1;
+            if password_snapshots > KEEP_PASSWORD_SNAPSHOTS {
+                expired.push(snapshot);
+            }
+            continue;
+        }
+
+        let local = snapshot.created_at.with_timezone(tz);
+        let week = local.iso_week();
+
+        let keep_day = days.len() < keep_daily as usize && days.insert(local.date_naive());
+        let keep_week =
+            weeks.len() < keep_weekly as usize && weeks.insert((week.year(), week.week()));
+
+        if !keep_day && !keep_week {
+            expired.push(snapshot);
+        }
+    }
+
+    expired
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> DatabaseError {
+    DatabaseError::QueryFailed(format!("{}: {}", path.display(), e))
+}
+
+/// Folder of snapshot files
+pub struct SnapshotStore {
+    dir: PathBuf,
+}
+
+impl SnapshotStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Path of a snapshot, refusing anything that is not a snapshot file name
+    fn resolve(&self, id: &str) -> DatabaseResult<PathBuf> {
+        if id.contains(['/', '\\']) || parse_snapshot_file_name(id).is_none() {
+            return Err(DatabaseError::ValidationError(format!(
+                "Invalid snapshot: {}",
+                id
+            )));
+        }
+        Ok(self.dir.join(id))
+    }
+
+    /// All snapshots, newest first
+    pub async fn list(&self) -> DatabaseResult<Vec<BackupSnapshot>> {
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(io_error(&self.dir, e)),
+        };
+
+        let mut snapshots = Vec::new();
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| io_error(&self.dir, e))?
+        {
+            let id = entry.file_name().to_string_lossy().to_string();
+            let Some((created_at, reason)) = parse_snapshot_file_name(&id) else {
+                continue;
+            };
+            let Ok(metadata) = entry.metadata().await else {
+                continue;
+            };
+            if metadata.is_file() {
+                snapshots.push(BackupSnapshot {
+                    id,
+                    reason,
+                    created_at,
+                    size_bytes: metadata.len(),
+                });
+            }
+        }
+
+        snapshots.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+        Ok(snapshots)
+    }
+
+    pub async fn read(&self, id: &str) -> DatabaseResult<String> {
+        let path = self.resolve(id)?;
+        match tokio::fs::read_to_string(&path).await {
+            Ok(content) => Ok(content),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                Err(DatabaseError::NotFound(format!("Snapshot {}", id)))
+            }
+            Err(e) => Err(io_error(&path, e)),
+        }
+    }
+
+    /// Written to a hidden temporary file first, so a crash never leaves a truncated snapshot
+    pub async fn write(
+        &self,
+        reason: SnapshotReason,
+        created_at: DateTime<Utc>,
+        content: &str,
+    ) -> DatabaseResult<BackupSnapshot> {
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|e| io_error(&self.dir, e))?;
+
+        // File names only hold whole seconds
+        let created_at = created_at.trunc_subsecs(0);
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        let id = snapshot_file_name(reason, created_at, &suffix[..UNIQUE_SUFFIX_LEN]);
+        let path = self.dir.join(&id);
+        let temp = self.dir.join(format!(".{}.tmp", id));
+
+        tokio::fs::write(&temp, content)
+            .await
+            .map_err(|e| io_error(&temp, e))?;
+        tokio::fs::rename(&temp, &path)
+            .await
+            .map_err(|e| io_error(&path, e))?;
+
+        Ok(BackupSnapshot {
+            id,
+            reason,
+            created_at,
+            size_bytes: content.len() as u64,
+        })
+    }
+
+    pub async fn delete(&self, id: &str) -> DatabaseResult<()> {
+        let path = self.resolve(id)?;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(io_error(&path, e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn snapshot(created_at: DateTime<Utc>) -> BackupSnapshot {
+        snapshot_for(SnapshotReason::Scheduled, created_at)
+    }
+
+    fn snapshot_for(reason: SnapshotReason, created_at: DateTime<Utc>) -> BackupSnapshot {
+        BackupSnapshot {
+            id: snapshot_file_name(reason, created_at, "00000000"),
+            reason,
+            created_at,
+            size_bytes: 0,
+        }
+    }
+
+    #[test]
+    fn test_snapshot_file_name_round_trip() {
+        let created_at = Utc.with_ymd_and_hms(2024, 5, 1, 3, 0, 9).unwrap();
+        let name = snapshot_file_name(SnapshotReason::PasswordChange, created_at, "0a1b2c3d");
+
+        assert_eq!(
+            name,
+            "kerminal-20240501-030009-password-change-0a1b2c3d.kbak"
+        );
+        assert_eq!(
+            parse_snapshot_file_name(&name),
+            Some((created_at, SnapshotReason::PasswordChange))
+        );
+        assert_eq!(
+            parse_snapshot_file_name("kerminal-20240501-030009-password-change.kbak"),
+            Some((created_at, SnapshotReason::PasswordChange))
+        );
+        assert_eq!(
+            parse_snapshot_file_name("kerminal-20240501-030009-password-change-../x.kbak"),
+            None
+        );
+        assert_eq!(
+            parse_snapshot_file_name("kerminal-20240501-030009-other.kbak"),
+            None
+        );
+        assert_eq!(parse_snapshot_file_name("../kerminal.db"), None);
+    }
+
+    #[test]
+    fn test_expired_snapshots_keeps_newest_per_day_and_week() {
+        // Wednesday 2024-05-15, one snapshot every 12 hours going back 30 days
+        let start = Utc.with_ymd_and_hms(2024, 5, 15, 20, 0, 0).unwrap();
+        let snapshots: Vec<_> = (0..60)
+            .map(|i| snapshot(start - Duration::hours(12 * i)))
+            .collect();
+
+        let expired = expired_snapshots(&snapshots, 3, 2, &Utc);
+        let kept: Vec<_> = snapshots
+            .iter()
+            .filter(|s| !expired.contains(s))
+            .map(|s| s.created_at)
+            .collect();
+
+        // 15th, 14th and 13th at 20:00, then the newest of the previous week (Sunday the 12th)
+        assert_eq!(
+            kept,
+            vec![
+                start,
+                start - Duration::days(1),
+                start - Duration::days(2),
+                start - Duration::days(3),
+            ]
+        );
+
+        assert!(expired_snapshots(&snapshots, 0, 0, &Utc).is_empty());
+    }
+
+    #[test]
+    fn test_expired_snapshots_keeps_password_snapshots_apart() {
+        let start = Utc.with_ymd_and_hms(2024, 5, 15, 20, 0, 0).unwrap();
+        let scheduled = snapshot(start - Duration::hours(2));
+        let password: Vec<_> = (0..5)
+            .map(|i| snapshot_for(SnapshotReason::PasswordChange, start - Duration::days(i)))
+            .collect();
+        let mut snapshots = password.clone();
+        snapshots.push(scheduled.clone());
+
+        let expired = expired_snapshots(&snapshots, 1, 0, &Utc);
+
+        // The newer password snapshot does not take the day from the scheduled one
+        assert!(!expired.contains(&&scheduled));
+        assert_eq!(expired, vec![&password[3], &password[4]]);
+    }
+
+    #[tokio::test]
+    async fn test_store_lists_and_rejects_foreign_files() {
+        let dir = std::env::temp_dir().join(format!("kerminal-snapshots-{}", uuid::Uuid::new_v4()));
+        let store = SnapshotStore::new(&dir);
+        let created_at = Utc.with_ymd_and_hms(2024, 5, 1, 3, 0, 0).unwrap();
+
+        let written = store
+            .write(SnapshotReason::Manual, created_at, "{}")
+            .await
+            .unwrap();
+        // Taken within the same second, so only the suffix tells them apart
+        let second = store
+            .write(SnapshotReason::Manual, created_at, "{}")
+            .await
+            .unwrap();
+        assert_ne!(written.id, second.id);
+        store.delete(&second.id).await.unwrap();
+        std::fs::write(dir.join("notes.txt"), "not a snapshot").unwrap();
+
+        assert_eq!(store.list().await.unwrap(), vec![written.clone()]);
+        assert_eq!(store.read(&written.id).await.unwrap(), "{}");
+        assert!(store.read("notes.txt").await.is_err());
+        assert!(store.delete("../notes.txt").await.is_err());
+
+        store.delete(&written.id).await.unwrap();
+        assert!(store.list().await.unwrap().is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,4 +1,5 @@
 pub mod auth;
+pub mod backup;
 pub mod buffer_manager;
 pub mod history;
 pub mod recording;
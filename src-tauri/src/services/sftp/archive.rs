@@ -0,0 +1,867 @@
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use russh::client::Handle;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+use crate::models::sftp::archive::{ArchiveFormat, ArchiveSummary};
+use crate::models::sftp::error::SFTPError;
+use crate::services::sftp::remote_exec::{run_command, shell_quote, RemoteCommand, RemoteOutput};
+use crate::services::sftp::service::{SFTPClientHandler, SFTPService};
+
+/// Chunk size for streamed archive data
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Entry sent to the local tar writer by the SFTP fallback
+enum ArchiveEntry {
+    Directory {
+        path: String,
+        mode: u32,
+        mtime: u64,
+    },
+    File {
+        path: String,
+        mode: u32,
+        mtime: u64,
+        size: u64,
+        data: mpsc::Receiver<std::io::Result<Vec<u8>>>,
+    },
+    Symlink {
+        path: String,
+        target: String,
+        mtime: u64,
+    },
+}
+
+/// Blocking reader over file chunks received from the SFTP side
+///
+/// Yields exactly `size` bytes as promised in the tar header: extra data (file grew while
+/// reading) is dropped and missing data (file shrank) is zero padded.
+struct ChunkReader {
+    data: mpsc::Receiver<std::io::Result<Vec<u8>>>,
+    buffer: Vec<u8>,
+    position: usize,
+    remaining: u64,
+    closed: bool,
+}
+
+impl ChunkReader {
+    fn new(data: mpsc::Receiver<std::io::Result<Vec<u8>>>, size: u64) -> Self {
+        Self {
+            data,
+            buffer: Vec::new(),
+            position: 0,
+            remaining: size,
+            closed: false,
+        }
+    }
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        if self.remaining == 0 || out.is_empty() {
+            return Ok(0);
+        }
+
+        while self.position == self.buffer.len() && !self.closed {
+            match self.data.blocking_recv() {
+                Some(chunk) => {
+                    self.buffer = chunk?;
+                    self.position = 0;
+                }
+                None => self.closed = true,
+            }
+        }
+
+        let limit = out.len().min(self.remaining as usize);
+        let count = if self.closed {
+            out[..limit].fill(0);
+            limit
+        } else {
+            let count = limit.min(self.buffer.len() - self.position);
+            out[..count].copy_from_slice(&self.buffer[self.position..self.position + count]);
+            self.position += count;
+            count
+        };
+
+        self.remaining -= count as u64;
+        Ok(count)
+    }
+}
+
+/// Blocking writer that forwards archive bytes to the async side in chunks
+struct ChannelWriter {
+    sender: mpsc::Sender<Vec<u8>>,
+    buffer: Vec<u8>,
+}
+
+impl ChannelWriter {
+    fn new(sender: mpsc::Sender<Vec<u8>>) -> Self {
+        Self {
+            sender,
+            buffer: Vec::with_capacity(CHUNK_SIZE),
+        }
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(data);
+        if self.buffer.len() >= CHUNK_SIZE {
+            self.flush()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::replace(&mut self.buffer, Vec::with_capacity(CHUNK_SIZE));
+        self.sender.blocking_send(chunk).map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::BrokenPipe, "archive upload stopped")
+        })
+    }
+}
+
+/// Split a remote path into its parent directory and final component
+pub fn split_remote_path(path: &str) -> (String, String) {
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() {
+        return ("/".to_string(), ".".to_string());
+    }
+    match trimmed.rsplit_once('/') {
+        Some(("", name)) => ("/".to_string(), name.to_string()),
+        Some((parent, name)) => (parent.to_string(), name.to_string()),
+        None => (".".to_string(), trimmed.to_string()),
+    }
+}
+
+/// Command that writes `parent/name` as a tar stream to stdout
+pub fn stream_create_command(
+    format: ArchiveFormat,
+    parent: &str,
+    name: &str,
+) -> Result<String, SFTPError> {
+    let flags = match format {
+        ArchiveFormat::Tar => "-cf -",
+        ArchiveFormat::TarGz => "-czf -",
+        ArchiveFormat::TarZst => "--zstd -cf -",
+        ArchiveFormat::Zip => {
+            return Err(SFTPError::Other {
+                message: "Zip archives cannot be streamed; use tar, tar.gz or tar.zst".to_string(),
+            })
+        }
+    };
+    Ok(format!(
+        "tar {} -C {} -- {}",
+        flags,
+        shell_quote(parent),
+        shell_quote(name)
+    ))
+}
+
+/// Command that extracts `archive` into `destination`, creating it if needed
+pub fn extract_command(format: ArchiveFormat, archive: &str, destination: &str) -> String {
+    let archive = shell_quote(archive);
+    let destination = shell_quote(destination);
+    let extract = match format {
+        ArchiveFormat::Tar => format!("tar -xf {} -C {}", archive, destination),
+        ArchiveFormat::TarGz => format!("tar -xzf {} -C {}", archive, destination),
+        ArchiveFormat::TarZst => format!("tar --zstd -xf {} -C {}", archive, destination),
+        ArchiveFormat::Zip => format!("unzip -o -q {} -d {}", archive, destination),
+    };
+    format!("mkdir -p -- {} && {}", destination, extract)
+}
+
+/// Command that creates `archive` from the given remote paths
+/// Entries are stored relative to each source's parent directory.
+pub fn create_command(format: ArchiveFormat, archive: &str, sources: &[String]) -> String {
+    let archive = shell_quote(archive);
+    let members = sources
+        .iter()
+        .map(|source| {
+            let (parent, name) = split_remote_path(source);
+            // Neither tar -C members nor zip accept `--`; ./ keeps names like -x from being options
+            let name = if name.starts_with('-') {
+                format!("./{}", shell_quote(&name))
+            } else {
+                shell_quote(&name)
+            };
+            (shell_quote(&parent), name)
+        })
+        .collect::<Vec<_>>();
+
+    let tar_members = members
+        .iter()
+        .map(|(parent, name)| format!("-C {} {}", parent, name))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    match format {
+        ArchiveFormat::Tar => format!("tar -cf {} {}", archive, tar_members),
+        ArchiveFormat::TarGz => format!("tar -czf {} {}", archive, tar_members),
+        // A pipe into zstd would report zstd's status and hide tar failing
+        ArchiveFormat::TarZst => format!("tar --zstd -cf {} {}", archive, tar_members),
+        ArchiveFormat::Zip => members
+            .iter()
+            .map(|(parent, name)| format!("(cd {} && zip -q -r -y {} {})", parent, archive, name))
+            .collect::<Vec<_>>()
+            .join(" && "),
+    }
+}
+
+/// Write a tar archive from entries received over a channel (runs on a blocking thread)
+fn write_tar(
+    local_path: String,
+    gzip: bool,
+    entries: mpsc::Receiver<ArchiveEntry>,
+) -> Result<(), SFTPError> {
+    let file = std::fs::File::create(&local_path).map_err(|e| SFTPError::IoError {
+        message: format!("Failed to create archive {}: {}", local_path, e),
+    })?;
+
+    let result = if gzip {
+        let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        append_entries(&mut builder, entries)
+            .and_then(|_| builder.into_inner())
+            .and_then(|encoder| encoder.finish())
+            .and_then(|file| file.sync_all())
+    } else {
+        let mut builder = tar::Builder::new(file);
+        append_entries(&mut builder, entries)
+            .and_then(|_| builder.into_inner())
+            .and_then(|file| file.sync_all())
+    };
+
+    result.map_err(|e| SFTPError::IoError {
+        message: format!("Failed to write archive {}: {}", local_path, e),
+    })
+}
+
+fn append_entries<W: Write>(
+    builder: &mut tar::Builder<W>,
+    mut entries: mpsc::Receiver<ArchiveEntry>,
+) -> std::io::Result<()> {
+    while let Some(entry) = entries.blocking_recv() {
+        let mut header = tar::Header::new_gnu();
+        match entry {
+            ArchiveEntry::Directory { path, mode, mtime } => {
+                header.set_entry_type(tar::EntryType::Directory);
+                header.set_mode(mode);
+                header.set_mtime(mtime);
+                header.set_size(0);
+                builder.append_data(&mut header, &path, std::io::empty())?;
+            }
+            ArchiveEntry::File {
+                path,
+                mode,
+                mtime,
+                size,
+                data,
+            } => {
+                header.set_entry_type(tar::EntryType::Regular);
+                header.set_mode(mode);
+                header.set_mtime(mtime);
+                header.set_size(size);
+                builder.append_data(&mut header, &path, ChunkReader::new(data, size))?;
+            }
+            ArchiveEntry::Symlink {
+                path,
+                target,
+                mtime,
+            } => {
+                header.set_entry_type(tar::EntryType::Symlink);
+                header.set_mode(0o777);
+                header.set_mtime(mtime);
+                header.set_size(0);
+                builder.append_link(&mut header, &path, &target)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Archive Service for moving whole directory trees as a single tar stream
+pub struct ArchiveService {
+    sftp_service: Arc<SFTPService>,
+}
+
+impl ArchiveService {
+    /// Create new archive service
+    pub fn new(sftp_service: Arc<SFTPService>) -> Self {
+        Self { sftp_service }
+    }
+
+    /// Download a remote file or directory into a local archive
+    /// Streams `tar` output over an exec channel; when remote tar is unavailable the archive
+    /// is built locally from files read over SFTP (tar and tar.gz only).
+    pub async fn download_archive<F>(
+        &self,
+        session_id: &str,
+        remote_path: &str,
+        local_path: &str,
+        format: ArchiveFormat,
+        operation_id: String,
+        mut on_progress: F,
+    ) -> Result<ArchiveSummary, SFTPError>
+    where
+        F: FnMut(u64) + Send,
+    {
+        let (parent, name) = split_remote_path(remote_path);
+        let command = stream_create_command(format, &parent, &name)?;
+        let client = self.sftp_service.client_handle(session_id).await?;
+
+        let tar_available = match run_command(&client, "command -v tar >/dev/null 2>&1").await {
+            Ok(output) => output.success(),
+            Err(_) => false,
+        };
+
+        if !tar_available {
+            if format == ArchiveFormat::TarZst {
+                return Err(SFTPError::Other {
+                    message: "Remote tar is not available and tar.zst cannot be built locally; use tar or tar.gz".to_string(),
+                });
+            }
+            eprintln!(
+                "[SFTP Archive] Remote tar unavailable, building {} locally over SFTP",
+                local_path
+            );
+            let bytes = self
+                .download_over_sftp(
+                    session_id,
+                    remote_path,
+                    &name,
+                    local_path,
+                    format == ArchiveFormat::TarGz,
+                    &mut on_progress,
+                )
+                .await;
+            if bytes.is_err() {
+                let _ = tokio::fs::remove_file(local_path).await;
+            }
+            return bytes.map(|bytes| ArchiveSummary {
+                operation_id,
+                bytes_transferred: bytes,
+                fallback: true,
+            });
+        }
+
+        let result = self
+            .download_with_exec(&client, &command, local_path, &mut on_progress)
+            .await;
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(local_path).await;
+        }
+        result.map(|bytes| ArchiveSummary {
+            operation_id,
+            bytes_transferred: bytes,
+            fallback: false,
+        })
+    }
+
+    async fn download_with_exec<F>(
+        &self,
+        client: &Handle<SFTPClientHandler>,
+        command: &str,
+        local_path: &str,
+        on_progress: &mut F,
+    ) -> Result<u64, SFTPError>
+    where
+        F: FnMut(u64) + Send,
+    {
+        let mut local_file =
+            tokio::fs::File::create(local_path)
+                .await
+                .map_err(|e| SFTPError::IoError {
+                    message: format!("Failed to create archive {}: {}", local_path, e),
+                })?;
+
+        let mut remote = RemoteCommand::spawn(client, command).await?;
+        remote.close_stdin().await?;
+
+        let mut bytes = 0u64;
+        let mut stderr = Vec::new();
+        let mut exit_status = None;
+
+        while let Some(output) = remote.next_output().await {
+            match output {
+                RemoteOutput::Stdout(data) => {
+                    local_file
+                        .write_all(&data)
+                        .await
+                        .map_err(|e| SFTPError::IoError {
+                            message: format!("Failed to write archive {}: {}", local_path, e),
+                        })?;
+                    bytes += data.len() as u64;
+                    on_progress(bytes);
+                }
+                RemoteOutput::Stderr(data) => stderr.extend_from_slice(&data),
+                RemoteOutput::Exit(status) => exit_status = Some(status),
+            }
+        }
+        remote.abort().await;
+
+        if exit_status != Some(0) {
+            return Err(SFTPError::RemoteError {
+                message: format!(
+                    "tar exited with status {}: {}",
+                    exit_status.map_or("unknown".to_string(), |s| s.to_string()),
+                    String::from_utf8_lossy(&stderr).trim()
+                ),
+            });
+        }
+
+        local_file.flush().await.map_err(|e| SFTPError::IoError {
+            message: format!("Failed to write archive {}: {}", local_path, e),
+        })?;
+
+        Ok(bytes)
+    }
+
+    /// Walk `remote_path` over SFTP and feed its entries to a local tar writer
+    async fn download_over_sftp<F>(
+        &self,
+        session_id: &str,
+        remote_path: &str,
+        root_name: &str,
+        local_path: &str,
+        gzip: bool,
+        on_progress: &mut F,
+    ) -> Result<u64, SFTPError>
+    where
+        F: FnMut(u64) + Send,
+    {
+        let session_data = self.sftp_service.get_session(session_id).await?;
+        let (entries_tx, entries_rx) = mpsc::channel(16);
+        let writer_path = local_path.to_string();
+        let writer = tokio::task::spawn_blocking(move || write_tar(writer_path, gzip, entries_rx));
+
+        let mut bytes = 0u64;
+        let mut pending = vec![(remote_path.to_string(), root_name.to_string())];
+
+        let walked: Result<(), SFTPError> = async {
+            while let Some((remote, archive_name)) = pending.pop() {
+                let attrs = {
+                    let data = session_data.lock().await;
+                    data.sftp
+                        .symlink_metadata(remote.as_str())
+                        .await
+                        .map_err(|e| SFTPError::FileNotFound {
+                            path: format!("{} ({})", remote, e),
+                        })?
+                };
+                let mode = attrs.permissions.unwrap_or(0o644) & 0o7777;
+                let mtime = attrs.mtime.unwrap_or(0) as u64;
+
+                match attrs.file_type() {
+                    russh_sftp::protocol::FileType::Dir => {
+                        let names = {
+                            let data = session_data.lock().await;
+                            data.sftp
+                                .read_dir(remote.as_str())
+                                .await
+                                .map_err(|e| SFTPError::Other {
+                                    message: format!("Failed to read directory {}: {}", remote, e),
+                                })?
+                                .map(|entry| entry.file_name())
+                                .filter(|name| name != "." && name != "..")
+                                .collect::<Vec<_>>()
+                        };
+
+                        let entry = ArchiveEntry::Directory {
+                            path: archive_name.clone(),
+                            mode,
+                            mtime,
+                        };
+                        if entries_tx.send(entry).await.is_err() {
+                            return Ok(());
+                        }
+
+                        for name in names.into_iter().rev() {
+                            pending.push((
+                                format!("{}/{}", remote.trim_end_matches('/'), name),
+                                format!("{}/{}", archive_name, name),
+                            ));
+                        }
+                    }
+                    russh_sftp::protocol::FileType::Symlink => {
+                        let target = {
+                            let data = session_data.lock().await;
+                            data.sftp.read_link(remote.as_str()).await.map_err(|e| {
+                                SFTPError::Other {
+                                    message: format!("Failed to read symlink {}: {}", remote, e),
+                                }
+                            })?
+                        };
+                        let entry = ArchiveEntry::Symlink {
+                            path: archive_name,
+                            target,
+                            mtime,
+                        };
+                        if entries_tx.send(entry).await.is_err() {
+                            return Ok(());
+                        }
+                    }
+                    russh_sftp::protocol::FileType::File => {
+                        let mut remote_file = {
+                            let data = session_data.lock().await;
+                            data.sftp
+                                .open(remote.as_str())
+                                .await
+                                .map_err(|e| SFTPError::Other {
+                                    message: format!("Failed to open file {}: {}", remote, e),
+                                })?
+                        };
+
+                        let (data_tx, data_rx) = mpsc::channel(4);
+                        let entry = ArchiveEntry::File {
+                            path: archive_name,
+                            mode,
+                            mtime,
+                            size: attrs.size.unwrap_or(0),
+                            data: data_rx,
+                        };
+                        if entries_tx.send(entry).await.is_err() {
+                            return Ok(());
+                        }
+
+                        let mut buffer = vec![0u8; CHUNK_SIZE];
+                        loop {
+                            match remote_file.read(&mut buffer).await {
+                                Ok(0) => break,
+                                Ok(n) => {
+                                    bytes += n as u64;
+                                    on_progress(bytes);
+                                    if data_tx.send(Ok(buffer[..n].to_vec())).await.is_err() {
+                                        break;
+                                    }
+                                }
+                                Err(e) => {
+                                    let _ = data_tx.send(Err(e)).await;
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    // Sockets, devices and FIFOs cannot be read over SFTP
+                    _ => {}
+                }
+            }
+            Ok(())
+        }
+        .await;
+
+        drop(entries_tx);
+        let written = writer.await.map_err(|e| SFTPError::Other {
+            message: format!("Archive writer failed: {}", e),
+        })?;
+
+        walked?;
+        written?;
+        Ok(bytes)
+    }
+
+    /// Upload a local file or directory to `remote_path` as a tar stream extracted remotely
+    /// The stream is gzip compressed when the remote host has gzip.
+    pub async fn upload_archive<F>(
+        &self,
+        session_id: &str,
+        local_path: &str,
+        remote_path: &str,
+        operation_id: String,
+        mut on_progress: F,
+    ) -> Result<ArchiveSummary, SFTPError>
+    where
+        F: FnMut(u64) + Send,
+    {
+        let metadata = tokio::fs::metadata(local_path)
+            .await
+            .map_err(|e| SFTPError::IoError {
+                message: format!("Failed to read {}: {}", local_path, e),
+            })?;
+
+        let client = self.sftp_service.client_handle(session_id).await?;
+        let probe = run_command(
+            &client,
+            "command -v tar >/dev/null 2>&1 || exit 127; command -v gzip >/dev/null 2>&1",
+        )
+        .await
+        .map_err(|e| SFTPError::Other {
+            message: format!("Remote command execution unavailable: {}", e),
+        })?;
+        if probe.exit_status == Some(127) {
+            return Err(SFTPError::Other {
+                message: "Remote tar is not available; use regular transfers instead".to_string(),
+            });
+        }
+        let gzip = probe.success();
+
+        // Directories are extracted into remote_path, single files into its parent
+        let (destination, file_name) = if metadata.is_dir() {
+            (remote_path.trim_end_matches('/').to_string(), None)
+        } else {
+            let (parent, name) = split_remote_path(remote_path);
+            (parent, Some(name))
+        };
+
+        let command = format!(
+            "mkdir -p -- {} && tar -x{}f - -C {}",
+            shell_quote(&destination),
+            if gzip { "z" } else { "" },
+            shell_quote(&destination)
+        );
+        let mut remote = RemoteCommand::spawn(&client, &command).await?;
+
+        let (chunks_tx, mut chunks_rx) = mpsc::channel::<Vec<u8>>(8);
+        let source = local_path.to_string();
+        let builder_task = tokio::task::spawn_blocking(move || -> std::io::Result<()> {
+            let writer = ChannelWriter::new(chunks_tx);
+            if gzip {
+                let mut builder = tar::Builder::new(GzEncoder::new(writer, Compression::fast()));
+                append_local(&mut builder, &source, file_name.as_deref())?;
+                builder.into_inner()?.finish()?.flush()
+            } else {
+                let mut builder = tar::Builder::new(writer);
+                append_local(&mut builder, &source, file_name.as_deref())?;
+                builder.into_inner()?.flush()
+            }
+        });
+
+        let mut bytes = 0u64;
+        let mut send_error = None;
+        while let Some(chunk) = chunks_rx.recv().await {
+            if let Err(e) = remote.write_stdin(&chunk).await {
+                send_error = Some(e);
+                break;
+            }
+            bytes += chunk.len() as u64;
+            on_progress(bytes);
+        }
+        // Stops the builder if sending failed
+        drop(chunks_rx);
+
+        let built = builder_task.await.map_err(|e| SFTPError::Other {
+            message: format!("Archive builder failed: {}", e),
+        })?;
+        if let Some(e) = send_error {
+            remote.abort().await;
+            return Err(e);
+        }
+        if let Err(e) = built {
+            remote.abort().await;
+            return Err(SFTPError::IoError {
+                message: format!("Failed to archive {}: {}", local_path, e),
+            });
+        }
+
+        remote.close_stdin().await?;
+        let output = remote.wait_with_output().await;
+        if !output.success() {
+            return Err(SFTPError::RemoteError {
+                message: format!("Remote tar failed: {}", output.stderr_text()),
+            });
+        }
+
+        Ok(ArchiveSummary {
+            operation_id,
+            bytes_transferred: bytes,
+            fallback: false,
+        })
+    }
+
+    /// Extract an archive in place on the remote host
+    pub async fn extract_archive(
+        &self,
+        session_id: &str,
+        archive_path: &str,
+        destination: Option<&str>,
+        format: Option<ArchiveFormat>,
+    ) -> Result<(), SFTPError> {
+        let format = Self::resolve_format(format, archive_path)?;
+        let destination = match destination {
+            Some(destination) => destination.to_string(),
+            None => split_remote_path(archive_path).0,
+        };
+
+        self.run_remote(
+            session_id,
+            &extract_command(format, archive_path, &destination),
+        )
+        .await
+    }
+
+    /// Create an archive from remote files and directories on the remote host
+    pub async fn create_archive(
+        &self,
+        session_id: &str,
+        source_paths: &[String],
+        archive_path: &str,
+        format: Option<ArchiveFormat>,
+    ) -> Result<(), SFTPError> {
+        if source_paths.is_empty() {
+            return Err(SFTPError::Other {
+                message: "No files selected for the archive".to_string(),
+            });
+        }
+        let format = Self::resolve_format(format, archive_path)?;
+
+        self.run_remote(
+            session_id,
+            &create_command(format, archive_path, source_paths),
+        )
+        .await
+    }
+
+    fn resolve_format(
+        format: Option<ArchiveFormat>,
+        archive_path: &str,
+    ) -> Result<ArchiveFormat, SFTPError> {
+        format
+            .or_else(|| ArchiveFormat::from_path(archive_path))
+            .ok_or_else(|| SFTPError::Other {
+                message: format!("Unknown archive format: {}", archive_path),
+            })
+    }
+
+    async fn run_remote(&self, session_id: &str, command: &str) -> Result<(), SFTPError> {
+        let client = self.sftp_service.client_handle(session_id).await?;
+        let output = run_command(&client, command).await?;
+
+        match output.exit_status {
+            Some(0) => Ok(()),
+            Some(127) => Err(SFTPError::RemoteError {
+                message: format!(
+                    "Required archive tool is not installed on the remote host: {}",
+                    output.stderr_text()
+                ),
+            }),
+            _ => Err(SFTPError::RemoteError {
+                message: output.stderr_text(),
+            }),
+        }
+    }
+}
+
+/// Add a local file or directory tree to a tar builder without following symlinks
+fn append_local<W: Write>(
+    builder: &mut tar::Builder<W>,
+    source: &str,
+    file_name: Option<&str>,
+) -> std::io::Result<()> {
+    builder.follow_symlinks(false);
+    match file_name {
+        Some(name) => builder.append_path_with_name(source, name),
+        None => builder.append_dir_all(".", Path::new(source)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_remote_path() {
+        assert_eq!(
+            split_remote_path("/srv/app/"),
+            ("/srv".to_string(), "app".to_string())
+        );
+        assert_eq!(
+            split_remote_path("/etc"),
+            ("/".to_string(), "etc".to_string())
+        );
+        assert_eq!(
+            split_remote_path("logs"),
+            (".".to_string(), "logs".to_string())
+        );
+        assert_eq!(split_remote_path("/"), ("/".to_string(), ".".to_string()));
+    }
+
+    #[test]
+    fn test_archive_commands() {
+        assert_eq!(
+            stream_create_command(ArchiveFormat::TarGz, "/srv", "my app").unwrap(),
+            "tar -czf - -C /srv -- 'my app'"
+        );
+        assert!(stream_create_command(ArchiveFormat::Zip, "/srv", "app").is_err());
+
+        assert_eq!(
+            extract_command(ArchiveFormat::TarZst, "/tmp/a.tar.zst", "/srv"),
+            "mkdir -p -- /srv && tar --zstd -xf /tmp/a.tar.zst -C /srv"
+        );
+        assert_eq!(
+            create_command(
+                ArchiveFormat::TarZst,
+                "/tmp/out.tar.zst",
+                &["/srv/a".to_string(), "/var/-b".to_string()]
+            ),
+            "tar --zstd -cf /tmp/out.tar.zst -C /srv a -C /var ./-b"
+        );
+        assert_eq!(
+            create_command(
+                ArchiveFormat::Zip,
+                "/tmp/out.zip",
+                &["/srv/a".to_string(), "/var/-b".to_string()]
+            ),
+            "(cd /srv && zip -q -r -y /tmp/out.zip a) && (cd /var && zip -q -r -y /tmp/out.zip ./-b)"
+        );
+    }
+
+    #[test]
+    fn test_write_tar_from_chunks() {
+        let dir = std::env::temp_dir().join(format!("aloe-archive-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let archive_path = dir.join("out.tar.gz").to_string_lossy().to_string();
+
+        let (entries_tx, entries_rx) = mpsc::channel(4);
+        let writer_path = archive_path.clone();
+        let writer = std::thread::spawn(move || write_tar(writer_path, true, entries_rx));
+
+        let (data_tx, data_rx) = mpsc::channel(4);
+        entries_tx
+            .blocking_send(ArchiveEntry::Directory {
+                path: "app".to_string(),
+                mode: 0o755,
+                mtime: 0,
+            })
+            .unwrap();
+        entries_tx
+            .blocking_send(ArchiveEntry::File {
+                path: "app/data.txt".to_string(),
+                mode: 0o600,
+                mtime: 0,
+                size: 8,
+                data: data_rx,
+            })
+            .unwrap();
+        // File grew after stat: extra bytes are dropped
+        data_tx.blocking_send(Ok(b"hello".to_vec())).unwrap();
+        data_tx.blocking_send(Ok(b" world".to_vec())).unwrap();
+        drop(data_tx);
+        drop(entries_tx);
+        writer.join().unwrap().unwrap();
+
+        let file = std::fs::File::open(&archive_path).unwrap();
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+        let mut names = Vec::new();
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let mut content = String::new();
+            entry.read_to_string(&mut content).unwrap();
+            names.push((entry.path().unwrap().to_string_lossy().to_string(), content));
+        }
+        assert_eq!(
+            names,
+            vec![
+                ("app".to_string(), String::new()),
+                ("app/data.txt".to_string(), "hello wo".to_string())
+            ]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
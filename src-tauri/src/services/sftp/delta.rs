@@ -0,0 +1,868 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncReadExt;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::models::sftp::error::SFTPError;
+use crate::services::sftp::remote_exec::{run_command, shell_quote, RemoteCommand};
+use crate::services::sftp::service::SFTPService;
+
+/// Modulus of the Adler-32 rolling checksum (same as zlib)
+const ADLER_MOD: u32 = 65521;
+
+/// Maximum size of a single literal op before it is flushed
+const MAX_LITERAL_SIZE: usize = 256 * 1024;
+
+/// Size of local read chunks while encoding
+const READ_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Remote helper: prints `<len> <adler32> <sha256>` for each block of a file
+const SIGNATURE_SCRIPT: &str = r#"import sys,zlib,hashlib
+f=open(sys.argv[1],"rb")
+n=int(sys.argv[2])
+o=sys.stdout
+while True:
+    b=f.read(n)
+    if not b:
+        break
+    o.write("%d %d %s\n"%(len(b),zlib.adler32(b)&0xffffffff,hashlib.sha256(b).hexdigest()))
+o.flush()
+"#;
+
+/// Remote helper: rebuilds a file from the old copy and a delta stream on stdin
+const PATCH_SCRIPT: &str = r#"import sys,os,struct
+src=open(sys.argv[1],"rb")
+dst=open(sys.argv[2],"wb")
+inp=sys.stdin.buffer
+def rd(n):
+    b=b""
+    while len(b)<n:
+        c=inp.read(n-len(b))
+        if not c:
+            sys.exit(3)
+        b+=c
+    return b
+while True:
+    op=rd(1)
+    if op==b"C":
+        off,ln=struct.unpack(">QQ",rd(16))
+        src.seek(off)
+        while ln:
+            c=src.read(min(ln,1<<20))
+            if not c:
+                sys.exit(4)
+            dst.write(c)
+            ln-=len(c)
+    elif op==b"D":
+        (ln,)=struct.unpack(">I",rd(4))
+        dst.write(rd(ln))
+    elif op==b"E":
+        break
+    else:
+        sys.exit(2)
+dst.flush()
+os.fsync(dst.fileno())
+dst.close()
+"#;
+
+/// Remote helper: prints the hex digest of a file
+const HASH_SCRIPT: &str = r#"import sys,hashlib
+h=hashlib.new(sys.argv[1])
+f=open(sys.argv[2],"rb")
+for c in iter(lambda:f.read(1<<20),b""):
+    h.update(c)
+print(h.hexdigest())
+"#;
+
+/// Adler-32 checksum that can slide over a byte stream
+#[derive(Debug, Clone, Copy)]
+pub struct RollingChecksum {
+    a: u32,
+    b: u32,
+    len: u32,
+}
+
+impl RollingChecksum {
+    /// Compute the checksum of a block
+    pub fn new(block: &[u8]) -> Self {
+        let mut a: u32 = 1;
+        let mut b: u32 = 0;
+        for &byte in block {
+            a = (a + byte as u32) % ADLER_MOD;
+            b = (b + a) % ADLER_MOD;
+        }
+        Self {
+            a,
+            b,
+            len: block.len() as u32,
+        }
+    }
+
+    /// Checksum value, identical to zlib's `adler32`
+    pub fn value(&self) -> u32 {
+        (self.b << 16) | self.a
+    }
+
+    /// Slide the window one byte: drop `out` from the front and append `incoming`
+    pub fn roll(&mut self, out: u8, incoming: u8) {
+        let out = out as u32;
+        let len = self.len % ADLER_MOD;
+        self.a = (self.a + ADLER_MOD - out + incoming as u32) % ADLER_MOD;
+        self.b =
+            (self.b + ADLER_MOD - (len * out) % ADLER_MOD + self.a + ADLER_MOD - 1) % ADLER_MOD;
+    }
+}
+
+/// Signature of one block of the remote (old) file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockSignature {
+    pub offset: u64,
+    pub len: usize,
+    pub weak: u32,
+    pub strong: [u8; 32],
+}
+
+/// Instruction for rebuilding the new file on the remote side
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeltaOp {
+    /// Copy a byte range from the old remote file
+    Copy { offset: u64, len: u64 },
+    /// Write literal bytes from the local file
+    Data(Vec<u8>),
+}
+
+impl DeltaOp {
+    /// Append the wire encoding understood by the remote patch helper
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            DeltaOp::Copy { offset, len } => {
+                out.push(b'C');
+                out.extend_from_slice(&offset.to_be_bytes());
+                out.extend_from_slice(&len.to_be_bytes());
+            }
+            DeltaOp::Data(data) => {
+                out.push(b'D');
+                out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+                out.extend_from_slice(data);
+            }
+        }
+    }
+}
+
+/// Pick a block size for a file: about sqrt(size), rounded to 8 KiB and clamped to 16 KiB..1 MiB
+pub fn block_size_for(file_size: u64) -> usize {
+    let root = (file_size as f64).sqrt() as usize;
+    let rounded = root.div_ceil(8192) * 8192;
+    rounded.clamp(16 * 1024, 1024 * 1024)
+}
+
+/// Parse the output of the remote signature helper
+pub fn parse_signatures(output: &str) -> Result<Vec<BlockSignature>, SFTPError> {
+    let mut signatures = Vec::new();
+    let mut offset = 0u64;
+
+    for line in output.lines().filter(|l| !l.trim().is_empty()) {
+        let invalid = || SFTPError::RemoteError {
+            message: format!("Invalid block signature line: {}", line),
+        };
+
+        let mut parts = line.split_whitespace();
+        let len: usize = parts
+            .next()
+            .and_then(|p| p.parse().ok())
+            .ok_or_else(invalid)?;
+        let weak: u32 = parts
+            .next()
+            .and_then(|p| p.parse().ok())
+            .ok_or_else(invalid)?;
+        let strong_hex = parts.next().ok_or_else(invalid)?;
+        if strong_hex.len() != 64 {
+            return Err(invalid());
+        }
+
+        let mut strong = [0u8; 32];
+        for (i, byte) in strong.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&strong_hex[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+        }
+
+        signatures.push(BlockSignature {
+            offset,
+            len,
+            weak,
+            strong,
+        });
+        offset += len as u64;
+    }
+
+    Ok(signatures)
+}
+
+/// Streaming rsync-style delta encoder
+///
+/// Local file bytes are pushed in arbitrary chunks; a rolling checksum window looks up
+/// matching remote blocks and emits `Copy` ops for them and `Data` ops for everything else.
+pub struct DeltaEncoder {
+    signatures: Vec<BlockSignature>,
+    index: HashMap<u32, Vec<usize>>,
+    block_size: usize,
+    buffer: Vec<u8>,
+    window_start: usize,
+    rolling: Option<RollingChecksum>,
+    window_checked: bool,
+    pending_copy: Option<(u64, u64)>,
+    matched_bytes: u64,
+    literal_bytes: u64,
+}
+
+impl DeltaEncoder {
+    /// Create encoder for the given remote block signatures
+    pub fn new(signatures: Vec<BlockSignature>, block_size: usize) -> Self {
+        let mut index: HashMap<u32, Vec<usize>> = HashMap::new();
+        for (i, signature) in signatures.iter().enumerate() {
+            if signature.len == block_size {
+                index.entry(signature.weak).or_default().push(i);
+            }
+        }
+
+        Self {
+            signatures,
+            index,
+            block_size,
+            buffer: Vec::new(),
+            window_start: 0,
+            rolling: None,
+            window_checked: false,
+            pending_copy: None,
+            matched_bytes: 0,
+            literal_bytes: 0,
+        }
+    }
+
+    /// Bytes that will be copied from the old remote file
+    pub fn matched_bytes(&self) -> u64 {
+        self.matched_bytes
+    }
+
+    /// Bytes that must be sent over the wire
+    pub fn literal_bytes(&self) -> u64 {
+        self.literal_bytes
+    }
+
+    /// Feed the next chunk of the local file and collect ready ops
+    pub fn push(&mut self, data: &[u8]) -> Vec<DeltaOp> {
+        let mut ops = Vec::new();
+        self.buffer.extend_from_slice(data);
+
+        loop {
+            let window_end = self.window_start + self.block_size;
+            if window_end > self.buffer.len() {
+                break;
+            }
+
+            if !self.window_checked {
+                let weak = self
+                    .rolling
+                    .get_or_insert_with(|| {
+                        RollingChecksum::new(&self.buffer[self.window_start..window_end])
+                    })
+                    .value();
+
+                if let Some(signature_index) =
+                    self.find_match(weak, &self.buffer[self.window_start..window_end])
+                {
+                    self.flush_literal(self.window_start, &mut ops);
+                    let signature = &self.signatures[signature_index];
+                    let (offset, len) = (signature.offset, signature.len as u64);
+                    self.push_copy(offset, len, &mut ops);
+                    self.buffer.drain(..self.block_size);
+                    self.rolling = None;
+                    continue;
+                }
+                self.window_checked = true;
+            }
+
+            // Need the byte after the window to roll forward
+            if window_end >= self.buffer.len() {
+                break;
+            }
+
+            let (out, incoming) = (self.buffer[self.window_start], self.buffer[window_end]);
+            if let Some(rolling) = self.rolling.as_mut() {
+                rolling.roll(out, incoming);
+            }
+            self.window_start += 1;
+            self.window_checked = false;
+
+            if self.window_start >= MAX_LITERAL_SIZE {
+                self.flush_literal(self.window_start, &mut ops);
+            }
+        }
+
+        ops
+    }
+
+    /// Flush everything left once the local file is exhausted
+    pub fn finish(&mut self) -> Vec<DeltaOp> {
+        let mut ops = Vec::new();
+
+        // The remote file's last block is usually short; try to reuse it for the tail
+        let tail = &self.buffer[self.window_start..];
+        if !tail.is_empty() && tail.len() < self.block_size {
+            let weak = RollingChecksum::new(tail).value();
+            let strong: [u8; 32] = Sha256::digest(tail).into();
+            let last_block = self
+                .signatures
+                .iter()
+                .find(|s| s.len == tail.len() && s.weak == weak && s.strong == strong)
+                .map(|s| (s.offset, s.len as u64));
+
+            if let Some((offset, len)) = last_block {
+                self.flush_literal(self.window_start, &mut ops);
+                self.push_copy(offset, len, &mut ops);
+                self.buffer.clear();
+                self.window_start = 0;
+            }
+        }
+
+        let remaining = self.buffer.len();
+        self.flush_literal(remaining, &mut ops);
+        if let Some((offset, len)) = self.pending_copy.take() {
+            ops.push(DeltaOp::Copy { offset, len });
+        }
+
+        ops
+    }
+
+    fn find_match(&self, weak: u32, window: &[u8]) -> Option<usize> {
+        let candidates = self.index.get(&weak)?;
+        let strong: [u8; 32] = Sha256::digest(window).into();
+        candidates
+            .iter()
+            .copied()
+            .find(|&i| self.signatures[i].strong == strong)
+    }
+
+    fn push_copy(&mut self, offset: u64, len: u64, ops: &mut Vec<DeltaOp>) {
+        self.matched_bytes += len;
+        match self.pending_copy {
+            Some((start, pending_len)) if start + pending_len == offset => {
+                self.pending_copy = Some((start, pending_len + len));
+            }
+            Some((start, pending_len)) => {
+                ops.push(DeltaOp::Copy {
+                    offset: start,
+                    len: pending_len,
+                });
+                self.pending_copy = Some((offset, len));
+            }
+            None => self.pending_copy = Some((offset, len)),
+        }
+    }
+
+    fn flush_literal(&mut self, len: usize, ops: &mut Vec<DeltaOp>) {
+        if len == 0 {
+            return;
+        }
+        if let Some((offset, pending_len)) = self.pending_copy.take() {
+            ops.push(DeltaOp::Copy {
+                offset,
+                len: pending_len,
+            });
+        }
+        self.literal_bytes += len as u64;
+        ops.push(DeltaOp::Data(self.buffer.drain(..len).collect()));
+        self.window_start -= len.min(self.window_start);
+    }
+}
+
+/// Checksum algorithm used to verify transferred files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+    Md5,
+    Sha256,
+}
+
+impl ChecksumAlgorithm {
+    /// Parse `SyncOperation::checksum_algorithm` (default: md5)
+    pub fn from_option(value: Option<&str>) -> Self {
+        match value.map(|v| v.to_lowercase()) {
+            Some(v) if v == "sha256" => ChecksumAlgorithm::Sha256,
+            _ => ChecksumAlgorithm::Md5,
+        }
+    }
+
+    /// Name understood by Python's `hashlib.new`
+    fn hashlib_name(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Md5 => "md5",
+            ChecksumAlgorithm::Sha256 => "sha256",
+        }
+    }
+
+    fn hasher(&self) -> FileHasher {
+        match self {
+            ChecksumAlgorithm::Md5 => FileHasher::Md5(Md5::new()),
+            ChecksumAlgorithm::Sha256 => FileHasher::Sha256(Sha256::new()),
+        }
+    }
+}
+
+/// Incremental hasher for the supported checksum algorithms
+enum FileHasher {
+    Md5(Md5),
+    Sha256(Sha256),
+}
+
+impl FileHasher {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            FileHasher::Md5(h) => h.update(data),
+            FileHasher::Sha256(h) => h.update(data),
+        }
+    }
+
+    fn finalize_hex(self) -> String {
+        let digest = match self {
+            FileHasher::Md5(h) => h.finalize().to_vec(),
+            FileHasher::Sha256(h) => h.finalize().to_vec(),
+        };
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+/// Result of a delta upload
+#[derive(Debug, Clone)]
+pub struct DeltaUploadStats {
+    /// Bytes sent as literal data
+    pub literal_bytes: u64,
+    /// Bytes reused from the existing remote file
+    pub matched_bytes: u64,
+}
+
+/// Uploads modified files by sending only the blocks that changed
+pub struct DeltaUploader {
+    sftp_service: Arc<SFTPService>,
+    /// Whether python3 is available, probed once per SFTP session
+    python_by_session: Mutex<HashMap<String, bool>>,
+}
+
+impl DeltaUploader {
+    /// Create new delta uploader
+    pub fn new(sftp_service: Arc<SFTPService>) -> Self {
+        Self {
+            sftp_service,
+            python_by_session: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Upload `local_path` over the existing `remote_path` using rolling-checksum deltas
+    ///
+    /// The new file is rebuilt in a temp file next to the target and renamed into place,
+    /// so readers never see a partially written file. With `verify` set, the rebuilt file
+    /// is hashed on the remote and compared against the local file before the rename.
+    ///
+    /// Returns `None` without touching the remote file when the host has no python3;
+    /// rebuilding through SFTP alone costs more than a plain upload, so the caller
+    /// should upload the file in full instead.
+    pub async fn upload(
+        &self,
+        session_id: &str,
+        local_path: &str,
+        remote_path: &str,
+        verify: Option<ChecksumAlgorithm>,
+    ) -> Result<Option<DeltaUploadStats>, SFTPError> {
+        if !self.has_python(session_id).await? {
+            return Ok(None);
+        }
+
+        let remote_entry = self
+            .sftp_service
+            .stat(session_id.to_string(), remote_path.to_string())
+            .await?;
+        let block_size = block_size_for(remote_entry.size.unwrap_or(0));
+
+        let signatures = self
+            .remote_signatures(session_id, remote_path, block_size)
+            .await?;
+
+        let temp_path = Self::temp_path_for(remote_path);
+        let mut encoder = DeltaEncoder::new(signatures, block_size);
+        let mut local_hasher = verify.map(|algorithm| algorithm.hasher());
+
+        if let Err(e) = self
+            .patch_with_helper(
+                session_id,
+                local_path,
+                remote_path,
+                &temp_path,
+                &mut encoder,
+                &mut local_hasher,
+            )
+            .await
+        {
+            self.remove_temp(session_id, &temp_path).await;
+            return Err(e);
+        }
+
+        if let (Some(algorithm), Some(hasher)) = (verify, local_hasher) {
+            let expected = hasher.finalize_hex();
+            let actual = match self
+                .remote_checksum(session_id, &temp_path, algorithm)
+                .await
+            {
+                Ok(actual) => actual,
+                Err(e) => {
+                    self.remove_temp(session_id, &temp_path).await;
+                    return Err(e);
+                }
+            };
+
+            if expected != actual {
+                self.remove_temp(session_id, &temp_path).await;
+                return Err(SFTPError::RemoteError {
+                    message: format!(
+                        "Checksum mismatch after delta transfer of {} (expected {}, got {})",
+                        remote_path, expected, actual
+                    ),
+                });
+            }
+        }
+
+        if let Err(e) = self
+            .sftp_service
+            .replace_file(session_id, &temp_path, remote_path)
+            .await
+        {
+            self.remove_temp(session_id, &temp_path).await;
+            return Err(e);
+        }
+
+        Ok(Some(DeltaUploadStats {
+            literal_bytes: encoder.literal_bytes(),
+            matched_bytes: encoder.matched_bytes(),
+        }))
+    }
+
+    /// Check for python3 on the remote host, probing only the first time per session
+    async fn has_python(&self, session_id: &str) -> Result<bool, SFTPError> {
+        if let Some(&python) = self.python_by_session.lock().await.get(session_id) {
+            return Ok(python);
+        }
+
+        let client = self.sftp_service.client_handle(session_id).await?;
+        let python = run_command(&client, "command -v python3")
+            .await
+            .map(|output| output.success())
+            .unwrap_or(false);
+
+        self.python_by_session
+            .lock()
+            .await
+            .insert(session_id.to_string(), python);
+        Ok(python)
+    }
+
+    /// Compute block signatures on the remote host
+    async fn remote_signatures(
+        &self,
+        session_id: &str,
+        remote_path: &str,
+        block_size: usize,
+    ) -> Result<Vec<BlockSignature>, SFTPError> {
+        let client = self.sftp_service.client_handle(session_id).await?;
+        let command = format!(
+            "python3 -c {} {} {}",
+            shell_quote(SIGNATURE_SCRIPT),
+            shell_quote(remote_path),
+            block_size
+        );
+
+        let output = run_command(&client, &command).await?;
+        if !output.success() {
+            return Err(SFTPError::RemoteError {
+                message: format!("Signature helper failed: {}", output.stderr_text()),
+            });
+        }
+
+        parse_signatures(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Stream delta ops to the remote patch helper, which writes the temp file
+    async fn patch_with_helper(
+        &self,
+        session_id: &str,
+        local_path: &str,
+        remote_path: &str,
+        temp_path: &str,
+        encoder: &mut DeltaEncoder,
+        local_hasher: &mut Option<FileHasher>,
+    ) -> Result<(), SFTPError> {
+        let client = self.sftp_service.client_handle(session_id).await?;
+        let command = format!(
+            "python3 -c {} {} {}",
+            shell_quote(PATCH_SCRIPT),
+            shell_quote(remote_path),
+            shell_quote(temp_path)
+        );
+        let remote = RemoteCommand::spawn(&client, &command).await?;
+
+        let mut local_file =
+            tokio::fs::File::open(local_path)
+                .await
+                .map_err(|e| SFTPError::IoError {
+                    message: format!("Failed to open local file {}: {}", local_path, e),
+                })?;
+
+        let mut chunk = vec![0u8; READ_CHUNK_SIZE];
+        let mut wire = Vec::with_capacity(MAX_LITERAL_SIZE * 2);
+
+        loop {
+            let n = match local_file.read(&mut chunk).await {
+                Ok(n) => n,
+                Err(e) => {
+                    remote.abort().await;
+                    return Err(SFTPError::IoError {
+                        message: format!("Failed to read local file {}: {}", local_path, e),
+                    });
+                }
+            };
+
+            let ops = if n == 0 {
+                encoder.finish()
+            } else {
+                if let Some(hasher) = local_hasher.as_mut() {
+                    hasher.update(&chunk[..n]);
+                }
+                encoder.push(&chunk[..n])
+            };
+
+            for op in ops {
+                op.encode(&mut wire);
+            }
+            if n == 0 {
+                wire.push(b'E');
+            }
+
+            if !wire.is_empty() {
+                if let Err(e) = remote.write_stdin(&wire).await {
+                    remote.abort().await;
+                    return Err(e);
+                }
+                wire.clear();
+            }
+
+            if n == 0 {
+                break;
+            }
+        }
+
+        remote.close_stdin().await?;
+        let output = remote.wait_with_output().await;
+        if !output.success() {
+            return Err(SFTPError::RemoteError {
+                message: format!(
+                    "Delta patch helper failed for {} (status {:?}): {}",
+                    remote_path,
+                    output.exit_status,
+                    output.stderr_text()
+                ),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Hash a remote file, preferring the remote helper over streaming it back
+    async fn remote_checksum(
+        &self,
+        session_id: &str,
+        remote_path: &str,
+        algorithm: ChecksumAlgorithm,
+    ) -> Result<String, SFTPError> {
+        let client = self.sftp_service.client_handle(session_id).await?;
+        let command = format!(
+            "python3 -c {} {} {}",
+            shell_quote(HASH_SCRIPT),
+            algorithm.hashlib_name(),
+            shell_quote(remote_path)
+        );
+        let output = run_command(&client, &command).await?;
+        if output.success() {
+            return Ok(String::from_utf8_lossy(&output.stdout).trim().to_string());
+        }
+
+        let session_data = self.sftp_service.get_session(session_id).await?;
+        let data = session_data.lock().await;
+        let mut remote_file = data
+            .sftp
+            .open(remote_path)
+            .await
+            .map_err(|e| SFTPError::Other {
+                message: format!("Failed to open remote file {}: {}", remote_path, e),
+            })?;
+
+        let mut hasher = algorithm.hasher();
+        let mut buffer = vec![0u8; 64 * 1024];
+        loop {
+            let n = remote_file
+                .read(&mut buffer)
+                .await
+                .map_err(|e| SFTPError::Other {
+                    message: format!("Failed to read remote file {}: {}", remote_path, e),
+                })?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buffer[..n]);
+        }
+
+        Ok(hasher.finalize_hex())
+    }
+
+    async fn remove_temp(&self, session_id: &str, temp_path: &str) {
+        if let Ok(session_data) = self.sftp_service.get_session(session_id).await {
+            let data = session_data.lock().await;
+            let _ = data.sftp.remove_file(temp_path).await;
+        }
+    }
+
+    /// Hidden temp file in the target's directory so the final rename stays on one filesystem
+    fn temp_path_for(remote_path: &str) -> String {
+        let suffix = &Uuid::new_v4().to_string()[..8];
+        match remote_path.rsplit_once('/') {
+            Some((dir, name)) => format!("{}/.{}.delta-{}", dir, name, suffix),
+            None => format!(".{}.delta-{}", remote_path, suffix),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signatures_of(data: &[u8], block_size: usize) -> Vec<BlockSignature> {
+        data.chunks(block_size)
+            .enumerate()
+            .map(|(i, block)| BlockSignature {
+                offset: (i * block_size) as u64,
+                len: block.len(),
+                weak: RollingChecksum::new(block).value(),
+                strong: Sha256::digest(block).into(),
+            })
+            .collect()
+    }
+
+    fn apply(old: &[u8], ops: &[DeltaOp]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for op in ops {
+            match op {
+                DeltaOp::Copy { offset, len } => {
+                    out.extend_from_slice(&old[*offset as usize..(*offset + *len) as usize])
+                }
+                DeltaOp::Data(data) => out.extend_from_slice(data),
+            }
+        }
+        out
+    }
+
+    fn encode_in_chunks(old: &[u8], new: &[u8], block_size: usize, chunk: usize) -> Vec<DeltaOp> {
+        let mut encoder = DeltaEncoder::new(signatures_of(old, block_size), block_size);
+        let mut ops = Vec::new();
+        for piece in new.chunks(chunk) {
+            ops.extend(encoder.push(piece));
+        }
+        ops.extend(encoder.finish());
+        ops
+    }
+
+    fn pseudo_random(len: usize, seed: u32) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1103515245).wrapping_add(12345);
+                (state >> 16) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_rolling_checksum_matches_fresh_computation() {
+        let data = pseudo_random(4096, 7);
+        let window = 512;
+        let mut rolling = RollingChecksum::new(&data[..window]);
+
+        for start in 1..(data.len() - window) {
+            rolling.roll(data[start - 1], data[start + window - 1]);
+            assert_eq!(
+                rolling.value(),
+                RollingChecksum::new(&data[start..start + window]).value()
+            );
+        }
+    }
+
+    #[test]
+    fn test_rolling_checksum_matches_zlib_adler32() {
+        // adler32("Wikipedia") from the zlib reference
+        assert_eq!(RollingChecksum::new(b"Wikipedia").value(), 0x11E60398);
+    }
+
+    #[test]
+    fn test_delta_reuses_unchanged_blocks() {
+        let block_size = 1024;
+        let old = pseudo_random(64 * 1024, 1);
+        let mut new = old.clone();
+        new[10_000] ^= 0xff;
+        new.splice(30_000..30_000, b"inserted bytes".iter().copied());
+        new.extend_from_slice(b"appended tail");
+
+        let ops = encode_in_chunks(&old, &new, block_size, 3000);
+        assert_eq!(apply(&old, &ops), new);
+
+        let literal: usize = ops
+            .iter()
+            .map(|op| match op {
+                DeltaOp::Data(data) => data.len(),
+                _ => 0,
+            })
+            .sum();
+        assert!(literal < 4 * block_size, "sent {} literal bytes", literal);
+    }
+
+    #[test]
+    fn test_delta_identical_file_is_single_copy() {
+        let old = pseudo_random(10_000, 3);
+        let ops = encode_in_chunks(&old, &old, 1024, 777);
+        assert_eq!(
+            ops,
+            vec![DeltaOp::Copy {
+                offset: 0,
+                len: old.len() as u64
+            }]
+        );
+    }
+
+    #[test]
+    fn test_delta_unrelated_file_is_all_literal() {
+        let old = pseudo_random(8192, 5);
+        let new = pseudo_random(9000, 11);
+        let ops = encode_in_chunks(&old, &new, 1024, 4096);
+        assert_eq!(apply(&old, &ops), new);
+        assert!(ops.iter().all(|op| matches!(op, DeltaOp::Data(_))));
+    }
+
+    #[test]
+    fn test_parse_signatures() {
+        let sha = "ab".repeat(32);
+        let output = format!("4 65536 {}\n2 131072 {}\n", sha, sha);
+        let signatures = parse_signatures(&output).unwrap();
+        assert_eq!(signatures.len(), 2);
+        assert_eq!(signatures[1].offset, 4);
+        assert_eq!(signatures[1].weak, 131072);
+        assert_eq!(signatures[0].strong, [0xab; 32]);
+        assert!(parse_signatures("bogus").is_err());
+    }
+}
@@ -0,0 +1,534 @@
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::models::sftp::editor::{
+    FileSnapshot, LineEnding, TextEncoding, TextFileContent, WriteFileOptions,
+};
+use crate::models::sftp::error::SFTPError;
+
+/// Largest file that can be opened as a whole for editing
+pub const MAX_EDIT_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Largest raw chunk returned by a single ranged read
+pub const MAX_CHUNK_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Number of leading bytes inspected for encoding detection
+pub const DETECT_SAMPLE_SIZE: usize = 8192;
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+/// Detect the encoding of a file from its first bytes
+///
+/// Byte order marks win; without one, NUL bytes at alternating positions indicate BOM-less
+/// UTF-16, other NUL bytes a binary file (None). Everything else is UTF-8 if it decodes as
+/// such (ignoring a sequence cut off at the end of the sample) and Latin-1 otherwise.
+pub fn detect_encoding(sample: &[u8]) -> Option<(TextEncoding, bool)> {
+    if sample.starts_with(UTF8_BOM) {
+        return Some((TextEncoding::Utf8, true));
+    }
+    if sample.starts_with(UTF16LE_BOM) {
+        return Some((TextEncoding::Utf16le, true));
+    }
+    if sample.starts_with(UTF16BE_BOM) {
+        return Some((TextEncoding::Utf16be, true));
+    }
+
+    if sample.contains(&0) {
+        let pairs = sample.len() / 2;
+        let even_nuls = sample.iter().step_by(2).filter(|b| **b == 0).count();
+        let odd_nuls = sample
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .filter(|b| **b == 0)
+            .count();
+
+        // ASCII-range text in UTF-16 has a NUL in every other byte
+        if pairs > 0 && odd_nuls * 10 >= pairs * 4 && even_nuls * 10 < pairs {
+            return Some((TextEncoding::Utf16le, false));
+        }
+        if pairs > 0 && even_nuls * 10 >= pairs * 4 && odd_nuls * 10 < pairs {
+            return Some((TextEncoding::Utf16be, false));
+        }
+        return None;
+    }
+
+    match std::str::from_utf8(sample) {
+        Ok(_) => Some((TextEncoding::Utf8, false)),
+        Err(e) if e.error_len().is_none() => Some((TextEncoding::Utf8, false)),
+        Err(_) => Some((TextEncoding::Latin1, false)),
+    }
+}
+
+/// Byte order mark for an encoding
+pub fn bom_for(encoding: TextEncoding) -> &'static [u8] {
+    match encoding {
+        TextEncoding::Utf8 => UTF8_BOM,
+        TextEncoding::Utf16le => UTF16LE_BOM,
+        TextEncoding::Utf16be => UTF16BE_BOM,
+        TextEncoding::Latin1 => &[],
+    }
+}
+
+/// Decode bytes (without BOM) to text
+pub fn decode(bytes: &[u8], encoding: TextEncoding) -> Result<String, SFTPError> {
+    match encoding {
+        TextEncoding::Utf8 => String::from_utf8(bytes.to_vec()).map_err(|e| SFTPError::Other {
+            message: format!("Content is not valid UTF-8: {}", e),
+        }),
+        TextEncoding::Utf16le | TextEncoding::Utf16be => {
+            if !bytes.len().is_multiple_of(2) {
+                return Err(SFTPError::Other {
+                    message: "Content is not valid UTF-16: odd number of bytes".to_string(),
+                });
+            }
+            let units = bytes.chunks_exact(2).map(|pair| {
+                if encoding == TextEncoding::Utf16le {
+                    u16::from_le_bytes([pair[0], pair[1]])
+                } else {
+                    u16::from_be_bytes([pair[0], pair[1]])
+                }
+            });
+            char::decode_utf16(units)
+                .collect::<Result<String, _>>()
+                .map_err(|e| SFTPError::Other {
+                    message: format!("Content is not valid UTF-16: {}", e),
+                })
+        }
+        TextEncoding::Latin1 => Ok(bytes.iter().map(|&b| b as char).collect()),
+    }
+}
+
+/// Encode text, optionally prefixed with a byte order mark
+pub fn encode(text: &str, encoding: TextEncoding, bom: bool) -> Result<Vec<u8>, SFTPError> {
+    let mut bytes = Vec::with_capacity(text.len() + 3);
+    if bom {
+        bytes.extend_from_slice(bom_for(encoding));
+    }
+
+    match encoding {
+        TextEncoding::Utf8 => bytes.extend_from_slice(text.as_bytes()),
+        TextEncoding::Utf16le => text
+            .encode_utf16()
+            .for_each(|unit| bytes.extend_from_slice(&unit.to_le_bytes())),
+        TextEncoding::Utf16be => text
+            .encode_utf16()
+            .for_each(|unit| bytes.extend_from_slice(&unit.to_be_bytes())),
+        TextEncoding::Latin1 => {
+            for c in text.chars() {
+                let code = c as u32;
+                if code > 0xFF {
+                    return Err(SFTPError::Other {
+                        message: format!("Character '{}' cannot be encoded as Latin-1", c),
+                    });
+                }
+                bytes.push(code as u8);
+            }
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// Find the dominant line ending (Lf when there are no line breaks)
+pub fn detect_line_ending(text: &str) -> LineEnding {
+    let bytes = text.as_bytes();
+    let (mut lf, mut crlf, mut cr) = (0usize, 0usize, 0usize);
+
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\r' if bytes.get(i + 1) == Some(&b'\n') => {
+                crlf += 1;
+                i += 1;
+            }
+            b'\r' => cr += 1,
+            b'\n' => lf += 1,
+            _ => {}
+        }
+        i += 1;
+    }
+
+    if crlf > lf && crlf >= cr {
+        LineEnding::Crlf
+    } else if cr > lf && cr > crlf {
+        LineEnding::Cr
+    } else {
+        LineEnding::Lf
+    }
+}
+
+/// Rewrite every line break in `text` to the given style
+pub fn convert_line_endings(text: &str, ending: LineEnding) -> String {
+    let normalized = text.replace("\r\n", "\n").replace('\r', "\n");
+    match ending {
+        LineEnding::Lf => normalized,
+        LineEnding::Crlf => normalized.replace('\n', "\r\n"),
+        LineEnding::Cr => normalized.replace('\n', "\r"),
+    }
+}
+
+/// Narrow a byte range read from the middle of a file to whole characters
+///
+/// `start_in_text` is the offset of `bytes` from the end of the BOM, `at_eof` whether the
+/// range ends at the end of the file. Returns the `(start, end)` slice to decode.
+pub fn align_range(
+    bytes: &[u8],
+    encoding: TextEncoding,
+    start_in_text: u64,
+    at_eof: bool,
+) -> (usize, usize) {
+    let mut start = 0;
+    let mut end = bytes.len();
+
+    match encoding {
+        TextEncoding::Utf8 => {
+            if start_in_text > 0 {
+                // Skip continuation bytes of a character that began before the range
+                while start < end && start < 3 && bytes[start] & 0xC0 == 0x80 {
+                    start += 1;
+                }
+            }
+            if !at_eof {
+                for i in (start..end).rev().take(4) {
+                    let needed = match bytes[i] {
+                        b if b & 0x80 == 0 => 1,
+                        b if b & 0xE0 == 0xC0 => 2,
+                        b if b & 0xF0 == 0xE0 => 3,
+                        b if b & 0xF8 == 0xF0 => 4,
+                        _ => continue,
+                    };
+                    if end - i < needed {
+                        end = i;
+                    }
+                    break;
+                }
+            }
+        }
+        TextEncoding::Utf16le | TextEncoding::Utf16be => {
+            if start_in_text % 2 == 1 {
+                start += 1;
+            }
+            let unit_at = |i: usize| {
+                if encoding == TextEncoding::Utf16le {
+                    u16::from_le_bytes([bytes[i], bytes[i + 1]])
+                } else {
+                    u16::from_be_bytes([bytes[i], bytes[i + 1]])
+                }
+            };
+            // A low surrogate at the start belongs to a pair that began before the range
+            if start_in_text > 0 && end >= start + 2 && (0xDC00..0xE000).contains(&unit_at(start)) {
+                start += 2;
+            }
+            end = start + (end.saturating_sub(start)) / 2 * 2;
+            if !at_eof && end >= start + 2 && (0xD800..0xDC00).contains(&unit_at(end - 2)) {
+                end -= 2;
+            }
+        }
+        TextEncoding::Latin1 => {}
+    }
+
+    (start, end.max(start))
+}
+
+/// Decode bytes read at `offset` of a file for editing
+///
+/// `head` is the start of the file, used to detect the encoding and BOM even for ranges
+/// further in; a requested `encoding` skips detection. The BOM is kept out of the text and
+/// restored on save from the `bom` flag.
+pub fn decode_text_range(
+    path: &str,
+    buffer: &[u8],
+    head: &[u8],
+    offset: u64,
+    file_size: u64,
+    encoding: Option<TextEncoding>,
+    snapshot: FileSnapshot,
+) -> Result<TextFileContent, SFTPError> {
+    let (encoding, bom) = match encoding {
+        Some(encoding) => {
+            let bom_bytes = bom_for(encoding);
+            (
+                encoding,
+                !bom_bytes.is_empty() && head.starts_with(bom_bytes),
+            )
+        }
+        None => detect_encoding(head).ok_or_else(|| SFTPError::Other {
+            message: format!(
+                "File {} appears to be binary and cannot be edited as text",
+                path
+            ),
+        })?,
+    };
+
+    let bom_len = if bom {
+        bom_for(encoding).len() as u64
+    } else {
+        0
+    };
+    let skip = bom_len.saturating_sub(offset).min(buffer.len() as u64) as usize;
+    let range_end = offset + buffer.len() as u64;
+    let (start, end) = align_range(
+        &buffer[skip..],
+        encoding,
+        (offset + skip as u64).saturating_sub(bom_len),
+        range_end >= file_size,
+    );
+
+    let content = decode(&buffer[skip + start..skip + end], encoding)?;
+    let line_ending = detect_line_ending(&content);
+    let range_offset = offset + (skip + start) as u64;
+
+    Ok(TextFileContent {
+        content,
+        encoding,
+        bom,
+        line_ending,
+        offset: range_offset,
+        length: (end - start) as u64,
+        partial: range_offset > bom_len || range_offset + ((end - start) as u64) < file_size,
+        snapshot,
+    })
+}
+
+/// Encode edited text for saving
+pub fn encode_text(content: String, options: &WriteFileOptions) -> Result<Vec<u8>, SFTPError> {
+    let content = match options.line_ending {
+        Some(line_ending) => convert_line_endings(&content, line_ending),
+        None => content,
+    };
+    encode(
+        &content,
+        options.encoding.unwrap_or(TextEncoding::Utf8),
+        options.bom,
+    )
+}
+
+/// Fail with `FileModified` unless the file still matches the snapshot taken when it was read
+/// `current` is None when the file no longer exists
+pub fn check_unchanged(
+    path: &str,
+    expected: &FileSnapshot,
+    current: Option<&FileSnapshot>,
+) -> Result<(), SFTPError> {
+    if current == Some(expected) {
+        Ok(())
+    } else {
+        Err(SFTPError::FileModified {
+            path: path.to_string(),
+        })
+    }
+}
+
+/// Size and modification time from SFTP attributes
+pub fn snapshot_of(attrs: &russh_sftp::protocol::FileAttributes) -> FileSnapshot {
+    FileSnapshot {
+        size: attrs.size.unwrap_or(0),
+        modified: attrs
+            .mtime
+            .and_then(|t| chrono::DateTime::<Utc>::from_timestamp(t as i64, 0)),
+    }
+}
+
+/// Hidden temp file in the target's directory so the final rename stays on one filesystem
+pub fn temp_path_for(remote_path: &str) -> String {
+    let suffix = &Uuid::new_v4().to_string()[..8];
+    match remote_path.rsplit_once('/') {
+        Some((dir, name)) => format!("{}/.{}.save-{}", dir, name, suffix),
+        None => format!(".{}.save-{}", remote_path, suffix),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(text: &str, encoding: TextEncoding, bom: bool) {
+        let bytes = encode(text, encoding, bom).unwrap();
+        let (detected, has_bom) = detect_encoding(&bytes).unwrap();
+        assert_eq!((detected, has_bom), (encoding, bom));
+
+        let body = &bytes[if has_bom { bom_for(detected).len() } else { 0 }..];
+        let decoded = decode(body, detected).unwrap();
+        assert_eq!(decoded, text);
+        assert_eq!(encode(&decoded, detected, has_bom).unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_encoding_round_trip() {
+        round_trip("plain ascii\n", TextEncoding::Utf8, false);
+        round_trip("grüße 🚀\r\n", TextEncoding::Utf8, true);
+        round_trip("hello wörld\r\n", TextEncoding::Utf16le, true);
+        round_trip("hello wörld\n", TextEncoding::Utf16be, true);
+        round_trip(
+            "hello world, some more text\n",
+            TextEncoding::Utf16le,
+            false,
+        );
+        round_trip(
+            "hello world, some more text\n",
+            TextEncoding::Utf16be,
+            false,
+        );
+        round_trip("caf\u{e9} na\u{ef}ve\n", TextEncoding::Latin1, false);
+    }
+
+    #[test]
+    fn test_detect_binary() {
+        assert!(detect_encoding(&[
+            0x7F, b'E', b'L', b'F', 2, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0x3E, 0
+        ])
+        .is_none());
+    }
+
+    #[test]
+    fn test_latin1_rejects_wide_chars() {
+        assert!(encode("€", TextEncoding::Latin1, false).is_err());
+    }
+
+    #[test]
+    fn test_line_endings() {
+        assert_eq!(detect_line_ending("a\r\nb\r\nc\n"), LineEnding::Crlf);
+        assert_eq!(detect_line_ending("a\nb\n"), LineEnding::Lf);
+        assert_eq!(detect_line_ending("a\rb\r"), LineEnding::Cr);
+        assert_eq!(detect_line_ending("no breaks"), LineEnding::Lf);
+
+        assert_eq!(
+            convert_line_endings("a\nb\r\nc\rd", LineEnding::Crlf),
+            "a\r\nb\r\nc\r\nd"
+        );
+        assert_eq!(convert_line_endings("a\r\nb", LineEnding::Lf), "a\nb");
+    }
+
+    #[test]
+    fn test_align_range_utf8() {
+        let text = "aé€🚀b".as_bytes();
+        // Range starting inside "é" and ending inside "🚀"
+        let slice = &text[2..9];
+        let (start, end) = align_range(slice, TextEncoding::Utf8, 2, false);
+        assert_eq!(std::str::from_utf8(&slice[start..end]).unwrap(), "€");
+
+        let (start, end) = align_range(text, TextEncoding::Utf8, 0, true);
+        assert_eq!((start, end), (0, text.len()));
+    }
+
+    fn snapshot(size: u64, secs: i64) -> FileSnapshot {
+        FileSnapshot {
+            size,
+            modified: chrono::DateTime::<Utc>::from_timestamp(secs, 0),
+        }
+    }
+
+    /// Read a whole file as the editor does, then save it back with what the read returned
+    fn read_then_write(bytes: &[u8]) -> (TextFileContent, Vec<u8>) {
+        let head = &bytes[..bytes.len().min(DETECT_SAMPLE_SIZE)];
+        let read = decode_text_range(
+            "/f",
+            bytes,
+            head,
+            0,
+            bytes.len() as u64,
+            None,
+            snapshot(bytes.len() as u64, 1_700_000_000),
+        )
+        .unwrap();
+
+        let options = WriteFileOptions {
+            encoding: Some(read.encoding),
+            bom: read.bom,
+            line_ending: None,
+            expected: Some(read.snapshot.clone()),
+        };
+        let written = encode_text(read.content.clone(), &options).unwrap();
+        (read, written)
+    }
+
+    #[test]
+    fn test_read_write_round_trip() {
+        let samples: Vec<Vec<u8>> = vec![
+            b"plain\nascii\n".to_vec(),
+            encode("grüße 🚀\r\nzwei\r\n", TextEncoding::Utf8, true).unwrap(),
+            encode("hello wörld\r\n", TextEncoding::Utf16le, true).unwrap(),
+            encode(
+                "hello world, some more text\n",
+                TextEncoding::Utf16be,
+                false,
+            )
+            .unwrap(),
+            b"caf\xe9 na\xefve\r".to_vec(),
+        ];
+
+        for bytes in samples {
+            let (read, written) = read_then_write(&bytes);
+            assert!(!read.partial);
+            assert_eq!(written, bytes, "{:?}", read.encoding);
+        }
+
+        let (read, _) = read_then_write(b"caf\xe9\n");
+        assert_eq!(read.encoding, TextEncoding::Latin1);
+        assert_eq!(read.content, "caf\u{e9}\n");
+
+        // Saving an edit keeps the encoding and converts new lines to the requested style
+        let options = WriteFileOptions {
+            encoding: Some(TextEncoding::Utf16le),
+            bom: true,
+            line_ending: Some(LineEnding::Crlf),
+            expected: None,
+        };
+        assert_eq!(
+            encode_text("a\nb".to_string(), &options).unwrap(),
+            encode("a\r\nb", TextEncoding::Utf16le, true).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_read_range_round_trip() {
+        let text = "a🚀b ".repeat(10);
+        let bytes = encode(&text, TextEncoding::Utf16le, true).unwrap();
+        let head = &bytes[..16];
+
+        // Odd offset inside the surrogate pair, ending after a high surrogate
+        let read = decode_text_range(
+            "/f",
+            &bytes[5..15],
+            head,
+            5,
+            bytes.len() as u64,
+            None,
+            snapshot(bytes.len() as u64, 0),
+        )
+        .unwrap();
+        assert_eq!(read.encoding, TextEncoding::Utf16le);
+        assert!(read.bom && read.partial);
+        assert_eq!(read.content, "b a");
+        assert_eq!(read.offset, 8);
+        assert_eq!(read.length, 6);
+
+        let options = WriteFileOptions {
+            encoding: Some(read.encoding),
+            ..Default::default()
+        };
+        let written = encode_text(read.content, &options).unwrap();
+        let start = read.offset as usize;
+        assert_eq!(written, &bytes[start..start + read.length as usize]);
+    }
+
+    #[test]
+    fn test_save_conflict() {
+        let opened = snapshot(10, 1_700_000_000);
+        assert!(check_unchanged("/f", &opened, Some(&snapshot(10, 1_700_000_000))).is_ok());
+
+        for current in [
+            Some(snapshot(12, 1_700_000_000)),
+            Some(snapshot(10, 1_700_000_060)),
+            None,
+        ] {
+            match check_unchanged("/f", &opened, current.as_ref()) {
+                Err(SFTPError::FileModified { path }) => assert_eq!(path, "/f"),
+                other => panic!("expected FileModified, got {:?}", other),
+            }
+        }
+    }
+}
@@ -1,4 +1,9 @@
+pub mod archive;
 pub mod channel_stream;
+pub mod delta;
+pub mod editor;
+pub mod remote_exec;
+pub mod search;
 pub mod service;
 pub mod sync;
 pub mod transfer;
@@ -0,0 +1,178 @@
+use russh::client::{Handle, Msg};
+use russh::{Channel, ChannelMsg};
+
+use crate::models::sftp::error::SFTPError;
+use crate::services::sftp::service::SFTPClientHandler;
+
+/// Output chunk received from a remote command
+#[derive(Debug)]
+pub enum RemoteOutput {
+    /// Data written to stdout
+    Stdout(Vec<u8>),
+    /// Data written to stderr
+    Stderr(Vec<u8>),
+    /// Command exited with the given status
+    Exit(u32),
+}
+
+/// Collected output of a finished remote command
+#[derive(Debug, Default)]
+pub struct CommandOutput {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    /// Exit status (None if the server did not report one)
+    pub exit_status: Option<u32>,
+}
+
+impl CommandOutput {
+    /// Check if the command exited successfully
+    pub fn success(&self) -> bool {
+        self.exit_status == Some(0)
+    }
+
+    /// Stderr as lossy UTF-8, trimmed
+    pub fn stderr_text(&self) -> String {
+        String::from_utf8_lossy(&self.stderr).trim().to_string()
+    }
+}
+
+/// Command running on an exec channel next to an SFTP session
+pub struct RemoteCommand {
+    channel: Channel<Msg>,
+    finished: bool,
+}
+
+impl RemoteCommand {
+    /// Open a new session channel and execute the command
+    pub async fn spawn(
+        client: &Handle<SFTPClientHandler>,
+        command: &str,
+    ) -> Result<Self, SFTPError> {
+        let channel = client
+            .channel_open_session()
+            .await
+            .map_err(|e| SFTPError::Other {
+                message: format!("Failed to open exec channel: {}", e),
+            })?;
+
+        channel
+            .exec(true, command)
+            .await
+            .map_err(|e| SFTPError::Other {
+                message: format!("Failed to execute remote command: {}", e),
+            })?;
+
+        Ok(Self {
+            channel,
+            finished: false,
+        })
+    }
+
+    /// Write data to the command's stdin
+    pub async fn write_stdin(&self, data: &[u8]) -> Result<(), SFTPError> {
+        self.channel
+            .data(data)
+            .await
+            .map_err(|e| SFTPError::ConnectionLost {
+                message: format!("Failed to write to remote command: {}", e),
+            })
+    }
+
+    /// Signal end of input to the command
+    pub async fn close_stdin(&self) -> Result<(), SFTPError> {
+        self.channel
+            .eof()
+            .await
+            .map_err(|e| SFTPError::ConnectionLost {
+                message: format!("Failed to close remote command input: {}", e),
+            })
+    }
+
+    /// Wait for the next output chunk (None once the channel is closed)
+    pub async fn next_output(&mut self) -> Option<RemoteOutput> {
+        if self.finished {
+            return None;
+        }
+
+        loop {
+            match self.channel.wait().await {
+                Some(ChannelMsg::Data { data }) => {
+                    return Some(RemoteOutput::Stdout(data.to_vec()))
+                }
+                Some(ChannelMsg::ExtendedData { data, ext: 1 }) => {
+                    return Some(RemoteOutput::Stderr(data.to_vec()))
+                }
+                Some(ChannelMsg::ExitStatus { exit_status }) => {
+                    return Some(RemoteOutput::Exit(exit_status))
+                }
+                Some(ChannelMsg::Close) | None => {
+                    self.finished = true;
+                    return None;
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    /// Read all remaining output until the channel closes
+    pub async fn wait_with_output(mut self) -> CommandOutput {
+        let mut output = CommandOutput::default();
+
+        while let Some(chunk) = self.next_output().await {
+            match chunk {
+                RemoteOutput::Stdout(data) => output.stdout.extend_from_slice(&data),
+                RemoteOutput::Stderr(data) => output.stderr.extend_from_slice(&data),
+                RemoteOutput::Exit(status) => output.exit_status = Some(status),
+            }
+        }
+
+        let _ = self.channel.close().await;
+        output
+    }
+
+    /// Abort the command by closing its channel
+    pub async fn abort(self) {
+        let _ = self.channel.close().await;
+    }
+}
+
+/// Run a command to completion and collect its output
+pub async fn run_command(
+    client: &Handle<SFTPClientHandler>,
+    command: &str,
+) -> Result<CommandOutput, SFTPError> {
+    let command = RemoteCommand::spawn(client, command).await?;
+    command.close_stdin().await?;
+    Ok(command.wait_with_output().await)
+}
+
+/// Quote a string for safe use as a single POSIX shell word
+///
+/// Wraps the value in single quotes and rewrites embedded single quotes as `'\''`,
+/// so `$()`, backticks, globs and whitespace are passed through literally.
+pub fn shell_quote(value: &str) -> String {
+    if !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"-_./=:,+@%".contains(&b))
+    {
+        return value.to_string();
+    }
+
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("/var/log/app.log"), "/var/log/app.log");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("a b"), "'a b'");
+        assert_eq!(shell_quote("$(rm -rf ~)"), "'$(rm -rf ~)'");
+        assert_eq!(shell_quote("`id`"), "'`id`'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    }
+}
@@ -0,0 +1,670 @@
+use std::sync::Arc;
+
+use regex::{Regex, RegexBuilder};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+use crate::models::sftp::error::SFTPError;
+use crate::models::sftp::search::{SearchOptions, SearchResult};
+use crate::services::sftp::remote_exec::shell_quote;
+use crate::services::sftp::service::SFTPSessionData;
+
+/// Default cap on the number of results
+pub const DEFAULT_MAX_RESULTS: usize = 1000;
+
+/// Matched lines longer than this are truncated (minified files, logs)
+const MAX_LINE_LENGTH: usize = 1000;
+
+/// Files larger than this are skipped by the SFTP fallback
+const FALLBACK_MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Shell check that the tools used by the search exist
+const TOOLS_CHECK: &str = "command -v find >/dev/null 2>&1 && command -v grep >/dev/null 2>&1";
+
+/// Build a POSIX `find | grep` command for the search
+///
+/// Only POSIX options are used so it works with BSD and BusyBox userlands; `/dev/null` is
+/// passed as an extra file so grep always prefixes matches with the file name. Exits with
+/// 127 when `find` or `grep` is missing so the caller can fall back to SFTP.
+pub fn build_search_command(path: &str, query: &str, options: &SearchOptions) -> String {
+    let root = if path.starts_with('-') {
+        format!("./{}", path)
+    } else {
+        path.to_string()
+    };
+
+    let name_tests = |patterns: &[String]| -> String {
+        patterns
+            .iter()
+            .map(|p| format!("-name {}", shell_quote(p)))
+            .collect::<Vec<_>>()
+            .join(" -o ")
+    };
+
+    let mut grep = String::from("grep -n");
+    if options.ignore_case {
+        grep.push_str(" -i");
+    }
+    grep.push_str(if options.regex { " -E" } else { " -F" });
+    grep.push_str(&format!(" -e {} /dev/null {{}} +", shell_quote(query)));
+
+    let mut file_tests = String::from("-type f");
+    for pattern in &options.exclude_patterns {
+        file_tests.push_str(&format!(" ! -name {}", shell_quote(pattern)));
+    }
+    if !options.include_patterns.is_empty() {
+        file_tests.push_str(&format!(
+            " \\( {} \\)",
+            name_tests(&options.include_patterns)
+        ));
+    }
+
+    let find = if options.exclude_patterns.is_empty() {
+        format!(
+            "find {} {} -exec {} 2>/dev/null",
+            shell_quote(&root),
+            file_tests,
+            grep
+        )
+    } else {
+        format!(
+            "find {} \\( -type d \\( {} \\) -prune \\) -o \\( {} -exec {} \\) 2>/dev/null",
+            shell_quote(&root),
+            name_tests(&options.exclude_patterns),
+            file_tests,
+            grep
+        )
+    };
+
+    format!("{} || exit 127; {}", TOOLS_CHECK, find)
+}
+
+/// Parse a `file:line:content` line of grep output
+///
+/// File names may themselves contain colons, so the first `:<digits>:` is taken as the
+/// line number separator.
+pub fn parse_grep_line(line: &str) -> Option<SearchResult> {
+    for (index, _) in line.match_indices(':') {
+        let rest = &line[index + 1..];
+        let digits = rest.bytes().take_while(|b| b.is_ascii_digit()).count();
+        if digits == 0 || !rest[digits..].starts_with(':') {
+            continue;
+        }
+
+        let line_number = rest[..digits].parse().ok()?;
+        return Some(SearchResult {
+            file_path: line[..index].to_string(),
+            line_number,
+            content: truncate_line(&rest[digits + 1..]),
+        });
+    }
+
+    None
+}
+
+/// Match a file name against a shell glob (`*`, `?` and `[...]` classes)
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() {
+            match pattern[p] {
+                '*' => {
+                    backtrack = Some((p, n));
+                    p += 1;
+                    continue;
+                }
+                '?' => {
+                    p += 1;
+                    n += 1;
+                    continue;
+                }
+                '[' => {
+                    if let Some((matched, next)) = match_class(&pattern, p, name[n]) {
+                        if matched {
+                            p = next;
+                            n += 1;
+                            continue;
+                        }
+                    } else if name[n] == '[' {
+                        p += 1;
+                        n += 1;
+                        continue;
+                    }
+                }
+                c if c == name[n] => {
+                    p += 1;
+                    n += 1;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        match backtrack {
+            Some((star, matched)) => {
+                p = star + 1;
+                n = matched + 1;
+                backtrack = Some((star, matched + 1));
+            }
+            None => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Match `c` against the bracket expression starting at `pattern[start]`
+/// Returns whether it matched and the index after the closing bracket
+fn match_class(pattern: &[char], start: usize, c: char) -> Option<(bool, usize)> {
+    let mut i = start + 1;
+    let negate = matches!(pattern.get(i), Some('!') | Some('^'));
+    if negate {
+        i += 1;
+    }
+
+    let mut matched = false;
+    let mut first = true;
+    while i < pattern.len() {
+        if pattern[i] == ']' && !first {
+            return Some((matched != negate, i + 1));
+        }
+        if i + 2 < pattern.len() && pattern[i + 1] == '-' && pattern[i + 2] != ']' {
+            if pattern[i] <= c && c <= pattern[i + 2] {
+                matched = true;
+            }
+            i += 3;
+        } else {
+            if pattern[i] == c {
+                matched = true;
+            }
+            i += 1;
+        }
+        first = false;
+    }
+
+    None
+}
+
+fn truncate_line(line: &str) -> String {
+    let line = line.trim_end_matches('\r');
+    match line.char_indices().nth(MAX_LINE_LENGTH) {
+        Some((index, _)) => format!("{}…", &line[..index]),
+        None => line.to_string(),
+    }
+}
+
+/// Check that a regex means the same to `grep -E` and to the SFTP fallback
+///
+/// Remote grep implements POSIX ERE while the fallback uses the `regex` crate. Their
+/// common subset covers most searches, so syntax outside it (Perl escapes such as `\d`,
+/// `(?...)` groups, lazy quantifiers, backslashes and nested sets inside brackets) is
+/// rejected instead of silently matching differently depending on the host.
+pub fn check_portable_regex(pattern: &str) -> Result<(), String> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => {
+                if let Some(&c) = chars.get(i + 1) {
+                    if c.is_ascii_alphanumeric() {
+                        return Err(format!(
+                            "`\\{}` is not supported by POSIX grep, use a bracket expression such as [[:digit:]] instead",
+                            c
+                        ));
+                    }
+                }
+                i += 2;
+            }
+            '(' if chars.get(i + 1) == Some(&'?') => {
+                return Err("`(?...)` groups are not supported by POSIX grep".to_string());
+            }
+            '*' | '+' | '?' | '}' if matches!(chars.get(i + 1), Some('?') | Some('+')) => {
+                return Err(
+                    "Lazy and possessive quantifiers are not supported by POSIX grep".to_string(),
+                );
+            }
+            '[' => i = check_bracket(&chars, i)?,
+            _ => i += 1,
+        }
+    }
+
+    Ok(())
+}
+
+/// Check the bracket expression starting at `chars[start]`
+/// Returns the index after the closing bracket
+fn check_bracket(chars: &[char], start: usize) -> Result<usize, String> {
+    let mut i = start + 1;
+    if chars.get(i) == Some(&'^') {
+        i += 1;
+    }
+    // A leading `]` is a literal in both dialects
+    if chars.get(i) == Some(&']') {
+        i += 1;
+    }
+
+    while i < chars.len() {
+        match chars[i] {
+            ']' => return Ok(i + 1),
+            '\\' => {
+                return Err(
+                    "Backslashes inside [...] differ between POSIX grep and the SFTP fallback"
+                        .to_string(),
+                );
+            }
+            '[' if chars.get(i + 1) == Some(&':') => {
+                let close = chars[i + 2..]
+                    .windows(2)
+                    .position(|w| w == [':', ']'])
+                    .ok_or_else(|| "Unterminated character class in [...]".to_string())?;
+                i += close + 4;
+            }
+            '[' => {
+                return Err(
+                    "Nested [...] sets and [. .]/[= =] classes are not supported".to_string(),
+                );
+            }
+            c @ ('&' | '-' | '~') if chars.get(i + 1) == Some(&c) => {
+                return Err(format!("`{}{}` inside [...] is not supported", c, c));
+            }
+            _ => i += 1,
+        }
+    }
+
+    // Unterminated; left for the regex compiler to report
+    Ok(i)
+}
+
+/// Line matcher used by the SFTP fallback
+pub struct LineMatcher {
+    regex: Regex,
+}
+
+impl LineMatcher {
+    /// Compile the query according to the search options
+    ///
+    /// Regex queries must also pass [`check_portable_regex`], so a query that is accepted
+    /// here finds the same lines through remote grep.
+    pub fn new(query: &str, options: &SearchOptions) -> Result<Self, SFTPError> {
+        let pattern = if options.regex {
+            check_portable_regex(query).map_err(|e| SFTPError::Other {
+                message: format!("Invalid search pattern: {}", e),
+            })?;
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(options.ignore_case)
+            .build()
+            .map_err(|e| SFTPError::Other {
+                message: format!("Invalid search pattern: {}", e),
+            })?;
+
+        Ok(Self { regex })
+    }
+
+    pub fn is_match(&self, line: &str) -> bool {
+        self.regex.is_match(line)
+    }
+}
+
+/// Find the matching lines of one file's content
+/// Returns None for binary files (NUL in the first 8KB)
+fn scan_content(path: &str, content: &[u8], matcher: &LineMatcher) -> Option<Vec<SearchResult>> {
+    let probe = &content[..content.len().min(8192)];
+    if probe.contains(&0) {
+        return None;
+    }
+
+    let text = String::from_utf8_lossy(content);
+    Some(
+        text.lines()
+            .enumerate()
+            .filter(|(_, line)| matcher.is_match(line))
+            .map(|(index, line)| SearchResult {
+                file_path: path.to_string(),
+                line_number: index as u64 + 1,
+                content: truncate_line(line),
+            })
+            .collect(),
+    )
+}
+
+/// Outcome of a fallback search
+pub struct FallbackOutcome {
+    pub results: Vec<SearchResult>,
+    pub truncated: bool,
+    pub cancelled: bool,
+}
+
+/// Walk `root` over SFTP and scan text files line by line
+///
+/// Used when the server does not allow exec channels or lacks `find`/`grep`. Symlinks are
+/// not followed, binary files (NUL in the first 8KB) and files over 10MB are skipped.
+pub async fn search_over_sftp<F>(
+    session_data: Arc<Mutex<SFTPSessionData>>,
+    root: &str,
+    matcher: &LineMatcher,
+    options: &SearchOptions,
+    max_results: usize,
+    cancel_token: &CancellationToken,
+    on_results: &mut F,
+) -> Result<FallbackOutcome, SFTPError>
+where
+    F: FnMut(&[SearchResult]) + Send,
+{
+    let root_is_file = {
+        let data = session_data.lock().await;
+        let attrs = data
+            .sftp
+            .metadata(root)
+            .await
+            .map_err(|e| SFTPError::FileNotFound {
+                path: format!("{} ({})", root, e),
+            })?;
+        attrs.file_type() == russh_sftp::protocol::FileType::File
+    };
+
+    let mut results = Vec::new();
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+    if root_is_file {
+        files.push((root.to_string(), 0));
+    } else {
+        dirs.push(root.to_string());
+    }
+
+    loop {
+        if cancel_token.is_cancelled() {
+            return Ok(FallbackOutcome {
+                results,
+                truncated: false,
+                cancelled: true,
+            });
+        }
+
+        if let Some((path, size)) = files.pop() {
+            if size > FALLBACK_MAX_FILE_SIZE {
+                continue;
+            }
+
+            let content = {
+                let data = session_data.lock().await;
+                match data.sftp.read(path.as_str()).await {
+                    Ok(content) => content,
+                    Err(_) => continue,
+                }
+            };
+
+            let Some(matches) = scan_content(&path, &content, matcher) else {
+                continue;
+            };
+
+            let start = results.len();
+            for result in matches {
+                results.push(result);
+                if results.len() >= max_results {
+                    on_results(&results[start..]);
+                    return Ok(FallbackOutcome {
+                        results,
+                        truncated: true,
+                        cancelled: false,
+                    });
+                }
+            }
+            if results.len() > start {
+                on_results(&results[start..]);
+            }
+            continue;
+        }
+
+        let Some(dir) = dirs.pop() else {
+            break;
+        };
+
+        let entries = {
+            let data = session_data.lock().await;
+            match data.sftp.read_dir(dir.as_str()).await {
+                Ok(read_dir) => read_dir
+                    .map(|entry| {
+                        let attrs = entry.metadata();
+                        (
+                            entry.file_name(),
+                            attrs.file_type(),
+                            attrs.size.unwrap_or(0),
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+                Err(_) => continue,
+            }
+        };
+
+        for (name, file_type, size) in entries {
+            if name == "." || name == ".." {
+                continue;
+            }
+            if options
+                .exclude_patterns
+                .iter()
+                .any(|pattern| glob_match(pattern, &name))
+            {
+                continue;
+            }
+
+            let full_path = if dir.ends_with('/') {
+                format!("{}{}", dir, name)
+            } else {
+                format!("{}/{}", dir, name)
+            };
+
+            match file_type {
+                russh_sftp::protocol::FileType::Dir => dirs.push(full_path),
+                russh_sftp::protocol::FileType::File => {
+                    if options.include_patterns.is_empty()
+                        || options
+                            .include_patterns
+                            .iter()
+                            .any(|pattern| glob_match(pattern, &name))
+                    {
+                        files.push((full_path, size));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(FallbackOutcome {
+        results,
+        truncated: false,
+        cancelled: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_search_command_quotes_query() {
+        let options = SearchOptions::default();
+        let command = build_search_command("/srv/my app", "$(rm -rf ~) `id`", &options);
+        assert_eq!(
+            command.split_once("exit 127; ").unwrap().1,
+            "find '/srv/my app' -type f -exec grep -n -F -e '$(rm -rf ~) `id`' /dev/null {} + 2>/dev/null"
+        );
+    }
+
+    #[test]
+    fn test_build_search_command_with_filters() {
+        let options = SearchOptions {
+            regex: true,
+            ignore_case: true,
+            include_patterns: vec!["*.rs".to_string()],
+            exclude_patterns: vec!["target".to_string()],
+            max_results: None,
+        };
+        let command = build_search_command("/src", "fn [[:alpha:]_]+", &options);
+        assert_eq!(
+            command.split_once("exit 127; ").unwrap().1,
+            "find /src \\( -type d \\( -name target \\) -prune \\) -o \\( -type f ! -name target \\( -name '*.rs' \\) -exec grep -n -i -E -e 'fn [[:alpha:]_]+' /dev/null {} + \\) 2>/dev/null"
+        );
+    }
+
+    #[test]
+    fn test_parse_grep_line() {
+        let result = parse_grep_line("/etc/app:8080.conf:12:listen = 0.0.0.0:80").unwrap();
+        assert_eq!(result.file_path, "/etc/app:8080.conf");
+        assert_eq!(result.line_number, 12);
+        assert_eq!(result.content, "listen = 0.0.0.0:80");
+
+        assert!(parse_grep_line("Binary file /bin/ls matches").is_none());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.rs", "main.rs"));
+        assert!(!glob_match("*.rs", "main.rs.bak"));
+        assert!(glob_match("file?.txt", "file1.txt"));
+        assert!(glob_match("[a-c]*", "beta"));
+        assert!(!glob_match("[!a-c]*", "beta"));
+        assert!(glob_match("node_modules", "node_modules"));
+        assert!(glob_match("*", ""));
+    }
+
+    #[test]
+    fn test_line_matcher() {
+        let options = SearchOptions {
+            ignore_case: true,
+            ..Default::default()
+        };
+        let matcher = LineMatcher::new("a.b", &options).unwrap();
+        assert!(matcher.is_match("xx A.B yy"));
+        assert!(!matcher.is_match("axb"));
+    }
+
+    #[test]
+    fn test_check_portable_regex() {
+        for pattern in [
+            "fn [[:alpha:]_]+\\(",
+            "^(GET|POST) /api/v[0-9]{1,2}",
+            "[]a-z]+$",
+            "[^[:space:]]+=",
+            "a\\.b\\*",
+        ] {
+            assert!(check_portable_regex(pattern).is_ok(), "{}", pattern);
+        }
+
+        for pattern in [
+            "\\d+",
+            "\\bword\\b",
+            "(?i)abc",
+            "(?:a|b)",
+            "a+?",
+            "[\\d]",
+            "[a-z&&[^aeiou]]",
+        ] {
+            assert!(check_portable_regex(pattern).is_err(), "{}", pattern);
+        }
+
+        let options = SearchOptions {
+            regex: true,
+            ..Default::default()
+        };
+        assert!(LineMatcher::new("\\d+", &options).is_err());
+    }
+
+    #[test]
+    fn test_fallback_scan_content() {
+        let options = SearchOptions {
+            regex: true,
+            ..Default::default()
+        };
+        let matcher = LineMatcher::new("^port *= *[0-9]+", &options).unwrap();
+
+        let results = scan_content(
+            "/etc/app.conf",
+            b"host = a\r\nport = 22\r\n# port = 1\n",
+            &matcher,
+        )
+        .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].file_path, "/etc/app.conf");
+        assert_eq!(results[0].line_number, 2);
+        assert_eq!(results[0].content, "port = 22");
+
+        assert!(scan_content("/bin/app", b"port = 22\0\x01", &matcher).is_none());
+    }
+
+    /// The fallback must find the same lines as `grep -E` for portable patterns
+    #[cfg(unix)]
+    #[test]
+    fn test_fallback_agrees_with_grep() {
+        let lines = [
+            "GET /api/v1/users",
+            "post /api/v12/items",
+            "fn main() {",
+            "let x = a.b * 2;",
+            "[section]",
+            "tab\there",
+        ];
+        let patterns = [
+            "^(GET|POST) /api/v[0-9]{1,2}",
+            "fn [[:alpha:]_]+\\(",
+            "a\\.b \\*",
+            "^\\[[^]]+]$",
+            "[[:space:]]here",
+            "x|y",
+        ];
+
+        let path = std::env::temp_dir().join(format!("search-{}.txt", std::process::id()));
+        std::fs::write(&path, lines.join("\n")).unwrap();
+
+        for ignore_case in [false, true] {
+            for pattern in patterns {
+                let mut grep = std::process::Command::new("grep");
+                grep.arg("-n").arg("-E");
+                if ignore_case {
+                    grep.arg("-i");
+                }
+                let Ok(output) = grep.arg("-e").arg(pattern).arg(&path).output() else {
+                    // grep is not installed
+                    let _ = std::fs::remove_file(&path);
+                    return;
+                };
+                let expected: Vec<u64> = String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .filter_map(|line| line.split(':').next()?.parse().ok())
+                    .collect();
+
+                let options = SearchOptions {
+                    regex: true,
+                    ignore_case,
+                    ..Default::default()
+                };
+                let matcher = LineMatcher::new(pattern, &options).unwrap();
+                let actual: Vec<u64> = scan_content("", lines.join("\n").as_bytes(), &matcher)
+                    .unwrap()
+                    .iter()
+                    .map(|result| result.line_number)
+                    .collect();
+
+                assert_eq!(
+                    actual, expected,
+                    "{} (ignore case: {})",
+                    pattern, ignore_case
+                );
+            }
+        }
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
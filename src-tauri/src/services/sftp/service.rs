@@ -4,18 +4,34 @@ use std::sync::Arc;
 use tokio::sync::{Mutex, RwLock};
 
 use crate::core::proxy::create_proxy_stream;
-use crate::models::sftp::search::SearchResult;
+use crate::models::sftp::editor::{
+    FileChunk, FileSnapshot, ReadFileOptions, TextFileContent, WriteFileOptions,
+};
+use crate::models::sftp::search::{SearchOptions, SearchResult, SearchSummary};
 use crate::models::sftp::{error::SFTPError, file_entry::FileEntry, FileType};
 use crate::models::ssh::AuthData;
+use crate::services::ssh::certificate_authority::load_certificate;
+use crate::services::ssh::hardware_key::authenticate_with_key;
 use crate::services::ssh::{SSHKeyService, SSHService};
 
 use crate::services::sftp::channel_stream::ChannelStream;
+use crate::services::sftp::editor::{
+    check_unchanged, decode_text_range, encode_text, snapshot_of, temp_path_for,
+    DETECT_SAMPLE_SIZE, MAX_CHUNK_SIZE, MAX_EDIT_SIZE,
+};
+use crate::services::sftp::remote_exec::{run_command, shell_quote, RemoteCommand, RemoteOutput};
+use crate::services::sftp::search::{
+    build_search_command, parse_grep_line, search_over_sftp, LineMatcher, DEFAULT_MAX_RESULTS,
+};
 use anyhow::Result;
 use async_trait::async_trait;
 use chrono::Utc;
 use russh::client::Config;
 use russh_keys::key::PublicKey;
 use russh_sftp::client::SftpSession;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+use zeroize::Zeroizing;
 
 /// Simple handler for SFTP connections
 #[derive(Clone)]
@@ -45,6 +61,7 @@ pub struct SFTPService {
     ssh_service: Arc<SSHService>,
     ssh_key_service: Arc<Mutex<SSHKeyService>>,
     sessions: Arc<RwLock<HashMap<String, Arc<Mutex<SFTPSessionData>>>>>,
+    searches: Arc<RwLock<HashMap<String, CancellationToken>>>,
 }
 
 impl SFTPService {
@@ -54,6 +71,7 @@ impl SFTPService {
             ssh_service,
             ssh_key_service,
             sessions: Arc::new(RwLock::new(HashMap::new())),
+            searches: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
@@ -62,7 +80,7 @@ impl SFTPService {
         // Get profile from database
         let profile = self
             .ssh_service
-            .get_ssh_profile(&profile_id)
+            .get_ssh_profile_for_connect(&profile_id)
             .await
             .map_err(|e| SFTPError::Other {
                 message: format!("Failed to get SSH profile: {}", e),
@@ -124,8 +142,9 @@ impl SFTPService {
                 })?
         };
 
-        // Authenticate
-        let authenticated = match &profile.auth_data {
+        // Authenticate; inline credentials are wiped once this returns
+        let auth_data = Zeroizing::new(profile.auth_data);
+        let authenticated = match &*auth_data {
             AuthData::Password { password } => session
                 .authenticate_password(&profile.username, password)
                 .await
@@ -141,33 +160,14 @@ impl SFTPService {
                         message: format!("Failed to resolve SSH key: {}", e),
                     })?;
 
-                let key = if Path::new(&resolved_key.private_key).exists() {
-                    russh_keys::load_secret_key(
-                        &resolved_key.private_key,
-                        resolved_key.passphrase.as_deref(),
-                    )
-                    .map_err(|e| SFTPError::SessionFailed {
-                        message: format!("Failed to load SSH key: {}", e),
-                    })?
-                } else {
-                    russh_keys::decode_secret_key(
-                        &resolved_key.private_key,
-                        resolved_key.passphrase.as_deref(),
-                    )
-                    .map_err(|e| SFTPError::SessionFailed {
-                        message: format!("Failed to parse SSH key: {}", e),
-                    })?
-                };
-
-                session
-                    .authenticate_publickey(&profile.username, Arc::new(key))
+                authenticate_with_key(&mut session, &profile.username, &resolved_key)
                     .await
                     .map_err(|e| SFTPError::SessionFailed {
                         message: format!("SSH key authentication failed: {}", e),
                     })?
             }
             AuthData::Certificate {
-                certificate: _,
+                certificate,
                 private_key,
                 ..
             } => {
@@ -185,8 +185,12 @@ impl SFTPService {
                     })?
                 };
 
+                let cert = load_certificate(certificate).map_err(|e| SFTPError::SessionFailed {
+                    message: e.to_string(),
+                })?;
+
                 session
-                    .authenticate_publickey(&profile.username, Arc::new(key))
+                    .authenticate_openssh_cert(&profile.username, Arc::new(key), cert)
                     .await
                     .map_err(|e| SFTPError::SessionFailed {
                         message: format!("Certificate authentication failed: {}", e),
@@ -265,6 +269,89 @@ impl SFTPService {
             })
     }
 
+    /// Get SSH client handle of a session for running remote commands
+    pub async fn client_handle(
+        &self,
+        session_id: &str,
+    ) -> Result<Arc<russh::client::Handle<SFTPClientHandler>>, SFTPError> {
+        let session_data = self.get_session(session_id).await?;
+        let mut data = session_data.lock().await;
+        data.last_used = Utc::now();
+        Ok(data.client.clone())
+    }
+
+    /// Atomically replace `target_path` with `temp_path`
+    /// Permissions (and ownership where the server allows it) of the existing target are
+    /// copied to the temp file first. SFTP v3 rename refuses to overwrite on most servers,
+    /// so this falls back to `mv -f` over an exec channel, and finally to a backup swap.
+    pub async fn replace_file(
+        &self,
+        session_id: &str,
+        temp_path: &str,
+        target_path: &str,
+    ) -> Result<(), SFTPError> {
+        let session_data = self.get_session(session_id).await?;
+        let mut data = session_data.lock().await;
+        data.last_used = Utc::now();
+
+        let target_attrs = data.sftp.metadata(target_path).await.ok();
+
+        if let Some(attrs) = &target_attrs {
+            let mut mode_attrs = russh_sftp::protocol::FileAttributes::empty();
+            mode_attrs.permissions = attrs.permissions.map(|p| p & 0o7777);
+            let _ = data.sftp.set_metadata(temp_path, mode_attrs).await;
+
+            if attrs.uid.is_some() && attrs.gid.is_some() {
+                let mut owner_attrs = russh_sftp::protocol::FileAttributes::empty();
+                owner_attrs.uid = attrs.uid;
+                owner_attrs.gid = attrs.gid;
+                // Only root can chown to another user; keep the temp owner otherwise
+                let _ = data.sftp.set_metadata(temp_path, owner_attrs).await;
+            }
+        }
+
+        if data.sftp.rename(temp_path, target_path).await.is_ok() {
+            return Ok(());
+        }
+
+        if target_attrs.is_none() {
+            return Err(SFTPError::Other {
+                message: format!("Failed to rename {} to {}", temp_path, target_path),
+            });
+        }
+
+        // Target exists: rename(2) through the shell replaces it atomically
+        let command = format!(
+            "mv -f -- {} {}",
+            shell_quote(temp_path),
+            shell_quote(target_path)
+        );
+        if let Ok(output) = run_command(&data.client, &command).await {
+            if output.success() {
+                return Ok(());
+            }
+        }
+
+        // No exec channel: swap through a backup name and restore it on failure
+        let backup_path = format!("{}.{}.bak", target_path, &Uuid::new_v4().to_string()[..8]);
+        data.sftp
+            .rename(target_path, &backup_path)
+            .await
+            .map_err(|e| SFTPError::Other {
+                message: format!("Failed to move {} aside: {}", target_path, e),
+            })?;
+
+        if let Err(e) = data.sftp.rename(temp_path, target_path).await {
+            let _ = data.sftp.rename(&backup_path, target_path).await;
+            return Err(SFTPError::Other {
+                message: format!("Failed to rename {} to {}: {}", temp_path, target_path, e),
+            });
+        }
+
+        let _ = data.sftp.remove_file(&backup_path).await;
+        Ok(())
+    }
+
     /// List directory contents
     pub async fn list_directory(
         &self,
@@ -629,67 +716,248 @@ impl SFTPService {
     }
 
     /// Read file content as text
-    pub async fn read_file(&self, session_id: String, path: String) -> Result<String, SFTPError> {
+    /// The encoding (UTF-8, UTF-16 or Latin-1) and line ending are detected so the file can be
+    /// saved back unchanged. Whole files are limited to 10MB; larger files can be read in
+    /// ranges with `options.offset`/`options.length`.
+    pub async fn read_file(
+        &self,
+        session_id: String,
+        path: String,
+        options: ReadFileOptions,
+    ) -> Result<TextFileContent, SFTPError> {
         let session_data = self.get_session(&session_id).await?;
         let mut data = session_data.lock().await;
         data.last_used = Utc::now();
 
-        // Check if file exists and is a regular file
-        let attrs = data.sftp.metadata(&path).await.map_err(|e| {
-            if e.to_string().contains("not found") || e.to_string().contains("No such file") {
-                SFTPError::FileNotFound { path: path.clone() }
-            } else {
-                SFTPError::Other {
-                    message: format!("Failed to get metadata for {}: {}", path, e),
-                }
+        let attrs = Self::regular_file_metadata(&data.sftp, &path).await?;
+        let file_size = attrs.size.unwrap_or(0);
+
+        let offset = options.offset.unwrap_or(0).min(file_size);
+        let length = match options.length {
+            Some(length) => length.min(MAX_EDIT_SIZE).min(file_size - offset),
+            None if offset == 0 && file_size > MAX_EDIT_SIZE => {
+                return Err(SFTPError::Other {
+                    message: format!(
+                        "File too large to edit ({} bytes). Maximum size is 10MB; read it in ranges instead",
+                        file_size
+                    ),
+                });
             }
+            None => (file_size - offset).min(MAX_EDIT_SIZE),
+        };
+
+        let mut remote_file = data.sftp.open(&path).await.map_err(|e| SFTPError::Other {
+            message: format!("Failed to open file {}: {}", path, e),
         })?;
 
-        if attrs.file_type() != russh_sftp::protocol::FileType::File {
-            return Err(SFTPError::Other {
-                message: format!("Path is not a regular file: {}", path),
-            });
+        use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+        // Encoding is detected from the start of the file even for ranges further in
+        let mut head = Vec::new();
+        if offset > 0 {
+            (&mut remote_file)
+                .take(DETECT_SAMPLE_SIZE as u64)
+                .read_to_end(&mut head)
+                .await
+                .map_err(|e| SFTPError::Other {
+                    message: format!("Failed to read file {}: {}", path, e),
+                })?;
+            remote_file
+                .seek(std::io::SeekFrom::Start(offset))
+                .await
+                .map_err(|e| SFTPError::Other {
+                    message: format!("Failed to seek in file {}: {}", path, e),
+                })?;
         }
 
-        // Check file size (limit to 10MB for text files)
-        let file_size = attrs.size.unwrap_or(0);
-        if file_size > 10 * 1024 * 1024 {
-            return Err(SFTPError::Other {
-                message: format!(
-                    "File too large to edit ({} bytes). Maximum size is 10MB",
-                    file_size
-                ),
-            });
+        let mut buffer = Vec::with_capacity(length as usize);
+        (&mut remote_file)
+            .take(length)
+            .read_to_end(&mut buffer)
+            .await
+            .map_err(|e| SFTPError::Other {
+                message: format!("Failed to read file {}: {}", path, e),
+            })?;
+        drop(data);
+
+        if offset == 0 {
+            head = buffer[..buffer.len().min(DETECT_SAMPLE_SIZE)].to_vec();
         }
 
-        // Open and read file
+        decode_text_range(
+            &path,
+            &buffer,
+            &head,
+            offset,
+            file_size,
+            options.encoding,
+            snapshot_of(&attrs),
+        )
+    }
+
+    /// Read a raw byte range of a file (binary safe)
+    /// Chunks are limited to 4MB
+    pub async fn read_file_range(
+        &self,
+        session_id: String,
+        path: String,
+        offset: u64,
+        length: u64,
+    ) -> Result<FileChunk, SFTPError> {
+        let session_data = self.get_session(&session_id).await?;
+        let mut data = session_data.lock().await;
+        data.last_used = Utc::now();
+
+        let attrs = Self::regular_file_metadata(&data.sftp, &path).await?;
+        let file_size = attrs.size.unwrap_or(0);
+        let offset = offset.min(file_size);
+        let length = length.min(MAX_CHUNK_SIZE).min(file_size - offset);
+
         let mut remote_file = data.sftp.open(&path).await.map_err(|e| SFTPError::Other {
             message: format!("Failed to open file {}: {}", path, e),
         })?;
 
-        use tokio::io::AsyncReadExt;
-        let mut buffer = Vec::with_capacity(file_size as usize);
+        use tokio::io::{AsyncReadExt, AsyncSeekExt};
         remote_file
+            .seek(std::io::SeekFrom::Start(offset))
+            .await
+            .map_err(|e| SFTPError::Other {
+                message: format!("Failed to seek in file {}: {}", path, e),
+            })?;
+
+        let mut buffer = Vec::with_capacity(length as usize);
+        (&mut remote_file)
+            .take(length)
             .read_to_end(&mut buffer)
             .await
             .map_err(|e| SFTPError::Other {
                 message: format!("Failed to read file {}: {}", path, e),
             })?;
 
-        // Try to decode as UTF-8
-        String::from_utf8(buffer).map_err(|e| SFTPError::Other {
-            message: format!("File {} is not valid UTF-8: {}", path, e),
+        use base64::Engine as _;
+        Ok(FileChunk {
+            offset,
+            data: base64::engine::general_purpose::STANDARD.encode(&buffer),
+            length: buffer.len() as u64,
+            total_size: file_size,
+            eof: offset + buffer.len() as u64 >= file_size,
         })
     }
 
-    /// Search for files containing text using grep
-    pub async fn search(
+    /// Get metadata of a path that must be a regular file
+    async fn regular_file_metadata(
+        sftp: &SftpSession,
+        path: &str,
+    ) -> Result<russh_sftp::protocol::FileAttributes, SFTPError> {
+        let attrs = sftp.metadata(path).await.map_err(|e| {
+            if e.to_string().contains("not found") || e.to_string().contains("No such file") {
+                SFTPError::FileNotFound {
+                    path: path.to_string(),
+                }
+            } else {
+                SFTPError::Other {
+                    message: format!("Failed to get metadata for {}: {}", path, e),
+                }
+            }
+        })?;
+
+        if attrs.file_type() != russh_sftp::protocol::FileType::File {
+            return Err(SFTPError::Other {
+                message: format!("Path is not a regular file: {}", path),
+            });
+        }
+
+        Ok(attrs)
+    }
+
+    /// Search file contents under `path`
+    /// Runs a POSIX `find`/`grep` pipeline over an exec channel and streams matches to
+    /// `on_results` as they arrive; falls back to walking and scanning files over SFTP when
+    /// exec is unavailable. A `search_id` makes the search cancellable via `cancel_search`.
+    pub async fn search<F>(
         &self,
         session_id: String,
         path: String,
         query: String,
-    ) -> Result<Vec<SearchResult>, SFTPError> {
+        options: SearchOptions,
+        search_id: Option<String>,
+        mut on_results: F,
+    ) -> Result<SearchSummary, SFTPError>
+    where
+        F: FnMut(&[SearchResult]) + Send,
+    {
+        if query.is_empty() {
+            return Err(SFTPError::Other {
+                message: "Search query cannot be empty".to_string(),
+            });
+        }
+
+        // Validated up front so a pattern is rejected the same way whichever path runs
+        let matcher = LineMatcher::new(&query, &options)?;
+
         let session_data = self.get_session(&session_id).await?;
+        let max_results = options.max_results.unwrap_or(DEFAULT_MAX_RESULTS).max(1);
+
+        let cancel_token = CancellationToken::new();
+        if let Some(id) = &search_id {
+            let mut searches = self.searches.write().await;
+            searches.insert(id.clone(), cancel_token.clone());
+        }
+
+        let result = self
+            .search_with_exec(
+                &session_data,
+                &path,
+                &query,
+                &options,
+                &cancel_token,
+                &mut on_results,
+            )
+            .await;
+
+        let summary = match result {
+            Some(summary) => Ok(summary),
+            None => search_over_sftp(
+                session_data,
+                &path,
+                &matcher,
+                &options,
+                max_results,
+                &cancel_token,
+                &mut on_results,
+            )
+            .await
+            .map(|outcome| SearchSummary {
+                results: outcome.results,
+                truncated: outcome.truncated,
+                cancelled: outcome.cancelled,
+                fallback: true,
+            }),
+        };
+
+        if let Some(id) = &search_id {
+            let mut searches = self.searches.write().await;
+            searches.remove(id);
+        }
+
+        summary
+    }
+
+    /// Run the search through remote `find`/`grep`
+    /// Returns None when exec is not available so the caller can fall back to SFTP
+    async fn search_with_exec<F>(
+        &self,
+        session_data: &Arc<Mutex<SFTPSessionData>>,
+        path: &str,
+        query: &str,
+        options: &SearchOptions,
+        cancel_token: &CancellationToken,
+        on_results: &mut F,
+    ) -> Option<SearchSummary>
+    where
+        F: FnMut(&[SearchResult]) + Send,
+    {
+        let max_results = options.max_results.unwrap_or(DEFAULT_MAX_RESULTS).max(1);
 
         // Clone client handle to avoid holding lock during search
         let client = {
@@ -698,105 +966,167 @@ impl SFTPService {
             data.client.clone()
         };
 
-        // Open a new channel for the search command
-        let mut channel = client
-            .channel_open_session()
-            .await
-            .map_err(|e| SFTPError::Other {
-                message: format!("Failed to open channel for search: {}", e),
-            })?;
-
-        // Escape query to prevent command injection
-        // This is a basic escaping, ideally we'd use a robust shell escaping library
-        let escaped_query = query.replace("\"", "\\\"");
-        let command = format!("grep -rInH \"{}\" \"{}\"", escaped_query, path);
-
-        channel
-            .exec(true, command)
-            .await
-            .map_err(|e| SFTPError::Other {
-                message: format!("Failed to execute search command: {}", e),
-            })?;
-
-        // Read stdout
-        let output = {
-            let mut stdout = channel.make_reader();
-            use tokio::io::AsyncReadExt;
-            let mut buffer = Vec::new();
-            stdout
-                .read_to_end(&mut buffer)
-                .await
-                .map_err(|e| SFTPError::Other {
-                    message: format!("Failed to read search output: {}", e),
-                })?;
-            String::from_utf8_lossy(&buffer).to_string()
-        };
+        let command = build_search_command(path, query, options);
+        let mut remote = RemoteCommand::spawn(&client, &command).await.ok()?;
+        let _ = remote.close_stdin().await;
 
         let mut results = Vec::new();
-        for line in output.lines() {
-            // Grep output format: filename:line:content
-            // Note: filename might contain colons, so we should look for the first two colons carefully
-            // But standard grep output puts filename first.
-            // Split by colon, limit 3 parts? No, content might have colons.
-
-            let parts: Vec<&str> = line.splitn(3, ':').collect();
-            if parts.len() >= 3 {
-                let file_path = parts[0].to_string();
-                if let Ok(line_number) = parts[1].parse::<u64>() {
-                    // content is rest
-                    let content = parts[2].to_string();
-                    results.push(SearchResult {
-                        file_path,
-                        line_number,
-                        content,
-                    });
+        let mut pending = Vec::new();
+        let mut exit_status = None;
+        let mut truncated = false;
+        let mut cancelled = false;
+
+        'read: loop {
+            tokio::select! {
+                _ = cancel_token.cancelled() => {
+                    cancelled = true;
+                    break 'read;
+                }
+                output = remote.next_output() => match output {
+                    Some(RemoteOutput::Stdout(data)) => {
+                        pending.extend_from_slice(&data);
+                        let start = results.len();
+
+                        while let Some(newline) = pending.iter().position(|b| *b == b'\n') {
+                            let line: Vec<u8> = pending.drain(..=newline).collect();
+                            let line = String::from_utf8_lossy(&line[..line.len() - 1]);
+                            if let Some(result) = parse_grep_line(&line) {
+                                results.push(result);
+                                if results.len() >= max_results {
+                                    truncated = true;
+                                    break;
+                                }
+                            }
+                        }
+
+                        if results.len() > start {
+                            on_results(&results[start..]);
+                        }
+                        if truncated {
+                            break 'read;
+                        }
+                    }
+                    Some(RemoteOutput::Exit(status)) => exit_status = Some(status),
+                    Some(RemoteOutput::Stderr(_)) => {}
+                    None => break 'read,
                 }
             }
         }
 
-        Ok(results)
+        if cancelled || truncated {
+            remote.abort().await;
+        } else if let Some(result) = parse_grep_line(&String::from_utf8_lossy(&pending)) {
+            on_results(std::slice::from_ref(&result));
+            results.push(result);
+        }
+
+        // 126/127: find or grep missing or not executable on this host
+        if matches!(exit_status, Some(126) | Some(127)) && results.is_empty() {
+            return None;
+        }
+
+        Some(SearchSummary {
+            results,
+            truncated,
+            cancelled,
+            fallback: false,
+        })
+    }
+
+    /// Cancel a running search
+    pub async fn cancel_search(&self, search_id: &str) -> Result<(), SFTPError> {
+        let searches = self.searches.read().await;
+        match searches.get(search_id) {
+            Some(token) => {
+                token.cancel();
+                Ok(())
+            }
+            None => Err(SFTPError::Other {
+                message: format!("Search not found: {}", search_id),
+            }),
+        }
     }
 
     /// Write file content as text
+    /// The content is encoded as requested and written to a temp file next to the target,
+    /// which then atomically replaces it keeping permissions and ownership. If
+    /// `options.expected` is set and the remote file changed since it was read, nothing is
+    /// overwritten and `FileModified` is returned.
     pub async fn write_file(
         &self,
         session_id: String,
         path: String,
         content: String,
-    ) -> Result<(), SFTPError> {
+        options: WriteFileOptions,
+    ) -> Result<FileSnapshot, SFTPError> {
+        let bytes = encode_text(content, &options)?;
+
         let session_data = self.get_session(&session_id).await?;
-        let mut data = session_data.lock().await;
-        data.last_used = Utc::now();
+        let temp_path = temp_path_for(&path);
 
-        use russh_sftp::protocol::OpenFlags;
-        use tokio::io::AsyncWriteExt;
+        {
+            let mut data = session_data.lock().await;
+            data.last_used = Utc::now();
 
-        // Open file for writing (create if not exists, truncate if exists)
-        let mut remote_file = data
-            .sftp
-            .open_with_flags(
-                &path,
-                OpenFlags::CREATE | OpenFlags::TRUNCATE | OpenFlags::WRITE,
-            )
-            .await
-            .map_err(|e| SFTPError::Other {
-                message: format!("Failed to open file for writing {}: {}", path, e),
-            })?;
+            use russh_sftp::protocol::OpenFlags;
+            use tokio::io::AsyncWriteExt;
 
-        // Write content
-        remote_file
-            .write_all(content.as_bytes())
+            let mut remote_file = data
+                .sftp
+                .open_with_flags(
+                    &temp_path,
+                    OpenFlags::CREATE | OpenFlags::TRUNCATE | OpenFlags::WRITE,
+                )
+                .await
+                .map_err(|e| SFTPError::Other {
+                    message: format!("Failed to open file for writing {}: {}", temp_path, e),
+                })?;
+
+            let written = async {
+                remote_file.write_all(&bytes).await?;
+                remote_file.flush().await?;
+                remote_file.shutdown().await
+            }
+            .await;
+
+            if let Err(e) = written {
+                let _ = data.sftp.remove_file(&temp_path).await;
+                return Err(SFTPError::Other {
+                    message: format!("Failed to write file {}: {}", path, e),
+                });
+            }
+
+            // Checked after the upload so the window before the rename stays short
+            if let Some(expected) = &options.expected {
+                let current = data
+                    .sftp
+                    .metadata(&path)
+                    .await
+                    .ok()
+                    .map(|a| snapshot_of(&a));
+                if let Err(e) = check_unchanged(&path, expected, current.as_ref()) {
+                    let _ = data.sftp.remove_file(&temp_path).await;
+                    return Err(e);
+                }
+            }
+        }
+
+        if let Err(e) = self.replace_file(&session_id, &temp_path, &path).await {
+            let data = session_data.lock().await;
+            let _ = data.sftp.remove_file(&temp_path).await;
+            return Err(e);
+        }
+
+        let data = session_data.lock().await;
+        let attrs = data
+            .sftp
+            .metadata(&path)
             .await
             .map_err(|e| SFTPError::Other {
-                message: format!("Failed to write file {}: {}", path, e),
+                message: format!("Failed to get metadata for {}: {}", path, e),
             })?;
 
-        // Flush to ensure data is written
-        remote_file.flush().await.map_err(|e| SFTPError::Other {
-            message: format!("Failed to flush file {}: {}", path, e),
-        })?;
-
-        Ok(())
+        Ok(snapshot_of(&attrs))
     }
 
     /// Upload local file to remote (binary safe)
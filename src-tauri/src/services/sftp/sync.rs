@@ -7,6 +7,7 @@ use crate::models::sftp::{
     sync::{DiffEntry, DiffType, SyncDirection, SyncOperation},
 };
 use crate::models::sync::SyncProgressEvent;
+use crate::services::sftp::delta::{ChecksumAlgorithm, DeltaUploader};
 use crate::services::sftp::service::SFTPService;
 
 use anyhow::Result;
@@ -15,9 +16,13 @@ use tauri::Emitter;
 use tokio::fs;
 use tokio::sync::RwLock;
 
+/// Default minimum file size for delta transfer (8MB)
+const DEFAULT_DELTA_MIN_SIZE: u64 = 8 * 1024 * 1024;
+
 /// Sync Service for comparing and synchronizing directories
 pub struct SyncService {
     sftp_service: Arc<SFTPService>,
+    delta_uploader: DeltaUploader,
     app_handle: Arc<RwLock<Option<tauri::AppHandle>>>,
 }
 
@@ -25,6 +30,7 @@ impl SyncService {
     /// Create new sync service
     pub fn new(sftp_service: Arc<SFTPService>) -> Self {
         Self {
+            delta_uploader: DeltaUploader::new(sftp_service.clone()),
             sftp_service,
             app_handle: Arc::new(RwLock::new(None)),
         }
@@ -53,22 +59,65 @@ impl SyncService {
         clock_skew_seconds: Option<i64>,
     ) -> Result<Vec<DiffEntry>, anyhow::Error> {
         // Get local file tree
-        let local_files = Self::build_local_tree(&local_path).await?;
+        let local_files =
+            Self::relative_tree(&local_path, Self::build_local_tree(&local_path).await?);
 
         // Get remote file tree
-        let remote_files = self
-            .build_remote_tree(session_id.clone(), &remote_path)
-            .await?;
+        let remote_files = Self::relative_tree(
+            &remote_path,
+            self.build_remote_tree(session_id.clone(), &remote_path)
+                .await?,
+        );
+
+        Ok(Self::diff_trees(
+            &local_files,
+            &remote_files,
+            clock_skew_seconds,
+        ))
+    }
+
+    /// Compare directories on two remote sessions (dual-pane compare)
+    /// The source side is reported as `local_entry` / `OnlyLocal`, the target side as
+    /// `remote_entry` / `OnlyRemote`.
+    pub async fn compare_remote_directories(
+        &self,
+        source_session_id: String,
+        source_path: String,
+        target_session_id: String,
+        target_path: String,
+        clock_skew_seconds: Option<i64>,
+    ) -> Result<Vec<DiffEntry>, anyhow::Error> {
+        let source_files = Self::relative_tree(
+            &source_path,
+            self.build_remote_tree(source_session_id, &source_path)
+                .await?,
+        );
+        let target_files = Self::relative_tree(
+            &target_path,
+            self.build_remote_tree(target_session_id, &target_path)
+                .await?,
+        );
+
+        Ok(Self::diff_trees(
+            &source_files,
+            &target_files,
+            clock_skew_seconds,
+        ))
+    }
 
-        // Compare and generate diffs
+    /// Diff two file trees keyed by path relative to their roots
+    fn diff_trees(
+        local_files: &HashMap<String, FileEntry>,
+        remote_files: &HashMap<String, FileEntry>,
+        clock_skew_seconds: Option<i64>,
+    ) -> Vec<DiffEntry> {
         let mut diffs = Vec::new();
 
         // Find files only in local
-        for (path, local_entry) in &local_files {
-            let relative_path = Self::relative_path(&local_path, path);
+        for (path, local_entry) in local_files {
             if !remote_files.contains_key(path) {
                 diffs.push(DiffEntry {
-                    path: relative_path,
+                    path: path.clone(),
                     diff_type: DiffType::OnlyLocal,
                     local_entry: Some(local_entry.clone()),
                     remote_entry: None,
@@ -77,11 +126,10 @@ impl SyncService {
         }
 
         // Find files only in remote
-        for (path, remote_entry) in &remote_files {
-            let relative_path = Self::relative_path(&remote_path, path);
+        for (path, remote_entry) in remote_files {
             if !local_files.contains_key(path) {
                 diffs.push(DiffEntry {
-                    path: relative_path,
+                    path: path.clone(),
                     diff_type: DiffType::OnlyRemote,
                     local_entry: None,
                     remote_entry: Some(remote_entry.clone()),
@@ -90,14 +138,12 @@ impl SyncService {
         }
 
         // Find differences in files that exist in both
-        for (path, local_entry) in &local_files {
+        for (path, local_entry) in local_files {
             if let Some(remote_entry) = remote_files.get(path) {
-                let relative_path = Self::relative_path(&local_path, path);
-
                 // Check size
                 if local_entry.size != remote_entry.size {
                     diffs.push(DiffEntry {
-                        path: relative_path.clone(),
+                        path: path.clone(),
                         diff_type: DiffType::SizeDiffers,
                         local_entry: Some(local_entry.clone()),
                         remote_entry: Some(remote_entry.clone()),
@@ -113,7 +159,7 @@ impl SyncService {
                 if time_diff > skew_tolerance {
                     // Allow configured difference for clock skew
                     diffs.push(DiffEntry {
-                        path: relative_path.clone(),
+                        path: path.clone(),
                         diff_type: DiffType::TimeDiffers,
                         local_entry: Some(local_entry.clone()),
                         remote_entry: Some(remote_entry.clone()),
@@ -124,7 +170,7 @@ impl SyncService {
                 // Check permissions
                 if local_entry.permissions != remote_entry.permissions {
                     diffs.push(DiffEntry {
-                        path: relative_path.clone(),
+                        path: path.clone(),
                         diff_type: DiffType::PermissionsDiffer,
                         local_entry: Some(local_entry.clone()),
                         remote_entry: Some(remote_entry.clone()),
@@ -134,7 +180,7 @@ impl SyncService {
 
                 // Files appear identical
                 diffs.push(DiffEntry {
-                    path: relative_path,
+                    path: path.clone(),
                     diff_type: DiffType::Identical,
                     local_entry: Some(local_entry.clone()),
                     remote_entry: Some(remote_entry.clone()),
@@ -142,7 +188,14 @@ impl SyncService {
             }
         }
 
-        Ok(diffs)
+        diffs
+    }
+
+    /// Re-key a file tree by path relative to `base`
+    fn relative_tree(base: &str, tree: HashMap<String, FileEntry>) -> HashMap<String, FileEntry> {
+        tree.into_iter()
+            .map(|(path, entry)| (Self::relative_path(base, &path), entry))
+            .collect()
     }
 
     /// Synchronize directories according to sync operation
@@ -362,6 +415,45 @@ impl SyncService {
             ))
             .await;
 
+            // Modified large files: send only changed blocks, fall back to a full upload
+            if Self::should_use_delta(diff, &operation) {
+                let verify = operation.verify_checksum.then(|| {
+                    ChecksumAlgorithm::from_option(operation.checksum_algorithm.as_deref())
+                });
+
+                match self
+                    .delta_uploader
+                    .upload(
+                        &session_id,
+                        &local_path.to_string_lossy(),
+                        &remote_path,
+                        verify,
+                    )
+                    .await
+                {
+                    Ok(Some(stats)) => {
+                        eprintln!(
+                            "[SFTP Sync] Delta uploaded: {} ({} bytes sent, {} bytes reused)",
+                            diff.path, stats.literal_bytes, stats.matched_bytes
+                        );
+                        processed += 1;
+                        continue;
+                    }
+                    Ok(None) => {
+                        eprintln!(
+                            "[SFTP Sync] No python3 on remote, uploading {} in full",
+                            diff.path
+                        );
+                    }
+                    Err(e) => {
+                        eprintln!(
+                            "[SFTP Sync] Delta transfer failed for {}, uploading in full: {}",
+                            diff.path, e
+                        );
+                    }
+                }
+            }
+
             // Upload file
             if local_path.exists() && local_path.is_file() {
                 match self
@@ -646,6 +738,30 @@ impl SyncService {
         Ok(())
     }
 
+    /// Check if a diff qualifies for delta transfer
+    fn should_use_delta(diff: &DiffEntry, operation: &SyncOperation) -> bool {
+        if !operation.delta_transfer {
+            return false;
+        }
+        if !matches!(
+            diff.diff_type,
+            DiffType::SizeDiffers | DiffType::TimeDiffers
+        ) {
+            return false;
+        }
+
+        let min_size = operation.delta_min_size.unwrap_or(DEFAULT_DELTA_MIN_SIZE);
+        match (&diff.local_entry, &diff.remote_entry) {
+            (Some(local), Some(remote)) => {
+                local.file_type == crate::models::sftp::file_entry::FileType::File
+                    && remote.file_type == crate::models::sftp::file_entry::FileType::File
+                    && local.size.unwrap_or(0) >= min_size
+                    && remote.size.unwrap_or(0) > 0
+            }
+            _ => false,
+        }
+    }
+
     /// Check if path matches any exclude patterns
     fn should_exclude(&self, path: &str, patterns: &[String]) -> bool {
         for pattern in patterns {
@@ -687,3 +803,120 @@ impl SyncService {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::sftp::file_entry::FileType;
+    use chrono::{TimeZone, Utc};
+
+    fn tree(root: &str, files: &[(&str, u64, i64)]) -> HashMap<String, FileEntry> {
+        files
+            .iter()
+            .map(|(name, size, modified)| {
+                let path = format!("{}/{}", root, name);
+                let entry = FileEntry {
+                    name: name.rsplit('/').next().unwrap().to_string(),
+                    path: path.clone(),
+                    file_type: FileType::File,
+                    size: Some(*size),
+                    permissions: 0o644,
+                    modified: Utc.timestamp_opt(*modified, 0).unwrap(),
+                    accessed: None,
+                    symlink_target: None,
+                    uid: None,
+                    gid: None,
+                };
+                (path, entry)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_compare_two_remote_trees() {
+        // Same layout under different roots on two servers
+        let source = SyncService::relative_tree(
+            "/srv/app",
+            tree(
+                "/srv/app",
+                &[
+                    ("same.txt", 10, 1000),
+                    ("bigger.txt", 20, 1000),
+                    ("only-source.txt", 1, 1000),
+                    ("conf/app.toml", 5, 1000),
+                ],
+            ),
+        );
+        let target = SyncService::relative_tree(
+            "/home/deploy/app",
+            tree(
+                "/home/deploy/app",
+                &[
+                    ("same.txt", 10, 1001),
+                    ("bigger.txt", 30, 1000),
+                    ("only-target.txt", 1, 1000),
+                    ("conf/app.toml", 5, 4000),
+                ],
+            ),
+        );
+
+        let mut diffs = SyncService::diff_trees(&source, &target, None);
+        diffs.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let summary: Vec<(&str, DiffType)> = diffs
+            .iter()
+            .map(|diff| (diff.path.as_str(), diff.diff_type.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("bigger.txt", DiffType::SizeDiffers),
+                ("conf/app.toml", DiffType::TimeDiffers),
+                ("only-source.txt", DiffType::OnlyLocal),
+                ("only-target.txt", DiffType::OnlyRemote),
+                ("same.txt", DiffType::Identical),
+            ]
+        );
+
+        // Source entries keep their full paths so files can be copied across
+        let only_source = &diffs[2];
+        assert_eq!(
+            only_source.local_entry.as_ref().unwrap().path,
+            "/srv/app/only-source.txt"
+        );
+        assert!(only_source.remote_entry.is_none());
+    }
+
+    #[test]
+    fn test_default_operation_uses_delta_for_large_files() {
+        // What the sync dialog sends when delta transfer is left alone
+        let operation: SyncOperation = serde_json::from_value(serde_json::json!({
+            "direction": "localToRemote",
+            "localPath": "/home/me/app",
+            "remotePath": "/srv/app",
+            "deleteExtraFiles": false,
+            "preserveSymlinks": true,
+            "preservePermissions": true,
+            "maxFileSize": null,
+            "excludePatterns": [],
+        }))
+        .unwrap();
+        assert!(operation.delta_transfer);
+
+        let diff = |size: u64| {
+            let local = tree("/home/me/app", &[("data.bin", size, 2000)]);
+            let remote = tree("/srv/app", &[("data.bin", size + 1, 1000)]);
+            DiffEntry {
+                path: "data.bin".to_string(),
+                diff_type: DiffType::SizeDiffers,
+                local_entry: local.into_values().next(),
+                remote_entry: remote.into_values().next(),
+            }
+        };
+        assert!(SyncService::should_use_delta(
+            &diff(DEFAULT_DELTA_MIN_SIZE),
+            &operation
+        ));
+        assert!(!SyncService::should_use_delta(&diff(1024), &operation));
+    }
+}
@@ -24,6 +24,8 @@ struct TransferMetadata {
     local_path: String,
     remote_path: String,
     direction: TransferDirection,
+    /// Source session of remote-to-remote transfers (`local_path` is the source path)
+    source_session_id: Option<String>,
 }
 
 /// Transfer Manager for handling file transfers with progress tracking
@@ -154,6 +156,16 @@ impl TransferManager {
                                 )
                                 .await
                         }
+                        TransferDirection::Remote => {
+                            manager
+                                .execute_remote_copy(
+                                    metadata,
+                                    id.clone(),
+                                    app_handle_clone.clone(),
+                                    cancel_token,
+                                )
+                                .await
+                        }
                     };
 
                     // Handle result
@@ -246,6 +258,7 @@ impl TransferManager {
             direction: TransferDirection::Upload,
             local_path: local_path.clone(),
             remote_path: remote_path.clone(),
+            source_session_id: None,
             total_bytes,
             transferred_bytes: 0,
             speed_bytes_per_sec: None,
@@ -270,6 +283,7 @@ impl TransferManager {
             local_path: local_path.clone(),
             remote_path: remote_path.clone(),
             direction: TransferDirection::Upload,
+            source_session_id: None,
         };
 
         {
@@ -552,6 +566,7 @@ impl TransferManager {
             direction: TransferDirection::Download,
             local_path: local_path.clone(),
             remote_path: remote_path.clone(),
+            source_session_id: None,
             total_bytes,
             transferred_bytes: 0,
             speed_bytes_per_sec: None,
@@ -576,6 +591,7 @@ impl TransferManager {
             local_path: local_path.clone(),
             remote_path: remote_path.clone(),
             direction: TransferDirection::Download,
+            source_session_id: None,
         };
 
         {
@@ -820,6 +836,289 @@ impl TransferManager {
         Ok(())
     }
 
+    /// Copy file between two remote sessions (Queued)
+    /// Data is streamed through the app without staging it on local disk.
+    pub async fn copy_remote_file(
+        &self,
+        source_session_id: String,
+        source_path: String,
+        target_session_id: String,
+        target_path: String,
+        app_handle: tauri::AppHandle,
+    ) -> Result<String, SFTPError> {
+        let transfer_id = Uuid::new_v4().to_string();
+
+        let sftp_service = self
+            .sftp_service
+            .upgrade()
+            .ok_or_else(|| SFTPError::Other {
+                message: "SFTP service is no longer available".to_string(),
+            })?;
+        let entry = sftp_service
+            .stat(source_session_id.clone(), source_path.clone())
+            .await?;
+        // Fail early if the target session is gone
+        sftp_service.get_session(&target_session_id).await?;
+
+        let progress = TransferProgress {
+            transfer_id: transfer_id.clone(),
+            status: TransferStatus::Queued,
+            direction: TransferDirection::Remote,
+            local_path: source_path.clone(),
+            remote_path: target_path.clone(),
+            source_session_id: Some(source_session_id.clone()),
+            total_bytes: entry.size.unwrap_or(0),
+            transferred_bytes: 0,
+            speed_bytes_per_sec: None,
+            eta_seconds: None,
+            error: None,
+            started_at: Utc::now(),
+            completed_at: None,
+            priority: 0,
+            retry_count: 0,
+            max_retries: 5,
+            next_retry_at: None,
+        };
+
+        {
+            let mut transfers = self.active_transfers.write().await;
+            transfers.insert(transfer_id.clone(), progress);
+        }
+
+        let metadata_entry = TransferMetadata {
+            session_id: target_session_id,
+            local_path: source_path,
+            remote_path: target_path,
+            direction: TransferDirection::Remote,
+            source_session_id: Some(source_session_id),
+        };
+
+        {
+            let mut metadata_map = self.transfer_metadata.write().await;
+            metadata_map.insert(transfer_id.clone(), metadata_entry);
+        }
+
+        // Trigger queue processing
+        self.process_queue(app_handle).await;
+
+        Ok(transfer_id)
+    }
+
+    /// Execute remote-to-remote transfer
+    async fn execute_remote_copy(
+        &self,
+        metadata: TransferMetadata,
+        transfer_id: String,
+        app_handle_clone: tauri::AppHandle,
+        cancel_token: CancellationToken,
+    ) -> Result<(), SFTPError> {
+        let TransferMetadata {
+            session_id: target_session_id,
+            local_path: source_path,
+            remote_path: target_path,
+            source_session_id,
+            ..
+        } = metadata;
+        let source_session_id = source_session_id.ok_or_else(|| SFTPError::Other {
+            message: "Remote transfer has no source session".to_string(),
+        })?;
+
+        // Update status to in progress and get resume position
+        let resume_from: u64 = {
+            let mut transfers = self.active_transfers.write().await;
+            if let Some(progress) = transfers.get_mut(&transfer_id) {
+                let resume_pos = progress.transferred_bytes;
+                progress.status = TransferStatus::InProgress;
+                resume_pos
+            } else {
+                return Err(SFTPError::TransferNotFound { transfer_id });
+            }
+        };
+
+        let sftp_service = self
+            .sftp_service
+            .upgrade()
+            .ok_or_else(|| SFTPError::Other {
+                message: "SFTP service is no longer available".to_string(),
+            })?;
+
+        // Sessions are locked one at a time and only while opening files, so copying
+        // within the same session cannot deadlock
+        let (mut source_file, total) = {
+            let source_session = sftp_service.get_session(&source_session_id).await?;
+            let data = source_session.lock().await;
+            let total = data
+                .sftp
+                .metadata(&source_path)
+                .await
+                .map_err(|e| SFTPError::Other {
+                    message: format!("Failed to get source file metadata: {}", e),
+                })?
+                .size
+                .unwrap_or(0);
+            let file = data
+                .sftp
+                .open(&source_path)
+                .await
+                .map_err(|e| SFTPError::Other {
+                    message: format!("Failed to open source file: {}", e),
+                })?;
+            (file, total)
+        };
+
+        use russh_sftp::protocol::OpenFlags;
+        let (mut target_file, actual_resume_from) = {
+            let target_session = sftp_service.get_session(&target_session_id).await?;
+            let data = target_session.lock().await;
+
+            // Append when the partial target matches the resume position (or is shorter)
+            let target_size = if resume_from > 0 {
+                data.sftp
+                    .metadata(&target_path)
+                    .await
+                    .ok()
+                    .and_then(|meta| meta.size)
+                    .filter(|size| *size <= resume_from)
+            } else {
+                None
+            };
+
+            match target_size {
+                Some(size) => {
+                    let file = data
+                        .sftp
+                        .open_with_flags(&target_path, OpenFlags::WRITE | OpenFlags::APPEND)
+                        .await
+                        .map_err(|e| SFTPError::Other {
+                            message: format!("Failed to open target file for append: {}", e),
+                        })?;
+                    (file, size)
+                }
+                None => {
+                    let file = data
+                        .sftp
+                        .open_with_flags(
+                            &target_path,
+                            OpenFlags::CREATE | OpenFlags::TRUNCATE | OpenFlags::WRITE,
+                        )
+                        .await
+                        .map_err(|e| SFTPError::Other {
+                            message: format!("Failed to open target file: {}", e),
+                        })?;
+                    (file, 0)
+                }
+            }
+        };
+
+        if actual_resume_from != resume_from {
+            let mut transfers = self.active_transfers.write().await;
+            if let Some(progress) = transfers.get_mut(&transfer_id) {
+                progress.transferred_bytes = actual_resume_from;
+            }
+        }
+
+        if actual_resume_from > 0 {
+            use tokio::io::AsyncSeekExt;
+            source_file
+                .seek(std::io::SeekFrom::Start(actual_resume_from))
+                .await
+                .map_err(|e| SFTPError::Other {
+                    message: format!("Failed to seek source file: {}", e),
+                })?;
+        }
+
+        // Copy file in chunks with progress updates
+        let chunk_size = 64 * 1024; // 64KB chunks
+        let mut transferred = actual_resume_from;
+        let mut buffer = vec![0u8; chunk_size];
+
+        loop {
+            tokio::select! {
+                _ = cancel_token.cancelled() => {
+                    // Transfer was paused or cancelled
+                    {
+                        let transfers = self.active_transfers.read().await;
+                        if let Some(progress) = transfers.get(&transfer_id) {
+                            if progress.status == TransferStatus::Paused {
+                                return Err(SFTPError::Other {
+                                    message: "Transfer paused".to_string(),
+                                });
+                            }
+                        }
+                    }
+                    return Err(SFTPError::Other {
+                        message: "Transfer cancelled".to_string(),
+                    });
+                }
+                result = source_file.read(&mut buffer) => {
+                    let bytes_read = result.map_err(|e| SFTPError::Other {
+                        message: format!("Failed to read from source file: {}", e),
+                    })?;
+
+                    if bytes_read == 0 {
+                        break; // EOF
+                    }
+
+                    // Write chunk to target file
+                    target_file.write_all(&buffer[..bytes_read]).await
+                        .map_err(|e| SFTPError::Other {
+                            message: format!("Failed to write to target file: {}", e),
+                        })?;
+
+                    transferred += bytes_read as u64;
+
+                    // Update progress
+                    {
+                        let mut transfers = self.active_transfers.write().await;
+                        if let Some(progress) = transfers.get_mut(&transfer_id) {
+                            // Check if status was changed to paused/cancelled
+                            if progress.status == TransferStatus::Paused || progress.status == TransferStatus::Cancelled {
+                                return Err(SFTPError::Other {
+                                    message: format!("Transfer {:?}", progress.status),
+                                });
+                            }
+                            progress.transferred_bytes = transferred;
+                        }
+                    }
+
+                    // Emit progress update
+                    let _ = app_handle_clone.emit(
+                        "sftp_transfer_progress",
+                        &serde_json::json!({
+                            "transferId": transfer_id,
+                            "transferredBytes": transferred,
+                            "totalBytes": total,
+                        }),
+                    );
+                }
+            }
+        }
+
+        // Flush and close target file
+        target_file.flush().await.map_err(|e| SFTPError::Other {
+            message: format!("Failed to flush target file: {}", e),
+        })?;
+
+        {
+            let mut transfers = self.active_transfers.write().await;
+            if let Some(progress) = transfers.get_mut(&transfer_id) {
+                progress.total_bytes = transferred;
+                progress.transferred_bytes = transferred;
+                progress.status = TransferStatus::Completed;
+                progress.completed_at = Some(Utc::now());
+            }
+        }
+
+        let _ = app_handle_clone.emit(
+            "sftp_transfer_complete",
+            &serde_json::json!({
+                "transferId": transfer_id,
+            }),
+        );
+
+        Ok(())
+    }
+
     /// Get transfer progress
     pub async fn get_progress(&self, transfer_id: String) -> Result<TransferProgress, SFTPError> {
         let transfers = self.active_transfers.read().await;
@@ -981,6 +1280,10 @@ impl TransferManager {
                 )
                 .await
             }
+            TransferDirection::Remote => {
+                self.execute_remote_copy(metadata, transfer_id, app_handle, cancel_token)
+                    .await
+            }
         }
     }
 
@@ -0,0 +1,504 @@
+//! Local SSH certificate authority issuing short-lived user certificates
+
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
+use rand::rngs::OsRng;
+use ssh_key::{
+    certificate::{Builder, CertType},
+    Certificate, HashAlg, LineEnding, PrivateKey, PublicKey,
+};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use crate::database::{
+    error::{DatabaseError, DatabaseResult},
+    service::DatabaseService,
+};
+use crate::models::base::BaseModel;
+use crate::models::ssh::profile::{AuthMethod, CertificateValidity, KeyType};
+use crate::models::ssh::{
+    AuthData, CreateCertificateAuthorityRequest, ExpiringCertificate, IssueCertificateRequest,
+    KeyFormat, SSHCertificateAuthority, SSHProfile, UpdateSSHProfileRequest,
+};
+use crate::services::ssh::key_format;
+
+/// Certificate lifetime used when neither the request nor the CA sets one
+pub const DEFAULT_CERTIFICATE_TTL_MINUTES: u32 = 8 * 60;
+/// Longest lifetime a certificate may be issued for
+const MAX_CERTIFICATE_TTL_MINUTES: u32 = 30 * 24 * 60;
+/// Certificates are backdated by this much to tolerate clock skew with servers
+const CLOCK_SKEW_ALLOWANCE: Duration = Duration::from_secs(5 * 60);
+/// Extensions OpenSSH grants to user certificates by default
+const USER_CERTIFICATE_EXTENSIONS: [&str; 5] = [
+    "permit-X11-forwarding",
+    "permit-agent-forwarding",
+    "permit-port-forwarding",
+    "permit-pty",
+    "permit-user-rc",
+];
+
+/// Sign a user certificate for `subject` with the CA key
+pub fn sign_user_certificate(
+    ca_key: &PrivateKey,
+    subject: &PublicKey,
+    key_id: &str,
+    principals: &[String],
+    serial: u64,
+    ttl: Duration,
+) -> DatabaseResult<Certificate> {
+    if principals.is_empty() {
+        return Err(DatabaseError::ValidationError(
+            "A certificate needs at least one principal".to_string(),
+        ));
+    }
+
+    let now = Utc::now().timestamp().max(0) as u64;
+    let valid_after = now.saturating_sub(CLOCK_SKEW_ALLOWANCE.as_secs());
+    let valid_before = now + ttl.as_secs();
+
+    let mut builder = Builder::new_with_random_nonce(
+        &mut OsRng,
+        subject.key_data().clone(),
+        valid_after,
+        valid_before,
+    )
+    .map_err(signing_error)?;
+    builder
+        .serial(serial)
+        .and_then(|b| b.cert_type(CertType::User))
+        .and_then(|b| b.key_id(key_id))
+        .map_err(signing_error)?;
+    for principal in principals {
+        builder
+            .valid_principal(principal.as_str())
+            .map_err(signing_error)?;
+    }
+    for extension in USER_CERTIFICATE_EXTENSIONS {
+        builder.extension(extension, "").map_err(signing_error)?;
+    }
+
+    builder.sign(ca_key).map_err(signing_error)
+}
+
+/// Load a certificate from a file path, inline OpenSSH certificate text or its bare base64 blob
+pub fn load_certificate(certificate: &str) -> DatabaseResult<Certificate> {
+    let content = read_path_or_inline(certificate, "certificate")?;
+    let content = content.trim();
+
+    // A bare blob has no key type in front of it
+    if content.split_whitespace().nth(1).is_none() {
+        let bytes = general_purpose::STANDARD
+            .decode(content)
+            .map_err(invalid_certificate)?;
+        return Certificate::from_bytes(&bytes).map_err(invalid_certificate);
+    }
+    Certificate::from_openssh(content).map_err(invalid_certificate)
+}
+
+fn invalid_certificate(e: impl std::fmt::Display) -> DatabaseError {
+    DatabaseError::ValidationError(format!("Invalid SSH certificate: {}", e))
+}
+
+/// Read a value stored either as a file path or inline
+pub(crate) fn read_path_or_inline(value: &str, what: &str) -> DatabaseResult<String> {
+    if !Path::new(value).exists() {
+        return Ok(value.to_string());
+    }
+    std::fs::read_to_string(value)
+        .map_err(|e| DatabaseError::ValidationError(format!("Failed to read {}: {}", what, e)))
+}
+
+/// Public key of a profile's certificate key, given as a file path or inline key text
+/// Only the public half is signed, so OpenSSH and PPK keys need no passphrase
+fn certificate_subject(private_key: &str) -> DatabaseResult<PublicKey> {
+    let content = read_path_or_inline(private_key, "private key")?;
+    key_format::read_public_key(&content, None).ok_or_else(|| {
+        DatabaseError::ValidationError(
+            "The profile's certificate key cannot be read; \
+             passphrase-protected PEM keys must be converted to OpenSSH format"
+                .to_string(),
+        )
+    })
+}
+
+/// Renew the profile's certificate when a local CA issued it and it is close to expiry
+///
+/// Returns the updated profile when a new certificate was issued. Profiles whose CA
+/// has been deleted keep their certificate until it expires.
+pub async fn renew_expiring_certificate(
+    db_service: &DatabaseService,
+    profile_id: &str,
+) -> DatabaseResult<Option<SSHProfile>> {
+    let profile = db_service.get_ssh_profile(profile_id).await?;
+    let AuthData::Certificate {
+        validity_period: Some(validity),
+        ..
+    } = &profile.auth_data
+    else {
+        return Ok(None);
+    };
+    let Some(ca_id) = validity.ca_id.clone() else {
+        return Ok(None);
+    };
+    if !validity.needs_renewal() {
+        return Ok(None);
+    }
+
+    let ca = match db_service.get_certificate_authority(&ca_id).await {
+        Ok(ca) => ca,
+        Err(DatabaseError::NotFound(_)) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let principals = (!validity.principals.is_empty()).then(|| validity.principals.clone());
+    let ttl_minutes = certificate_lifetime_minutes(validity);
+    issue(db_service, ca, profile, principals, ttl_minutes)
+        .await
+        .map(Some)
+}
+
+/// Sign a certificate for the profile and switch it to certificate authentication
+async fn issue(
+    db_service: &DatabaseService,
+    mut ca: SSHCertificateAuthority,
+    profile: SSHProfile,
+    principals: Option<Vec<String>>,
+    ttl_minutes: Option<u32>,
+) -> DatabaseResult<SSHProfile> {
+    let principals = principals
+        .filter(|p| !p.is_empty())
+        .or_else(|| (!ca.default_principals.is_empty()).then(|| ca.default_principals.clone()))
+        .unwrap_or_else(|| vec![profile.username.clone()]);
+
+    let ttl_minutes = ttl_minutes.unwrap_or(ca.default_ttl_minutes);
+    if ttl_minutes == 0 || ttl_minutes > MAX_CERTIFICATE_TTL_MINUTES {
+        return Err(DatabaseError::ValidationError(format!(
+            "Certificate lifetime must be between 1 and {} minutes",
+            MAX_CERTIFICATE_TTL_MINUTES
+        )));
+    }
+
+    // Keep the profile's certificate key across renewals; generate one on first issue
+    let (private_key, key_type, subject) = match &profile.auth_data {
+        AuthData::Certificate {
+            private_key,
+            key_type,
+            ..
+        } if !private_key.trim().is_empty() => (
+            private_key.clone(),
+            key_type.clone(),
+            certificate_subject(private_key)?,
+        ),
+        _ => {
+            let key = key_format::generate_private_key(&KeyType::Ed25519, None, &profile.username)?;
+            (
+                key_format::encode_private_key(&key, KeyFormat::OpenSsh, None)?,
+                KeyType::Ed25519,
+                key.public_key().clone(),
+            )
+        }
+    };
+
+    let ca_key = key_format::parse_private_key(&ca.private_key, None)?;
+    let serial = ca.next_serial;
+    let key_id = format!("{}@{}", profile.username, profile.name);
+    let certificate = sign_user_certificate(
+        &ca_key,
+        &subject,
+        &key_id,
+        &principals,
+        serial,
+        Duration::from_secs(ttl_minutes as u64 * 60),
+    )?;
+
+    ca.next_serial += 1;
+    ca.base.touch();
+    db_service.save_certificate_authority(&ca).await?;
+
+    let validity = CertificateValidity {
+        valid_from: unix_to_rfc3339(certificate.valid_after()),
+        valid_to: unix_to_rfc3339(certificate.valid_before()),
+        serial: Some(serial.to_string()),
+        ca_fingerprint: Some(ca.fingerprint.clone()),
+        ca_id: Some(ca.base.id.clone()),
+        principals,
+    };
+    let certificate = certificate.to_openssh().map_err(signing_error)?;
+
+    db_service
+        .update_ssh_profile(
+            &profile.base.id,
+            UpdateSSHProfileRequest {
+                auth_method: Some(AuthMethod::Certificate),
+                auth_data: Some(AuthData::Certificate {
+                    certificate,
+                    private_key,
+                    key_type,
+                    validity_period: Some(validity),
+                }),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    db_service.get_ssh_profile(&profile.base.id).await
+}
+
+/// Lifetime of an issued certificate, without the clock skew allowance
+fn certificate_lifetime_minutes(validity: &CertificateValidity) -> Option<u32> {
+    let valid_from = DateTime::parse_from_rfc3339(&validity.valid_from).ok()?;
+    let valid_to = DateTime::parse_from_rfc3339(&validity.valid_to).ok()?;
+    let seconds = (valid_to - valid_from).num_seconds() - CLOCK_SKEW_ALLOWANCE.as_secs() as i64;
+    u32::try_from(seconds / 60).ok().filter(|m| *m > 0)
+}
+
+fn unix_to_rfc3339(timestamp: u64) -> String {
+    DateTime::<Utc>::from_timestamp(timestamp as i64, 0)
+        .unwrap_or_default()
+        .to_rfc3339()
+}
+
+fn signing_error(e: ssh_key::Error) -> DatabaseError {
+    DatabaseError::Internal(anyhow::anyhow!("Failed to sign certificate: {}", e))
+}
+
+/// Service managing local certificate authorities and the certificates they issue
+pub struct CertificateAuthorityService {
+    database_service: Arc<Mutex<DatabaseService>>,
+}
+
+impl CertificateAuthorityService {
+    /// Create new CertificateAuthorityService instance
+    pub fn new(database_service: Arc<Mutex<DatabaseService>>) -> Self {
+        Self { database_service }
+    }
+
+    /// Create a certificate authority from a generated or imported signing key
+    pub async fn create_certificate_authority(
+        &self,
+        request: CreateCertificateAuthorityRequest,
+    ) -> DatabaseResult<SSHCertificateAuthority> {
+        if request.name.trim().is_empty() {
+            return Err(DatabaseError::ValidationError(
+                "Certificate authority name is required".to_string(),
+            ));
+        }
+
+        let ca_key = match request
+            .private_key
+            .as_deref()
+            .filter(|k| !k.trim().is_empty())
+        {
+            Some(private_key) => {
+                key_format::parse_private_key(private_key, request.passphrase.as_deref())?
+            }
+            None => key_format::generate_private_key(
+                request.key_type.as_ref().unwrap_or(&KeyType::Ed25519),
+                request.bits,
+                &request.name,
+            )?,
+        };
+        let key_type = key_format::key_type(&ca_key.algorithm()).ok_or_else(|| {
+            DatabaseError::ValidationError(format!(
+                "Unsupported CA key algorithm: {}",
+                ca_key.algorithm()
+            ))
+        })?;
+
+        let public_key = ca_key.public_key().to_openssh().map_err(signing_error)?;
+        let fingerprint = ca_key.public_key().fingerprint(HashAlg::Sha256).to_string();
+        let private_key = ca_key
+            .to_openssh(LineEnding::LF)
+            .map_err(signing_error)?
+            .to_string();
+
+        let db_service = self.database_service.lock().await;
+        let ca = SSHCertificateAuthority {
+            base: BaseModel::new(db_service.get_device_id().to_string()),
+            name: request.name.trim().to_string(),
+            key_type,
+            private_key,
+            public_key,
+            fingerprint,
+            default_principals: request.default_principals,
+            default_ttl_minutes: request
+                .default_ttl_minutes
+                .unwrap_or(DEFAULT_CERTIFICATE_TTL_MINUTES),
+            next_serial: 1,
+            description: request.description,
+        };
+        db_service.save_certificate_authority(&ca).await?;
+
+        Ok(ca)
+    }
+
+    /// Get all certificate authorities
+    pub async fn get_certificate_authorities(
+        &self,
+    ) -> DatabaseResult<Vec<SSHCertificateAuthority>> {
+        let db_service = self.database_service.lock().await;
+        db_service.get_certificate_authorities().await
+    }
+
+    /// Delete a certificate authority
+    /// Certificates it issued stay valid until they expire but are no longer renewed
+    pub async fn delete_certificate_authority(&self, id: &str) -> DatabaseResult<()> {
+        let db_service = self.database_service.lock().await;
+        db_service.delete_certificate_authority(id).await
+    }
+
+    /// Sign a certificate for a profile and switch the profile to certificate authentication
+    pub async fn issue_certificate(
+        &self,
+        request: IssueCertificateRequest,
+    ) -> DatabaseResult<SSHProfile> {
+        let db_service = self.database_service.lock().await;
+        let ca = db_service.get_certificate_authority(&request.ca_id).await?;
+        let profile = db_service.get_ssh_profile(&request.profile_id).await?;
+
+        issue(
+            &db_service,
+            ca,
+            profile,
+            request.principals,
+            request.ttl_minutes,
+        )
+        .await
+    }
+
+    /// Profiles whose certificate expires within the given number of minutes
+    pub async fn get_expiring_certificates(
+        &self,
+        within_minutes: u32,
+    ) -> DatabaseResult<Vec<ExpiringCertificate>> {
+        let db_service = self.database_service.lock().await;
+        let ca_ids: Vec<String> = db_service
+            .get_certificate_authorities()
+            .await?
+            .into_iter()
+            .map(|ca| ca.base.id)
+            .collect();
+        let threshold = chrono::Duration::minutes(within_minutes as i64);
+
+        let mut expiring: Vec<ExpiringCertificate> = db_service
+            .get_ssh_profiles(None)
+            .await?
+            .into_iter()
+            .filter_map(|profile| {
+                let AuthData::Certificate {
+                    validity_period: Some(validity),
+                    ..
+                } = &profile.auth_data
+                else {
+                    return None;
+                };
+                let expires_in = validity.expires_in()?;
+                (expires_in <= threshold).then(|| ExpiringCertificate {
+                    profile_id: profile.base.id.clone(),
+                    profile_name: profile.name.clone(),
+                    valid_to: validity.valid_to.clone(),
+                    expires_in_seconds: expires_in.num_seconds(),
+                    auto_renew: validity
+                        .ca_id
+                        .as_ref()
+                        .is_some_and(|id| ca_ids.contains(id)),
+                })
+            })
+            .collect();
+        expiring.sort_by_key(|c| c.expires_in_seconds);
+
+        Ok(expiring)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ed25519_key() -> PrivateKey {
+        key_format::generate_private_key(&KeyType::Ed25519, None, "test").unwrap()
+    }
+
+    #[test]
+    fn test_signed_certificate_validates_against_ca() {
+        let ca_key = ed25519_key();
+        let user_key = ed25519_key();
+        let principals = vec!["deploy".to_string(), "root".to_string()];
+
+        let certificate = sign_user_certificate(
+            &ca_key,
+            user_key.public_key(),
+            "deploy@web",
+            &principals,
+            7,
+            Duration::from_secs(3600),
+        )
+        .unwrap();
+
+        let ca_fingerprint = ca_key.public_key().fingerprint(HashAlg::Sha256);
+        let now = Utc::now().timestamp() as u64;
+        assert!(certificate.validate_at(now, [&ca_fingerprint]).is_ok());
+        assert!(certificate
+            .validate_at(now + 2 * 3600, [&ca_fingerprint])
+            .is_err());
+        assert_eq!(certificate.serial(), 7);
+        assert_eq!(certificate.cert_type(), CertType::User);
+        assert_eq!(certificate.valid_principals(), principals.as_slice());
+        assert_eq!(certificate.public_key(), user_key.public_key().key_data());
+
+        let encoded = certificate.to_openssh().unwrap();
+        assert_eq!(load_certificate(&encoded).unwrap(), certificate);
+        let blob = encoded.split_whitespace().nth(1).unwrap();
+        assert_eq!(load_certificate(blob).unwrap(), certificate);
+    }
+
+    #[test]
+    fn test_certificate_subject_reads_paths_and_encrypted_keys() {
+        let key = ed25519_key();
+        let encrypted =
+            key_format::encode_private_key(&key, KeyFormat::OpenSsh, Some("hunter2")).unwrap();
+        assert_eq!(&certificate_subject(&encrypted).unwrap(), key.public_key());
+
+        let path = std::env::temp_dir().join(format!("kerminal-ca-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, &encrypted).unwrap();
+        assert_eq!(
+            &certificate_subject(&path.to_string_lossy()).unwrap(),
+            key.public_key()
+        );
+        let _ = std::fs::remove_file(&path);
+
+        assert!(certificate_subject("not a key").is_err());
+    }
+
+    #[test]
+    fn test_sign_requires_principals() {
+        let ca_key = ed25519_key();
+        let user_key = ed25519_key();
+
+        assert!(sign_user_certificate(
+            &ca_key,
+            user_key.public_key(),
+            "id",
+            &[],
+            1,
+            Duration::from_secs(60),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_certificate_lifetime_excludes_skew_allowance() {
+        let validity = CertificateValidity {
+            valid_from: "2026-01-01T09:55:00+00:00".to_string(),
+            valid_to: "2026-01-01T18:00:00+00:00".to_string(),
+            serial: None,
+            ca_fingerprint: None,
+            ca_id: None,
+            principals: Vec::new(),
+        };
+
+        assert_eq!(certificate_lifetime_minutes(&validity), Some(8 * 60));
+    }
+}
@@ -0,0 +1,1302 @@
+//! Export SSH profiles as OpenSSH client config
+//!
+//! Profiles are written as `Host` blocks, with the stored keys they use written next to the
+//! config. The managed export keeps `~/.ssh/kerminal/config` in step with the database and
+//! adds an `Include` for it to `~/.ssh/config`.
+
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::{
+    io::AsyncWriteExt,
+    sync::Mutex,
+    time::{interval, Duration},
+};
+
+use super::certificate_authority::read_path_or_inline;
+use crate::database::{
+    error::{DatabaseError, DatabaseResult},
+    service::DatabaseService,
+};
+use crate::models::ssh::key::HardwareKey;
+use crate::models::ssh::profile::{AuthMethod, ProxyConfig, ProxyType};
+use crate::models::ssh::{
+    AuthData, ExportSSHConfigRequest, ManagedSSHConfigStatus, SSHConfigExportPreview,
+    SSHConfigExportResult, SSHConfigExportSelection, SSHConfigExportTarget, SSHGroup, SSHKey,
+    SSHProfile, SSHTunnel, TunnelType,
+};
+
+const SETTINGS_FILE: &str = "ssh_config_export.json";
+/// Key files the managed export wrote; only these are ever removed again
+const WRITTEN_FILES_FILE: &str = "ssh_config_export_files.json";
+/// Managed files live in this folder of `~/.ssh`
+const MANAGED_DIR: &str = "kerminal";
+const MANAGED_CONFIG_FILE: &str = "config";
+const DEFAULT_KEY_DIR: &str = "keys";
+/// Written key files start with this
+const KEY_FILE_PREFIX: &str = "kerminal-";
+const INCLUDE_COMMENT: &str = "# SSH profiles exported by Kerminal";
+const CONFIG_HEADER: &str = "# Generated by Kerminal from saved SSH profiles.\n\
+                             # Edit the profiles in Kerminal; changes made here are overwritten.\n";
+/// Same interval the terminal uses for keepalives
+const KEEPALIVE_INTERVAL_SECS: u64 = 15;
+const SYNC_INTERVAL_SECS: u64 = 30;
+
+/// Credentials a Host block authenticates with
+#[derive(Debug, Clone, PartialEq)]
+enum Identity {
+    None,
+    File(PathBuf),
+    Certificate { key: PathBuf, certificate: PathBuf },
+    Pkcs11(String),
+}
+
+#[derive(Debug, Clone)]
+struct HostEntry {
+    alias: String,
+    group: Option<String>,
+    profile: SSHProfile,
+    identity: Identity,
+    tunnels: Vec<SSHTunnel>,
+}
+
+/// File written next to the config
+#[derive(Debug, Clone, PartialEq)]
+struct KeyFile {
+    path: PathBuf,
+    content: String,
+    /// Private keys are only readable by the owner
+    private: bool,
+}
+
+#[derive(Debug, Default)]
+struct ExportPlan {
+    entries: Vec<HostEntry>,
+    key_files: Vec<KeyFile>,
+    warnings: Vec<String>,
+}
+
+/// Lowercase slug of a name, safe in host aliases and file names
+fn slug(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_matches('-').to_string()
+}
+
+/// Whether a synced value can be written as a bare config token
+///
+/// Host names, users and addresses never need whitespace, quotes, `%` tokens or shell
+/// metacharacters, so anything else is refused rather than escaped.
+fn is_plain_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '.' | '-' | '_' | '@' | ':'))
+}
+
+/// Whether a path can be written inside a quoted config value
+fn is_quotable(value: &str) -> bool {
+    !value.is_empty()
+        && !value
+            .chars()
+            .any(|c| c.is_control() || c == '"' || c == '\\')
+}
+
+/// First field of a profile that cannot be written to ssh_config safely
+fn unsafe_field(profile: &SSHProfile, tunnels: &[SSHTunnel]) -> Option<&'static str> {
+    if !is_plain_token(&profile.host) {
+        return Some("host");
+    }
+    if !is_plain_token(&profile.username) {
+        return Some("username");
+    }
+    if let Some(proxy) = &profile.proxy {
+        if !is_plain_token(&proxy.host) {
+            return Some("proxy host");
+        }
+        if proxy
+            .username
+            .as_deref()
+            .is_some_and(|user| !is_plain_token(user))
+        {
+            return Some("proxy username");
+        }
+    }
+    let tunnel_hosts = tunnels
+        .iter()
+        .flat_map(|tunnel| std::iter::once(&tunnel.local_host).chain(&tunnel.remote_host));
+    for host in tunnel_hosts {
+        if !is_plain_token(host) {
+            return Some("tunnel address");
+        }
+    }
+    None
+}
+
+/// Quote an argument for the shell ssh runs ProxyCommand with
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Quote a config value when it contains whitespace
+fn quote(value: &str) -> String {
+    if value.contains(char::is_whitespace) {
+        format!("\"{}\"", value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// `host:port`, bracketing IPv6 addresses
+fn endpoint(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+/// Path as written in the config, relative to the home directory where possible
+fn display_path(path: &Path, home: Option<&Path>) -> String {
+    match home.and_then(|home| path.strip_prefix(home).ok()) {
+        Some(rest) => quote(&format!("~/{}", rest.to_string_lossy())),
+        None => quote(&path.to_string_lossy()),
+    }
+}
+
+/// OpenBSD netcat command connecting through a proxy
+pub(crate) fn proxy_command(proxy: &ProxyConfig) -> String {
+    let protocol = match proxy.proxy_type {
+        ProxyType::Socks5 => "5",
+        ProxyType::Socks4 => "4",
+        ProxyType::Http => "connect",
+    };
+    let user = match (&proxy.proxy_type, &proxy.username) {
+        (ProxyType::Http, Some(user)) => format!(" -P {}", shell_quote(user)),
+        _ => String::new(),
+    };
+    format!(
+        "nc -X {}{} -x {} %h %p",
+        protocol,
+        user,
+        shell_quote(&endpoint(&proxy.host, proxy.port))
+    )
+}
+
+/// Forward directive for a tunnel
+fn forward(tunnel: &SSHTunnel) -> Option<String> {
+    match tunnel.tunnel_type {
+        TunnelType::Local => Some(format!(
+            "LocalForward {} {}",
+            endpoint(&tunnel.local_host, tunnel.local_port),
+            endpoint(tunnel.remote_host.as_deref()?, tunnel.remote_port?)
+        )),
+        // The server listens on the remote side and connects back to the local one
+        TunnelType::Remote => Some(format!(
+            "RemoteForward {} {}",
+            match tunnel.remote_host.as_deref() {
+                Some(host) => endpoint(host, tunnel.remote_port?),
+                None => tunnel.remote_port?.to_string(),
+            },
+            endpoint(&tunnel.local_host, tunnel.local_port)
+        )),
+        TunnelType::Dynamic => Some(format!(
+            "DynamicForward {}",
+            endpoint(&tunnel.local_host, tunnel.local_port)
+        )),
+    }
+}
+
+fn render_host(entry: &HostEntry, home: Option<&Path>) -> String {
+    let profile = &entry.profile;
+    let mut lines = Vec::new();
+
+    if let Some(description) = profile.description.as_deref() {
+        lines.extend(description.lines().map(|line| format!("# {}", line)));
+    }
+    lines.push(format!("Host {}", entry.alias));
+
+    let mut option = |line: String| lines.push(format!("    {}", line));
+    option(format!("HostName {}", profile.host));
+    if profile.port != 22 {
+        option(format!("Port {}", profile.port));
+    }
+    option(format!("User {}", quote(&profile.username)));
+
+    match &entry.identity {
+        Identity::None => {}
+        Identity::File(path) => {
+            option(format!("IdentityFile {}", display_path(path, home)));
+            option("IdentitiesOnly yes".to_string());
+        }
+        Identity::Certificate { key, certificate } => {
+            option(format!("IdentityFile {}", display_path(key, home)));
+            option(format!(
+                "CertificateFile {}",
+                display_path(certificate, home)
+            ));
+            option("IdentitiesOnly yes".to_string());
+        }
+        Identity::Pkcs11(provider) => {
+            option(format!("PKCS11Provider {}", quote(provider)));
+        }
+    }
+
+    if let Some(proxy) = &profile.proxy {
+        option(format!("ProxyCommand {}", proxy_command(proxy)));
+    }
+    if profile.keep_alive {
+        option(format!("ServerAliveInterval {}", KEEPALIVE_INTERVAL_SECS));
+    }
+    if profile.compression {
+        option("Compression yes".to_string());
+    }
+    for tunnel in &entry.tunnels {
+        if let Some(directive) = forward(tunnel) {
+            option(directive);
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// Config file content for the planned hosts, grouped as in the app
+fn render_config(entries: &[HostEntry], home: Option<&Path>) -> String {
+    let mut content = String::from(CONFIG_HEADER);
+    let mut current_group: Option<&str> = None;
+
+    for (index, entry) in entries.iter().enumerate() {
+        let group = entry.group.as_deref();
+        if index == 0 || group != current_group {
+            // A control character in a synced name must not end the comment line
+            let heading: String = group
+                .unwrap_or("Ungrouped")
+                .chars()
+                .map(|c| if c.is_control() { ' ' } else { c })
+                .collect();
+            content.push_str(&format!("\n# ===== {} =====\n", heading));
+            current_group = group;
+        }
+        content.push('\n');
+        content.push_str(&render_host(entry, home));
+        content.push('\n');
+    }
+
+    content
+}
+
+/// Profiles picked directly or through their group
+fn select_profiles(
+    profiles: Vec<SSHProfile>,
+    selection: &SSHConfigExportSelection,
+) -> Vec<SSHProfile> {
+    let profile_ids: HashSet<&str> = selection.profile_ids.iter().map(String::as_str).collect();
+    let group_ids: HashSet<&str> = selection.group_ids.iter().map(String::as_str).collect();
+
+    profiles
+        .into_iter()
+        .filter(|profile| {
+            profile_ids.contains(profile.base.id.as_str())
+                || profile
+                    .group_id
+                    .as_deref()
+                    .is_some_and(|group| group_ids.contains(group))
+        })
+        .collect()
+}
+
+/// Decide aliases, key files and warnings for the selected profiles
+///
+/// `keys` holds the decrypted keys the profiles reference.
+fn build_plan(
+    mut profiles: Vec<SSHProfile>,
+    groups: &[SSHGroup],
+    keys: &HashMap<String, SSHKey>,
+    tunnels: &[SSHTunnel],
+    key_dir: &Path,
+) -> ExportPlan {
+    let group_names: HashMap<&str, &str> = groups
+        .iter()
+        .map(|group| (group.base.id.as_str(), group.name.as_str()))
+        .collect();
+    let group_name = |profile: &SSHProfile| {
+        profile
+            .group_id
+            .as_deref()
+            .and_then(|id| group_names.get(id).copied())
+    };
+    // Grouped profiles first, ungrouped ones last
+    profiles.sort_by_cached_key(|profile| {
+        (
+            group_name(profile).is_none(),
+            group_name(profile).map(str::to_lowercase),
+            profile.name.to_lowercase(),
+        )
+    });
+
+    let mut plan = ExportPlan::default();
+    let mut aliases = HashSet::new();
+    let mut written_keys = HashSet::new();
+
+    for profile in profiles {
+        let mut profile_tunnels: Vec<SSHTunnel> = tunnels
+            .iter()
+            .filter(|tunnel| tunnel.profile_id == profile.base.id)
+            .cloned()
+            .collect();
+        profile_tunnels.sort_by_key(|tunnel| tunnel.local_port);
+
+        // Synced values end up in a file ssh reads on every connection
+        if let Some(field) = unsafe_field(&profile, &profile_tunnels) {
+            eprintln!(
+                "[SSHConfigExporter] Skipping profile {}: unsafe characters in its {}",
+                profile.base.id, field
+            );
+            plan.warnings.push(format!(
+                "{}: skipped, its {} contains characters ssh_config cannot hold safely",
+                profile.name, field
+            ));
+            continue;
+        }
+
+        let base_alias = Some(slug(&profile.name))
+            .filter(|alias| !alias.is_empty())
+            .unwrap_or_else(|| slug(&profile.host));
+        let mut alias = base_alias.clone();
+        let mut suffix = 2;
+        while !aliases.insert(alias.clone()) {
+            alias = format!("{}-{}", base_alias, suffix);
+            suffix += 1;
+        }
+
+        let identity = match (&profile.auth_method, &profile.auth_data) {
+            (AuthMethod::KeyReference, AuthData::KeyReference { key_id }) => {
+                match keys.get(key_id) {
+                    Some(SSHKey {
+                        hardware: Some(HardwareKey::Pkcs11 { provider_path, .. }),
+                        ..
+                    }) if is_quotable(provider_path) => Identity::Pkcs11(provider_path.clone()),
+                    Some(SSHKey {
+                        hardware: Some(HardwareKey::Pkcs11 { .. }),
+                        name,
+                        ..
+                    }) => {
+                        plan.warnings.push(format!(
+                            "{}: the PKCS#11 provider path of {} cannot be written to ssh_config",
+                            profile.name, name
+                        ));
+                        Identity::None
+                    }
+                    Some(key) => {
+                        let path = key_dir.join(format!(
+                            "{}{}-{}",
+                            KEY_FILE_PREFIX,
+                            slug(&key.name),
+                            key.base.id.chars().take(8).collect::<String>()
+                        ));
+                        if written_keys.insert(key.base.id.clone()) {
+                            if key.passphrase.is_some() {
+                                plan.warnings.push(format!(
+                                    "{}: the key is passphrase protected; ssh will ask for it",
+                                    key.name
+                                ));
+                            }
+                            plan.key_files.push(KeyFile {
+                                path: path.clone(),
+                                content: key.private_key.clone(),
+                                private: true,
+                            });
+                            if let Some(public_key) = &key.public_key {
+                                plan.key_files.push(KeyFile {
+                                    path: PathBuf::from(format!("{}.pub", path.display())),
+                                    content: public_key.clone(),
+                                    private: false,
+                                });
+                            }
+                        }
+                        Identity::File(path)
+                    }
+                    None => {
+                        plan.warnings.push(format!(
+                            "{}: its SSH key no longer exists, so no IdentityFile was written",
+                            profile.name
+                        ));
+                        Identity::None
+                    }
+                }
+            }
+            (
+                AuthMethod::Certificate,
+                AuthData::Certificate {
+                    certificate,
+                    private_key,
+                    ..
+                },
+            ) => {
+                // Either may be stored as a file path rather than inline
+                let contents = read_path_or_inline(private_key, "private key")
+                    .and_then(|key| Ok((key, read_path_or_inline(certificate, "certificate")?)));
+                match contents {
+                    Ok((private_key, certificate)) => {
+                        let path = key_dir.join(format!("{}profile-{}", KEY_FILE_PREFIX, alias));
+                        let certificate_path =
+                            PathBuf::from(format!("{}-cert.pub", path.display()));
+                        plan.key_files.push(KeyFile {
+                            path: path.clone(),
+                            content: private_key,
+                            private: true,
+                        });
+                        plan.key_files.push(KeyFile {
+                            path: certificate_path.clone(),
+                            content: certificate,
+                            private: false,
+                        });
+                        Identity::Certificate {
+                            key: path,
+                            certificate: certificate_path,
+                        }
+                    }
+                    Err(e) => {
+                        plan.warnings.push(format!(
+                            "{}: {}, so no IdentityFile was written",
+                            profile.name, e
+                        ));
+                        Identity::None
+                    }
+                }
+            }
+            _ => Identity::None,
+        };
+
+        if profile.auth_method == AuthMethod::Password {
+            plan.warnings.push(format!(
+                "{}: passwords are not exported; ssh will ask for it",
+                profile.name
+            ));
+        }
+        if let Some(proxy) = &profile.proxy {
+            if proxy.password.is_some() {
+                plan.warnings.push(format!(
+                    "{}: the proxy password cannot be written to ssh_config",
+                    profile.name
+                ));
+            }
+            if proxy.username.is_some() && proxy.proxy_type != ProxyType::Http {
+                plan.warnings.push(format!(
+                    "{}: netcat only sends a user name to HTTP proxies",
+                    profile.name
+                ));
+            }
+        }
+
+        plan.entries.push(HostEntry {
+            alias,
+            group: group_name(&profile).map(str::to_string),
+            profile,
+            identity,
+            tunnels: profile_tunnels,
+        });
+    }
+
+    plan
+}
+
+/// Add the managed `Include` to the top of a user config; includes only apply globally
+/// before the first `Host` block
+fn with_include(config: &str, include_line: &str) -> Option<String> {
+    let already_included = config
+        .lines()
+        .any(|line| line.trim().eq_ignore_ascii_case(include_line));
+    if already_included {
+        return None;
+    }
+    Some(format!(
+        "{}\n{}\n\n{}",
+        INCLUDE_COMMENT, include_line, config
+    ))
+}
+
+/// Remove the managed `Include` and its comment
+fn without_include(config: &str, include_line: &str) -> Option<String> {
+    let mut changed = false;
+    let mut lines = Vec::new();
+    let mut skip_blank = false;
+    for line in config.lines() {
+        let trimmed = line.trim();
+        if trimmed == INCLUDE_COMMENT || trimmed.eq_ignore_ascii_case(include_line) {
+            changed = true;
+            skip_blank = true;
+            continue;
+        }
+        if skip_blank && trimmed.is_empty() {
+            skip_blank = false;
+            continue;
+        }
+        skip_blank = false;
+        lines.push(line);
+    }
+
+    changed.then(|| {
+        let mut content = lines.join("\n");
+        if config.ends_with('\n') && !content.is_empty() {
+            content.push('\n');
+        }
+        content
+    })
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> DatabaseError {
+    DatabaseError::QueryFailed(format!("{}: {}", path.display(), e))
+}
+
+/// Restrict a file or folder to the given Unix permissions
+#[allow(unused_variables)]
+async fn set_mode(path: &Path, mode: u32) -> DatabaseResult<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+            .await
+            .map_err(|e| io_error(path, e))?;
+    }
+    Ok(())
+}
+
+/// Write a file through a temporary file unless it already has this content
+///
+/// Returns whether the file was written.
+pub(crate) async fn write_if_changed(path: &Path, content: &str, mode: u32) -> DatabaseResult<bool> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(existing) if existing == content => {
+            set_mode(path, mode).await?;
+            return Ok(false);
+        }
+        Ok(_) => {}
+        Err(e) if e.kind() == ErrorKind::NotFound || e.kind() == ErrorKind::InvalidData => {}
+        Err(e) => return Err(io_error(path, e)),
+    }
+
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp = path.with_file_name(format!(".{}.tmp", file_name));
+    // A leftover temporary file would keep its permissions, so start from a fresh one
+    match tokio::fs::remove_file(&temp).await {
+        Err(e) if e.kind() != ErrorKind::NotFound => return Err(io_error(&temp, e)),
+        _ => {}
+    }
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    // Created owner-only, so secrets are never readable by others, even briefly
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(&temp).await.map_err(|e| io_error(&temp, e))?;
+    file.write_all(content.as_bytes())
+        .await
+        .map_err(|e| io_error(&temp, e))?;
+    file.flush().await.map_err(|e| io_error(&temp, e))?;
+    drop(file);
+    set_mode(&temp, mode).await?;
+    tokio::fs::rename(&temp, path)
+        .await
+        .map_err(|e| io_error(path, e))?;
+    Ok(true)
+}
+
+async fn create_dir(path: &Path, mode: u32) -> DatabaseResult<()> {
+    tokio::fs::create_dir_all(path)
+        .await
+        .map_err(|e| io_error(path, e))?;
+    set_mode(path, mode).await
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn ssh_dir() -> DatabaseResult<PathBuf> {
+    dirs::home_dir()
+        .map(|home| home.join(".ssh"))
+        .ok_or_else(|| DatabaseError::ConfigError("Could not determine home directory".to_string()))
+}
+
+fn load_settings(path: &Path) -> Option<SSHConfigExportSelection> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| eprintln!("[SSHConfigExporter] Invalid {}: {}", path.display(), e))
+            .ok(),
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => {
+            eprintln!(
+                "[SSHConfigExporter] Failed to read {}: {}",
+                path.display(),
+                e
+            );
+            None
+        }
+    }
+}
+
+fn load_written_files(path: &Path) -> Vec<PathBuf> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            eprintln!("[SSHConfigExporter] Invalid {}: {}", path.display(), e);
+            Vec::new()
+        }),
+        Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+        Err(e) => {
+            eprintln!(
+                "[SSHConfigExporter] Failed to read {}: {}",
+                path.display(),
+                e
+            );
+            Vec::new()
+        }
+    }
+}
+
+async fn save_written_files(path: &Path, files: &[PathBuf]) -> DatabaseResult<()> {
+    let content = serde_json::to_string_pretty(files)?;
+    write_if_changed(path, &content, 0o600).await?;
+    Ok(())
+}
+
+/// Remove a file, treating one that is already gone as removed
+async fn remove_if_exists(path: &Path) -> DatabaseResult<()> {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(io_error(path, e)),
+        _ => Ok(()),
+    }
+}
+
+/// Outcome of the last managed sync
+#[derive(Debug, Clone, Default)]
+struct SyncState {
+    last_synced_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+/// Writes SSH profiles as OpenSSH config and keeps the managed `Include` snippet current
+pub struct SSHConfigExporter {
+    database_service: Arc<Mutex<DatabaseService>>,
+    data_dir: PathBuf,
+    /// Selection of the managed export; `None` when it is off
+    managed: Arc<Mutex<Option<SSHConfigExportSelection>>>,
+    sync_state: Arc<Mutex<SyncState>>,
+    is_running: Arc<Mutex<bool>>,
+    /// Serializes writes to the managed files
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl SSHConfigExporter {
+    pub fn new(database_service: Arc<Mutex<DatabaseService>>, data_dir: PathBuf) -> Self {
+        let managed = load_settings(&data_dir.join(SETTINGS_FILE));
+
+        Self {
+            database_service,
+            data_dir,
+            managed: Arc::new(Mutex::new(managed)),
+            sync_state: Arc::new(Mutex::new(SyncState::default())),
+            is_running: Arc::new(Mutex::new(false)),
+            write_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Start keeping the managed snippet in sync
+    pub async fn start(&self) -> DatabaseResult<()> {
+        let mut is_running = self.is_running.lock().await;
+        if *is_running {
+            return Ok(());
+        }
+
+        *is_running = true;
+        drop(is_running);
+
+        let exporter = self.clone_for_task();
+        tokio::spawn(async move {
+            exporter.run_loop().await;
+        });
+
+        Ok(())
+    }
+
+    /// Stop the sync loop
+    #[allow(dead_code)]
+    pub async fn stop(&self) -> DatabaseResult<()> {
+        let mut is_running = self.is_running.lock().await;
+        *is_running = false;
+        Ok(())
+    }
+
+    async fn run_loop(&self) {
+        let mut ticker = interval(Duration::from_secs(SYNC_INTERVAL_SECS));
+
+        loop {
+            ticker.tick().await;
+
+            if !*self.is_running.lock().await {
+                break;
+            }
+            if self.managed.lock().await.is_none() || !self.is_unlocked().await {
+                continue;
+            }
+            if let Err(e) = self.sync_managed().await {
+                eprintln!("[SSHConfigExporter] Sync failed: {}", e);
+            }
+        }
+    }
+
+    fn clone_for_task(&self) -> Self {
+        Self {
+            database_service: self.database_service.clone(),
+            data_dir: self.data_dir.clone(),
+            managed: self.managed.clone(),
+            sync_state: self.sync_state.clone(),
+            is_running: self.is_running.clone(),
+            write_lock: self.write_lock.clone(),
+        }
+    }
+
+    /// Keys and certificates need the database unlocked
+    async fn is_unlocked(&self) -> bool {
+        let db_service = self.database_service.lock().await;
+        let manager = db_service.get_master_password_manager_arc();
+        let manager_guard = manager.read().await;
+        manager_guard.get_status().await.is_unlocked
+    }
+
+    fn key_dir(selection: &SSHConfigExportSelection, ssh_dir: &Path) -> PathBuf {
+        match selection
+            .key_directory
+            .as_deref()
+            .filter(|dir| !dir.trim().is_empty())
+        {
+            Some(dir) => expand_home(dir.trim()),
+            None => ssh_dir.join(MANAGED_DIR).join(DEFAULT_KEY_DIR),
+        }
+    }
+
+    /// Load the selected profiles with everything their Host blocks need
+    async fn plan(
+        &self,
+        selection: &SSHConfigExportSelection,
+        key_dir: &Path,
+    ) -> DatabaseResult<ExportPlan> {
+        let db_service = self.database_service.lock().await;
+
+        let mut profiles = Vec::new();
+        for profile in select_profiles(db_service.get_ssh_profiles(None).await?, selection) {
+            // Listed profiles keep inline certificates encrypted
+            profiles.push(match profile.auth_method {
+                AuthMethod::Certificate => db_service.get_ssh_profile(&profile.base.id).await?,
+                _ => profile,
+            });
+        }
+
+        let mut keys = HashMap::new();
+        for profile in &profiles {
+            if let AuthData::KeyReference { key_id } = &profile.auth_data {
+                if keys.contains_key(key_id) {
+                    continue;
+                }
+                match db_service.get_ssh_key(key_id).await {
+                    Ok(key) => {
+                        keys.insert(key_id.clone(), key);
+                    }
+                    Err(DatabaseError::NotFound(_)) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        let groups = db_service.get_ssh_groups().await?;
+        let tunnels = db_service.get_ssh_tunnels().await?;
+
+        Ok(build_plan(profiles, &groups, &keys, &tunnels, key_dir))
+    }
+
+    fn require_selection(selection: &SSHConfigExportSelection) -> DatabaseResult<()> {
+        if selection.profile_ids.is_empty() && selection.group_ids.is_empty() {
+            return Err(DatabaseError::ValidationError(
+                "Select at least one profile or group to export".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Render the config for a selection without writing anything
+    pub async fn preview(
+        &self,
+        selection: SSHConfigExportSelection,
+    ) -> DatabaseResult<SSHConfigExportPreview> {
+        Self::require_selection(&selection)?;
+        let key_dir = Self::key_dir(&selection, &ssh_dir()?);
+        let plan = self.plan(&selection, &key_dir).await?;
+
+        Ok(SSHConfigExportPreview {
+            content: render_config(&plan.entries, dirs::home_dir().as_deref()),
+            warnings: plan.warnings,
+        })
+    }
+
+    /// Write the key files of a plan and return how many changed
+    async fn write_key_files(plan: &ExportPlan, key_dir: &Path) -> DatabaseResult<usize> {
+        if !plan.key_files.is_empty() {
+            create_dir(key_dir, 0o700).await?;
+        }
+
+        let mut written = 0;
+        for file in &plan.key_files {
+            let mode = if file.private { 0o600 } else { 0o644 };
+            // OpenSSH refuses keys without a trailing newline
+            let content = format!("{}\n", file.content.trim_end());
+            if write_if_changed(&file.path, &content, mode).await? {
+                written += 1;
+            }
+        }
+        Ok(written)
+    }
+
+    /// Export a selection to a file, or make it the managed export
+    pub async fn export(
+        &self,
+        request: ExportSSHConfigRequest,
+    ) -> DatabaseResult<SSHConfigExportResult> {
+        Self::require_selection(&request.selection)?;
+
+        match request.target {
+            SSHConfigExportTarget::File { path } => {
+                let path = expand_home(path.trim());
+                let key_dir = Self::key_dir(&request.selection, &ssh_dir()?);
+                let plan = self.plan(&request.selection, &key_dir).await?;
+                let keys_written = Self::write_key_files(&plan, &key_dir).await?;
+
+                if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                    tokio::fs::create_dir_all(parent)
+                        .await
+                        .map_err(|e| io_error(parent, e))?;
+                }
+                let content = render_config(&plan.entries, dirs::home_dir().as_deref());
+                write_if_changed(&path, &content, 0o600).await?;
+
+                Ok(SSHConfigExportResult {
+                    config_path: path.to_string_lossy().to_string(),
+                    hosts_exported: plan.entries.len(),
+                    keys_written,
+                    warnings: plan.warnings,
+                })
+            }
+            SSHConfigExportTarget::ManagedInclude => {
+                self.save_settings(Some(&request.selection)).await?;
+                *self.managed.lock().await = Some(request.selection);
+                self.sync_managed().await
+            }
+        }
+    }
+
+    async fn save_settings(
+        &self,
+        selection: Option<&SSHConfigExportSelection>,
+    ) -> DatabaseResult<()> {
+        let path = self.data_dir.join(SETTINGS_FILE);
+        match selection {
+            Some(selection) => {
+                let content = serde_json::to_string_pretty(selection)?;
+                write_if_changed(&path, &content, 0o600).await?;
+            }
+            None => match tokio::fs::remove_file(&path).await {
+                Err(e) if e.kind() != ErrorKind::NotFound => return Err(io_error(&path, e)),
+                _ => {}
+            },
+        }
+        Ok(())
+    }
+
+    /// Rewrite the managed snippet and key files from the database
+    pub async fn sync_managed(&self) -> DatabaseResult<SSHConfigExportResult> {
+        let result = self.write_managed().await;
+
+        let mut state = self.sync_state.lock().await;
+        match &result {
+            Ok(_) => {
+                state.last_synced_at = Some(Utc::now());
+                state.last_error = None;
+            }
+            Err(e) => state.last_error = Some(e.to_string()),
+        }
+        result
+    }
+
+    async fn write_managed(&self) -> DatabaseResult<SSHConfigExportResult> {
+        let selection = self.managed.lock().await.clone().ok_or_else(|| {
+            DatabaseError::ValidationError("The managed SSH config export is off".to_string())
+        })?;
+        let _guard = self.write_lock.lock().await;
+
+        let ssh_dir = ssh_dir()?;
+        let managed_dir = ssh_dir.join(MANAGED_DIR);
+        let key_dir = Self::key_dir(&selection, &ssh_dir);
+        let plan = self.plan(&selection, &key_dir).await?;
+
+        create_dir(&managed_dir, 0o700).await?;
+
+        // Key files are tracked before they are written, so a failed sync never loses one
+        let tracked_path = self.data_dir.join(WRITTEN_FILES_FILE);
+        let previous = load_written_files(&tracked_path);
+        let current: Vec<PathBuf> = plan.key_files.iter().map(|f| f.path.clone()).collect();
+        let stale: Vec<PathBuf> = previous
+            .into_iter()
+            .filter(|path| !current.contains(path))
+            .collect();
+        save_written_files(&tracked_path, &[current.clone(), stale.clone()].concat()).await?;
+
+        let keys_written = Self::write_key_files(&plan, &key_dir).await?;
+
+        // Keys of profiles that left the export must not stay on disk
+        for path in &stale {
+            remove_if_exists(path).await?;
+        }
+        save_written_files(&tracked_path, &current).await?;
+
+        let config_path = managed_dir.join(MANAGED_CONFIG_FILE);
+        let content = render_config(&plan.entries, dirs::home_dir().as_deref());
+        write_if_changed(&config_path, &content, 0o600).await?;
+
+        let user_config = ssh_dir.join("config");
+        let existing = match tokio::fs::read_to_string(&user_config).await {
+            Ok(existing) => existing,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(io_error(&user_config, e)),
+        };
+        if let Some(updated) = with_include(&existing, &Self::include_line()) {
+            write_if_changed(&user_config, &updated, 0o600).await?;
+        }
+
+        Ok(SSHConfigExportResult {
+            config_path: config_path.to_string_lossy().to_string(),
+            hosts_exported: plan.entries.len(),
+            keys_written,
+            warnings: plan.warnings,
+        })
+    }
+
+    fn include_line() -> String {
+        format!("Include {}/{}", MANAGED_DIR, MANAGED_CONFIG_FILE)
+    }
+
+    /// Managed export settings, or `None` when it is off
+    pub async fn get_managed_status(&self) -> DatabaseResult<Option<ManagedSSHConfigStatus>> {
+        let Some(selection) = self.managed.lock().await.clone() else {
+            return Ok(None);
+        };
+        let ssh_dir = ssh_dir()?;
+        let state = self.sync_state.lock().await.clone();
+
+        Ok(Some(ManagedSSHConfigStatus {
+            selection,
+            config_path: ssh_dir
+                .join(MANAGED_DIR)
+                .join(MANAGED_CONFIG_FILE)
+                .to_string_lossy()
+                .to_string(),
+            include_path: ssh_dir.join("config").to_string_lossy().to_string(),
+            last_synced_at: state.last_synced_at,
+            last_error: state.last_error,
+        }))
+    }
+
+    /// Turn the managed export off, removing the snippet, its `Include` and the key files
+    pub async fn disable_managed(&self) -> DatabaseResult<()> {
+        if self.managed.lock().await.take().is_none() {
+            return Ok(());
+        }
+        let _guard = self.write_lock.lock().await;
+        self.save_settings(None).await?;
+        *self.sync_state.lock().await = SyncState::default();
+
+        let ssh_dir = ssh_dir()?;
+        let user_config = ssh_dir.join("config");
+        if let Ok(existing) = tokio::fs::read_to_string(&user_config).await {
+            if let Some(updated) = without_include(&existing, &Self::include_line()) {
+                write_if_changed(&user_config, &updated, 0o600).await?;
+            }
+        }
+
+        remove_if_exists(&ssh_dir.join(MANAGED_DIR).join(MANAGED_CONFIG_FILE)).await?;
+        let tracked_path = self.data_dir.join(WRITTEN_FILES_FILE);
+        for path in load_written_files(&tracked_path) {
+            remove_if_exists(&path).await?;
+        }
+        remove_if_exists(&tracked_path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str, group_id: Option<&str>) -> SSHProfile {
+        let mut profile = SSHProfile::new(
+            "device".to_string(),
+            name.to_string(),
+            format!("{}.example.com", slug(name)),
+            22,
+            "deploy".to_string(),
+        );
+        profile.group_id = group_id.map(str::to_string);
+        profile.keep_alive = false;
+        profile
+    }
+
+    #[test]
+    fn test_build_plan_groups_keys_and_tunnels() {
+        let group = SSHGroup::new("device".to_string(), "Production".to_string());
+        let mut key = SSHKey::new(
+            "device".to_string(),
+            "Deploy Key".to_string(),
+            crate::models::ssh::profile::KeyType::Ed25519,
+            "PRIVATE".to_string(),
+            Some("ssh-ed25519 AAAA deploy".to_string()),
+            None,
+        );
+        key.base.id = "0123456789abcdef".to_string();
+
+        let mut web = profile("Web Server", Some(&group.base.id));
+        web.port = 2222;
+        web.auth_method = AuthMethod::KeyReference;
+        web.auth_data = AuthData::KeyReference {
+            key_id: key.base.id.clone(),
+        };
+        web.proxy = Some(ProxyConfig {
+            proxy_type: ProxyType::Http,
+            host: "proxy.lan".to_string(),
+            port: 3128,
+            username: Some("me".to_string()),
+            password: None,
+        });
+        let mut api = profile("web server", Some(&group.base.id));
+        api.auth_method = AuthMethod::KeyReference;
+        api.auth_data = web.auth_data.clone();
+        let loose = profile("Loose", None);
+
+        let tunnels = vec![
+            SSHTunnel::new(
+                "device".to_string(),
+                "db".to_string(),
+                web.base.id.clone(),
+                TunnelType::Local,
+                "127.0.0.1".to_string(),
+                5432,
+                Some("db.internal".to_string()),
+                Some(5432),
+            ),
+            SSHTunnel::new(
+                "device".to_string(),
+                "socks".to_string(),
+                web.base.id.clone(),
+                TunnelType::Dynamic,
+                "::1".to_string(),
+                1080,
+                None,
+                None,
+            ),
+        ];
+
+        let keys = HashMap::from([(key.base.id.clone(), key)]);
+        let key_dir = PathBuf::from("/home/me/.ssh/kerminal/keys");
+        let plan = build_plan(
+            vec![loose, web, api],
+            std::slice::from_ref(&group),
+            &keys,
+            &tunnels,
+            &key_dir,
+        );
+
+        let aliases: Vec<&str> = plan.entries.iter().map(|e| e.alias.as_str()).collect();
+        assert_eq!(aliases, vec!["web-server", "web-server-2", "loose"]);
+        // The shared key is written once, with its public half
+        assert_eq!(plan.key_files.len(), 2);
+        assert_eq!(
+            plan.key_files[0].path,
+            key_dir.join("kerminal-deploy-key-01234567")
+        );
+        assert!(plan.key_files[0].private);
+        assert_eq!(plan.warnings.len(), 1);
+
+        let content = render_config(&plan.entries, Some(Path::new("/home/me")));
+        let expected_web = "Host web-server\n\
+                            \x20   HostName web-server.example.com\n\
+                            \x20   Port 2222\n\
+                            \x20   User deploy\n\
+                            \x20   IdentityFile ~/.ssh/kerminal/keys/kerminal-deploy-key-01234567\n\
+                            \x20   IdentitiesOnly yes\n\
+                            \x20   ProxyCommand nc -X connect -P 'me' -x 'proxy.lan:3128' %h %p\n\
+                            \x20   DynamicForward [::1]:1080\n\
+                            \x20   LocalForward 127.0.0.1:5432 db.internal:5432\n";
+        assert!(content.contains(expected_web), "{}", content);
+        assert!(content.contains("# ===== Production =====\n"));
+        assert!(content.contains("# ===== Ungrouped =====\n\nHost loose\n"));
+    }
+
+    #[test]
+    fn test_injected_values_are_never_rendered() {
+        let mut bad_host = profile("Bad Host", None);
+        bad_host.host = "evil.example.com\n    ProxyCommand touch /tmp/pwned".to_string();
+        let mut bad_user = profile("Bad User", None);
+        bad_user.username = "deploy;$(touch /tmp/pwned)".to_string();
+        let mut bad_proxy = profile("Bad Proxy", None);
+        bad_proxy.proxy = Some(ProxyConfig {
+            proxy_type: ProxyType::Http,
+            host: "proxy.lan".to_string(),
+            port: 3128,
+            username: Some("me;$(touch /tmp/pwned)".to_string()),
+            password: None,
+        });
+        let bad_tunnel = profile("Bad Tunnel", None);
+        let tunnels = vec![SSHTunnel::new(
+            "device".to_string(),
+            "db".to_string(),
+            bad_tunnel.base.id.clone(),
+            TunnelType::Local,
+            "127.0.0.1".to_string(),
+            5432,
+            Some("db\nLocalCommand touch /tmp/pwned".to_string()),
+            Some(5432),
+        )];
+        let group = SSHGroup::new("device".to_string(), "Ops\nLocalCommand id".to_string());
+        let mut good = profile("Good", Some(&group.base.id));
+        good.proxy = Some(ProxyConfig {
+            proxy_type: ProxyType::Http,
+            host: "proxy.lan".to_string(),
+            port: 3128,
+            username: Some("me".to_string()),
+            password: None,
+        });
+
+        let plan = build_plan(
+            vec![bad_host, bad_user, bad_proxy, bad_tunnel, good],
+            std::slice::from_ref(&group),
+            &HashMap::new(),
+            &tunnels,
+            Path::new("/keys"),
+        );
+
+        let aliases: Vec<&str> = plan.entries.iter().map(|e| e.alias.as_str()).collect();
+        assert_eq!(aliases, vec!["good"]);
+        assert_eq!(
+            plan.warnings
+                .iter()
+                .filter(|w| w.contains("skipped"))
+                .count(),
+            4
+        );
+
+        let content = render_config(&plan.entries, None);
+        assert!(!content.contains("pwned"), "{}", content);
+        assert!(!content.contains("\nLocalCommand"), "{}", content);
+        assert!(content.contains("# ===== Ops LocalCommand id =====\n"));
+        assert!(content.contains("ProxyCommand nc -X connect -P 'me' -x 'proxy.lan:3128' %h %p"));
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    }
+
+    #[test]
+    fn test_certificate_files_are_read() {
+        let dir = std::env::temp_dir().join(format!("kerminal-export-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let key_path = dir.join("id_ed25519");
+        let certificate_path = dir.join("id_ed25519-cert.pub");
+        std::fs::write(&key_path, "PRIVATE").unwrap();
+        std::fs::write(&certificate_path, "CERTIFICATE").unwrap();
+
+        let certificate = |certificate: &Path| {
+            let mut host = profile("Cert", None);
+            host.auth_method = AuthMethod::Certificate;
+            host.auth_data = AuthData::Certificate {
+                certificate: certificate.to_string_lossy().to_string(),
+                private_key: key_path.to_string_lossy().to_string(),
+                key_type: crate::models::ssh::profile::KeyType::Ed25519,
+                validity_period: None,
+            };
+            host
+        };
+        let plan = build_plan(
+            vec![certificate(&certificate_path)],
+            &[],
+            &HashMap::new(),
+            &[],
+            &dir.join("keys"),
+        );
+
+        let contents: Vec<&str> = plan.key_files.iter().map(|f| f.content.as_str()).collect();
+        assert_eq!(contents, vec!["PRIVATE", "CERTIFICATE"]);
+        assert!(plan.warnings.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_write_if_changed_never_widens_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("kerminal-export-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("kerminal-key");
+        // A world-readable temporary file left behind by an interrupted write
+        let temp = dir.join(".kerminal-key.tmp");
+        std::fs::write(&temp, "old").unwrap();
+        std::fs::set_permissions(&temp, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        assert!(write_if_changed(&path, "PRIVATE\n", 0o600).await.unwrap());
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "PRIVATE\n");
+        assert!(!write_if_changed(&path, "PRIVATE\n", 0o600).await.unwrap());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_include_added_once_and_removed() {
+        let include = "Include kerminal/config";
+        let original = "Host old\n    HostName old.example.com\n";
+
+        let added = with_include(original, include).unwrap();
+        assert!(added.starts_with(INCLUDE_COMMENT));
+        assert!(added.ends_with(original));
+        assert_eq!(with_include(&added, include), None);
+
+        assert_eq!(without_include(&added, include).as_deref(), Some(original));
+        assert_eq!(without_include(original, include), None);
+    }
+
+    #[test]
+    fn test_select_profiles_by_id_and_group() {
+        let grouped = profile("a", Some("group-1"));
+        let picked = profile("b", None);
+        let other = profile("c", Some("group-2"));
+        let selection = SSHConfigExportSelection {
+            profile_ids: vec![picked.base.id.clone()],
+            group_ids: vec!["group-1".to_string()],
+            key_directory: None,
+        };
+
+        let names: Vec<String> = select_profiles(vec![grouped, picked, other], &selection)
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        assert_eq!(names, vec!["a", "b"]);
+    }
+}
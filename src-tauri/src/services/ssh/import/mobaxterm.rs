@@ -0,0 +1,120 @@
+//! Sessions from a MobaXterm `.mxtsessions` export
+
+use super::add_key_file;
+use crate::models::ssh::{ImportPreview, ImportedHost};
+
+/// Session type MobaXterm uses for SSH
+const SSH_SESSION_TYPE: &str = "109";
+/// Position of the private key among the `%`-separated SSH settings
+const PRIVATE_KEY_FIELD: usize = 14;
+
+/// Parse the bookmarks in a sessions export
+///
+/// Every `[Bookmarks…]` section is one folder, named by its `SubRep` path. Sessions are
+/// `name=#<type>#<settings>#<terminal settings>…` lines; SSH settings start with
+/// `0%host%port%username`.
+pub fn parse(content: &str) -> ImportPreview {
+    let mut preview = ImportPreview::default();
+    let mut in_bookmarks = false;
+    let mut group: Option<String> = None;
+    let mut skipped = 0;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_bookmarks = section.starts_with("Bookmarks");
+            group = None;
+            continue;
+        }
+        if !in_bookmarks {
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        match key {
+            "SubRep" => {
+                group = Some(value.replace('\\', " / ")).filter(|g| !g.is_empty());
+                continue;
+            }
+            "ImgNum" => continue,
+            _ => {}
+        }
+
+        let mut parts = value.split('#');
+        parts.next();
+        if parts.next() != Some(SSH_SESSION_TYPE) {
+            skipped += 1;
+            continue;
+        }
+
+        let settings: Vec<&str> = parts.next().unwrap_or_default().split('%').collect();
+        let field = |index: usize| {
+            settings
+                .get(index)
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+        };
+        let Some(host_name) = field(1) else {
+            preview
+                .warnings
+                .push(format!("{}: session has no host and was skipped", key));
+            continue;
+        };
+
+        let port = field(2).and_then(|port| port.parse().ok()).unwrap_or(22);
+        let mut host = ImportedHost::new(key.to_string(), host_name.to_string(), port);
+        host.username = field(3).map(str::to_string);
+        host.group = group.clone();
+        if let Some(path) = field(PRIVATE_KEY_FIELD) {
+            host.key = Some(add_key_file(&mut preview, path));
+        }
+
+        preview.hosts.push(host);
+    }
+
+    if skipped > 0 {
+        preview
+            .warnings
+            .push(format!("{} non-SSH session(s) were skipped", skipped));
+    }
+
+    preview
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = "[Bookmarks]\r
+SubRep=\r
+ImgNum=42\r
+jump=#109#0%jump.example.com%22%admin%%-1%-1%%%22%%0%0%0%%%-1%0%0%0%%1080%%0%0%1#MobaFont%10%0%0%-1%15#0# #-1\r
+\r
+[Bookmarks_1]\r
+SubRep=Production\\Web\r
+ImgNum=41\r
+web1=#109#0%10.0.0.11%2222%deploy%%-1%-1%%%%%0%0%0%C:\\keys\\web.ppk%%-1%0%0%0%%1080%%0%0%1#MobaFont%10#0# #-1\r
+desktop=#91#4%10.0.0.50%3389%%-1%1%0%0%%#MobaFont%10#0# #-1\r
+";
+
+    #[test]
+    fn test_parse_bookmarks() {
+        let preview = parse(EXPORT);
+
+        assert_eq!(preview.hosts.len(), 2);
+        assert_eq!(preview.hosts[0].name, "jump");
+        assert_eq!(preview.hosts[0].group, None);
+        assert_eq!(preview.hosts[0].key, None);
+
+        let web = &preview.hosts[1];
+        assert_eq!(web.host, "10.0.0.11");
+        assert_eq!(web.port, 2222);
+        assert_eq!(web.username.as_deref(), Some("deploy"));
+        assert_eq!(web.group.as_deref(), Some("Production / Web"));
+        assert_eq!(web.key.as_deref(), Some("C:\\keys\\web.ppk"));
+
+        assert_eq!(preview.warnings, vec!["1 non-SSH session(s) were skipped"]);
+    }
+}
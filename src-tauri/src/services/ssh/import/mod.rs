@@ -0,0 +1,591 @@
+//! Import connections from other SSH clients
+//!
+//! Each source is parsed into an `ImportPreview` of hosts and keys. The preview marks entries
+//! that are already stored; the import re-reads the source and creates the selected hosts as
+//! profiles together with their groups, keys and tunnels. Secrets read from the source stay
+//! in the backend.
+
+mod mobaxterm;
+mod openssh;
+mod putty;
+mod remmina;
+mod termius;
+
+use ssh_key::HashAlg;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use super::{key_format, SSHKeyService};
+use crate::database::{
+    error::{DatabaseError, DatabaseResult},
+    service::DatabaseService,
+};
+use crate::models::ssh::profile::AuthMethod;
+use crate::models::ssh::{
+    AuthData, CreateSSHGroupRequest, CreateSSHKeyRequest, CreateSSHProfileRequest,
+    CreateSSHTunnelRequest, ImportConnectionsRequest, ImportPreview, ImportResult, ImportSource,
+    ImportedHost, ImportedKey, ImportedTunnel, PreviewImportRequest, TunnelType,
+};
+use crate::services::ssh_config_parser;
+
+/// Add a key to the preview unless one with the same id is there, and return its id
+fn add_key(preview: &mut ImportPreview, key: ImportedKey) -> String {
+    let id = key.id.clone();
+    if !preview.keys.iter().any(|existing| existing.id == id) {
+        preview.keys.push(key);
+    }
+    id
+}
+
+/// Add a key read from a file and return its id
+fn add_key_file(preview: &mut ImportPreview, path: &str) -> String {
+    // Exports from Windows clients use backslashes whatever platform reads them
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path).to_string();
+    add_key(
+        preview,
+        ImportedKey {
+            id: path.to_string(),
+            name,
+            path: Some(path.to_string()),
+            private_key: None,
+            passphrase: None,
+            duplicate_of: None,
+        },
+    )
+}
+
+/// Parse a `[address:]port` or `host:port` pair
+/// IPv6 addresses are bracketed (`[::1]:8080`) or use the OpenSSH `address/port` form; an
+/// unbracketed address with several colons is refused rather than split at a guess
+fn split_host_port(spec: &str) -> Option<(Option<String>, u16)> {
+    let (host, port) = if let Some(rest) = spec.strip_prefix('[') {
+        rest.split_once("]:")?
+    } else if let Some(pair) = spec.rsplit_once('/') {
+        pair
+    } else if spec.matches(':').count() > 1 {
+        return None;
+    } else {
+        match spec.split_once(':') {
+            Some(pair) => pair,
+            None => return Some((None, spec.parse().ok()?)),
+        }
+    };
+
+    Some((
+        Some(host.to_string()).filter(|h| !h.is_empty()),
+        port.parse().ok()?,
+    ))
+}
+
+/// Port forward from a listen spec and, except for dynamic forwards, a `host:port` target
+///
+/// Local and dynamic forwards listen locally. Remote forwards listen on the server, which
+/// `SSHTunnel` stores as the remote side, and connect to the local target.
+fn tunnel(tunnel_type: TunnelType, listen: &str, target: Option<&str>) -> Option<ImportedTunnel> {
+    let (listen_host, listen_port) = split_host_port(listen.trim())?;
+    let listen_host = listen_host.unwrap_or_else(|| "127.0.0.1".to_string());
+
+    match tunnel_type {
+        TunnelType::Dynamic => Some(ImportedTunnel {
+            tunnel_type,
+            local_host: listen_host,
+            local_port: listen_port,
+            remote_host: None,
+            remote_port: None,
+        }),
+        TunnelType::Local => {
+            let (target_host, target_port) = split_host_port(target?.trim())?;
+            Some(ImportedTunnel {
+                tunnel_type,
+                local_host: listen_host,
+                local_port: listen_port,
+                remote_host: Some(target_host?),
+                remote_port: Some(target_port),
+            })
+        }
+        TunnelType::Remote => {
+            let (target_host, target_port) = split_host_port(target?.trim())?;
+            Some(ImportedTunnel {
+                tunnel_type,
+                local_host: target_host?,
+                local_port: target_port,
+                remote_host: Some(listen_host),
+                remote_port: Some(listen_port),
+            })
+        }
+    }
+}
+
+/// Decode `%XX` escapes, as PuTTY uses in session names
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| value.get(i + 1..i + 3))
+            .flatten()
+            .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Decode an export as UTF-8 or, when it starts with a byte order mark, UTF-16
+/// `reg export` writes UTF-16LE
+fn decode_text(bytes: &[u8]) -> String {
+    let utf16 = |bytes: &[u8], read: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| read([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    };
+
+    match bytes {
+        [0xFF, 0xFE, rest @ ..] => utf16(rest, u16::from_le_bytes),
+        [0xFE, 0xFF, rest @ ..] => utf16(rest, u16::from_be_bytes),
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+/// Expand a leading `~` to the home directory
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+async fn read_text(path: &Path) -> DatabaseResult<String> {
+    let bytes = tokio::fs::read(path).await.map_err(|e| {
+        DatabaseError::ValidationError(format!("Failed to read {}: {}", path.display(), e))
+    })?;
+    Ok(decode_text(&bytes))
+}
+
+/// Give every host the id it is selected by
+fn assign_host_ids(preview: &mut ImportPreview) {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for host in &mut preview.hosts {
+        let id = format!(
+            "{}/{}/{}@{}:{}",
+            host.group.as_deref().unwrap_or_default(),
+            host.name,
+            host.username.as_deref().unwrap_or_default(),
+            host.host,
+            host.port
+        );
+        let count = seen.entry(id.clone()).or_default();
+        *count += 1;
+        host.id = match *count {
+            1 => id,
+            n => format!("{}#{}", id, n),
+        };
+    }
+}
+
+/// Parse an import source into hosts and keys
+async fn read_source(source: ImportSource, path: Option<&str>) -> DatabaseResult<ImportPreview> {
+    let mut preview = parse_source(source, path).await?;
+    assign_host_ids(&mut preview);
+    Ok(preview)
+}
+
+async fn parse_source(source: ImportSource, path: Option<&str>) -> DatabaseResult<ImportPreview> {
+    let path = path.filter(|p| !p.trim().is_empty()).map(expand_home);
+    let required_path = || {
+        path.clone().ok_or_else(|| {
+            DatabaseError::ValidationError("Choose a file to import from".to_string())
+        })
+    };
+
+    match source {
+        ImportSource::OpenSsh => {
+            let hosts = ssh_config_parser::parse_ssh_config(path.clone())
+                .await
+                .map_err(|e| DatabaseError::ValidationError(e.to_string()))?;
+            Ok(openssh::convert(hosts))
+        }
+        ImportSource::Putty => Ok(putty::parse(&read_text(&required_path()?).await?)),
+        ImportSource::MobaXterm => Ok(mobaxterm::parse(&read_text(&required_path()?).await?)),
+        ImportSource::Termius => termius::parse(&read_text(&required_path()?).await?),
+        ImportSource::Remmina => {
+            let path = required_path()?;
+            let files = if path.is_dir() {
+                let mut files = Vec::new();
+                let mut entries = tokio::fs::read_dir(&path).await.map_err(|e| {
+                    DatabaseError::ValidationError(format!(
+                        "Failed to read {}: {}",
+                        path.display(),
+                        e
+                    ))
+                })?;
+                while let Ok(Some(entry)) = entries.next_entry().await {
+                    let file = entry.path();
+                    if file.extension().is_some_and(|ext| ext == "remmina") {
+                        files.push(file);
+                    }
+                }
+                files.sort();
+                files
+            } else {
+                vec![path]
+            };
+
+            let mut preview = ImportPreview::default();
+            for file in files {
+                let file_name = file
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                remmina::parse_file(&read_text(&file).await?, &file_name, &mut preview);
+            }
+            Ok(preview)
+        }
+    }
+}
+
+/// Private key content of an imported key
+async fn load_key(key: &ImportedKey) -> Result<String, String> {
+    if let Some(content) = &key.private_key {
+        return Ok(content.clone());
+    }
+    let path = key.path.as_deref().ok_or("no key content")?;
+    tokio::fs::read_to_string(expand_home(path))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Imports connections from other SSH clients
+pub struct ConnectionImportService {
+    database_service: Arc<Mutex<DatabaseService>>,
+    ssh_key_service: Arc<Mutex<SSHKeyService>>,
+}
+
+impl ConnectionImportService {
+    /// Create new ConnectionImportService instance
+    pub fn new(
+        database_service: Arc<Mutex<DatabaseService>>,
+        ssh_key_service: Arc<Mutex<SSHKeyService>>,
+    ) -> Self {
+        Self {
+            database_service,
+            ssh_key_service,
+        }
+    }
+
+    /// Read a source and mark the hosts and keys that are already stored
+    pub async fn preview(&self, request: PreviewImportRequest) -> DatabaseResult<ImportPreview> {
+        let mut preview = read_source(request.source, request.path.as_deref()).await?;
+        self.mark_duplicates(&mut preview).await?;
+        Ok(preview)
+    }
+
+    /// Keys are matched by fingerprint, hosts by address, port and user name
+    async fn mark_duplicates(&self, preview: &mut ImportPreview) -> DatabaseResult<()> {
+        let (stored_keys, stored_profiles) = {
+            let db_service = self.database_service.lock().await;
+            (
+                db_service.get_ssh_keys().await?,
+                db_service.get_ssh_profiles(None).await?,
+            )
+        };
+
+        let fingerprints: HashMap<&str, &str> = stored_keys
+            .iter()
+            .map(|key| (key.fingerprint.as_str(), key.base.id.as_str()))
+            .collect();
+        for key in &mut preview.keys {
+            let content = match load_key(key).await {
+                Ok(content) => content,
+                Err(e) => {
+                    preview.warnings.push(format!(
+                        "Key {} could not be read ({}); hosts using it fall back to password authentication",
+                        key.id, e
+                    ));
+                    continue;
+                }
+            };
+            key.duplicate_of = key_format::read_public_key(&content, key.passphrase.as_deref())
+                .map(|public_key| public_key.fingerprint(HashAlg::Sha256).to_string())
+                .and_then(|fingerprint| fingerprints.get(fingerprint.as_str()).copied())
+                .map(str::to_string);
+        }
+
+        for host in &mut preview.hosts {
+            host.duplicate_of = stored_profiles
+                .iter()
+                .find(|profile| {
+                    profile.host.eq_ignore_ascii_case(&host.host)
+                        && profile.port == host.port
+                        && host
+                            .username
+                            .as_deref()
+                            .is_none_or(|username| profile.username == username)
+                })
+                .map(|profile| profile.base.id.clone());
+        }
+
+        Ok(())
+    }
+
+    /// Create profiles for the selected hosts, with the groups, keys and tunnels they use
+    pub async fn import(&self, request: ImportConnectionsRequest) -> DatabaseResult<ImportResult> {
+        let mut preview = read_source(request.source, request.path.as_deref()).await?;
+        self.mark_duplicates(&mut preview).await?;
+
+        let selected: Vec<&ImportedHost> = match &request.hosts {
+            Some(ids) => {
+                let ids: HashSet<&str> = ids.iter().map(String::as_str).collect();
+                preview
+                    .hosts
+                    .iter()
+                    .filter(|host| ids.contains(host.id.as_str()))
+                    .collect()
+            }
+            None => preview
+                .hosts
+                .iter()
+                .filter(|host| host.duplicate_of.is_none())
+                .collect(),
+        };
+
+        let mut result = ImportResult {
+            warnings: preview.warnings.clone(),
+            ..Default::default()
+        };
+
+        let used_keys: HashSet<&str> = selected
+            .iter()
+            .filter_map(|host| host.key.as_deref())
+            .collect();
+        let mut key_ids: HashMap<&str, String> = HashMap::new();
+        for key in preview
+            .keys
+            .iter()
+            .filter(|key| used_keys.contains(key.id.as_str()))
+        {
+            if let Some(existing) = &key.duplicate_of {
+                key_ids.insert(&key.id, existing.clone());
+                result.keys_reused += 1;
+                continue;
+            }
+            // Unreadable keys were reported while marking duplicates
+            let Ok(private_key) = load_key(key).await else {
+                continue;
+            };
+
+            let created = self
+                .ssh_key_service
+                .lock()
+                .await
+                .create_ssh_key(CreateSSHKeyRequest {
+                    name: key.name.clone(),
+                    key_type: None,
+                    private_key,
+                    public_key: None,
+                    passphrase: key.passphrase.clone(),
+                    description: Some(format!(
+                        "Imported from {}",
+                        key.path.as_deref().unwrap_or(&key.name)
+                    )),
+                    hardware: None,
+                })
+                .await;
+            match created {
+                Ok(stored) => {
+                    key_ids.insert(&key.id, stored.base.id);
+                    result.keys_created += 1;
+                }
+                Err(e) => result
+                    .warnings
+                    .push(format!("Key {} was not imported: {}", key.name, e)),
+            }
+        }
+
+        let db_service = self.database_service.lock().await;
+
+        let mut group_ids: HashMap<String, String> = db_service
+            .get_ssh_groups()
+            .await?
+            .into_iter()
+            .map(|group| (group.name.to_lowercase(), group.base.id))
+            .collect();
+
+        for host in selected {
+            let group_id = match &host.group {
+                Some(name) => match group_ids.get(&name.to_lowercase()) {
+                    Some(id) => Some(id.clone()),
+                    None => {
+                        let group = db_service
+                            .create_ssh_group(CreateSSHGroupRequest {
+                                name: name.clone(),
+                                description: None,
+                                color: None,
+                                icon: None,
+                            })
+                            .await?;
+                        result.groups_created += 1;
+                        group_ids.insert(name.to_lowercase(), group.base.id.clone());
+                        Some(group.base.id)
+                    }
+                },
+                None => None,
+            };
+
+            let (auth_method, auth_data) = match host.key.as_deref().and_then(|id| key_ids.get(id))
+            {
+                Some(key_id) => (
+                    AuthMethod::KeyReference,
+                    AuthData::KeyReference {
+                        key_id: key_id.clone(),
+                    },
+                ),
+                None => (
+                    AuthMethod::Password,
+                    AuthData::Password {
+                        password: host.password.clone().unwrap_or_default(),
+                    },
+                ),
+            };
+
+            let profile = db_service
+                .create_ssh_profile(CreateSSHProfileRequest {
+                    name: host.name.clone(),
+                    host: host.host.clone(),
+                    port: Some(host.port),
+                    username: host
+                        .username
+                        .clone()
+                        .unwrap_or_else(ssh_config_parser::local_username),
+                    group_id,
+                    auth_method,
+                    auth_data,
+                    timeout: None,
+                    keep_alive: None,
+                    compression: None,
+                    proxy: host.proxy.clone(),
+                    color: None,
+                    description: host.description.clone(),
+                    command: None,
+                    working_dir: None,
+                    env: None,
+                })
+                .await?;
+            result.profiles_created += 1;
+
+            if !request.import_tunnels {
+                continue;
+            }
+            for tunnel in &host.tunnels {
+                let port = match tunnel.tunnel_type {
+                    TunnelType::Remote => tunnel.remote_port.unwrap_or(tunnel.local_port),
+                    _ => tunnel.local_port,
+                };
+                let created = db_service
+                    .create_ssh_tunnel(CreateSSHTunnelRequest {
+                        name: format!("{} {:?} {}", host.name, tunnel.tunnel_type, port),
+                        description: None,
+                        profile_id: profile.base.id.clone(),
+                        tunnel_type: tunnel.tunnel_type.clone(),
+                        local_host: tunnel.local_host.clone(),
+                        local_port: tunnel.local_port,
+                        remote_host: tunnel.remote_host.clone(),
+                        remote_port: tunnel.remote_port,
+                        auto_start: Some(false),
+                    })
+                    .await;
+                match created {
+                    Ok(_) => result.tunnels_created += 1,
+                    Err(e) => result.warnings.push(format!(
+                        "{}: tunnel on port {} was not imported: {}",
+                        host.name, port, e
+                    )),
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tunnel_sides() {
+        let local = tunnel(TunnelType::Local, "8080", Some("db.internal:5432")).unwrap();
+        assert_eq!(local.local_host, "127.0.0.1");
+        assert_eq!(local.local_port, 8080);
+        assert_eq!(local.remote_host.as_deref(), Some("db.internal"));
+        assert_eq!(local.remote_port, Some(5432));
+
+        let remote = tunnel(TunnelType::Remote, "0.0.0.0:9000", Some("[::1]:3000")).unwrap();
+        assert_eq!(remote.remote_host.as_deref(), Some("0.0.0.0"));
+        assert_eq!(remote.remote_port, Some(9000));
+        assert_eq!(remote.local_host, "::1");
+        assert_eq!(remote.local_port, 3000);
+
+        let ipv6 = tunnel(TunnelType::Local, "::1/8080", Some("fd00::5/5432")).unwrap();
+        assert_eq!(ipv6.local_host, "::1");
+        assert_eq!(ipv6.local_port, 8080);
+        assert_eq!(ipv6.remote_host.as_deref(), Some("fd00::5"));
+        assert_eq!(split_host_port("2001:db8::1"), None);
+        assert_eq!(split_host_port("[::1]"), None);
+
+        assert!(tunnel(TunnelType::Local, "8080", None).is_none());
+        assert!(tunnel(TunnelType::Dynamic, "socks", None).is_none());
+    }
+
+    #[test]
+    fn test_host_ids_are_stable_and_unique() {
+        let mut preview = ImportPreview::default();
+        for _ in 0..2 {
+            preview.hosts.push(ImportedHost::new(
+                "web".to_string(),
+                "10.0.0.1".to_string(),
+                22,
+            ));
+        }
+        let mut grouped = ImportedHost::new("web".to_string(), "10.0.0.1".to_string(), 22);
+        grouped.group = Some("prod".to_string());
+        preview.hosts.insert(0, grouped);
+
+        assign_host_ids(&mut preview);
+        let ids: Vec<&str> = preview.hosts.iter().map(|h| h.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "prod/web/@10.0.0.1:22",
+                "/web/@10.0.0.1:22",
+                "/web/@10.0.0.1:22#2"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_text_and_percent_decode() {
+        let mut utf16 = vec![0xFF, 0xFE];
+        utf16.extend("[Sessions]".encode_utf16().flat_map(u16::to_le_bytes));
+        assert_eq!(decode_text(&utf16), "[Sessions]");
+        assert_eq!(decode_text(b"\xEF\xBB\xBFplain"), "plain");
+
+        assert_eq!(percent_decode("My%20Server%2Fprod"), "My Server/prod");
+        assert_eq!(percent_decode("100%"), "100%");
+    }
+}
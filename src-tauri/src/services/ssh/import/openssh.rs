@@ -0,0 +1,106 @@
+//! Hosts from an OpenSSH client config
+
+use super::{add_key_file, tunnel};
+use crate::models::ssh::{ImportPreview, ImportedHost, SSHConfigHost, TunnelType};
+
+/// Convert parsed config hosts; `Include`s are already resolved by the config parser
+pub fn convert(config_hosts: Vec<SSHConfigHost>) -> ImportPreview {
+    let mut preview = ImportPreview::default();
+
+    for config_host in config_hosts {
+        let mut host = ImportedHost::new(
+            config_host.name.clone(),
+            config_host.hostname.clone(),
+            config_host.port,
+        );
+        host.username = config_host.user.clone();
+
+        if let Some(path) = &config_host.identity_file {
+            host.key = Some(add_key_file(&mut preview, path));
+        }
+
+        if let Some(proxy_jump) = &config_host.proxy_jump {
+            preview.warnings.push(format!(
+                "{}: ProxyJump {} is not supported and was left out",
+                host.name, proxy_jump
+            ));
+        }
+        if config_host.proxy_command.is_some() {
+            preview.warnings.push(format!(
+                "{}: ProxyCommand is not supported and was left out",
+                host.name
+            ));
+        }
+
+        let forwards = [
+            (TunnelType::Local, &config_host.local_forwards),
+            (TunnelType::Remote, &config_host.remote_forwards),
+            (TunnelType::Dynamic, &config_host.dynamic_forwards),
+        ];
+        for (tunnel_type, specs) in forwards {
+            for spec in specs {
+                let mut parts = spec.split_whitespace();
+                let listen = parts.next().unwrap_or_default();
+                match tunnel(tunnel_type.clone(), listen, parts.next()) {
+                    Some(tunnel) => host.tunnels.push(tunnel),
+                    None => preview
+                        .warnings
+                        .push(format!("{}: could not read forward '{}'", host.name, spec)),
+                }
+            }
+        }
+
+        preview.hosts.push(host);
+    }
+
+    preview
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_host(name: &str) -> SSHConfigHost {
+        SSHConfigHost {
+            name: name.to_string(),
+            hostname: format!("{}.example.com", name),
+            port: 22,
+            user: Some("deploy".to_string()),
+            identity_file: Some("/home/me/.ssh/id_ed25519".to_string()),
+            identity_files: vec!["/home/me/.ssh/id_ed25519".to_string()],
+            certificate_files: Vec::new(),
+            proxy_jump: None,
+            proxy_command: None,
+            forward_agent: None,
+            other_options: None,
+            local_forwards: Vec::new(),
+            remote_forwards: Vec::new(),
+            dynamic_forwards: Vec::new(),
+            server_alive_interval: None,
+            compression: None,
+        }
+    }
+
+    #[test]
+    fn test_convert_shares_keys_and_reads_forwards() {
+        let mut web = config_host("web");
+        web.local_forwards = vec!["8080 localhost:80".to_string()];
+        web.dynamic_forwards = vec!["1080".to_string(), "bogus".to_string()];
+        let mut db = config_host("db");
+        db.proxy_jump = Some("bastion".to_string());
+
+        let preview = convert(vec![web, db]);
+
+        assert_eq!(preview.hosts.len(), 2);
+        assert_eq!(preview.keys.len(), 1);
+        assert_eq!(preview.keys[0].name, "id_ed25519");
+        assert_eq!(preview.hosts[0].key, preview.hosts[1].key);
+
+        let tunnels = &preview.hosts[0].tunnels;
+        assert_eq!(tunnels.len(), 2);
+        assert_eq!(tunnels[0].local_port, 8080);
+        assert_eq!(tunnels[0].remote_host.as_deref(), Some("localhost"));
+        assert_eq!(tunnels[1].tunnel_type, TunnelType::Dynamic);
+        assert_eq!(preview.warnings.len(), 2);
+    }
+}
@@ -0,0 +1,216 @@
+//! Sessions from a PuTTY registry export
+//!
+//! Exported with `reg export HKCU\Software\SimonTatham\PuTTY\Sessions putty.reg`.
+
+use std::collections::HashMap;
+
+use super::{add_key_file, percent_decode, tunnel};
+use crate::models::ssh::profile::{ProxyConfig, ProxyType};
+use crate::models::ssh::{ImportPreview, ImportedHost, ImportedTunnel, TunnelType};
+
+const SESSIONS_KEY: &str = "\\Software\\SimonTatham\\PuTTY\\Sessions\\";
+
+/// Registry value of a session
+enum RegValue {
+    String(String),
+    Dword(u32),
+}
+
+/// Read a `"Name"="value"` or `"Name"=dword:0000001f` line
+fn parse_value_line(line: &str) -> Option<(String, RegValue)> {
+    let line = line.strip_prefix('"')?;
+    let (name, value) = line.split_once("\"=")?;
+
+    let value = if let Some(hex) = value.strip_prefix("dword:") {
+        RegValue::Dword(u32::from_str_radix(hex.trim(), 16).ok()?)
+    } else {
+        let quoted = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+        RegValue::String(quoted.replace("\\\"", "\"").replace("\\\\", "\\"))
+    };
+
+    Some((name.to_string(), value))
+}
+
+/// Parse the sessions in a `.reg` export
+pub fn parse(content: &str) -> ImportPreview {
+    let mut sessions: Vec<(String, HashMap<String, RegValue>)> = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            if let Some((_, name)) = section.split_once(SESSIONS_KEY) {
+                sessions.push((percent_decode(name), HashMap::new()));
+            } else {
+                // Keys outside the session list, such as SshHostKeys
+                sessions.push((String::new(), HashMap::new()));
+            }
+        } else if let Some((name, value)) = parse_value_line(line) {
+            if let Some((_, values)) = sessions.last_mut() {
+                values.insert(name, value);
+            }
+        }
+    }
+
+    let mut preview = ImportPreview::default();
+    for (name, values) in sessions.into_iter().filter(|(name, _)| !name.is_empty()) {
+        let string = |key: &str| match values.get(key) {
+            Some(RegValue::String(value)) if !value.is_empty() => Some(value.clone()),
+            _ => None,
+        };
+        let dword = |key: &str| match values.get(key) {
+            Some(RegValue::Dword(value)) => Some(*value),
+            _ => None,
+        };
+
+        let protocol = string("Protocol").unwrap_or_else(|| "ssh".to_string());
+        if protocol != "ssh" {
+            preview.warnings.push(format!(
+                "{}: {} sessions are not supported and were skipped",
+                name, protocol
+            ));
+            continue;
+        }
+
+        let Some(host_name) = string("HostName") else {
+            // "Default Settings" and other templates have no host
+            continue;
+        };
+        // PuTTY accepts user@host in the host field
+        let (username, host_name) = match host_name.split_once('@') {
+            Some((user, host)) => (Some(user.to_string()), host.to_string()),
+            None => (string("UserName"), host_name),
+        };
+
+        let port = dword("PortNumber")
+            .and_then(|port| u16::try_from(port).ok())
+            .unwrap_or(22);
+        let mut host = ImportedHost::new(name.clone(), host_name, port);
+        host.username = username;
+
+        if let Some(path) = string("PublicKeyFile") {
+            host.key = Some(add_key_file(&mut preview, &path));
+        }
+
+        let proxy_type = match dword("ProxyMethod").unwrap_or(0) {
+            0 => None,
+            1 => Some(ProxyType::Socks4),
+            2 => Some(ProxyType::Socks5),
+            3 => Some(ProxyType::Http),
+            _ => {
+                preview.warnings.push(format!(
+                    "{}: telnet and local proxy commands are not supported and were left out",
+                    name
+                ));
+                None
+            }
+        };
+        if let (Some(proxy_type), Some(proxy_host)) = (proxy_type, string("ProxyHost")) {
+            host.proxy = Some(ProxyConfig {
+                proxy_type,
+                host: proxy_host,
+                port: dword("ProxyPort")
+                    .and_then(|port| u16::try_from(port).ok())
+                    .unwrap_or(1080),
+                username: string("ProxyUsername"),
+                password: None,
+            });
+        }
+
+        for spec in string("PortForwardings")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|spec| !spec.is_empty())
+        {
+            match parse_port_forwarding(spec) {
+                Some(tunnel) => host.tunnels.push(tunnel),
+                None => preview
+                    .warnings
+                    .push(format!("{}: could not read port forward '{}'", name, spec)),
+            }
+        }
+
+        preview.hosts.push(host);
+    }
+
+    preview
+}
+
+/// Read a PuTTY forward such as `L8080=host:80`, `4R127.0.0.1:9000=localhost:3000` or `D1080`
+fn parse_port_forwarding(spec: &str) -> Option<ImportedTunnel> {
+    // Optional address family prefix
+    let spec = spec.trim_start_matches(['4', '6']);
+    let tunnel_type = match spec.chars().next()? {
+        'L' => TunnelType::Local,
+        'R' => TunnelType::Remote,
+        'D' => TunnelType::Dynamic,
+        _ => return None,
+    };
+
+    let rest = &spec[1..];
+    match rest.split_once('=') {
+        Some((listen, target)) if !target.is_empty() => tunnel(tunnel_type, listen, Some(target)),
+        Some((listen, _)) => tunnel(tunnel_type, listen, None),
+        None => tunnel(tunnel_type, rest, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"Windows Registry Editor Version 5.00
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions]
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\Default%20Settings]
+"HostName"=""
+"Protocol"="ssh"
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\Web%20Server]
+"HostName"="web.example.com"
+"PortNumber"=dword:00000922
+"UserName"="deploy"
+"Protocol"="ssh"
+"PublicKeyFile"="C:\\Users\\me\\keys\\web.ppk"
+"PortForwardings"="L8080=localhost:80,4R127.0.0.1:9000=localhost:3000,D1080"
+"ProxyMethod"=dword:00000002
+"ProxyHost"="proxy.example.com"
+"ProxyPort"=dword:00000438
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\router]
+"HostName"="192.168.1.1"
+"Protocol"="telnet"
+"#;
+
+    #[test]
+    fn test_parse_sessions() {
+        let preview = parse(EXPORT);
+
+        assert_eq!(preview.hosts.len(), 1);
+        let host = &preview.hosts[0];
+        assert_eq!(host.name, "Web Server");
+        assert_eq!(host.host, "web.example.com");
+        assert_eq!(host.port, 2338);
+        assert_eq!(host.username.as_deref(), Some("deploy"));
+
+        assert_eq!(preview.keys.len(), 1);
+        assert_eq!(
+            preview.keys[0].path.as_deref(),
+            Some("C:\\Users\\me\\keys\\web.ppk")
+        );
+        assert_eq!(preview.keys[0].name, "web.ppk");
+
+        let proxy = host.proxy.as_ref().unwrap();
+        assert_eq!(proxy.proxy_type, ProxyType::Socks5);
+        assert_eq!(proxy.port, 1080);
+
+        assert_eq!(host.tunnels.len(), 3);
+        assert_eq!(host.tunnels[1].tunnel_type, TunnelType::Remote);
+        assert_eq!(host.tunnels[1].remote_host.as_deref(), Some("127.0.0.1"));
+        assert_eq!(host.tunnels[1].remote_port, Some(9000));
+        assert_eq!(host.tunnels[1].local_port, 3000);
+        assert_eq!(host.tunnels[2].local_port, 1080);
+
+        assert_eq!(preview.warnings.len(), 1);
+    }
+}
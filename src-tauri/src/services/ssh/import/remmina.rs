@@ -0,0 +1,104 @@
+//! Connections from Remmina `.remmina` profiles
+
+use super::add_key_file;
+use crate::models::ssh::{ImportPreview, ImportedHost};
+
+/// Add the connection in one `.remmina` file to the preview
+///
+/// Profiles are INI files with a single `[remmina]` section. Only SSH profiles are read;
+/// passwords are encrypted with a per-install secret and cannot be imported.
+pub fn parse_file(content: &str, file_name: &str, preview: &mut ImportPreview) {
+    let mut in_profile = false;
+    let mut values = std::collections::HashMap::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_profile = section == "remmina";
+        } else if let (true, Some((key, value))) = (in_profile, line.split_once('=')) {
+            if !value.trim().is_empty() {
+                values.insert(key.trim(), value.trim());
+            }
+        }
+    }
+
+    let name = values.get("name").copied().unwrap_or(file_name);
+    match values.get("protocol").copied() {
+        Some(protocol) if protocol.eq_ignore_ascii_case("ssh") => {}
+        protocol => {
+            preview.warnings.push(format!(
+                "{}: {} connections are not supported and were skipped",
+                name,
+                protocol.unwrap_or("unknown")
+            ));
+            return;
+        }
+    }
+
+    let Some(server) = values.get("server") else {
+        preview
+            .warnings
+            .push(format!("{}: profile has no server and was skipped", name));
+        return;
+    };
+    // host, host:port, [v6 address] or [v6 address]:port
+    let (host_name, port) = match server.strip_prefix('[').and_then(|s| s.split_once(']')) {
+        Some((host, rest)) => (host, rest.strip_prefix(':').and_then(|p| p.parse().ok())),
+        None => match server.split_once(':') {
+            Some((host, port)) if !port.contains(':') => (host, port.parse().ok()),
+            _ => (*server, None),
+        },
+    };
+    let port = port.unwrap_or(22);
+
+    let mut host = ImportedHost::new(name.to_string(), host_name.to_string(), port);
+    host.username = values
+        .get("username")
+        .or_else(|| values.get("ssh_username"))
+        .map(|user| user.to_string());
+    host.group = values.get("group").map(|group| group.to_string());
+    if let Some(path) = values.get("ssh_privatekey") {
+        host.key = Some(add_key_file(preview, path));
+    }
+
+    preview.hosts.push(host);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ssh_profile_and_skip_others() {
+        let mut preview = ImportPreview::default();
+        parse_file(
+            "[remmina]\nname=Build box\ngroup=Lab\nprotocol=SSH\nserver=build.lan:2200\n\
+             username=ci\nssh_privatekey=/home/me/.ssh/id_rsa\npassword=.\n",
+            "build.remmina",
+            &mut preview,
+        );
+        parse_file(
+            "[remmina]\nprotocol=SSH\nserver=[fe80::1]\n",
+            "v6.remmina",
+            &mut preview,
+        );
+        parse_file(
+            "[remmina]\nname=Desktop\nprotocol=RDP\nserver=desk.lan\n",
+            "desk.remmina",
+            &mut preview,
+        );
+
+        assert_eq!(preview.hosts.len(), 2);
+        let build = &preview.hosts[0];
+        assert_eq!(build.name, "Build box");
+        assert_eq!(build.host, "build.lan");
+        assert_eq!(build.port, 2200);
+        assert_eq!(build.username.as_deref(), Some("ci"));
+        assert_eq!(build.group.as_deref(), Some("Lab"));
+        assert_eq!(build.key.as_deref(), Some("/home/me/.ssh/id_rsa"));
+
+        assert_eq!(preview.hosts[1].name, "v6.remmina");
+        assert_eq!(preview.hosts[1].host, "fe80::1");
+        assert_eq!(preview.warnings.len(), 1);
+    }
+}
@@ -0,0 +1,428 @@
+use async_trait::async_trait;
+use russh::client::{Config, Handle, Handler};
+use russh::{ChannelMsg, Disconnect};
+use russh_keys::key::PublicKey;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::core::proxy::create_proxy_stream;
+use crate::database::error::{DatabaseError, DatabaseResult};
+use crate::error::AppError;
+use crate::models::ssh::key::ResolvedSSHKey;
+use crate::models::ssh::{AuthData, SSHKey, SSHProfile};
+use crate::services::sftp::remote_exec::shell_quote;
+use crate::services::ssh::certificate_authority::load_certificate;
+use crate::services::ssh::hardware_key::authenticate_with_key;
+use crate::services::ssh::key_format;
+
+/// Connect timeout used when the profile does not set one
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Minimal handler for short-lived deploy sessions
+#[derive(Clone)]
+pub struct DeployClientHandler;
+
+#[async_trait]
+impl Handler for DeployClientHandler {
+    type Error = russh::Error;
+
+    async fn check_server_key(
+        &mut self,
+        _server_public_key: &PublicKey,
+    ) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+}
+
+/// Public key prepared for writing to authorized_keys
+#[derive(Debug, Clone)]
+pub struct AuthorizedKey {
+    /// Full `type base64 comment` line
+    pub line: String,
+    /// Base64 key blob, used to find the key regardless of its comment
+    pub blob: String,
+}
+
+impl AuthorizedKey {
+    /// Build the authorized_keys entry for a stored key
+    ///
+    /// Uses the stored public key or derives it from the private key. Keys without
+    /// a comment are labelled with the key name so they can be recognized on the host.
+    pub fn from_key(key: &SSHKey) -> DatabaseResult<Self> {
+        let mut public_key = key
+            .public_key
+            .as_deref()
+            .and_then(|line| ssh_key::PublicKey::from_openssh(line.trim()).ok())
+            .or_else(|| key_format::read_public_key(&key.private_key, key.passphrase.as_deref()))
+            .ok_or_else(|| {
+                DatabaseError::ValidationError(format!(
+                    "SSH key '{}' has no usable public key",
+                    key.name
+                ))
+            })?;
+
+        if public_key.comment().is_empty() {
+            public_key.set_comment(key.name.replace(char::is_whitespace, "-"));
+        }
+
+        let line = public_key.to_openssh().map_err(|e| {
+            DatabaseError::Internal(anyhow::anyhow!("Failed to encode public key: {}", e))
+        })?;
+        let blob = line
+            .split_whitespace()
+            .nth(1)
+            .unwrap_or_default()
+            .to_string();
+
+        Ok(Self { line, blob })
+    }
+}
+
+/// Result of installing a key in authorized_keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstallOutcome {
+    Added,
+    AlreadyPresent,
+}
+
+/// Open an authenticated session to the profile's host
+///
+/// Authenticates with `key` when given, otherwise with the profile's own password or
+/// certificate. Profiles using a key reference must have the key resolved by the caller.
+pub async fn connect(
+    profile: &SSHProfile,
+    key: Option<&ResolvedSSHKey>,
+) -> Result<Handle<DeployClientHandler>, AppError> {
+    let timeout = profile
+        .timeout
+        .map(|t| Duration::from_secs(t as u64))
+        .unwrap_or(DEFAULT_CONNECT_TIMEOUT);
+
+    let config = Arc::new(Config {
+        inactivity_timeout: Some(timeout),
+        ..Default::default()
+    });
+
+    let connecting = async {
+        if let Some(proxy_config) = &profile.proxy {
+            let stream = create_proxy_stream(proxy_config, &profile.host, profile.port)
+                .await
+                .map_err(|e| {
+                    AppError::connection_failed(format!("Failed to create proxy connection: {}", e))
+                })?;
+            russh::client::connect_stream(config, stream, DeployClientHandler)
+                .await
+                .map_err(|e| AppError::connection_failed(e.to_string()))
+        } else {
+            russh::client::connect(
+                config,
+                (profile.host.as_str(), profile.port),
+                DeployClientHandler,
+            )
+            .await
+            .map_err(|e| AppError::connection_failed(e.to_string()))
+        }
+    };
+
+    let mut session = tokio::time::timeout(timeout, connecting)
+        .await
+        .map_err(|_| {
+            AppError::connection_failed(format!(
+                "Timed out connecting to {}:{}",
+                profile.host, profile.port
+            ))
+        })??;
+
+    let authenticated = match (key, &profile.auth_data) {
+        (Some(key), _) => authenticate_with_key(&mut session, &profile.username, key).await?,
+        (None, AuthData::Password { password }) => session
+            .authenticate_password(&profile.username, password)
+            .await
+            .map_err(|e| AppError::authentication_failed(e.to_string()))?,
+        (
+            None,
+            AuthData::Certificate {
+                certificate,
+                private_key,
+                ..
+            },
+        ) => {
+            let secret = load_secret_key(private_key, None)?;
+            let cert = load_certificate(certificate)
+                .map_err(|e| AppError::authentication_failed(e.to_string()))?;
+            session
+                .authenticate_openssh_cert(&profile.username, Arc::new(secret), cert)
+                .await
+                .map_err(|e| AppError::authentication_failed(e.to_string()))?
+        }
+        (None, AuthData::KeyReference { key_id }) => {
+            return Err(AppError::authentication_failed(format!(
+                "No resolved key data provided for KeyReference {}",
+                key_id
+            )));
+        }
+    };
+
+    if !authenticated {
+        let _ = session
+            .disconnect(Disconnect::ByApplication, "", "en")
+            .await;
+        return Err(AppError::authentication_failed(format!(
+            "Authentication failed for user '{}'",
+            profile.username
+        )));
+    }
+
+    Ok(session)
+}
+
+/// Close a deploy session
+pub async fn disconnect(session: Handle<DeployClientHandler>) {
+    let _ = session
+        .disconnect(Disconnect::ByApplication, "", "en")
+        .await;
+}
+
+/// Append the key to ~/.ssh/authorized_keys unless it is already there
+///
+/// Creates ~/.ssh (0700) and authorized_keys (0600) as needed. The key line is sent
+/// on stdin so its comment never reaches the shell.
+pub async fn install_key(
+    session: &Handle<DeployClientHandler>,
+    key: &AuthorizedKey,
+) -> Result<InstallOutcome, AppError> {
+    let script = install_script(&key.blob);
+    let output = exec(session, &script, Some(format!("{}\n", key.line).as_bytes())).await?;
+    match output.trim() {
+        "added" => Ok(InstallOutcome::Added),
+        "present" => Ok(InstallOutcome::AlreadyPresent),
+        other => Err(AppError::ssh_command_failed(format!(
+            "Unexpected output while updating authorized_keys: {}",
+            other
+        ))),
+    }
+}
+
+/// Remove every authorized_keys line containing the key blob
+///
+/// Returns whether a line was removed.
+pub async fn remove_key(
+    session: &Handle<DeployClientHandler>,
+    blob: &str,
+) -> Result<bool, AppError> {
+    let output = exec(session, &remove_script(blob), None).await?;
+    match output.trim() {
+        "removed" => Ok(true),
+        "absent" => Ok(false),
+        other => Err(AppError::ssh_command_failed(format!(
+            "Unexpected output while updating authorized_keys: {}",
+            other
+        ))),
+    }
+}
+
+/// Shell script appending the key line read from stdin, printing `added` or `present`
+fn install_script(blob: &str) -> String {
+    format!(
+        "umask 077; mkdir -p ~/.ssh && chmod 700 ~/.ssh && f=~/.ssh/authorized_keys && \
+         touch \"$f\" && chmod 600 \"$f\" && \
+         if grep -qF {blob} \"$f\"; then cat >/dev/null; echo present; else \
+         if [ -s \"$f\" ] && [ -n \"$(tail -c 1 \"$f\")\" ]; then echo >> \"$f\"; fi; \
+         cat >> \"$f\" && echo added; fi",
+        blob = shell_quote(blob)
+    )
+}
+
+/// Shell script removing the lines containing the blob, printing `removed` or `absent`
+fn remove_script(blob: &str) -> String {
+    format!(
+        "f=~/.ssh/authorized_keys; [ -f \"$f\" ] || {{ echo absent; exit 0; }}; \
+         if grep -qF {blob} \"$f\"; then t=\"$f.tmp.$$\"; \
+         {{ grep -vF {blob} \"$f\" || true; }} > \"$t\" && chmod 600 \"$t\" && mv \"$t\" \"$f\" \
+         && echo removed; else echo absent; fi",
+        blob = shell_quote(blob)
+    )
+}
+
+/// Run a command to completion and return its stdout
+///
+/// Fails with the command's stderr when it exits non-zero.
+async fn exec(
+    session: &Handle<DeployClientHandler>,
+    command: &str,
+    stdin: Option<&[u8]>,
+) -> Result<String, AppError> {
+    let mut channel = session
+        .channel_open_session()
+        .await
+        .map_err(|e| AppError::ssh_channel_failed(e.to_string()))?;
+
+    channel
+        .exec(true, command)
+        .await
+        .map_err(|e| AppError::ssh_command_failed(e.to_string()))?;
+
+    if let Some(data) = stdin {
+        channel
+            .data(data)
+            .await
+            .map_err(|e| AppError::ssh_command_failed(e.to_string()))?;
+    }
+    channel
+        .eof()
+        .await
+        .map_err(|e| AppError::ssh_command_failed(e.to_string()))?;
+
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let mut exit_status = None;
+    while let Some(msg) = channel.wait().await {
+        match msg {
+            ChannelMsg::Data { data } => stdout.extend_from_slice(&data),
+            ChannelMsg::ExtendedData { data, ext: 1 } => stderr.extend_from_slice(&data),
+            ChannelMsg::ExitStatus {
+                exit_status: status,
+            } => exit_status = Some(status),
+            ChannelMsg::Close => break,
+            _ => {}
+        }
+    }
+
+    if exit_status.unwrap_or(0) != 0 {
+        return Err(AppError::ssh_command_failed(format!(
+            "Remote command exited with status {}: {}",
+            exit_status.unwrap_or_default(),
+            String::from_utf8_lossy(&stderr).trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&stdout).into_owned())
+}
+
+/// Load a private key from a file path or inline key data
+fn load_secret_key(
+    private_key: &str,
+    passphrase: Option<&str>,
+) -> Result<russh_keys::key::KeyPair, AppError> {
+    if Path::new(private_key).exists() {
+        russh_keys::load_secret_key(private_key, passphrase)
+    } else {
+        russh_keys::decode_secret_key(private_key, passphrase)
+    }
+    .map_err(|e| AppError::authentication_failed(format!("Failed to load SSH key: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ssh::{profile::KeyType, KeyFormat};
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    const BLOB: &str = "AAAAC3NzaC1lZDI1NTE5AAAAIKerminalTestBlob";
+
+    /// Run a deploy script with `home` as the home directory, as the remote shell would
+    fn run_script(home: &Path, script: &str, stdin: &str) -> String {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(script)
+            .env("HOME", home)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(stdin.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    }
+
+    fn temp_home() -> std::path::PathBuf {
+        let home = std::env::temp_dir().join(format!("kerminal-deploy-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&home).unwrap();
+        home
+    }
+
+    #[test]
+    fn test_authorized_key_derives_public_key_and_labels_it() {
+        let private_key = key_format::generate_private_key(&KeyType::Ed25519, None, "").unwrap();
+        let encoded =
+            key_format::encode_private_key(&private_key, KeyFormat::OpenSsh, None).unwrap();
+        let key = SSHKey::new(
+            "device".to_string(),
+            "deploy key".to_string(),
+            KeyType::Ed25519,
+            encoded,
+            None,
+            None,
+        );
+
+        let authorized = AuthorizedKey::from_key(&key).unwrap();
+
+        assert!(authorized.line.starts_with("ssh-ed25519 "));
+        assert!(authorized.line.ends_with(" deploy-key"));
+        assert_eq!(
+            authorized.line.split_whitespace().nth(1),
+            Some(authorized.blob.as_str())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_install_script_adds_key_once() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let home = temp_home();
+        let authorized_keys = home.join(".ssh").join("authorized_keys");
+        std::fs::create_dir_all(home.join(".ssh")).unwrap();
+        // An existing last line without a newline must not be joined with the new key
+        std::fs::write(&authorized_keys, "ssh-rsa AAAAother old").unwrap();
+
+        let line = format!("ssh-ed25519 {} deploy-key\n", BLOB);
+        assert_eq!(run_script(&home, &install_script(BLOB), &line), "added");
+        assert_eq!(run_script(&home, &install_script(BLOB), &line), "present");
+
+        assert_eq!(
+            std::fs::read_to_string(&authorized_keys).unwrap(),
+            format!("ssh-rsa AAAAother old\n{}", line)
+        );
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&home.join(".ssh")), 0o700);
+        assert_eq!(mode(&authorized_keys), 0o600);
+
+        let _ = std::fs::remove_dir_all(home);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remove_script_removes_every_line_of_the_key() {
+        let home = temp_home();
+        assert_eq!(run_script(&home, &remove_script(BLOB), ""), "absent");
+
+        let authorized_keys = home.join(".ssh").join("authorized_keys");
+        std::fs::create_dir_all(home.join(".ssh")).unwrap();
+        std::fs::write(
+            &authorized_keys,
+            format!(
+                "ssh-ed25519 {blob} first\nssh-rsa AAAAother old\nssh-ed25519 {blob} copy\n",
+                blob = BLOB
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(run_script(&home, &remove_script(BLOB), ""), "removed");
+        assert_eq!(run_script(&home, &remove_script(BLOB), ""), "absent");
+        assert_eq!(
+            std::fs::read_to_string(&authorized_keys).unwrap(),
+            "ssh-rsa AAAAother old\n"
+        );
+
+        let _ = std::fs::remove_dir_all(home);
+    }
+}
@@ -0,0 +1,500 @@
+//! PuTTY private key files (PPK versions 2 and 3)
+
+use std::collections::HashMap;
+
+use aes::cipher::{block_padding::NoPadding, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hmac::{Hmac, Mac};
+use rand::{rngs::OsRng, RngCore};
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use ssh_key::{
+    private::{Ed25519Keypair, KeypairData, RsaKeypair, RsaPrivateKey},
+    public::KeyData,
+    EcdsaCurve, Mpint, PrivateKey, PublicKey,
+};
+
+use super::{internal, invalid_key, passphrase_required, wrong_passphrase};
+use crate::database::error::{DatabaseError, DatabaseResult};
+
+type Aes256CbcEnc = cbc::Encryptor<aes::Aes256>;
+type Aes256CbcDec = cbc::Decryptor<aes::Aes256>;
+
+const HEADER_PREFIX: &str = "PuTTY-User-Key-File-";
+const CIPHER: &str = "aes256-cbc";
+const BLOCK_SIZE: usize = 16;
+/// Base64 characters per line, as written by PuTTYgen
+const LINE_WIDTH: usize = 64;
+
+/// PuTTYgen's default Argon2id cost
+const ARGON2_MEMORY_KIB: u32 = 8192;
+const ARGON2_PASSES: u32 = 21;
+const ARGON2_PARALLELISM: u32 = 1;
+const ARGON2_SALT_LEN: usize = 16;
+
+/// Fields of a PPK file, with the private blob still encrypted
+struct PpkFile {
+    version: u8,
+    algorithm: String,
+    encryption: String,
+    comment: String,
+    public_blob: Vec<u8>,
+    private_blob: Vec<u8>,
+    mac: Vec<u8>,
+    /// Key derivation headers of version 3 files
+    headers: HashMap<String, String>,
+}
+
+/// Cipher and MAC keys derived from the passphrase
+struct FileKeys {
+    cipher_key: [u8; 32],
+    iv: [u8; 16],
+    mac_key: Vec<u8>,
+}
+
+/// Decode a PPK file into a decrypted private key
+pub(super) fn decode(content: &str, passphrase: Option<&str>) -> DatabaseResult<PrivateKey> {
+    let file = parse(content)?;
+    let encrypted = file.encryption != "none";
+    if encrypted && file.encryption != CIPHER {
+        return Err(DatabaseError::ValidationError(format!(
+            "Unsupported PPK encryption: {}",
+            file.encryption
+        )));
+    }
+
+    let passphrase = if encrypted {
+        passphrase.ok_or_else(passphrase_required)?
+    } else {
+        ""
+    };
+    let keys = derive_keys(&file, passphrase)?;
+
+    let mut private_blob = file.private_blob.clone();
+    if encrypted {
+        Aes256CbcDec::new(&keys.cipher_key.into(), &keys.iv.into())
+            .decrypt_padded_mut::<NoPadding>(&mut private_blob)
+            .map_err(|_| invalid_key("PPK private blob is not block aligned"))?;
+    }
+
+    let mac_data = mac_data(&file, &private_blob);
+    let verified = match file.version {
+        2 => Hmac::<Sha1>::new_from_slice(&keys.mac_key)
+            .map_err(internal)?
+            .chain_update(&mac_data)
+            .verify_slice(&file.mac)
+            .is_ok(),
+        _ => Hmac::<Sha256>::new_from_slice(&keys.mac_key)
+            .map_err(internal)?
+            .chain_update(&mac_data)
+            .verify_slice(&file.mac)
+            .is_ok(),
+    };
+    if !verified {
+        return Err(if encrypted {
+            wrong_passphrase()
+        } else {
+            invalid_key("PPK MAC does not match, the file is corrupted")
+        });
+    }
+
+    let public = PublicKey::from_bytes(&file.public_blob).map_err(invalid_key)?;
+    let key_data = keypair_data(public.key_data(), &private_blob)?;
+    PrivateKey::new(key_data, file.comment).map_err(internal)
+}
+
+/// Encode a decrypted private key as a version 3 PPK file
+pub(super) fn encode(key: &PrivateKey, passphrase: Option<&str>) -> DatabaseResult<String> {
+    let mut file = PpkFile {
+        version: 3,
+        algorithm: key.algorithm().as_str().to_string(),
+        encryption: if passphrase.is_some() { CIPHER } else { "none" }.to_string(),
+        comment: key.comment().to_string(),
+        public_blob: key.public_key().to_bytes().map_err(internal)?,
+        private_blob: private_blob(key.key_data())?,
+        mac: Vec::new(),
+        headers: HashMap::new(),
+    };
+
+    if passphrase.is_some() {
+        let mut salt = [0u8; ARGON2_SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        file.headers
+            .insert("Key-Derivation".to_string(), "Argon2id".to_string());
+        file.headers
+            .insert("Argon2-Memory".to_string(), ARGON2_MEMORY_KIB.to_string());
+        file.headers
+            .insert("Argon2-Passes".to_string(), ARGON2_PASSES.to_string());
+        file.headers.insert(
+            "Argon2-Parallelism".to_string(),
+            ARGON2_PARALLELISM.to_string(),
+        );
+        file.headers
+            .insert("Argon2-Salt".to_string(), hex_encode(&salt));
+
+        // Pad to the cipher block size; the MAC covers the padding
+        let padding = (BLOCK_SIZE - file.private_blob.len() % BLOCK_SIZE) % BLOCK_SIZE;
+        let mut pad = vec![0u8; padding];
+        OsRng.fill_bytes(&mut pad);
+        file.private_blob.extend_from_slice(&pad);
+    }
+
+    let keys = derive_keys(&file, passphrase.unwrap_or(""))?;
+    file.mac = Hmac::<Sha256>::new_from_slice(&keys.mac_key)
+        .map_err(internal)?
+        .chain_update(mac_data(&file, &file.private_blob))
+        .finalize()
+        .into_bytes()
+        .to_vec();
+
+    if passphrase.is_some() {
+        let len = file.private_blob.len();
+        Aes256CbcEnc::new(&keys.cipher_key.into(), &keys.iv.into())
+            .encrypt_padded_mut::<NoPadding>(&mut file.private_blob, len)
+            .map_err(internal)?;
+    }
+
+    let mut out = format!(
+        "{}{}: {}\nEncryption: {}\nComment: {}\n",
+        HEADER_PREFIX, file.version, file.algorithm, file.encryption, file.comment
+    );
+    write_lines(&mut out, "Public-Lines", &file.public_blob);
+    for name in [
+        "Key-Derivation",
+        "Argon2-Memory",
+        "Argon2-Passes",
+        "Argon2-Parallelism",
+        "Argon2-Salt",
+    ] {
+        if let Some(value) = file.headers.get(name) {
+            out.push_str(&format!("{}: {}\n", name, value));
+        }
+    }
+    write_lines(&mut out, "Private-Lines", &file.private_blob);
+    out.push_str(&format!("Private-MAC: {}\n", hex_encode(&file.mac)));
+    Ok(out)
+}
+
+/// Read the unencrypted public key of a PPK file
+pub(super) fn read_public_key(content: &str) -> DatabaseResult<PublicKey> {
+    let file = parse(content)?;
+    let mut public = PublicKey::from_bytes(&file.public_blob).map_err(invalid_key)?;
+    public.set_comment(file.comment);
+    Ok(public)
+}
+
+pub(super) fn is_encrypted(content: &str) -> DatabaseResult<bool> {
+    Ok(parse(content)?.encryption != "none")
+}
+
+fn parse(content: &str) -> DatabaseResult<PpkFile> {
+    let mut lines = content.lines().map(|line| line.trim_end_matches('\r'));
+    let mut version = None;
+    let mut algorithm = None;
+    let mut public_blob = None;
+    let mut private_blob = None;
+    let mut headers = HashMap::new();
+
+    while let Some(line) = lines.next() {
+        if line.is_empty() {
+            continue;
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| invalid_key(format!("malformed PPK line '{}'", line)))?;
+        let value = value.strip_prefix(' ').unwrap_or(value);
+
+        if let Some(file_version) = name.strip_prefix(HEADER_PREFIX) {
+            version = Some(
+                file_version
+                    .parse::<u8>()
+                    .map_err(|_| invalid_key("unknown PPK version"))?,
+            );
+            algorithm = Some(value.to_string());
+            continue;
+        }
+        match name {
+            "Public-Lines" => public_blob = Some(read_lines(&mut lines, value)?),
+            "Private-Lines" => private_blob = Some(read_lines(&mut lines, value)?),
+            _ => {
+                headers.insert(name.to_string(), value.to_string());
+            }
+        }
+    }
+
+    let version = version.ok_or_else(|| invalid_key("missing PPK header"))?;
+    if !(2..=3).contains(&version) {
+        return Err(DatabaseError::ValidationError(format!(
+            "Unsupported PPK version: {}",
+            version
+        )));
+    }
+    let mut field = |name: &str| {
+        headers
+            .remove(name)
+            .ok_or_else(|| invalid_key(format!("missing PPK field '{}'", name)))
+    };
+
+    Ok(PpkFile {
+        version,
+        algorithm: algorithm.unwrap_or_default(),
+        encryption: field("Encryption")?,
+        comment: field("Comment")?,
+        mac: hex_decode(&field("Private-MAC")?)?,
+        public_blob: public_blob.ok_or_else(|| invalid_key("missing PPK public key"))?,
+        private_blob: private_blob.ok_or_else(|| invalid_key("missing PPK private key"))?,
+        headers,
+    })
+}
+
+fn read_lines<'a>(
+    lines: &mut impl Iterator<Item = &'a str>,
+    count: &str,
+) -> DatabaseResult<Vec<u8>> {
+    let count: usize = count
+        .parse()
+        .map_err(|_| invalid_key("invalid PPK line count"))?;
+    let encoded: String = lines.by_ref().take(count).collect();
+    STANDARD.decode(encoded).map_err(invalid_key)
+}
+
+fn write_lines(out: &mut String, name: &str, blob: &[u8]) {
+    let encoded = STANDARD.encode(blob);
+    let lines: Vec<&str> = encoded
+        .as_bytes()
+        .chunks(LINE_WIDTH)
+        .filter_map(|chunk| std::str::from_utf8(chunk).ok())
+        .collect();
+    out.push_str(&format!("{}: {}\n", name, lines.len()));
+    for line in lines {
+        out.push_str(line);
+        out.push('\n');
+    }
+}
+
+fn derive_keys(file: &PpkFile, passphrase: &str) -> DatabaseResult<FileKeys> {
+    let encrypted = file.encryption != "none";
+
+    if file.version == 2 {
+        let mut cipher_key = [0u8; 32];
+        let first = Sha1::new()
+            .chain_update([0, 0, 0, 0])
+            .chain_update(passphrase)
+            .finalize();
+        let second = Sha1::new()
+            .chain_update([0, 0, 0, 1])
+            .chain_update(passphrase)
+            .finalize();
+        cipher_key[..20].copy_from_slice(&first);
+        cipher_key[20..].copy_from_slice(&second[..12]);
+        let mac_key = Sha1::new()
+            .chain_update("putty-private-key-file-mac-key")
+            .chain_update(passphrase)
+            .finalize()
+            .to_vec();
+        return Ok(FileKeys {
+            cipher_key,
+            iv: [0u8; 16],
+            mac_key,
+        });
+    }
+
+    if !encrypted {
+        return Ok(FileKeys {
+            cipher_key: [0u8; 32],
+            iv: [0u8; 16],
+            mac_key: Vec::new(),
+        });
+    }
+
+    let header = |name: &str| {
+        file.headers
+            .get(name)
+            .ok_or_else(|| invalid_key(format!("missing PPK field '{}'", name)))
+    };
+    let number = |name: &str| -> DatabaseResult<u32> {
+        header(name)?
+            .parse()
+            .map_err(|_| invalid_key(format!("invalid PPK field '{}'", name)))
+    };
+    let flavour = match header("Key-Derivation")?.as_str() {
+        "Argon2id" => argon2::Algorithm::Argon2id,
+        "Argon2i" => argon2::Algorithm::Argon2i,
+        "Argon2d" => argon2::Algorithm::Argon2d,
+        other => {
+            return Err(DatabaseError::ValidationError(format!(
+                "Unsupported PPK key derivation: {}",
+                other
+            )))
+        }
+    };
+    let salt = hex_decode(header("Argon2-Salt")?)?;
+    let params = argon2::Params::new(
+        number("Argon2-Memory")?,
+        number("Argon2-Passes")?,
+        number("Argon2-Parallelism")?,
+        Some(80),
+    )
+    .map_err(invalid_key)?;
+
+    let mut output = [0u8; 80];
+    argon2::Argon2::new(flavour, argon2::Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), &salt, &mut output)
+        .map_err(internal)?;
+
+    let mut cipher_key = [0u8; 32];
+    let mut iv = [0u8; 16];
+    cipher_key.copy_from_slice(&output[..32]);
+    iv.copy_from_slice(&output[32..48]);
+    Ok(FileKeys {
+        cipher_key,
+        iv,
+        mac_key: output[48..].to_vec(),
+    })
+}
+
+/// Data covered by the Private-MAC line
+fn mac_data(file: &PpkFile, private_blob: &[u8]) -> Vec<u8> {
+    let mut data = Vec::new();
+    write_string(&mut data, file.algorithm.as_bytes());
+    write_string(&mut data, file.encryption.as_bytes());
+    write_string(&mut data, file.comment.as_bytes());
+    write_string(&mut data, &file.public_blob);
+    write_string(&mut data, private_blob);
+    data
+}
+
+fn keypair_data(public: &KeyData, private_blob: &[u8]) -> DatabaseResult<KeypairData> {
+    let mut reader = private_blob;
+
+    match public {
+        KeyData::Ed25519(_) => {
+            // PuTTY stores the seed as an unsigned little-endian integer, dropping high zero bytes
+            let value = read_string(&mut reader)?;
+            if value.len() > 32 {
+                return Err(invalid_key("Ed25519 private key is too long"));
+            }
+            let mut seed = [0u8; 32];
+            seed[..value.len()].copy_from_slice(value);
+            Ok(Ed25519Keypair::from_seed(&seed).into())
+        }
+        KeyData::Rsa(public) => {
+            let d = read_mpint(&mut reader)?;
+            let p = read_mpint(&mut reader)?;
+            let q = read_mpint(&mut reader)?;
+            let iqmp = read_mpint(&mut reader)?;
+            Ok(RsaKeypair {
+                public: public.clone(),
+                private: RsaPrivateKey { d, iqmp, p, q },
+            }
+            .into())
+        }
+        KeyData::Ecdsa(public) => {
+            let scalar = read_string(&mut reader)?;
+            let scalar = strip_leading_zeros(scalar);
+            let keypair = match public.curve() {
+                EcdsaCurve::NistP256 => super::p256_keypair(
+                    p256::SecretKey::from_slice(&left_pad(scalar, 32)?).map_err(invalid_key)?,
+                ),
+                EcdsaCurve::NistP384 => super::p384_keypair(
+                    p384::SecretKey::from_slice(&left_pad(scalar, 48)?).map_err(invalid_key)?,
+                ),
+                EcdsaCurve::NistP521 => super::p521_keypair(
+                    p521::SecretKey::from_slice(&left_pad(scalar, 66)?).map_err(invalid_key)?,
+                ),
+            };
+            Ok(keypair)
+        }
+        other => Err(DatabaseError::ValidationError(format!(
+            "Unsupported PPK key algorithm: {}",
+            other.algorithm()
+        ))),
+    }
+}
+
+fn private_blob(key_data: &KeypairData) -> DatabaseResult<Vec<u8>> {
+    let mut blob = Vec::new();
+
+    match key_data {
+        KeypairData::Ed25519(keypair) => {
+            // Full width, which readers of either integer convention accept
+            write_string(&mut blob, &keypair.private.to_bytes());
+        }
+        KeypairData::Rsa(keypair) => {
+            write_string(&mut blob, keypair.private.d.as_bytes());
+            write_string(&mut blob, keypair.private.p.as_bytes());
+            write_string(&mut blob, keypair.private.q.as_bytes());
+            write_string(&mut blob, keypair.private.iqmp.as_bytes());
+        }
+        KeypairData::Ecdsa(keypair) => {
+            let scalar =
+                Mpint::from_positive_bytes(keypair.private_key_bytes()).map_err(internal)?;
+            write_string(&mut blob, scalar.as_bytes());
+        }
+        other => {
+            return Err(DatabaseError::ValidationError(format!(
+                "{} keys cannot be written as PPK",
+                other
+                    .algorithm()
+                    .map(|a| a.to_string())
+                    .unwrap_or_else(|_| "Unknown".to_string())
+            )))
+        }
+    }
+
+    Ok(blob)
+}
+
+fn read_string<'a>(reader: &mut &'a [u8]) -> DatabaseResult<&'a [u8]> {
+    if reader.len() < 4 {
+        return Err(invalid_key("truncated PPK private key"));
+    }
+    let (len, rest) = reader.split_at(4);
+    let len = u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize;
+    if rest.len() < len {
+        return Err(invalid_key("truncated PPK private key"));
+    }
+    let (value, rest) = rest.split_at(len);
+    *reader = rest;
+    Ok(value)
+}
+
+fn read_mpint(reader: &mut &[u8]) -> DatabaseResult<Mpint> {
+    Mpint::from_bytes(read_string(reader)?).map_err(invalid_key)
+}
+
+fn write_string(out: &mut Vec<u8>, value: &[u8]) {
+    out.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    out.extend_from_slice(value);
+}
+
+fn strip_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+fn left_pad(bytes: &[u8], len: usize) -> DatabaseResult<Vec<u8>> {
+    if bytes.len() > len {
+        return Err(invalid_key("ECDSA private key is too long"));
+    }
+    let mut padded = vec![0u8; len - bytes.len()];
+    padded.extend_from_slice(bytes);
+    Ok(padded)
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hex_decode(value: &str) -> DatabaseResult<Vec<u8>> {
+    if !value.is_ascii() || !value.len().is_multiple_of(2) {
+        return Err(invalid_key("invalid hex in PPK file"));
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&value[i..i + 2], 16)
+                .map_err(|_| invalid_key("invalid hex in PPK file"))
+        })
+        .collect()
+}
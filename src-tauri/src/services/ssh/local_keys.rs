@@ -2,6 +2,9 @@ use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
 
+use super::key_format;
+use crate::models::ssh::profile::KeyType;
+
 /// Represents a discovered local SSH key
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LocalSSHKey {
@@ -95,10 +98,15 @@ fn try_parse_ssh_key(path: &PathBuf) -> Option<LocalSSHKey> {
         None
     };
 
-    // Try to get fingerprint from public key
+    // Fingerprint the public key, or the public half the private key file carries
     let fingerprint = public_key_path
         .as_ref()
-        .and_then(|_| get_key_fingerprint(path).ok());
+        .and_then(|pub_path| fs::read_to_string(pub_path).ok())
+        .and_then(|public_key| key_format::fingerprint(&public_key))
+        .or_else(|| {
+            key_format::read_public_key(&content, None)
+                .map(|key| key.fingerprint(ssh_key::HashAlg::Sha256).to_string())
+        });
 
     Some(LocalSSHKey {
         name: file_name.to_string(),
@@ -112,6 +120,18 @@ fn try_parse_ssh_key(path: &PathBuf) -> Option<LocalSSHKey> {
 
 /// Detect the type of SSH key from its content
 fn detect_key_type(content: &str) -> String {
+    if let Some(key_type) = key_format::detect_key_type(content) {
+        return match key_type {
+            KeyType::RSA => "RSA",
+            KeyType::Ed25519 => "Ed25519",
+            KeyType::ECDSA => "ECDSA",
+            KeyType::DSA => "DSA",
+            KeyType::SkEd25519 => "Ed25519-SK",
+            KeyType::SkECDSA => "ECDSA-SK",
+        }
+        .to_string();
+    }
+
     if content.contains("BEGIN RSA PRIVATE KEY") {
         "RSA".to_string()
     } else if content.contains("BEGIN EC PRIVATE KEY") || content.contains("ecdsa") {
@@ -138,6 +158,10 @@ fn detect_key_type(content: &str) -> String {
 
 /// Detect if the key appears to be encrypted (has passphrase)
 fn detect_passphrase(content: &str) -> bool {
+    if let Some(encrypted) = key_format::is_encrypted(content) {
+        return encrypted;
+    }
+
     // Old PEM format encryption headers
     if content.contains("ENCRYPTED") || content.contains("Proc-Type: 4,ENCRYPTED") {
         return true;
@@ -155,29 +179,6 @@ fn detect_passphrase(content: &str) -> bool {
     false
 }
 
-/// Try to get the key fingerprint using ssh-keygen
-fn get_key_fingerprint(key_path: &PathBuf) -> Result<String, String> {
-    use std::process::Command;
-
-    let output = Command::new("ssh-keygen")
-        .args(["-lf", &key_path.to_string_lossy()])
-        .output()
-        .map_err(|e| format!("Failed to run ssh-keygen: {}", e))?;
-
-    if output.status.success() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        // Extract fingerprint from output like "2048 SHA256:xxx... comment (RSA)"
-        if let Some(fp_start) = stdout.find("SHA256:") {
-            let fp_part = &stdout[fp_start..];
-            if let Some(space_pos) = fp_part.find(' ') {
-                return Ok(fp_part[..space_pos].to_string());
-            }
-        }
-    }
-
-    Err("Could not extract fingerprint".to_string())
-}
-
 /// Parse SSH config file for IdentityFile entries
 fn parse_ssh_config_keys(ssh_dir: &PathBuf) -> Result<Vec<LocalSSHKey>, String> {
     let config_path = ssh_dir.join("config");
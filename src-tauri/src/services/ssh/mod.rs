@@ -1,20 +1,41 @@
+pub mod certificate_authority;
+pub mod config_export;
 pub mod connection_pool;
+pub mod hardware_key;
+pub mod import;
 pub mod key;
+pub mod key_deploy;
+pub mod key_format;
 pub mod local_keys;
 
 use anyhow;
+use futures::stream::{self, StreamExt};
+use std::collections::HashSet;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+pub use certificate_authority::CertificateAuthorityService;
+pub use config_export::SSHConfigExporter;
 pub use connection_pool::SSHConnectionPool;
+pub use import::ConnectionImportService;
 pub use key::SSHKeyService;
 pub use local_keys::{scan_local_ssh_keys, LocalSSHKey};
 
-use crate::database::{error::DatabaseResult, service::DatabaseService};
+use crate::database::{
+    error::{DatabaseError, DatabaseResult},
+    service::DatabaseService,
+};
+use crate::models::ssh::key::ResolvedSSHKey;
+use crate::models::ssh::profile::AuthMethod;
 use crate::models::ssh::{
-    CreateSSHGroupRequest, CreateSSHProfileRequest, DeleteGroupAction, SSHGroup, SSHProfile,
-    TestSSHConnectionRequest, UpdateSSHGroupRequest, UpdateSSHProfileRequest,
+    AuthData, CreateSSHGroupRequest, CreateSSHProfileRequest, DeleteGroupAction,
+    DeploySSHKeyRequest, KeyDeployReport, KeyDeployResult, KeyDeployStatus, RotateSSHKeyRequest,
+    SSHGroup, SSHProfile, TestSSHConnectionRequest, UpdateSSHGroupRequest, UpdateSSHProfileRequest,
 };
+use key_deploy::{AuthorizedKey, InstallOutcome};
+
+/// Hosts contacted at the same time during a key deploy
+const DEPLOY_CONCURRENCY: usize = 4;
 
 /// SSH service for handling SSH profiles and groups
 pub struct SSHService {
@@ -96,6 +117,19 @@ impl SSHService {
         db_service.get_ssh_profile(id).await
     }
 
+    /// Get SSH profile for a new connection
+    /// Renews the profile's certificate first when a local CA issued it and it expires soon
+    pub async fn get_ssh_profile_for_connect(&self, id: &str) -> DatabaseResult<SSHProfile> {
+        let db_service = self.database_service.lock().await;
+        if let Err(e) = certificate_authority::renew_expiring_certificate(&db_service, id).await {
+            eprintln!(
+                "Warning: Failed to renew certificate for SSH profile {}: {}",
+                id, e
+            );
+        }
+        db_service.get_ssh_profile(id).await
+    }
+
     /// Update SSH profile
     pub async fn update_ssh_profile(
         &self,
@@ -200,4 +234,349 @@ impl SSHService {
 
         Ok(())
     }
+
+    /// Install an SSH key's public key on the target profiles' hosts
+    ///
+    /// Each host is reached with the profile's current credentials, then login with the
+    /// deployed key is verified before the profile is optionally switched over to it.
+    pub async fn deploy_ssh_key(
+        &self,
+        request: DeploySSHKeyRequest,
+    ) -> DatabaseResult<KeyDeployReport> {
+        let profiles = self
+            .resolve_deploy_targets(&request.profile_ids, request.group_id.as_deref())
+            .await?;
+        if profiles.is_empty() {
+            return Err(DatabaseError::ValidationError(
+                "No profiles selected for key deployment".to_string(),
+            ));
+        }
+
+        self.run_deploy(&request.key_id, profiles, request.switch_auth, None)
+            .await
+    }
+
+    /// Replace one SSH key with another on the target profiles' hosts
+    ///
+    /// The new key is deployed and verified, profiles are switched to it, and only then is
+    /// the old key removed from authorized_keys over a session authenticated with the new key.
+    pub async fn rotate_ssh_key(
+        &self,
+        request: RotateSSHKeyRequest,
+    ) -> DatabaseResult<KeyDeployReport> {
+        if request.old_key_id == request.new_key_id {
+            return Err(DatabaseError::ValidationError(
+                "The new key must differ from the key being rotated".to_string(),
+            ));
+        }
+
+        let profiles = if request.profile_ids.is_empty() && request.group_id.is_none() {
+            self.get_ssh_profiles()
+                .await?
+                .into_iter()
+                .filter(|profile| {
+                    matches!(&profile.auth_data,
+                        AuthData::KeyReference { key_id } if key_id == &request.old_key_id)
+                })
+                .collect()
+        } else {
+            self.resolve_deploy_targets(&request.profile_ids, request.group_id.as_deref())
+                .await?
+        };
+        if profiles.is_empty() {
+            return Err(DatabaseError::ValidationError(
+                "No profiles use the key being rotated".to_string(),
+            ));
+        }
+
+        let old_key = {
+            let key_service = self.ssh_key_service.lock().await;
+            key_service.get_ssh_key(&request.old_key_id).await?
+        };
+        let old_blob = AuthorizedKey::from_key(&old_key)?.blob;
+
+        self.run_deploy(&request.new_key_id, profiles, true, Some(old_blob))
+            .await
+    }
+
+    /// Collect the requested profiles and the profiles of the requested group, in order
+    async fn resolve_deploy_targets(
+        &self,
+        profile_ids: &[String],
+        group_id: Option<&str>,
+    ) -> DatabaseResult<Vec<SSHProfile>> {
+        let mut profiles = Vec::new();
+        for id in profile_ids {
+            profiles.push(self.get_ssh_profile(id).await?);
+        }
+        if let Some(group_id) = group_id {
+            let db_service = self.database_service.lock().await;
+            profiles.extend(db_service.get_ssh_profiles(Some(group_id)).await?);
+        }
+
+        let mut seen = HashSet::new();
+        profiles.retain(|profile| seen.insert(profile.base.id.clone()));
+        Ok(profiles)
+    }
+
+    /// Deploy a key to every profile, a few hosts at a time
+    async fn run_deploy(
+        &self,
+        key_id: &str,
+        profiles: Vec<SSHProfile>,
+        switch_auth: bool,
+        remove_blob: Option<String>,
+    ) -> DatabaseResult<KeyDeployReport> {
+        let (key, authorized_key) = {
+            let key_service = self.ssh_key_service.lock().await;
+            let key = key_service.get_ssh_key(key_id).await?;
+            (
+                ResolvedSSHKey::from_key(&key),
+                AuthorizedKey::from_key(&key)?,
+            )
+        };
+        // A new key sharing the old key's public key must not be removed again
+        let remove_blob = remove_blob.filter(|blob| *blob != authorized_key.blob);
+
+        let results = stream::iter(profiles)
+            .map(|profile| {
+                self.deploy_to_profile(
+                    profile,
+                    key_id,
+                    &key,
+                    &authorized_key,
+                    switch_auth,
+                    remove_blob.as_deref(),
+                )
+            })
+            .buffered(DEPLOY_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await;
+
+        Ok(KeyDeployReport {
+            key_id: key_id.to_string(),
+            results,
+        })
+    }
+
+    /// Deploy a key to one host and record what happened
+    async fn deploy_to_profile(
+        &self,
+        profile: SSHProfile,
+        key_id: &str,
+        key: &ResolvedSSHKey,
+        authorized_key: &AuthorizedKey,
+        switch_auth: bool,
+        remove_blob: Option<&str>,
+    ) -> KeyDeployResult {
+        let mut result = KeyDeployResult {
+            profile_id: profile.base.id.clone(),
+            profile_name: profile.name.clone(),
+            host: format!("{}@{}:{}", profile.username, profile.host, profile.port),
+            status: KeyDeployStatus::Failed,
+            verified: false,
+            auth_switched: false,
+            old_key_removed: None,
+            error: None,
+        };
+
+        // Install with the credentials the profile uses today
+        let current_key = match &profile.auth_data {
+            AuthData::KeyReference { key_id } => {
+                let key_service = self.ssh_key_service.lock().await;
+                match key_service.resolve_key_for_auth(key_id).await {
+                    Ok(resolved) => Some(resolved),
+                    Err(e) => {
+                        result.error = Some(format!("Failed to resolve current key: {}", e));
+                        return result;
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        let installed = match key_deploy::connect(&profile, current_key.as_ref()).await {
+            Ok(session) => {
+                let installed = key_deploy::install_key(&session, authorized_key).await;
+                key_deploy::disconnect(session).await;
+                installed
+            }
+            Err(e) => Err(e),
+        };
+        match installed {
+            Ok(InstallOutcome::Added) => result.status = KeyDeployStatus::Added,
+            Ok(InstallOutcome::AlreadyPresent) => result.status = KeyDeployStatus::AlreadyPresent,
+            Err(e) => {
+                result.error = Some(e.to_string());
+                return result;
+            }
+        }
+
+        // Verify with a fresh login that only offers the deployed key
+        let session = match key_deploy::connect(&profile, Some(key)).await {
+            Ok(session) => session,
+            Err(e) => {
+                result.error = Some(format!("Key installed but login with it failed: {}", e));
+                return result;
+            }
+        };
+        result.verified = true;
+
+        let uses_key = matches!(&profile.auth_data,
+            AuthData::KeyReference { key_id: current } if current == key_id);
+        if switch_auth && !uses_key {
+            let update = UpdateSSHProfileRequest {
+                auth_method: Some(AuthMethod::KeyReference),
+                auth_data: Some(AuthData::KeyReference {
+                    key_id: key_id.to_string(),
+                }),
+                ..Default::default()
+            };
+            match self.update_ssh_profile(&profile.base.id, update).await {
+                Ok(_) => result.auth_switched = true,
+                Err(e) => result.error = Some(format!("Failed to update profile: {}", e)),
+            }
+        }
+
+        // Only drop the old key once the profile no longer depends on it
+        if let Some(blob) = remove_blob {
+            if uses_key || result.auth_switched {
+                match key_deploy::remove_key(&session, blob).await {
+                    Ok(removed) => result.old_key_removed = Some(removed),
+                    Err(e) => {
+                        result.old_key_removed = Some(false);
+                        result.error = Some(format!("Failed to remove old key: {}", e));
+                    }
+                }
+            }
+        }
+
+        key_deploy::disconnect(session).await;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ssh::profile::KeyType;
+    use crate::models::ssh::GenerateSSHKeyRequest;
+    use crate::services::sync::fixture;
+
+    async fn ssh_service() -> SSHService {
+        let dir = fixture::temp_dir();
+        let database_service =
+            Arc::new(Mutex::new(fixture::database_service(&dir, "device").await));
+        let ssh_key_service = Arc::new(Mutex::new(SSHKeyService::new(database_service.clone())));
+        SSHService::new(database_service, ssh_key_service)
+    }
+
+    async fn generate_key(service: &SSHService, name: &str) -> String {
+        let key_service = service.ssh_key_service.lock().await;
+        key_service
+            .generate_ssh_key(GenerateSSHKeyRequest {
+                name: name.to_string(),
+                key_type: KeyType::Ed25519,
+                bits: None,
+                comment: None,
+                passphrase: None,
+                description: None,
+            })
+            .await
+            .unwrap()
+            .base
+            .id
+    }
+
+    /// Profile logging in with the key on a port nothing listens on
+    async fn unreachable_profile(service: &SSHService, key_id: &str) -> SSHProfile {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        service
+            .create_ssh_profile(CreateSSHProfileRequest {
+                name: "web".to_string(),
+                host: "127.0.0.1".to_string(),
+                port: Some(port),
+                username: "deploy".to_string(),
+                group_id: None,
+                auth_method: AuthMethod::KeyReference,
+                auth_data: AuthData::KeyReference {
+                    key_id: key_id.to_string(),
+                },
+                timeout: Some(5),
+                keep_alive: None,
+                compression: None,
+                proxy: None,
+                color: None,
+                description: None,
+                command: None,
+                working_dir: None,
+                env: None,
+            })
+            .await
+            .unwrap()
+    }
+
+    fn rotate_request(old_key_id: &str, new_key_id: &str) -> RotateSSHKeyRequest {
+        RotateSSHKeyRequest {
+            old_key_id: old_key_id.to_string(),
+            new_key_id: new_key_id.to_string(),
+            profile_ids: Vec::new(),
+            group_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rotate_targets_profiles_using_the_old_key() {
+        let service = ssh_service().await;
+        let old_key = generate_key(&service, "old").await;
+        let new_key = generate_key(&service, "new").await;
+
+        assert!(service
+            .rotate_ssh_key(rotate_request(&old_key, &old_key))
+            .await
+            .is_err());
+        assert!(service
+            .rotate_ssh_key(rotate_request(&old_key, &new_key))
+            .await
+            .is_err());
+
+        unreachable_profile(&service, &new_key).await;
+        let profile = unreachable_profile(&service, &old_key).await;
+        let report = service
+            .rotate_ssh_key(rotate_request(&old_key, &new_key))
+            .await
+            .unwrap();
+
+        assert_eq!(report.results.len(), 1);
+        assert_eq!(report.results[0].profile_id, profile.base.id);
+    }
+
+    #[tokio::test]
+    async fn test_failed_rotation_keeps_profile_on_old_key() {
+        let service = ssh_service().await;
+        let old_key = generate_key(&service, "old").await;
+        let new_key = generate_key(&service, "new").await;
+        let profile = unreachable_profile(&service, &old_key).await;
+
+        let report = service
+            .rotate_ssh_key(rotate_request(&old_key, &new_key))
+            .await
+            .unwrap();
+
+        let result = &report.results[0];
+        assert_eq!(result.status, KeyDeployStatus::Failed);
+        assert!(result.error.is_some());
+        assert!(!result.verified);
+        assert!(!result.auth_switched);
+        assert_eq!(result.old_key_removed, None);
+
+        let profile = service.get_ssh_profile(&profile.base.id).await.unwrap();
+        assert!(matches!(profile.auth_data,
+            AuthData::KeyReference { key_id } if key_id == old_key));
+    }
 }
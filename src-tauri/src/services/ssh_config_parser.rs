@@ -1,131 +1,698 @@
 use crate::error::AppError;
 use crate::models::ssh::SSHConfigHost;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// Deepest chain of `Include` directives followed, as in OpenSSH
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Directive of a config file
+#[derive(Debug, Clone, PartialEq)]
+enum ConfigEntry {
+    /// `Host` patterns; the options after it apply when they match
+    Host(Vec<String>),
+    /// `Match` criteria with their arguments
+    Match(Vec<String>),
+    /// Entries of the included files, evaluated inside the enclosing block
+    Include(Vec<ConfigEntry>),
+    /// Any other keyword, lowercased, with its arguments and the unsplit value
+    Option {
+        keyword: String,
+        args: Vec<String>,
+        raw: String,
+    },
+}
 
 /**
- * Parse SSH config file and extract host configurations
+ * Parsed SSH config with its includes loaded
+ *
+ * Options are resolved per host the way ssh does: entries are read top to bottom, a `Host` or
+ * `Match` line decides whether the options after it apply, and the first value of an option
+ * wins while IdentityFile, CertificateFile and forwards accumulate.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct SSHConfigFile {
+    entries: Vec<ConfigEntry>,
+}
+
+impl SSHConfigFile {
+    /**
+     * Parse config content, reading included files from disk
+     *
+     * @param content - Config file content
+     * @param base_dir - Directory relative include paths resolve against
+     * @returns Parsed config
+     */
+    pub fn parse(content: &str, base_dir: &Path) -> Result<Self, AppError> {
+        Ok(Self {
+            entries: parse_entries(content, base_dir, 0)?,
+        })
+    }
+
+    /**
+     * Read a config file and the files it includes
+     *
+     * Relative `Include` paths resolve against the directory of this file.
+     *
+     * @param path - Config file to read
+     * @returns Parsed config
+     */
+    pub fn load(path: &Path) -> Result<Self, AppError> {
+        let base_dir = path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."));
+
+        Ok(Self {
+            entries: read_config_file(path, &base_dir, 0)?,
+        })
+    }
+
+    /**
+     * Host aliases named by `Host` lines, without wildcard or negated patterns
+     *
+     * @returns Aliases in the order they first appear
+     */
+    pub fn host_aliases(&self) -> Vec<String> {
+        fn collect(entries: &[ConfigEntry], aliases: &mut Vec<String>) {
+            for entry in entries {
+                match entry {
+                    ConfigEntry::Host(patterns) => {
+                        for pattern in patterns {
+                            let concrete =
+                                !pattern.starts_with('!') && !pattern.contains(['*', '?']);
+                            if concrete && !aliases.contains(pattern) {
+                                aliases.push(pattern.clone());
+                            }
+                        }
+                    }
+                    ConfigEntry::Include(included) => collect(included, aliases),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut aliases = Vec::new();
+        collect(&self.entries, &mut aliases);
+        aliases
+    }
+
+    /**
+     * Effective configuration of every alias the config names
+     *
+     * @returns Resolved hosts in config order
+     */
+    pub fn hosts(&self) -> Vec<SSHConfigHost> {
+        self.host_aliases()
+            .iter()
+            .map(|alias| self.resolve(alias))
+            .collect()
+    }
+
+    /**
+     * Effective configuration for connecting to a host alias
+     *
+     * `Match final` and `Match canonical` blocks are applied in a second pass, as ssh does
+     * without hostname canonicalization. `Match exec` and address criteria cannot be
+     * evaluated before connecting, so blocks using them are skipped.
+     *
+     * @param alias - Host as it would be given to ssh
+     * @returns Resolved host with tokens in paths and ProxyCommand expanded
+     */
+    pub fn resolve(&self, alias: &str) -> SSHConfigHost {
+        let mut resolver = Resolver {
+            alias,
+            local_user: local_username(),
+            final_pass: false,
+            wants_final_pass: false,
+            options: ResolvedOptions::default(),
+        };
+        resolver.apply(&self.entries, true);
+        if resolver.wants_final_pass {
+            resolver.final_pass = true;
+            resolver.apply(&self.entries, true);
+        }
+
+        resolver.into_host()
+    }
+}
+
+/// Options collected while evaluating the config for one host
+#[derive(Debug, Default)]
+struct ResolvedOptions {
+    hostname: Option<String>,
+    port: Option<u16>,
+    user: Option<String>,
+    identity_files: Vec<String>,
+    certificate_files: Vec<String>,
+    proxy_jump: Option<String>,
+    proxy_command: Option<String>,
+    forward_agent: Option<bool>,
+    server_alive_interval: Option<u32>,
+    compression: Option<bool>,
+    local_forwards: Vec<String>,
+    remote_forwards: Vec<String>,
+    dynamic_forwards: Vec<String>,
+}
+
+impl ResolvedOptions {
+    /// Apply an option unless an earlier block already set it
+    fn set(&mut self, keyword: &str, args: &[String], raw: &str) {
+        let Some(value) = args.first() else {
+            return;
+        };
+        let push = |list: &mut Vec<String>, value: String| {
+            if !list.contains(&value) {
+                list.push(value);
+            }
+        };
+
+        match keyword {
+            "hostname" => {
+                self.hostname.get_or_insert_with(|| value.clone());
+            }
+            "port" if self.port.is_none() => self.port = value.parse().ok(),
+            "user" => {
+                self.user.get_or_insert_with(|| value.clone());
+            }
+            "identityfile" => push(&mut self.identity_files, value.clone()),
+            "certificatefile" => push(&mut self.certificate_files, value.clone()),
+            "proxyjump" => {
+                self.proxy_jump.get_or_insert_with(|| value.clone());
+            }
+            "proxycommand" => {
+                // The command is handed to a shell, so it keeps its quoting
+                self.proxy_command.get_or_insert_with(|| raw.to_string());
+            }
+            "forwardagent" => {
+                self.forward_agent
+                    .get_or_insert_with(|| !value.eq_ignore_ascii_case("no"));
+            }
+            "serveraliveinterval" if self.server_alive_interval.is_none() => {
+                self.server_alive_interval = value.parse().ok()
+            }
+            "compression" => {
+                self.compression
+                    .get_or_insert_with(|| value.eq_ignore_ascii_case("yes"));
+            }
+            "localforward" => push(&mut self.local_forwards, args.join(" ")),
+            "remoteforward" => push(&mut self.remote_forwards, args.join(" ")),
+            "dynamicforward" => push(&mut self.dynamic_forwards, args.join(" ")),
+            _ => {}
+        }
+    }
+}
+
+/// Evaluates config entries for one host alias
+struct Resolver<'a> {
+    alias: &'a str,
+    local_user: String,
+    /// Second pass, where `Match final` and `Match canonical` apply
+    final_pass: bool,
+    /// A `Match final` or `Match canonical` asked for the second pass
+    wants_final_pass: bool,
+    options: ResolvedOptions,
+}
+
+impl Resolver<'_> {
+    /**
+     * Apply the options of matching blocks
+     *
+     * @param entries - Entries of one file
+     * @param enabled - Whether the block enclosing these entries applies; an included file
+     *                  never applies under a block that does not
+     */
+    fn apply(&mut self, entries: &[ConfigEntry], enabled: bool) {
+        let mut active = enabled;
+        for entry in entries {
+            match entry {
+                ConfigEntry::Host(patterns) => {
+                    active = enabled && host_matches(patterns, self.alias);
+                }
+                ConfigEntry::Match(criteria) => {
+                    active = enabled && self.match_criteria(criteria);
+                }
+                ConfigEntry::Include(included) => self.apply(included, active),
+                ConfigEntry::Option { keyword, args, raw } => {
+                    if active {
+                        self.options.set(keyword, args, raw);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Host name the connection goes to so far, for `Match host`
+    fn target_host(&self) -> String {
+        match &self.options.hostname {
+            Some(hostname) => expand_tokens(hostname, &[('h', self.alias)]),
+            None => self.alias.to_string(),
+        }
+    }
+
+    /**
+     * Evaluate the criteria of a `Match` line; all of them must hold
+     *
+     * @param criteria - Words after `Match`
+     * @returns Whether the block applies
+     */
+    fn match_criteria(&mut self, criteria: &[String]) -> bool {
+        let mut words = criteria.iter();
+        let mut matched = true;
+
+        while let Some(word) = words.next() {
+            let (negate, attribute) = match word.strip_prefix('!') {
+                Some(attribute) => (true, attribute.to_lowercase()),
+                None => (false, word.to_lowercase()),
+            };
+
+            let result = match attribute.as_str() {
+                "all" => true,
+                "canonical" | "final" => {
+                    self.wants_final_pass = true;
+                    self.final_pass
+                }
+                _ => {
+                    let Some(list) = words.next() else {
+                        return false;
+                    };
+                    match attribute.as_str() {
+                        "host" => pattern_list_matches(
+                            &list.to_lowercase(),
+                            &self.target_host().to_lowercase(),
+                        ),
+                        "originalhost" => {
+                            pattern_list_matches(&list.to_lowercase(), &self.alias.to_lowercase())
+                        }
+                        "user" => pattern_list_matches(
+                            list,
+                            self.options.user.as_deref().unwrap_or(&self.local_user),
+                        ),
+                        "localuser" => pattern_list_matches(list, &self.local_user),
+                        // exec, address, tagged and the like need a command run or a connection
+                        _ => return false,
+                    }
+                }
+            };
+
+            if result == negate {
+                matched = false;
+            }
+        }
+
+        matched
+    }
+
+    /// Build the host, expanding `%` tokens the way ssh does after reading the config
+    fn into_host(self) -> SSHConfigHost {
+        let options = self.options;
+        let hostname = match &options.hostname {
+            Some(hostname) => expand_tokens(hostname, &[('h', self.alias)]),
+            None => self.alias.to_string(),
+        };
+        let port = options.port.unwrap_or(22);
+        let port_string = port.to_string();
+        let remote_user = options
+            .user
+            .clone()
+            .unwrap_or_else(|| self.local_user.clone());
+        let home = dirs::home_dir()
+            .map(|home| home.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let tokens = [
+            ('h', hostname.as_str()),
+            ('p', port_string.as_str()),
+            ('r', remote_user.as_str()),
+            ('n', self.alias),
+            ('u', self.local_user.as_str()),
+            ('d', home.as_str()),
+        ];
+        let expand_path = |path: &String| expand_tokens(&expand_tilde(path), &tokens);
+        let unless_none = |value: Option<String>| value.filter(|v| !v.eq_ignore_ascii_case("none"));
+
+        let identity_files: Vec<String> = options.identity_files.iter().map(expand_path).collect();
+
+        SSHConfigHost {
+            name: self.alias.to_string(),
+            hostname: hostname.clone(),
+            port,
+            user: options.user,
+            identity_file: identity_files.first().cloned(),
+            identity_files,
+            certificate_files: options.certificate_files.iter().map(expand_path).collect(),
+            proxy_jump: unless_none(options.proxy_jump),
+            proxy_command: unless_none(options.proxy_command)
+                .map(|command| expand_tokens(&command, &tokens)),
+            forward_agent: options.forward_agent,
+            other_options: None,
+            local_forwards: options.local_forwards,
+            remote_forwards: options.remote_forwards,
+            dynamic_forwards: options.dynamic_forwards,
+            server_alive_interval: options.server_alive_interval,
+            compression: options.compression,
+        }
+    }
+}
+
+/**
+ * Whether a `Host` line applies to an alias; patterns are compared case-insensitively
+ *
+ * @param patterns - Patterns of the `Host` line
+ * @param alias - Host alias being resolved
+ * @returns Whether a pattern matches and no negated one does
+ */
+fn host_matches(patterns: &[String], alias: &str) -> bool {
+    let patterns: Vec<String> = patterns.iter().map(|p| p.to_lowercase()).collect();
+    patterns_match(patterns.iter().map(String::as_str), &alias.to_lowercase())
+}
+
+/**
+ * Match text against a comma-separated pattern list as used by `Match`
+ *
+ * @param list - Patterns separated by commas
+ * @param text - Text to test
+ * @returns Whether a pattern matches and no negated one does
+ */
+fn pattern_list_matches(list: &str, text: &str) -> bool {
+    patterns_match(list.split(','), text)
+}
+
+/**
+ * Match text against patterns that may be negated with `!`
+ *
+ * A negated pattern that matches excludes the text even when another pattern matches.
+ *
+ * @param patterns - Patterns to test
+ * @param text - Text to test
+ * @returns Whether a pattern matches and no negated one does
+ */
+fn patterns_match<'a>(patterns: impl IntoIterator<Item = &'a str>, text: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns {
+        match pattern.strip_prefix('!') {
+            Some(negated) if wildcard_match(negated, text) => return false,
+            Some(_) => {}
+            None => matched |= wildcard_match(pattern, text),
+        }
+    }
+    matched
+}
+
+/**
+ * Replace `%x` tokens; `%%` is a literal percent sign and unknown tokens are left as written
+ *
+ * @param text - Text containing tokens
+ * @param tokens - Token letters and their values
+ * @returns Expanded text
+ */
+fn expand_tokens(text: &str, tokens: &[(char, &str)]) -> String {
+    let mut expanded = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => expanded.push('%'),
+            Some(token) => match tokens.iter().find(|(name, _)| *name == token) {
+                Some((_, value)) => expanded.push_str(value),
+                None => {
+                    expanded.push('%');
+                    expanded.push(token);
+                }
+            },
+            None => expanded.push('%'),
+        }
+    }
+    expanded
+}
+
+/// User name ssh logs in as when the config sets none
+pub fn local_username() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+}
+
+/// Default config location, `~/.ssh/config`
+fn default_config_path() -> Result<PathBuf, AppError> {
+    let home = dirs::home_dir()
+        .ok_or_else(|| AppError::config_error("Could not determine home directory"))?;
+    Ok(home.join(".ssh").join("config"))
+}
+
+/**
+ * Load an SSH config file with its includes
  *
  * @param config_path - Path to SSH config file (defaults to ~/.ssh/config)
- * @returns Vector of SSHConfigHost entries
+ * @returns Parsed config; empty when the file does not exist
  */
-pub async fn parse_ssh_config(
-    config_path: Option<PathBuf>,
-) -> Result<Vec<SSHConfigHost>, AppError> {
+pub async fn load_ssh_config(config_path: Option<PathBuf>) -> Result<SSHConfigFile, AppError> {
     let path = match config_path {
         Some(p) => p,
-        None => {
-            let home = dirs::home_dir()
-                .ok_or_else(|| AppError::config_error("Could not determine home directory"))?;
-            home.join(".ssh").join("config")
-        }
+        None => default_config_path()?,
     };
 
     if !path.exists() {
-        return Ok(Vec::new());
+        return Ok(SSHConfigFile::default());
     }
 
-    let config_content = tokio::fs::read_to_string(&path)
+    tokio::task::spawn_blocking(move || SSHConfigFile::load(&path))
         .await
-        .map_err(|e| AppError::config_error(format!("Failed to read SSH config: {}", e)))?;
-
-    parse_config_content(&config_content)
+        .map_err(|e| AppError::config_error(format!("Failed to read SSH config: {}", e)))?
 }
 
 /**
- * Parse SSH config content string
+ * Parse SSH config file and extract host configurations
+ *
+ * Each host carries its effective options, including those from wildcard and `Match` blocks.
  *
- * @param content - SSH config file content
+ * @param config_path - Path to SSH config file (defaults to ~/.ssh/config)
  * @returns Vector of SSHConfigHost entries
  */
-fn parse_config_content(content: &str) -> Result<Vec<SSHConfigHost>, AppError> {
-    let mut hosts = Vec::new();
-    let mut current_host: Option<SSHConfigHost> = None;
+pub async fn parse_ssh_config(
+    config_path: Option<PathBuf>,
+) -> Result<Vec<SSHConfigHost>, AppError> {
+    Ok(load_ssh_config(config_path).await?.hosts())
+}
+
+/**
+ * Read a config file into entries, loading the files it includes
+ *
+ * @param path - Config file to read
+ * @param base_dir - Directory relative include paths resolve against
+ * @param depth - Number of includes followed to reach this file
+ * @returns Entries of the file
+ */
+fn read_config_file(
+    path: &Path,
+    base_dir: &Path,
+    depth: usize,
+) -> Result<Vec<ConfigEntry>, AppError> {
+    if depth > MAX_INCLUDE_DEPTH {
+        return Err(AppError::config_error(format!(
+            "Too many nested includes at {}",
+            path.display()
+        )));
+    }
 
-    for line in content.lines() {
-        let trimmed = line.trim();
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        AppError::config_error(format!(
+            "Failed to read SSH config {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
 
-        if trimmed.is_empty() || trimmed.starts_with('#') {
-            continue;
-        }
+    parse_entries(&content, base_dir, depth)
+}
 
-        let parts: Vec<&str> = trimmed.splitn(2, char::is_whitespace).collect();
-        if parts.len() < 2 {
-            continue;
-        }
+/**
+ * Parse config content into entries
+ *
+ * @param content - Config file content
+ * @param base_dir - Directory relative include paths resolve against
+ * @param depth - Number of includes followed to reach this content
+ * @returns Entries in file order
+ */
+fn parse_entries(
+    content: &str,
+    base_dir: &Path,
+    depth: usize,
+) -> Result<Vec<ConfigEntry>, AppError> {
+    let mut entries = Vec::new();
 
-        let keyword = parts[0].to_lowercase();
-        let value = parts[1].trim();
+    for line in content.lines() {
+        let Some((keyword, raw)) = split_keyword(line) else {
+            continue;
+        };
+        let args = split_args(raw);
 
         match keyword.as_str() {
-            "host" => {
-                if let Some(host) = current_host.take() {
-                    hosts.push(host);
-                }
-
-                for pattern in value.split_whitespace() {
-                    if !pattern.contains('*') && !pattern.contains('?') {
-                        current_host = Some(SSHConfigHost {
-                            name: pattern.to_string(),
-                            hostname: pattern.to_string(),
-                            port: 22,
-                            user: None,
-                            identity_file: None,
-                            proxy_jump: None,
-                            proxy_command: None,
-                            forward_agent: None,
-                            other_options: None,
-                        });
-                        break;
+            "host" => entries.push(ConfigEntry::Host(args)),
+            "match" => entries.push(ConfigEntry::Match(args)),
+            "include" => {
+                let mut included = Vec::new();
+                for pattern in &args {
+                    for path in resolve_include(pattern, base_dir) {
+                        included.extend(read_config_file(&path, base_dir, depth + 1)?);
                     }
                 }
+                entries.push(ConfigEntry::Include(included));
             }
-            "hostname" => {
-                if let Some(ref mut host) = current_host {
-                    host.hostname = value.to_string();
-                }
-            }
-            "port" => {
-                if let Some(ref mut host) = current_host {
-                    if let Ok(port) = value.parse::<u16>() {
-                        host.port = port;
-                    }
-                }
-            }
-            "user" => {
-                if let Some(ref mut host) = current_host {
-                    host.user = Some(value.to_string());
-                }
-            }
-            "identityfile" => {
-                if let Some(ref mut host) = current_host {
-                    let expanded_path = expand_tilde(value);
-                    host.identity_file = Some(expanded_path);
-                }
-            }
-            "proxyjump" => {
-                if let Some(ref mut host) = current_host {
-                    host.proxy_jump = Some(value.to_string());
-                }
+            _ => entries.push(ConfigEntry::Option {
+                keyword,
+                args,
+                raw: raw.to_string(),
+            }),
+        }
+    }
+
+    Ok(entries)
+}
+
+/**
+ * Split a config line into its lowercased keyword and the value after it
+ *
+ * The keyword may be followed by whitespace or `=`, as in `Port=2222`.
+ *
+ * @param line - Config line
+ * @returns Keyword and value, or None for blank lines and comments
+ */
+fn split_keyword(line: &str) -> Option<(String, &str)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let end = line
+        .find(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or(line.len());
+    let rest = line[end..].trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest).trim();
+    Some((line[..end].to_lowercase(), rest))
+}
+
+/**
+ * Split an option value into arguments, honouring double and single quotes
+ *
+ * An unquoted argument starting with `#` begins a comment that runs to the end of the line.
+ *
+ * @param value - Value after the keyword
+ * @returns Arguments with quotes removed
+ */
+fn split_args(value: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote: Option<char> = None;
+
+    for c in value.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_arg = true;
             }
-            "proxycommand" => {
-                if let Some(ref mut host) = current_host {
-                    host.proxy_command = Some(value.to_string());
+            None if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
                 }
             }
-            "forwardagent" => {
-                if let Some(ref mut host) = current_host {
-                    host.forward_agent = Some(value.to_lowercase() == "yes");
-                }
+            None if c == '#' && !in_arg => break,
+            None => {
+                current.push(c);
+                in_arg = true;
             }
-            _ => {}
         }
     }
+    if in_arg {
+        args.push(current);
+    }
+
+    args
+}
+
+/**
+ * Resolve an `Include` argument to the files it names
+ *
+ * Wildcards are supported in the file name; matches are returned in sorted order.
+ *
+ * @param pattern - Include argument, possibly relative or starting with ~
+ * @param base_dir - Directory relative paths resolve against
+ * @returns Existing files matching the pattern
+ */
+fn resolve_include(pattern: &str, base_dir: &Path) -> Vec<PathBuf> {
+    let expanded = PathBuf::from(expand_tilde(pattern));
+    let full = if expanded.is_absolute() {
+        expanded
+    } else {
+        base_dir.join(expanded)
+    };
+
+    let file_pattern = match full.file_name() {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => return Vec::new(),
+    };
+
+    if !file_pattern.contains(['*', '?']) {
+        return if full.is_file() {
+            vec![full]
+        } else {
+            Vec::new()
+        };
+    }
+
+    let dir = full.parent().unwrap_or(base_dir);
+    let mut matches: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file())
+                .filter(|path| {
+                    path.file_name()
+                        .is_some_and(|name| wildcard_match(&file_pattern, &name.to_string_lossy()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    matches.sort();
+    matches
+}
+
+/**
+ * Match text against an ssh_config pattern where `*` matches any run of characters and `?` one character
+ *
+ * @param pattern - Pattern to match
+ * @param text - Text to test
+ * @returns Whether the whole text matches
+ */
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
 
-    if let Some(host) = current_host {
-        hosts.push(host);
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
     }
 
-    Ok(hosts)
+    pattern[p..].iter().all(|&c| c == '*')
 }
 
 /**
@@ -134,7 +701,7 @@ fn parse_config_content(content: &str) -> Result<Vec<SSHConfigHost>, AppError> {
  * @param path - Path that may contain tilde
  * @returns Expanded path
  */
-fn expand_tilde(path: &str) -> String {
+pub fn expand_tilde(path: &str) -> String {
     if path.starts_with("~/") {
         if let Some(home) = dirs::home_dir() {
             return path.replacen("~", &home.to_string_lossy(), 1);
@@ -147,6 +714,10 @@ fn expand_tilde(path: &str) -> String {
 mod tests {
     use super::*;
 
+    fn parse_config_content(content: &str) -> Result<Vec<SSHConfigHost>, AppError> {
+        Ok(SSHConfigFile::parse(content, Path::new("."))?.hosts())
+    }
+
     #[test]
     fn test_parse_basic_config() {
         let config = r#"
@@ -189,4 +760,182 @@ Host specific
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].name, "specific");
     }
+
+    #[test]
+    fn test_parse_forwards_and_skip_match_blocks() {
+        let config = r#"
+Host tunnel
+    HostName tunnel.example.com
+    LocalForward 8080 localhost:80
+    LocalForward 127.0.0.1:5432 db.internal:5432
+    RemoteForward 9000 localhost:3000
+    DynamicForward 1080
+
+Match host *.internal exec "test -f ~/.vpn"
+    User ignored
+"#;
+
+        let result = parse_config_content(config).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].user, None);
+        assert_eq!(
+            result[0].local_forwards,
+            vec!["8080 localhost:80", "127.0.0.1:5432 db.internal:5432"]
+        );
+        assert_eq!(result[0].remote_forwards, vec!["9000 localhost:3000"]);
+        assert_eq!(result[0].dynamic_forwards, vec!["1080"]);
+    }
+
+    #[tokio::test]
+    async fn test_parse_follows_includes() {
+        let dir =
+            std::env::temp_dir().join(format!("kerminal-ssh-config-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("conf.d")).unwrap();
+        std::fs::write(
+            dir.join("config"),
+            "Include conf.d/*.conf\nHost main\n    HostName main.example.com\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("conf.d/b.conf"), "Host second\n    Port 2200\n").unwrap();
+        std::fs::write(dir.join("conf.d/a.conf"), "Host first\n    User alice\n").unwrap();
+        std::fs::write(dir.join("conf.d/notes.txt"), "Host skipped\n").unwrap();
+
+        let result = parse_ssh_config(Some(dir.join("config"))).await.unwrap();
+        let names: Vec<&str> = result.iter().map(|h| h.name.as_str()).collect();
+        assert_eq!(names, vec!["first", "second", "main"]);
+        assert_eq!(result[0].user.as_deref(), Some("alice"));
+        assert_eq!(result[1].port, 2200);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.conf", "a.conf"));
+        assert!(wildcard_match("web-??", "web-01"));
+        assert!(wildcard_match("*", ""));
+        assert!(!wildcard_match("*.conf", "a.conf.bak"));
+        assert!(!wildcard_match("web-??", "web-1"));
+    }
+
+    #[test]
+    fn test_resolve_first_match_wins_with_wildcards_and_negation() {
+        let config = r#"
+Host web-* !web-test
+    User deploy
+    IdentityFile ~/.ssh/web_key
+
+Host web-01
+    HostName %h.internal
+    User ignored
+    Port 2222
+
+Host *
+    User fallback
+    IdentityFile /keys/%r@%h-%p
+    ServerAliveInterval 30
+    Compression yes
+"#;
+        let config = SSHConfigFile::parse(config, Path::new(".")).unwrap();
+        assert_eq!(config.host_aliases(), vec!["web-01"]);
+
+        let web = config.resolve("web-01");
+        assert_eq!(web.hostname, "web-01.internal");
+        assert_eq!(web.port, 2222);
+        assert_eq!(web.user.as_deref(), Some("deploy"));
+        assert_eq!(web.identity_files.len(), 2);
+        assert!(web.identity_files[0].ends_with("/.ssh/web_key"));
+        assert_eq!(web.identity_files[1], "/keys/deploy@web-01.internal-2222");
+        assert_eq!(web.identity_file, Some(web.identity_files[0].clone()));
+        assert_eq!(web.server_alive_interval, Some(30));
+        assert_eq!(web.compression, Some(true));
+
+        let excluded = config.resolve("web-test");
+        assert_eq!(excluded.hostname, "web-test");
+        assert_eq!(excluded.user.as_deref(), Some("fallback"));
+        assert_eq!(excluded.identity_files, vec!["/keys/fallback@web-test-22"]);
+    }
+
+    #[test]
+    fn test_resolve_match_blocks() {
+        let config = r#"
+Host db
+    HostName db.internal
+    ProxyCommand ssh -W %h:%p gateway
+
+Match host *.internal !originalhost other
+    User dba
+    LocalForward 5432 localhost:5432
+
+Match originalhost db exec "vpn-up"
+    Port 6543
+
+Match final host db.internal
+    ProxyJump bastion
+
+Match all
+    ServerAliveInterval=0
+"#;
+        let db = SSHConfigFile::parse(config, Path::new("."))
+            .unwrap()
+            .resolve("db");
+
+        assert_eq!(db.user.as_deref(), Some("dba"));
+        // Blocks that need a command run never apply
+        assert_eq!(db.port, 22);
+        assert_eq!(db.proxy_jump.as_deref(), Some("bastion"));
+        assert_eq!(
+            db.proxy_command.as_deref(),
+            Some("ssh -W db.internal:22 gateway")
+        );
+        // The final pass does not repeat options collected in the first one
+        assert_eq!(db.local_forwards, vec!["5432 localhost:5432"]);
+        assert_eq!(db.server_alive_interval, Some(0));
+    }
+
+    #[test]
+    fn test_include_inside_host_block_is_conditional() {
+        let dir =
+            std::env::temp_dir().join(format!("kerminal-ssh-config-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("config"),
+            "Host a\n    Include a.conf\n    User ignored\nHost b\n    User bob\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("a.conf"),
+            "User alice\nPort=2201\nHost *\n    Compression yes\n",
+        )
+        .unwrap();
+
+        let config = SSHConfigFile::load(&dir.join("config")).unwrap();
+        let a = config.resolve("a");
+        assert_eq!(a.user.as_deref(), Some("alice"));
+        assert_eq!(a.port, 2201);
+        assert_eq!(a.compression, Some(true));
+
+        let b = config.resolve("b");
+        assert_eq!(b.user.as_deref(), Some("bob"));
+        assert_eq!(b.port, 22);
+        assert_eq!(b.compression, None);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_split_args_and_expand_tokens() {
+        assert_eq!(
+            split_args(r#""/path/with space" 'x y'z second # comment"#),
+            vec!["/path/with space", "x yz", "second"]
+        );
+        assert_eq!(
+            split_keyword("  IdentityFile = \"a b\""),
+            Some(("identityfile".to_string(), "\"a b\""))
+        );
+        assert_eq!(
+            expand_tokens("%%h %h:%p %x %", &[('h', "host"), ('p', "22")]),
+            "%h host:22 %x %"
+        );
+    }
 }
@@ -0,0 +1,84 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::database::{
+    error::{DatabaseError, DatabaseResult},
+    traits_sync::{ChangeNotifications, RecordChanges, SyncTarget},
+};
+
+/// Stands in for a database nothing was ever synced to, so previews can plan against it
+/// without creating its schema; anything that would write to it fails
+pub struct EmptySyncTarget;
+
+fn read_only() -> DatabaseError {
+    DatabaseError::SyncError("Sync database has not been set up yet".to_string())
+}
+
+#[async_trait]
+impl SyncTarget for EmptySyncTarget {
+    async fn connect(&mut self) -> DatabaseResult<()> {
+        Ok(())
+    }
+
+    async fn test_connection(&self) -> DatabaseResult<()> {
+        Ok(())
+    }
+
+    async fn push_records(&self, _table: &str, _records: Vec<Value>) -> DatabaseResult<usize> {
+        Err(read_only())
+    }
+
+    async fn pull_records(
+        &self,
+        _table: &str,
+        _since: Option<DateTime<Utc>>,
+    ) -> DatabaseResult<Vec<Value>> {
+        Ok(Vec::new())
+    }
+
+    async fn pull_changes(
+        &self,
+        _table: &str,
+        cursor: Option<&str>,
+    ) -> DatabaseResult<RecordChanges> {
+        Ok(RecordChanges {
+            records: Vec::new(),
+            cursor: cursor.unwrap_or_default().to_string(),
+        })
+    }
+
+    async fn watch_changes(&self) -> DatabaseResult<Option<ChangeNotifications>> {
+        Ok(None)
+    }
+
+    async fn get_record_versions(
+        &self,
+        _table: &str,
+        _ids: Vec<String>,
+    ) -> DatabaseResult<HashMap<String, u64>> {
+        Ok(HashMap::new())
+    }
+
+    async fn register_device(&self, _device_id: &str) -> DatabaseResult<()> {
+        Err(read_only())
+    }
+
+    async fn acknowledge_tombstones(
+        &self,
+        _table: &str,
+        _device_id: &str,
+        _ids: Vec<String>,
+    ) -> DatabaseResult<()> {
+        Err(read_only())
+    }
+
+    async fn purge_acknowledged_tombstones(&self, _table: &str) -> DatabaseResult<Vec<String>> {
+        Err(read_only())
+    }
+
+    async fn clear_records(&self, _table: &str) -> DatabaseResult<usize> {
+        Err(read_only())
+    }
+}
@@ -0,0 +1,412 @@
+use base64::{engine::general_purpose, Engine as _};
+use std::sync::{Arc, PoisonError, RwLock, Weak};
+use tokio::sync::Mutex;
+use zeroize::Zeroizing;
+
+use crate::database::{
+    encryption::{AESEncryption, SyncKeyring, WrappedSyncKey},
+    error::{DatabaseError, DatabaseResult, EncryptionError},
+    service::DatabaseService,
+    traits::EncryptionService,
+    traits_sync::{SyncTarget, SEALED_RECORDS_TABLE, SYNC_KEYS_TABLE, TOMBSTONE_TABLES},
+};
+use crate::models::sync::{
+    external_db::ExternalDatabaseConfig, SyncEncryptionKey, SyncEncryptionStatus, SyncSettings,
+};
+use crate::services::sync::{
+    engine::load_local_records,
+    manager::SyncManager,
+    sealed::{SealedSyncTarget, SharedKeyring},
+};
+
+/// End-to-end encryption of sync data
+/// Sync keys are random, published on the sync target wrapped with a key derived from
+/// the master password (Argon2id), and kept locally encrypted with the device key
+pub struct SyncEncryption {
+    database_service: Arc<Mutex<DatabaseService>>,
+    sync_manager: Arc<SyncManager>,
+    /// Keyrings of the sealed targets handed out, emptied when the session locks
+    keyrings: std::sync::Mutex<Vec<Weak<RwLock<SyncKeyring>>>>,
+}
+
+impl SyncEncryption {
+    pub fn new(
+        database_service: Arc<Mutex<DatabaseService>>,
+        sync_manager: Arc<SyncManager>,
+    ) -> Self {
+        Self {
+            database_service,
+            sync_manager,
+            keyrings: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Wipe the sync keys held by sealed targets still in use
+    pub fn wipe_keys(&self) {
+        let keyrings =
+            std::mem::take(&mut *self.keyrings.lock().unwrap_or_else(PoisonError::into_inner));
+        for keyring in keyrings.iter().filter_map(Weak::upgrade) {
+            keyring
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clear();
+        }
+    }
+
+    /// Sealed target over a connection, with its keyring tracked so it can be wiped
+    fn sealed_target(&self, inner: Arc<dyn SyncTarget>, keyring: SyncKeyring) -> SealedSyncTarget {
+        let keyring: SharedKeyring = Arc::new(RwLock::new(keyring));
+        let mut keyrings = self.keyrings.lock().unwrap_or_else(PoisonError::into_inner);
+        keyrings.retain(|keyring| keyring.strong_count() > 0);
+        keyrings.push(Arc::downgrade(&keyring));
+
+        SealedSyncTarget::new(inner, keyring)
+    }
+
+    /// Sync target for a database, sealing records when its data is end-to-end encrypted
+    pub async fn target_for(
+        &self,
+        config: &ExternalDatabaseConfig,
+    ) -> DatabaseResult<Arc<dyn SyncTarget>> {
+        let inner = self.sync_manager.get_provider(&config.base.id).await?;
+        self.wrap_target(config, inner).await
+    }
+
+    /// Seal records sent through a connection to a database when its data is end-to-end encrypted
+    pub async fn wrap_target(
+        &self,
+        config: &ExternalDatabaseConfig,
+        inner: Arc<dyn SyncTarget>,
+    ) -> DatabaseResult<Arc<dyn SyncTarget>> {
+        let keyring = self.load_keyring(&config.base.id).await?;
+
+        if !keyring.is_empty() {
+            return Ok(Arc::new(self.sealed_target(inner, keyring)));
+        }
+
+        // Never push plaintext next to sealed records
+        if !fetch_wrapped_keys(&inner).await?.is_empty() {
+            return Err(DatabaseError::SyncError(
+                "Sync data is end-to-end encrypted, unlock sync encryption with the master password"
+                    .to_string(),
+            ));
+        }
+
+        Ok(inner)
+    }
+
+    /// Get the end-to-end encryption state of a database
+    pub async fn status(
+        &self,
+        config: &ExternalDatabaseConfig,
+    ) -> DatabaseResult<SyncEncryptionStatus> {
+        self.sync_manager.ensure_connection(config).await?;
+        let inner = self.sync_manager.get_provider(&config.base.id).await?;
+
+        let published = fetch_wrapped_keys(&inner).await?;
+        let keyring = self.load_keyring(&config.base.id).await?;
+
+        Ok(SyncEncryptionStatus {
+            enabled: !published.is_empty(),
+            unlocked: !published.is_empty()
+                && published.iter().all(|key| keyring.has_key(&key.key_id)),
+            active_key_id: keyring.active_key_id().map(|id| id.to_string()),
+            key_count: keyring.key_count(),
+        })
+    }
+
+    /// Unwrap the published sync keys with the master password and keep them on this device
+    pub async fn unlock(
+        &self,
+        config: &ExternalDatabaseConfig,
+        password: &str,
+    ) -> DatabaseResult<SyncEncryptionStatus> {
+        self.verify_password(password).await?;
+        self.sync_manager.ensure_connection(config).await?;
+        let inner = self.sync_manager.get_provider(&config.base.id).await?;
+
+        let published = fetch_wrapped_keys(&inner).await?;
+        if published.is_empty() {
+            return Err(DatabaseError::SyncError(
+                "End-to-end encryption is not enabled for this database".to_string(),
+            ));
+        }
+
+        let keyring = self.load_keyring(&config.base.id).await?;
+        for wrapped in published.iter().filter(|k| !keyring.has_key(&k.key_id)) {
+            let key = wrapped.unwrap_key(password)?;
+            self.store_key(&config.base.id, wrapped, &key).await?;
+        }
+
+        self.status(config).await
+    }
+
+    /// Create the first sync key, then replace the plaintext remote data with sealed records
+    /// Local data should be synced first so no remote change is lost
+    pub async fn enable(
+        &self,
+        config: &ExternalDatabaseConfig,
+        password: &str,
+    ) -> DatabaseResult<()> {
+        self.verify_password(password).await?;
+        self.sync_manager.ensure_connection(config).await?;
+        let inner = self.sync_manager.get_provider(&config.base.id).await?;
+
+        if !fetch_wrapped_keys(&inner).await?.is_empty() {
+            return Err(DatabaseError::SyncError(
+                "End-to-end encryption is already enabled for this database, unlock it instead"
+                    .to_string(),
+            ));
+        }
+
+        self.publish_new_key(config, &inner, password).await?;
+        self.reseal_local_records(config, &inner).await?;
+
+        for table in TOMBSTONE_TABLES {
+            let count = inner.clear_records(table).await?;
+            eprintln!("[INFO] Removed {} plaintext records from {}", count, table);
+        }
+
+        Ok(())
+    }
+
+    /// Seal every record again with a new sync key
+    /// Older keys are kept so records sealed by devices that have not unlocked the new key still open
+    pub async fn rotate_key(
+        &self,
+        config: &ExternalDatabaseConfig,
+        password: &str,
+    ) -> DatabaseResult<SyncEncryptionStatus> {
+        let status = self.unlock(config, password).await?;
+        if !status.unlocked {
+            return Err(DatabaseError::SyncError(
+                "Sync encryption is locked on this device".to_string(),
+            ));
+        }
+
+        let inner = self.sync_manager.get_provider(&config.base.id).await?;
+        self.publish_new_key(config, &inner, password).await?;
+        self.reseal_local_records(config, &inner).await?;
+
+        self.status(config).await
+    }
+
+    /// Go back to plaintext sync, replacing the sealed remote data with local records
+    /// Local data should be synced first so no remote change is lost
+    pub async fn disable(
+        &self,
+        config: &ExternalDatabaseConfig,
+        password: &str,
+    ) -> DatabaseResult<()> {
+        self.verify_password(password).await?;
+        self.sync_manager.ensure_connection(config).await?;
+        let inner = self.sync_manager.get_provider(&config.base.id).await?;
+
+        let settings = self.get_sync_settings().await?;
+        {
+            let db_service = self.database_service.lock().await;
+            let local = db_service.get_local_database();
+            drop(db_service);
+            let local_guard = local.read().await;
+
+            for table in TOMBSTONE_TABLES {
+                if !settings.synced_entities.includes(table) {
+                    continue;
+                }
+                let records = load_local_records(&local_guard, table).await?;
+                inner.push_records(table, records).await?;
+            }
+        }
+
+        inner.clear_records(SEALED_RECORDS_TABLE).await?;
+        inner.clear_records(SYNC_KEYS_TABLE).await?;
+
+        let db_service = self.database_service.lock().await;
+        let local = db_service.get_local_database();
+        let guard = local.write().await;
+        guard.delete_sync_encryption_keys(&config.base.id).await
+    }
+
+    /// Generate a sync key, publish it wrapped with the master password and keep it locally
+    async fn publish_new_key(
+        &self,
+        config: &ExternalDatabaseConfig,
+        inner: &Arc<dyn SyncTarget>,
+        password: &str,
+    ) -> DatabaseResult<()> {
+        let key = AESEncryption::generate_key();
+        let wrapped = WrappedSyncKey::wrap(uuid::Uuid::new_v4().to_string(), &key, password)?;
+
+        inner
+            .push_records(SYNC_KEYS_TABLE, vec![wrapped.to_record()])
+            .await?;
+        self.store_key(&config.base.id, &wrapped, &key).await?;
+
+        eprintln!(
+            "[INFO] Published sync key {} for {}",
+            wrapped.key_id, config.name
+        );
+
+        Ok(())
+    }
+
+    /// Push every local record sealed with the active sync key
+    async fn reseal_local_records(
+        &self,
+        config: &ExternalDatabaseConfig,
+        inner: &Arc<dyn SyncTarget>,
+    ) -> DatabaseResult<()> {
+        let keyring = self.load_keyring(&config.base.id).await?;
+        let sealed = self.sealed_target(inner.clone(), keyring);
+        let settings = self.get_sync_settings().await?;
+
+        let db_service = self.database_service.lock().await;
+        let local = db_service.get_local_database();
+        drop(db_service);
+        let local_guard = local.read().await;
+
+        for table in TOMBSTONE_TABLES {
+            if !settings.synced_entities.includes(table) {
+                continue;
+            }
+            let records = load_local_records(&local_guard, table).await?;
+            sealed.push_records(table, records).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Load this device's copy of a database's sync keys, oldest first
+    async fn load_keyring(&self, database_id: &str) -> DatabaseResult<SyncKeyring> {
+        let db_service = self.database_service.lock().await;
+        let local = db_service.get_local_database();
+        let manager = db_service.get_master_password_manager_arc();
+        drop(db_service);
+
+        let stored = local
+            .read()
+            .await
+            .get_sync_encryption_keys(database_id)
+            .await?;
+        let manager = manager.read().await;
+
+        let mut keyring = SyncKeyring::new();
+        for stored_key in stored {
+            let encoded = Zeroizing::new(
+                manager
+                    .decrypt_string(&stored_key.encrypted_key, None)
+                    .await?,
+            );
+            let bytes = Zeroizing::new(
+                general_purpose::STANDARD
+                    .decode(encoded.as_str())
+                    .map_err(|_| EncryptionError::InvalidFormat)?,
+            );
+            let key: [u8; 32] = bytes
+                .as_slice()
+                .try_into()
+                .map_err(|_| EncryptionError::InvalidFormat)?;
+            keyring.add_key(stored_key.key_id, key);
+        }
+
+        Ok(keyring)
+    }
+
+    /// Keep a sync key locally, encrypted with this device's key
+    async fn store_key(
+        &self,
+        database_id: &str,
+        wrapped: &WrappedSyncKey,
+        key: &[u8; 32],
+    ) -> DatabaseResult<()> {
+        let db_service = self.database_service.lock().await;
+        let local = db_service.get_local_database();
+        let manager = db_service.get_master_password_manager_arc();
+        drop(db_service);
+
+        let encrypted_key = manager
+            .read()
+            .await
+            .encrypt_string(&general_purpose::STANDARD.encode(key), None)
+            .await?;
+
+        let guard = local.write().await;
+        guard
+            .save_sync_encryption_key(&SyncEncryptionKey {
+                database_id: database_id.to_string(),
+                key_id: wrapped.key_id.clone(),
+                encrypted_key,
+                created_at: wrapped.created_at,
+            })
+            .await
+    }
+
+    async fn verify_password(&self, password: &str) -> DatabaseResult<()> {
+        let db_service = self.database_service.lock().await;
+        db_service
+            .verify_master_password(password.to_string())
+            .await
+    }
+
+    async fn get_sync_settings(&self) -> DatabaseResult<SyncSettings> {
+        let db_service = self.database_service.lock().await;
+        let local = db_service.get_local_database();
+        let guard = local.read().await;
+        Ok(guard
+            .get_global_sync_settings()
+            .await?
+            .unwrap_or_else(SyncSettings::new))
+    }
+}
+
+/// Sync keys published on a sync target, oldest first
+async fn fetch_wrapped_keys(target: &Arc<dyn SyncTarget>) -> DatabaseResult<Vec<WrappedSyncKey>> {
+    let mut keys: Vec<WrappedSyncKey> = target
+        .pull_records(SYNC_KEYS_TABLE, None)
+        .await?
+        .iter()
+        .filter_map(|record| WrappedSyncKey::from_record(record).ok())
+        .collect();
+    keys.sort_by_key(|key| key.created_at);
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::providers::SQLiteTargetProvider;
+    use crate::services::sync::fixture;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_wiped_keys_no_longer_seal() {
+        let dir = fixture::temp_dir();
+        let database_service =
+            Arc::new(Mutex::new(fixture::database_service(&dir, "device").await));
+        let encryption = SyncEncryption::new(
+            database_service.clone(),
+            Arc::new(SyncManager::new(database_service)),
+        );
+
+        let mut target = SQLiteTargetProvider::new(dir.join("sync.db"));
+        target.connect().await.unwrap();
+        let mut keyring = SyncKeyring::new();
+        keyring.add_key("k1".to_string(), AESEncryption::generate_key());
+        let sealed = encryption.sealed_target(Arc::new(target), keyring);
+
+        let group = json!({ "id": "g1", "name": "servers", "version": 1 });
+        sealed
+            .push_records("ssh_groups", vec![group.clone()])
+            .await
+            .unwrap();
+
+        encryption.wipe_keys();
+        assert!(sealed
+            .push_records("ssh_groups", vec![group])
+            .await
+            .is_err());
+        assert!(sealed.pull_records("ssh_groups", None).await.is_err());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
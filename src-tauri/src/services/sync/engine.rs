@@ -1,39 +1,98 @@
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use futures::future::try_join_all;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::{Mutex, RwLock};
 
 use crate::database::{
     error::{DatabaseError, DatabaseResult},
+    providers::sqlite::SQLiteProvider,
     service::DatabaseService,
     traits::Database,
+    traits_sync::TOMBSTONE_TABLES,
 };
 use crate::models::sync::{
+    conflict::FieldChoice,
     external_db::ExternalDatabaseConfig,
     log::{SyncDirection, SyncLog, SyncStatus},
-    ConflictResolutionStrategy,
+    plan::{PlannedAction, PlannedChange, SyncPlan, TableSyncPlan},
+    settings::SyncedEntities,
+    ConflictResolutionStrategy, SyncCursor, SyncSettings,
 };
 use crate::services::sync::{
+    empty::EmptySyncTarget,
+    encryption::SyncEncryption,
+    known_hosts,
     manager::SyncManager,
-    resolver::{ConflictResolver, DataConflict},
+    resolver::{ConflictResolution, ConflictResolver, DataConflict, ThreeWayMerge},
 };
 
+/// Tables synced before the rest, since ssh_tunnels references ssh_profiles
+const FIRST_PHASE_TABLES: &[&str] = &["ssh_groups", "ssh_keys", "ssh_profiles"];
+
+/// Runs before a sync applies remote changes to the local database
+#[async_trait]
+pub trait PullHandler: Send + Sync {
+    async fn before_pull(&self, config: &ExternalDatabaseConfig);
+}
+
 /// Sync engine for managing data synchronization
 pub struct SyncEngine {
     database_service: Arc<Mutex<DatabaseService>>,
     sync_manager: Arc<SyncManager>,
+    encryption: Arc<SyncEncryption>,
     #[allow(dead_code)]
     conflict_resolver: Arc<ConflictResolver>,
+    pull_handlers: std::sync::RwLock<Vec<Arc<dyn PullHandler>>>,
+    known_hosts_path: Option<PathBuf>,
 }
 
 impl SyncEngine {
     pub fn new(
         database_service: Arc<Mutex<DatabaseService>>,
         sync_manager: Arc<SyncManager>,
+        encryption: Arc<SyncEncryption>,
     ) -> Self {
         Self {
             database_service,
             sync_manager,
+            encryption,
             conflict_resolver: Arc::new(ConflictResolver::new()),
+            pull_handlers: std::sync::RwLock::new(Vec::new()),
+            known_hosts_path: known_hosts::default_path(),
+        }
+    }
+
+    /// Sync another known_hosts file than `~/.ssh/known_hosts`
+    #[cfg(test)]
+    pub fn with_known_hosts_path(mut self, path: PathBuf) -> Self {
+        self.known_hosts_path = Some(path);
+        self
+    }
+
+    /// known_hosts file kept in step with the `known_hosts` table, when that table is synced
+    fn known_hosts_file(&self, synced_entities: &SyncedEntities) -> Option<&Path> {
+        self.known_hosts_path
+            .as_deref()
+            .filter(|_| synced_entities.known_hosts)
+    }
+
+    /// Register a handler to run before pulls and bidirectional syncs
+    pub fn register_pull_handler(&self, handler: Arc<dyn PullHandler>) {
+        if let Ok(mut handlers) = self.pull_handlers.write() {
+            handlers.push(handler);
+        }
+    }
+
+    async fn run_pull_handlers(&self, config: &ExternalDatabaseConfig) {
+        let handlers = match self.pull_handlers.read() {
+            Ok(handlers) => handlers.clone(),
+            Err(_) => return,
+        };
+        for handler in handlers {
+            handler.before_pull(config).await;
         }
     }
 
@@ -62,6 +121,7 @@ impl SyncEngine {
 
     /// Pull remote data to local database
     pub async fn pull(&self, config: &ExternalDatabaseConfig) -> DatabaseResult<SyncLog> {
+        self.run_pull_handlers(config).await;
         let mut sync_log = self.create_sync_log(config, SyncDirection::Pull).await?;
 
         match self.pull_internal(config).await {
@@ -85,12 +145,18 @@ impl SyncEngine {
 
     /// Full bidirectional sync with conflict resolution
     pub async fn sync(&self, config: &ExternalDatabaseConfig) -> DatabaseResult<SyncLog> {
+        self.run_pull_handlers(config).await;
         let mut sync_log = self
             .create_sync_log(config, SyncDirection::Bidirectional)
             .await?;
 
-        match self.sync_internal(config).await {
-            Ok(stats) => {
+        let result = match self.connected_target(config).await {
+            Ok(remote) => self.sync_internal(config, &remote, false).await,
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok((stats, _)) => {
                 sync_log.status = SyncStatus::Completed;
                 sync_log.records_synced = stats.total_synced as i32;
                 sync_log.conflicts_resolved = stats.conflicts_resolved as i32;
@@ -108,54 +174,92 @@ impl SyncEngine {
         Ok(sync_log)
     }
 
-    /// Internal push implementation - Push local data to remote
-    async fn push_internal(&self, config: &ExternalDatabaseConfig) -> DatabaseResult<SyncStats> {
-        use crate::services::sync::SyncSerializable;
+    /// Compute everything a bidirectional sync would change, without changing either side
+    pub async fn preview(&self, config: &ExternalDatabaseConfig) -> DatabaseResult<SyncPlan> {
+        // A preview must not create or migrate the remote schema
+        let inner = match self.sync_manager.connect_read_only(config).await? {
+            Some(inner) => inner,
+            None => Arc::new(EmptySyncTarget),
+        };
+        let remote = self.encryption.wrap_target(config, inner).await?;
+        let (_, tables) = self.sync_internal(config, &remote, true).await?;
 
+        Ok(SyncPlan {
+            database_id: config.base.id.clone(),
+            tables,
+            created_at: Utc::now(),
+        })
+    }
+
+    /// Settle a conflict saved for manual resolution, writing the resulting record locally
+    /// The next sync pushes it to the remote
+    pub async fn resolve_conflict(
+        &self,
+        id: &str,
+        strategy: ConflictResolutionStrategy,
+        choices: &HashMap<String, FieldChoice>,
+    ) -> DatabaseResult<()> {
+        let db_service = self.database_service.lock().await;
+        let local = db_service.get_local_database();
+        let guard = local.read().await;
+
+        let conflict = guard
+            .find_conflict_resolution(id)
+            .await?
+            .ok_or_else(|| DatabaseError::NotFound(format!("Conflict {} not found", id)))?;
+        let record = ConflictResolver::resolve_stored(&conflict, strategy, choices)?;
+        guard
+            .resolve_conflict_resolution(&conflict, strategy, &record)
+            .await
+    }
+
+    /// Id of this device, as registered on sync targets
+    pub async fn current_device_id(&self) -> DatabaseResult<String> {
+        let db_service = self.database_service.lock().await;
+        let local = db_service.get_local_database();
+        let guard = local.read().await;
+        Ok(guard
+            .get_current_device()
+            .await?
+            .ok_or_else(|| DatabaseError::NotFound("Current device not found".to_string()))?
+            .device_id)
+    }
+
+    /// Sync target of a database, connecting and setting up its schema first if needed
+    async fn connected_target(
+        &self,
+        config: &ExternalDatabaseConfig,
+    ) -> DatabaseResult<Arc<dyn crate::database::traits_sync::SyncTarget>> {
         self.sync_manager.ensure_connection(config).await?;
-        let remote = self.sync_manager.get_provider(&config.base.id).await?;
+        self.encryption.target_for(config).await
+    }
+
+    /// Internal push implementation - Push local data to remote
+    async fn push_internal(&self, config: &ExternalDatabaseConfig) -> DatabaseResult<SyncStats> {
+        let remote = self.connected_target(config).await?;
 
         let mut stats = SyncStats::default();
+        let synced_entities = self.get_sync_settings().await?.synced_entities;
 
         let db_service = self.database_service.lock().await;
         let local = db_service.get_local_database();
         let local_guard = local.read().await;
 
-        let profiles = local_guard.find_all_ssh_profiles().await?;
-        let json_profiles: Vec<_> = profiles.iter().filter_map(|p| p.to_json().ok()).collect();
-        if !json_profiles.is_empty() {
-            let count = remote.push_records("ssh_profiles", json_profiles).await?;
-            stats.total_synced += count;
+        if let Some(path) = self.known_hosts_file(&synced_entities) {
+            known_hosts::import(&local_guard, db_service.get_device_id(), path).await?;
         }
 
-        let groups = local_guard.find_all_ssh_groups().await?;
-        let json_groups: Vec<_> = groups.iter().filter_map(|g| g.to_json().ok()).collect();
-        if !json_groups.is_empty() {
-            let count = remote.push_records("ssh_groups", json_groups).await?;
-            stats.total_synced += count;
-        }
-
-        let keys = local_guard.find_all_ssh_keys().await?;
-        let json_keys: Vec<_> = keys.iter().filter_map(|k| k.to_json().ok()).collect();
-        if !json_keys.is_empty() {
-            let count = remote.push_records("ssh_keys", json_keys).await?;
-            stats.total_synced += count;
-        }
-
-        let cmd_groups = local_guard.find_all_saved_command_groups().await?;
-        let json_cmd_groups: Vec<_> = cmd_groups.iter().filter_map(|g| g.to_json().ok()).collect();
-        if !json_cmd_groups.is_empty() {
-            let count = remote
-                .push_records("saved_command_groups", json_cmd_groups)
-                .await?;
-            stats.total_synced += count;
-        }
+        for table in TOMBSTONE_TABLES {
+            if !synced_entities.includes(table) {
+                continue;
+            }
 
-        let commands = local_guard.find_all_saved_commands().await?;
-        let json_commands: Vec<_> = commands.iter().filter_map(|c| c.to_json().ok()).collect();
-        if !json_commands.is_empty() {
-            let count = remote.push_records("saved_commands", json_commands).await?;
-            stats.total_synced += count;
+            let records = load_local_records(&local_guard, table).await?;
+            if !records.is_empty() {
+                let count = remote.push_records(table, records.clone()).await?;
+                stats.total_synced += count;
+                local_guard.save_sync_base_records(table, &records).await?;
+            }
         }
 
         Ok(stats)
@@ -163,172 +267,175 @@ impl SyncEngine {
 
     /// Internal pull implementation - Pull remote data to local
     async fn pull_internal(&self, config: &ExternalDatabaseConfig) -> DatabaseResult<SyncStats> {
-        use crate::services::sync::SyncSerializable;
-
-        self.sync_manager.ensure_connection(config).await?;
-        let remote = self.sync_manager.get_provider(&config.base.id).await?;
+        let remote = self.connected_target(config).await?;
 
         let mut stats = SyncStats::default();
 
-        let last_sync = self.get_last_sync_time(&config.base.id).await?;
+        let synced_entities = self.get_sync_settings().await?.synced_entities;
 
         let db_service = self.database_service.lock().await;
         let local = db_service.get_local_database();
 
         let local_guard = local.write().await;
 
-        let json_profiles = remote.pull_records("ssh_profiles", last_sync).await?;
-        for json in json_profiles {
-            if let Ok(profile) = crate::models::ssh::SSHProfile::from_json(&json) {
-                local_guard.save_ssh_profile(&profile).await?;
-                stats.total_synced += 1;
-            }
+        if let Some(path) = self.known_hosts_file(&synced_entities) {
+            known_hosts::import(&local_guard, db_service.get_device_id(), path).await?;
         }
 
-        let json_groups = remote.pull_records("ssh_groups", last_sync).await?;
-        for json in json_groups {
-            if let Ok(group) = crate::models::ssh::SSHGroup::from_json(&json) {
-                local_guard.save_ssh_group(&group).await?;
-                stats.total_synced += 1;
+        for table in TOMBSTONE_TABLES {
+            if !synced_entities.includes(table) {
+                continue;
             }
-        }
 
-        let json_keys = remote.pull_records("ssh_keys", last_sync).await?;
-        for json in json_keys {
-            if let Ok(key) = crate::models::ssh::SSHKey::from_json(&json) {
-                local_guard.save_ssh_key(&key).await?;
-                stats.total_synced += 1;
+            let cursor = local_guard.get_sync_cursor(&config.base.id, table).await?;
+            let changes = remote
+                .pull_changes(table, cursor.as_ref().map(|c| c.remote_cursor.as_str()))
+                .await?;
+
+            let mut pulled = Vec::new();
+            for json in changes.records {
+                if save_local_record(&local_guard, table, &json).await? {
+                    stats.total_synced += 1;
+                    pulled.push(json);
+                }
             }
-        }
+            local_guard.save_sync_base_records(table, &pulled).await?;
 
-        let json_cmd_groups = remote
-            .pull_records("saved_command_groups", last_sync)
-            .await?;
-        for json in json_cmd_groups {
-            if let Ok(group) = crate::models::saved_command::SavedCommandGroup::from_json(&json) {
-                local_guard.save_saved_command_group(&group).await?;
-                stats.total_synced += 1;
+            // Local changes were not pushed, so only the remote side of the cursor moves
+            if let Some(mut cursor) = cursor {
+                cursor.remote_cursor = changes.cursor;
+                cursor.updated_at = Utc::now();
+                local_guard.save_sync_cursor(&cursor).await?;
             }
         }
 
-        let json_commands = remote.pull_records("saved_commands", last_sync).await?;
-        for json in json_commands {
-            if let Ok(command) = crate::models::saved_command::SavedCommand::from_json(&json) {
-                local_guard.save_saved_command(&command).await?;
-                stats.total_synced += 1;
-            }
+        if let Some(path) = self.known_hosts_file(&synced_entities) {
+            known_hosts::export(&local_guard, path).await?;
         }
 
         Ok(stats)
     }
 
     /// Internal bidirectional sync with conflict resolution
-    async fn sync_internal(&self, config: &ExternalDatabaseConfig) -> DatabaseResult<SyncStats> {
-        use crate::models::sync::ConflictResolutionStrategy;
+    /// A dry run only plans the changes, returning the tables that have any
+    async fn sync_internal(
+        &self,
+        config: &ExternalDatabaseConfig,
+        remote: &Arc<dyn crate::database::traits_sync::SyncTarget>,
+        dry_run: bool,
+    ) -> DatabaseResult<(SyncStats, Vec<TableSyncPlan>)> {
+        let last_sync = self.get_last_sync_time(&config.base.id).await?;
 
-        self.sync_manager.ensure_connection(config).await?;
-        let remote = self.sync_manager.get_provider(&config.base.id).await?;
+        let settings = self.get_sync_settings().await?;
+        let strategy = settings.conflict_strategy;
 
-        let last_sync = self.get_last_sync_time(&config.base.id).await?;
+        let device_id = self.current_device_id().await?;
+
+        // Tombstones are only purged once every registered device has acknowledged them
+        if !dry_run {
+            remote.register_device(&device_id).await?;
+        }
 
-        let strategy = {
+        let known_hosts_file = self
+            .known_hosts_file(&settings.synced_entities)
+            .filter(|_| !dry_run);
+        if let Some(path) = known_hosts_file {
             let db_service = self.database_service.lock().await;
             let local = db_service.get_local_database();
-            let sync_settings = {
-                let guard = local.read().await;
-                guard.get_global_sync_settings().await?
-            };
-            drop(db_service); // Explicitly drop lock before proceeding
-            sync_settings
-                .map(|s| s.conflict_strategy)
-                .unwrap_or(ConflictResolutionStrategy::Manual)
-        };
-
-        // Parallel sync: SSH tables can be synced together, command tables can be synced together
-        // Using try_join! to run syncs in parallel and fail fast on first error
-        let (profile_stats, group_stats, key_stats) = tokio::try_join!(
-            self.sync_table_bidirectional(&remote, "ssh_profiles", last_sync, strategy),
-            self.sync_table_bidirectional(&remote, "ssh_groups", last_sync, strategy),
-            self.sync_table_bidirectional(&remote, "ssh_keys", last_sync, strategy),
-        )?;
+            let guard = local.read().await;
+            known_hosts::import(&guard, &device_id, path).await?;
+        }
 
-        let (cmd_group_stats, cmd_stats) = tokio::try_join!(
-            self.sync_table_bidirectional(&remote, "saved_command_groups", last_sync, strategy),
-            self.sync_table_bidirectional(&remote, "saved_commands", last_sync, strategy),
-        )?;
+        let (first_phase, second_phase): (Vec<&str>, Vec<&str>) = TOMBSTONE_TABLES
+            .iter()
+            .copied()
+            .filter(|table| settings.synced_entities.includes(table))
+            .partition(|table| FIRST_PHASE_TABLES.contains(table));
 
-        // Merge all stats
+        // Tables within a phase are synced in parallel, failing fast on the first error
         let mut stats = SyncStats::default();
-        stats.merge(profile_stats);
-        stats.merge(group_stats);
-        stats.merge(key_stats);
-        stats.merge(cmd_group_stats);
-        stats.merge(cmd_stats);
+        let mut plans = Vec::new();
+        for phase in [first_phase, second_phase] {
+            let phase_stats = try_join_all(phase.into_iter().map(|table| {
+                self.sync_table_bidirectional(
+                    remote,
+                    &config.base.id,
+                    table,
+                    &device_id,
+                    last_sync,
+                    strategy,
+                    dry_run,
+                )
+            }))
+            .await?;
 
-        Ok(stats)
+            for (table_stats, table_plan) in phase_stats {
+                stats.merge(table_stats);
+                if !table_plan.changes.is_empty() {
+                    plans.push(table_plan);
+                }
+            }
+        }
+
+        if let Some(path) = known_hosts_file {
+            let db_service = self.database_service.lock().await;
+            let local = db_service.get_local_database();
+            let guard = local.read().await;
+            known_hosts::export(&guard, path).await?;
+        }
+
+        Ok((stats, plans))
     }
 
     /// Sync a single table bidirectionally with conflict resolution
     async fn sync_table_bidirectional(
         &self,
         remote: &Arc<dyn crate::database::traits_sync::SyncTarget>,
+        database_id: &str,
         table: &str,
+        device_id: &str,
         last_sync: Option<DateTime<Utc>>,
         strategy: ConflictResolutionStrategy,
-    ) -> DatabaseResult<SyncStats> {
-        use crate::services::sync::SyncSerializable;
+        dry_run: bool,
+    ) -> DatabaseResult<(SyncStats, TableSyncPlan)> {
         use chrono::{DateTime, Utc};
         use serde_json::Value;
-        use std::collections::HashMap;
+        use std::collections::HashSet;
 
         let mut stats = SyncStats::default();
+        let mut plan = TableSyncPlan {
+            table: table.to_string(),
+            changes: Vec::new(),
+        };
 
         let db_service = self.database_service.lock().await;
         let local = db_service.get_local_database();
         drop(db_service);
 
-        let local_records = {
+        // Without a cursor this is the first sync with the target, which exchanges every record;
+        // afterwards only records written since the cursor positions are exchanged
+        let (cursor, local_seq, local_records, bases) = {
             let local_guard = local.read().await;
-            match table {
-                "ssh_profiles" => {
-                    let profiles = local_guard.find_all_ssh_profiles().await?;
-                    profiles
-                        .iter()
-                        .filter_map(|p| p.to_json().ok())
-                        .collect::<Vec<_>>()
-                }
-                "ssh_groups" => {
-                    let groups = local_guard.find_all_ssh_groups().await?;
-                    groups
-                        .iter()
-                        .filter_map(|g| g.to_json().ok())
-                        .collect::<Vec<_>>()
-                }
-                "ssh_keys" => {
-                    let keys = local_guard.find_all_ssh_keys().await?;
-                    keys.iter()
-                        .filter_map(|k| k.to_json().ok())
-                        .collect::<Vec<_>>()
-                }
-                "saved_command_groups" => {
-                    let groups = local_guard.find_all_saved_command_groups().await?;
-                    groups
-                        .iter()
-                        .filter_map(|g| g.to_json().ok())
-                        .collect::<Vec<_>>()
-                }
-                "saved_commands" => {
-                    let commands = local_guard.find_all_saved_commands().await?;
-                    commands
-                        .iter()
-                        .filter_map(|c| c.to_json().ok())
-                        .collect::<Vec<_>>()
-                }
-                _ => vec![],
-            }
+            let cursor = local_guard.get_sync_cursor(database_id, table).await?;
+            // Read before loading so writes racing with this sync are picked up by the next one
+            let local_seq = local_guard.latest_change_seq().await?;
+            let local_records = match &cursor {
+                Some(cursor) => load_changed_records(&local_guard, table, cursor.local_seq).await?,
+                None => load_local_records(&local_guard, table).await?,
+            };
+            (
+                cursor,
+                local_seq,
+                local_records,
+                local_guard.get_sync_base_records(table).await?,
+            )
         };
+        let incremental = cursor.is_some();
 
-        let remote_records = remote.pull_records(table, last_sync).await?;
+        let changes = remote
+            .pull_changes(table, cursor.as_ref().map(|c| c.remote_cursor.as_str()))
+            .await?;
+        let remote_records = changes.records;
 
         let local_ids: Vec<String> = local_records
             .iter()
@@ -337,7 +444,7 @@ impl SyncEngine {
 
         let remote_versions = remote.get_record_versions(table, local_ids.clone()).await?;
 
-        let local_versions: HashMap<String, u64> = local_records
+        let mut local_versions: HashMap<String, u64> = local_records
             .iter()
             .filter_map(|r| {
                 let id = r.get("id")?.as_str()?.to_string();
@@ -346,17 +453,245 @@ impl SyncEngine {
             })
             .collect();
 
+        // Unchanged local copies of the pulled records decide whether the remote copy is newer
+        let unloaded_ids: Vec<String> = remote_records
+            .iter()
+            .filter_map(|r| r.get("id")?.as_str().map(|s| s.to_string()))
+            .filter(|id| !local_versions.contains_key(id))
+            .collect();
+        if !unloaded_ids.is_empty() {
+            let local_guard = local.read().await;
+            local_versions.extend(
+                local_guard
+                    .get_record_versions(table, &unloaded_ids)
+                    .await?,
+            );
+        }
+
+        let remote_by_id: HashMap<String, &Value> = remote_records
+            .iter()
+            .filter_map(|r| Some((r.get("id")?.as_str()?.to_string(), r)))
+            .collect();
+
+        // A dry run shows the local copies pulls would overwrite, which an incremental sync
+        // does not load
+        let local_by_id: HashMap<String, Value> = if dry_run {
+            let local_guard = local.read().await;
+            load_local_records(&local_guard, table)
+                .await?
+                .into_iter()
+                .filter_map(|r| Some((r.get("id")?.as_str()?.to_string(), r)))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
         let mut records_to_push = Vec::new();
-        let mut _records_to_pull: Vec<Value> = Vec::new();
+        let mut records_to_save: Vec<Value> = Vec::new();
+        // Records settled by delete-vs-edit resolution (or left for manual resolution)
+        let mut settled_ids: HashSet<String> = HashSet::new();
+        // Local tombstones for this table, acknowledged once the sync succeeds
+        let mut tombstone_ids: HashSet<String> = HashSet::new();
+        // Acknowledged tombstones already purged from the remote
+        let mut stale_tombstone_ids: Vec<String> = Vec::new();
 
         for local_record in local_records {
             if let Some(id) = local_record.get("id").and_then(|v| v.as_str()) {
-                if let Some(&remote_version) = remote_versions.get(id) {
-                    let local_version = local_versions.get(id).copied().unwrap_or(0);
+                let id = id.to_string();
+                let is_tombstone = ConflictResolver::is_tombstone(&local_record);
+
+                if let Some(remote_record) = remote_by_id.get(&id) {
+                    let is_delete_conflict = if incremental {
+                        ConflictResolver::is_delete_conflict_since_base(
+                            &local_record,
+                            remote_record,
+                            bases.get(&id),
+                        )
+                    } else {
+                        ConflictResolver::is_delete_conflict(
+                            &local_record,
+                            remote_record,
+                            last_sync,
+                        )
+                    };
+                    if is_delete_conflict {
+                        settled_ids.insert(id.clone());
+                        let remote_version = remote_record
+                            .get("version")
+                            .and_then(|v| v.as_u64())
+                            .unwrap_or(0);
+                        let local_version = local_versions.get(&id).copied().unwrap_or(0);
+                        let before = Some(local_record.clone());
+
+                        let conflict = DataConflict {
+                            entity_type: table.to_string(),
+                            entity_id: id.clone(),
+                            local_updated_at: ConflictResolver::record_updated_at(&local_record)
+                                .unwrap_or_else(Utc::now),
+                            remote_updated_at: ConflictResolver::record_updated_at(remote_record)
+                                .unwrap_or_else(Utc::now),
+                            local_data: local_record,
+                            remote_data: (*remote_record).clone(),
+                        };
+
+                        match self.conflict_resolver.resolve(conflict, strategy)? {
+                            ConflictResolution::UseLocal(winner)
+                            | ConflictResolution::UseRemote(winner)
+                            | ConflictResolution::UseMerged(winner) => {
+                                // The winner gets a version above both sides so every device accepts it
+                                let winner =
+                                    with_version(winner, local_version.max(remote_version) + 1);
+                                if ConflictResolver::is_tombstone(&winner) {
+                                    tombstone_ids.insert(id.clone());
+                                }
+                                plan.changes.push(PlannedChange {
+                                    record_id: id.clone(),
+                                    action: PlannedAction::Conflict,
+                                    before,
+                                    after: Some(winner.clone()),
+                                    remote: Some((*remote_record).clone()),
+                                });
+                                records_to_save.push(winner.clone());
+                                records_to_push.push(winner);
+                                stats.conflicts_resolved += 1;
+                            }
+                            ConflictResolution::RequiresManual(conflict) => {
+                                plan.changes.push(PlannedChange {
+                                    record_id: id.clone(),
+                                    action: PlannedAction::Conflict,
+                                    before,
+                                    after: None,
+                                    remote: Some((*remote_record).clone()),
+                                });
+                                if !dry_run {
+                                    self.save_conflict_for_manual_resolution(conflict).await?;
+                                }
+                                stats.manual_conflicts += 1;
+                            }
+                        }
+                        continue;
+                    }
+                }
+
+                if is_tombstone {
+                    tombstone_ids.insert(id.clone());
+                }
+
+                // Both sides edited a record since the last sync: merge it field by field
+                if let (Some(base), Some(remote_record)) = (bases.get(&id), remote_by_id.get(&id)) {
+                    let merge =
+                        ConflictResolver::three_way_merge(base, &local_record, remote_record);
+                    if !is_tombstone
+                        && !ConflictResolver::is_tombstone(remote_record)
+                        && merge.local_changed
+                        && merge.remote_changed
+                    {
+                        settled_ids.insert(id.clone());
+                        let remote_version = remote_record
+                            .get("version")
+                            .and_then(|v| v.as_u64())
+                            .unwrap_or(0);
+                        let version = local_versions.get(&id).copied().unwrap_or(0);
+                        let version = version.max(remote_version) + 1;
+
+                        if merge.conflicts.is_empty() {
+                            let merged = touch_record(with_version(merge.merged, version));
+                            plan.changes.push(PlannedChange {
+                                record_id: id.clone(),
+                                action: PlannedAction::Merge,
+                                before: Some(local_record.clone()),
+                                after: Some(merged.clone()),
+                                remote: Some((*remote_record).clone()),
+                            });
+                            records_to_save.push(merged.clone());
+                            records_to_push.push(merged);
+                            stats.conflicts_resolved += 1;
+                            continue;
+                        }
+
+                        // Settle the fields both sides changed with the configured strategy
+                        let mut remote_merged = merge.merged.clone();
+                        if let Some(obj) = remote_merged.as_object_mut() {
+                            for field in &merge.conflicts {
+                                obj.insert(field.field.clone(), field.remote.clone());
+                            }
+                        }
+                        let conflict = DataConflict {
+                            entity_type: table.to_string(),
+                            entity_id: id.clone(),
+                            local_updated_at: ConflictResolver::record_updated_at(&local_record)
+                                .unwrap_or_else(Utc::now),
+                            remote_updated_at: ConflictResolver::record_updated_at(remote_record)
+                                .unwrap_or_else(Utc::now),
+                            local_data: merge.merged.clone(),
+                            remote_data: remote_merged,
+                        };
+
+                        match self.conflict_resolver.resolve(conflict, strategy)? {
+                            ConflictResolution::UseLocal(winner)
+                            | ConflictResolution::UseRemote(winner)
+                            | ConflictResolution::UseMerged(winner) => {
+                                let winner = touch_record(with_version(winner, version));
+                                plan.changes.push(PlannedChange {
+                                    record_id: id.clone(),
+                                    action: PlannedAction::Conflict,
+                                    before: Some(local_record.clone()),
+                                    after: Some(winner.clone()),
+                                    remote: Some((*remote_record).clone()),
+                                });
+                                records_to_save.push(winner.clone());
+                                records_to_push.push(winner);
+                                stats.conflicts_resolved += 1;
+                            }
+                            ConflictResolution::RequiresManual(_) => {
+                                plan.changes.push(PlannedChange {
+                                    record_id: id.clone(),
+                                    action: PlannedAction::Conflict,
+                                    before: Some(local_record.clone()),
+                                    after: None,
+                                    remote: Some((*remote_record).clone()),
+                                });
+                                if !dry_run {
+                                    self.save_field_conflicts_for_manual_resolution(
+                                        table,
+                                        &id,
+                                        &local_record,
+                                        remote_record,
+                                        base,
+                                        merge,
+                                    )
+                                    .await?;
+                                }
+                                stats.manual_conflicts += 1;
+                            }
+                        }
+                        continue;
+                    }
+                }
+
+                if let Some(&remote_version) = remote_versions.get(&id) {
+                    let local_version = local_versions.get(&id).copied().unwrap_or(0);
 
                     if local_version > remote_version {
                         records_to_push.push(local_record);
                     } else if remote_version > local_version {
+                        let local_changed = if incremental {
+                            bases
+                                .get(&id)
+                                .and_then(|base| base.get("version"))
+                                .and_then(|v| v.as_u64())
+                                != Some(local_version)
+                        } else {
+                            ConflictResolver::record_updated_at(&local_record)
+                                .map(|updated| last_sync.is_none_or(|since| updated > since))
+                                .unwrap_or(true)
+                        };
+
+                        // A newer remote copy of an untouched local record is simply pulled
+                        if !local_changed {
+                            continue;
+                        }
+
                         match strategy {
                             ConflictResolutionStrategy::LocalWins => {
                                 records_to_push.push(local_record);
@@ -407,78 +742,172 @@ impl SyncEngine {
                             }
                         }
                     }
+                } else if is_tombstone && is_acknowledged(&local_record) {
+                    // Every device has seen this deletion and the remote already purged it
+                    tombstone_ids.remove(&id);
+                    stale_tombstone_ids.push(id);
                 } else {
                     records_to_push.push(local_record);
                 }
             }
         }
 
+        // Merges and conflict winners are already planned
+        for record in &records_to_push {
+            let Some(id) = record.get("id").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            if settled_ids.contains(id) {
+                continue;
+            }
+            plan.changes.push(PlannedChange {
+                record_id: id.to_string(),
+                action: if ConflictResolver::is_tombstone(record) {
+                    PlannedAction::DeleteRemote
+                } else {
+                    PlannedAction::Push
+                },
+                before: remote_by_id.get(id).map(|r| (*r).clone()),
+                after: Some(record.clone()),
+                remote: None,
+            });
+        }
+
+        // Records both sides agree on after this sync become the next merge base
+        let mut synced_records = records_to_push.clone();
+
         if !records_to_push.is_empty() {
-            let count = remote.push_records(table, records_to_push).await?;
-            stats.total_synced += count;
+            stats.total_synced += if dry_run {
+                records_to_push.len()
+            } else {
+                remote.push_records(table, records_to_push).await?
+            };
+        }
+
+        if !dry_run {
+            for record in &records_to_save {
+                let local_guard = local.write().await;
+                save_local_record(&local_guard, table, record).await?;
+            }
         }
 
-        for remote_record in remote_records {
+        for remote_record in remote_records.iter() {
             if let Some(id) = remote_record.get("id").and_then(|v| v.as_str()) {
+                if settled_ids.contains(id) {
+                    continue;
+                }
+
                 let remote_version = remote_record
                     .get("version")
                     .and_then(|v| v.as_u64())
                     .unwrap_or(0);
-                let local_version = local_versions.get(id).copied().unwrap_or(0);
-
-                if remote_version >= local_version {
+                // Once incremental, an equal version is this device's own push coming back
+                let is_newer = match local_versions.get(id) {
+                    Some(&local_version) => {
+                        remote_version > local_version
+                            || (remote_version == local_version && !incremental)
+                    }
+                    None => true,
+                };
+
+                if is_newer && dry_run {
+                    plan.changes.push(PlannedChange {
+                        record_id: id.to_string(),
+                        action: if ConflictResolver::is_tombstone(remote_record) {
+                            PlannedAction::DeleteLocal
+                        } else {
+                            PlannedAction::Pull
+                        },
+                        before: local_by_id.get(id).cloned(),
+                        after: Some(remote_record.clone()),
+                        remote: None,
+                    });
+                    stats.total_synced += 1;
+                } else if is_newer {
                     let local_guard = local.write().await;
-
-                    match table {
-                        "ssh_profiles" => {
-                            if let Ok(profile) =
-                                crate::models::ssh::SSHProfile::from_json(&remote_record)
-                            {
-                                local_guard.save_ssh_profile(&profile).await?;
-                                stats.total_synced += 1;
-                            }
-                        }
-                        "ssh_groups" => {
-                            if let Ok(group) =
-                                crate::models::ssh::SSHGroup::from_json(&remote_record)
-                            {
-                                local_guard.save_ssh_group(&group).await?;
-                                stats.total_synced += 1;
-                            }
-                        }
-                        "ssh_keys" => {
-                            if let Ok(key) = crate::models::ssh::SSHKey::from_json(&remote_record) {
-                                local_guard.save_ssh_key(&key).await?;
-                                stats.total_synced += 1;
-                            }
-                        }
-                        "saved_command_groups" => {
-                            if let Ok(group) =
-                                crate::models::saved_command::SavedCommandGroup::from_json(
-                                    &remote_record,
-                                )
-                            {
-                                local_guard.save_saved_command_group(&group).await?;
-                                stats.total_synced += 1;
-                            }
-                        }
-                        "saved_commands" => {
-                            if let Ok(command) =
-                                crate::models::saved_command::SavedCommand::from_json(
-                                    &remote_record,
-                                )
-                            {
-                                local_guard.save_saved_command(&command).await?;
-                                stats.total_synced += 1;
-                            }
+                    if save_local_record(&local_guard, table, remote_record).await? {
+                        stats.total_synced += 1;
+                        synced_records.push(remote_record.clone());
+                        if ConflictResolver::is_tombstone(remote_record) {
+                            tombstone_ids.insert(id.to_string());
+                        } else {
+                            tombstone_ids.remove(id);
                         }
-                        _ => {}
                     }
                 }
             }
         }
 
-        Ok(stats)
+        if dry_run {
+            return Ok((stats, plan));
+        }
+
+        {
+            let local_guard = local.write().await;
+            local_guard
+                .save_sync_base_records(table, &synced_records)
+                .await?;
+        }
+
+        self.collect_tombstones(
+            remote,
+            table,
+            device_id,
+            tombstone_ids.into_iter().collect(),
+            stale_tombstone_ids,
+        )
+        .await?;
+
+        {
+            let local_guard = local.write().await;
+            local_guard
+                .save_sync_cursor(&SyncCursor {
+                    database_id: database_id.to_string(),
+                    table_name: table.to_string(),
+                    local_seq,
+                    remote_cursor: changes.cursor,
+                    updated_at: Utc::now(),
+                })
+                .await?;
+        }
+
+        Ok((stats, plan))
+    }
+
+    /// Acknowledge the tombstones this device has seen and purge the ones every device has seen
+    async fn collect_tombstones(
+        &self,
+        remote: &Arc<dyn crate::database::traits_sync::SyncTarget>,
+        table: &str,
+        device_id: &str,
+        seen_ids: Vec<String>,
+        mut purged_ids: Vec<String>,
+    ) -> DatabaseResult<()> {
+        let db_service = self.database_service.lock().await;
+        let local = db_service.get_local_database();
+        drop(db_service);
+
+        if !seen_ids.is_empty() {
+            remote
+                .acknowledge_tombstones(table, device_id, seen_ids.clone())
+                .await?;
+            let local_guard = local.write().await;
+            local_guard
+                .mark_tombstones_acknowledged(table, &seen_ids)
+                .await?;
+        }
+
+        purged_ids.extend(remote.purge_acknowledged_tombstones(table).await?);
+        if !purged_ids.is_empty() {
+            let local_guard = local.write().await;
+            let count = local_guard.purge_tombstones(table, &purged_ids).await?;
+            eprintln!(
+                "[INFO] Purged {} acknowledged tombstones from {}",
+                count, table
+            );
+        }
+
+        Ok(())
     }
 
     /// Create a new sync log entry
@@ -518,6 +947,17 @@ impl SyncEngine {
         guard.save_sync_log(log).await
     }
 
+    /// Get global sync settings, falling back to defaults when none were saved
+    async fn get_sync_settings(&self) -> DatabaseResult<SyncSettings> {
+        let db_service = self.database_service.lock().await;
+        let local = db_service.get_local_database();
+        let guard = local.read().await;
+        Ok(guard
+            .get_global_sync_settings()
+            .await?
+            .unwrap_or_else(SyncSettings::new))
+    }
+
     /// Get last sync time for a database
     async fn get_last_sync_time(&self, database_id: &str) -> DatabaseResult<Option<DateTime<Utc>>> {
         let db_service = self.database_service.lock().await;
@@ -547,6 +987,9 @@ impl SyncEngine {
                 .map_err(DatabaseError::SerializationError)?,
             remote_data: serde_json::to_value(&conflict.remote_data)
                 .map_err(DatabaseError::SerializationError)?,
+            base_data: None,
+            merged_data: None,
+            field_conflicts: Vec::new(),
             resolution_strategy: None,
             resolved_at: None,
             created_at: Utc::now(),
@@ -563,6 +1006,46 @@ impl SyncEngine {
         Ok(())
     }
 
+    /// Save the fields both sides changed for manual resolution, with the merged record as a starting point
+    async fn save_field_conflicts_for_manual_resolution(
+        &self,
+        table: &str,
+        id: &str,
+        local_data: &serde_json::Value,
+        remote_data: &serde_json::Value,
+        base_data: &serde_json::Value,
+        merge: ThreeWayMerge,
+    ) -> DatabaseResult<()> {
+        let db_service = self.database_service.lock().await;
+        let local = db_service.get_local_database();
+
+        let conflict_resolution = crate::models::sync::conflict::ConflictResolution {
+            id: uuid::Uuid::new_v4().to_string(),
+            entity_type: table.to_string(),
+            entity_id: id.to_string(),
+            local_data: local_data.clone(),
+            remote_data: remote_data.clone(),
+            base_data: Some(base_data.clone()),
+            merged_data: Some(merge.merged),
+            field_conflicts: merge.conflicts,
+            resolution_strategy: None,
+            resolved_at: None,
+            created_at: Utc::now(),
+        };
+
+        let guard = local.write().await;
+        guard.save_conflict_resolution(&conflict_resolution).await?;
+
+        eprintln!(
+            "[INFO] Saved {} conflicting fields for manual resolution: {} ({})",
+            conflict_resolution.field_conflicts.len(),
+            table,
+            id
+        );
+
+        Ok(())
+    }
+
     /// Sync SSH Profiles with conflict detection
     #[allow(dead_code)]
     async fn sync_ssh_profiles(
@@ -816,6 +1299,187 @@ impl SyncEngine {
     }
 }
 
+/// Replace the version of a sync record
+fn with_version(mut record: serde_json::Value, version: u64) -> serde_json::Value {
+    if let Some(obj) = record.as_object_mut() {
+        obj.insert("version".to_string(), serde_json::Value::from(version));
+    }
+    record
+}
+
+/// Stamp a sync record produced by a merge as modified now
+fn touch_record(mut record: serde_json::Value) -> serde_json::Value {
+    if let Some(obj) = record.as_object_mut() {
+        obj.insert(
+            "updatedAt".to_string(),
+            serde_json::Value::from(Utc::now().to_rfc3339()),
+        );
+    }
+    record
+}
+
+/// Check whether a local tombstone has already been acknowledged on the remote
+fn is_acknowledged(record: &serde_json::Value) -> bool {
+    record.get("syncStatus").and_then(|v| v.as_str()) == Some("Synced")
+}
+
+/// Load every local record of a sync table, tombstones included
+pub(super) async fn load_local_records(
+    local: &SQLiteProvider,
+    table: &str,
+) -> DatabaseResult<Vec<serde_json::Value>> {
+    use crate::services::sync::SyncSerializable;
+
+    fn to_json<T: SyncSerializable>(mut live: Vec<T>, deleted: Vec<T>) -> Vec<serde_json::Value> {
+        live.extend(deleted);
+        live.iter().filter_map(|r| r.to_json().ok()).collect()
+    }
+
+    Ok(match table {
+        "ssh_profiles" => to_json(
+            local.find_all_ssh_profiles().await?,
+            local.find_deleted_ssh_profiles().await?,
+        ),
+        "ssh_groups" => to_json(
+            local.find_all_ssh_groups().await?,
+            local.find_deleted_ssh_groups().await?,
+        ),
+        "ssh_keys" => to_json(
+            local.find_all_ssh_keys().await?,
+            local.find_deleted_ssh_keys().await?,
+        ),
+        "ssh_tunnels" => to_json(
+            local.find_all_ssh_tunnels().await?,
+            local.find_deleted_ssh_tunnels().await?,
+        ),
+        "saved_command_groups" => to_json(
+            local.find_all_saved_command_groups().await?,
+            local.find_deleted_saved_command_groups().await?,
+        ),
+        "saved_commands" => to_json(
+            local.find_all_saved_commands().await?,
+            local.find_deleted_saved_commands().await?,
+        ),
+        "terminal_profiles" => to_json(
+            local.find_all_terminal_profiles().await?,
+            local.find_deleted_terminal_profiles().await?,
+        ),
+        "session_recordings" => to_json(
+            local.list_session_recordings().await?,
+            local.find_deleted_session_recordings().await?,
+        ),
+        "known_hosts" => to_json(
+            local.find_all_known_hosts().await?,
+            local.find_deleted_known_hosts().await?,
+        ),
+        _ => vec![],
+    })
+}
+
+/// Load the local records of a sync table written after a change log position, tombstones included
+async fn load_changed_records(
+    local: &SQLiteProvider,
+    table: &str,
+    after_seq: i64,
+) -> DatabaseResult<Vec<serde_json::Value>> {
+    use crate::services::sync::SyncSerializable;
+
+    fn to_json<T: SyncSerializable>(records: Vec<T>) -> Vec<serde_json::Value> {
+        records.iter().filter_map(|r| r.to_json().ok()).collect()
+    }
+
+    Ok(match table {
+        "ssh_profiles" => to_json(local.find_changed_ssh_profiles(after_seq).await?),
+        "ssh_groups" => to_json(local.find_changed_ssh_groups(after_seq).await?),
+        "ssh_keys" => to_json(local.find_changed_ssh_keys(after_seq).await?),
+        "ssh_tunnels" => to_json(local.find_changed_ssh_tunnels(after_seq).await?),
+        "saved_command_groups" => {
+            to_json(local.find_changed_saved_command_groups(after_seq).await?)
+        }
+        "saved_commands" => to_json(local.find_changed_saved_commands(after_seq).await?),
+        "terminal_profiles" => to_json(local.find_changed_terminal_profiles(after_seq).await?),
+        "session_recordings" => to_json(local.find_changed_session_recordings(after_seq).await?),
+        "known_hosts" => to_json(local.find_changed_known_hosts(after_seq).await?),
+        _ => vec![],
+    })
+}
+
+/// Write a sync record to the local database, returning false if it could not be decoded
+async fn save_local_record(
+    local: &SQLiteProvider,
+    table: &str,
+    record: &serde_json::Value,
+) -> DatabaseResult<bool> {
+    use crate::services::sync::SyncSerializable;
+
+    match table {
+        "ssh_profiles" => {
+            if let Ok(profile) = crate::models::ssh::SSHProfile::from_json(record) {
+                local.save_ssh_profile(&profile).await?;
+                if profile.base.is_deleted() {
+                    // The row is already a tombstone; this also deletes the profile's tunnels
+                    local.delete_ssh_profile(&profile.base.id).await?;
+                }
+                return Ok(true);
+            }
+        }
+        "ssh_groups" => {
+            if let Ok(group) = crate::models::ssh::SSHGroup::from_json(record) {
+                local.save_ssh_group(&group).await?;
+                return Ok(true);
+            }
+        }
+        "ssh_keys" => {
+            if let Ok(key) = crate::models::ssh::SSHKey::from_json(record) {
+                local.save_ssh_key(&key).await?;
+                return Ok(true);
+            }
+        }
+        "saved_command_groups" => {
+            if let Ok(group) = crate::models::saved_command::SavedCommandGroup::from_json(record) {
+                local.save_saved_command_group(&group).await?;
+                return Ok(true);
+            }
+        }
+        "saved_commands" => {
+            if let Ok(command) = crate::models::saved_command::SavedCommand::from_json(record) {
+                local.save_saved_command(&command).await?;
+                return Ok(true);
+            }
+        }
+        "ssh_tunnels" => {
+            if let Ok(tunnel) = crate::models::ssh::SSHTunnel::from_json(record) {
+                local.save_ssh_tunnel(&tunnel).await?;
+                return Ok(true);
+            }
+        }
+        "terminal_profiles" => {
+            if let Ok(profile) =
+                crate::models::terminal::profile::TerminalProfile::from_json(record)
+            {
+                local.save_terminal_profile(&profile).await?;
+                return Ok(true);
+            }
+        }
+        "session_recordings" => {
+            if let Ok(recording) = crate::models::recording::SessionRecording::from_json(record) {
+                // The cast file is left alone: its path comes from another device
+                local.save_session_recording(&recording).await?;
+                return Ok(true);
+            }
+        }
+        "known_hosts" => {
+            if let Ok(host) = crate::models::ssh::KnownHost::from_json(record) {
+                local.save_known_host(&host).await?;
+                return Ok(true);
+            }
+        }
+        _ => {}
+    }
+
+    Ok(false)
+}
+
 /// Sync statistics
 #[derive(Debug, Default, Clone)]
 struct SyncStats {
@@ -835,6 +1499,17 @@ impl SyncStats {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::base::BaseModel;
+    use crate::models::recording::SessionRecording;
+    use crate::models::saved_command::{SavedCommand, SavedCommandGroup};
+    use crate::models::ssh::profile::KeyType;
+    use crate::models::ssh::{
+        DeleteGroupAction, KnownHost, SSHGroup, SSHKey, SSHProfile, SSHTunnel, TunnelType,
+    };
+    use crate::models::terminal::profile::TerminalProfile;
+    use crate::services::sync::fixture::{self, TestDevice};
+
+    const KNOWN_HOST_LINE: &str = "example.com ssh-ed25519 AAAAC3NzaC1lZDI1NTE5";
 
     #[test]
     fn test_sync_stats_merge() {
@@ -856,4 +1531,459 @@ mod tests {
         assert_eq!(stats1.conflicts_resolved, 3);
         assert_eq!(stats1.manual_conflicts, 1);
     }
+
+    async fn group_names(device: &TestDevice) -> Vec<String> {
+        let db_service = device.database_service.lock().await;
+        db_service
+            .get_ssh_groups()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|group| group.name)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_sync_shares_records_between_devices() {
+        let dir = fixture::temp_dir();
+        let remote = dir.join("share").join("sync.db");
+        let device_a = TestDevice::new(&dir, "device-a", &remote).await;
+        let device_b = TestDevice::new(&dir, "device-b", &remote).await;
+
+        device_a.create_group("production").await;
+
+        device_a.sync().await;
+        device_b.sync().await;
+
+        assert_eq!(group_names(&device_b).await, vec!["production"]);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_sync_propagates_deletions() {
+        let dir = fixture::temp_dir();
+        let remote = dir.join("share").join("sync.db");
+        let device_a = TestDevice::new(&dir, "device-a", &remote).await;
+        let device_b = TestDevice::new(&dir, "device-b", &remote).await;
+
+        let group = device_a.create_group("staging").await;
+
+        device_a.sync().await;
+        device_b.sync().await;
+        assert_eq!(group_names(&device_b).await, vec!["staging"]);
+
+        device_a
+            .database_service
+            .lock()
+            .await
+            .delete_ssh_group(&group.base.id, DeleteGroupAction::MoveToUngrouped)
+            .await
+            .unwrap();
+
+        device_a.sync().await;
+        device_b.sync().await;
+        assert!(group_names(&device_b).await.is_empty());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_sync_picks_up_edits_from_skewed_clocks() {
+        let dir = fixture::temp_dir();
+        let remote = dir.join("share").join("sync.db");
+        let device_a = TestDevice::new(&dir, "device-a", &remote).await;
+        let device_b = TestDevice::new(&dir, "device-b", &remote).await;
+
+        device_a.create_group("staging").await;
+
+        device_a.sync().await;
+        device_b.sync().await;
+
+        // Device B's clock is years behind, so its edit predates every previous sync
+        {
+            let db_service = device_b.database_service.lock().await;
+            let local = db_service.get_local_database();
+            let local_guard = local.read().await;
+            let mut group = local_guard.find_all_ssh_groups().await.unwrap().remove(0);
+            group.name = "production".to_string();
+            group.base.version += 1;
+            group.base.updated_at = "2001-01-01T00:00:00Z".parse().unwrap();
+            local_guard.save_ssh_group(&group).await.unwrap();
+        }
+
+        device_b.sync().await;
+        device_a.sync().await;
+        assert_eq!(group_names(&device_a).await, vec!["production"]);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_preview_plans_changes_without_applying_them() {
+        let dir = fixture::temp_dir();
+        let remote = dir.join("share").join("sync.db");
+        let device_a = TestDevice::new(&dir, "device-a", &remote).await;
+        let device_b = TestDevice::new(&dir, "device-b", &remote).await;
+
+        let group = device_a.create_group("staging").await;
+
+        // Nothing was synced yet, and previewing does not set the remote up either
+        let plan = device_a.engine.preview(&device_a.config).await.unwrap();
+        assert!(!remote.exists());
+        assert_eq!(plan.tables.len(), 1);
+        assert_eq!(plan.tables[0].table, "ssh_groups");
+        let change = &plan.tables[0].changes[0];
+        assert_eq!(change.record_id, group.base.id);
+        assert_eq!(change.action, PlannedAction::Push);
+        assert!(change.before.is_none());
+
+        // Nothing reached the remote, so device B has nothing to pull yet
+        let plan = device_b.engine.preview(&device_b.config).await.unwrap();
+        assert_eq!(plan.change_count(), 0);
+
+        device_a.sync().await;
+
+        let plan = device_b.engine.preview(&device_b.config).await.unwrap();
+        assert_eq!(plan.change_count(), 1);
+        let change = &plan.tables[0].changes[0];
+        assert_eq!(change.action, PlannedAction::Pull);
+        assert_eq!(change.after.as_ref().unwrap()["name"], "staging");
+        assert!(group_names(&device_b).await.is_empty());
+
+        device_b.sync().await;
+        assert_eq!(group_names(&device_b).await, vec!["staging"]);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    /// Save a sample record of a synced table on a device, returning its ID
+    async fn save_sample_record(device: &TestDevice, table: &str) -> String {
+        if table == "known_hosts" {
+            std::fs::write(&device.known_hosts, format!("{}\n", KNOWN_HOST_LINE)).unwrap();
+            return KnownHost::parse(KNOWN_HOST_LINE, "").unwrap().base.id;
+        }
+
+        let db_service = device.database_service.lock().await;
+        let device_id = db_service.get_device_id().to_string();
+        let local = db_service.get_local_database();
+        let local = local.read().await;
+
+        match table {
+            "ssh_groups" => {
+                let group = SSHGroup::new(device_id, "group".to_string());
+                local.save_ssh_group(&group).await.unwrap();
+                group.base.id
+            }
+            "ssh_keys" => {
+                let key = SSHKey::new(
+                    device_id,
+                    "key".to_string(),
+                    KeyType::Ed25519,
+                    "private key".to_string(),
+                    None,
+                    None,
+                );
+                local.save_ssh_key(&key).await.unwrap();
+                key.base.id
+            }
+            "ssh_profiles" | "ssh_tunnels" => {
+                let profile = SSHProfile::new(
+                    device_id.clone(),
+                    "profile".to_string(),
+                    "example.com".to_string(),
+                    22,
+                    "root".to_string(),
+                );
+                local.save_ssh_profile(&profile).await.unwrap();
+                if table == "ssh_profiles" {
+                    return profile.base.id;
+                }
+
+                let tunnel = SSHTunnel::new(
+                    device_id,
+                    "tunnel".to_string(),
+                    profile.base.id,
+                    TunnelType::Local,
+                    "127.0.0.1".to_string(),
+                    8080,
+                    Some("localhost".to_string()),
+                    Some(80),
+                );
+                local.save_ssh_tunnel(&tunnel).await.unwrap();
+                tunnel.base.id
+            }
+            "saved_command_groups" => {
+                let group = SavedCommandGroup::new(device_id, "group".to_string());
+                local.save_saved_command_group(&group).await.unwrap();
+                group.base.id
+            }
+            "saved_commands" => {
+                let command =
+                    SavedCommand::new(device_id, "uptime".to_string(), "uptime".to_string(), None);
+                local.save_saved_command(&command).await.unwrap();
+                command.base.id
+            }
+            "terminal_profiles" => {
+                let profile = TerminalProfile {
+                    base: BaseModel::new(device_id),
+                    name: "shell".to_string(),
+                    shell: "/bin/sh".to_string(),
+                    working_dir: None,
+                    env: None,
+                    icon: None,
+                    color: None,
+                    command: None,
+                };
+                local.save_terminal_profile(&profile).await.unwrap();
+                profile.base.id
+            }
+            "session_recordings" => {
+                let recording = SessionRecording {
+                    base: BaseModel::new(device_id),
+                    terminal_id: "terminal".to_string(),
+                    session_name: "session".to_string(),
+                    terminal_type: "Local".to_string(),
+                    started_at: Utc::now(),
+                    ended_at: None,
+                    duration_ms: None,
+                    file_path: "session.cast".to_string(),
+                    file_size: 0,
+                    width: 80,
+                    height: 24,
+                    metadata: None,
+                };
+                local.save_session_recording(&recording).await.unwrap();
+                recording.base.id
+            }
+            _ => panic!("no sample record for {}", table),
+        }
+    }
+
+    /// Delete a sample record the way the app does, leaving a tombstone
+    async fn delete_sample_record(device: &TestDevice, table: &str, id: &str) {
+        if table == "known_hosts" {
+            std::fs::write(&device.known_hosts, "").unwrap();
+            return;
+        }
+
+        let db_service = device.database_service.lock().await;
+        let local = db_service.get_local_database();
+        let local = local.read().await;
+        local.mark_deleted(table, id).await.unwrap();
+    }
+
+    /// Check whether a device holds a record that is not a tombstone
+    async fn is_live(device: &TestDevice, table: &str, id: &str) -> bool {
+        let db_service = device.database_service.lock().await;
+        let local = db_service.get_local_database();
+        let local = local.read().await;
+        load_local_records(&local, table)
+            .await
+            .unwrap()
+            .iter()
+            .any(|record| record["id"] == id && record["deletedAt"].is_null())
+    }
+
+    /// Sync a record of `table` from device A to device B, delete it on A and sync again
+    async fn check_tombstone_propagates(table: &str) {
+        let dir = fixture::temp_dir();
+        let remote = dir.join("share").join("sync.db");
+        let device_a = TestDevice::new(&dir, "device-a", &remote).await;
+        let device_b = TestDevice::new(&dir, "device-b", &remote).await;
+
+        let id = save_sample_record(&device_a, table).await;
+        device_a.sync().await;
+        device_b.sync().await;
+        assert!(
+            is_live(&device_b, table, &id).await,
+            "{} record was not synced",
+            table
+        );
+
+        delete_sample_record(&device_a, table, &id).await;
+        device_a.sync().await;
+        device_b.sync().await;
+        assert!(
+            !is_live(&device_b, table, &id).await,
+            "{} deletion was not synced",
+            table
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_tombstones_propagate_ssh_groups() {
+        check_tombstone_propagates("ssh_groups").await;
+    }
+
+    #[tokio::test]
+    async fn test_tombstones_propagate_ssh_keys() {
+        check_tombstone_propagates("ssh_keys").await;
+    }
+
+    #[tokio::test]
+    async fn test_tombstones_propagate_ssh_profiles() {
+        check_tombstone_propagates("ssh_profiles").await;
+    }
+
+    #[tokio::test]
+    async fn test_tombstones_propagate_ssh_tunnels() {
+        check_tombstone_propagates("ssh_tunnels").await;
+    }
+
+    #[tokio::test]
+    async fn test_tombstones_propagate_saved_command_groups() {
+        check_tombstone_propagates("saved_command_groups").await;
+    }
+
+    #[tokio::test]
+    async fn test_tombstones_propagate_saved_commands() {
+        check_tombstone_propagates("saved_commands").await;
+    }
+
+    #[tokio::test]
+    async fn test_tombstones_propagate_terminal_profiles() {
+        check_tombstone_propagates("terminal_profiles").await;
+    }
+
+    #[tokio::test]
+    async fn test_tombstones_propagate_session_recordings() {
+        check_tombstone_propagates("session_recordings").await;
+    }
+
+    #[tokio::test]
+    async fn test_tombstones_propagate_known_hosts() {
+        check_tombstone_propagates("known_hosts").await;
+    }
+
+    #[tokio::test]
+    async fn test_known_hosts_reach_the_other_device_file() {
+        let dir = fixture::temp_dir();
+        let remote = dir.join("share").join("sync.db");
+        let device_a = TestDevice::new(&dir, "device-a", &remote).await;
+        let device_b = TestDevice::new(&dir, "device-b", &remote).await;
+        std::fs::write(&device_b.known_hosts, "# kept\n").unwrap();
+
+        save_sample_record(&device_a, "known_hosts").await;
+        device_a.sync().await;
+        device_b.sync().await;
+        assert_eq!(
+            std::fs::read_to_string(&device_b.known_hosts).unwrap(),
+            format!("# kept\n{}\n", KNOWN_HOST_LINE)
+        );
+
+        std::fs::write(&device_a.known_hosts, "").unwrap();
+        device_a.sync().await;
+        device_b.sync().await;
+        assert_eq!(
+            std::fs::read_to_string(&device_b.known_hosts).unwrap(),
+            "# kept\n"
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    /// Edit the only group of a device the way a user edit would
+    async fn edit_group(device: &TestDevice, edit: impl FnOnce(&mut SSHGroup)) {
+        let db_service = device.database_service.lock().await;
+        let local = db_service.get_local_database();
+        let local_guard = local.read().await;
+        let mut group = local_guard.find_all_ssh_groups().await.unwrap().remove(0);
+        edit(&mut group);
+        group.base.touch();
+        local_guard.save_ssh_group(&group).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_manual_conflict_resolved_field_by_field() {
+        let dir = fixture::temp_dir();
+        let remote = dir.join("share").join("sync.db");
+        let device_a = TestDevice::new(&dir, "device-a", &remote).await;
+        let device_b = TestDevice::new(&dir, "device-b", &remote).await;
+
+        device_a.create_group("staging").await;
+        device_a.sync().await;
+        device_b.sync().await;
+
+        {
+            let db_service = device_b.database_service.lock().await;
+            let local = db_service.get_local_database();
+            let local_guard = local.read().await;
+            let mut settings = SyncSettings::new();
+            settings.conflict_strategy = ConflictResolutionStrategy::Manual;
+            local_guard
+                .save_global_sync_settings(&settings)
+                .await
+                .unwrap();
+        }
+
+        edit_group(&device_a, |group| {
+            group.name = "production".to_string();
+            group.description = Some("from a".to_string());
+        })
+        .await;
+        edit_group(&device_b, |group| {
+            group.name = "prod".to_string();
+            group.description = Some("from b".to_string());
+            group.color = Some("#00ff00".to_string());
+        })
+        .await;
+        device_a.sync().await;
+        device_b.sync().await;
+
+        let conflict = {
+            let db_service = device_b.database_service.lock().await;
+            let local = db_service.get_local_database();
+            let local_guard = local.read().await;
+            local_guard
+                .get_unresolved_conflict_resolutions()
+                .await
+                .unwrap()
+                .remove(0)
+        };
+        let mut fields: Vec<&str> = conflict
+            .field_conflicts
+            .iter()
+            .map(|field| field.field.as_str())
+            .collect();
+        fields.sort();
+        assert_eq!(fields, vec!["description", "name"]);
+
+        let choices = HashMap::from([
+            ("name".to_string(), FieldChoice::Remote),
+            ("description".to_string(), FieldChoice::Local),
+        ]);
+        device_b
+            .engine
+            .resolve_conflict(&conflict.id, ConflictResolutionStrategy::Manual, &choices)
+            .await
+            .unwrap();
+        // The conflict is settled once, together with the record
+        assert!(device_b
+            .engine
+            .resolve_conflict(&conflict.id, ConflictResolutionStrategy::Manual, &choices)
+            .await
+            .is_err());
+
+        device_b.sync().await;
+        device_a.sync().await;
+
+        for device in [&device_a, &device_b] {
+            let groups = device
+                .database_service
+                .lock()
+                .await
+                .get_ssh_groups()
+                .await
+                .unwrap();
+            assert_eq!(groups.len(), 1);
+            assert_eq!(groups[0].name, "production");
+            assert_eq!(groups[0].description.as_deref(), Some("from b"));
+            assert_eq!(groups[0].color.as_deref(), Some("#00ff00"));
+        }
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
 }
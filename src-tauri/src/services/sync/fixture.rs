@@ -0,0 +1,154 @@
+//! Devices syncing through a shared SQLite file, for `SyncEngine` integration tests
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::database::{
+    config::MasterPasswordConfig,
+    encryption::{master_password::SetupMasterPasswordRequest, ExternalDbEncryptor},
+    DatabaseService, DatabaseServiceConfig,
+};
+use crate::models::ssh::{CreateSSHGroupRequest, SSHGroup};
+use crate::models::sync::external_db::{ConnectionDetails, DatabaseType, ExternalDatabaseConfig};
+use crate::models::sync::SyncSettings;
+
+use super::{SyncEncryption, SyncEngine, SyncManager};
+
+/// A device with its own local database, registered with a SQLite sync target
+pub struct TestDevice {
+    pub database_service: Arc<Mutex<DatabaseService>>,
+    pub engine: SyncEngine,
+    pub config: ExternalDatabaseConfig,
+    /// known_hosts file the device syncs, in place of `~/.ssh/known_hosts`
+    pub known_hosts: PathBuf,
+}
+
+/// Fresh directory holding the local databases and the shared sync target
+pub fn temp_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("kerminal-sync-{}", uuid::Uuid::new_v4()))
+}
+
+/// Local database of a device named `name` in `dir`, unlocked with a master password
+pub async fn database_service(dir: &Path, name: &str) -> DatabaseService {
+    std::fs::create_dir_all(dir).unwrap();
+
+    let mut database_service = DatabaseService::new(DatabaseServiceConfig {
+        local_db_path: dir
+            .join(format!("{}.db", name))
+            .to_string_lossy()
+            .to_string(),
+        master_password_config: MasterPasswordConfig {
+            auto_unlock: true,
+            session_timeout_minutes: None,
+            require_on_startup: false,
+            use_keychain: false,
+        },
+    })
+    .await
+    .unwrap();
+
+    // Saves the current device, which syncing requires
+    database_service
+        .setup_master_password(SetupMasterPasswordRequest {
+            device_name: name.to_string(),
+            password: "password".to_string(),
+            confirm_password: "password".to_string(),
+            auto_unlock: true,
+            use_keychain: false,
+            auto_lock_timeout: None,
+        })
+        .await
+        .unwrap();
+
+    database_service
+}
+
+impl TestDevice {
+    /// Set up a device in `dir` syncing with the SQLite file at `remote`
+    pub async fn new(dir: &Path, name: &str, remote: &Path) -> Self {
+        let database_service = database_service(dir, name).await;
+
+        let connection_details = ConnectionDetails {
+            host: remote.to_string_lossy().to_string(),
+            port: 0,
+            username: String::new(),
+            password: String::new(),
+            database_name: String::new(),
+            ssl_enabled: false,
+            ssl_cert: None,
+            protocol: None,
+            options: None,
+            encryption_passphrase: None,
+        };
+        let encrypted =
+            ExternalDbEncryptor::new(database_service.get_master_password_manager_arc())
+                .encrypt_connection_details(&connection_details)
+                .await
+                .unwrap();
+
+        let config = ExternalDatabaseConfig::new(
+            database_service.get_device_id().to_string(),
+            "Shared SQLite".to_string(),
+            DatabaseType::SQLite,
+            encrypted,
+        );
+        database_service
+            .save_external_database(&config)
+            .await
+            .unwrap();
+
+        // known_hosts is opt-in; the tests cover it along with everything else
+        let mut settings = SyncSettings::new();
+        settings.synced_entities.known_hosts = true;
+        database_service
+            .get_local_database()
+            .read()
+            .await
+            .save_global_sync_settings(&settings)
+            .await
+            .unwrap();
+
+        let database_service = Arc::new(Mutex::new(database_service));
+        let sync_manager = Arc::new(SyncManager::new(database_service.clone()));
+        let encryption = Arc::new(SyncEncryption::new(
+            database_service.clone(),
+            sync_manager.clone(),
+        ));
+        let known_hosts = dir.join(format!("{}_known_hosts", name));
+        let engine = SyncEngine::new(database_service.clone(), sync_manager, encryption)
+            .with_known_hosts_path(known_hosts.clone());
+
+        Self {
+            database_service,
+            engine,
+            config,
+            known_hosts,
+        }
+    }
+
+    /// Create an SSH group in the device's local database
+    pub async fn create_group(&self, name: &str) -> SSHGroup {
+        self.database_service
+            .lock()
+            .await
+            .create_ssh_group(CreateSSHGroupRequest {
+                name: name.to_string(),
+                description: None,
+                color: None,
+                icon: None,
+            })
+            .await
+            .unwrap()
+    }
+
+    /// Run a bidirectional sync, failing the test if it does not complete
+    pub async fn sync(&self) {
+        let log = self.engine.sync(&self.config).await.unwrap();
+        assert!(
+            matches!(log.status, crate::models::sync::log::SyncStatus::Completed),
+            "sync failed: {:?}",
+            log.error_message
+        );
+    }
+}
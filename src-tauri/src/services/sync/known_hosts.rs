@@ -0,0 +1,183 @@
+//! Keeps a known_hosts file and the synced `known_hosts` table in step
+//!
+//! Before a sync, lines added to the file become records and lines removed from it become
+//! tombstones. After a sync, the file is rewritten to hold the live records, so host keys
+//! learned or removed on other devices reach this one.
+//!
+//! `@cert-authority` and `@revoked` lines stay on the device they were written on; trusting a
+//! CA or revoking a key is not something another device gets to decide.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::database::{
+    error::{DatabaseError, DatabaseResult},
+    providers::sqlite::SQLiteProvider,
+};
+use crate::models::ssh::KnownHost;
+use crate::services::ssh::config_export::write_if_changed;
+
+/// `~/.ssh/known_hosts`, the file synced outside of tests
+pub fn default_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".ssh").join("known_hosts"))
+}
+
+async fn read_file(path: &Path) -> DatabaseResult<String> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(DatabaseError::ConfigError(format!(
+            "Failed to read {}: {}",
+            path.display(),
+            e
+        ))),
+    }
+}
+
+/// Record host keys added to the file since the last sync, and tombstone the removed ones
+pub async fn import(local: &SQLiteProvider, device_id: &str, path: &Path) -> DatabaseResult<()> {
+    let content = read_file(path).await?;
+    let mut in_file: HashMap<String, KnownHost> = content
+        .lines()
+        .filter_map(|line| KnownHost::parse(line, device_id))
+        .filter(|host| host.marker.is_none())
+        .map(|host| (host.base.id.clone(), host))
+        .collect();
+
+    for host in local.find_all_known_hosts().await? {
+        if in_file.remove(&host.base.id).is_none() {
+            local.mark_deleted("known_hosts", &host.base.id).await?;
+        }
+    }
+
+    let tombstones: HashMap<String, KnownHost> = local
+        .find_deleted_known_hosts()
+        .await?
+        .into_iter()
+        .map(|host| (host.base.id.clone(), host))
+        .collect();
+
+    for (id, mut host) in in_file {
+        // A line added back after its deletion synced must outrank the tombstone
+        if let Some(tombstone) = tombstones.get(&id) {
+            host.base = tombstone.base.clone();
+            host.base.device_id = device_id.to_string();
+            host.base.deleted_at = None;
+            host.base.touch();
+        }
+        local.save_known_host(&host).await?;
+    }
+
+    Ok(())
+}
+
+/// Rewrite the file to hold exactly the live host keys, keeping comments and unparsed lines
+pub async fn export(local: &SQLiteProvider, path: &Path) -> DatabaseResult<()> {
+    let content = read_file(path).await?;
+    let live = local.find_all_known_hosts().await?;
+    let updated = render(&content, &live);
+    if updated == content {
+        return Ok(());
+    }
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await.map_err(|e| {
+            DatabaseError::ConfigError(format!("Failed to create {}: {}", parent.display(), e))
+        })?;
+    }
+    write_if_changed(path, &updated, 0o600).await?;
+    Ok(())
+}
+
+/// File content holding the live host keys, in their existing order with new ones appended
+///
+/// Marker lines in the file are kept as they are and synced marker records are never written.
+fn render(content: &str, live: &[KnownHost]) -> String {
+    let live: Vec<&KnownHost> = live.iter().filter(|host| host.marker.is_none()).collect();
+    let live_ids: HashSet<&str> = live.iter().map(|host| host.base.id.as_str()).collect();
+    let mut written = HashSet::new();
+    let mut lines = Vec::new();
+
+    for line in content.lines() {
+        match KnownHost::parse(line, "").filter(|host| host.marker.is_none()) {
+            Some(host) => {
+                if live_ids.contains(host.base.id.as_str()) && written.insert(host.base.id) {
+                    lines.push(line.to_string());
+                }
+            }
+            None => lines.push(line.to_string()),
+        }
+    }
+
+    for host in live {
+        if written.insert(host.base.id.clone()) {
+            lines.push(host.line());
+        }
+    }
+
+    if lines.is_empty() {
+        String::new()
+    } else {
+        lines.join("\n") + "\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_known_host_lines() {
+        let host =
+            KnownHost::parse("example.com,10.0.0.1 ssh-ed25519 AAAAC3 user@laptop", "d").unwrap();
+        assert_eq!(host.marker, None);
+        assert_eq!(host.hosts, "example.com,10.0.0.1");
+        assert_eq!(host.key_type, "ssh-ed25519");
+        assert_eq!(host.public_key, "AAAAC3");
+        assert_eq!(host.line(), "example.com,10.0.0.1 ssh-ed25519 AAAAC3");
+
+        let ca = KnownHost::parse("@cert-authority *.example.com ssh-rsa AAAAB3", "d").unwrap();
+        assert_eq!(ca.marker.as_deref(), Some("@cert-authority"));
+        assert_eq!(ca.line(), "@cert-authority *.example.com ssh-rsa AAAAB3");
+
+        assert!(KnownHost::parse("# comment", "d").is_none());
+        assert!(KnownHost::parse("example.com ssh-ed25519", "d").is_none());
+
+        // The ID depends on the key line only, so every device derives the same one
+        let other_device = KnownHost::parse("example.com,10.0.0.1  ssh-ed25519 AAAAC3", "e");
+        assert_eq!(other_device.unwrap().base.id, host.base.id);
+    }
+
+    #[test]
+    fn test_render_keeps_comments_and_order() {
+        let content = "# managed by hand\nb.example ssh-ed25519 BBBB\nstale.example ssh-ed25519 SSSS\nbroken-line\n";
+        let live: Vec<KnownHost> = ["a.example ssh-ed25519 AAAA", "b.example ssh-ed25519 BBBB"]
+            .iter()
+            .map(|line| KnownHost::parse(line, "d").unwrap())
+            .collect();
+
+        assert_eq!(
+            render(content, &live),
+            "# managed by hand\nb.example ssh-ed25519 BBBB\nbroken-line\na.example ssh-ed25519 AAAA\n"
+        );
+        assert_eq!(render("", &[]), "");
+    }
+
+    #[test]
+    fn test_render_never_writes_synced_markers() {
+        // A marker line only the file has stays, a synced one is never written
+        let content = "@revoked old.example ssh-ed25519 OOOO\n";
+        let live: Vec<KnownHost> = [
+            "@cert-authority *.example ssh-ed25519 CCCC",
+            "a.example ssh-ed25519 AAAA",
+        ]
+        .iter()
+        .map(|line| KnownHost::parse(line, "d").unwrap())
+        .collect();
+
+        assert_eq!(
+            render(content, &live),
+            "@revoked old.example ssh-ed25519 OOOO\na.example ssh-ed25519 AAAA\n"
+        );
+    }
+}
@@ -4,7 +4,9 @@ use tokio::sync::{Mutex, RwLock};
 
 use crate::database::{
     error::{DatabaseError, DatabaseResult},
-    providers::{MongoDBProvider, MySQLProvider, PostgreSQLProvider},
+    providers::{
+        ChangeLogProvider, MongoDBProvider, MySQLProvider, PostgreSQLProvider, SQLiteTargetProvider,
+    },
     service::DatabaseService,
     traits_sync::SyncTarget,
 };
@@ -24,30 +26,37 @@ impl SyncManager {
         }
     }
 
-    /// Connect to an external database
-    pub async fn connect(&self, config: &ExternalDatabaseConfig) -> DatabaseResult<()> {
+    /// Provider for a database, not connected yet
+    async fn new_provider(
+        &self,
+        config: &ExternalDatabaseConfig,
+    ) -> DatabaseResult<Box<dyn SyncTarget>> {
         let connection_string = self.get_decrypted_connection_string(config).await?;
 
         let provider: Box<dyn SyncTarget> = match config.db_type {
-            DatabaseType::MySQL => {
-                let mut provider = MySQLProvider::new(connection_string);
-                provider.connect().await?;
-                Box::new(provider)
-            }
-            DatabaseType::PostgreSQL => {
-                let mut provider = PostgreSQLProvider::new(connection_string);
-                provider.connect().await?;
-                Box::new(provider)
-            }
+            DatabaseType::MySQL => Box::new(MySQLProvider::new(connection_string)),
+            DatabaseType::PostgreSQL => Box::new(PostgreSQLProvider::new(connection_string)),
             DatabaseType::MongoDB => {
                 let connection_details = self.decrypt_connection_details(config).await?;
                 let database_name = connection_details.database_name.clone();
-                let mut provider = MongoDBProvider::new(connection_string, database_name);
-                provider.connect().await?;
-                Box::new(provider)
+                Box::new(MongoDBProvider::new(connection_string, database_name))
+            }
+            DatabaseType::SQLite => Box::new(SQLiteTargetProvider::new(connection_string)),
+            DatabaseType::LocalFolder | DatabaseType::WebDAV | DatabaseType::S3 => {
+                let connection_details = self.decrypt_connection_details(config).await?;
+                Box::new(ChangeLogProvider::from_connection_details(
+                    &config.db_type,
+                    &connection_details,
+                )?)
             }
         };
+        Ok(provider)
+    }
 
+    /// Connect to an external database
+    pub async fn connect(&self, config: &ExternalDatabaseConfig) -> DatabaseResult<()> {
+        let mut provider = self.new_provider(config).await?;
+        provider.connect().await?;
         provider.test_connection().await?;
 
         let mut connections = self.active_connections.write().await;
@@ -65,6 +74,7 @@ impl SyncManager {
                 conflict_strategy: None,
                 sync_direction: None,
                 selected_database_id: None,
+                synced_entities: None,
             };
 
             if let Err(e) = local_db_guard.update_sync_settings(&update_request).await {
@@ -96,6 +106,7 @@ impl SyncManager {
                 conflict_strategy: None,
                 sync_direction: None,
                 selected_database_id: None,
+                synced_entities: None,
             };
 
             if let Err(e) = local_db_guard.update_sync_settings(&update_request).await {
@@ -135,6 +146,25 @@ impl SyncManager {
         connections.contains_key(database_id)
     }
 
+    /// Connect to a database without creating or migrating its schema, nor keeping the connection
+    /// Used by previews, which must leave the target untouched
+    /// Returns None when nothing was ever synced to the database
+    pub async fn connect_read_only(
+        &self,
+        config: &ExternalDatabaseConfig,
+    ) -> DatabaseResult<Option<Arc<dyn SyncTarget>>> {
+        if self.is_connected(&config.base.id).await {
+            return self.get_provider(&config.base.id).await.map(Some);
+        }
+
+        let mut provider = self.new_provider(config).await?;
+        if !provider.connect_read_only().await? {
+            return Ok(None);
+        }
+        provider.test_connection().await?;
+        Ok(Some(Arc::from(provider)))
+    }
+
     /// Ensure connection exists, reconnect if needed
     pub async fn ensure_connection(&self, config: &ExternalDatabaseConfig) -> DatabaseResult<()> {
         if !self.is_connected(&config.base.id).await {
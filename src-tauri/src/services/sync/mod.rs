@@ -1,53 +1,94 @@
+mod empty;
+mod encryption;
 mod engine;
+#[cfg(test)]
+pub(crate) mod fixture;
+mod known_hosts;
 mod manager;
 mod queue;
 mod resolver;
 mod scheduler;
+mod sealed;
 mod serializer;
+mod watcher;
 
-pub use engine::SyncEngine;
+pub use encryption::SyncEncryption;
+pub use engine::{PullHandler, SyncEngine};
 pub use manager::SyncManager;
 pub use queue::SyncQueue;
 pub use scheduler::SyncScheduler;
 pub use serializer::SyncSerializable;
+pub use watcher::SyncWatcher;
 
+use async_trait::async_trait;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use crate::core::auth_session_manager::SessionLockHandler;
 use crate::database::{error::DatabaseResult, service::DatabaseService};
-use crate::models::sync::{log::SyncLog, SyncDirection};
+use crate::models::sync::{
+    conflict::FieldChoice,
+    external_db::ExternalDatabaseConfig,
+    log::{SyncLog, SyncStatus},
+    ConflictResolutionStrategy, SyncDirection, SyncEncryptionStatus, SyncPlan,
+};
 
 /// High-level sync service that orchestrates all sync operations
 pub struct SyncService {
     database_service: Arc<Mutex<DatabaseService>>,
     sync_manager: Arc<SyncManager>,
     sync_engine: Arc<SyncEngine>,
+    sync_encryption: Arc<SyncEncryption>,
     sync_scheduler: Arc<SyncScheduler>,
+    sync_watcher: Arc<SyncWatcher>,
     sync_queue: Arc<SyncQueue>,
 }
 
 impl SyncService {
     pub fn new(database_service: Arc<Mutex<DatabaseService>>) -> Self {
         let sync_manager = Arc::new(SyncManager::new(database_service.clone()));
+        let sync_encryption = Arc::new(SyncEncryption::new(
+            database_service.clone(),
+            sync_manager.clone(),
+        ));
         let sync_engine = Arc::new(SyncEngine::new(
             database_service.clone(),
             sync_manager.clone(),
+            sync_encryption.clone(),
         ));
         let sync_scheduler = Arc::new(SyncScheduler::new(
             database_service.clone(),
             sync_engine.clone(),
         ));
         let sync_queue = Arc::new(SyncQueue::new(1)); // Max 1 concurrent sync
+        let sync_watcher = Arc::new(SyncWatcher::new(
+            sync_manager.clone(),
+            sync_engine.clone(),
+            sync_queue.clone(),
+        ));
 
         Self {
             database_service,
             sync_manager,
             sync_engine,
+            sync_encryption,
             sync_scheduler,
+            sync_watcher,
             sync_queue,
         }
     }
 
+    /// Register a handler to run before remote changes are applied locally
+    pub fn register_pull_handler(&self, handler: Arc<dyn PullHandler>) {
+        self.sync_engine.register_pull_handler(handler);
+    }
+
+    /// Set app handle for emitting events
+    pub async fn set_app_handle(&self, app_handle: tauri::AppHandle) {
+        self.sync_watcher.set_app_handle(app_handle).await;
+    }
+
     /// Initialize sync service (start scheduler, load enabled databases)
     pub async fn initialize(&self) -> DatabaseResult<()> {
         let is_unlocked = {
@@ -90,6 +131,7 @@ impl SyncService {
                     self.sync_scheduler
                         .enable_database(config.base.id.clone())
                         .await?;
+                    self.watch_remote_changes(&config).await;
                 }
             }
         }
@@ -122,6 +164,7 @@ impl SyncService {
     #[allow(dead_code)]
     pub async fn shutdown(&self) -> DatabaseResult<()> {
         self.sync_scheduler.stop().await?;
+        self.sync_watcher.unwatch_all().await;
 
         self.sync_manager.disconnect_all().await?;
 
@@ -152,9 +195,31 @@ impl SyncService {
 
     /// Disconnect from an external database
     pub async fn disconnect(&self, database_id: &str) -> DatabaseResult<()> {
+        self.sync_watcher.unwatch(database_id).await;
         self.sync_manager.disconnect(database_id).await
     }
 
+    /// Sync a database as soon as its target announces remote changes, where it can
+    /// Failures are only logged, since the scheduler keeps polling the database either way
+    async fn watch_remote_changes(&self, config: &ExternalDatabaseConfig) {
+        let watching = match self.sync_manager.ensure_connection(config).await {
+            Ok(()) => self.sync_watcher.watch(config).await,
+            Err(e) => Err(e),
+        };
+
+        match watching {
+            Ok(true) => eprintln!("[INFO] Syncing {} on remote changes", config.name),
+            Ok(false) => eprintln!(
+                "[INFO] {} cannot announce remote changes, syncing on schedule",
+                config.name
+            ),
+            Err(e) => eprintln!(
+                "[WARN] Failed to watch {} for remote changes: {}",
+                config.name, e
+            ),
+        }
+    }
+
     /// Check if database is connected
     #[allow(dead_code)]
     pub async fn is_connected(&self, database_id: &str) -> bool {
@@ -209,6 +274,127 @@ impl SyncService {
         // _guard drops here, releasing the sync slot
     }
 
+    /// Preview what a bidirectional sync would change, without changing either side
+    pub async fn preview_sync(&self, database_id: &str) -> DatabaseResult<SyncPlan> {
+        let _guard = self.acquire_sync_slot(database_id).await?;
+        let config = self.find_config(database_id).await?;
+
+        let plan = self.sync_engine.preview(&config).await?;
+        eprintln!(
+            "[INFO] Sync preview for {}: {} pending changes",
+            config.name,
+            plan.change_count()
+        );
+        Ok(plan)
+    }
+
+    /// Settle a conflict saved for manual resolution, optionally choosing a side per field
+    pub async fn resolve_conflict(
+        &self,
+        id: &str,
+        strategy: ConflictResolutionStrategy,
+        choices: &HashMap<String, FieldChoice>,
+    ) -> DatabaseResult<()> {
+        self.sync_engine
+            .resolve_conflict(id, strategy, choices)
+            .await
+    }
+
+    /// Get the end-to-end encryption state of a database
+    pub async fn get_encryption_status(
+        &self,
+        database_id: &str,
+    ) -> DatabaseResult<SyncEncryptionStatus> {
+        let config = self.find_config(database_id).await?;
+        self.sync_encryption.status(&config).await
+    }
+
+    /// Turn on end-to-end encryption, migrating the plaintext remote data to sealed records
+    pub async fn enable_encryption(
+        &self,
+        database_id: &str,
+        password: &str,
+    ) -> DatabaseResult<SyncEncryptionStatus> {
+        let _guard = self.acquire_sync_slot(database_id).await?;
+        let config = self.find_config(database_id).await?;
+
+        self.sync_before_migration(&config).await?;
+        self.sync_encryption.enable(&config, password).await?;
+        self.sync_encryption.status(&config).await
+    }
+
+    /// Unlock end-to-end encryption on this device with the master password
+    pub async fn unlock_encryption(
+        &self,
+        database_id: &str,
+        password: &str,
+    ) -> DatabaseResult<SyncEncryptionStatus> {
+        let config = self.find_config(database_id).await?;
+        self.sync_encryption.unlock(&config, password).await
+    }
+
+    /// Seal all sync data with a new key
+    pub async fn rotate_encryption_key(
+        &self,
+        database_id: &str,
+        password: &str,
+    ) -> DatabaseResult<SyncEncryptionStatus> {
+        let _guard = self.acquire_sync_slot(database_id).await?;
+        let config = self.find_config(database_id).await?;
+
+        self.sync_before_migration(&config).await?;
+        self.sync_encryption.rotate_key(&config, password).await
+    }
+
+    /// Turn off end-to-end encryption, migrating the sealed remote data back to plaintext
+    pub async fn disable_encryption(
+        &self,
+        database_id: &str,
+        password: &str,
+    ) -> DatabaseResult<()> {
+        let _guard = self.acquire_sync_slot(database_id).await?;
+        let config = self.find_config(database_id).await?;
+
+        self.sync_before_migration(&config).await?;
+        self.sync_encryption.disable(&config, password).await
+    }
+
+    /// Merge remote changes before rewriting the remote data, so none are lost
+    async fn sync_before_migration(&self, config: &ExternalDatabaseConfig) -> DatabaseResult<()> {
+        let log = self.sync_engine.sync(config).await?;
+        if matches!(log.status, SyncStatus::Failed) {
+            return Err(crate::database::error::DatabaseError::SyncError(
+                log.error_message
+                    .unwrap_or_else(|| "Sync failed".to_string()),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn acquire_sync_slot(&self, database_id: &str) -> DatabaseResult<queue::SyncGuard> {
+        self.sync_queue.acquire(database_id).await.ok_or_else(|| {
+            crate::database::error::DatabaseError::SyncError(
+                "Sync already in progress for this database".to_string(),
+            )
+        })
+    }
+
+    async fn find_config(&self, database_id: &str) -> DatabaseResult<ExternalDatabaseConfig> {
+        let db_service = self.database_service.lock().await;
+        let local_db = db_service.get_local_database();
+        let local_db_guard = local_db.read().await;
+
+        local_db_guard
+            .find_external_database_by_id(database_id)
+            .await?
+            .ok_or_else(|| {
+                crate::database::error::DatabaseError::NotFound(format!(
+                    "External database not found: {}",
+                    database_id
+                ))
+            })
+    }
+
     /// Get sync status for a database
     pub async fn get_status(&self, database_id: &str) -> DatabaseResult<SyncServiceStatus> {
         let is_connected = self.sync_manager.is_connected(database_id).await;
@@ -229,10 +415,13 @@ impl SyncService {
             .await
             .contains(&database_id.to_string());
 
+        let realtime_enabled = self.sync_watcher.is_watching(database_id).await;
+
         Ok(SyncServiceStatus {
             is_connected,
             last_sync: last_sync_log,
             scheduler_enabled,
+            realtime_enabled,
         })
     }
 
@@ -266,6 +455,7 @@ impl SyncService {
             conflict_strategy: None,
             sync_direction: None,
             selected_database_id: None,
+            synced_entities: None,
         };
 
         local_db
@@ -273,10 +463,14 @@ impl SyncService {
             .await
             .update_sync_settings(&update_request)
             .await?;
+        drop(db_service); // Connecting locks the database service again
 
         self.sync_scheduler
             .enable_database(config.base.id.clone())
-            .await
+            .await?;
+
+        self.watch_remote_changes(&config).await;
+        Ok(())
     }
 
     /// Disable auto-sync for a database
@@ -309,6 +503,7 @@ impl SyncService {
             conflict_strategy: None,
             sync_direction: None,
             selected_database_id: None,
+            synced_entities: None,
         };
 
         local_db
@@ -319,6 +514,7 @@ impl SyncService {
 
         let _ = config; // Suppress unused warning
 
+        self.sync_watcher.unwatch(database_id).await;
         self.sync_scheduler.disable_database(database_id).await
     }
 
@@ -335,6 +531,16 @@ impl SyncService {
     }
 }
 
+#[async_trait]
+impl SessionLockHandler for SyncService {
+    /// Connections and sealed targets hold unwrapped keys; the next sync after unlocking
+    /// connects again
+    async fn on_session_locked(&self) {
+        let _ = self.sync_manager.disconnect_all().await;
+        self.sync_encryption.wipe_keys();
+    }
+}
+
 /// Sync service status
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -342,6 +548,8 @@ pub struct SyncServiceStatus {
     pub is_connected: bool,
     pub last_sync: Option<SyncLog>,
     pub scheduler_enabled: bool,
+    /// Synced as soon as the target announces remote changes
+    pub realtime_enabled: bool,
 }
 
 /// Service-wide statistics
@@ -1,9 +1,27 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::cmp::Ordering;
+use std::collections::HashMap;
 
-use crate::database::error::DatabaseResult;
-use crate::models::sync::conflict::ConflictResolutionStrategy;
+use crate::database::error::{DatabaseError, DatabaseResult};
+use crate::models::sync::conflict::{
+    ConflictResolution as StoredConflict, ConflictResolutionStrategy, FieldChoice, FieldConflict,
+};
+
+/// Sync metadata fields that are never merged
+const METADATA_FIELDS: &[&str] = &[
+    "id",
+    "version",
+    "createdAt",
+    "updatedAt",
+    "deviceId",
+    "syncStatus",
+    "created_at",
+    "updated_at",
+    "device_id",
+    "sync_status",
+];
 
 /// Represents a conflict between local and remote data
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +45,18 @@ pub enum ConflictResolution<T> {
     RequiresManual(DataConflict<T>),
 }
 
+/// Outcome of a field-level three-way merge
+#[derive(Debug, Clone)]
+pub struct ThreeWayMerge {
+    /// Local record with every non-overlapping remote edit applied
+    /// Conflicting fields keep the local value
+    pub merged: Value,
+    /// Fields both sides changed to different values
+    pub conflicts: Vec<FieldConflict>,
+    pub local_changed: bool,
+    pub remote_changed: bool,
+}
+
 /// Conflict resolver with multiple strategies
 pub struct ConflictResolver;
 
@@ -145,6 +175,190 @@ impl ConflictResolver {
 
         conflicts
     }
+
+    /// Check whether a sync record is a deletion tombstone
+    pub fn is_tombstone(record: &Value) -> bool {
+        ["deletedAt", "deleted_at"]
+            .iter()
+            .any(|key| record.get(*key).is_some_and(|v| !v.is_null()))
+    }
+
+    /// Check whether one side deleted a record that the other side edited
+    /// Both sides must have changed since the last sync for this to be a conflict
+    pub fn is_delete_conflict(
+        local: &Value,
+        remote: &Value,
+        last_sync_at: Option<DateTime<Utc>>,
+    ) -> bool {
+        if Self::is_tombstone(local) == Self::is_tombstone(remote) {
+            return false;
+        }
+
+        match (
+            Self::record_updated_at(local),
+            Self::record_updated_at(remote),
+        ) {
+            (Some(local_updated), Some(remote_updated)) => {
+                Self::has_conflict(local_updated, remote_updated, last_sync_at)
+            }
+            _ => false,
+        }
+    }
+
+    /// Check whether one side deleted a record that the other side edited,
+    /// judging changes by version against the last-synced base instead of by clock
+    pub fn is_delete_conflict_since_base(
+        local: &Value,
+        remote: &Value,
+        base: Option<&Value>,
+    ) -> bool {
+        if Self::is_tombstone(local) == Self::is_tombstone(remote) {
+            return false;
+        }
+
+        let version = |record: &Value| record.get("version").and_then(|v| v.as_u64());
+        match base.map(version) {
+            Some(base_version) => version(local) != base_version && version(remote) != base_version,
+            None => true,
+        }
+    }
+
+    /// Merge local and remote edits of a record against its last-synced base
+    /// Fields missing on one side are treated as unchanged on that side
+    pub fn three_way_merge(base: &Value, local: &Value, remote: &Value) -> ThreeWayMerge {
+        let mut merged = local.clone();
+        let mut conflicts = Vec::new();
+        let mut local_changed = false;
+        let mut remote_changed = false;
+
+        let empty = serde_json::Map::new();
+        let base_obj = base.as_object().unwrap_or(&empty);
+        let local_obj = local.as_object().unwrap_or(&empty);
+        let remote_obj = remote.as_object().unwrap_or(&empty);
+
+        let mut fields: Vec<&String> = base_obj.keys().chain(local_obj.keys()).collect();
+        fields.extend(remote_obj.keys().filter(|key| local_obj.contains_key(*key)));
+        fields.sort();
+        fields.dedup();
+
+        for field in fields {
+            if METADATA_FIELDS.contains(&field.as_str()) {
+                continue;
+            }
+
+            let base_value = base_obj.get(field);
+            let local_value = local_obj.get(field).or(base_value);
+            let remote_value = remote_obj.get(field).or(base_value);
+
+            let local_edit = local_value != base_value;
+            let remote_edit = remote_value != base_value;
+            local_changed |= local_edit;
+            remote_changed |= remote_edit;
+
+            if !remote_edit || local_value == remote_value {
+                continue;
+            }
+
+            let remote_value = remote_value.cloned().unwrap_or(Value::Null);
+            if local_edit {
+                conflicts.push(FieldConflict {
+                    field: field.clone(),
+                    base: base_value.cloned(),
+                    local: local_value.cloned().unwrap_or(Value::Null),
+                    remote: remote_value,
+                });
+            } else if let Some(obj) = merged.as_object_mut() {
+                obj.insert(field.clone(), remote_value);
+            }
+        }
+
+        ThreeWayMerge {
+            merged,
+            conflicts,
+            local_changed,
+            remote_changed,
+        }
+    }
+
+    /// Record a conflict saved for manual resolution settles on
+    /// Field conflicts keep the merged record and take each conflicting field from the side
+    /// the strategy picks, or from `choices` under manual resolution; whole-record conflicts
+    /// keep one side. The result gets a version above both sides so every device accepts it.
+    pub fn resolve_stored(
+        conflict: &StoredConflict,
+        strategy: ConflictResolutionStrategy,
+        choices: &HashMap<String, FieldChoice>,
+    ) -> DatabaseResult<Value> {
+        let local_at = Self::record_updated_at(&conflict.local_data);
+        let remote_at = Self::record_updated_at(&conflict.remote_data);
+        let remote_wins = match strategy {
+            ConflictResolutionStrategy::LocalWins => Some(false),
+            ConflictResolutionStrategy::RemoteWins => Some(true),
+            ConflictResolutionStrategy::LastWriteWins => Some(local_at < remote_at),
+            ConflictResolutionStrategy::FirstWriteWins => Some(local_at > remote_at),
+            ConflictResolutionStrategy::Manual => None,
+        };
+
+        let mut resolved = match (&conflict.merged_data, remote_wins) {
+            (Some(merged), _) => {
+                let mut resolved = merged.clone();
+                for field in &conflict.field_conflicts {
+                    let take_remote = remote_wins
+                        .or_else(|| {
+                            choices
+                                .get(&field.field)
+                                .map(|choice| *choice == FieldChoice::Remote)
+                        })
+                        .ok_or_else(|| {
+                            DatabaseError::ValidationError(format!(
+                                "No version chosen for field {}",
+                                field.field
+                            ))
+                        })?;
+                    let value = if take_remote {
+                        &field.remote
+                    } else {
+                        &field.local
+                    };
+                    if let Some(obj) = resolved.as_object_mut() {
+                        obj.insert(field.field.clone(), value.clone());
+                    }
+                }
+                resolved
+            }
+            (None, Some(true)) => conflict.remote_data.clone(),
+            (None, Some(false)) => conflict.local_data.clone(),
+            (None, None) => {
+                return Err(DatabaseError::ValidationError(
+                    "Choose the local or the remote version of the record".to_string(),
+                ))
+            }
+        };
+
+        let version = [&conflict.local_data, &conflict.remote_data]
+            .iter()
+            .filter_map(|record| record.get("version")?.as_u64())
+            .max()
+            .unwrap_or(0);
+        if let Some(obj) = resolved.as_object_mut() {
+            obj.insert("version".to_string(), Value::from(version + 1));
+            obj.insert(
+                "updatedAt".to_string(),
+                Value::from(Utc::now().to_rfc3339()),
+            );
+        }
+
+        Ok(resolved)
+    }
+
+    /// Read the last modification time of a sync record
+    pub fn record_updated_at(record: &Value) -> Option<DateTime<Utc>> {
+        ["updatedAt", "updated_at"]
+            .iter()
+            .filter_map(|key| record.get(*key)?.as_str())
+            .find_map(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(|dt| dt.with_timezone(&Utc))
+    }
 }
 
 impl Default for ConflictResolver {
@@ -216,4 +430,163 @@ mod tests {
 
         assert!(ConflictResolver::has_conflict(now, before_sync, None));
     }
+
+    #[test]
+    fn test_three_way_merge() {
+        let base = serde_json::json!({ "id": "1", "version": 3, "name": "web", "port": 22, "color": null });
+        let local = serde_json::json!({ "id": "1", "version": 4, "name": "web-prod", "port": 22, "color": null });
+        let remote = serde_json::json!({ "id": "1", "version": 4, "name": "web", "port": 2222, "color": null });
+
+        let merge = ConflictResolver::three_way_merge(&base, &local, &remote);
+        assert!(merge.local_changed);
+        assert!(merge.remote_changed);
+        assert!(merge.conflicts.is_empty());
+        assert_eq!(merge.merged["name"], "web-prod");
+        assert_eq!(merge.merged["port"], 2222);
+        assert_eq!(merge.merged["version"], 4);
+
+        let remote =
+            serde_json::json!({ "id": "1", "name": "web-staging", "port": 22, "color": "red" });
+        let merge = ConflictResolver::three_way_merge(&base, &local, &remote);
+        assert_eq!(merge.merged["color"], "red");
+        assert_eq!(merge.merged["name"], "web-prod");
+        assert_eq!(
+            merge.conflicts,
+            vec![FieldConflict {
+                field: "name".to_string(),
+                base: Some(serde_json::json!("web")),
+                local: serde_json::json!("web-prod"),
+                remote: serde_json::json!("web-staging"),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_three_way_merge_same_edit_and_missing_fields() {
+        let base = serde_json::json!({ "id": "1", "name": "web", "port": 22 });
+        let local = serde_json::json!({ "id": "1", "name": "api", "port": 22 });
+        // Same edit on both sides, and a remote record without the port field
+        let remote = serde_json::json!({ "id": "1", "name": "api" });
+
+        let merge = ConflictResolver::three_way_merge(&base, &local, &remote);
+        assert!(merge.conflicts.is_empty());
+        assert!(merge.local_changed && merge.remote_changed);
+        assert_eq!(merge.merged["port"], 22);
+        assert_eq!(merge.merged["name"], "api");
+    }
+
+    #[test]
+    fn test_resolve_stored_field_by_field() {
+        let base = serde_json::json!({ "id": "1", "version": 3, "name": "web", "port": 22, "color": null });
+        let local = serde_json::json!({ "id": "1", "version": 4, "name": "web-prod", "port": 2200, "color": null });
+        let remote = serde_json::json!({ "id": "1", "version": 5, "name": "web-staging", "port": 2222, "color": "red" });
+        let merge = ConflictResolver::three_way_merge(&base, &local, &remote);
+        let conflict = StoredConflict {
+            id: "c".to_string(),
+            entity_type: "ssh_profiles".to_string(),
+            entity_id: "1".to_string(),
+            local_data: local,
+            remote_data: remote,
+            base_data: Some(base),
+            merged_data: Some(merge.merged),
+            field_conflicts: merge.conflicts,
+            resolution_strategy: None,
+            resolved_at: None,
+            created_at: Utc::now(),
+        };
+
+        let choices = HashMap::from([
+            ("name".to_string(), FieldChoice::Remote),
+            ("port".to_string(), FieldChoice::Local),
+        ]);
+        let resolved = ConflictResolver::resolve_stored(
+            &conflict,
+            ConflictResolutionStrategy::Manual,
+            &choices,
+        )
+        .unwrap();
+        assert_eq!(resolved["name"], "web-staging");
+        assert_eq!(resolved["port"], 2200);
+        assert_eq!(resolved["color"], "red");
+        assert_eq!(resolved["version"], 6);
+
+        // Every conflicting field needs a side
+        let partial = HashMap::from([("name".to_string(), FieldChoice::Local)]);
+        assert!(ConflictResolver::resolve_stored(
+            &conflict,
+            ConflictResolutionStrategy::Manual,
+            &partial
+        )
+        .is_err());
+
+        let resolved = ConflictResolver::resolve_stored(
+            &conflict,
+            ConflictResolutionStrategy::RemoteWins,
+            &HashMap::new(),
+        )
+        .unwrap();
+        assert_eq!(resolved["name"], "web-staging");
+        assert_eq!(resolved["port"], 2222);
+    }
+
+    #[test]
+    fn test_delete_conflict() {
+        let last_sync = Utc::now() - chrono::Duration::hours(1);
+        let edited_at = (last_sync + chrono::Duration::minutes(10)).to_rfc3339();
+        let deleted_at = (last_sync + chrono::Duration::minutes(20)).to_rfc3339();
+        let stale_at = (last_sync - chrono::Duration::minutes(10)).to_rfc3339();
+
+        let edited = serde_json::json!({ "id": "1", "updatedAt": edited_at, "deletedAt": null });
+        let deleted =
+            serde_json::json!({ "id": "1", "updatedAt": deleted_at, "deletedAt": deleted_at });
+        let untouched = serde_json::json!({ "id": "1", "updatedAt": stale_at });
+
+        assert!(ConflictResolver::is_tombstone(&deleted));
+        assert!(!ConflictResolver::is_tombstone(&edited));
+        assert!(ConflictResolver::is_delete_conflict(
+            &deleted,
+            &edited,
+            Some(last_sync)
+        ));
+        assert!(ConflictResolver::is_delete_conflict(
+            &edited,
+            &deleted,
+            Some(last_sync)
+        ));
+        assert!(!ConflictResolver::is_delete_conflict(
+            &untouched,
+            &deleted,
+            Some(last_sync)
+        ));
+        assert!(!ConflictResolver::is_delete_conflict(
+            &deleted,
+            &deleted,
+            Some(last_sync)
+        ));
+    }
+
+    #[test]
+    fn test_delete_conflict_since_base() {
+        let base = serde_json::json!({ "id": "1", "version": 2, "deletedAt": null });
+        let edited = serde_json::json!({ "id": "1", "version": 3, "deletedAt": null });
+        let deleted =
+            serde_json::json!({ "id": "1", "version": 3, "deletedAt": "2020-01-01T00:00:00Z" });
+
+        assert!(ConflictResolver::is_delete_conflict_since_base(
+            &edited,
+            &deleted,
+            Some(&base)
+        ));
+        assert!(!ConflictResolver::is_delete_conflict_since_base(
+            &base,
+            &deleted,
+            Some(&base)
+        ));
+        assert!(ConflictResolver::is_delete_conflict_since_base(
+            &deleted, &edited, None
+        ));
+        assert!(!ConflictResolver::is_delete_conflict_since_base(
+            &deleted, &deleted, None
+        ));
+    }
 }
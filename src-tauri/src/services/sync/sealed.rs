@@ -0,0 +1,166 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use crate::database::{
+    encryption::{sync_keys::sealed_record_id, SyncKeyring},
+    error::DatabaseResult,
+    traits_sync::{
+        sealed_table_name, ChangeNotifications, RecordChanges, SyncTarget, SEALED_RECORDS_TABLE,
+    },
+};
+
+/// Marks cursors of the sealed records table, so cursors kept from before end-to-end
+/// encryption was turned on or off start over with a full pull
+const CURSOR_PREFIX: &str = "sealed:";
+
+/// Keyring shared with `SyncEncryption`, which empties it when the session locks
+pub type SharedKeyring = Arc<RwLock<SyncKeyring>>;
+
+/// Sync target wrapper for end-to-end encrypted sync
+/// Every record is sealed before it leaves the device and stored in a single
+/// table that only exposes ids, versions and timestamps to the server
+pub struct SealedSyncTarget {
+    inner: Arc<dyn SyncTarget>,
+    keyring: SharedKeyring,
+}
+
+impl SealedSyncTarget {
+    pub fn new(inner: Arc<dyn SyncTarget>, keyring: SharedKeyring) -> Self {
+        Self { inner, keyring }
+    }
+
+    fn keyring(&self) -> std::sync::RwLockReadGuard<'_, SyncKeyring> {
+        self.keyring.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn sealed_ids(table: &str, ids: Vec<String>) -> Vec<String> {
+        ids.iter().map(|id| sealed_record_id(table, id)).collect()
+    }
+
+    /// Open the envelopes of the given table, skipping those of other tables
+    fn open_envelopes(&self, table: &str, envelopes: &[Value]) -> DatabaseResult<Vec<Value>> {
+        let keyring = self.keyring();
+        envelopes
+            .iter()
+            .filter(|envelope| sealed_table_name(envelope) == Some(table))
+            .map(|envelope| Ok(keyring.open(table, envelope)?))
+            .collect()
+    }
+
+    /// Ids of the given table among sealed record ids
+    fn record_ids(table: &str, sealed_ids: Vec<String>) -> Vec<String> {
+        let prefix = sealed_record_id(table, "");
+        sealed_ids
+            .into_iter()
+            .filter_map(|id| id.strip_prefix(&prefix).map(|id| id.to_string()))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl SyncTarget for SealedSyncTarget {
+    /// The wrapped target is connected by the sync manager
+    async fn connect(&mut self) -> DatabaseResult<()> {
+        Ok(())
+    }
+
+    async fn test_connection(&self) -> DatabaseResult<()> {
+        self.inner.test_connection().await
+    }
+
+    async fn push_records(&self, table: &str, records: Vec<Value>) -> DatabaseResult<usize> {
+        let envelopes = {
+            let keyring = self.keyring();
+            records
+                .iter()
+                .map(|record| keyring.seal(table, record))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+
+        self.inner
+            .push_records(SEALED_RECORDS_TABLE, envelopes)
+            .await
+    }
+
+    async fn pull_records(
+        &self,
+        table: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> DatabaseResult<Vec<Value>> {
+        let envelopes = self.inner.pull_sealed_records(table, since).await?;
+        self.open_envelopes(table, &envelopes)
+    }
+
+    async fn pull_changes(
+        &self,
+        table: &str,
+        cursor: Option<&str>,
+    ) -> DatabaseResult<RecordChanges> {
+        let cursor = cursor.and_then(|cursor| cursor.strip_prefix(CURSOR_PREFIX));
+        let changes = self.inner.pull_sealed_changes(table, cursor).await?;
+
+        Ok(RecordChanges {
+            records: self.open_envelopes(table, &changes.records)?,
+            cursor: format!("{}{}", CURSOR_PREFIX, changes.cursor),
+        })
+    }
+
+    async fn watch_changes(&self) -> DatabaseResult<Option<ChangeNotifications>> {
+        self.inner.watch_changes().await
+    }
+
+    async fn get_record_versions(
+        &self,
+        table: &str,
+        ids: Vec<String>,
+    ) -> DatabaseResult<HashMap<String, u64>> {
+        let prefix = sealed_record_id(table, "");
+        let versions = self
+            .inner
+            .get_record_versions(SEALED_RECORDS_TABLE, Self::sealed_ids(table, ids))
+            .await?;
+
+        Ok(versions
+            .into_iter()
+            .filter_map(|(id, version)| Some((id.strip_prefix(&prefix)?.to_string(), version)))
+            .collect())
+    }
+
+    async fn register_device(&self, device_id: &str) -> DatabaseResult<()> {
+        self.inner.register_device(device_id).await
+    }
+
+    async fn acknowledge_tombstones(
+        &self,
+        table: &str,
+        device_id: &str,
+        ids: Vec<String>,
+    ) -> DatabaseResult<()> {
+        self.inner
+            .acknowledge_tombstones(
+                SEALED_RECORDS_TABLE,
+                device_id,
+                Self::sealed_ids(table, ids),
+            )
+            .await
+    }
+
+    /// Purging covers every sealed table at once; tombstones of other tables purged here
+    /// are dropped locally once they are found acknowledged and missing from the remote
+    async fn purge_acknowledged_tombstones(&self, table: &str) -> DatabaseResult<Vec<String>> {
+        let purged = self
+            .inner
+            .purge_acknowledged_tombstones(SEALED_RECORDS_TABLE)
+            .await?;
+
+        Ok(Self::record_ids(table, purged))
+    }
+
+    /// Sealed records of all tables share one remote table, so only the whole table can be cleared
+    async fn clear_records(&self, _table: &str) -> DatabaseResult<usize> {
+        self.inner.clear_records(SEALED_RECORDS_TABLE).await
+    }
+}
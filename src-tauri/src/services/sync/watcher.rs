@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::Emitter;
+use tokio::sync::{Mutex, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration};
+
+use crate::database::{
+    error::DatabaseResult,
+    traits_sync::{ChangeNotification, ChangeNotifications},
+};
+use crate::models::sync::external_db::ExternalDatabaseConfig;
+use crate::services::sync::{engine::SyncEngine, manager::SyncManager, queue::SyncQueue};
+
+/// Notifications arriving within this window are handled by a single sync
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// How long a notified sync waits before retrying while another sync is running
+const BUSY_RETRY: Duration = Duration::from_secs(1);
+
+/// Delay before the first attempt to listen again once a target stops sending notifications
+const RECONNECT_MIN: Duration = Duration::from_secs(1);
+
+/// Longest delay between attempts to listen again
+const RECONNECT_MAX: Duration = Duration::from_secs(300);
+
+/// Delay before a reconnection attempt, doubling with every failed attempt
+fn reconnect_delay(attempt: u32) -> Duration {
+    RECONNECT_MIN
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(RECONNECT_MAX)
+}
+
+/// Whether a burst of notifications announces changes pushed by another device
+/// Notifications that do not name a device may come from any device
+fn has_foreign_changes(burst: &[ChangeNotification], device_id: &str) -> bool {
+    burst
+        .iter()
+        .any(|notification| notification.device_id.as_deref() != Some(device_id))
+}
+
+/// Syncs a database as soon as its sync target announces changes from other devices
+/// Targets that cannot announce changes are only synced by `SyncScheduler`
+pub struct SyncWatcher {
+    sync_manager: Arc<SyncManager>,
+    sync_engine: Arc<SyncEngine>,
+    sync_queue: Arc<SyncQueue>,
+    app_handle: Arc<RwLock<Option<tauri::AppHandle>>>,
+    watches: Arc<Mutex<HashMap<String, JoinHandle<()>>>>,
+}
+
+impl SyncWatcher {
+    pub fn new(
+        sync_manager: Arc<SyncManager>,
+        sync_engine: Arc<SyncEngine>,
+        sync_queue: Arc<SyncQueue>,
+    ) -> Self {
+        Self {
+            sync_manager,
+            sync_engine,
+            sync_queue,
+            app_handle: Arc::new(RwLock::new(None)),
+            watches: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Set app handle for emitting events
+    pub async fn set_app_handle(&self, app_handle: tauri::AppHandle) {
+        let mut handle = self.app_handle.write().await;
+        *handle = Some(app_handle);
+    }
+
+    /// Start watching a connected database for remote changes
+    /// Returns false if its sync target can only be polled
+    pub async fn watch(&self, config: &ExternalDatabaseConfig) -> DatabaseResult<bool> {
+        let mut watches = self.watches.lock().await;
+        if watches
+            .get(&config.base.id)
+            .is_some_and(|watch| !watch.is_finished())
+        {
+            return Ok(true);
+        }
+
+        let Some(notifications) = self.listen(config).await? else {
+            watches.remove(&config.base.id);
+            return Ok(false);
+        };
+
+        let watcher = self.clone_for_task();
+        let task_config = config.clone();
+        let watch = tokio::spawn(async move {
+            watcher.run(task_config, notifications).await;
+        });
+        watches.insert(config.base.id.clone(), watch);
+
+        Ok(true)
+    }
+
+    /// Stop watching a database
+    pub async fn unwatch(&self, database_id: &str) {
+        if let Some(watch) = self.watches.lock().await.remove(database_id) {
+            watch.abort();
+        }
+    }
+
+    /// Stop watching all databases
+    pub async fn unwatch_all(&self) {
+        for (_, watch) in self.watches.lock().await.drain() {
+            watch.abort();
+        }
+    }
+
+    /// Check if a database is synced as soon as remote changes are announced
+    pub async fn is_watching(&self, database_id: &str) -> bool {
+        self.watches
+            .lock()
+            .await
+            .get(database_id)
+            .is_some_and(|watch| !watch.is_finished())
+    }
+
+    /// Sync the database for each burst of notifications from other devices
+    /// Listens again with backoff whenever the target stops sending them
+    async fn run(&self, config: ExternalDatabaseConfig, mut notifications: ChangeNotifications) {
+        // An empty id matches no device, so every change is synced
+        let device_id = self
+            .sync_engine
+            .current_device_id()
+            .await
+            .unwrap_or_default();
+
+        loop {
+            self.follow(&config, &device_id, &mut notifications).await;
+
+            let mut attempt = 0;
+            notifications = loop {
+                let delay = reconnect_delay(attempt);
+                eprintln!(
+                    "[WARN] Lost change notifications from {}, listening again in {:?}",
+                    config.name, delay
+                );
+                sleep(delay).await;
+
+                match self.listen(&config).await {
+                    Ok(Some(notifications)) => break notifications,
+                    Ok(None) => {
+                        eprintln!(
+                            "[INFO] Stopped watching {} for remote changes, falling back to scheduled syncs",
+                            config.name
+                        );
+                        return;
+                    }
+                    Err(e) => {
+                        eprintln!(
+                            "[WARN] Failed to listen to {} for changes: {}",
+                            config.name, e
+                        );
+                        attempt += 1;
+                    }
+                }
+            };
+
+            // Changes pushed while the listener was down were never announced
+            self.sync_now(&config).await;
+        }
+    }
+
+    /// Handle notifications until the target stops sending them
+    async fn follow(
+        &self,
+        config: &ExternalDatabaseConfig,
+        device_id: &str,
+        notifications: &mut ChangeNotifications,
+    ) {
+        while let Some(first) = notifications.recv().await {
+            sleep(DEBOUNCE).await;
+            let mut burst = vec![first];
+            while let Ok(notification) = notifications.try_recv() {
+                burst.push(notification);
+            }
+
+            // Our own pushes need no sync
+            if !has_foreign_changes(&burst, device_id) {
+                continue;
+            }
+
+            eprintln!(
+                "[INFO] Remote changes to {} on {}, syncing",
+                burst[0].table, config.name
+            );
+            self.sync_now(config).await;
+        }
+    }
+
+    async fn listen(
+        &self,
+        config: &ExternalDatabaseConfig,
+    ) -> DatabaseResult<Option<ChangeNotifications>> {
+        let provider = self.sync_manager.get_provider(&config.base.id).await?;
+        provider.watch_changes().await
+    }
+
+    async fn sync_now(&self, config: &ExternalDatabaseConfig) {
+        // Wait for any running sync instead of dropping the notification
+        let _guard = loop {
+            match self.sync_queue.acquire(&config.base.id).await {
+                Some(guard) => break guard,
+                None => sleep(BUSY_RETRY).await,
+            }
+        };
+
+        match self.sync_engine.sync(config).await {
+            Ok(log) => {
+                if let Some(ref app_handle) = *self.app_handle.read().await {
+                    let _ = app_handle.emit("sync_log_added", &log);
+                }
+            }
+            Err(e) => eprintln!(
+                "[WARN] Sync after remote changes failed for {}: {}",
+                config.name, e
+            ),
+        }
+    }
+
+    /// Clone for spawning background task
+    fn clone_for_task(&self) -> Self {
+        Self {
+            sync_manager: self.sync_manager.clone(),
+            sync_engine: self.sync_engine.clone(),
+            sync_queue: self.sync_queue.clone(),
+            app_handle: self.app_handle.clone(),
+            watches: self.watches.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification(table: &str, device_id: Option<&str>) -> ChangeNotification {
+        ChangeNotification {
+            table: table.to_string(),
+            device_id: device_id.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_own_notifications_are_ignored() {
+        let own = [
+            notification("ssh_groups", Some("d1")),
+            notification("ssh_profiles", Some("d1")),
+        ];
+        assert!(!has_foreign_changes(&own, "d1"));
+
+        let mixed = [
+            notification("ssh_groups", Some("d1")),
+            notification("ssh_keys", Some("d2")),
+        ];
+        assert!(has_foreign_changes(&mixed, "d1"));
+        assert!(has_foreign_changes(&[notification("ssh_keys", None)], "d1"));
+    }
+
+    #[test]
+    fn test_reconnect_delay_backs_off_up_to_limit() {
+        assert_eq!(reconnect_delay(0), RECONNECT_MIN);
+        assert_eq!(reconnect_delay(1), RECONNECT_MIN * 2);
+        assert_eq!(reconnect_delay(3), RECONNECT_MIN * 8);
+        assert_eq!(reconnect_delay(20), RECONNECT_MAX);
+        assert_eq!(reconnect_delay(u32::MAX), RECONNECT_MAX);
+    }
+}
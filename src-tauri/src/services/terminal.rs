@@ -7,6 +7,7 @@ use crate::models::terminal::{
 };
 use crate::services::buffer_manager::TerminalBufferManager;
 use crate::services::recording::SessionRecorder;
+use crate::services::ssh::hardware_key::is_security_key;
 use crate::services::ssh::SSHKeyService;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -116,12 +117,23 @@ impl TerminalManager {
                         &ssh_profile.auth_data
                     {
                         let key_service = ssh_key_service.lock().await;
-                        Some(
-                            key_service
-                                .resolve_key_for_auth(key_id)
-                                .await
-                                .map_err(|e| AppError::Database(e.to_string()))?,
-                        )
+                        let resolved_key = key_service
+                            .resolve_key_for_auth(key_id)
+                            .await
+                            .map_err(|e| AppError::Database(e.to_string()))?;
+
+                        // russh cannot present security keys, so OpenSSH connects instead
+                        if is_security_key(&resolved_key) {
+                            terminal = TerminalFactory::create_openssh_terminal(
+                                terminal_id.clone(),
+                                config.clone(),
+                                &ssh_profile,
+                                &resolved_key,
+                            )?;
+                            None
+                        } else {
+                            Some(resolved_key)
+                        }
                     } else {
                         None
                     }
@@ -7,6 +7,7 @@ use std::sync::Arc;
 use tokio::net::TcpListener;
 use tokio::sync::{Mutex, RwLock};
 use tokio_util::sync::CancellationToken;
+use zeroize::Zeroize;
 
 use crate::database::{error::DatabaseResult, service::DatabaseService};
 use crate::models::ssh::{
@@ -314,13 +315,17 @@ impl TunnelService {
     /// Run tunnel implementation
     async fn run_tunnel(
         tunnel: SSHTunnel,
-        profile: SSHProfile,
+        mut profile: SSHProfile,
         cancel_token: CancellationToken,
         status: Arc<RwLock<TunnelStatus>>,
         error_message: Arc<RwLock<Option<String>>>,
         sessions: Arc<RwLock<HashMap<String, Arc<Mutex<Handle<SSHClientHandler>>>>>>,
     ) -> Result<()> {
-        let session = match Self::get_or_create_ssh_session(&profile, sessions).await {
+        let session = Self::get_or_create_ssh_session(&profile, sessions).await;
+        // Credentials are only needed to open the session, not for the tunnel's lifetime
+        profile.auth_data.zeroize();
+
+        let session = match session {
             Ok(s) => s,
             Err(e) => {
                 let mut error_msg = error_message.write().await;
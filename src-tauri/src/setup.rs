@@ -28,9 +28,14 @@ pub fn init(app: &mut App) -> std::result::Result<(), Box<dyn std::error::Error>
             Ok(app_state) => {
                 let auth_session_manager = app_state.auth_session_manager.clone();
                 let sftp_transfer_manager = app_state.sftp_transfer_manager.clone();
+                let sync_service = app_state.sync_service.clone();
+                let backup_scheduler = app_state.backup_scheduler.clone();
+                let ssh_config_exporter = app_state.ssh_config_exporter.clone();
 
                 app_handle.manage(app_state);
 
+                sync_service.set_app_handle(app_handle.clone()).await;
+
                 let auth_manager_clone = auth_session_manager.clone();
                 let app_handle_clone = app_handle.clone();
 
@@ -41,6 +46,14 @@ pub fn init(app: &mut App) -> std::result::Result<(), Box<dyn std::error::Error>
                 });
 
                 sftp_transfer_manager.start_queue_processor(app_handle.clone());
+
+                if let Err(e) = backup_scheduler.start().await {
+                    eprintln!("Failed to start backup scheduler: {}", e);
+                }
+
+                if let Err(e) = ssh_config_exporter.start().await {
+                    eprintln!("Failed to start SSH config export sync: {}", e);
+                }
             }
             Err(e) => {
                 eprintln!("Failed to initialize AppState: {}", e);
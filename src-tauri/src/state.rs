@@ -2,10 +2,17 @@ use crate::core::auth_session_manager::AuthSessionManager;
 use crate::database::{DatabaseService, DatabaseServiceConfig};
 use crate::services::{
     auth::AuthService,
+    backup::{BackupScheduler, BackupService},
     history::HistoryManager,
     saved_command::SavedCommandService,
-    sftp::{sync::SyncService as SFTPSyncService, transfer::TransferManager, SFTPService},
-    ssh::{SSHConnectionPool, SSHKeyService, SSHService},
+    sftp::{
+        archive::ArchiveService, sync::SyncService as SFTPSyncService, transfer::TransferManager,
+        SFTPService,
+    },
+    ssh::{
+        CertificateAuthorityService, ConnectionImportService, SSHConfigExporter, SSHConnectionPool,
+        SSHKeyService, SSHService,
+    },
     sync::SyncService,
     terminal::TerminalManager,
     tunnel::TunnelService,
@@ -19,15 +26,21 @@ pub struct AppState {
     pub auth_service: AuthService,
     pub ssh_service: Arc<SSHService>,
     pub ssh_key_service: Arc<Mutex<SSHKeyService>>,
+    pub certificate_authority_service: Arc<CertificateAuthorityService>,
+    pub connection_import_service: Arc<ConnectionImportService>,
+    pub ssh_config_exporter: Arc<SSHConfigExporter>,
     pub ssh_connection_pool: Arc<SSHConnectionPool>,
     pub tunnel_service: TunnelService,
     pub saved_command_service: SavedCommandService,
+    pub backup_service: Arc<BackupService>,
+    pub backup_scheduler: Arc<BackupScheduler>,
     pub sync_service: Arc<SyncService>,
     pub terminal_manager: Arc<TerminalManager>,
     pub auth_session_manager: Arc<Mutex<AuthSessionManager>>,
     pub sftp_service: Arc<SFTPService>,
     pub sftp_transfer_manager: Arc<TransferManager>,
     pub sftp_sync_service: Arc<SFTPSyncService>,
+    pub sftp_archive_service: Arc<ArchiveService>,
     pub history_manager: HistoryManager,
 }
 
@@ -35,6 +48,7 @@ impl AppState {
     /// Create new app state with initialized database service
     pub async fn new() -> Result<Self, String> {
         let config = DatabaseServiceConfig::default();
+        let data_dir = config.data_dir();
         let database_service = DatabaseService::new(config)
             .await
             .map_err(|e| format!("Failed to initialize database service: {}", e))?;
@@ -42,14 +56,33 @@ impl AppState {
         let database_service_arc = Arc::new(Mutex::new(database_service));
 
         let auth_service = AuthService::new(database_service_arc.clone());
-        let ssh_key_service =
-            Arc::new(Mutex::new(SSHKeyService::new(database_service_arc.clone())));
+        let ssh_key_service = SSHKeyService::new(database_service_arc.clone());
+        let ssh_key_cache = ssh_key_service.key_cache();
+        let ssh_key_service = Arc::new(Mutex::new(ssh_key_service));
         let ssh_service = SSHService::new(database_service_arc.clone(), ssh_key_service.clone());
         let ssh_service_arc = Arc::new(ssh_service);
+        let certificate_authority_service = Arc::new(CertificateAuthorityService::new(
+            database_service_arc.clone(),
+        ));
+        let connection_import_service = Arc::new(ConnectionImportService::new(
+            database_service_arc.clone(),
+            ssh_key_service.clone(),
+        ));
+        let ssh_config_exporter = Arc::new(SSHConfigExporter::new(
+            database_service_arc.clone(),
+            data_dir.clone(),
+        ));
         let tunnel_service = TunnelService::new_with_auto_start(database_service_arc.clone()).await;
         let saved_command_service = SavedCommandService::new(database_service_arc.clone());
+        let backup_service = Arc::new(BackupService::new(database_service_arc.clone()));
+        let backup_scheduler = Arc::new(BackupScheduler::new(
+            database_service_arc.clone(),
+            backup_service.clone(),
+            data_dir,
+        ));
 
         let sync_service = Arc::new(SyncService::new(database_service_arc.clone()));
+        sync_service.register_pull_handler(backup_scheduler.clone());
         sync_service
             .initialize()
             .await
@@ -60,9 +93,10 @@ impl AppState {
             ssh_key_service.clone(),
         );
 
-        let auth_session_manager = Arc::new(Mutex::new(AuthSessionManager::new(
-            database_service_arc.clone(),
-        )));
+        let mut auth_session_manager = AuthSessionManager::new(database_service_arc.clone());
+        auth_session_manager.register_lock_handler(ssh_key_cache);
+        auth_session_manager.register_lock_handler(sync_service.clone());
+        let auth_session_manager = Arc::new(Mutex::new(auth_session_manager));
 
         let ssh_connection_pool = Arc::new(SSHConnectionPool::default());
         let sftp_service = Arc::new(SFTPService::new(
@@ -71,6 +105,7 @@ impl AppState {
         ));
         let sftp_transfer_manager = Arc::new(TransferManager::new(sftp_service.clone()));
         let sftp_sync_service = Arc::new(SFTPSyncService::new(sftp_service.clone()));
+        let sftp_archive_service = Arc::new(ArchiveService::new(sftp_service.clone()));
         let terminal_manager_arc = Arc::new(terminal_manager);
         let history_manager =
             HistoryManager::new(terminal_manager_arc.clone(), ssh_service_arc.clone());
@@ -80,15 +115,21 @@ impl AppState {
             auth_service,
             ssh_service: ssh_service_arc,
             ssh_key_service,
+            certificate_authority_service,
+            connection_import_service,
+            ssh_config_exporter,
             ssh_connection_pool,
             tunnel_service,
             saved_command_service,
+            backup_service,
+            backup_scheduler,
             sync_service,
             terminal_manager: terminal_manager_arc,
             auth_session_manager,
             sftp_service,
             sftp_transfer_manager,
             sftp_sync_service,
+            sftp_archive_service,
             history_manager,
         })
     }
@@ -96,31 +137,53 @@ impl AppState {
 
 impl Default for AppState {
     fn default() -> Self {
+        let config = DatabaseServiceConfig::default();
+        let data_dir = config.data_dir();
         let database_service_arc = Arc::new(Mutex::new(
-            DatabaseService::new(DatabaseServiceConfig::default())
+            DatabaseService::new(config)
                 .now_or_never()
                 .unwrap()
                 .unwrap(),
         ));
 
         let auth_service = AuthService::new(database_service_arc.clone());
-        let ssh_key_service =
-            Arc::new(Mutex::new(SSHKeyService::new(database_service_arc.clone())));
+        let ssh_key_service = SSHKeyService::new(database_service_arc.clone());
+        let ssh_key_cache = ssh_key_service.key_cache();
+        let ssh_key_service = Arc::new(Mutex::new(ssh_key_service));
         let ssh_service = SSHService::new(database_service_arc.clone(), ssh_key_service.clone());
         let ssh_service_arc = Arc::new(ssh_service);
+        let certificate_authority_service = Arc::new(CertificateAuthorityService::new(
+            database_service_arc.clone(),
+        ));
+        let connection_import_service = Arc::new(ConnectionImportService::new(
+            database_service_arc.clone(),
+            ssh_key_service.clone(),
+        ));
+        let ssh_config_exporter = Arc::new(SSHConfigExporter::new(
+            database_service_arc.clone(),
+            data_dir.clone(),
+        ));
         let tunnel_service = TunnelService::new(database_service_arc.clone());
         let saved_command_service = SavedCommandService::new(database_service_arc.clone());
+        let backup_service = Arc::new(BackupService::new(database_service_arc.clone()));
+        let backup_scheduler = Arc::new(BackupScheduler::new(
+            database_service_arc.clone(),
+            backup_service.clone(),
+            data_dir,
+        ));
 
         let sync_service = Arc::new(SyncService::new(database_service_arc.clone()));
+        sync_service.register_pull_handler(backup_scheduler.clone());
 
         let terminal_manager = TerminalManager::new_with_ssh_key_service(
             database_service_arc.clone(),
             ssh_key_service.clone(),
         );
 
-        let auth_session_manager = Arc::new(Mutex::new(AuthSessionManager::new(
-            database_service_arc.clone(),
-        )));
+        let mut auth_session_manager = AuthSessionManager::new(database_service_arc.clone());
+        auth_session_manager.register_lock_handler(ssh_key_cache);
+        auth_session_manager.register_lock_handler(sync_service.clone());
+        let auth_session_manager = Arc::new(Mutex::new(auth_session_manager));
 
         let ssh_connection_pool = Arc::new(SSHConnectionPool::default());
         let sftp_service = Arc::new(SFTPService::new(
@@ -129,6 +192,7 @@ impl Default for AppState {
         ));
         let sftp_transfer_manager = Arc::new(TransferManager::new(sftp_service.clone()));
         let sftp_sync_service = Arc::new(SFTPSyncService::new(sftp_service.clone()));
+        let sftp_archive_service = Arc::new(ArchiveService::new(sftp_service.clone()));
         let terminal_manager_arc = Arc::new(terminal_manager);
         let history_manager =
             HistoryManager::new(terminal_manager_arc.clone(), ssh_service_arc.clone());
@@ -138,15 +202,21 @@ impl Default for AppState {
             auth_service,
             ssh_service: ssh_service_arc,
             ssh_key_service,
+            certificate_authority_service,
+            connection_import_service,
+            ssh_config_exporter,
             ssh_connection_pool,
             tunnel_service,
             saved_command_service,
+            backup_service,
+            backup_scheduler,
             sync_service,
             terminal_manager: terminal_manager_arc,
             auth_session_manager,
             sftp_service,
             sftp_transfer_manager,
             sftp_sync_service,
+            sftp_archive_service,
             history_manager,
         }
     }
@@ -80,6 +80,11 @@ class ApiClient {
       update_ssh_key: ["id", "request"],
       delete_ssh_key: ["id", "force"],
       count_profiles_using_key: ["keyId"],
+      change_ssh_key_passphrase: ["id", "passphrase"],
+      export_ssh_key: ["id", "format"],
+      list_pkcs11_keys: ["providerPath", "pin"],
+      delete_certificate_authority: ["id"],
+      get_expiring_certificates: ["withinMinutes"],
       import_ssh_key_from_file: [
         "name",
         "filePath",
@@ -0,0 +1,65 @@
+import { api } from "./api";
+import type {
+  CreateCertificateAuthorityRequest,
+  ExpiringCertificate,
+  IssueCertificateRequest,
+  SSHCertificateAuthority,
+  SSHProfile,
+} from "../types/ssh";
+
+/**
+ * Create a local SSH certificate authority
+ * @param request - Create request (generates a key unless one is imported)
+ * @returns Created certificate authority
+ */
+export async function createCertificateAuthority(
+  request: CreateCertificateAuthorityRequest,
+): Promise<SSHCertificateAuthority> {
+  return await api.call<SSHCertificateAuthority>(
+    "create_certificate_authority",
+    request,
+  );
+}
+
+/**
+ * Get all local SSH certificate authorities
+ * @returns Array of certificate authorities
+ */
+export async function getCertificateAuthorities(): Promise<
+  SSHCertificateAuthority[]
+> {
+  return await api.call<SSHCertificateAuthority[]>(
+    "get_certificate_authorities",
+  );
+}
+
+/**
+ * Delete a certificate authority
+ * Certificates it issued stay valid until they expire
+ * @param id - Certificate authority ID
+ */
+export async function deleteCertificateAuthority(id: string): Promise<void> {
+  return await api.callRaw("delete_certificate_authority", id);
+}
+
+/**
+ * Sign a certificate for a profile and switch it to certificate authentication
+ * @param request - Issue request
+ * @returns Updated SSH profile
+ */
+export async function issueSSHCertificate(
+  request: IssueCertificateRequest,
+): Promise<SSHProfile> {
+  return await api.call<SSHProfile>("issue_ssh_certificate", request);
+}
+
+/**
+ * Get profiles whose certificate expires soon
+ * @param withinMinutes - Look-ahead window in minutes
+ * @returns Expiring certificates, soonest first
+ */
+export async function getExpiringCertificates(
+  withinMinutes: number,
+): Promise<ExpiringCertificate[]> {
+  return await api.callRaw("get_expiring_certificates", withinMinutes);
+}
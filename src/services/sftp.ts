@@ -5,6 +5,10 @@ import type {
   SyncOperation,
   DiffEntry,
   SearchResult,
+  FileSnapshot,
+  ReadFileOptions,
+  TextFileContent,
+  WriteFileOptions,
 } from "../types/sftp";
 
 /**
@@ -144,6 +148,23 @@ export async function downloadSFTPFile(
   });
 }
 
+/**
+ * Copy file between two remote sessions, streamed through the app
+ */
+export async function copySFTPRemoteFile(
+  sourceSessionId: string,
+  sourcePath: string,
+  targetSessionId: string,
+  targetPath: string,
+): Promise<string> {
+  return await api.call("sftp_copy_remote_file", {
+    sourceSessionId,
+    sourcePath,
+    targetSessionId,
+    targetPath,
+  });
+}
+
 /**
  * Get transfer progress
  */
@@ -189,6 +210,24 @@ export async function compareSFTPDirectories(
   });
 }
 
+/**
+ * Compare directories on two remote sessions
+ * The source side is reported as local, the target side as remote
+ */
+export async function compareSFTPRemoteDirectories(
+  sourceSessionId: string,
+  sourcePath: string,
+  targetSessionId: string,
+  targetPath: string,
+): Promise<DiffEntry[]> {
+  return await api.call("sftp_compare_remote_directories", {
+    sourceSessionId,
+    sourcePath,
+    targetSessionId,
+    targetPath,
+  });
+}
+
 /**
  * Sync directories
  */
@@ -200,24 +239,42 @@ export async function syncSFTPDirectories(
 }
 
 /**
- * Read file content as text
+ * Read file content as text, detecting its encoding and line endings
  */
 export async function readSFTPFile(
   sessionId: string,
   path: string,
-): Promise<string> {
-  return await api.call("sftp_read_file", { sessionId, path });
+  options: ReadFileOptions = {},
+): Promise<TextFileContent> {
+  return await api.call("sftp_read_file", { sessionId, path, options });
 }
 
 /**
  * Write file content as text
+ * Pass the encoding, BOM and snapshot returned by the read so the file keeps
+ * its encoding and is not saved over changes made on the server meanwhile
  */
 export async function writeSFTPFile(
   sessionId: string,
   path: string,
   content: string,
-): Promise<void> {
-  return await api.call("sftp_write_file", { sessionId, path, content });
+  options: WriteFileOptions = {},
+): Promise<FileSnapshot> {
+  return await api.call("sftp_write_file", {
+    sessionId,
+    path,
+    content,
+    options,
+  });
+}
+
+/**
+ * Whether a save failed because the file changed on the server since it was
+ * read
+ */
+export function isFileModifiedError(error: unknown): boolean {
+  const text = error instanceof Error ? error.message : String(error);
+  return text.includes("modified on the server since it was opened");
 }
 
 /**
@@ -2,6 +2,12 @@ import { api } from "./api";
 import type {
   SSHKey,
   CreateSSHKeyRequest,
+  DeploySSHKeyRequest,
+  GenerateSSHKeyRequest,
+  KeyDeployReport,
+  KeyFormat,
+  Pkcs11KeyInfo,
+  RotateSSHKeyRequest,
   UpdateSSHKeyRequest,
 } from "../types/ssh";
 
@@ -16,6 +22,17 @@ export async function createSSHKey(
   return await api.call<SSHKey>("create_ssh_key", request);
 }
 
+/**
+ * Generate a new SSH key pair
+ * @param request - Generate SSH key request
+ * @returns Created SSH key
+ */
+export async function generateSSHKey(
+  request: GenerateSSHKeyRequest,
+): Promise<SSHKey> {
+  return await api.call<SSHKey>("generate_ssh_key", request);
+}
+
 /**
  * Get all SSH keys
  * @returns List of all SSH keys
@@ -86,3 +103,68 @@ export async function importSSHKeyFromFile(
     description,
   );
 }
+
+/**
+ * Add, change or remove the passphrase of an SSH key
+ * @param id - SSH key ID
+ * @param passphrase - New passphrase, or null to remove it
+ * @returns Updated SSH key
+ */
+export async function changeSSHKeyPassphrase(
+  id: string,
+  passphrase: string | null,
+): Promise<SSHKey> {
+  return await api.callRaw<SSHKey>("change_ssh_key_passphrase", id, passphrase);
+}
+
+/**
+ * Export an SSH private key in another format
+ * @param id - SSH key ID
+ * @param format - Target format
+ * @returns Private key file content
+ */
+export async function exportSSHKey(
+  id: string,
+  format: KeyFormat,
+): Promise<string> {
+  return await api.callRaw<string>("export_ssh_key", id, format);
+}
+
+/**
+ * List the keys on the tokens of a PKCS#11 module
+ * @param providerPath - Path to the PKCS#11 module
+ * @param pin - Token PIN, needed by tokens that hide keys until login
+ * @returns Keys found on the tokens
+ */
+export async function listPkcs11Keys(
+  providerPath: string,
+  pin?: string,
+): Promise<Pkcs11KeyInfo[]> {
+  return await api.callRaw<Pkcs11KeyInfo[]>(
+    "list_pkcs11_keys",
+    providerPath,
+    pin ?? null,
+  );
+}
+
+/**
+ * Install an SSH key on the hosts of the given profiles
+ * @param request - Deploy request
+ * @returns Per-host report
+ */
+export async function deploySSHKey(
+  request: DeploySSHKeyRequest,
+): Promise<KeyDeployReport> {
+  return await api.call<KeyDeployReport>("deploy_ssh_key", request);
+}
+
+/**
+ * Replace an SSH key with another on the hosts that use it
+ * @param request - Rotate request
+ * @returns Per-host report
+ */
+export async function rotateSSHKey(
+  request: RotateSSHKeyRequest,
+): Promise<KeyDeployReport> {
+  return await api.call<KeyDeployReport>("rotate_ssh_key", request);
+}
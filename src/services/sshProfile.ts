@@ -8,6 +8,15 @@ import type {
   DeleteGroupAction,
   CreateSSHGroupRequest,
   UpdateSSHGroupRequest,
+  ImportSource,
+  ImportPreview,
+  ImportConnectionsRequest,
+  ImportResult,
+  SSHConfigExportSelection,
+  SSHConfigExportPreview,
+  ExportSSHConfigRequest,
+  SSHConfigExportResult,
+  ManagedSSHConfigStatus,
 } from "../types/ssh";
 
 /**
@@ -167,6 +176,69 @@ export async function getSSHConfigHosts(): Promise<SSHConfigHost[]> {
   return await api.call("get_ssh_config_hosts");
 }
 
+/**
+ * Read connections from another SSH client without importing them
+ * @param source - Client or file format to read
+ * @param path - File or folder to read; ~/.ssh/config when omitted for OpenSSH
+ * @returns Hosts and keys found, with the ones already stored marked
+ */
+export async function previewConnectionImport(
+  source: ImportSource,
+  path?: string,
+): Promise<ImportPreview> {
+  return await api.call("preview_connection_import", { source, path });
+}
+
+/**
+ * Import connections from another SSH client as profiles
+ * @param request - Source and the preview hosts to import
+ * @returns Counts of created profiles, groups, keys and tunnels
+ */
+export async function importConnections(
+  request: ImportConnectionsRequest,
+): Promise<ImportResult> {
+  return await api.call("import_connections", request);
+}
+
+/**
+ * Render the selected profiles as OpenSSH config without writing anything
+ * @param selection - Profiles and groups to export
+ * @returns Config content and settings that could not be exported
+ */
+export async function previewSSHConfigExport(
+  selection: SSHConfigExportSelection,
+): Promise<SSHConfigExportPreview> {
+  return await api.call("preview_ssh_config_export", selection);
+}
+
+/**
+ * Export profiles to an OpenSSH config file or the managed Include
+ * @param request - Profiles to export and where to write them
+ * @returns Written config path and counts
+ */
+export async function exportSSHConfig(
+  request: ExportSSHConfigRequest,
+): Promise<SSHConfigExportResult> {
+  return await api.call("export_ssh_config", request);
+}
+
+/**
+ * Get the managed OpenSSH config export
+ * @returns Its selection and sync state, or null when it is off
+ */
+export async function getManagedSSHConfig(): Promise<
+  ManagedSSHConfigStatus | null
+> {
+  return await api.call("get_managed_ssh_config");
+}
+
+/**
+ * Stop the managed OpenSSH config export and remove its files
+ */
+export async function disableManagedSSHConfig(): Promise<void> {
+  return await api.call("disable_managed_ssh_config");
+}
+
 /**
  * Test SSH connection
  * @param request - SSH connection test request
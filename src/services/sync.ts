@@ -11,6 +11,9 @@ import type {
   SyncSettings,
   DatabaseSyncSettings,
   ConflictResolutionStrategy,
+  FieldChoice,
+  SyncEncryptionStatus,
+  SyncPlan,
 } from "../types/sync";
 
 class SyncService {
@@ -86,10 +89,46 @@ class SyncService {
     });
   }
 
+  async previewSync(id: string): Promise<SyncPlan> {
+    return api.callRaw("preview_sync", { databaseId: id });
+  }
+
   async getSyncStatus(id: string): Promise<SyncServiceStatus> {
     return api.callRaw("get_sync_status", { databaseId: id });
   }
 
+  async getEncryptionStatus(id: string): Promise<SyncEncryptionStatus> {
+    return api.callRaw("get_sync_encryption_status", { databaseId: id });
+  }
+
+  async enableEncryption(
+    id: string,
+    password: string,
+  ): Promise<SyncEncryptionStatus> {
+    return api.callRaw("enable_sync_encryption", { databaseId: id, password });
+  }
+
+  async unlockEncryption(
+    id: string,
+    password: string,
+  ): Promise<SyncEncryptionStatus> {
+    return api.callRaw("unlock_sync_encryption", { databaseId: id, password });
+  }
+
+  async rotateEncryptionKey(
+    id: string,
+    password: string,
+  ): Promise<SyncEncryptionStatus> {
+    return api.callRaw("rotate_sync_encryption_key", {
+      databaseId: id,
+      password,
+    });
+  }
+
+  async disableEncryption(id: string, password: string): Promise<void> {
+    return api.callRaw("disable_sync_encryption", { databaseId: id, password });
+  }
+
   async loadSyncLogs(id: string, limit?: number): Promise<SyncLog[]> {
     return api.callRaw("get_sync_logs", { databaseId: id, limit });
   }
@@ -138,8 +177,13 @@ class SyncService {
   async resolveConflictResolution(
     id: string,
     strategy: ConflictResolutionStrategy,
+    fieldChoices?: Record<string, FieldChoice>,
   ): Promise<void> {
-    return api.callRaw("resolve_conflict_resolution", { id, strategy });
+    return api.callRaw("resolve_conflict_resolution", {
+      id,
+      strategy,
+      fieldChoices,
+    });
   }
 
   async cleanupResolvedConflicts(days: number): Promise<number> {
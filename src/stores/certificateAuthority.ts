@@ -0,0 +1,173 @@
+import { defineStore } from "pinia";
+import { ref } from "vue";
+import type {
+  CreateCertificateAuthorityRequest,
+  ExpiringCertificate,
+  IssueCertificateRequest,
+  SSHCertificateAuthority,
+  SSHProfile,
+} from "../types/ssh";
+import * as caService from "../services/certificateAuthority";
+import { message } from "../utils/message";
+import { handleError, type ErrorContext } from "../utils/errorHandler";
+
+/** Certificates expiring within this window are flagged */
+export const CERTIFICATE_EXPIRY_WARNING_MINUTES = 24 * 60;
+
+export const useCertificateAuthorityStore = defineStore(
+  "certificateAuthority",
+  () => {
+    const authorities = ref<SSHCertificateAuthority[]>([]);
+    const expiring = ref<ExpiringCertificate[]>([]);
+    const isLoading = ref(false);
+
+    /**
+     * Load all certificate authorities with error handling
+     */
+    async function loadAuthorities(): Promise<void> {
+      isLoading.value = true;
+      const context: ErrorContext = {
+        operation: "Load Certificate Authorities",
+      };
+
+      try {
+        authorities.value = await caService.getCertificateAuthorities();
+      } catch (error) {
+        const errorMessage = handleError(error, context);
+        message.error(errorMessage);
+        throw new Error(errorMessage);
+      } finally {
+        isLoading.value = false;
+      }
+    }
+
+    /**
+     * Create a certificate authority with error handling
+     * @param request - Create request
+     * @returns Created certificate authority
+     */
+    async function createAuthority(
+      request: CreateCertificateAuthorityRequest,
+    ): Promise<SSHCertificateAuthority> {
+      isLoading.value = true;
+      const context: ErrorContext = {
+        operation: "Create Certificate Authority",
+        context: { name: request.name },
+      };
+
+      try {
+        const ca = await caService.createCertificateAuthority(request);
+        authorities.value = [...authorities.value, ca];
+        message.success(`Certificate authority "${ca.name}" created`);
+        return ca;
+      } catch (error) {
+        const errorMessage = handleError(error, context);
+        message.error(errorMessage);
+        throw new Error(errorMessage);
+      } finally {
+        isLoading.value = false;
+      }
+    }
+
+    /**
+     * Delete a certificate authority with error handling
+     * @param id - Certificate authority ID
+     */
+    async function deleteAuthority(id: string): Promise<void> {
+      isLoading.value = true;
+      const context: ErrorContext = {
+        operation: "Delete Certificate Authority",
+        context: { caId: id },
+      };
+
+      try {
+        await caService.deleteCertificateAuthority(id);
+        authorities.value = authorities.value.filter((ca) => ca.id !== id);
+        message.success("Certificate authority deleted");
+      } catch (error) {
+        const errorMessage = handleError(error, context);
+        message.error(errorMessage);
+        throw new Error(errorMessage);
+      } finally {
+        isLoading.value = false;
+      }
+    }
+
+    /**
+     * Issue a certificate for a profile with error handling
+     * The updated profile also arrives through the ssh_profile_updated event
+     * @param request - Issue request
+     * @returns Updated SSH profile
+     */
+    async function issueCertificate(
+      request: IssueCertificateRequest,
+    ): Promise<SSHProfile> {
+      isLoading.value = true;
+      const context: ErrorContext = {
+        operation: "Issue SSH Certificate",
+        context: { caId: request.caId, profileId: request.profileId },
+      };
+
+      try {
+        const profile = await caService.issueSSHCertificate(request);
+        expiring.value = expiring.value.filter(
+          (c) => c.profileId !== profile.id,
+        );
+        message.success(`Certificate issued for "${profile.name}"`);
+        return profile;
+      } catch (error) {
+        const errorMessage = handleError(error, context);
+        message.error(errorMessage);
+        throw new Error(errorMessage);
+      } finally {
+        isLoading.value = false;
+      }
+    }
+
+    /**
+     * Load certificates expiring soon and warn about those that will not renew
+     * Certificates from a local CA are renewed automatically on connect
+     * @param notify - Show a warning for each certificate that will not renew
+     */
+    async function checkExpiringCertificates(notify = true): Promise<void> {
+      try {
+        expiring.value = await caService.getExpiringCertificates(
+          CERTIFICATE_EXPIRY_WARNING_MINUTES,
+        );
+      } catch (error) {
+        console.error("Failed to check expiring certificates:", error);
+        return;
+      }
+
+      if (!notify) return;
+      for (const cert of expiring.value.filter((c) => !c.autoRenew)) {
+        message.warning(
+          cert.expiresInSeconds <= 0
+            ? `SSH certificate for "${cert.profileName}" has expired`
+            : `SSH certificate for "${cert.profileName}" expires ${new Date(cert.validTo).toLocaleString()}`,
+          "Certificate Expiring",
+          8000,
+        );
+      }
+    }
+
+    /**
+     * Get certificate authority by ID
+     */
+    function getAuthorityById(id: string): SSHCertificateAuthority | undefined {
+      return authorities.value.find((ca) => ca.id === id);
+    }
+
+    return {
+      authorities,
+      expiring,
+      isLoading,
+      loadAuthorities,
+      createAuthority,
+      deleteAuthority,
+      issueCertificate,
+      checkExpiringCertificates,
+      getAuthorityById,
+    };
+  },
+);
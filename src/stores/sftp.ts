@@ -7,6 +7,10 @@ import type {
   SyncOperation,
   DiffEntry,
   SearchResult,
+  FileSnapshot,
+  ReadFileOptions,
+  TextFileContent,
+  WriteFileOptions,
 } from "../types/sftp";
 import * as sftpService from "../services/sftp";
 import { api } from "../services/api";
@@ -132,6 +136,40 @@ async function downloadFile(
   }
 }
 
+/**
+ * Copy file between two remote sessions with error handling
+ * @param sourceSessionId - Session to read from
+ * @param sourcePath - Remote file path on the source session
+ * @param targetSessionId - Session to write to
+ * @param targetPath - Remote file path on the target session
+ * @returns Transfer ID
+ * @throws Enhanced error if the copy cannot be queued
+ */
+async function copyRemoteFile(
+  sourceSessionId: string,
+  sourcePath: string,
+  targetSessionId: string,
+  targetPath: string,
+): Promise<string> {
+  const context: ErrorContext = {
+    operation: "Copy Remote File",
+    context: { sourcePath, targetPath },
+  };
+
+  try {
+    return await sftpService.copySFTPRemoteFile(
+      sourceSessionId,
+      sourcePath,
+      targetSessionId,
+      targetPath,
+    );
+  } catch (error) {
+    const errorMessage = handleError(error, context);
+    message.error(errorMessage);
+    throw new Error(errorMessage);
+  }
+}
+
 /**
  * Compare directories with retry logic
  * @param sessionId - SFTP session ID
@@ -164,6 +202,45 @@ async function compareDirectories(
   }
 }
 
+/**
+ * Compare directories on two remote sessions with retry logic
+ * @param sourceSessionId - Session of the source side
+ * @param sourcePath - Directory on the source session
+ * @param targetSessionId - Session of the target side
+ * @param targetPath - Directory on the target session
+ * @returns Array of differences (source as local, target as remote)
+ * @throws Enhanced error if comparison fails
+ */
+async function compareRemoteDirectories(
+  sourceSessionId: string,
+  sourcePath: string,
+  targetSessionId: string,
+  targetPath: string,
+): Promise<DiffEntry[]> {
+  const context: ErrorContext = {
+    operation: "Compare Remote Directories",
+    context: { sourcePath, targetPath },
+  };
+
+  try {
+    return await withRetry(
+      () =>
+        sftpService.compareSFTPRemoteDirectories(
+          sourceSessionId,
+          sourcePath,
+          targetSessionId,
+          targetPath,
+        ),
+      { maxRetries: 1 },
+      context,
+    );
+  } catch (error) {
+    const errorMessage = handleError(error, context);
+    console.error("Failed to compare remote directories:", errorMessage);
+    throw new Error(errorMessage);
+  }
+}
+
 /**
  * Sync directories with error handling
  * @param sessionId - SFTP session ID
@@ -197,17 +274,22 @@ async function syncDirectories(
  * Read file content as text (remote) with error handling
  * @param sessionId - SFTP session ID
  * @param path - Remote file path
- * @returns File content as string
+ * @param options - Optional range and encoding
+ * @returns Decoded content with its encoding and file snapshot
  * @throws Enhanced error if read fails
  */
-async function readFile(sessionId: string, path: string): Promise<string> {
+async function readFile(
+  sessionId: string,
+  path: string,
+  options: ReadFileOptions = {},
+): Promise<TextFileContent> {
   const context: ErrorContext = {
     operation: "Read File",
     context: { path },
   };
 
   try {
-    return await sftpService.readSFTPFile(sessionId, path);
+    return await sftpService.readSFTPFile(sessionId, path, options);
   } catch (error) {
     const errorMessage = handleError(error, context);
     console.error("Failed to read file:", errorMessage);
@@ -220,21 +302,28 @@ async function readFile(sessionId: string, path: string): Promise<string> {
  * @param sessionId - SFTP session ID
  * @param path - Remote file path
  * @param content - File content to write
- * @throws Enhanced error if write fails
+ * @param options - Encoding, BOM and the snapshot from the read
+ * @returns Snapshot of the saved file
+ * @throws Enhanced error if write fails; the original error if the file was
+ * modified on the server (see isFileModifiedError)
  */
 async function writeFile(
   sessionId: string,
   path: string,
   content: string,
-): Promise<void> {
+  options: WriteFileOptions = {},
+): Promise<FileSnapshot> {
   const context: ErrorContext = {
     operation: "Write File",
     context: { path, contentLength: content.length },
   };
 
   try {
-    await sftpService.writeSFTPFile(sessionId, path, content);
+    return await sftpService.writeSFTPFile(sessionId, path, content, options);
   } catch (error) {
+    if (sftpService.isFileModifiedError(error)) {
+      throw error;
+    }
     const errorMessage = handleError(error, context);
     console.error("Failed to write file:", errorMessage);
     throw new Error(errorMessage);
@@ -760,7 +849,8 @@ export const useSFTPStore = defineStore("sftp", () => {
    * Read file content as text (remote) with error handling
    * @param sessionId - SFTP session ID
    * @param path - Remote file path
-   * @returns File content as string
+   * @param options - Optional range and encoding
+   * @returns Decoded content with its encoding and file snapshot
    * @throws Enhanced error if read fails
    */
 
@@ -769,6 +859,8 @@ export const useSFTPStore = defineStore("sftp", () => {
    * @param sessionId - SFTP session ID
    * @param path - Remote file path
    * @param content - File content to write
+   * @param options - Encoding, BOM and the snapshot from the read
+   * @returns Snapshot of the saved file
    * @throws Enhanced error if write fails
    */
 
@@ -936,8 +1028,10 @@ export const useSFTPStore = defineStore("sftp", () => {
     listRemoteDirectory,
     uploadFile,
     downloadFile,
+    copyRemoteFile,
     cancelTransfer,
     compareDirectories,
+    compareRemoteDirectories,
     syncDirectories,
     renameFile,
     deleteFile,
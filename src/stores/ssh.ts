@@ -10,6 +10,7 @@ import type {
   CreateSSHGroupRequest,
   UpdateSSHGroupRequest,
 } from "../types/ssh";
+import type { SyncLog } from "../types/sync";
 import * as sshService from "../services/sshProfile";
 import { api } from "../services/api";
 import {
@@ -534,9 +535,15 @@ export const useSSHStore = defineStore("ssh", () => {
 
   let unsubscribeProfileRealtime: (() => void) | null = null;
   let unsubscribeGroupRealtime: (() => void) | null = null;
+  let unsubscribeSyncRealtime: (() => void) | null = null;
 
   const startRealtime = async (): Promise<void> => {
-    if (unsubscribeProfileRealtime && unsubscribeGroupRealtime) return;
+    if (
+      unsubscribeProfileRealtime &&
+      unsubscribeGroupRealtime &&
+      unsubscribeSyncRealtime
+    )
+      return;
     try {
       if (!unsubscribeProfileRealtime) {
         const u1 = await api.listen<SSHProfile>(
@@ -571,6 +578,16 @@ export const useSSHStore = defineStore("ssh", () => {
           g3();
         };
       }
+
+      // Syncs triggered by remote changes may bring in profiles from other devices
+      if (!unsubscribeSyncRealtime) {
+        unsubscribeSyncRealtime = await api.listen<SyncLog>(
+          "sync_log_added",
+          (log) => {
+            if (log.recordsSynced > 0) void loadAll();
+          },
+        );
+      }
     } catch (e) {
       console.error("Failed to subscribe SSH realtime events:", e);
     }
@@ -585,6 +602,10 @@ export const useSSHStore = defineStore("ssh", () => {
       unsubscribeGroupRealtime();
       unsubscribeGroupRealtime = null;
     }
+    if (unsubscribeSyncRealtime) {
+      unsubscribeSyncRealtime();
+      unsubscribeSyncRealtime = null;
+    }
   };
 
   return {
@@ -3,6 +3,12 @@ import { ref, computed } from "vue";
 import type {
   SSHKey,
   CreateSSHKeyRequest,
+  DeploySSHKeyRequest,
+  GenerateSSHKeyRequest,
+  KeyDeployReport,
+  KeyFormat,
+  Pkcs11KeyInfo,
+  RotateSSHKeyRequest,
   UpdateSSHKeyRequest,
 } from "../types/ssh";
 import * as sshKeyService from "../services/sshKey";
@@ -86,6 +92,32 @@ export const useSSHKeyStore = defineStore("sshKey", () => {
     }
   }
 
+  /**
+   * Generate a new SSH key pair with error handling
+   * @param request - Key generation request
+   * @returns Generated key
+   */
+  async function generateKey(request: GenerateSSHKeyRequest): Promise<SSHKey> {
+    isLoading.value = true;
+    const context: ErrorContext = {
+      operation: "Generate SSH Key",
+      context: { name: request.name, keyType: request.keyType },
+    };
+
+    try {
+      const key = await sshKeyService.generateSSHKey(request);
+      upsertKey(key);
+      message.success(`SSH key "${key.name}" generated successfully`);
+      return key;
+    } catch (error) {
+      const errorMessage = handleError(error, context);
+      message.error(errorMessage);
+      throw new Error(errorMessage);
+    } finally {
+      isLoading.value = false;
+    }
+  }
+
   /**
    * Update an existing SSH key with error handling
    * @param id - Key ID to update
@@ -153,6 +185,147 @@ export const useSSHKeyStore = defineStore("sshKey", () => {
     }
   }
 
+  /**
+   * Add, change or remove a key's passphrase with error handling
+   * @param id - Key ID
+   * @param passphrase - New passphrase, or null to remove it
+   * @returns Updated key
+   */
+  async function changePassphrase(
+    id: string,
+    passphrase: string | null,
+  ): Promise<SSHKey> {
+    isLoading.value = true;
+    const context: ErrorContext = {
+      operation: "Change SSH Key Passphrase",
+      context: { keyId: id },
+    };
+
+    try {
+      const updatedKey = await sshKeyService.changeSSHKeyPassphrase(
+        id,
+        passphrase,
+      );
+      upsertKey(updatedKey);
+      message.success(
+        passphrase
+          ? `Passphrase for "${updatedKey.name}" updated`
+          : `Passphrase removed from "${updatedKey.name}"`,
+      );
+      return updatedKey;
+    } catch (error) {
+      const errorMessage = handleError(error, context);
+      message.error(errorMessage);
+      throw new Error(errorMessage);
+    } finally {
+      isLoading.value = false;
+    }
+  }
+
+  /**
+   * Export a key's private key in the given format with error handling
+   * @param id - Key ID
+   * @param format - Target format
+   * @returns Private key file content
+   */
+  async function exportKey(id: string, format: KeyFormat): Promise<string> {
+    const context: ErrorContext = {
+      operation: "Export SSH Key",
+      context: { keyId: id, format },
+    };
+
+    try {
+      return await sshKeyService.exportSSHKey(id, format);
+    } catch (error) {
+      const errorMessage = handleError(error, context);
+      message.error(errorMessage);
+      throw new Error(errorMessage);
+    }
+  }
+
+  /**
+   * List the keys on a PKCS#11 token with error handling
+   * @param providerPath - Path to the PKCS#11 module
+   * @param pin - Token PIN
+   * @returns Keys found on the token
+   */
+  async function listPkcs11Keys(
+    providerPath: string,
+    pin?: string,
+  ): Promise<Pkcs11KeyInfo[]> {
+    const context: ErrorContext = {
+      operation: "List PKCS#11 Keys",
+      context: { providerPath },
+    };
+
+    try {
+      return await sshKeyService.listPkcs11Keys(providerPath, pin);
+    } catch (error) {
+      const errorMessage = handleError(error, context);
+      message.error(errorMessage);
+      throw new Error(errorMessage);
+    }
+  }
+
+  /**
+   * Deploy a key to hosts with error handling
+   * Host failures are reported per host rather than thrown
+   * @param request - Deploy request
+   * @returns Per-host report
+   */
+  async function deployKey(
+    request: DeploySSHKeyRequest,
+  ): Promise<KeyDeployReport> {
+    const context: ErrorContext = {
+      operation: "Deploy SSH Key",
+      context: { keyId: request.keyId },
+    };
+
+    try {
+      const report = await sshKeyService.deploySSHKey(request);
+      reportDeployOutcome(report, "deployed to");
+      return report;
+    } catch (error) {
+      const errorMessage = handleError(error, context);
+      message.error(errorMessage);
+      throw new Error(errorMessage);
+    }
+  }
+
+  /**
+   * Rotate a key on the hosts that use it with error handling
+   * @param request - Rotate request
+   * @returns Per-host report
+   */
+  async function rotateKey(
+    request: RotateSSHKeyRequest,
+  ): Promise<KeyDeployReport> {
+    const context: ErrorContext = {
+      operation: "Rotate SSH Key",
+      context: { oldKeyId: request.oldKeyId, newKeyId: request.newKeyId },
+    };
+
+    try {
+      const report = await sshKeyService.rotateSSHKey(request);
+      reportDeployOutcome(report, "rotated on");
+      return report;
+    } catch (error) {
+      const errorMessage = handleError(error, context);
+      message.error(errorMessage);
+      throw new Error(errorMessage);
+    }
+  }
+
+  const reportDeployOutcome = (report: KeyDeployReport, action: string) => {
+    const total = report.results.length;
+    const failed = report.results.filter((r) => r.error).length;
+    if (failed === 0) {
+      message.success(`SSH key ${action} ${total} host(s)`);
+    } else {
+      message.warning(`SSH key ${action} ${total - failed}/${total} host(s)`);
+    }
+  };
+
   /**
    * Import SSH key from file with error handling
    * keyType will be auto-detected from key content
@@ -252,8 +425,14 @@ export const useSSHKeyStore = defineStore("sshKey", () => {
     keysByName,
     loadKeys,
     createKey,
+    generateKey,
     updateKey,
     deleteKey,
+    changePassphrase,
+    exportKey,
+    listPkcs11Keys,
+    deployKey,
+    rotateKey,
     importKeyFromFile,
     getKeyById,
     countProfilesUsing,
@@ -9,8 +9,11 @@ import type {
   ConnectionDetails,
   DatabaseSyncSettings,
   ConflictResolutionStrategy,
+  SyncedEntities,
+  FieldChoice,
   SyncDirection,
   SyncSettings,
+  SyncPlan,
   Device,
 } from "../types/sync";
 
@@ -85,6 +88,7 @@ async function updateGlobalSyncSettings(settings: {
   autoSyncEnabled?: boolean;
   conflictStrategy?: ConflictResolutionStrategy;
   syncDirection?: SyncDirectionOption;
+  syncedEntities?: SyncedEntities;
 }): Promise<void> {
   const context: ErrorContext = {
     operation: "Update Global Sync Settings",
@@ -232,10 +236,12 @@ async function registerDevice(
  * Resolve conflict resolution (direct service call)
  * @param id - Conflict ID
  * @param strategy - Resolution strategy
+ * @param fieldChoices - Side to keep for each conflicting field (Manual only)
  */
 async function resolveConflictResolution(
   id: string,
   strategy: ConflictResolutionStrategy,
+  fieldChoices?: Record<string, FieldChoice>,
 ): Promise<void> {
   const context: ErrorContext = {
     operation: "Resolve Conflict Resolution",
@@ -244,7 +250,7 @@ async function resolveConflictResolution(
 
   try {
     await withRetry(
-      () => syncService.resolveConflictResolution(id, strategy),
+      () => syncService.resolveConflictResolution(id, strategy, fieldChoices),
       { maxRetries: 1 },
       context,
     );
@@ -549,6 +555,26 @@ export const useSyncStore = defineStore("sync", () => {
     }
   }
 
+  /**
+   * Preview the changes a bidirectional sync would make, without applying them
+   * @param id - Database ID
+   * @returns Pending changes per table
+   */
+  async function previewSync(id: string): Promise<SyncPlan> {
+    const context: ErrorContext = {
+      operation: "Preview Sync",
+      context: { databaseId: id },
+    };
+
+    try {
+      return await syncService.previewSync(id);
+    } catch (error) {
+      const errorMessage = handleError(error, context);
+      message.error(errorMessage);
+      throw new Error(errorMessage);
+    }
+  }
+
   /**
    * Load sync status for database
    */
@@ -808,6 +834,7 @@ export const useSyncStore = defineStore("sync", () => {
     connect,
     disconnect,
     sync,
+    previewSync,
     loadSyncStatus,
     loadSyncLogs,
     loadConflicts,
@@ -0,0 +1,98 @@
+export type BackupEntity =
+  | "ssh_groups"
+  | "ssh_keys"
+  | "ssh_profiles"
+  | "ssh_tunnels"
+  | "saved_commands"
+  | "terminal_profiles"
+  | "sync_settings"
+  | "known_hosts";
+
+export type BackupKdf = "argon2id" | "pbkdf2";
+
+export type RestoreMode = "merge" | "replace";
+
+export type ConflictPolicy = "overwrite" | "skip" | "keep_both";
+
+export interface BackupEntityCount {
+  entity: BackupEntity;
+  count: number;
+}
+
+export interface BackupSummary {
+  formatVersion: number;
+  schemaVersion: number;
+  createdAt?: string;
+  appVersion?: string;
+  encrypted: boolean;
+  kdf?: string;
+  entities: BackupEntity[];
+  /** Only known once an encrypted backup is unlocked */
+  counts?: BackupEntityCount[];
+}
+
+export interface RestoreOptions {
+  /** Every entity in the backup when unset */
+  entities?: BackupEntity[];
+  mode: RestoreMode;
+  conflictPolicy: ConflictPolicy;
+}
+
+export interface EntityRestoreReport {
+  entity: BackupEntity;
+  created: number;
+  updated: number;
+  skipped: number;
+  renamed: number;
+  deleted: number;
+}
+
+export interface RestoreReport {
+  sourceSchemaVersion: number;
+  entities: EntityRestoreReport[];
+}
+
+export type SnapshotReason =
+  | "scheduled"
+  | "manual"
+  | "password_change"
+  | "password_reset"
+  | "sync_pull";
+
+export interface BackupScheduleSettings {
+  enabled: boolean;
+  /** Five-field cron expression in local time */
+  cron: string;
+  directory?: string;
+  keepDaily: number;
+  keepWeekly: number;
+  kdf: BackupKdf;
+  snapshotBeforeRiskyOperations: boolean;
+}
+
+export interface BackupScheduleStatus extends BackupScheduleSettings {
+  passwordConfigured: boolean;
+  snapshotDirectory: string;
+  nextRunAt?: string;
+  lastSnapshotAt?: string;
+}
+
+export interface UpdateBackupScheduleRequest {
+  enabled?: boolean;
+  cron?: string;
+  /** Empty string resets to the default folder */
+  directory?: string;
+  keepDaily?: number;
+  keepWeekly?: number;
+  kdf?: BackupKdf;
+  snapshotBeforeRiskyOperations?: boolean;
+  /** Stored in the system keychain; empty string removes it */
+  password?: string;
+}
+
+export interface BackupSnapshot {
+  id: string;
+  reason: SnapshotReason;
+  createdAt: string;
+  sizeBytes: number;
+}
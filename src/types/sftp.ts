@@ -37,7 +37,7 @@ export type TransferStatus =
 /**
  * Transfer direction
  */
-export type TransferDirection = "upload" | "download";
+export type TransferDirection = "upload" | "download" | "remote";
 
 /**
  * Transfer progress information
@@ -46,8 +46,9 @@ export interface TransferProgress {
   transferId: string;
   status: TransferStatus;
   direction: TransferDirection;
-  localPath: string;
-  remotePath: string;
+  localPath: string; // Source path for remote-to-remote transfers
+  remotePath: string; // Target path for remote-to-remote transfers
+  sourceSessionId: string | null; // Source session for remote-to-remote transfers
   totalBytes: number;
   transferredBytes: number;
   speedBytesPerSec: number | null;
@@ -99,6 +100,8 @@ export interface SyncOperation {
   preservePermissions: boolean;
   maxFileSize: number | null;
   excludePatterns: string[];
+  deltaTransfer?: boolean; // Upload only changed blocks of large files (default: true)
+  deltaMinSize?: number | null;
 }
 
 /**
@@ -162,3 +165,56 @@ export interface SearchResult {
   lineNumber: number;
   content: string;
 }
+
+/**
+ * Text encoding of an edited file
+ */
+export type TextEncoding = "utf8" | "utf16le" | "utf16be" | "latin1";
+
+/**
+ * Line ending style of an edited file
+ */
+export type LineEnding = "lf" | "crlf" | "cr";
+
+/**
+ * Size and modification time of a remote file, passed back on save to
+ * detect conflicting changes
+ */
+export interface FileSnapshot {
+  size: number;
+  modified: string | null; // ISO 8601 datetime
+}
+
+/**
+ * Options for reading a file as text
+ */
+export interface ReadFileOptions {
+  offset?: number;
+  length?: number;
+  encoding?: TextEncoding;
+}
+
+/**
+ * Text content of a remote file
+ */
+export interface TextFileContent {
+  content: string;
+  encoding: TextEncoding;
+  bom: boolean;
+  lineEnding: LineEnding;
+  offset: number;
+  length: number;
+  partial: boolean;
+  snapshot: FileSnapshot;
+}
+
+/**
+ * Options for saving a file as text
+ */
+export interface WriteFileOptions {
+  encoding?: TextEncoding;
+  bom?: boolean;
+  lineEnding?: LineEnding;
+  /** Saving fails if the remote file no longer matches this snapshot */
+  expected?: FileSnapshot | null;
+}
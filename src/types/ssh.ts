@@ -1,3 +1,5 @@
+import type { TunnelType } from "./tunnel";
+
 /**
  * Base model interface from backend
  */
@@ -20,10 +22,17 @@ export interface SSHConfigHost {
   port: number;
   user: string | null;
   identityFile: string | null;
+  identityFiles: string[];
+  certificateFiles: string[];
   proxyJump: string | null;
   proxyCommand: string | null;
   forwardAgent: boolean | null;
   otherOptions: string | null;
+  localForwards: string[];
+  remoteForwards: string[];
+  dynamicForwards: string[];
+  serverAliveInterval: number | null;
+  compression: boolean | null;
 }
 
 /**
@@ -61,7 +70,38 @@ export type AuthMethod =
 /**
  * SSH Key types - matches backend KeyType enum
  */
-export type KeyType = "RSA" | "Ed25519" | "ECDSA" | "DSA";
+export type KeyType =
+  | "RSA"
+  | "Ed25519"
+  | "ECDSA"
+  | "DSA"
+  | "SkEd25519"
+  | "SkECDSA";
+
+/**
+ * Key held on a hardware token - matches backend HardwareKey
+ * keyId is the hex-encoded CKA_ID; keyLabel is used when it is unset
+ */
+export type HardwareKey = {
+  type: "pkcs11";
+  providerPath: string;
+  slotId?: number;
+  keyId?: string;
+  keyLabel?: string;
+};
+
+/**
+ * Key found on a PKCS#11 token - matches backend Pkcs11KeyInfo
+ */
+export interface Pkcs11KeyInfo {
+  slotId: number;
+  tokenLabel: string;
+  keyId?: string;
+  keyLabel?: string;
+  keyType: KeyType;
+  publicKey: string;
+  fingerprint: string;
+}
 
 /**
  * Certificate validity information
@@ -71,6 +111,9 @@ export interface CertificateValidity {
   validTo: string;
   serial?: string;
   caFingerprint?: string;
+  /** Local certificate authority that issued the certificate */
+  caId?: string;
+  principals?: string[];
 }
 
 /**
@@ -147,6 +190,8 @@ export interface SSHKey extends BaseModel {
   publicKey?: string;
   passphrase?: string;
   fingerprint: string;
+  /** Token holding the private key; privateKey is empty when set */
+  hardware?: HardwareKey;
   description?: string;
   lastUsed?: string;
 }
@@ -236,8 +281,121 @@ export interface CreateSSHKeyRequest {
   keyType?: KeyType;
   privateKey: string;
   publicKey?: string;
+  /** Key passphrase, or the token PIN for hardware keys */
   passphrase?: string;
   description?: string;
+  hardware?: HardwareKey;
+}
+
+/**
+ * Private key file formats - matches backend KeyFormat
+ */
+export type KeyFormat = "openssh" | "pem" | "ppk";
+
+/**
+ * Generate SSH Key Request - matches backend GenerateSSHKeyRequest
+ * bits is the RSA modulus (default 3072) or ECDSA curve size (default 256)
+ */
+export interface GenerateSSHKeyRequest {
+  name: string;
+  keyType: KeyType;
+  bits?: number;
+  comment?: string;
+  passphrase?: string;
+  description?: string;
+}
+
+/**
+ * Deploy SSH Key Request - matches backend DeploySSHKeyRequest
+ */
+export interface DeploySSHKeyRequest {
+  keyId: string;
+  profileIds: string[];
+  groupId?: string;
+  switchAuth: boolean;
+}
+
+/**
+ * Rotate SSH Key Request - matches backend RotateSSHKeyRequest
+ * Without profileIds or groupId, every profile using the old key is rotated
+ */
+export interface RotateSSHKeyRequest {
+  oldKeyId: string;
+  newKeyId: string;
+  profileIds: string[];
+  groupId?: string;
+}
+
+export type KeyDeployStatus = "added" | "alreadyPresent" | "failed";
+
+/**
+ * Per-host result of a key deploy or rotation
+ */
+export interface KeyDeployResult {
+  profileId: string;
+  profileName: string;
+  host: string;
+  status: KeyDeployStatus;
+  verified: boolean;
+  authSwitched: boolean;
+  oldKeyRemoved?: boolean;
+  error?: string;
+}
+
+export interface KeyDeployReport {
+  keyId: string;
+  results: KeyDeployResult[];
+}
+
+/**
+ * SSH Certificate Authority - matches backend SSHCertificateAuthority
+ * The signing key stays on the backend and is never returned
+ */
+export interface SSHCertificateAuthority extends BaseModel {
+  name: string;
+  keyType: KeyType;
+  publicKey: string;
+  fingerprint: string;
+  defaultPrincipals: string[];
+  defaultTtlMinutes: number;
+  nextSerial: number;
+  description?: string;
+}
+
+/**
+ * Create Certificate Authority Request - matches backend CreateCertificateAuthorityRequest
+ * A new key is generated unless privateKey is given
+ */
+export interface CreateCertificateAuthorityRequest {
+  name: string;
+  keyType?: KeyType;
+  bits?: number;
+  privateKey?: string;
+  passphrase?: string;
+  defaultPrincipals: string[];
+  defaultTtlMinutes?: number;
+  description?: string;
+}
+
+/**
+ * Issue Certificate Request - matches backend IssueCertificateRequest
+ */
+export interface IssueCertificateRequest {
+  caId: string;
+  profileId: string;
+  principals?: string[];
+  ttlMinutes?: number;
+}
+
+/**
+ * Profile whose certificate expires soon
+ */
+export interface ExpiringCertificate {
+  profileId: string;
+  profileName: string;
+  validTo: string;
+  expiresInSeconds: number;
+  autoRenew: boolean;
 }
 
 /**
@@ -249,6 +407,142 @@ export interface UpdateSSHKeyRequest {
   privateKey?: string;
   publicKey?: string | null;
   passphrase?: string | null;
+  hardware?: HardwareKey | null;
+}
+
+/**
+ * Application or file format connections are imported from
+ */
+export type ImportSource =
+  | "open_ssh"
+  | "putty"
+  | "moba_xterm"
+  | "termius"
+  | "remmina";
+
+/**
+ * Port forward read from an imported host
+ */
+export interface ImportedTunnel {
+  tunnelType: TunnelType;
+  localHost: string;
+  localPort: number;
+  remoteHost: string | null;
+  remotePort: number | null;
+}
+
+/**
+ * Private key referenced by imported hosts
+ * Inline key content and passphrases stay on the backend
+ */
+export interface ImportedKey {
+  id: string;
+  name: string;
+  path: string | null;
+  duplicateOf: string | null;
+}
+
+/**
+ * Connection read from an import source
+ */
+export interface ImportedHost {
+  id: string;
+  name: string;
+  host: string;
+  port: number;
+  username: string | null;
+  group: string | null;
+  key: string | null;
+  hasPassword: boolean;
+  proxy: ProxyConfig | null;
+  tunnels: ImportedTunnel[];
+  description: string | null;
+  duplicateOf: string | null;
+}
+
+/**
+ * Everything read from an import source
+ */
+export interface ImportPreview {
+  hosts: ImportedHost[];
+  keys: ImportedKey[];
+  warnings: string[];
+}
+
+/**
+ * Import Connections Request - matches backend ImportConnectionsRequest
+ * hosts are indexes into ImportPreview.hosts
+ */
+export interface ImportConnectionsRequest {
+  source: ImportSource;
+  path?: string;
+  hosts?: string[];
+  importTunnels?: boolean;
+}
+
+/**
+ * Outcome of an import
+ */
+export interface ImportResult {
+  profilesCreated: number;
+  groupsCreated: number;
+  keysCreated: number;
+  keysReused: number;
+  tunnelsCreated: number;
+  warnings: string[];
+}
+
+/**
+ * Profiles to export as OpenSSH config, by ID or by whole group
+ */
+export interface SSHConfigExportSelection {
+  profileIds: string[];
+  groupIds: string[];
+  /** Folder key files are written to; ~/.ssh/kerminal/keys when unset */
+  keyDirectory?: string | null;
+}
+
+/**
+ * Where exported Host blocks go: a standalone file, or the managed
+ * ~/.ssh/kerminal/config included from ~/.ssh/config and kept in sync
+ */
+export type SSHConfigExportTarget =
+  | { type: "file"; path: string }
+  | { type: "managedInclude" };
+
+/**
+ * Export SSH Config Request - matches backend ExportSSHConfigRequest
+ */
+export interface ExportSSHConfigRequest extends SSHConfigExportSelection {
+  target: SSHConfigExportTarget;
+}
+
+/**
+ * Rendered config and settings it cannot express
+ */
+export interface SSHConfigExportPreview {
+  content: string;
+  warnings: string[];
+}
+
+/**
+ * Outcome of an OpenSSH config export
+ */
+export interface SSHConfigExportResult {
+  configPath: string;
+  hostsExported: number;
+  keysWritten: number;
+  warnings: string[];
+}
+
+/**
+ * Managed OpenSSH config export, when it is on
+ */
+export interface ManagedSSHConfigStatus extends SSHConfigExportSelection {
+  configPath: string;
+  includePath: string;
+  lastSyncedAt: string | null;
+  lastError: string | null;
 }
 
 /**
@@ -25,7 +25,14 @@ export interface SyncStatus {
  */
 export type ConflictResolution = "local" | "remote" | "merge" | "ask";
 
-export type DatabaseType = "mysql" | "postgresql" | "mongodb";
+export type DatabaseType =
+  | "mysql"
+  | "postgresql"
+  | "mongodb"
+  | "sqlite"
+  | "folder"
+  | "webdav"
+  | "s3";
 
 export type ConflictResolutionStrategy =
   | "LastWriteWins"
@@ -47,6 +54,17 @@ export interface DatabaseSyncSettings {
   syncDirection?: SyncDirection;
 }
 
+export interface SyncedEntities {
+  sshProfiles: boolean;
+  sshGroups: boolean;
+  sshKeys: boolean;
+  sshTunnels: boolean;
+  savedCommands: boolean;
+  terminalProfiles: boolean;
+  sessionRecordings: boolean;
+  knownHosts: boolean;
+}
+
 export interface SyncSettings {
   id: string; // Always "global"
   isActive: boolean;
@@ -56,6 +74,7 @@ export interface SyncSettings {
   syncDirection: SyncDirection;
   selectedDatabaseId?: string; // Last selected database for UI persistence
   lastSyncAt?: string;
+  syncedEntities: SyncedEntities;
   createdAt: string;
   updatedAt: string;
 }
@@ -67,6 +86,7 @@ export interface UpdateSyncSettingsRequest {
   conflictStrategy?: string;
   syncDirection?: string;
   selectedDatabaseId?: string;
+  syncedEntities?: SyncedEntities;
 }
 
 export interface ConnectionDetails {
@@ -77,6 +97,7 @@ export interface ConnectionDetails {
   databaseName: string;
   protocol?: string;
   options?: string;
+  encryptionPassphrase?: string;
 }
 
 export interface ExternalDatabaseConfig {
@@ -118,21 +139,73 @@ export interface SyncLog {
   errorMessage?: string;
 }
 
+export type PlannedAction =
+  | "push"
+  | "pull"
+  | "deleteRemote"
+  | "deleteLocal"
+  | "merge"
+  | "conflict";
+
+export interface PlannedChange {
+  recordId: string;
+  action: PlannedAction;
+  /** Copy the change overwrites: the remote one for pushes, the local one otherwise */
+  before?: Record<string, unknown>;
+  /** Copy written by the change, missing for conflicts left for manual resolution */
+  after?: Record<string, unknown>;
+  /** Remote copy a merge or conflict was settled against */
+  remote?: Record<string, unknown>;
+}
+
+export interface TableSyncPlan {
+  table: string;
+  changes: PlannedChange[];
+}
+
+export interface SyncPlan {
+  databaseId: string;
+  tables: TableSyncPlan[];
+  createdAt: string;
+}
+
+export interface FieldConflict {
+  field: string;
+  base?: unknown;
+  local: unknown;
+  remote: unknown;
+}
+
+/** Side a conflicting field is taken from when resolving field by field */
+export type FieldChoice = "local" | "remote";
+
 export interface ConflictResolutionData {
   id: string;
   entityType: string;
   entityId: string;
   localData: Record<string, unknown>;
   remoteData: Record<string, unknown>;
+  baseData?: Record<string, unknown>;
+  mergedData?: Record<string, unknown>;
+  fieldConflicts: FieldConflict[];
   resolutionStrategy?: ConflictResolutionStrategy;
   resolvedAt?: string;
   createdAt: string;
 }
 
+export interface SyncEncryptionStatus {
+  enabled: boolean;
+  unlocked: boolean;
+  activeKeyId?: string;
+  keyCount: number;
+}
+
 export interface SyncServiceStatus {
   isConnected: boolean;
   lastSync?: SyncLog;
   schedulerEnabled: boolean;
+  /** Synced as soon as the target announces remote changes */
+  realtimeEnabled: boolean;
 }
 
 export interface SyncServiceStatistics {